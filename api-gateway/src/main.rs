@@ -1,47 +1,109 @@
 use actix_cors::Cors;
-use actix_session::CookieSession;
+use actix_web::dev::Service;
 use actix_web::{get, App, HttpResponse, HttpServer, Responder};
-use rustls::internal::pemfile::{certs, pkcs8_private_keys};
-use rustls::{NoClientAuth, ServerConfig};
+use http::Method;
 use std::collections::HashMap;
 use std::env;
-use std::fs::File;
-use std::io::BufReader;
-use time::Duration;
 
 /// A layer that defines data structure.
 pub mod models {
+    /// Model related to administration.
+    pub mod admin;
+    /// Model related to attachment.
+    pub mod attachment;
     /// Model related to authentication.
     pub mod auth;
+    /// Model related to capability negotiation.
+    pub mod capability;
     /// Model related to error.
     pub mod error;
+    /// Model related to export.
+    pub mod export;
+    /// Model related to import.
+    pub mod import;
+    /// Model related to journal.
+    pub mod journal;
     /// Model related to post.
     pub mod post;
+    /// Model related to post comment.
+    pub mod post_comment;
+    /// Model related to post share.
+    pub mod post_share;
+    /// Model related to writing prompt.
+    pub mod prompt;
+    /// Model related to tag.
+    pub mod tag;
+    /// Model related to telemetry.
+    pub mod telemetry;
+    /// Model related to template.
+    pub mod template;
     /// Model related to user.
     pub mod user;
 }
 
 /// A presentation layer that makes API public and passes request to back-end service.
 pub mod routes {
+    /// API related to administration.
+    pub mod admin;
+    /// API related to attachment.
+    pub mod attachment;
     /// API related to authentication.
     pub mod auth;
+    /// API related to capability negotiation.
+    pub mod capability;
+    /// API related to export.
+    pub mod export;
+    /// API related to import.
+    pub mod import;
+    /// API related to journal.
+    pub mod journal;
     /// API related to post.
     pub mod post;
+    /// API related to post comment.
+    pub mod post_comment;
+    /// API related to post share.
+    pub mod post_share;
+    /// API related to writing prompt.
+    pub mod prompt;
+    /// API related to tag.
+    pub mod tag;
+    /// API related to telemetry.
+    pub mod telemetry;
+    /// API related to template.
+    pub mod template;
+    /// API related to unsubscribe links in emails.
+    pub mod unsubscribe;
     /// API related to user.
     pub mod user;
 }
 
 /// Reusable functions for multiple modules.
 pub mod utils {
+    /// Utilities related to capability negotiation.
+    pub mod capability_util;
+    /// Utilities related to self-test of external dependencies.
+    pub mod check_util;
+    /// Utilities related to datetimes in JSON.
+    pub mod datetime_util;
     /// Utilities related to HTTP.
     pub mod http_util;
+    /// Utilities related to access token.
+    pub mod jwt_util;
     /// Utilities related to service.
     pub mod meta_util;
+    /// Utilities related to permission.
+    pub mod permission_util;
     /// Utilities related to session.
     pub mod session_util;
+    /// Utilities related to request timeout.
+    pub mod timeout_util;
 }
 
-use utils::meta_util::{MetaInfo, ENV};
+use utils::check_util;
+use utils::http_util::{self, Convention};
+use utils::meta_util::{self, MetaInfo, ENV};
+use utils::session_util;
+use utils::timeout_util::{self, RequestTimeout};
 
 /// Health check
 #[get("/")]
@@ -58,49 +120,94 @@ async fn main() -> std::io::Result<()> {
     let env = env::var("ENV").expect("ENV not found");
     let meta_info = MetaInfo::new(ENV::from_string(&env));
 
+    // `darim-api-gateway check` tests external dependencies and exits instead of running the server.
+    if env::args().nth(1).as_deref() == Some("check") {
+        std::process::exit(check_util::run(&meta_info).await);
+    }
+
     let host = env::var("HOST").expect("HOST not found");
     let port = env::var("PORT").expect("PORT not found");
     let address = format!("{}:{}", host, port);
 
-    let server = HttpServer::new(|| {
+    let request_timeout = RequestTimeout::from_env();
+
+    let server = HttpServer::new(move || {
         let client_address = env::var("CLIENT_ADDRESS").expect("CLIENT_ADDRESS not found");
+        let request_timeout = request_timeout.clone();
         App::new()
+            .wrap_fn(move |req, srv| {
+                let http_req = req.request().clone();
+                let timeout = request_timeout.get(req.path());
+                let response = srv.call(req);
+                timeout_util::apply_timeout(http_req, timeout, response)
+            })
+            .wrap_fn(|req, srv| {
+                let convention = Convention::from_request(req.request());
+                let response = srv.call(req);
+                async move { http_util::apply_convention(convention, response.await?).await }
+            })
+            .wrap_fn(|mut req, srv| {
+                http_util::serve_head_by_get(&mut req);
+                let response = srv.call(req);
+                async move { http_util::apply_etag(response.await?).await }
+            })
+            .wrap_fn(|req, srv| {
+                let response = srv.call(req);
+                async move { Ok(session_util::apply_impersonating_header(response.await?)) }
+            })
             .wrap(
                 Cors::default()
                     .allowed_origin(&client_address)
-                    .allowed_methods(vec!["GET", "POST", "PATCH", "DELETE"])
+                    .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
                     .allowed_headers(vec![
                         http::header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                        http::header::AUTHORIZATION,
                         http::header::CONTENT_TYPE,
+                        http::header::IF_UNMODIFIED_SINCE,
+                        http::header::HeaderName::from_static("x-api-convention"),
+                        http::header::HeaderName::from_static(
+                            routes::post_share::SHARE_PASSPHRASE_HEADER,
+                        ),
+                    ])
+                    .expose_headers(vec![
+                        http::header::CONTENT_DISPOSITION,
+                        http::header::ETAG,
+                        http::header::RETRY_AFTER,
+                        http::header::HeaderName::from_static("ratelimit-limit"),
+                        http::header::HeaderName::from_static("ratelimit-remaining"),
+                        http::header::HeaderName::from_static("ratelimit-reset"),
+                        http::header::HeaderName::from_static("x-impersonating"),
                     ])
                     .supports_credentials()
                     .max_age(3600),
             )
-            .wrap(
-                CookieSession::signed(&[0; 64])
-                    .secure(true)
-                    .http_only(true)
-                    .max_age_time(Duration::days(30)),
-            )
+            .wrap(session_util::get_session_store())
+            .app_data(http_util::get_json_config())
             .service(health_check)
+            .service(http_util::get_options_resource("/", &[Method::GET]))
             .configure(routes::auth::init_routes)
+            .configure(routes::capability::init_routes)
+            // Export routes come before post routes, whose `/posts/{id}` takes any path.
+            .configure(routes::export::init_routes)
             .configure(routes::post::init_routes)
+            .configure(routes::post_share::init_routes)
+            .configure(routes::post_comment::init_routes)
+            .configure(routes::tag::init_routes)
+            .configure(routes::journal::init_routes)
+            .configure(routes::template::init_routes)
+            .configure(routes::prompt::init_routes)
+            .configure(routes::unsubscribe::init_routes)
             .configure(routes::user::init_routes)
+            .configure(routes::telemetry::init_routes)
+            .configure(routes::import::init_routes)
+            .configure(routes::attachment::init_routes)
+            .configure(routes::admin::init_routes)
     });
 
     println!("Server running at {}", address);
 
     if meta_info.is_production() {
-        let cert_file_path = env::var("TLS_CERT_FILE_PATH").expect("TLS_CERT_FILE_PATH not found");
-        let key_file_path = env::var("TLS_KEY_FILE_PATH").expect("TLS_KEY_FILE_PATH not found");
-
-        let mut config = ServerConfig::new(NoClientAuth::new());
-        let cert_file = &mut BufReader::new(File::open(cert_file_path).unwrap());
-        let key_file = &mut BufReader::new(File::open(key_file_path).unwrap());
-        let cert_chain = certs(cert_file).unwrap();
-        let mut keys = pkcs8_private_keys(key_file).unwrap();
-
-        config.set_single_cert(cert_chain, keys.remove(0)).unwrap();
+        let config = meta_util::get_tls_config().unwrap_or_else(|error| panic!("{}", error));
         server.bind_rustls(address, config)
     } else {
         server.bind(address)
@@ -0,0 +1,152 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime_util;
+
+/// Arguments for `GET /admin/users` API.
+#[derive(Serialize, Deserialize)]
+pub struct UserListArgs {
+    /// A keyword contained in names or emails of users.
+    pub q: Option<String>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Arguments for `GET /admin/stats` API.
+#[derive(Serialize, Deserialize)]
+pub struct StatsArgs {
+    /// Number of days counted, up to today.
+    pub days: Option<u32>,
+}
+
+/// Arguments for `GET /admin/telemetry` API.
+#[derive(Serialize, Deserialize)]
+pub struct TelemetryArgs {
+    /// Number of days counted, up to today.
+    pub days: Option<u32>,
+}
+
+/// Arguments for `POST /admin/impersonate/:user_id` API.
+#[derive(Serialize, Deserialize)]
+pub struct ImpersonateArgs {
+    /// Whether the admin may write posts as the user. It is false by default.
+    pub allow_writes: Option<bool>,
+}
+
+/// Login session issued to an admin impersonating a user, using between api gateway and
+/// the service.
+#[derive(Serialize, Deserialize)]
+pub struct ImpersonationDTO {
+    pub user_id: u64,
+    pub allow_writes: bool,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub expires_at: NaiveDateTime,
+}
+
+/// Count of a usage event on a day using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct TelemetryCountDTO {
+    pub event: String,
+    pub date: NaiveDate,
+    pub count: u64,
+}
+
+/// Result of a probe on an external dependency using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct CheckResultDTO {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+/// Results of every probe using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct SelftestDTO {
+    pub passed: bool,
+    pub checks: Vec<CheckResultDTO>,
+}
+
+/// Arguments for `GET /admin/audit` API.
+#[derive(Serialize, Deserialize)]
+pub struct AuditArgs {
+    pub user_id: Option<u64>,
+    pub event: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub ip: Option<String>,
+    pub cursor: Option<String>,
+    pub per_page: Option<u32>,
+    pub format: Option<String>,
+}
+
+/// Entry of the audit log using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct AuditEventDTO {
+    pub source: String,
+    pub id: u64,
+    pub user_id: u64,
+    pub admin_id: Option<u64>,
+    pub event: String,
+    pub request: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// A page of the audit log using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct AuditPageDTO {
+    pub events: Vec<AuditEventDTO>,
+    pub next_cursor: Option<String>,
+}
+
+/// Arguments for `GET /admin/invites` API.
+#[derive(Serialize, Deserialize)]
+pub struct InviteListArgs {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// User DTO of the admin API using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct AdminUserDTO {
+    pub id: u64,
+    pub name: String,
+    pub email: String,
+    pub role: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub suspended_at: Option<NaiveDateTime>,
+}
+
+/// Number of accounts or posts created on a date.
+#[derive(Serialize, Deserialize)]
+pub struct DailyCountDTO {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+/// Statistics of the instance using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct AdminStatsDTO {
+    pub user_count: i64,
+    pub suspended_user_count: i64,
+    pub post_count: i64,
+    pub signups: Vec<DailyCountDTO>,
+    pub posts: Vec<DailyCountDTO>,
+}
+
+/// Invite DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct InviteDTO {
+    pub id: u64,
+    pub code: String,
+    pub created_by: Option<u64>,
+    pub redeemed_by: Option<u64>,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub redeemed_at: Option<NaiveDateTime>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
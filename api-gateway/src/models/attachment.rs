@@ -0,0 +1,23 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime_util;
+
+/// Arguments for `POST /posts/:id/attachments` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceUploadArgs {
+    pub user_id: u64,
+    pub filename: String,
+}
+
+/// Attachment DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct AttachmentDTO {
+    pub id: u64,
+    pub post_id: u64,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: u64,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
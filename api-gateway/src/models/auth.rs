@@ -1,10 +1,24 @@
+use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::utils::datetime_util;
 
 /// Arguments for `GET /auth` API.
 #[derive(Serialize, Deserialize)]
 pub struct LoginArgs {
     pub email: String,
     pub password: String,
+    /// Label of the session, which tells the sessions signed in on the device apart
+    /// for switching between them.
+    pub client_session_label: Option<String>,
+}
+
+/// Arguments for `POST /auth/login` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceLoginArgs {
+    pub email: String,
+    pub password: String,
 }
 
 /// Arguments for `POST /auth/token` API.
@@ -14,6 +28,7 @@ pub struct SetSignUpTokenArgs {
     pub email: String,
     pub password: String,
     pub avatar_url: Option<String>,
+    pub invite_code: Option<String>,
 }
 
 /// Arguments for `POST /auth/token/password` API.
@@ -22,6 +37,242 @@ pub struct SetPasswordTokenArgs {
     pub email: String,
 }
 
+/// Arguments for `POST /auth/token` API.
+#[derive(Serialize, Deserialize)]
+pub struct TokenArgs {
+    pub email: String,
+    pub password: String,
+    /// A TOTP code or a recovery code, if the user has enabled two-factor authentication.
+    pub code: Option<String>,
+}
+
+/// Arguments for `POST /auth/token/refresh` and `POST /auth/token/revoke` API.
+#[derive(Serialize, Deserialize)]
+pub struct RefreshTokenArgs {
+    pub refresh_token: String,
+}
+
+/// Arguments for `POST /auth/magic-link` API.
+#[derive(Serialize, Deserialize)]
+pub struct MagicLinkArgs {
+    pub email: String,
+}
+
+/// Arguments for `POST /auth/magic-link/login` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceMagicLinkLoginArgs {
+    pub token: String,
+}
+
+/// Arguments for `POST /auth/login/2fa` API.
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorLoginArgs {
+    /// A TOTP code or a recovery code.
+    pub code: String,
+}
+
+/// Arguments for `POST /auth/login/2fa` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceTwoFactorLoginArgs {
+    pub token: String,
+    pub code: String,
+}
+
+/// Arguments for `POST /auth/2fa/setup` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceTwoFactorSetupArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /auth/2fa/verify` and `POST /auth/2fa/disable` API.
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorCodeArgs {
+    /// A TOTP code, or a recovery code to disable two-factor authentication.
+    pub code: String,
+}
+
+/// Arguments for `POST /auth/2fa/verify` and `POST /auth/2fa/disable` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceTwoFactorCodeArgs {
+    pub user_id: u64,
+    pub code: String,
+}
+
+/// Arguments for `GET /auth/oauth/:provider/callback` API, given by the provider.
+#[derive(Serialize, Deserialize)]
+pub struct OAuthCallbackArgs {
+    pub code: Option<String>,
+    pub state: Option<String>,
+    /// Error of the provider, such as `access_denied` if the user has declined.
+    pub error: Option<String>,
+}
+
+/// Arguments for `POST /auth/oauth/:provider/login` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceOAuthLoginArgs {
+    pub code: String,
+}
+
+/// Arguments for `POST /auth/webauthn/register/challenge` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceWebauthnRegisterChallengeArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /auth/webauthn/register` API.
+///
+/// The credential is passed to the service as it is, which verifies it.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnRegisterArgs {
+    pub credential: Value,
+}
+
+/// Arguments for `POST /auth/webauthn/register` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceWebauthnRegisterArgs {
+    pub user_id: u64,
+    pub credential: Value,
+}
+
+/// Arguments for `POST /auth/webauthn/login/challenge` API.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnLoginChallengeArgs {
+    pub email: String,
+}
+
+/// Arguments for `POST /auth/webauthn/login` API.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnLoginArgs {
+    pub credential: Value,
+}
+
+/// Arguments for `POST /auth/webauthn/login` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceWebauthnLoginArgs {
+    pub token: String,
+    pub credential: Value,
+}
+
+/// Two-factor setup DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorSetupDTO {
+    /// TOTP secret encoded in base32.
+    pub secret: String,
+    /// `otpauth` URI of the secret.
+    pub uri: String,
+}
+
+/// Challenge of signing in with a passkey in the service.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnLoginChallengeDTO {
+    /// Token of the ceremony, which is kept in the session instead of being responded.
+    pub token: String,
+    /// Options passed to `navigator.credentials.get()`.
+    pub options: Value,
+}
+
+/// Passkey DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnCredentialDTO {
+    pub id: u64,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+/// Arguments for `POST /auth/sessions` and `POST /auth/tokens` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceCreateLoginSessionArgs {
+    pub user_id: u64,
+    /// Label of the session given by the client when signing in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// Arguments for `POST /auth/sessions/verify` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceVerifyLoginSessionArgs {
+    pub session_id: String,
+    /// Method of the request made with the session, audited if the session is impersonated.
+    pub method: Option<String>,
+    /// Path of the request made with the session, audited if the session is impersonated.
+    pub path: Option<String>,
+}
+
+/// Arguments for `POST /auth/sessions/logout` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceLoginSessionArgs {
+    pub user_id: u64,
+    pub session_id: String,
+}
+
+/// Login session DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct LoginSessionDTO {
+    pub id: u64,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub last_seen_at: NaiveDateTime,
+    pub is_current: bool,
+}
+
+/// Login session of a device DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct DeviceLoginSessionDTO {
+    pub id: u64,
+    pub user_id: u64,
+    pub user_name: String,
+    pub user_avatar_url: Option<String>,
+    pub label: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub last_seen_at: NaiveDateTime,
+    pub is_current: bool,
+}
+
+/// Access token and refresh token, which clients without cookies authenticate with.
+#[derive(Serialize, Deserialize)]
+pub struct TokenDTO {
+    pub access_token: String,
+    /// Always `Bearer`.
+    pub token_type: String,
+    /// Seconds the access token is valid.
+    pub expires_in: i64,
+    pub refresh_token: String,
+}
+
+/// Session of a user refreshed by a refresh token in the service, with a new refresh token.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceTokenDTO {
+    pub session: UserSession,
+    pub refresh_token: String,
+}
+
+/// Arguments for `POST /auth/personal-access-tokens/verify` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServicePersonalAccessTokenArgs {
+    pub token: String,
+}
+
+/// Session of the user who owns a personal access token, with the scope of the token.
+#[derive(Serialize, Deserialize)]
+pub struct ServicePersonalAccessTokenSessionDTO {
+    pub session: UserSession,
+    pub scope: String,
+}
+
+/// Result of signing in with email and password in the service.
+///
+/// It has a login token instead of the session if the user has to enter a two-factor code.
+#[derive(Serialize, Deserialize)]
+pub struct LoginDTO {
+    pub session: Option<UserSession>,
+    pub two_factor_token: Option<String>,
+}
+
 /// Session containing information of the logged-in user.
 #[derive(Serialize, Deserialize)]
 pub struct UserSession {
@@ -30,4 +281,93 @@ pub struct UserSession {
     pub user_name: String,
     pub user_public_key: String,
     pub user_avatar_url: Option<String>,
+    /// `user` or `admin`. Sessions issued before roles existed are regular users.
+    #[serde(default = "get_default_user_role")]
+    pub user_role: String,
+    /// Admin impersonating the user, if the session has been issued to the admin.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<Impersonator>,
+}
+
+/// Admin impersonating the user of a session.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Impersonator {
+    pub admin_id: u64,
+    /// Whether the admin may write posts. Otherwise, the session can only read them.
+    pub allow_writes: bool,
+}
+
+/// Returns the role of a session which does not have one.
+pub fn get_default_user_role() -> String {
+    String::from("user")
+}
+
+/// Permissions that can be granted to an authenticated principal.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Permission {
+    ReadPosts,
+    WritePosts,
+    ManageAccount,
+    Admin,
+}
+
+/// An authenticated principal and the permissions granted to it.
+pub struct Principal {
+    pub user_session: UserSession,
+    pub permissions: Vec<Permission>,
+}
+
+impl Principal {
+    /// Creates a principal authenticated by a full session.
+    /// A session is granted every permission of a regular user, and the admin permission
+    /// if the user is an admin.
+    ///
+    /// A session issued to an admin impersonating the user is granted only to read posts,
+    /// and to write them if the admin has allowed writes, but never to manage the account
+    /// or to administer.
+    ///
+    /// The session must have been verified by `session_util::verify_session`, which reads
+    /// the user from the service rather than the access token.
+    pub fn from_session(user_session: UserSession) -> Self {
+        let permissions = match &user_session.impersonator {
+            Some(impersonator) if impersonator.allow_writes => {
+                vec![Permission::ReadPosts, Permission::WritePosts]
+            }
+            Some(_) => vec![Permission::ReadPosts],
+            None if user_session.user_role == "admin" => vec![
+                Permission::ReadPosts,
+                Permission::WritePosts,
+                Permission::ManageAccount,
+                Permission::Admin,
+            ],
+            None => vec![
+                Permission::ReadPosts,
+                Permission::WritePosts,
+                Permission::ManageAccount,
+            ],
+        };
+        Self {
+            user_session,
+            permissions,
+        }
+    }
+
+    /// Creates a principal authenticated by a personal access token.
+    /// A token is granted to read posts, and to write them if its scope is `write`,
+    /// but never to manage the account.
+    pub fn from_personal_access_token(user_session: UserSession, scope: &str) -> Self {
+        let permissions = match scope {
+            "write" => vec![Permission::ReadPosts, Permission::WritePosts],
+            _ => vec![Permission::ReadPosts],
+        };
+        Self {
+            user_session,
+            permissions,
+        }
+    }
+
+    /// Returns whether the principal is granted the permission.
+    pub fn has_permission(&self, permission: Permission) -> bool {
+        self.permissions.contains(&permission)
+    }
 }
@@ -0,0 +1,9 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Capabilities DTO responded to the client.
+#[derive(Serialize, Deserialize)]
+pub struct CapabilitiesDTO {
+    pub version: String,
+    pub features: BTreeMap<String, bool>,
+}
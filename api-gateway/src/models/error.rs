@@ -8,6 +8,24 @@ pub enum ApiGatewayError {
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("missing_permission")]
+    MissingPermission,
+
+    #[error("two_factor_required")]
+    TwoFactorRequired,
+
+    #[error("unsupported_media_type")]
+    UnsupportedMediaType,
+
+    #[error("missing_file")]
+    MissingFile,
+
+    #[error("payload_too_large")]
+    PayloadTooLarge,
+
+    #[error("timeout")]
+    Timeout,
+
     #[error("internal server error")]
     InternalServerError,
 
@@ -0,0 +1,67 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime_util;
+
+/// Arguments for `GET /export` API.
+#[derive(Serialize, Deserialize)]
+pub struct ExportArgs {
+    /// Whether posts in the trash are included.
+    pub include_trash: Option<bool>,
+}
+
+/// Arguments for `POST /posts/export-jobs` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateExportJobArgs {
+    /// A format of the archive, which is only `tar.gz` for now.
+    pub format: String,
+    /// Whether posts in the trash are included.
+    pub include_trash: Option<bool>,
+}
+
+/// Arguments for `POST /export/jobs` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceCreateExportJobArgs {
+    pub user_id: u64,
+    pub format: String,
+    pub include_trash: Option<bool>,
+}
+
+/// Export job DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct ExportJobDTO {
+    pub id: u64,
+    pub format: String,
+    pub include_trash: bool,
+    pub status: String,
+    pub progress: u32,
+    pub total: u32,
+    pub size: Option<u64>,
+    /// URL downloading the archive in the client, until the archive is downloaded.
+    pub download_url: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub expires_at: NaiveDateTime,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+/// Arguments for `GET /export/calendar.ics` API.
+#[derive(Serialize, Deserialize)]
+pub struct CalendarArgs {
+    /// A token of the calendar feed.
+    pub token: String,
+}
+
+/// Arguments for `POST /export/calendar` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceCreateCalendarFeedArgs {
+    pub user_id: u64,
+}
+
+/// Calendar feed DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct CalendarFeedDTO {
+    pub token: String,
+}
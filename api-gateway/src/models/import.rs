@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+/// Arguments for `POST /import` API.
+#[derive(Serialize, Deserialize)]
+pub struct ImportArgs {
+    /// Whether the import is only reported without creating anything.
+    pub dry_run: Option<bool>,
+}
+
+/// Imported file DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct ImportedFileDTO {
+    pub path: String,
+    /// `created`, `duplicated`, or `failed`.
+    pub status: String,
+    pub post_id: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Post import DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PostImportDTO {
+    pub dry_run: bool,
+    pub created_count: usize,
+    pub duplicated_count: usize,
+    pub failed_count: usize,
+    pub created_tag_count: usize,
+    pub files: Vec<ImportedFileDTO>,
+}
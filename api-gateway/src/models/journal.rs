@@ -0,0 +1,32 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime_util;
+
+/// Arguments for `POST /journals` and `PATCH /journals/:id` API.
+#[derive(Serialize, Deserialize)]
+pub struct JournalArgs {
+    /// A name of the journal, encrypted by the client like titles of posts.
+    pub name: String,
+}
+
+/// Arguments for `POST /journals` and `PATCH /journals/:id` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceJournalArgs {
+    pub user_id: u64,
+    pub name: String,
+}
+
+/// Journal DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct JournalDTO {
+    pub id: u64,
+    /// A name of the journal, which is empty for the default journal until it is renamed.
+    pub name: String,
+    /// Whether posts are written in the journal unless another journal is given.
+    pub is_default: bool,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
@@ -1,21 +1,79 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 
+use crate::utils::datetime_util;
+
 /// Arguments for `POST /posts` API.
 #[derive(Serialize, Deserialize)]
 pub struct CreateArgs {
-    pub title: String,
-    pub content: String,
-    pub date: NaiveDateTime,
+    /// A title of the post, which is taken from the template if omitted with `template_id`.
+    pub title: Option<String>,
+    /// A content of the post, which is taken from the template if omitted with `template_id`.
+    pub content: Option<String>,
+    /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+    pub date: String,
+    /// Ids of tags of the post.
+    pub tags: Option<Vec<u64>>,
+    /// `published` or `draft`.
+    pub status: Option<String>,
+    /// Mood from 1 (worst) to 5 (best).
+    pub mood: Option<u8>,
+    /// `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`.
+    pub weather: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Name of the place, encrypted by the client.
+    pub place_name: Option<String>,
+    /// Id of the journal of the post, which is the default journal if omitted.
+    pub journal_id: Option<u64>,
+    /// Number of words in the content, counted by the client before encryption.
+    pub word_count: Option<u32>,
+    /// Whether the title and content are encrypted by the client, which is true if omitted.
+    pub encrypted: Option<bool>,
+    /// Name of the encryption scheme such as `aes-256-gcm`.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption.
+    pub nonce: Option<String>,
+    /// Version of the key of the user, starting from 1.
+    pub key_version: Option<u32>,
+    /// Id of the template the post is created from.
+    pub template_id: Option<u64>,
 }
 
 /// Arguments for `POST /posts` API of the service.
 #[derive(Serialize, Deserialize)]
 pub struct ServiceCreateArgs {
     pub user_id: u64,
-    pub title: String,
-    pub content: String,
-    pub date: NaiveDateTime,
+    pub title: Option<String>,
+    pub content: Option<String>,
+    /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+    pub date: String,
+    /// Ids of tags of the post.
+    pub tags: Option<Vec<u64>>,
+    /// `published` or `draft`.
+    pub status: Option<String>,
+    /// Mood from 1 (worst) to 5 (best).
+    pub mood: Option<u8>,
+    /// `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`.
+    pub weather: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Name of the place, encrypted by the client.
+    pub place_name: Option<String>,
+    /// Id of the journal of the post, which is the default journal if omitted.
+    pub journal_id: Option<u64>,
+    /// Number of words in the content, counted by the client before encryption.
+    pub word_count: Option<u32>,
+    /// Whether the title and content are encrypted by the client, which is true if omitted.
+    pub encrypted: Option<bool>,
+    /// Name of the encryption scheme such as `aes-256-gcm`.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption.
+    pub nonce: Option<String>,
+    /// Version of the key of the user, starting from 1.
+    pub key_version: Option<u32>,
+    /// Id of the template the post is created from.
+    pub template_id: Option<u64>,
 }
 
 /// Arguments for `PATCH /posts/:id` API.
@@ -23,7 +81,31 @@ pub struct ServiceCreateArgs {
 pub struct UpdateArgs {
     pub title: Option<String>,
     pub content: Option<String>,
-    pub date: Option<NaiveDateTime>,
+    /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+    pub date: Option<String>,
+    /// Ids of tags replacing the tags of the post.
+    pub tags: Option<Vec<u64>>,
+    pub mood: Option<u8>,
+    pub weather: Option<String>,
+    /// Location replacing the location of the post, which requires both latitude and longitude.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Name of the place, encrypted by the client.
+    pub place_name: Option<String>,
+    /// Id of the journal the post is moved to.
+    pub journal_id: Option<u64>,
+    /// Number of words in the new content, counted by the client before encryption.
+    pub word_count: Option<u32>,
+    /// Whether the new title and content are encrypted by the client, which is true if omitted.
+    pub encrypted: Option<bool>,
+    /// Name of the encryption scheme such as `aes-256-gcm`.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption.
+    pub nonce: Option<String>,
+    /// Version of the key of the user, starting from 1.
+    pub key_version: Option<u32>,
+    /// Version of the post the edit is based on.
+    pub version: Option<u32>,
 }
 
 /// Arguments for `PATCH /posts/:id` API of the service.
@@ -32,7 +114,139 @@ pub struct ServiceUpdateArgs {
     pub user_id: u64,
     pub title: Option<String>,
     pub content: Option<String>,
-    pub date: Option<NaiveDateTime>,
+    /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+    pub date: Option<String>,
+    /// Ids of tags replacing the tags of the post.
+    pub tags: Option<Vec<u64>>,
+    pub mood: Option<u8>,
+    pub weather: Option<String>,
+    /// Location replacing the location of the post, which requires both latitude and longitude.
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Name of the place, encrypted by the client.
+    pub place_name: Option<String>,
+    /// Id of the journal the post is moved to.
+    pub journal_id: Option<u64>,
+    /// Number of words in the new content, counted by the client before encryption.
+    pub word_count: Option<u32>,
+    /// Whether the new title and content are encrypted by the client, which is true if omitted.
+    pub encrypted: Option<bool>,
+    /// Name of the encryption scheme such as `aes-256-gcm`.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption.
+    pub nonce: Option<String>,
+    /// Version of the key of the user, starting from 1.
+    pub key_version: Option<u32>,
+    /// Version of the post the edit is based on.
+    pub version: Option<u32>,
+}
+
+/// Operation on a post in `POST /posts/bulk` API.
+///
+/// The kind of the operation is distinguished by `op` field.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PostOperation {
+    Create {
+        title: String,
+        content: String,
+        /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+        date: String,
+        /// Ids of tags of the post.
+        tags: Option<Vec<u64>>,
+        /// `published` or `draft`.
+        status: Option<String>,
+        /// Mood from 1 (worst) to 5 (best).
+        mood: Option<u8>,
+        /// `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`.
+        weather: Option<String>,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+        /// Name of the place, encrypted by the client.
+        place_name: Option<String>,
+        /// Id of the journal of the post, which is the default journal if omitted.
+        journal_id: Option<u64>,
+        /// Number of words in the content, counted by the client before encryption.
+        word_count: Option<u32>,
+        /// Whether the title and content are encrypted by the client, which is true if omitted.
+        encrypted: Option<bool>,
+        /// Name of the encryption scheme such as `aes-256-gcm`.
+        encryption_scheme: Option<String>,
+        /// Base64 nonce of the encryption.
+        nonce: Option<String>,
+        /// Version of the key of the user, starting from 1.
+        key_version: Option<u32>,
+    },
+    Update {
+        id: u64,
+        title: Option<String>,
+        content: Option<String>,
+        /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+        date: Option<String>,
+        /// Ids of tags replacing the tags of the post.
+        tags: Option<Vec<u64>>,
+        mood: Option<u8>,
+        weather: Option<String>,
+        latitude: Option<f64>,
+        longitude: Option<f64>,
+        place_name: Option<String>,
+        /// Id of the journal the post is moved to.
+        journal_id: Option<u64>,
+        /// Number of words in the new content, counted by the client before encryption.
+        word_count: Option<u32>,
+        /// Whether the new title and content are encrypted by the client, which is true if omitted.
+        encrypted: Option<bool>,
+        /// Name of the encryption scheme such as `aes-256-gcm`.
+        encryption_scheme: Option<String>,
+        /// Base64 nonce of the encryption.
+        nonce: Option<String>,
+        /// Version of the key of the user, starting from 1.
+        key_version: Option<u32>,
+        /// Version of the post the edit is based on.
+        version: Option<u32>,
+    },
+    Delete {
+        id: u64,
+    },
+}
+
+/// Arguments for `POST /posts/bulk` API.
+#[derive(Serialize, Deserialize)]
+pub struct BulkArgs {
+    pub operations: Vec<PostOperation>,
+    /// Index of the first operation to execute, which is `next_cursor` of the last batch.
+    pub continue_from: Option<usize>,
+}
+
+/// Arguments for `POST /posts/bulk` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceBulkArgs {
+    pub user_id: u64,
+    pub operations: Vec<PostOperation>,
+    pub continue_from: Option<usize>,
+}
+
+/// Result of an operation in `POST /posts/bulk` API.
+#[derive(Serialize, Deserialize)]
+pub struct BulkResultDTO {
+    /// Status code of the operation, as if it were requested alone.
+    pub status: u16,
+    /// Id of the created, updated, or deleted post.
+    pub data: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Batch of operations executed in `POST /posts/bulk` API.
+#[derive(Serialize, Deserialize)]
+pub struct BulkBatchDTO {
+    /// Result of each operation in the batch.
+    pub results: Vec<BulkResultDTO>,
+    /// Number of operations executed so far, including the previous batches.
+    pub processed: usize,
+    /// Number of operations left after the batch.
+    pub remaining: usize,
+    /// `continue_from` of the next request, or `None` if all operations are executed.
+    pub next_cursor: Option<usize>,
 }
 
 /// Post DTO using between api gateway and the service.
@@ -41,9 +255,45 @@ pub struct PostDTO {
     pub id: u64,
     pub title: String,
     pub content: String,
-    pub date: NaiveDateTime,
+    /// RFC 3339 datetime with offset, or naive datetime for posts written by legacy clients.
+    pub date: String,
+    /// Order among the posts of the same date, starting from 0.
+    pub intra_day_order: u16,
+    /// Ids of tags of the post.
+    pub tags: Vec<u64>,
+    /// Id of the journal of the post.
+    pub journal_id: u64,
+    /// `published` or `draft`.
+    pub status: String,
+    #[serde(with = "datetime_util::rfc3339")]
     pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
     pub updated_at: Option<NaiveDateTime>,
+    pub version: u32,
+    /// Whether the post is marked as a favorite.
+    pub is_favorite: bool,
+    /// Mood from 1 (worst) to 5 (best).
+    pub mood: Option<u8>,
+    /// `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`.
+    pub weather: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Name of the place, encrypted by the client.
+    pub place_name: Option<String>,
+    /// Number of words in the content counted by the client, or `None` if it is not given
+    /// since the content was changed.
+    pub word_count: Option<u32>,
+    /// Whether the post is locked against updates and deletion.
+    pub is_locked: bool,
+    /// Whether the title and content are encrypted by the client. They are opaque to the service
+    /// either way.
+    pub encrypted: bool,
+    /// Name of the encryption scheme, or `None` for posts encrypted before it was recorded.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption.
+    pub nonce: Option<String>,
+    /// Version of the key of the user the post is encrypted with.
+    pub key_version: Option<u32>,
 }
 
 /// Summarized post DTO using between api gateway and the service.
@@ -51,5 +301,340 @@ pub struct PostDTO {
 pub struct SummarizedPostDTO {
     pub id: u64,
     pub title: String,
-    pub date: NaiveDateTime,
+    /// RFC 3339 datetime with offset, or naive datetime for posts written by legacy clients.
+    pub date: String,
+}
+
+/// Posts of a day in a calendar DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct CalendarDayDTO {
+    /// The date in the offset where the posts were written.
+    pub date: NaiveDate,
+    pub count: usize,
+    pub posts: Vec<SummarizedPostDTO>,
+}
+
+/// Year in an archive DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveYearDTO {
+    pub year: i32,
+    pub count: usize,
+    pub months: Vec<ArchiveMonthDTO>,
+}
+
+/// Month in an archive DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveMonthDTO {
+    pub month: u32,
+    pub count: usize,
+}
+
+/// Posts of a month in an archive DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveMonthPostsDTO {
+    pub month: u32,
+    pub count: usize,
+    pub posts: Vec<SummarizedPostDTO>,
+}
+
+/// Arguments for `GET /posts/heatmap` API.
+#[derive(Serialize, Deserialize)]
+pub struct HeatmapArgs {
+    /// A year, which is the current year in UTC by default.
+    pub year: Option<i32>,
+}
+
+/// Numbers of posts on each day of a year DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct HeatmapDTO {
+    pub year: i32,
+    /// Number of posts on each day from January 1.
+    pub counts: Vec<usize>,
+}
+
+/// Post in the trash DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct TrashedPostDTO {
+    pub id: u64,
+    pub title: String,
+    pub content: String,
+    /// RFC 3339 datetime with offset, or naive datetime for posts written by legacy clients.
+    pub date: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub deleted_at: NaiveDateTime,
+    /// Datetime after which the post is permanently deleted.
+    #[serde(with = "datetime_util::rfc3339")]
+    pub purge_at: NaiveDateTime,
+    pub encrypted: bool,
+    pub encryption_scheme: Option<String>,
+    pub nonce: Option<String>,
+    pub key_version: Option<u32>,
+}
+
+/// Post revision DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PostRevisionDTO {
+    /// Version of the post the revision was taken from.
+    pub version: u32,
+    pub title: String,
+    pub content: String,
+    /// RFC 3339 datetime with offset, or naive datetime for posts written by legacy clients.
+    pub date: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    pub encrypted: bool,
+    pub encryption_scheme: Option<String>,
+    pub nonce: Option<String>,
+    pub key_version: Option<u32>,
+}
+
+/// Arguments for `GET /posts` API.
+#[derive(Serialize, Deserialize)]
+pub struct ListArgs {
+    pub tag: Option<u64>,
+    pub journal: Option<u64>,
+    /// The first local date in `YYYY-MM-DD` format, inclusive.
+    pub from: Option<String>,
+    /// The last local date in `YYYY-MM-DD` format, inclusive.
+    pub to: Option<String>,
+    /// `published` or `draft`.
+    pub status: Option<String>,
+    /// Whether to list only favorites, or only the others.
+    pub favorite: Option<bool>,
+    /// A location in `latitude,longitude` format the posts are written near.
+    pub near: Option<String>,
+    /// Radius in meters around `near`.
+    pub radius: Option<f64>,
+    pub sort_by: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    /// `all`, or `summary` to list only ids, titles and dates of the posts.
+    pub fields: Option<String>,
+}
+
+/// Arguments for `GET /posts/on-this-day` API.
+#[derive(Serialize, Deserialize)]
+pub struct OnThisDayArgs {
+    /// The local date of the client in `YYYY-MM-DD` format.
+    pub date: Option<String>,
+}
+
+/// Arguments for `PUT /posts/:id/autosave` API.
+#[derive(Serialize, Deserialize)]
+pub struct AutosaveArgs {
+    pub title: Option<String>,
+    pub content: String,
+    /// Whether the saved title and content are encrypted by the client, which is true if omitted.
+    pub encrypted: Option<bool>,
+    /// Name of the encryption scheme such as `aes-256-gcm`.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption.
+    pub nonce: Option<String>,
+    /// Version of the key of the user, starting from 1.
+    pub key_version: Option<u32>,
+    /// Version of the post the edit is based on.
+    pub version: Option<u32>,
+}
+
+/// Arguments for `PUT /posts/:id/autosave` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceAutosaveArgs {
+    pub user_id: u64,
+    pub title: Option<String>,
+    pub content: String,
+    /// Whether the saved title and content are encrypted by the client, which is true if omitted.
+    pub encrypted: Option<bool>,
+    /// Name of the encryption scheme such as `aes-256-gcm`.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption.
+    pub nonce: Option<String>,
+    /// Version of the key of the user, starting from 1.
+    pub key_version: Option<u32>,
+    /// Version of the post the edit is based on.
+    pub version: Option<u32>,
+}
+
+/// Number of encrypted posts and revisions in a key version DTO using between api gateway
+/// and the service.
+#[derive(Serialize, Deserialize)]
+pub struct KeyVersionCountDTO {
+    /// Name of the encryption scheme, or `None` for posts encrypted before it was recorded.
+    pub encryption_scheme: Option<String>,
+    pub key_version: Option<u32>,
+    /// Number of posts including posts in the trash.
+    pub post_count: i64,
+    pub revision_count: i64,
+}
+
+/// Arguments for `GET /posts/changes` API.
+#[derive(Serialize, Deserialize)]
+pub struct ChangesArgs {
+    /// `cursor` of the last sync, or an RFC 3339 datetime.
+    pub since: Option<String>,
+}
+
+/// Arguments for `GET /posts/stats/moods` API.
+#[derive(Serialize, Deserialize)]
+pub struct MoodStatsArgs {
+    /// The first local date in `YYYY-MM-DD` format, inclusive.
+    pub from: Option<String>,
+    /// The last local date in `YYYY-MM-DD` format, inclusive.
+    pub to: Option<String>,
+    /// `day`, `week` or `month`.
+    pub interval: Option<String>,
+}
+
+/// Arguments for `GET /posts/stats/entries` API.
+#[derive(Serialize, Deserialize)]
+pub struct EntryStatsArgs {
+    /// The first local date in `YYYY-MM-DD` format, inclusive.
+    pub from: Option<String>,
+    /// The last local date in `YYYY-MM-DD` format, inclusive.
+    pub to: Option<String>,
+    /// `daily`, `weekly` or `monthly`.
+    pub granularity: Option<String>,
+}
+
+/// Number of posts in a period DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct EntryCountDTO {
+    /// The first date of the period.
+    pub date: NaiveDate,
+    pub count: usize,
+}
+
+/// Moods of posts in a period DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct MoodTrendDTO {
+    /// The first date of the period.
+    pub date: NaiveDate,
+    /// Number of posts with moods in the period.
+    pub count: usize,
+    pub average: f64,
+    /// Number of posts of each mood from 1 to 5.
+    pub distribution: [usize; 5],
+}
+
+/// Deleted post DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct DeletedPostDTO {
+    pub id: u64,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub deleted_at: NaiveDateTime,
+}
+
+/// Changes of posts DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PostChangesDTO {
+    pub posts: Vec<PostDTO>,
+    pub deleted: Vec<DeletedPostDTO>,
+    /// `since` of the next sync.
+    #[serde(with = "datetime_util::rfc3339")]
+    pub cursor: NaiveDateTime,
+}
+
+/// Arguments for `PATCH /posts/:id/reorder` API.
+#[derive(Serialize, Deserialize)]
+pub struct ReorderArgs {
+    pub position: usize,
+}
+
+/// Arguments for `PATCH /posts/:id/reorder` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceReorderArgs {
+    pub user_id: u64,
+    pub position: usize,
+}
+
+/// Arguments for `PATCH /posts/:id/publish` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServicePublishArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `PUT /posts/:id/favorite` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceFavoriteArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /posts/:id/lock` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceLockArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /posts/:id/restore` and `POST /posts/:id/revisions/:version/restore` API
+/// of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceRestoreArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /posts/:id/duplicate` API.
+#[derive(Serialize, Deserialize)]
+pub struct DuplicateArgs {
+    /// RFC 3339 datetime of the new post, which is now if omitted.
+    pub date: Option<String>,
+}
+
+/// Arguments for `POST /posts/:id/duplicate` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceDuplicateArgs {
+    pub user_id: u64,
+    pub date: Option<String>,
+}
+
+/// Query of `DELETE /posts/by-date/:date` API.
+#[derive(Serialize, Deserialize)]
+pub struct DeleteByDateQuery {
+    pub permanent: Option<bool>,
+    pub dry_run: Option<bool>,
+}
+
+/// Arguments for `DELETE /posts/by-date/:date` API.
+#[derive(Serialize, Deserialize)]
+pub struct DeleteByDateArgs {
+    pub password: String,
+}
+
+/// Arguments for `DELETE /posts/:user_id/by-date/:date` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceDeleteByDateArgs {
+    pub password: String,
+    pub permanent: Option<bool>,
+    pub dry_run: Option<bool>,
+}
+
+/// Post date deletion DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PostDateDeletionDTO {
+    pub dry_run: bool,
+    pub date: NaiveDate,
+    pub post_ids: Vec<u64>,
+    pub post_audit_count: usize,
+    pub attachment_count: usize,
+}
+
+/// Arguments for `GET /posts/audit` and `GET /posts/:id/audit` API.
+#[derive(Serialize, Deserialize)]
+pub struct AuditListArgs {
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub since: Option<NaiveDateTime>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Post audit DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PostAuditDTO {
+    pub id: u64,
+    pub post_id: u64,
+    pub action: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub session_id: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
 }
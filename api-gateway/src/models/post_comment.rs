@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime_util;
+
+/// Arguments for `POST /posts/:id/comments` and `POST /shared/:token/comments` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateArgs {
+    /// Plaintext content of the comment.
+    pub content: String,
+}
+
+/// Arguments for `POST /posts/:id/comments` and `POST /shared/:token/comments` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceCreateArgs {
+    pub user_id: u64,
+    pub content: String,
+}
+
+/// Post comment DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PostCommentDTO {
+    pub id: u64,
+    pub writer_name: String,
+    pub content: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    /// Whether the comment is written by logged-in user.
+    pub is_mine: bool,
+}
@@ -0,0 +1,61 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime_util;
+
+/// Arguments for `POST /posts/:id/share` API.
+#[derive(Serialize, Deserialize)]
+pub struct ShareArgs {
+    /// RFC 3339 datetime when the share expires. The share never expires without it.
+    pub expires_at: Option<String>,
+    /// A passphrase which readers of the share must give.
+    pub passphrase: Option<String>,
+    /// Plaintext copy of the title for readers without the key.
+    pub title: Option<String>,
+    /// Plaintext copy of the content in markdown for readers without the key.
+    pub content: Option<String>,
+}
+
+/// Arguments for `POST /posts/:id/share` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceShareArgs {
+    pub user_id: u64,
+    pub expires_at: Option<String>,
+    pub passphrase: Option<String>,
+    pub title: Option<String>,
+    pub content: Option<String>,
+}
+
+/// Form submitted from the page asking the passphrase of a share.
+#[derive(Serialize, Deserialize)]
+pub struct PassphraseForm {
+    pub passphrase: String,
+}
+
+/// Post share DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PostShareDTO {
+    pub token: String,
+    /// URL of the shared post in the client.
+    pub url: String,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub expires_at: Option<NaiveDateTime>,
+    pub has_passphrase: bool,
+}
+
+/// Shared post DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct SharedPostDTO {
+    pub title: String,
+    pub content: String,
+    pub date: String,
+    pub mood: Option<u8>,
+    pub weather: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+    /// Whether title and content are plaintext copies given by the writer,
+    /// or encrypted ones which only the client can read.
+    pub is_plaintext: bool,
+}
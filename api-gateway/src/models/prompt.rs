@@ -0,0 +1,23 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+/// Arguments for `GET /prompts/today` API.
+#[derive(Serialize, Deserialize)]
+pub struct TodayArgs {
+    /// Local date of the client in `YYYY-MM-DD` format.
+    pub date: Option<String>,
+}
+
+/// Arguments for `POST /prompts/subscription` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceSubscribeArgs {
+    pub user_id: u64,
+}
+
+/// Prompt DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PromptDTO {
+    pub id: u64,
+    pub text: String,
+    pub date: NaiveDate,
+}
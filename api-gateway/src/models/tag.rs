@@ -0,0 +1,37 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime_util;
+
+/// Arguments for `POST /tags` and `PATCH /tags/:id` API.
+#[derive(Serialize, Deserialize)]
+pub struct TagArgs {
+    /// A name of the tag, encrypted by the client like titles of posts.
+    pub name: String,
+}
+
+/// Arguments for `POST /tags` and `PATCH /tags/:id` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceTagArgs {
+    pub user_id: u64,
+    pub name: String,
+}
+
+/// Tag DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct TagDTO {
+    pub id: u64,
+    pub name: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Tag with usage DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct TagUsageDTO {
+    pub id: u64,
+    pub name: String,
+    pub post_count: u64,
+}
@@ -0,0 +1,14 @@
+use serde::{Deserialize, Serialize};
+
+/// Arguments for `POST /telemetry` API.
+#[derive(Serialize, Deserialize)]
+pub struct RecordArgs {
+    pub event: String,
+}
+
+/// Arguments for `POST /telemetry` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceRecordArgs {
+    pub user_id: u64,
+    pub event: String,
+}
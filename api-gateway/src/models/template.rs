@@ -0,0 +1,55 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::utils::datetime_util;
+
+/// Arguments for `POST /templates` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateArgs {
+    /// A name of the template, encrypted by the client like titles of posts.
+    pub name: String,
+    /// A title of posts created from the template, encrypted by the client. It may be empty.
+    #[serde(default)]
+    pub title: String,
+    /// A content of posts created from the template, encrypted by the client.
+    pub content: String,
+}
+
+/// Arguments for `POST /templates` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceCreateArgs {
+    pub user_id: u64,
+    pub name: String,
+    pub title: String,
+    pub content: String,
+}
+
+/// Arguments for `PATCH /templates/:id` API.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateArgs {
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub content: Option<String>,
+}
+
+/// Arguments for `PATCH /templates/:id` API of the service.
+#[derive(Serialize, Deserialize)]
+pub struct ServiceUpdateArgs {
+    pub user_id: u64,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub content: Option<String>,
+}
+
+/// Template DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct TemplateDTO {
+    pub id: u64,
+    pub name: String,
+    pub title: String,
+    pub content: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
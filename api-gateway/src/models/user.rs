@@ -1,6 +1,8 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 
+use crate::utils::datetime_util;
+
 /// Arguments for `POST /users` API.
 #[derive(Serialize, Deserialize)]
 pub struct CreateArgs {
@@ -15,7 +17,12 @@ pub struct CreateArgs {
 pub struct UpdateArgs {
     pub name: Option<String>,
     pub password: Option<String>,
-    pub avatar_url: Option<String>,
+}
+
+/// Arguments for `DELETE /users/:id` API.
+#[derive(Serialize, Deserialize)]
+pub struct DeleteArgs {
+    pub dry_run: Option<bool>,
 }
 
 /// Arguments for `POST /users/password` API.
@@ -27,13 +34,188 @@ pub struct ResetPasswordArgs {
     pub new_password: String,
 }
 
-/// User DTO using between api gateway and the service.
+/// Arguments for `GET /users/:id/streak` API.
+#[derive(Serialize, Deserialize)]
+pub struct StreakArgs {
+    pub date: Option<String>,
+}
+
+/// Arguments for `PATCH /users/:id/goals` API.
+#[derive(Serialize, Deserialize)]
+pub struct WordGoalsArgs {
+    /// Number of words to write in a day, or 0 to remove the goal.
+    pub daily: Option<u32>,
+    /// Number of words to write in a month, or 0 to remove the goal.
+    pub monthly: Option<u32>,
+}
+
+/// Arguments for `GET /users/:id/goals/progress` API.
+#[derive(Serialize, Deserialize)]
+pub struct WordGoalProgressArgs {
+    pub date: Option<String>,
+}
+
+/// Arguments for `PATCH /users/:id/settings` API.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateSettingsArgs {
+    pub timezone: Option<String>,
+    pub locale: Option<String>,
+    pub week_start_day: Option<String>,
+    pub editor_preferences: Option<EditorPreferences>,
+    pub default_journal_id: Option<u64>,
+    pub telemetry_opt_in: Option<bool>,
+}
+
+/// Arguments for `GET /users/:id/logins` API.
+#[derive(Serialize, Deserialize)]
+pub struct LoginHistoryArgs {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Arguments for `POST /users/:id/email` API.
+#[derive(Serialize, Deserialize)]
+pub struct ChangeEmailArgs {
+    pub email: String,
+}
+
+/// Arguments for `POST /users/:id/export` API.
 #[derive(Serialize, Deserialize)]
-pub struct UserDTO {
+pub struct ExportAccountArgs {
+    /// `download` (default) streams the archive, and `email` emails a link downloading it.
+    pub delivery: Option<String>,
+}
+
+/// Arguments for `POST /users/:id/tokens` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreatePersonalAccessTokenArgs {
+    pub name: String,
+    /// `read` or `write`
+    pub scope: String,
+}
+
+/// Profile DTO of the logged-in user using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct ProfileDTO {
     pub id: u64,
     pub name: String,
     pub email: String,
     pub avatar_url: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
     pub created_at: NaiveDateTime,
-    pub updated_at: Option<NaiveDateTime>,
+    pub public_key_fingerprint: Option<String>,
+    pub settings: ProfileSettingsDTO,
+}
+
+/// Settings of a user in the profile.
+#[derive(Serialize, Deserialize)]
+pub struct ProfileSettingsDTO {
+    pub daily_word_goal: Option<u32>,
+    pub monthly_word_goal: Option<u32>,
+}
+
+/// User settings DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct UserSettingsDTO {
+    pub timezone: String,
+    pub locale: String,
+    pub week_start_day: String,
+    pub editor_preferences: EditorPreferences,
+    pub default_journal_id: Option<u64>,
+    pub telemetry_opt_in: bool,
+}
+
+/// Preferences of the editor of the client.
+#[derive(Serialize, Deserialize)]
+pub struct EditorPreferences {
+    pub font_size: Option<u8>,
+    pub font_family: Option<String>,
+    pub spell_check: Option<bool>,
+    pub show_word_count: Option<bool>,
+}
+
+/// User deletion DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct UserDeletionDTO {
+    pub dry_run: bool,
+    pub user_id: u64,
+    pub post_ids: Vec<u64>,
+    pub tag_count: usize,
+    pub user_key_count: usize,
+}
+
+/// Metadata of the key material kept by the client.
+///
+/// It never contains keys themselves, so unknown fields are rejected.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyMetadata {
+    pub keys: Vec<KeyMetadataEntry>,
+    pub checksum: Option<String>,
+}
+
+/// Metadata of a key kept by the client.
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyMetadataEntry {
+    pub key_id: String,
+    pub algorithm: String,
+    /// Datetime defined by the client, which is kept in the format the client has sent.
+    pub created_at: NaiveDateTime,
+}
+
+/// Streak DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct StreakDTO {
+    pub current_streak: u32,
+    pub longest_streak: u32,
+    pub last_entry_date: Option<NaiveDate>,
+}
+
+/// Progress toward word goals DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct WordGoalProgressDTO {
+    pub date: NaiveDate,
+    pub daily: WordGoalDTO,
+    pub monthly: WordGoalDTO,
+}
+
+/// Progress toward a word goal DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct WordGoalDTO {
+    pub goal: Option<u32>,
+    pub word_count: u64,
+    pub is_achieved: bool,
+}
+
+/// Login history DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct LoginHistoryDTO {
+    pub id: u64,
+    pub ip: Option<String>,
+    pub country: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Personal access token DTO using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct PersonalAccessTokenDTO {
+    pub id: u64,
+    pub name: String,
+    pub scope: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+/// Personal access token DTO with the token itself, which is responded only once.
+#[derive(Serialize, Deserialize)]
+pub struct CreatedPersonalAccessTokenDTO {
+    pub id: u64,
+    pub name: String,
+    pub scope: String,
+    pub token: String,
 }
@@ -0,0 +1,691 @@
+use actix_session::Session;
+use actix_web::{delete, get, post, web, HttpRequest, Responder};
+use http::header::HeaderValue;
+use http::{Method, StatusCode};
+use reqwest::Client;
+
+use crate::models::admin::*;
+use crate::models::error::{get_api_error_message, ApiGatewayError};
+use crate::models::user::UserDeletionDTO;
+use crate::utils::permission_util::{self, Authorized, CanAdmin};
+use crate::utils::{http_util, session_util};
+
+/// Lists users found by a keyword, the most recent signup first
+///
+/// Admin APIs require a session of an admin, made by `darim-server grant-admin <email>`.
+/// The service checks the admin again, and records actions taken on users in `admin_audits` table.
+///
+/// # Request
+///
+/// ```text
+/// GET /admin/users?q=park&page=1&per_page=20
+/// ```
+///
+/// ## Parameters
+///
+/// * q - A keyword contained in names or emails of users. (optional)
+/// * page - A page number starting from 1. (optional, default: 1)
+/// * per_page - Number of users in a page, up to 100. (optional, default: 20)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "name": "park",
+///             "email": "park@email.com",
+///             "role": "user",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "suspended_at": null
+///         }
+///     ],
+///     "meta": {
+///         "total_count": 1,
+///         "page": 1,
+///         "per_page": 20
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/admin/users")]
+pub async fn get_users(
+    auth: Authorized<CanAdmin>,
+    args: web::Query<UserListArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = Client::new()
+        .get(&http_util::get_url(&format!("/admin/users?{}", query)))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<Vec<AdminUserDTO>>(response).await
+}
+
+/// Suspends a user
+///
+/// The user is signed out of every device, and cannot sign in until the suspension is lifted.
+/// It responds `400 Bad Request` if the user is an admin.
+///
+/// # Request
+///
+/// ```text
+/// POST /admin/users/:id/suspend
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/admin/users/{id}/suspend")]
+pub async fn suspend_user(auth: Authorized<CanAdmin>, id: web::Path<u64>) -> impl Responder {
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/admin/users/{}/suspend",
+            id.into_inner()
+        )))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Lifts the suspension of a user
+///
+/// # Request
+///
+/// ```text
+/// DELETE /admin/users/:id/suspend
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/admin/users/{id}/suspend")]
+pub async fn unsuspend_user(auth: Authorized<CanAdmin>, id: web::Path<u64>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/admin/users/{}/suspend",
+            id.into_inner()
+        )))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Deletes a user with every data of the user
+///
+/// It responds `400 Bad Request` if the user is an admin.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /admin/users/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "dry_run": false,
+///         "user_id": 1,
+///         "post_ids": [1, 2, 3],
+///         "tag_count": 2,
+///         "user_key_count": 1
+///     },
+///     "error": null
+/// }
+/// ```
+#[delete("/admin/users/{id}")]
+pub async fn delete_user(auth: Authorized<CanAdmin>, id: web::Path<u64>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/admin/users/{}",
+            id.into_inner()
+        )))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<UserDeletionDTO>(response).await
+}
+
+/// Impersonates a user to see the service as the user does
+///
+/// The session cookie is switched to a new session of the user, issued to the admin, until
+/// `POST /admin/impersonate/stop` switches it back. The session is read-only unless
+/// `allow_writes` is true, never manages the account or administers, and expires in 30 minutes.
+/// Every request made with it is audited with both the admin and the user, and its response
+/// has `X-Impersonating` header with id of the user.
+///
+/// It responds `400 Bad Request` if the user is an admin.
+///
+/// # Request
+///
+/// ```text
+/// POST /admin/impersonate/:user_id
+/// ```
+///
+/// ```json
+/// {
+///     "allow_writes": false
+/// }
+/// ```
+///
+/// ## Parameters
+///
+/// * allow_writes - Whether the admin may write posts as the user. (optional, default: false)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "user_id": 5,
+///         "allow_writes": false,
+///         "expires_at": "2020-04-13T17:01:09Z"
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/admin/impersonate/{user_id}")]
+pub async fn impersonate(
+    auth: Authorized<CanAdmin>,
+    mut session: Session,
+    user_id: web::Path<u64>,
+    args: web::Json<ImpersonateArgs>,
+) -> impl Responder {
+    let admin_session_id = match session_util::get_session_id(&session) {
+        Some(session_id) => session_id,
+        None => {
+            return http_util::get_err_response::<ImpersonationDTO>(
+                StatusCode::UNAUTHORIZED,
+                &get_api_error_message(ApiGatewayError::Unauthorized),
+            )
+        }
+    };
+
+    // The impersonation is a new session of the user, rather than the session of the admin.
+    let new_session_id = session_util::generate_session_id();
+    let mut headers = auth.admin_headers();
+    if let Ok(session_id) = HeaderValue::from_str(&new_session_id) {
+        headers.insert("X-Session-Id", session_id);
+    }
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/admin/impersonate/{}",
+            user_id.into_inner()
+        )))
+        .headers(headers)
+        .json(&args.into_inner())
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) if response.status() == StatusCode::OK => response,
+        response => return http_util::pass_response::<ImpersonationDTO>(response).await,
+    };
+
+    match http_util::parse_data_from_service_response::<ImpersonationDTO>(response).await {
+        Ok(Some(impersonation)) => {
+            if session_util::set_impersonator_session_id(&mut session, &admin_session_id)
+                && session_util::switch_session_id(&mut session, &new_session_id)
+            {
+                http_util::get_ok_response::<ImpersonationDTO>(impersonation)
+            } else {
+                let _ = session_util::switch_session_id(&mut session, &admin_session_id);
+                http_util::get_err_response::<ImpersonationDTO>(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &get_api_error_message(ApiGatewayError::InternalServerError),
+                )
+            }
+        }
+        _ => http_util::get_err_response::<ImpersonationDTO>(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+        ),
+    }
+}
+
+/// Stops impersonating a user
+///
+/// It is requested with the session of the impersonation, which is ended, and the session
+/// cookie is switched back to the session of the admin.
+///
+/// # Request
+///
+/// ```text
+/// POST /admin/impersonate/stop
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/admin/impersonate/stop")]
+pub async fn stop_impersonation(req: HttpRequest, mut session: Session) -> impl Responder {
+    let session_id = session_util::get_session_id(&session);
+    let response = Client::new()
+        .post(&http_util::get_url("/admin/impersonate/stop"))
+        .headers(permission_util::get_forwarded_headers(&req, &session_id))
+        .send()
+        .await;
+
+    // The admin gets back to the own session even if the impersonation has already expired.
+    if let Some(admin_session_id) = session_util::take_impersonator_session_id(&mut session) {
+        let _ = session_util::switch_session_id(&mut session, &admin_session_id);
+    }
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Responds the number of users and posts, and signups and posts on each day
+///
+/// Days are UTC dates, and days without signups or posts are omitted.
+///
+/// # Request
+///
+/// ```text
+/// GET /admin/stats?days=30
+/// ```
+///
+/// ## Parameters
+///
+/// * days - Number of days counted up to today, up to 365. (optional, default: 30)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "user_count": 12,
+///         "suspended_user_count": 1,
+///         "post_count": 340,
+///         "signups": [
+///             {
+///                 "date": "2020-05-09",
+///                 "count": 2
+///             }
+///         ],
+///         "posts": [
+///             {
+///                 "date": "2020-05-08",
+///                 "count": 14
+///             },
+///             {
+///                 "date": "2020-05-09",
+///                 "count": 9
+///             }
+///         ]
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/admin/stats")]
+pub async fn get_stats(auth: Authorized<CanAdmin>, args: web::Query<StatsArgs>) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = Client::new()
+        .get(&http_util::get_url(&format!("/admin/stats?{}", query)))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<AdminStatsDTO>(response).await
+}
+
+/// Lists counts of usage events on each day, sent by users who opted in
+///
+/// Events are counted by name and UTC date only, without users. Days without an event
+/// are omitted.
+///
+/// # Request
+///
+/// ```text
+/// GET /admin/telemetry?days=30
+/// ```
+///
+/// ## Parameters
+///
+/// * days - Number of days counted up to today, up to 365. (optional, default: 30)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "event": "calendar_view",
+///             "date": "2020-05-09",
+///             "count": 21
+///         },
+///         {
+///             "event": "post_write",
+///             "date": "2020-05-09",
+///             "count": 8
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/admin/telemetry")]
+pub async fn get_telemetry(
+    auth: Authorized<CanAdmin>,
+    args: web::Query<TelemetryArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = Client::new()
+        .get(&http_util::get_url(&format!("/admin/telemetry?{}", query)))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<Vec<TelemetryCountDTO>>(response).await
+}
+
+/// Lists entries of the audit log, the most recent first, or streams all of them as CSV
+///
+/// The audit log contains actions of admins (`admin.*`), logins (`auth.login`), and actions on
+/// posts (`post.*`). Requests made by admins impersonating users are `admin.impersonated_request`
+/// with `request` such as `GET /posts`. Reading or exporting it is audited as `admin.read_audit` or
+/// `admin.export_audit` as well, on the user filtered by or the admin.
+///
+/// Pages are continued by `next_cursor` of the last page, so that entries written while paging
+/// do not shift the pages. With `Accept: text/csv` or `format=csv`, all entries meeting
+/// the filters are streamed as a CSV file with the header row, ignoring `cursor` and `per_page`.
+/// Cells which a spreadsheet would read as a formula are prefixed with `'`.
+///
+/// # Request
+///
+/// ```text
+/// GET /admin/audit?user_id=5&event=post.delete&from=2020-05-01&to=2020-05-09
+/// ```
+///
+/// ## Parameters
+///
+/// * user_id - An id of the user of the entries. (optional)
+/// * event - An event of the entries like `post.delete`. (optional)
+/// * from - The first UTC date of the entries. (optional)
+/// * to - The last UTC date of the entries. (optional)
+/// * ip - An IP from which the entries were made. (optional)
+/// * cursor - `next_cursor` of the last page. (optional)
+/// * per_page - Number of entries in a page, up to 100. (optional, default: 20)
+/// * format - `json` or `csv`. (optional, default: `json`)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "events": [
+///             {
+///                 "source": "post",
+///                 "id": 31,
+///                 "user_id": 5,
+///                 "admin_id": null,
+///                 "event": "post.delete",
+///                 "request": null,
+///                 "ip": "203.0.113.7",
+///                 "user_agent": "Mozilla/5.0",
+///                 "created_at": "2020-05-09T12:00:00Z"
+///             }
+///         ],
+///         "next_cursor": "1589025600-post-31"
+///     },
+///     "error": null
+/// }
+/// ```
+///
+/// ```text
+/// created_at,event,user_id,admin_id,request,ip,user_agent,source,id
+/// 2020-05-09T12:00:00Z,post.delete,5,,,203.0.113.7,Mozilla/5.0,post,31
+/// ```
+#[get("/admin/audit")]
+pub async fn get_audit(
+    req: HttpRequest,
+    auth: Authorized<CanAdmin>,
+    args: web::Query<AuditArgs>,
+) -> impl Responder {
+    let mut args = args.into_inner();
+    if http_util::accepts_csv(req.headers()) {
+        args.format = Some(String::from("csv"));
+    }
+    let is_csv = args.format.as_deref() == Some("csv");
+
+    let query = serde_urlencoded::to_string(&args).unwrap_or_default();
+    let response = Client::new()
+        .get(&http_util::get_url(&format!("/admin/audit?{}", query)))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    if is_csv {
+        http_util::pass_stream(response).await
+    } else {
+        http_util::pass_response::<AuditPageDTO>(response).await
+    }
+}
+
+/// Probes the database, redis, email, and storage of the server, like `darim-server check`
+///
+/// Each probe fails if it does not finish in 5 seconds. It responds 200 OK even if a probe
+/// fails, with `passed` false.
+///
+/// # Request
+///
+/// ```text
+/// POST /admin/selftest
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "passed": false,
+///         "checks": [
+///             {
+///                 "name": "database",
+///                 "passed": true,
+///                 "message": "42 migrations applied"
+///             },
+///             {
+///                 "name": "redis",
+///                 "passed": true,
+///                 "message": "PONG"
+///             },
+///             {
+///                 "name": "email",
+///                 "passed": false,
+///                 "message": "EMAIL_ADDRESS not found"
+///             },
+///             {
+///                 "name": "storage",
+///                 "passed": true,
+///                 "message": "writable ./storage"
+///             }
+///         ]
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/admin/selftest")]
+pub async fn selftest(auth: Authorized<CanAdmin>) -> impl Responder {
+    let response = Client::new()
+        .post(&http_util::get_url("/admin/selftest"))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<SelftestDTO>(response).await
+}
+
+/// Lists invite codes with who redeemed them, the most recently minted first
+///
+/// # Request
+///
+/// ```text
+/// GET /admin/invites?page=1&per_page=20
+/// ```
+///
+/// ## Parameters
+///
+/// * page - A page number starting from 1. (optional, default: 1)
+/// * per_page - Number of invites in a page, up to 100. (optional, default: 20)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 2,
+///             "code": "k3Zp9QaW1xTb",
+///             "created_by": 1,
+///             "redeemed_by": null,
+///             "redeemed_at": null,
+///             "created_at": "2020-05-10T09:12:40Z"
+///         },
+///         {
+///             "id": 1,
+///             "code": "Hq7uN2vLc0Ye",
+///             "created_by": 1,
+///             "redeemed_by": 5,
+///             "redeemed_at": "2020-05-09T21:03:11Z",
+///             "created_at": "2020-05-09T20:47:02Z"
+///         }
+///     ],
+///     "meta": {
+///         "total_count": 2,
+///         "page": 1,
+///         "per_page": 20
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/admin/invites")]
+pub async fn get_invites(
+    auth: Authorized<CanAdmin>,
+    args: web::Query<InviteListArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = Client::new()
+        .get(&http_util::get_url(&format!("/admin/invites?{}", query)))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<Vec<InviteDTO>>(response).await
+}
+
+/// Mints a single-use invite code
+///
+/// Signups require an unused code in `invite_code` of `POST /auth/token/sign_up`
+/// if the service runs with `REGISTRATION_MODE=invite`.
+///
+/// # Request
+///
+/// ```text
+/// POST /admin/invites
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "id": 2,
+///         "code": "k3Zp9QaW1xTb",
+///         "created_by": 1,
+///         "redeemed_by": null,
+///         "redeemed_at": null,
+///         "created_at": "2020-05-10T09:12:40Z"
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/admin/invites")]
+pub async fn create_invite(auth: Authorized<CanAdmin>) -> impl Responder {
+    let response = Client::new()
+        .post(&http_util::get_url("/admin/invites"))
+        .headers(auth.admin_headers())
+        .send()
+        .await;
+
+    http_util::pass_response::<InviteDTO>(response).await
+}
+
+/// Initializes the admin routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_users);
+    cfg.service(suspend_user);
+    cfg.service(unsuspend_user);
+    cfg.service(delete_user);
+    // `stop` is registered first, since `{user_id}` matches it as well.
+    cfg.service(stop_impersonation);
+    cfg.service(impersonate);
+    cfg.service(get_stats);
+    cfg.service(get_telemetry);
+    cfg.service(selftest);
+    cfg.service(get_audit);
+    cfg.service(get_invites);
+    cfg.service(create_invite);
+
+    cfg.service(http_util::get_options_resource(
+        "/admin/users",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/users/{id}",
+        &[Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/users/{id}/suspend",
+        &[Method::POST, Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/impersonate/stop",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/impersonate/{user_id}",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/stats",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/telemetry",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/selftest",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/audit",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/admin/invites",
+        &[Method::GET, Method::POST],
+    ));
+}
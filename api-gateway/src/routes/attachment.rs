@@ -0,0 +1,224 @@
+use actix_multipart::Multipart;
+use actix_web::{delete, get, post, web, HttpRequest, Responder};
+use http::header::{CONTENT_TYPE, IF_NONE_MATCH};
+use http::Method;
+use reqwest::Client;
+
+use crate::models::attachment::*;
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanReadPosts, CanWritePosts};
+
+/// Maximum size of a photo to attach.
+const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// Content type of files forwarded to the service, which detects the actual type by the content.
+const UPLOAD_CONTENT_TYPE: &str = "application/octet-stream";
+
+/// Lists attachments of a post
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/:id/attachments
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the post.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "post_id": 3,
+///             "filename": "photo.jpg",
+///             "mime_type": "image/jpeg",
+///             "size": 204800,
+///             "created_at": "2020-04-13T16:31:09Z"
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/{id}/attachments")]
+pub async fn get_attachments(
+    auth: Authorized<CanReadPosts>,
+    post_id: web::Path<u64>,
+) -> impl Responder {
+    let response = Client::new()
+        .get(&http_util::get_url(&format!(
+            "/posts/{}/{}/attachments",
+            auth.user_id(),
+            post_id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<Vec<AttachmentDTO>>(response).await
+}
+
+/// Attaches a photo to a post
+///
+/// The photo is uploaded as `file` field of `multipart/form-data`. Its type is detected
+/// by the content regardless of `Content-Type`, and responds `415 Unsupported Media Type`
+/// if it is not JPEG, PNG, GIF, WebP, or HEIC. Posts in the trash cannot have new attachments.
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/:id/attachments
+/// Content-Type: multipart/form-data; boundary=boundary
+///
+/// --boundary
+/// Content-Disposition: form-data; name="file"; filename="photo.jpg"
+/// Content-Type: image/jpeg
+///
+/// ...
+/// --boundary--
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the post.
+/// * file - A photo to attach, up to 10 MiB.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": 1,
+///     "error": null
+/// }
+/// ```
+#[post("/posts/{id}/attachments")]
+pub async fn upload_attachment(
+    auth: Authorized<CanWritePosts>,
+    post_id: web::Path<u64>,
+    payload: Multipart,
+) -> impl Responder {
+    let file = match http_util::read_file_field(payload, MAX_ATTACHMENT_SIZE).await {
+        Ok(file) => file,
+        Err(response) => return response,
+    };
+
+    let args = ServiceUploadArgs {
+        user_id: auth.user_id(),
+        filename: file.filename.unwrap_or_default(),
+    };
+    let query = serde_urlencoded::to_string(&args).unwrap_or_default();
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/posts/{}/attachments?{}",
+            post_id, query
+        )))
+        .headers(auth.forwarded_headers())
+        .header(CONTENT_TYPE, UPLOAD_CONTENT_TYPE)
+        .body(file.data)
+        .send()
+        .await;
+
+    http_util::pass_response::<u64>(response).await
+}
+
+/// Downloads the file of an attachment of logged-in user
+///
+/// `ETag` is the hash of the file, and `304 Not Modified` is responded if it matches
+/// `If-None-Match` of the request.
+///
+/// # Request
+///
+/// ```text
+/// GET /attachments/:id
+/// If-None-Match: "9f86d081884c7d65..."
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the attachment.
+///
+/// # Response
+///
+/// ```text
+/// Content-Type: image/jpeg
+/// Content-Disposition: inline; filename="photo.jpg"
+/// ETag: "9f86d081884c7d65..."
+/// Cache-Control: private, no-cache
+/// ```
+#[get("/attachments/{id}")]
+pub async fn download_attachment(
+    req: HttpRequest,
+    auth: Authorized<CanReadPosts>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let mut request = Client::new()
+        .get(&http_util::get_url(&format!(
+            "/attachments/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers());
+    if let Some(if_none_match) = req.headers().get(IF_NONE_MATCH) {
+        request = request.header(IF_NONE_MATCH, if_none_match.clone());
+    }
+
+    http_util::pass_stream(request.send().await).await
+}
+
+/// Deletes an attachment of logged-in user
+///
+/// The file is kept while another attachment has the same content.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /attachments/:id
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the attachment.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/attachments/{id}")]
+pub async fn delete_attachment(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/attachments/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the attachment routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_attachments);
+    cfg.service(upload_attachment);
+    cfg.service(download_attachment);
+    cfg.service(delete_attachment);
+
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/attachments",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/attachments/{id}",
+        &[Method::GET, Method::DELETE],
+    ));
+}
@@ -1,12 +1,16 @@
 use actix_session::Session;
-use actix_web::{get, post, web, Responder};
-use http::StatusCode;
-use reqwest::Client;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use http::header::{HeaderMap, HeaderValue};
+use http::{Method, StatusCode};
+use reqwest::{Client, Response};
+use serde_json::Value;
+use std::env;
 
 use crate::models::auth::*;
 use crate::models::error::{get_api_error_message, ApiGatewayError};
-use crate::models::user::UserDTO;
-use crate::utils::{http_util, session_util};
+use crate::utils::permission_util::{self, Authorized, CanManageAccount};
+use crate::utils::session_util::{self, CurrentUser};
+use crate::utils::{http_util, jwt_util};
 
 /// Responds auth information as user session.
 ///
@@ -24,27 +28,22 @@ use crate::utils::{http_util, session_util};
 ///         "user_id": 0,
 ///         "user_email": "park@email.com"
 ///         "user_name": "park",
-///         "user_avatar_url": "avatar.jpg"
+///         "user_avatar_url": "avatar.jpg",
+///         "user_role": "user"
 ///     },
 ///     "error": null
 /// }
 /// ```
 #[get("/auth")]
-pub async fn get_auth(session: Session) -> impl Responder {
-    let user_session = session_util::get_session(&session);
-
-    if let Some(user_session) = user_session {
-        http_util::get_ok_response::<UserSession>(user_session)
-    } else {
-        http_util::get_err_response::<UserSession>(
-            StatusCode::UNAUTHORIZED,
-            &get_api_error_message(ApiGatewayError::Unauthorized),
-        )
-    }
+pub async fn get_auth(current_user: CurrentUser) -> impl Responder {
+    http_util::get_ok_response::<UserSession>(current_user.0)
 }
 
 /// Refresh auth information as user session.
 ///
+/// The session is read from the service on every request, so it is the same as `GET /auth`.
+/// It is kept for clients refreshing the session after updating the profile.
+///
 /// # Request
 ///
 /// ```text
@@ -59,60 +58,15 @@ pub async fn get_auth(session: Session) -> impl Responder {
 ///         "user_id": 0,
 //          "user_email": "park@email.com"
 ///         "user_name": "park",
-///         "user_avatar_url": "avatar.jpg"
+///         "user_avatar_url": "avatar.jpg",
+///         "user_role": "user"
 ///     },
 ///     "error": null
 /// }
 /// ```
 #[post("/auth")]
-pub async fn refresh_session(mut session: Session) -> impl Responder {
-    let user_session = session_util::get_session(&session);
-    if let Some(user_session) = user_session {
-        let response = reqwest::get(&http_util::get_url(&format!(
-            "/users/{}",
-            user_session.user_id
-        )))
-        .await
-        .unwrap();
-
-        let result = http_util::parse_data_from_service_response::<UserDTO>(response).await;
-        if let Ok(user) = result {
-            if let Some(user) = user {
-                session_util::set_session(
-                    &mut session,
-                    user_session.user_id,
-                    &user_session.user_email,
-                    &user.name,
-                    &user_session.user_public_key,
-                    &user.avatar_url,
-                );
-
-                if let Some(refreshed_user_session) = session_util::get_session(&session) {
-                    http_util::get_ok_response::<UserSession>(refreshed_user_session)
-                } else {
-                    http_util::get_err_response::<UserSession>(
-                        StatusCode::UNAUTHORIZED,
-                        &get_api_error_message(ApiGatewayError::Unauthorized),
-                    )
-                }
-            } else {
-                http_util::get_err_response::<UserSession>(
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
-                )
-            }
-        } else {
-            http_util::get_err_response::<UserSession>(
-                StatusCode::UNAUTHORIZED,
-                &get_api_error_message(ApiGatewayError::Unauthorized),
-            )
-        }
-    } else {
-        http_util::get_err_response::<UserSession>(
-            StatusCode::UNAUTHORIZED,
-            &get_api_error_message(ApiGatewayError::Unauthorized),
-        )
-    }
+pub async fn refresh_session(current_user: CurrentUser) -> impl Responder {
+    http_util::get_ok_response::<UserSession>(current_user.0)
 }
 
 /// Sets token for creating user.
@@ -129,13 +83,16 @@ pub async fn refresh_session(mut session: Session) -> impl Responder {
 /// * email - A unique email of the user.
 /// * password - A password of the user.
 /// * avatar_url - An avatar image url of the user.
+/// * invite_code - An unused invite code minted by an admin.
+///   (required if the service runs with `REGISTRATION_MODE=invite`, otherwise ignored)
 ///
 /// ```json
 /// {
 ///     "name": "park",
 ///     "email": "park@email.com",
 ///     "password": "Ir5c7y8dS3",
-///     "avatar_url": "avatar.jpg"
+///     "avatar_url": "avatar.jpg",
+///     "invite_code": "k3Zp9QaW1xTb"
 /// }
 /// ```
 ///
@@ -147,6 +104,8 @@ pub async fn refresh_session(mut session: Session) -> impl Responder {
 ///     "error": null
 /// }
 /// ```
+///
+/// It responds `422 Unprocessable Entity` if the invite code is required, invalid, or already used.
 #[post("/auth/token/sign_up")]
 pub async fn set_sign_up_token(args: web::Json<SetSignUpTokenArgs>) -> impl Responder {
     let args: SetSignUpTokenArgs = args.into_inner();
@@ -160,6 +119,9 @@ pub async fn set_sign_up_token(args: web::Json<SetSignUpTokenArgs>) -> impl Resp
 
 /// Sets token for resetting password.
 ///
+/// Every email sent is counted in the rate limit of the account and the IP, which is responded
+/// in `RateLimit-*` headers like `POST /auth/login`.
+///
 /// # Request
 ///
 /// ```text
@@ -185,18 +147,160 @@ pub async fn set_sign_up_token(args: web::Json<SetSignUpTokenArgs>) -> impl Resp
 /// }
 /// ```
 #[post("/auth/token/password")]
-pub async fn set_password_token(args: web::Json<SetPasswordTokenArgs>) -> impl Responder {
+pub async fn set_password_token(
+    req: HttpRequest,
+    args: web::Json<SetPasswordTokenArgs>,
+) -> impl Responder {
     let args: SetPasswordTokenArgs = args.into_inner();
     let response = Client::new()
         .post(&http_util::get_url("/auth/token/password"))
+        .headers(permission_util::get_forwarded_headers(&req, &None))
         .json(&args)
         .send()
         .await;
     http_util::pass_response::<bool>(response).await
 }
 
+/// Sets the session of the user who has signed in, and responds it.
+async fn respond_login(
+    session: &mut Session,
+    req: &HttpRequest,
+    user_session: UserSession,
+) -> HttpResponse {
+    if set_login_session(session, req, &user_session).await {
+        http_util::get_ok_response::<UserSession>(user_session)
+    } else {
+        http_util::get_err_response::<UserSession>(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &get_api_error_message(ApiGatewayError::InternalServerError),
+        )
+    }
+}
+
+/// Sets the session of the user who has signed in, and returns whether it is set.
+///
+/// The session is stored in the service with the device, so that `DELETE /auth/sessions/:id`
+/// can revoke it and `POST /auth/switch/:session_id` can switch back to it.
+/// The cookie keeps only the session id and the device id. It is unset if the service fails
+/// to store the session.
+async fn set_login_session(
+    session: &mut Session,
+    req: &HttpRequest,
+    user_session: &UserSession,
+) -> bool {
+    let label = session_util::take_session_label(session);
+    session_util::unset_session(session);
+    let (session_id, device_id) = match (
+        session_util::set_session_id(session),
+        session_util::get_or_set_device_id(session),
+    ) {
+        (Some(session_id), Some(device_id)) => (session_id, device_id),
+        _ => {
+            session_util::unset_session(session);
+            return false;
+        }
+    };
+
+    let headers = get_device_headers(req, &Some(session_id), &device_id);
+    let args = ServiceCreateLoginSessionArgs {
+        user_id: user_session.user_id,
+        label,
+    };
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/sessions"))
+        .headers(headers)
+        .json(&args)
+        .send()
+        .await;
+
+    let is_created = match response {
+        Ok(response) => matches!(
+            http_util::parse_data_from_service_response::<bool>(response).await,
+            Ok(Some(true))
+        ),
+        Err(_) => false,
+    };
+    if !is_created {
+        session_util::unset_session(session);
+    }
+    is_created
+}
+
+/// Returns headers forwarded to the service with the id of the device in `X-Device-Id` header.
+fn get_device_headers(
+    req: &HttpRequest,
+    session_id: &Option<String>,
+    device_id: &str,
+) -> HeaderMap {
+    let mut headers = permission_util::get_forwarded_headers(req, session_id);
+    if let Ok(device_id) = HeaderValue::from_str(device_id) {
+        headers.insert("X-Device-Id", device_id);
+    }
+    headers
+}
+
+/// Sets the session of the user who has signed in by the response of the service, and responds it.
+///
+/// If the user has enabled two-factor authentication, it keeps the login token
+/// for `POST /auth/login/2fa` and responds `two_factor_required` error instead.
+async fn respond_login_result(
+    session: &mut Session,
+    req: &HttpRequest,
+    response: reqwest::Result<Response>,
+) -> HttpResponse {
+    let response = match response {
+        Ok(response) if response.status() != StatusCode::TOO_MANY_REQUESTS => response,
+        response => return http_util::pass_response::<UserSession>(response).await,
+    };
+
+    let login = http_util::parse_data_from_service_response::<LoginDTO>(response).await;
+    session_util::take_two_factor_token(session);
+    match login {
+        Ok(Some(LoginDTO {
+            session: Some(user_session),
+            ..
+        })) => respond_login(session, req, user_session).await,
+        Ok(Some(LoginDTO {
+            two_factor_token: Some(two_factor_token),
+            ..
+        })) => {
+            session_util::set_two_factor_token(session, &two_factor_token);
+            http_util::get_err_response::<UserSession>(
+                StatusCode::UNAUTHORIZED,
+                &get_api_error_message(ApiGatewayError::TwoFactorRequired),
+            )
+        }
+        Ok(_) => http_util::get_err_response::<UserSession>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        ),
+        Err(_) => http_util::get_err_response::<UserSession>(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+        ),
+    }
+}
+
 /// Signs in to set user session.
 ///
+/// If the user has enabled two-factor authentication, the session is not set yet and it responds
+/// `401 Unauthorized` with `two_factor_required` error. Then `POST /auth/login/2fa`
+/// finishes signing in with a code in 5 minutes.
+///
+/// Repeated failures lock the account and the IP for a while, which doubles on each failure
+/// after that. Every response has `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset`
+/// headers of the failures allowed until being locked. While locked, it responds
+/// `429 Too Many Requests` with `Retry-After` header:
+///
+/// ```json
+/// {
+///     "data": null,
+///     "error": "too many attempts, retry after 60 seconds",
+///     "retry_after": 60,
+///     "reset_at": 1586795529
+/// }
+/// ```
+///
 /// # Request
 ///
 /// ```text
@@ -207,11 +311,14 @@ pub async fn set_password_token(args: web::Json<SetPasswordTokenArgs>) -> impl R
 ///
 /// * email - A unique email of the user.
 /// * password - A password of the user.
+/// * client_session_label - (Optional) A label of the session. Signing in as another user
+/// keeps the current session on the device, which `POST /auth/switch/:session_id` switches back to.
 ///
 /// ```json
 /// {
 ///     "email": "park@email.com",
 ///     "password": "Ir5c7y8dS3",
+///     "client_session_label": "Dad"
 /// }
 /// ```
 ///
@@ -228,81 +335,1526 @@ pub async fn set_password_token(args: web::Json<SetPasswordTokenArgs>) -> impl R
 /// }
 /// ```
 #[post("/auth/login")]
-pub async fn login(mut session: Session, args: web::Json<LoginArgs>) -> impl Responder {
-    let args: LoginArgs = args.into_inner();
+pub async fn login(
+    mut session: Session,
+    req: HttpRequest,
+    args: web::Json<LoginArgs>,
+) -> impl Responder {
+    let LoginArgs {
+        email,
+        password,
+        client_session_label,
+    } = args.into_inner();
+    session_util::set_session_label(&mut session, &client_session_label);
+
     let response = Client::new()
         .post(&http_util::get_url("/auth/login"))
-        .json(&args)
+        .headers(permission_util::get_forwarded_headers(&req, &None))
+        .json(&ServiceLoginArgs { email, password })
         .send()
         .await;
 
-    if let Ok(response) = response {
-        let user_session =
-            http_util::parse_data_from_service_response::<UserSession>(response).await;
-        if let Ok(user_session) = user_session {
-            if let Some(user_session) = user_session {
-                session_util::set_session(
-                    &mut session,
-                    user_session.user_id,
-                    &user_session.user_email,
-                    &user_session.user_name,
-                    &user_session.user_public_key,
-                    &user_session.user_avatar_url,
-                );
-                http_util::get_ok_response::<UserSession>(user_session)
-            } else {
-                http_util::get_err_response::<UserSession>(
+    let rate_limit_headers = http_util::get_rate_limit_headers(&response);
+    let response = respond_login_result(&mut session, &req, response).await;
+    http_util::with_headers(response, &rate_limit_headers)
+}
+
+/// Issues an access token of the user and the session id of a new login session.
+fn get_token(
+    user_session: &UserSession,
+    session_id: &str,
+    refresh_token: String,
+) -> Result<TokenDTO, ApiGatewayError> {
+    Ok(TokenDTO {
+        access_token: jwt_util::issue_access_token(user_session, session_id)?,
+        token_type: String::from("Bearer"),
+        expires_in: jwt_util::ACCESS_TOKEN_TTL_SECONDS,
+        refresh_token,
+    })
+}
+
+/// Returns the session of the user by the response of signing in with email and password,
+/// or an error response.
+///
+/// If the user has enabled two-factor authentication, `code` finishes signing in.
+async fn login_for_token(
+    req: &HttpRequest,
+    response: reqwest::Result<Response>,
+    code: Option<String>,
+) -> Result<UserSession, HttpResponse> {
+    let response = match response {
+        Ok(response) if response.status() != StatusCode::TOO_MANY_REQUESTS => response,
+        response => return Err(http_util::pass_response::<TokenDTO>(response).await),
+    };
+
+    let two_factor_token =
+        match http_util::parse_data_from_service_response::<LoginDTO>(response).await {
+            Ok(Some(LoginDTO {
+                session: Some(user_session),
+                ..
+            })) => return Ok(user_session),
+            Ok(Some(LoginDTO {
+                two_factor_token: Some(two_factor_token),
+                ..
+            })) => two_factor_token,
+            Ok(_) => {
+                return Err(http_util::get_err_response::<TokenDTO>(
                     StatusCode::UNAUTHORIZED,
-                    &format!("{}", ApiGatewayError::Unauthorized),
-                )
+                    &get_api_error_message(ApiGatewayError::Unauthorized),
+                ))
             }
-        } else {
-            http_util::get_err_response::<UserSession>(
+            Err(_) => {
+                return Err(http_util::get_err_response::<TokenDTO>(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+                ))
+            }
+        };
+
+    let args = match code {
+        Some(code) => ServiceTwoFactorLoginArgs {
+            token: two_factor_token,
+            code,
+        },
+        None => {
+            return Err(http_util::get_err_response::<TokenDTO>(
+                StatusCode::UNAUTHORIZED,
+                &get_api_error_message(ApiGatewayError::TwoFactorRequired),
+            ))
+        }
+    };
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/login/2fa"))
+        .headers(permission_util::get_forwarded_headers(req, &None))
+        .json(&args)
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) if response.status() != StatusCode::TOO_MANY_REQUESTS => response,
+        response => return Err(http_util::pass_response::<TokenDTO>(response).await),
+    };
+
+    match http_util::parse_data_from_service_response::<UserSession>(response).await {
+        Ok(Some(user_session)) => Ok(user_session),
+        Ok(None) => Err(http_util::get_err_response::<TokenDTO>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )),
+        Err(_) => Err(http_util::get_err_response::<TokenDTO>(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+        )),
+    }
+}
+
+/// Signs in to issue an access token and a refresh token, for clients without cookies.
+///
+/// The access token is valid for 15 minutes, and sent in `Authorization: Bearer` header
+/// instead of the session cookie. `POST /auth/token/refresh` issues a new access token
+/// by the refresh token, which is valid until the device is signed out.
+///
+/// If the user has enabled two-factor authentication, it responds `401 Unauthorized` with
+/// `two_factor_required` error, and the client requests again with a code.
+///
+/// Signing in is throttled like `POST /auth/login`, with the same `RateLimit-*` headers.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/token
+/// ```
+///
+/// ## Parameters
+///
+/// * email - An email of the user.
+/// * password - A password of the user.
+/// * code - A TOTP code or a recovery code, if two-factor authentication is enabled.
+///
+/// ```json
+/// {
+///     "email": "park@email.com",
+///     "password": "Ir5c7y8dS3",
+///     "code": "081804"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "access_token": "eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzI1NiJ9.eyJzdWIiOjB9.a1b2c3",
+///         "token_type": "Bearer",
+///         "expires_in": 900,
+///         "refresh_token": "Xo3h2nQlRkWbt7vbXo3h2nQlRkWbt7vbXo3h2nQlRkWbt7vb"
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/auth/token")]
+pub async fn issue_token(req: HttpRequest, args: web::Json<TokenArgs>) -> impl Responder {
+    let TokenArgs {
+        email,
+        password,
+        code,
+    } = args.into_inner();
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/login"))
+        .headers(permission_util::get_forwarded_headers(&req, &None))
+        .json(&ServiceLoginArgs { email, password })
+        .send()
+        .await;
+
+    let rate_limit_headers = http_util::get_rate_limit_headers(&response);
+    let response = match login_for_token(&req, response, code).await {
+        Ok(user_session) => respond_token(&req, user_session).await,
+        Err(response) => response,
+    };
+    http_util::with_headers(response, &rate_limit_headers)
+}
+
+/// Stores a new login session of the user who has signed in, and responds the tokens of it.
+async fn respond_token(req: &HttpRequest, user_session: UserSession) -> HttpResponse {
+    let session_id = session_util::generate_session_id();
+    let args = ServiceCreateLoginSessionArgs {
+        user_id: user_session.user_id,
+        label: None,
+    };
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/tokens"))
+        .headers(permission_util::get_forwarded_headers(
+            req,
+            &Some(session_id.clone()),
+        ))
+        .json(&args)
+        .send()
+        .await;
+
+    if let Ok(response) = response {
+        match http_util::parse_data_from_service_response::<String>(response).await {
+            Ok(Some(refresh_token)) => match get_token(&user_session, &session_id, refresh_token) {
+                Ok(token) => http_util::get_ok_response::<TokenDTO>(token),
+                Err(error) => http_util::get_err_response::<TokenDTO>(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &get_api_error_message(error),
+                ),
+            },
+            _ => http_util::get_err_response::<TokenDTO>(
                 StatusCode::INTERNAL_SERVER_ERROR,
-                &format!("{}", ApiGatewayError::ServiceResponseParsingFailure),
-            )
+                &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+            ),
         }
     } else {
-        http_util::pass_response::<UserSession>(response).await
+        http_util::pass_response::<TokenDTO>(response).await
     }
 }
 
-/// Signs out to unset user session.
+/// Issues a new access token by a refresh token.
+///
+/// The refresh token is rotated, so the client must keep the new one. Access tokens issued before
+/// are not accepted anymore. A refresh token expires with its session, 30 days after signing in
+/// by default, and the client must sign in again then.
+///
+/// A refresh token which has been rotated out must not be sent again. It revokes the whole session,
+/// since the token may have been leaked to another client.
 ///
 /// # Request
 ///
 /// ```text
-/// POST /auth/logout
+/// POST /auth/token/refresh
+/// ```
+///
+/// ## Parameters
+///
+/// * refresh_token - A refresh token issued by `POST /auth/token` or the last refresh.
+///
+/// ```json
+/// {
+///     "refresh_token": "Xo3h2nQlRkWbt7vbXo3h2nQlRkWbt7vbXo3h2nQlRkWbt7vb"
+/// }
 /// ```
 ///
 /// # Response
 ///
 /// ```json
 /// {
-///     "data": true,
+///     "data": {
+///         "access_token": "eyJ0eXAiOiJKV1QiLCJhbGciOiJIUzI1NiJ9.eyJzdWIiOjB9.d4e5f6",
+///         "token_type": "Bearer",
+///         "expires_in": 900,
+///         "refresh_token": "a1lam9cBkoa1lam9cBkoa1lam9cBkoa1lam9cBkoa1lam9cB"
+///     },
 ///     "error": null
 /// }
 /// ```
-#[post("/auth/logout")]
-pub async fn logout(mut session: Session) -> impl Responder {
-    let is_logged_in = session_util::get_session(&session);
-    if is_logged_in.is_some() {
-        session_util::unset_session(&mut session);
-        http_util::get_ok_response::<bool>(true)
+#[post("/auth/token/refresh")]
+pub async fn refresh_token(req: HttpRequest, args: web::Json<RefreshTokenArgs>) -> impl Responder {
+    let session_id = session_util::generate_session_id();
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/tokens/refresh"))
+        .headers(permission_util::get_forwarded_headers(
+            &req,
+            &Some(session_id.clone()),
+        ))
+        .json(&args.into_inner())
+        .send()
+        .await;
+
+    if let Ok(response) = response {
+        match http_util::parse_data_from_service_response::<ServiceTokenDTO>(response).await {
+            Ok(Some(ServiceTokenDTO {
+                session,
+                refresh_token,
+            })) => match get_token(&session, &session_id, refresh_token) {
+                Ok(token) => http_util::get_ok_response::<TokenDTO>(token),
+                Err(error) => http_util::get_err_response::<TokenDTO>(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &get_api_error_message(error),
+                ),
+            },
+            Ok(None) => http_util::get_err_response::<TokenDTO>(
+                StatusCode::UNAUTHORIZED,
+                &get_api_error_message(ApiGatewayError::Unauthorized),
+            ),
+            Err(_) => http_util::get_err_response::<TokenDTO>(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+            ),
+        }
     } else {
-        http_util::get_err_response::<bool>(
-            StatusCode::INTERNAL_SERVER_ERROR,
-            &format!("{}", ApiGatewayError::InternalServerError),
-        )
+        http_util::pass_response::<TokenDTO>(response).await
     }
 }
 
-/// Initializes the auth routes.
-pub fn init_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_auth);
-    cfg.service(refresh_session);
-    cfg.service(set_sign_up_token);
-    cfg.service(set_password_token);
-    cfg.service(login);
-    cfg.service(logout);
+/// Revokes a refresh token, which signs out the device using it.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/token/revoke
+/// ```
+///
+/// ## Parameters
+///
+/// * refresh_token - A refresh token to be revoked.
+///
+/// ```json
+/// {
+///     "refresh_token": "Xo3h2nQlRkWbt7vbXo3h2nQlRkWbt7vbXo3h2nQlRkWbt7vb"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/auth/token/revoke")]
+pub async fn revoke_token(args: web::Json<RefreshTokenArgs>) -> impl Responder {
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/tokens/revoke"))
+        .json(&args.into_inner())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Finishes signing in with a two-factor code to set user session.
+///
+/// It takes a code after `POST /auth/login` responded `two_factor_required` error.
+/// A wrong code responds `401 Unauthorized`, and the user must sign in from the password again.
+/// Wrong codes are throttled per IP, with `RateLimit-*` headers like `POST /auth/login`.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/login/2fa
+/// ```
+///
+/// ## Parameters
+///
+/// * code - A TOTP code from the authenticator app, or one of the recovery codes.
+///
+/// ```json
+/// {
+///     "code": "081804"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "user_id": 0,
+///         "user_email": "park@email.com"
+///         "user_name": "park",
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/auth/login/2fa")]
+pub async fn login_with_two_factor(
+    mut session: Session,
+    req: HttpRequest,
+    args: web::Json<TwoFactorLoginArgs>,
+) -> impl Responder {
+    let token = match session_util::take_two_factor_token(&mut session) {
+        Some(token) => token,
+        None => {
+            return http_util::get_err_response::<UserSession>(
+                StatusCode::UNAUTHORIZED,
+                &get_api_error_message(ApiGatewayError::Unauthorized),
+            )
+        }
+    };
+    let args = ServiceTwoFactorLoginArgs {
+        token,
+        code: args.into_inner().code,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/login/2fa"))
+        .headers(permission_util::get_forwarded_headers(&req, &None))
+        .json(&args)
+        .send()
+        .await;
+
+    let rate_limit_headers = http_util::get_rate_limit_headers(&response);
+    let response = match response {
+        Ok(response) if response.status() != StatusCode::TOO_MANY_REQUESTS => response,
+        response => return http_util::pass_response::<UserSession>(response).await,
+    };
+    let response = match http_util::parse_data_from_service_response::<UserSession>(response).await
+    {
+        Ok(Some(user_session)) => respond_login(&mut session, &req, user_session).await,
+        Ok(None) => http_util::get_err_response::<UserSession>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        ),
+        Err(_) => http_util::get_err_response::<UserSession>(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+        ),
+    };
+    http_util::with_headers(response, &rate_limit_headers)
+}
+
+/// Emails a link signing in without the password.
+///
+/// The link is valid for 3 minutes and signs in only once.
+/// It responds `true` for an email without an account as well, which is not emailed.
+/// Every email sent is counted in the rate limit of the account and the IP, which is responded
+/// in `RateLimit-*` headers like `POST /auth/login`.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/magic-link
+/// ```
+///
+/// ## Parameters
+///
+/// * email - A unique email of the user.
+///
+/// ```json
+/// {
+///     "email": "park@email.com",
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/auth/magic-link")]
+pub async fn send_magic_link(req: HttpRequest, args: web::Json<MagicLinkArgs>) -> impl Responder {
+    let args: MagicLinkArgs = args.into_inner();
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/magic-link"))
+        .headers(permission_util::get_forwarded_headers(&req, &None))
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Signs in with the token of a magic link to set user session.
+///
+/// The token is consumed even if two-factor authentication is enabled, in which case it responds
+/// `401 Unauthorized` with `two_factor_required` error like `POST /auth/login`.
+///
+/// # Request
+///
+/// ```text
+/// GET /auth/magic-link/:token
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "user_id": 0,
+///         "user_email": "park@email.com"
+///         "user_name": "park",
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/auth/magic-link/{token}")]
+pub async fn login_with_magic_link(
+    mut session: Session,
+    req: HttpRequest,
+    web::Path(token): web::Path<String>,
+) -> impl Responder {
+    let args = ServiceMagicLinkLoginArgs { token };
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/magic-link/login"))
+        .json(&args)
+        .send()
+        .await;
+
+    respond_login_result(&mut session, &req, response).await
+}
+
+/// Starts to set up two-factor authentication of logged-in user
+///
+/// It responds a new secret, which the client shows as a QR code of `uri`.
+/// Two-factor authentication is not enabled until `POST /auth/2fa/verify` verifies a code
+/// of the secret. It responds `409 Conflict` if it is already enabled.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/2fa/setup
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "secret": "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+///         "uri": "otpauth://totp/Darim:park@email.com?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Darim&algorithm=SHA1&digits=6&period=30"
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/auth/2fa/setup")]
+pub async fn setup_two_factor(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let args = ServiceTwoFactorSetupArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/2fa/setup"))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<TwoFactorSetupDTO>(response).await
+}
+
+/// Enables two-factor authentication of logged-in user by a code of the secret
+///
+/// It responds recovery codes, each of which can be used once instead of a TOTP code.
+/// They are not shown again.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/2fa/verify
+/// ```
+///
+/// ## Parameters
+///
+/// * code - A TOTP code from the authenticator app.
+///
+/// ```json
+/// {
+///     "code": "081804"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         "k3v9q-2m8xa",
+///         "p0c7d-y4n1s"
+///     ],
+///     "error": null
+/// }
+/// ```
+#[post("/auth/2fa/verify")]
+pub async fn verify_two_factor(
+    auth: Authorized<CanManageAccount>,
+    args: web::Json<TwoFactorCodeArgs>,
+) -> impl Responder {
+    let args = ServiceTwoFactorCodeArgs {
+        user_id: auth.user_id(),
+        code: args.into_inner().code,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/2fa/verify"))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<Vec<String>>(response).await
+}
+
+/// Disables two-factor authentication of logged-in user
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/2fa/disable
+/// ```
+///
+/// ## Parameters
+///
+/// * code - A TOTP code from the authenticator app, or one of the recovery codes.
+///
+/// ```json
+/// {
+///     "code": "081804"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/auth/2fa/disable")]
+pub async fn disable_two_factor(
+    auth: Authorized<CanManageAccount>,
+    args: web::Json<TwoFactorCodeArgs>,
+) -> impl Responder {
+    let args = ServiceTwoFactorCodeArgs {
+        user_id: auth.user_id(),
+        code: args.into_inner().code,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/2fa/disable"))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Redirects to the client after signing in with an OAuth provider.
+///
+/// A failure is given to the client by `oauth_error` query parameter.
+fn redirect_to_client(error: Option<ApiGatewayError>) -> HttpResponse {
+    let client_address = env::var("CLIENT_ADDRESS").expect("CLIENT_ADDRESS not found");
+    let location = match error {
+        Some(error) => format!(
+            "{}/?oauth_error={}",
+            client_address.trim_end_matches('/'),
+            get_api_error_message(error).replace(' ', "%20")
+        ),
+        None => client_address,
+    };
+    HttpResponse::Found()
+        .header(http::header::LOCATION, location)
+        .finish()
+}
+
+/// Redirects to the consent page of an OAuth provider to sign in
+///
+/// The provider redirects back to `GET /auth/oauth/:provider/callback`.
+/// Supported providers are `google` and `github`, and a provider whose client is not configured
+/// responds `404 Not Found`.
+///
+/// # Request
+///
+/// ```text
+/// GET /auth/oauth/:provider
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// 302 Found
+/// Location: https://github.com/login/oauth/authorize?client_id=...&state=...
+/// ```
+#[get("/auth/oauth/{provider}")]
+pub async fn redirect_to_oauth_provider(
+    mut session: Session,
+    provider: web::Path<String>,
+) -> impl Responder {
+    let provider = provider.into_inner();
+    let state = match session_util::set_oauth_state(&mut session, &provider) {
+        Some(state) => state,
+        None => {
+            return http_util::get_err_response::<String>(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &get_api_error_message(ApiGatewayError::InternalServerError),
+            )
+        }
+    };
+
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/auth/oauth/{}/url?state={}",
+        provider, state
+    )))
+    .await;
+
+    if let Ok(response) = response {
+        if response.status() != StatusCode::OK {
+            return http_util::pass_response::<String>(Ok(response)).await;
+        }
+        match http_util::parse_data_from_service_response::<String>(response).await {
+            Ok(Some(url)) => HttpResponse::Found()
+                .header(http::header::LOCATION, url)
+                .finish(),
+            _ => http_util::get_err_response::<String>(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+            ),
+        }
+    } else {
+        http_util::pass_response::<String>(response).await
+    }
+}
+
+/// Signs in with the code an OAuth provider has redirected with, and redirects to the client
+///
+/// The account of the provider is linked to the user whose email is its verified email
+/// at first. It does not sign up, so the user of the email must exist.
+/// If the user has enabled two-factor authentication, it redirects with `two_factor_required` error,
+/// and `POST /auth/login/2fa` finishes signing in as the password does.
+///
+/// # Request
+///
+/// ```text
+/// GET /auth/oauth/:provider/callback?code=a1b2c3&state=Xo3h2nQlRkWbt7vb
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// 302 Found
+/// Location: https://darim.vercel.app/?oauth_error=two_factor_required
+/// ```
+#[get("/auth/oauth/{provider}/callback")]
+pub async fn handle_oauth_callback(
+    mut session: Session,
+    req: HttpRequest,
+    provider: web::Path<String>,
+    args: web::Query<OAuthCallbackArgs>,
+) -> impl Responder {
+    let provider = provider.into_inner();
+    let OAuthCallbackArgs { code, state, error } = args.into_inner();
+    let state = state.unwrap_or_default();
+    if !session_util::take_oauth_state(&mut session, &provider, &state) || error.is_some() {
+        return redirect_to_client(Some(ApiGatewayError::Unauthorized));
+    }
+    let args = match code {
+        Some(code) => ServiceOAuthLoginArgs { code },
+        None => return redirect_to_client(Some(ApiGatewayError::Unauthorized)),
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/auth/oauth/{}/login",
+            provider
+        )))
+        .json(&args)
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) => response,
+        Err(_) => return redirect_to_client(Some(ApiGatewayError::InternalServerError)),
+    };
+
+    session_util::take_two_factor_token(&mut session);
+    match http_util::parse_data_from_service_response::<LoginDTO>(response).await {
+        Ok(Some(LoginDTO {
+            session: Some(user_session),
+            ..
+        })) => {
+            if set_login_session(&mut session, &req, &user_session).await {
+                redirect_to_client(None)
+            } else {
+                redirect_to_client(Some(ApiGatewayError::InternalServerError))
+            }
+        }
+        Ok(Some(LoginDTO {
+            two_factor_token: Some(two_factor_token),
+            ..
+        })) => {
+            session_util::set_two_factor_token(&mut session, &two_factor_token);
+            redirect_to_client(Some(ApiGatewayError::TwoFactorRequired))
+        }
+        Ok(_) => redirect_to_client(Some(ApiGatewayError::Unauthorized)),
+        Err(_) => redirect_to_client(Some(ApiGatewayError::ServiceResponseParsingFailure)),
+    }
+}
+
+/// Starts to register a passkey of logged-in user
+///
+/// It responds options which the client passes to `navigator.credentials.create()`.
+/// The ceremony must be finished by `POST /auth/webauthn/register` in 5 minutes.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/webauthn/register/challenge
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "publicKey": {
+///             "rp": { "name": "Darim", "id": "darim.vercel.app" },
+///             "user": { "id": "AAAAAAAAAAU", "name": "park@email.com", "displayName": "park" },
+///             "challenge": "pS2kn1ZKgGbc3sPcq5B4xQ",
+///             "pubKeyCredParams": [{ "type": "public-key", "alg": -7 }],
+///             "excludeCredentials": [],
+///             "authenticatorSelection": { "userVerification": "required" }
+///         }
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/auth/webauthn/register/challenge")]
+pub async fn start_webauthn_registration(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let args = ServiceWebauthnRegisterChallengeArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/webauthn/register/challenge"))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<Value>(response).await
+}
+
+/// Finishes registering a passkey of logged-in user
+///
+/// It responds `401 Unauthorized` if the authenticator failed the challenge,
+/// and `409 Conflict` if the passkey has already been registered.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/webauthn/register
+/// ```
+///
+/// ## Parameters
+///
+/// * credential - The credential returned by `navigator.credentials.create()`,
+///   whose binary fields are encoded in base64url.
+///
+/// ```json
+/// {
+///     "credential": {
+///         "id": "mT7mRQ",
+///         "rawId": "mT7mRQ",
+///         "response": {
+///             "attestationObject": "o2NmbXRkbm9uZQ",
+///             "clientDataJSON": "eyJ0eXBlIjoid2ViYXV0aG4uY3JlYXRlIn0"
+///         },
+///         "type": "public-key"
+///     }
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/auth/webauthn/register")]
+pub async fn finish_webauthn_registration(
+    auth: Authorized<CanManageAccount>,
+    args: web::Json<WebauthnRegisterArgs>,
+) -> impl Responder {
+    let args = ServiceWebauthnRegisterArgs {
+        user_id: auth.user_id(),
+        credential: args.into_inner().credential,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/webauthn/register"))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Starts to sign in with a passkey
+///
+/// It responds options which the client passes to `navigator.credentials.get()`,
+/// and the ceremony must be finished by `POST /auth/webauthn/login` in 5 minutes.
+/// It responds `401 Unauthorized` if the user has no passkey.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/webauthn/login/challenge
+/// ```
+///
+/// ## Parameters
+///
+/// * email - A unique email of the user.
+///
+/// ```json
+/// {
+///     "email": "park@email.com"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "publicKey": {
+///             "challenge": "Xo3h2nQlRkWbt7vbdSP8Mg",
+///             "rpId": "darim.vercel.app",
+///             "allowCredentials": [{ "type": "public-key", "id": "mT7mRQ" }],
+///             "userVerification": "required"
+///         }
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/auth/webauthn/login/challenge")]
+pub async fn start_webauthn_login(
+    mut session: Session,
+    args: web::Json<WebauthnLoginChallengeArgs>,
+) -> impl Responder {
+    let args: WebauthnLoginChallengeArgs = args.into_inner();
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/webauthn/login/challenge"))
+        .json(&args)
+        .send()
+        .await;
+
+    if let Ok(response) = response {
+        match http_util::parse_data_from_service_response::<WebauthnLoginChallengeDTO>(response)
+            .await
+        {
+            Ok(Some(challenge)) => {
+                session_util::set_webauthn_token(&mut session, &challenge.token);
+                http_util::get_ok_response::<Value>(challenge.options)
+            }
+            Ok(None) => http_util::get_err_response::<Value>(
+                StatusCode::UNAUTHORIZED,
+                &get_api_error_message(ApiGatewayError::Unauthorized),
+            ),
+            Err(_) => http_util::get_err_response::<Value>(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+            ),
+        }
+    } else {
+        http_util::pass_response::<Value>(response).await
+    }
+}
+
+/// Signs in with a passkey to set user session.
+///
+/// A passkey verifies the user by itself, so two-factor authentication is not required.
+/// A failed ceremony must be started again by `POST /auth/webauthn/login/challenge`.
+/// Failures are throttled per IP, with `RateLimit-*` headers like `POST /auth/login`.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/webauthn/login
+/// ```
+///
+/// ## Parameters
+///
+/// * credential - The credential returned by `navigator.credentials.get()`,
+///   whose binary fields are encoded in base64url.
+///
+/// ```json
+/// {
+///     "credential": {
+///         "id": "mT7mRQ",
+///         "rawId": "mT7mRQ",
+///         "response": {
+///             "authenticatorData": "SZYN5YgOjGh0NBcPZHZgW4_krrmihjLHmVzzuoMdl2MFAAAABQ",
+///             "clientDataJSON": "eyJ0eXBlIjoid2ViYXV0aG4uZ2V0In0",
+///             "signature": "MEUCIQCv7EqsBRtf2E4o_BjzZfBwNpP8fLjd5y6TUOLWt5l9DQ",
+///             "userHandle": "AAAAAAAAAAU"
+///         },
+///         "type": "public-key"
+///     }
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "user_id": 0,
+///         "user_email": "park@email.com"
+///         "user_name": "park",
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/auth/webauthn/login")]
+pub async fn login_with_webauthn(
+    mut session: Session,
+    req: HttpRequest,
+    args: web::Json<WebauthnLoginArgs>,
+) -> impl Responder {
+    let token = match session_util::take_webauthn_token(&mut session) {
+        Some(token) => token,
+        None => {
+            return http_util::get_err_response::<UserSession>(
+                StatusCode::UNAUTHORIZED,
+                &get_api_error_message(ApiGatewayError::Unauthorized),
+            )
+        }
+    };
+    let args = ServiceWebauthnLoginArgs {
+        token,
+        credential: args.into_inner().credential,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/webauthn/login"))
+        .headers(permission_util::get_forwarded_headers(&req, &None))
+        .json(&args)
+        .send()
+        .await;
+
+    let rate_limit_headers = http_util::get_rate_limit_headers(&response);
+    let response = match response {
+        Ok(response) if response.status() != StatusCode::TOO_MANY_REQUESTS => response,
+        response => return http_util::pass_response::<UserSession>(response).await,
+    };
+    let response = match http_util::parse_data_from_service_response::<UserSession>(response).await
+    {
+        Ok(Some(user_session)) => {
+            session_util::take_two_factor_token(&mut session);
+            respond_login(&mut session, &req, user_session).await
+        }
+        Ok(None) => http_util::get_err_response::<UserSession>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        ),
+        Err(_) => http_util::get_err_response::<UserSession>(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+        ),
+    };
+    http_util::with_headers(response, &rate_limit_headers)
+}
+
+/// Lists passkeys of logged-in user
+///
+/// # Request
+///
+/// ```text
+/// GET /auth/webauthn/credentials
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "last_used_at": "2020-04-20T09:12:45Z"
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/auth/webauthn/credentials")]
+pub async fn get_webauthn_credentials(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/auth/webauthn/credentials/{}",
+        auth.user_id()
+    )))
+    .await;
+    http_util::pass_response::<Vec<WebauthnCredentialDTO>>(response).await
+}
+
+/// Deletes a passkey of logged-in user
+///
+/// # Request
+///
+/// ```text
+/// DELETE /auth/webauthn/credentials/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/auth/webauthn/credentials/{id}")]
+pub async fn delete_webauthn_credential(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/auth/webauthn/credentials/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Lists devices logged-in user has signed in on
+///
+/// `is_current` marks the device requesting.
+///
+/// # Request
+///
+/// ```text
+/// GET /auth/sessions
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "user_agent": "Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_4)",
+///             "ip": "127.0.0.1",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "last_seen_at": "2020-04-20T09:12:45Z",
+///             "is_current": true
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/auth/sessions")]
+pub async fn get_login_sessions(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let response = Client::new()
+        .get(&http_util::get_url(&format!(
+            "/auth/sessions/{}",
+            auth.user_id()
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<Vec<LoginSessionDTO>>(response).await
+}
+
+/// Lists the sessions signed in on the device requesting, which it can switch to
+///
+/// Each session has the label given by `client_session_label` of `POST /auth/login`,
+/// and the session the device is using is marked by `is_current`. A device that has not
+/// signed in yet has no session.
+///
+/// # Request
+///
+/// ```text
+/// GET /auth/sessions/current
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "user_id": 0,
+///             "user_name": "park",
+///             "user_avatar_url": null,
+///             "label": "Dad",
+///             "last_seen_at": "2020-04-20T09:12:45Z",
+///             "is_current": true
+///         },
+///         {
+///             "id": 3,
+///             "user_id": 2,
+///             "user_name": "kim",
+///             "user_avatar_url": null,
+///             "label": "Mom",
+///             "last_seen_at": "2020-04-19T21:03:10Z",
+///             "is_current": false
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/auth/sessions/current")]
+pub async fn get_device_login_sessions(req: HttpRequest, session: Session) -> impl Responder {
+    let device_id = match session_util::get_device_id(&session) {
+        Some(device_id) => device_id,
+        None => return http_util::get_ok_response::<Vec<DeviceLoginSessionDTO>>(vec![]),
+    };
+    let session_id = session_util::get_session_id(&session);
+
+    let response = Client::new()
+        .get(&http_util::get_url("/auth/devices/sessions"))
+        .headers(get_device_headers(&req, &session_id, &device_id))
+        .send()
+        .await;
+    http_util::pass_response::<Vec<DeviceLoginSessionDTO>>(response).await
+}
+
+/// Switches to another session signed in on the device requesting, and responds it
+///
+/// Only the sessions listed by `GET /auth/sessions/current` can be switched to. The device keeps
+/// a single user session, which every other API sees as before.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/switch/:session_id
+/// ```
+///
+/// ## Parameters
+///
+/// * session_id - An id of the session in `GET /auth/sessions/current`.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "user_id": 2,
+///         "user_email": "kim@email.com"
+///         "user_name": "kim",
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/auth/switch/{session_id}")]
+pub async fn switch_login_session(
+    req: HttpRequest,
+    mut session: Session,
+    session_id: web::Path<u64>,
+) -> impl Responder {
+    let device_id = match session_util::get_device_id(&session) {
+        Some(device_id) => device_id,
+        None => {
+            return http_util::get_err_response::<UserSession>(
+                StatusCode::UNAUTHORIZED,
+                &get_api_error_message(ApiGatewayError::Unauthorized),
+            )
+        }
+    };
+
+    // The device keeps only the session id it is using, so the service rotates it.
+    let new_session_id = session_util::generate_session_id();
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/auth/devices/sessions/{}/switch",
+            session_id
+        )))
+        .headers(get_device_headers(
+            &req,
+            &Some(new_session_id.clone()),
+            &device_id,
+        ))
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) if response.status() == StatusCode::OK => response,
+        response => return http_util::pass_response::<UserSession>(response).await,
+    };
+
+    match http_util::parse_data_from_service_response::<UserSession>(response).await {
+        Ok(Some(user_session)) => {
+            session_util::unset_session(&mut session);
+            if session_util::switch_session_id(&mut session, &new_session_id) {
+                http_util::get_ok_response::<UserSession>(user_session)
+            } else {
+                session_util::unset_session(&mut session);
+                http_util::get_err_response::<UserSession>(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    &get_api_error_message(ApiGatewayError::InternalServerError),
+                )
+            }
+        }
+        _ => http_util::get_err_response::<UserSession>(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &get_api_error_message(ApiGatewayError::ServiceResponseParsingFailure),
+        ),
+    }
+}
+
+/// Signs out a device of logged-in user
+///
+/// The device is signed out on its next request.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /auth/sessions/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/auth/sessions/{id}")]
+pub async fn delete_login_session(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/auth/sessions/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Signs out every device of logged-in user, including the device requesting
+///
+/// # Request
+///
+/// ```text
+/// DELETE /auth/sessions
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/auth/sessions")]
+pub async fn delete_login_sessions(
+    auth: Authorized<CanManageAccount>,
+    mut session: Session,
+) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/auth/sessions/{}",
+            auth.user_id()
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    session_util::unset_session(&mut session);
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Signs out to unset user session.
+///
+/// # Request
+///
+/// ```text
+/// POST /auth/logout
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/auth/logout")]
+pub async fn logout(
+    current_user: CurrentUser,
+    req: HttpRequest,
+    mut session: Session,
+) -> impl Responder {
+    let session_id = session_util::get_request_session_id(&req);
+    let args = ServiceLoginSessionArgs {
+        user_id: current_user.0.user_id,
+        session_id: session_id.unwrap_or_default(),
+    };
+    session_util::unset_session(&mut session);
+
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/sessions/logout"))
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the auth routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_auth);
+    cfg.service(refresh_session);
+    cfg.service(set_sign_up_token);
+    cfg.service(set_password_token);
+    cfg.service(login);
+    cfg.service(login_with_two_factor);
+    cfg.service(issue_token);
+    cfg.service(refresh_token);
+    cfg.service(revoke_token);
+    cfg.service(setup_two_factor);
+    cfg.service(verify_two_factor);
+    cfg.service(disable_two_factor);
+    cfg.service(send_magic_link);
+    cfg.service(login_with_magic_link);
+    cfg.service(redirect_to_oauth_provider);
+    cfg.service(handle_oauth_callback);
+    cfg.service(start_webauthn_registration);
+    cfg.service(finish_webauthn_registration);
+    cfg.service(start_webauthn_login);
+    cfg.service(login_with_webauthn);
+    cfg.service(get_webauthn_credentials);
+    cfg.service(delete_webauthn_credential);
+    cfg.service(get_login_sessions);
+    cfg.service(get_device_login_sessions);
+    cfg.service(switch_login_session);
+    cfg.service(delete_login_session);
+    cfg.service(delete_login_sessions);
+    cfg.service(logout);
+
+    cfg.service(http_util::get_options_resource(
+        "/auth",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/token/sign_up",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/token/password",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/login",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/login/2fa",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/token",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/token/refresh",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/token/revoke",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/2fa/setup",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/2fa/verify",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/2fa/disable",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/magic-link",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/magic-link/{token}",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/oauth/{provider}",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/oauth/{provider}/callback",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/webauthn/register/challenge",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/webauthn/register",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/webauthn/login/challenge",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/webauthn/login",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/webauthn/credentials",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/webauthn/credentials/{id}",
+        &[Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/sessions",
+        &[Method::GET, Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/sessions/current",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/sessions/{id}",
+        &[Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/switch/{session_id}",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/auth/logout",
+        &[Method::POST],
+    ));
 }
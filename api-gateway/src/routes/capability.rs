@@ -0,0 +1,101 @@
+use actix_web::{get, web, Responder};
+use http::Method;
+
+use crate::models::capability::CapabilitiesDTO;
+use crate::utils::capability_util;
+use crate::utils::http_util;
+
+/// Responds the version and features supported by the API
+///
+/// It doesn't require login. A feature missing from `features` is not supported.
+///
+/// # Request
+///
+/// ```text
+/// GET /capabilities
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "version": "0.1.0",
+///         "features": {
+///             "account_export": true,
+///             "activity_heatmap": true,
+///             "admin": true,
+///             "attachments": true,
+///             "autosave": true,
+///             "avatar_upload": true,
+///             "bulk_operations": true,
+///             "calendar_feed": true,
+///             "conditional_update": true,
+///             "delete_posts_by_date": true,
+///             "delta_sync": true,
+///             "drafts": true,
+///             "email_change": true,
+///             "export": true,
+///             "favorites": true,
+///             "import": true,
+///             "intra_day_order": true,
+///             "invites": true,
+///             "journals": true,
+///             "key_metadata": true,
+///             "locations": true,
+///             "login_history": true,
+///             "login_rate_limit": true,
+///             "magic_link_login": true,
+///             "moods": true,
+///             "oauth_login": true,
+///             "on_this_day": true,
+///             "partial_update": true,
+///             "passkeys": true,
+///             "password_policy": true,
+///             "personal_access_tokens": true,
+///             "post_archive": true,
+///             "post_calendar": true,
+///             "post_comments": true,
+///             "post_date_offset": true,
+///             "post_duplication": true,
+///             "post_encryption_metadata": true,
+///             "post_list_filters": true,
+///             "post_locks": true,
+///             "post_pagination": true,
+///             "post_revisions": true,
+///             "post_summaries": true,
+///             "post_versioning": true,
+///             "profile": true,
+///             "session_management": true,
+///             "share_links": true,
+///             "shared_post_pages": true,
+///             "tag_cloud": true,
+///             "tags": true,
+///             "telemetry": true,
+///             "templates": true,
+///             "token_auth": true,
+///             "trash": true,
+///             "two_factor_auth": true,
+///             "user_settings": true,
+///             "word_goals": true,
+///             "writing_prompts": true,
+///             "writing_streak": true
+///         }
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/capabilities")]
+pub async fn get_capabilities() -> impl Responder {
+    http_util::get_ok_response::<CapabilitiesDTO>(capability_util::get_capabilities().to_dto())
+}
+
+/// Initializes the capability routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_capabilities);
+
+    cfg.service(http_util::get_options_resource(
+        "/capabilities",
+        &[Method::GET],
+    ));
+}
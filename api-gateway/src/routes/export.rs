@@ -0,0 +1,382 @@
+use actix_web::{delete, get, post, web, HttpRequest, Responder};
+use http::header::RANGE;
+use http::Method;
+use reqwest::Client;
+
+use crate::models::export::*;
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanManageAccount, CanReadPosts};
+
+/// Downloads an archive of posts written by logged-in user
+///
+/// The archive is a gzipped tar streamed as it is written, which contains:
+///
+/// * `tags.json` - Tags of logged-in user.
+/// * `posts/:date-:id.md` - A Markdown document per post, whose front matter contains
+///   the metadata of the post. `date` of the file name is the date in the offset where
+///   the post was written.
+/// * `trash/:date-:id.md` - A Markdown document per post in the trash, with `deleted_at`
+///   in the front matter. Only if `include_trash` is true.
+///
+/// Title and content of posts and names of tags are archived as they are encrypted by the client.
+/// The archive is sent in chunks of bounded size as it is written. If the archive fails
+/// in the middle, it ends with `INCOMPLETE.txt` telling the error, so that it is still a valid
+/// archive which is not mistaken for a complete one.
+///
+/// An account with more posts than `EXPORT_SYNC_MAX_POSTS` of the service (1000 by default)
+/// responds `413 Payload Too Large`, and should be exported by `POST /posts/export-jobs` instead.
+///
+/// # Request
+///
+/// ```text
+/// GET /export?include_trash=true
+/// ```
+///
+/// ## Parameters
+///
+/// * include_trash - If true, posts in the trash are also archived. (optional, default: false)
+///
+/// # Response
+///
+/// ```text
+/// Content-Type: application/gzip
+/// Content-Disposition: attachment; filename="darim-export.tar.gz"
+/// ```
+///
+/// Each post is archived as below.
+///
+/// ```text
+/// ---
+/// id: 1
+/// title: "U2FsdGVkX1+Wc2FsdA=="
+/// date: "2020-04-12T16:43:03+09:00"
+/// intra_day_order: 0
+/// tags: [2]
+/// status: "published"
+/// created_at: "2020-04-13T16:31:09"
+/// updated_at: null
+/// version: 1
+/// ---
+///
+/// U2FsdGVkX1+bG9yZW0gaXBzdW0=
+/// ```
+#[get("/export")]
+pub async fn export(
+    auth: Authorized<CanReadPosts>,
+    args: web::Query<ExportArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/export/{}?{}",
+        auth.user_id(),
+        query
+    )))
+    .await;
+    http_util::pass_stream(response).await
+}
+
+/// Enqueues an export job making an archive of posts written by logged-in user
+///
+/// The archive is the same as `GET /export`, but it is written in background, so that
+/// a large diary does not hold up the request. The progress is polled by
+/// `GET /posts/export-jobs/:id`, which gives the download URL once the archive is written.
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/export-jobs
+/// ```
+///
+/// ## Parameters
+///
+/// * format - A format of the archive, which is only `tar.gz` for now.
+/// * include_trash - If true, posts in the trash are also archived. (optional, default: false)
+///
+/// ```json
+/// {
+///     "format": "tar.gz",
+///     "include_trash": true
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "id": 3,
+///         "format": "tar.gz",
+///         "include_trash": true,
+///         "status": "pending",
+///         "progress": 0,
+///         "total": 1200,
+///         "size": null,
+///         "download_url": null,
+///         "expires_at": "2020-05-02T09:00:00Z",
+///         "created_at": "2020-05-01T09:00:00Z",
+///         "finished_at": null
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/posts/export-jobs")]
+pub async fn create_export_job(
+    auth: Authorized<CanReadPosts>,
+    args: web::Json<CreateExportJobArgs>,
+) -> impl Responder {
+    let CreateExportJobArgs {
+        format,
+        include_trash,
+    } = args.into_inner();
+    let args = ServiceCreateExportJobArgs {
+        user_id: auth.user_id(),
+        format,
+        include_trash,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/export/jobs"))
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<ExportJobDTO>(response).await
+}
+
+/// Responds an export job of logged-in user
+///
+/// `status` is one of the followings:
+///
+/// * `pending` - Waiting to be run.
+/// * `running` - Writing the archive. `progress` posts out of `total` have been written.
+/// * `done` - The archive can be downloaded at `download_url`.
+/// * `failed` - The archive could not be written. A new job should be created.
+/// * `downloaded` - The archive has been downloaded and deleted.
+///
+/// The job and its archive are deleted at `expires_at`, which is a day after the job is created
+/// or finished, and then it responds `404 Not Found`.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/export-jobs/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "id": 3,
+///         "format": "tar.gz",
+///         "include_trash": true,
+///         "status": "done",
+///         "progress": 1200,
+///         "total": 1200,
+///         "size": 5242880,
+///         "download_url": "https://darim.vercel.app/export_download/a1b2c3",
+///         "expires_at": "2020-05-02T09:03:00Z",
+///         "created_at": "2020-05-01T09:00:00Z",
+///         "finished_at": "2020-05-01T09:03:00Z"
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/posts/export-jobs/{id}")]
+pub async fn get_export_job(
+    auth: Authorized<CanReadPosts>,
+    web::Path(id): web::Path<u64>,
+) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/export/jobs/{}/{}",
+        auth.user_id(),
+        id
+    )))
+    .await;
+    http_util::pass_response::<ExportJobDTO>(response).await
+}
+
+/// Downloads the archive of an export job
+///
+/// It doesn't require login, since the token of the download URL is the credential.
+/// The token can be used until the archive is downloaded to the end, so that a broken download
+/// can be started again, and then the archive is deleted. It responds `404 Not Found`
+/// if the token is unknown, used, or expired.
+///
+/// A broken download can be resumed with a single range of bytes in `Range`, which responds
+/// `206 Partial Content` with `Content-Range`. The archive is deleted once its last byte is
+/// sent, whether in the whole or in a range. An unsatisfiable range responds
+/// `416 Range Not Satisfiable` with the size of the archive in `Content-Range`.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/export-jobs/downloads/:token
+/// Range: bytes=1048576-
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// HTTP/1.1 206 Partial Content
+/// Content-Type: application/gzip
+/// Content-Disposition: attachment; filename="darim-export.tar.gz"
+/// Content-Length: 4194304
+/// Content-Range: bytes 1048576-5242879/5242880
+/// Accept-Ranges: bytes
+/// ```
+#[get("/posts/export-jobs/downloads/{token}")]
+pub async fn download_export_job(
+    req: HttpRequest,
+    web::Path(token): web::Path<String>,
+) -> impl Responder {
+    let mut request =
+        Client::new().get(&http_util::get_url(&format!("/export/downloads/{}", token)));
+    if let Some(range) = req.headers().get(RANGE) {
+        request = request.header(RANGE, range.clone());
+    }
+    let response = request.send().await;
+    http_util::pass_stream(response).await
+}
+
+/// Responds an iCalendar feed of the dates of posts, for calendar apps
+///
+/// The feed is authenticated by the token in the URL instead of a session, since calendar apps
+/// subscribe to it without logging in. Each date with published posts is an all-day event
+/// named `Diary entry`, because titles of posts are encrypted by the client.
+///
+/// # Request
+///
+/// ```text
+/// GET /export/calendar.ics?token=a1b2c3
+/// ```
+///
+/// ## Parameters
+///
+/// * token - A token of the calendar feed, issued by `POST /export/calendar`.
+///
+/// # Response
+///
+/// ```text
+/// Content-Type: text/calendar; charset=utf-8
+///
+/// BEGIN:VCALENDAR
+/// VERSION:2.0
+/// PRODID:-//Darim//Diary dates//EN
+/// CALSCALE:GREGORIAN
+/// X-WR-CALNAME:Darim
+/// BEGIN:VEVENT
+/// UID:1-20200412@darim
+/// DTSTAMP:20200501T090000Z
+/// DTSTART;VALUE=DATE:20200412
+/// DTEND;VALUE=DATE:20200413
+/// SUMMARY:Diary entry
+/// TRANSP:TRANSPARENT
+/// END:VEVENT
+/// END:VCALENDAR
+/// ```
+#[get("/export/calendar.ics")]
+pub async fn get_calendar(args: web::Query<CalendarArgs>) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/export/calendar.ics?{}",
+        query
+    )))
+    .await;
+    http_util::pass_stream(response).await
+}
+
+/// Creates a calendar feed of logged-in user with a new token
+///
+/// The previous token stops working. The feed is subscribed to at
+/// `GET /export/calendar.ics?token=:token`.
+///
+/// # Request
+///
+/// ```text
+/// POST /export/calendar
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "token": "a1b2c3"
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/export/calendar")]
+pub async fn create_calendar_feed(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let args = ServiceCreateCalendarFeedArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/export/calendar"))
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<CalendarFeedDTO>(response).await
+}
+
+/// Revokes the calendar feed of logged-in user
+///
+/// # Request
+///
+/// ```text
+/// DELETE /export/calendar
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/export/calendar")]
+pub async fn delete_calendar_feed(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/export/calendar/{}",
+            auth.user_id()
+        )))
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the export routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(export);
+    cfg.service(get_calendar);
+    cfg.service(create_calendar_feed);
+    cfg.service(delete_calendar_feed);
+    cfg.service(create_export_job);
+    cfg.service(get_export_job);
+    cfg.service(download_export_job);
+
+    cfg.service(http_util::get_options_resource("/export", &[Method::GET]));
+    cfg.service(http_util::get_options_resource(
+        "/export/calendar.ics",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/export/calendar",
+        &[Method::POST, Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/export-jobs",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/export-jobs/{id}",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/export-jobs/downloads/{token}",
+        &[Method::GET],
+    ));
+}
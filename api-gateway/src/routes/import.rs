@@ -0,0 +1,113 @@
+use actix_multipart::Multipart;
+use actix_web::{post, web, Responder};
+use http::header::CONTENT_TYPE;
+use http::Method;
+use reqwest::Client;
+
+use crate::models::import::*;
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanWritePosts};
+
+/// Maximum size of an archive to import.
+const MAX_ARCHIVE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Content type of import archives.
+const ARCHIVE_CONTENT_TYPE: &str = "application/gzip";
+
+/// Imports posts of logged-in user from an archive
+///
+/// The archive is in the format of `GET /export`, uploaded as `file` field of `multipart/form-data`.
+/// Posts in `trash/` are created in the trash, keeping `deleted_at`.
+/// Tags in `tags.json` are matched to tags of logged-in user by name, and created if missing.
+///
+/// A post of the same date and title as an existing post, including posts in the trash,
+/// is skipped as `duplicated`. A file which cannot be read is reported as `failed`,
+/// and the other files are still imported. `post_id` is the id of the created post.
+///
+/// # Request
+///
+/// ```text
+/// POST /import?dry_run=true
+/// Content-Type: multipart/form-data; boundary=boundary
+///
+/// --boundary
+/// Content-Disposition: form-data; name="file"; filename="darim-export.tar.gz"
+/// Content-Type: application/gzip
+///
+/// ...
+/// --boundary--
+/// ```
+///
+/// ## Parameters
+///
+/// * file - An archive to import, up to 32 MiB.
+/// * dry_run - If true, reports the result without creating anything, and `post_id` is null.
+///   (optional, default: false)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "dry_run": false,
+///         "created_count": 1,
+///         "duplicated_count": 1,
+///         "failed_count": 1,
+///         "created_tag_count": 1,
+///         "files": [
+///             {
+///                 "path": "posts/2020-04-12-1.md",
+///                 "status": "created",
+///                 "post_id": 10,
+///                 "error": null
+///             },
+///             {
+///                 "path": "posts/2020-04-12-2.md",
+///                 "status": "duplicated",
+///                 "post_id": null,
+///                 "error": null
+///             },
+///             {
+///                 "path": "notes.txt",
+///                 "status": "failed",
+///                 "post_id": null,
+///                 "error": "invalid format"
+///             }
+///         ]
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/import")]
+pub async fn import_posts(
+    auth: Authorized<CanWritePosts>,
+    args: web::Query<ImportArgs>,
+    payload: Multipart,
+) -> impl Responder {
+    let archive = match http_util::read_file_field(payload, MAX_ARCHIVE_SIZE).await {
+        Ok(file) => file.data,
+        Err(response) => return response,
+    };
+
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/import/{}?{}",
+            auth.user_id(),
+            query
+        )))
+        .headers(auth.forwarded_headers())
+        .header(CONTENT_TYPE, ARCHIVE_CONTENT_TYPE)
+        .body(archive)
+        .send()
+        .await;
+
+    http_util::pass_response::<PostImportDTO>(response).await
+}
+
+/// Initializes the import routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(import_posts);
+
+    cfg.service(http_util::get_options_resource("/import", &[Method::POST]));
+}
@@ -0,0 +1,223 @@
+use actix_web::{delete, get, patch, post, web, Responder};
+use http::Method;
+use reqwest::Client;
+
+use crate::models::journal::*;
+use crate::models::post::{ListArgs, PostDTO};
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanReadPosts, CanWritePosts};
+
+/// Lists journals of logged-in user
+///
+/// The default journal is listed first, and the others are listed in the order of creation.
+/// Every user has a default journal, which has an empty name until it is renamed.
+///
+/// # Request
+///
+/// ```text
+/// GET /journals
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "name": "",
+///             "is_default": true,
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "updated_at": null
+///         },
+///         {
+///             "id": 3,
+///             "name": "U2FsdGVkX1+Wc2FsdA==",
+///             "is_default": false,
+///             "created_at": "2020-05-07T07:43:03Z",
+///             "updated_at": null
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/journals")]
+pub async fn get_journals(auth: Authorized<CanReadPosts>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/journals/{}",
+        auth.user_id()
+    )))
+    .await;
+    http_util::pass_response::<Vec<JournalDTO>>(response).await
+}
+
+/// Lists posts in a journal of logged-in user
+///
+/// It takes the same parameters and responds in the same way as `GET /posts`,
+/// except that `journal` is the journal of the path.
+///
+/// # Request
+///
+/// ```text
+/// GET /journals/:id/posts?from=2020-04-01&to=2020-04-30&page=1&per_page=20
+/// ```
+#[get("/journals/{id}/posts")]
+pub async fn get_journal_posts(
+    auth: Authorized<CanReadPosts>,
+    id: web::Path<u64>,
+    args: web::Query<ListArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/journals/{}/{}/posts?{}",
+        auth.user_id(),
+        id,
+        query
+    )))
+    .await;
+    http_util::pass_response::<Vec<PostDTO>>(response).await
+}
+
+/// Creates a new journal
+///
+/// # Request
+///
+/// ```text
+/// POST /journals
+/// ```
+///
+/// ## Parameters
+///
+/// * name - A name of the journal, encrypted by the client like titles of posts.
+///
+/// ```json
+/// {
+///     "name": "U2FsdGVkX1+Wc2FsdA=="
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": 3,
+///     "error": null
+/// }
+/// ```
+#[post("/journals")]
+pub async fn create_journal(
+    auth: Authorized<CanWritePosts>,
+    args: web::Json<JournalArgs>,
+) -> impl Responder {
+    let args = ServiceJournalArgs {
+        user_id: auth.user_id(),
+        name: args.into_inner().name,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/journals"))
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<u64>(response).await
+}
+
+/// Renames a journal
+///
+/// # Request
+///
+/// ```text
+/// PATCH /journals/:id
+/// ```
+///
+/// ## Parameters
+///
+/// * name - A new name of the journal, encrypted by the client like titles of posts.
+///
+/// ```json
+/// {
+///     "name": "U2FsdGVkX1+Wc2FsdA=="
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[patch("/journals/{id}")]
+pub async fn update_journal(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<JournalArgs>,
+) -> impl Responder {
+    let args = ServiceJournalArgs {
+        user_id: auth.user_id(),
+        name: args.into_inner().name,
+    };
+
+    let response = Client::new()
+        .patch(&http_util::get_url(&format!("/journals/{}", id)))
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Deletes a journal
+///
+/// Posts in the journal are moved to the default journal, which cannot be deleted.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /journals/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/journals/{id}")]
+pub async fn delete_journal(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/journals/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the journal routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_journals);
+    cfg.service(get_journal_posts);
+    cfg.service(create_journal);
+    cfg.service(update_journal);
+    cfg.service(delete_journal);
+
+    cfg.service(http_util::get_options_resource(
+        "/journals",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/journals/{id}/posts",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/journals/{id}",
+        &[Method::PATCH, Method::DELETE],
+    ));
+}
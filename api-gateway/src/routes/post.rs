@@ -1,11 +1,14 @@
-use actix_session::Session;
-use actix_web::{delete, get, patch, post, web, Responder};
-use http::StatusCode;
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, Responder};
+use http::header::IF_UNMODIFIED_SINCE;
+use http::Method;
 use reqwest::Client;
 
-use crate::models::error::*;
 use crate::models::post::*;
-use crate::utils::{http_util, session_util};
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanReadPosts, CanWritePosts};
+
+/// Maximum size of the body of `POST /posts/bulk` API.
+const MAX_BULK_PAYLOAD_SIZE: usize = 8 * 1024 * 1024;
 
 /// Responds a post written by logged-in user
 ///
@@ -24,41 +27,80 @@ use crate::utils::{http_util, session_util};
 ///             "id": 1,
 ///             "title": "Lorem ipsum",
 ///             "content": "Lorem ipsum dolor sit amet",
-///             "date": "2020-04-12T07:43:03",
-///             "created_at": "2020-04-13T16:31:09",
-///             "updated_at": null
+///             "date": "2020-04-12T16:43:03+09:00",
+///             "intra_day_order": 0,
+///             "tags": [2],
+///             "journal_id": 1,
+///             "status": "published",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "updated_at": null,
+///             "version": 1,
+///             "is_favorite": false,
+///             "mood": 4,
+///             "weather": "sunny",
+///             "latitude": 37.5665,
+///             "longitude": 126.978,
+///             "place_name": "U2FsdGVkX3",
+///             "word_count": 5,
+///             "is_locked": false,
+///             "encrypted": true,
+///             "encryption_scheme": "aes-256-gcm",
+///             "nonce": "bm9uY2Vub25jZQ==",
+///             "key_version": 1
 ///         },
 ///     ],
 ///     "error": null
 /// }
 /// ```
 #[get("/posts/{id}")]
-pub async fn get_post(session: Session, id: web::Path<u64>) -> impl Responder {
-    if let Some(user_session) = session_util::get_session(&session) {
-        let response = reqwest::get(&http_util::get_url(&format!(
-            "/posts/{}/{}",
-            user_session.user_id, id
-        )))
-        .await;
-        http_util::pass_response::<PostDTO>(response).await
-    } else {
-        http_util::get_err_response::<PostDTO>(
-            StatusCode::UNAUTHORIZED,
-            &get_api_error_message(ApiGatewayError::Unauthorized),
-        )
-    }
+pub async fn get_post(auth: Authorized<CanReadPosts>, id: web::Path<u64>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/{}",
+        auth.user_id(),
+        id
+    )))
+    .await;
+    http_util::pass_response::<PostDTO>(response).await
 }
 
 /// Lists posts written by logged-in user
 ///
+/// Posts are listed in desc date order by default, and posts of the same date are listed
+/// by `intra_day_order`. If neither `page` nor `per_page` is given, all posts are listed.
+/// Drafts are listed only if `status` is `draft`.
+///
 /// # Request
 ///
 /// ```text
-/// GET /posts
+/// GET /posts?tag=2&from=2020-04-01&to=2020-04-30&status=published&favorite=true&sort_by=date&order=desc&page=1&per_page=20
 /// ```
 ///
+/// ## Parameters
+///
+/// * tag - An id of a tag to list only the posts with the tag. (optional)
+/// * journal - An id of a journal to list only the posts in the journal. (optional)
+/// * from - The first date in `YYYY-MM-DD` format, compared in the offset where each post
+///   was written. (optional)
+/// * to - The last date in `YYYY-MM-DD` format, compared in the offset where each post
+///   was written. (optional)
+/// * status - `published` or `draft`. (optional, default: `published`)
+/// * favorite - `true` to list only favorites, or `false` to list only the others. (optional)
+/// * near - A location in `latitude,longitude` format to list only the posts written
+///   near there, such as `37.5665,126.978`. (optional)
+/// * radius - A distance in meters from `near`. (optional, default: 1000)
+/// * sort_by - `date`, `created_at` or `updated_at`. Posts never updated are sorted
+///   by `created_at` for `updated_at`. (optional, default: `date`)
+/// * order - `asc` or `desc`. (optional, default: `desc`)
+/// * page - A page number starting from 1. (optional)
+/// * per_page - A number of posts in a page, up to 100. (optional)
+/// * fields - `all`, or `summary` to list only `id`, `title` and `date` of the posts
+///   without loading their contents. (optional, default: `all`)
+///
 /// # Response
 ///
+/// `meta` contains the total count of posts, and the page if it is given.
+/// Each post has only `id`, `title` and `date` if `fields` is `summary`.
+///
 /// ```json
 /// {
 ///     "data": [
@@ -66,36 +108,80 @@ pub async fn get_post(session: Session, id: web::Path<u64>) -> impl Responder {
 ///             "id": 1,
 ///             "title": "Lorem ipsum",
 ///             "content": "Lorem ipsum dolor sit amet",
-///             "date": "2020-04-12T07:43:03",
-///             "created_at": "2020-04-13T16:31:09",
-///             "updated_at": null
+///             "date": "2020-04-12T16:43:03+09:00",
+///             "intra_day_order": 0,
+///             "tags": [2],
+///             "journal_id": 1,
+///             "status": "published",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "updated_at": null,
+///             "version": 1,
+///             "is_favorite": false,
+///             "mood": 4,
+///             "weather": "sunny",
+///             "latitude": 37.5665,
+///             "longitude": 126.978,
+///             "place_name": "U2FsdGVkX3",
+///             "word_count": 5,
+///             "is_locked": false,
+///             "encrypted": true,
+///             "encryption_scheme": "aes-256-gcm",
+///             "nonce": "bm9uY2Vub25jZQ==",
+///             "key_version": 1
 ///         },
 ///         {
 ///             "id": 2,
 ///             "title": "Lorem ipsum",
 ///             "content": "Lorem ipsum dolor sit amet",
 ///             "date": "2020-04-10T07:43:03",
-///             "created_at": "2020-05-07T07:43:03",
-///             "updated_at": "2020-05-09T16:07:41"
+///             "intra_day_order": 0,
+///             "tags": [],
+///             "journal_id": 1,
+///             "status": "published",
+///             "created_at": "2020-05-07T07:43:03Z",
+///             "updated_at": "2020-05-09T16:07:41Z",
+///             "version": 3,
+///             "is_favorite": false,
+///             "mood": 4,
+///             "weather": "sunny",
+///             "latitude": 37.5665,
+///             "longitude": 126.978,
+///             "place_name": "U2FsdGVkX3",
+///             "word_count": 5,
+///             "is_locked": false,
+///             "encrypted": true,
+///             "encryption_scheme": "aes-256-gcm",
+///             "nonce": "bm9uY2Vub25jZQ==",
+///             "key_version": 1
 ///         },
 ///     ],
+///     "meta": {
+///         "total_count": 42,
+///         "page": 1,
+///         "per_page": 20
+///     },
 ///     "error": null
 /// }
 /// ```
 #[get("/posts")]
-pub async fn get_posts(session: Session) -> impl Responder {
-    if let Some(user_session) = session_util::get_session(&session) {
-        let response = reqwest::get(&http_util::get_url(&format!(
-            "/posts/{}",
-            user_session.user_id
-        )))
-        .await;
-        http_util::pass_response::<Vec<PostDTO>>(response).await
+pub async fn get_posts(
+    auth: Authorized<CanReadPosts>,
+    args: web::Query<ListArgs>,
+) -> impl Responder {
+    let args = args.into_inner();
+    let is_summary = args.fields.as_deref() == Some("summary");
+    let query = serde_urlencoded::to_string(&args).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}?{}",
+        auth.user_id(),
+        query
+    )))
+    .await;
+
+    if is_summary {
+        http_util::pass_response::<Vec<SummarizedPostDTO>>(response).await
     } else {
-        http_util::get_err_response::<Vec<PostDTO>>(
-            StatusCode::UNAUTHORIZED,
-            &get_api_error_message(ApiGatewayError::Unauthorized),
-        )
+        http_util::pass_response::<Vec<PostDTO>>(response).await
     }
 }
 
@@ -115,7 +201,7 @@ pub async fn get_posts(session: Session) -> impl Responder {
 ///         {
 ///             "id": 1,
 ///             "title": "Lorem ipsum",
-///             "date": "2020-04-12T07:43:03",
+///             "date": "2020-04-12T16:43:03+09:00",
 ///         },
 ///         {
 ///             "id": 2,
@@ -127,132 +213,593 @@ pub async fn get_posts(session: Session) -> impl Responder {
 /// }
 /// ```
 #[get("/summarized_posts")]
-pub async fn get_summarized_posts(session: Session) -> impl Responder {
-    if let Some(user_session) = session_util::get_session(&session) {
-        let response = reqwest::get(&http_util::get_url(&format!(
-            "/summarized_posts/{}",
-            user_session.user_id
-        )))
-        .await;
-        http_util::pass_response::<Vec<SummarizedPostDTO>>(response).await
-    } else {
-        http_util::get_err_response::<Vec<SummarizedPostDTO>>(
-            StatusCode::UNAUTHORIZED,
-            &get_api_error_message(ApiGatewayError::Unauthorized),
-        )
-    }
+pub async fn get_summarized_posts(auth: Authorized<CanReadPosts>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/summarized_posts/{}",
+        auth.user_id()
+    )))
+    .await;
+    http_util::pass_response::<Vec<SummarizedPostDTO>>(response).await
 }
 
-/// Creates a new post
+/// Lists ids and titles of posts written by logged-in user in a month by the day
+///
+/// Only the days with posts are listed in asc date order, and posts of each day are listed
+/// by `intra_day_order`. The date of each post is compared in the offset where it was written.
 ///
 /// # Request
 ///
 /// ```text
-/// POST /posts
+/// GET /posts/calendar/:year/:month
 /// ```
 ///
 /// ## Parameters
 ///
-/// * content - A content of the post.
+/// * year - A year, such as 2020.
+/// * month - A month from 1 to 12.
+///
+/// # Response
 ///
 /// ```json
 /// {
-///     "title": "Lorem ipsum"
-///     "content": "Lorem ipsum dolor sit amet"
-///     "date": "2020-06-07T07:43:03",
+///     "data": [
+///         {
+///             "date": "2020-04-10",
+///             "count": 1,
+///             "posts": [
+///                 {
+///                     "id": 2,
+///                     "title": "Lorem ipsum",
+///                     "date": "2020-04-10T07:43:03"
+///                 }
+///             ]
+///         },
+///         {
+///             "date": "2020-04-12",
+///             "count": 2,
+///             "posts": [
+///                 {
+///                     "id": 1,
+///                     "title": "Lorem ipsum",
+///                     "date": "2020-04-12T16:43:03+09:00"
+///                 },
+///                 {
+///                     "id": 3,
+///                     "title": "Lorem ipsum",
+///                     "date": "2020-04-12T21:10:00+09:00"
+///                 }
+///             ]
+///         }
+///     ],
+///     "error": null
 /// }
 /// ```
+#[get("/posts/calendar/{year}/{month}")]
+pub async fn get_calendar(
+    auth: Authorized<CanReadPosts>,
+    web::Path((year, month)): web::Path<(i32, u32)>,
+) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/calendar/{}/{}",
+        auth.user_id(),
+        year,
+        month
+    )))
+    .await;
+    http_util::pass_response::<Vec<CalendarDayDTO>>(response).await
+}
+
+/// Lists numbers of posts written by logged-in user by the year and month
+///
+/// Only the years and months with posts are listed in asc order, so that clients can build
+/// an archive without fetching posts. The date of each post is compared in the offset where
+/// it was written. Drafts are not counted.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/archive
+/// ```
 ///
 /// # Response
 ///
 /// ```json
 /// {
-///     "data": 1,
+///     "data": [
+///         {
+///             "year": 2019,
+///             "count": 2,
+///             "months": [
+///                 {
+///                     "month": 12,
+///                     "count": 2
+///                 }
+///             ]
+///         },
+///         {
+///             "year": 2020,
+///             "count": 4,
+///             "months": [
+///                 {
+///                     "month": 1,
+///                     "count": 3
+///                 },
+///                 {
+///                     "month": 4,
+///                     "count": 1
+///                 }
+///             ]
+///         }
+///     ],
 ///     "error": null
 /// }
 /// ```
-#[post("/posts")]
-pub async fn create_post(session: Session, args: web::Json<CreateArgs>) -> impl Responder {
-    if let Some(user_session) = session_util::get_session(&session) {
-        let args = {
-            let CreateArgs {
-                title,
-                content,
-                date,
-            } = args.into_inner();
-            ServiceCreateArgs {
-                title,
-                content,
-                date,
-                user_id: user_session.user_id,
-            }
-        };
-
-        let response = Client::new()
-            .post(&http_util::get_url("/posts"))
-            .json(&args)
-            .send()
-            .await;
-
-        http_util::pass_response::<u64>(response).await
-    } else {
-        http_util::get_err_response::<u64>(
-            StatusCode::UNAUTHORIZED,
-            &get_api_error_message(ApiGatewayError::Unauthorized),
-        )
-    }
+#[get("/posts/archive")]
+pub async fn get_archive(auth: Authorized<CanReadPosts>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/archive",
+        auth.user_id()
+    )))
+    .await;
+    http_util::pass_response::<Vec<ArchiveYearDTO>>(response).await
 }
 
-/// Deletes a post
+/// Lists ids and titles of posts written by logged-in user in a year by the month
+///
+/// Only the months with posts are listed in asc order, and posts of each month are listed
+/// in asc date order and by `intra_day_order`. The date of each post is compared in the offset
+/// where it was written. Drafts are not listed.
 ///
 /// # Request
 ///
 /// ```text
-/// DELETE /posts/:id
+/// GET /posts/archive/:year
+/// ```
+///
+/// ## Parameters
+///
+/// * year - A year, such as 2020.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "month": 4,
+///             "count": 2,
+///             "posts": [
+///                 {
+///                     "id": 2,
+///                     "title": "Lorem ipsum",
+///                     "date": "2020-04-10T07:43:03"
+///                 },
+///                 {
+///                     "id": 1,
+///                     "title": "Lorem ipsum",
+///                     "date": "2020-04-12T16:43:03+09:00"
+///                 }
+///             ]
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/archive/{year}")]
+pub async fn get_archive_year(
+    auth: Authorized<CanReadPosts>,
+    year: web::Path<i32>,
+) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/archive/{}",
+        auth.user_id(),
+        year
+    )))
+    .await;
+    http_util::pass_response::<Vec<ArchiveMonthPostsDTO>>(response).await
+}
+
+/// Lists numbers of posts written by logged-in user on each day of a year
+///
+/// `counts` has a number for every day from January 1, so that clients can render a heatmap
+/// of writing activity from a compact array. Its length is 366 in leap years and 365 otherwise.
+/// The date of each post is compared in the offset where it was written. Drafts are not counted.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/heatmap?year=2020
 /// ```
 ///
+/// ## Parameters
+///
+/// * year - A year. (optional, default: the current year in UTC)
+///
 /// # Response
 ///
 /// ```json
 /// {
-///     "data": true,
+///     "data": {
+///         "year": 2020,
+///         "counts": [0, 2, 0, 1, 0, 0, 3, ..., 0]
+///     },
 ///     "error": null
 /// }
 /// ```
-#[delete("/posts/{id}")]
-pub async fn delete_post(session: Session, id: web::Path<u64>) -> impl Responder {
-    if let Some(user_session) = session_util::get_session(&session) {
-        let response = Client::new()
-            .delete(&http_util::get_url(&format!(
-                "/posts/{}/{}",
-                user_session.user_id, id
-            )))
-            .send()
-            .await;
-        http_util::pass_response::<bool>(response).await
-    } else {
-        http_util::get_err_response::<u64>(
-            StatusCode::UNAUTHORIZED,
-            &get_api_error_message(ApiGatewayError::Unauthorized),
-        )
-    }
+#[get("/posts/heatmap")]
+pub async fn get_heatmap(
+    auth: Authorized<CanReadPosts>,
+    args: web::Query<HeatmapArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/heatmap?{}",
+        auth.user_id(),
+        query
+    )))
+    .await;
+    http_util::pass_response::<HeatmapDTO>(response).await
 }
 
-/// Updates a post
+/// Lists posts written by logged-in user on the same day in previous years
+///
+/// Posts whose month and day are the same as `date` in the offset where each post was written
+/// are listed in desc date order, and posts of the same date are listed by `intra_day_order`.
+/// On February 28 of a common year, posts written on February 29 are also listed.
+/// Drafts are not listed.
 ///
 /// # Request
 ///
 /// ```text
-/// PATCH /posts/:id
+/// GET /posts/on-this-day?date=2021-04-12
 /// ```
 ///
 /// ## Parameters
 ///
-/// * content - A content of the post.
+/// * date - The local date of the client in `YYYY-MM-DD` format. (optional, default: today in
+///   the time zone of the user)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "title": "Lorem ipsum",
+///             "content": "Lorem ipsum dolor sit amet",
+///             "date": "2020-04-12T16:43:03+09:00",
+///             "intra_day_order": 0,
+///             "tags": [2],
+///             "journal_id": 1,
+///             "status": "published",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "updated_at": null,
+///             "version": 1,
+///             "is_favorite": false,
+///             "mood": 4,
+///             "weather": "sunny",
+///             "latitude": 37.5665,
+///             "longitude": 126.978,
+///             "place_name": "U2FsdGVkX3",
+///             "word_count": 5,
+///             "is_locked": false,
+///             "encrypted": true,
+///             "encryption_scheme": "aes-256-gcm",
+///             "nonce": "bm9uY2Vub25jZQ==",
+///             "key_version": 1
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/on-this-day")]
+pub async fn get_on_this_day(
+    auth: Authorized<CanReadPosts>,
+    args: web::Query<OnThisDayArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/on-this-day?{}",
+        auth.user_id(),
+        query
+    )))
+    .await;
+    http_util::pass_response::<Vec<PostDTO>>(response).await
+}
+
+/// Lists changes of posts written by logged-in user since the last sync
+///
+/// Posts created or changed since `since` are listed with their current state, and posts moved
+/// to the trash or permanently deleted since then are listed in `deleted`. A restored post
+/// is listed as changed. `cursor` is to be sent as `since` of the next sync. It overlaps the
+/// changes a little, so the same post may be listed again. Without `since`, all posts except
+/// posts in the trash are listed including drafts.
+///
+/// Permanently deleted posts are forgotten after a while, so `since` older than that
+/// responds 400 Bad Request, and the client must sync from scratch without `since`.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/changes?since=2020-04-13T16:30:09.123456
+/// ```
+///
+/// ## Parameters
+///
+/// * since - `cursor` of the last sync, or an RFC 3339 datetime. (optional)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "posts": [
+///             {
+///                 "id": 1,
+///                 "title": "Lorem ipsum",
+///                 "content": "Lorem ipsum dolor sit amet",
+///                 "date": "2020-04-12T16:43:03+09:00",
+///                 "intra_day_order": 0,
+///                 "tags": [2],
+///                 "journal_id": 1,
+///                 "status": "published",
+///                 "created_at": "2020-04-13T16:31:09Z",
+///                 "updated_at": null,
+///                 "version": 1,
+///                 "is_favorite": false,
+///                 "mood": 4,
+///                 "weather": "sunny",
+///                 "latitude": 37.5665,
+///                 "longitude": 126.978,
+///                 "place_name": "U2FsdGVkX3",
+///                 "word_count": 5,
+///                 "is_locked": false,
+///                 "encrypted": true,
+///                 "encryption_scheme": "aes-256-gcm",
+///                 "nonce": "bm9uY2Vub25jZQ==",
+///                 "key_version": 1
+///             }
+///         ],
+///         "deleted": [
+///             {
+///                 "id": 3,
+///                 "deleted_at": "2020-04-13T16:31:40.482913Z"
+///             }
+///         ],
+///         "cursor": "2020-04-13T16:31:09.123456Z"
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/posts/changes")]
+pub async fn get_changes(
+    auth: Authorized<CanReadPosts>,
+    args: web::Query<ChangesArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/changes?{}",
+        auth.user_id(),
+        query
+    )))
+    .await;
+    http_util::pass_response::<PostChangesDTO>(response).await
+}
+
+/// Lists numbers of encrypted posts and revisions of logged-in user by the key version
+///
+/// Titles and contents of posts are opaque to the service, so clients rotating keys re-encrypt
+/// posts in old key versions with `PATCH /posts/:id`. Revisions cannot be re-encrypted, so old
+/// keys are kept while `revision_count` of them is not zero. Posts encrypted before the scheme
+/// was recorded are counted with `null` scheme and key version.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/key-versions
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "encryption_scheme": null,
+///             "key_version": null,
+///             "post_count": 12,
+///             "revision_count": 30
+///         },
+///         {
+///             "encryption_scheme": "aes-256-gcm",
+///             "key_version": 1,
+///             "post_count": 3,
+///             "revision_count": 4
+///         },
+///         {
+///             "encryption_scheme": "aes-256-gcm",
+///             "key_version": 2,
+///             "post_count": 41,
+///             "revision_count": 8
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/key-versions")]
+pub async fn get_key_versions(auth: Authorized<CanReadPosts>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/key-versions",
+        auth.user_id()
+    )))
+    .await;
+    http_util::pass_response::<Vec<KeyVersionCountDTO>>(response).await
+}
+
+/// Lists mood trends of posts written by logged-in user
+///
+/// Moods of published posts are aggregated by `interval`, and the average and the number
+/// of posts of each mood are listed by the period in asc order. Periods without moods
+/// are omitted. `distribution` is the number of posts of each mood from 1 to 5.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/stats/moods?from=2020-01-01&to=2020-12-31&interval=month
+/// ```
+///
+/// ## Parameters
+///
+/// * from - The first date in `YYYY-MM-DD` format, compared in the offset where each post
+///   was written. (optional)
+/// * to - The last date in `YYYY-MM-DD` format, compared in the offset where each post
+///   was written. (optional)
+/// * interval - `day`, `week` starting on the week start day of the user, or `month`.
+///   (optional, default: `month`)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "date": "2020-03-01",
+///             "count": 1,
+///             "average": 2.0,
+///             "distribution": [0, 1, 0, 0, 0]
+///         },
+///         {
+///             "date": "2020-04-01",
+///             "count": 3,
+///             "average": 4.0,
+///             "distribution": [0, 0, 1, 1, 1]
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/stats/moods")]
+pub async fn get_mood_stats(
+    auth: Authorized<CanReadPosts>,
+    args: web::Query<MoodStatsArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/stats/moods?{}",
+        auth.user_id(),
+        query
+    )))
+    .await;
+    http_util::pass_response::<Vec<MoodTrendDTO>>(response).await
+}
+
+/// Counts posts written by logged-in user in each period
+///
+/// Published posts are counted by `granularity`, and the counts are listed by the period
+/// in asc order. Periods without posts are omitted. A post is on the date in the offset
+/// where it was written, or in the time zone of the user if the offset was not recorded,
+/// with daylight saving time of the time.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/stats/entries?from=2020-12-01&to=2021-01-31&granularity=weekly
+/// ```
+///
+/// ## Parameters
+///
+/// * from - The first local date in `YYYY-MM-DD` format. (optional)
+/// * to - The last local date in `YYYY-MM-DD` format. (optional)
+/// * granularity - `daily`, `weekly` starting on the week start day of the user, or `monthly`.
+///   (optional, default: `weekly`)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "date": "2020-12-27",
+///             "count": 4
+///         },
+///         {
+///             "date": "2021-01-03",
+///             "count": 1
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/stats/entries")]
+pub async fn get_entry_stats(
+    auth: Authorized<CanReadPosts>,
+    args: web::Query<EntryStatsArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/stats/entries?{}",
+        auth.user_id(),
+        query
+    )))
+    .await;
+    http_util::pass_response::<Vec<EntryCountDTO>>(response).await
+}
+
+/// Creates a new post
+///
+/// # Request
+///
+/// ```text
+/// POST /posts
+/// ```
+///
+/// ## Parameters
+///
+/// * title - A title of the post. (optional with `template_id`, default: the title of the template)
+/// * content - A content of the post. (optional with `template_id`, default: the content of the
+///   template)
+/// * date - RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+/// * tags - Ids of tags of the post. (optional)
+/// * status - `published`, or `draft` to save an unfinished post. (optional, default: `published`)
+/// * mood - A mood of the writer from 1 (worst) to 5 (best). (optional)
+/// * weather - `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`. (optional)
+/// * latitude - A latitude where the post is written. (optional)
+/// * longitude - A longitude where the post is written, required with `latitude`. (optional)
+/// * place_name - A name of the place, encrypted by the client in the same way as the content.
+///   It requires `latitude` and `longitude`. (optional)
+/// * journal_id - An id of the journal to write the post in. (optional, default: the default
+///   journal)
+/// * word_count - A number of words in the content, counted by the client before it encrypts
+///   the content. It is summed for word goals. (optional)
+/// * encrypted - Whether the title and content are encrypted by the client. The service stores
+///   them as they are either way. (optional, default: true)
+/// * encryption_scheme - A name of the scheme the client encrypted the post with, in lowercase
+///   letters, digits and hyphens such as `aes-256-gcm`. (optional)
+/// * nonce - A base64 nonce of the encryption, which requires `encryption_scheme`. (optional)
+/// * key_version - A version of the key of the user the post is encrypted with, starting from 1.
+///   (optional)
+/// * template_id - An id of the template to create the post from. The encrypted title and content
+///   of the template are copied as they are, so placeholders such as `{{date}}` in the template
+///   must be expanded by the client when it decrypts the post. (optional)
 ///
 /// ```json
 /// {
+///     "title": "Lorem ipsum"
 ///     "content": "Lorem ipsum dolor sit amet"
+///     "date": "2020-06-07T16:43:03+09:00",
+///     "tags": [2],
+///     "journal_id": 1,
+///     "status": "draft",
+///     "mood": 4,
+///     "weather": "sunny",
+///     "latitude": 37.5665,
+///     "longitude": 126.978,
+///     "place_name": "U2FsdGVkX3",
+///     "word_count": 5,
+///     "encryption_scheme": "aes-256-gcm",
+///     "nonce": "bm9uY2Vub25jZQ==",
+///     "key_version": 1
 /// }
 /// ```
 ///
@@ -260,52 +807,1225 @@ pub async fn delete_post(session: Session, id: web::Path<u64>) -> impl Responder
 ///
 /// ```json
 /// {
-///     "data": true,
+///     "data": 1,
+///     "meta": {
+///         "posts_in_trash_purging_soon": 3
+///     },
 ///     "error": null
 /// }
 /// ```
-#[patch("/posts/{id}")]
-pub async fn update_post(
-    session: Session,
-    id: web::Path<u64>,
-    args: web::Json<UpdateArgs>,
+///
+/// `meta` warns about limits to be reached soon, such as posts in the trash permanently
+/// deleted within 3 days. It is omitted if there is nothing to warn about.
+///
+/// If the encrypted title or content is longer than the limit of the service, it responds
+/// 422 Unprocessable Entity with an error of each field.
+///
+/// ```json
+/// {
+///     "data": null,
+///     "error": "invalid fields",
+///     "fields": [
+///         {
+///             "field": "title",
+///             "message": "must be at most 1000 bytes"
+///         }
+///     ]
+/// }
+/// ```
+#[post("/posts")]
+pub async fn create_post(
+    auth: Authorized<CanWritePosts>,
+    args: web::Json<CreateArgs>,
 ) -> impl Responder {
-    if let Some(user_session) = session_util::get_session(&session) {
-        let args = {
-            let UpdateArgs {
-                title,
-                content,
-                date,
-            } = args.into_inner();
-            ServiceUpdateArgs {
-                title,
-                content,
-                date,
-                user_id: user_session.user_id,
-            }
-        };
-
-        let response = Client::new()
-            .patch(&http_util::get_url(&format!("/posts/{}", id)))
-            .json(&args)
-            .send()
-            .await;
-
-        http_util::pass_response::<bool>(response).await
-    } else {
-        http_util::get_err_response::<bool>(
-            StatusCode::UNAUTHORIZED,
-            &get_api_error_message(ApiGatewayError::Unauthorized),
-        )
-    }
+    let args = {
+        let CreateArgs {
+            title,
+            content,
+            date,
+            tags,
+            status,
+            mood,
+            weather,
+            latitude,
+            longitude,
+            place_name,
+            journal_id,
+            word_count,
+            encrypted,
+            encryption_scheme,
+            nonce,
+            key_version,
+            template_id,
+        } = args.into_inner();
+        ServiceCreateArgs {
+            title,
+            content,
+            date,
+            tags,
+            status,
+            mood,
+            weather,
+            latitude,
+            longitude,
+            place_name,
+            journal_id,
+            word_count,
+            encrypted,
+            encryption_scheme,
+            nonce,
+            key_version,
+            template_id,
+            user_id: auth.user_id(),
+        }
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/posts"))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<u64>(response).await
 }
 
-/// Initializes the post routes.
-pub fn init_routes(cfg: &mut web::ServiceConfig) {
-    cfg.service(get_post);
-    cfg.service(get_posts);
-    cfg.service(get_summarized_posts);
-    cfg.service(create_post);
-    cfg.service(delete_post);
-    cfg.service(update_post);
+/// Creates, updates, and deletes posts at once, batch by batch
+///
+/// Up to 500 operations are executed in the given order, in batches of 100. A request executes
+/// a batch in a transaction from `continue_from`, and responds the progress with `next_cursor`,
+/// which is `null` when all operations are executed. The client sends the same operations again
+/// with `next_cursor` in `continue_from` until it is done. A request retried with the last
+/// `next_cursor` resumes from the failed batch, without executing the previous batches again.
+///
+/// Each operation in a batch has its own result, and a failed operation does not affect
+/// the others. `status` and `error` of a result are the same as the response of the operation
+/// requested alone.
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/bulk
+/// ```
+///
+/// ## Parameters
+///
+/// * operations - Operations distinguished by `op`, which is `create`, `update`, or `delete`.
+///   Parameters of each operation are the same as `POST /posts`, `PATCH /posts/:id`,
+///   and `DELETE /posts/:id`, with `id` of the post to update or delete.
+///   `template_id` is not supported, and `title` and `content` are required to create.
+/// * continue_from - `next_cursor` of the last batch. (optional, default: the first operation)
+///
+/// ```json
+/// {
+///     "operations": [
+///         {
+///             "op": "create",
+///             "title": "Lorem ipsum",
+///             "content": "Lorem ipsum dolor sit amet",
+///             "date": "2020-06-07T16:43:03+09:00"
+///         },
+///         {
+///             "op": "update",
+///             "id": 3,
+///             "content": "Lorem ipsum dolor sit amet",
+///             "version": 2
+///         },
+///         {
+///             "op": "delete",
+///             "id": 4
+///         }
+///     ],
+///     "continue_from": null
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "results": [
+///             {
+///                 "status": 200,
+///                 "data": 12,
+///                 "error": null
+///             },
+///             {
+///                 "status": 409,
+///                 "data": null,
+///                 "error": "conflict with current version `3`",
+///                 "current_version": 3
+///             },
+///             {
+///                 "status": 200,
+///                 "data": 4,
+///                 "error": null
+///             }
+///         ],
+///         "processed": 3,
+///         "remaining": 0,
+///         "next_cursor": null
+///     },
+///     "error": null
+/// }
+/// ```
+pub async fn execute_bulk(
+    auth: Authorized<CanWritePosts>,
+    args: web::Json<BulkArgs>,
+) -> impl Responder {
+    let BulkArgs {
+        operations,
+        continue_from,
+    } = args.into_inner();
+    let args = ServiceBulkArgs {
+        user_id: auth.user_id(),
+        operations,
+        continue_from,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/posts/bulk"))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<BulkBatchDTO>(response).await
+}
+
+/// Moves a post to the trash
+///
+/// The post can be restored from the trash until it is permanently deleted.
+/// If the post is locked, it responds 423 Locked.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /posts/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/posts/{id}")]
+pub async fn delete_post(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/posts/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Permanently deletes all posts written on a date
+///
+/// The password of logged-in user is required again, since the deletion cannot be undone.
+/// The date of each post is compared in the offset where the post was written.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /posts/by-date/:date?permanent=true&dry_run=true
+/// ```
+///
+/// ## Parameters
+///
+/// * date - A date in `YYYY-MM-DD` format.
+/// * permanent - Must be true to confirm the deletion.
+/// * dry_run - If true, responds the data to be removed without removing anything. (optional)
+/// * password - A password of logged-in user.
+///
+/// ```json
+/// {
+///     "password": "Password123!"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "dry_run": true,
+///         "date": "2020-04-12",
+///         "post_ids": [1, 2],
+///         "post_audit_count": 5,
+///         "attachment_count": 2
+///     },
+///     "error": null
+/// }
+/// ```
+#[delete("/posts/by-date/{date}")]
+pub async fn delete_posts_by_date(
+    auth: Authorized<CanWritePosts>,
+    date: web::Path<String>,
+    query: web::Query<DeleteByDateQuery>,
+    args: web::Json<DeleteByDateArgs>,
+) -> impl Responder {
+    let args = {
+        let DeleteByDateQuery { permanent, dry_run } = query.into_inner();
+        let DeleteByDateArgs { password } = args.into_inner();
+        ServiceDeleteByDateArgs {
+            password,
+            permanent,
+            dry_run,
+        }
+    };
+
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/posts/{}/by-date/{}",
+            auth.user_id(),
+            date
+        )))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<PostDateDeletionDTO>(response).await
+}
+
+/// Updates a post
+///
+/// # Request
+///
+/// ```text
+/// PATCH /posts/:id
+/// ```
+///
+/// ## Parameters
+///
+/// * content - A content of the post.
+/// * tags - Ids of tags replacing the tags of the post. (optional)
+/// * mood - A mood of the writer from 1 (worst) to 5 (best). (optional)
+/// * weather - `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`. (optional)
+/// * latitude, longitude, place_name - A location replacing the location of the post.
+///   The place name is cleared unless it is given with the coordinates. (optional)
+/// * journal_id - An id of the journal to move the post to. (optional)
+/// * word_count - A number of words in the new content, counted by the client. The word count
+///   of the post is cleared if `content` is given without it. (optional)
+/// * encrypted, encryption_scheme, nonce, key_version - Encryption of the new title and content
+///   in the same way as creating a post, which requires both `title` and `content`.
+///   If either of them is given without it, the post is marked as encrypted in an unknown scheme.
+///   (optional)
+/// * version - A version of the post the edit is based on. If the post has been updated
+///   since then, it responds 409 Conflict with the current version. (optional)
+///
+/// ```json
+/// {
+///     "content": "Lorem ipsum dolor sit amet",
+///     "version": 3
+/// }
+/// ```
+///
+/// Instead of `version`, `If-Unmodified-Since` header can be sent with `updated_at` of the post,
+/// or `created_at` if it has never been updated. If the post has been modified after that time,
+/// it responds 409 Conflict in the same way.
+///
+/// ```text
+/// PATCH /posts/:id
+/// If-Unmodified-Since: Sun, 07 Jun 2020 07:43:03 GMT
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+///
+/// If the title or content is too long, it responds 422 Unprocessable Entity in the same way as
+/// creating a post. If the post is locked, it responds 423 Locked.
+///
+/// If the post has been updated since the version, it responds 409 Conflict with the current
+/// version in `current_version`:
+///
+/// ```json
+/// {
+///     "data": null,
+///     "error": "conflict with current version `4`",
+///     "current_version": 4
+/// }
+/// ```
+#[patch("/posts/{id}")]
+pub async fn update_post(
+    req: HttpRequest,
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<UpdateArgs>,
+) -> impl Responder {
+    let args = {
+        let UpdateArgs {
+            title,
+            content,
+            date,
+            tags,
+            mood,
+            weather,
+            latitude,
+            longitude,
+            place_name,
+            journal_id,
+            word_count,
+            encrypted,
+            encryption_scheme,
+            nonce,
+            key_version,
+            version,
+        } = args.into_inner();
+        ServiceUpdateArgs {
+            title,
+            content,
+            date,
+            tags,
+            mood,
+            weather,
+            latitude,
+            longitude,
+            place_name,
+            journal_id,
+            word_count,
+            encrypted,
+            encryption_scheme,
+            nonce,
+            key_version,
+            version,
+            user_id: auth.user_id(),
+        }
+    };
+
+    let mut request = Client::new()
+        .patch(&http_util::get_url(&format!("/posts/{}", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args);
+    if let Some(unmodified_since) = req.headers().get(IF_UNMODIFIED_SINCE) {
+        request = request.header(IF_UNMODIFIED_SINCE, unmodified_since.clone());
+    }
+    let response = request.send().await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Saves a post while it is edited
+///
+/// Unlike `PATCH /posts/:id`, it keeps `updated_at`, and autosaves are coalesced into
+/// one revision every 10 minutes by default. The first autosave after an update keeps the post
+/// before it as a revision and increases the version, and the following autosaves overwrite
+/// the post in that version until the interval passes.
+///
+/// # Request
+///
+/// ```text
+/// PUT /posts/:id/autosave
+/// ```
+///
+/// ## Parameters
+///
+/// * title - A title of the post. (optional)
+/// * content - A content of the post.
+/// * encrypted, encryption_scheme, nonce, key_version - Encryption of the title and content
+///   in the same way as creating a post, which requires `title`. (optional)
+/// * version - A version of the post the edit is based on. If the post has been updated
+///   since then, it responds 409 Conflict with the current version. (optional)
+///
+/// ```json
+/// {
+///     "content": "Lorem ipsum dolor sit amet",
+///     "version": 3
+/// }
+/// ```
+///
+/// # Response
+///
+/// The version of the post after the autosave, to be sent with the next edit.
+///
+/// ```json
+/// {
+///     "data": 4,
+///     "error": null
+/// }
+/// ```
+#[put("/posts/{id}/autosave")]
+pub async fn autosave_post(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<AutosaveArgs>,
+) -> impl Responder {
+    let args = {
+        let AutosaveArgs {
+            title,
+            content,
+            encrypted,
+            encryption_scheme,
+            nonce,
+            key_version,
+            version,
+        } = args.into_inner();
+        ServiceAutosaveArgs {
+            title,
+            content,
+            encrypted,
+            encryption_scheme,
+            nonce,
+            key_version,
+            version,
+            user_id: auth.user_id(),
+        }
+    };
+
+    let response = Client::new()
+        .put(&http_util::get_url(&format!("/posts/{}/autosave", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<u32>(response).await
+}
+
+/// Moves a post among the posts of its date
+///
+/// The other posts of the date are shifted to make room for the post.
+///
+/// # Request
+///
+/// ```text
+/// PATCH /posts/:id/reorder
+/// ```
+///
+/// ## Parameters
+///
+/// * position - A position among the posts of the date, starting from 0.
+///   A position past the last post moves the post to the last.
+///
+/// ```json
+/// {
+///     "position": 0
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[patch("/posts/{id}/reorder")]
+pub async fn reorder_post(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<ReorderArgs>,
+) -> impl Responder {
+    let args = ServiceReorderArgs {
+        user_id: auth.user_id(),
+        position: args.into_inner().position,
+    };
+
+    let response = Client::new()
+        .patch(&http_util::get_url(&format!("/posts/{}/reorder", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Lists posts in the trash of logged-in user
+///
+/// Posts are listed in the order of deletion, recently deleted first.
+/// Each post is permanently deleted at `purge_at`.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/trash
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "title": "Lorem ipsum",
+///             "content": "Lorem ipsum dolor sit amet",
+///             "date": "2020-04-12T16:43:03+09:00",
+///             "deleted_at": "2020-05-01T09:00:00Z",
+///             "purge_at": "2020-05-31T09:00:00Z",
+///             "encrypted": true,
+///             "encryption_scheme": "aes-256-gcm",
+///             "nonce": "bm9uY2Vub25jZQ==",
+///             "key_version": 1
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/trash")]
+pub async fn get_trash(auth: Authorized<CanReadPosts>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/trash",
+        auth.user_id()
+    )))
+    .await;
+    http_util::pass_response::<Vec<TrashedPostDTO>>(response).await
+}
+
+/// Publishes a draft
+///
+/// Publishing a post which is already published responds `false`.
+///
+/// # Request
+///
+/// ```text
+/// PATCH /posts/:id/publish
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[patch("/posts/{id}/publish")]
+pub async fn publish_post(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let args = ServicePublishArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .patch(&http_util::get_url(&format!("/posts/{}/publish", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Marks a post as a favorite
+///
+/// It is not an edit of the post, so neither `updated_at` nor `version` of the post changes.
+/// Marking a post which is already a favorite responds `false`.
+///
+/// # Request
+///
+/// ```text
+/// PUT /posts/:id/favorite
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[put("/posts/{id}/favorite")]
+pub async fn favorite_post(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let args = ServiceFavoriteArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .put(&http_util::get_url(&format!("/posts/{}/favorite", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Unmarks a post as a favorite
+///
+/// Unmarking a post which is not a favorite responds `false`.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /posts/:id/favorite
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/posts/{id}/favorite")]
+pub async fn unfavorite_post(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/posts/{}/{}/favorite",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Locks a post against accidental edits
+///
+/// Updating, autosaving, or deleting a locked post responds `423 Locked` until it is unlocked,
+/// which is also reported for each locked post in `POST /posts/bulk`.
+/// Deleting posts by the date of a locked post responds `423 Locked` without deleting any post.
+/// Like marking favorites, neither `updated_at` nor `version` of the post changes.
+/// Locking a post which is already locked responds `false`.
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/:id/lock
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+///
+/// A locked post responds as below on updates and deletion.
+///
+/// ```json
+/// {
+///     "data": null,
+///     "error": "post `1` is locked"
+/// }
+/// ```
+#[post("/posts/{id}/lock")]
+pub async fn lock_post(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let args = ServiceLockArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url(&format!("/posts/{}/lock", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Unlocks a post
+///
+/// Unlocking a post which is not locked responds `false`.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /posts/:id/lock
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/posts/{id}/lock")]
+pub async fn unlock_post(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/posts/{}/{}/lock",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Restores a post from the trash
+///
+/// The post is placed after the other posts of its date.
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/:id/restore
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/posts/{id}/restore")]
+pub async fn restore_post(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let args = ServiceRestoreArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url(&format!("/posts/{}/restore", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Copies a post into a new post
+///
+/// Title, content, status, journal, tags and attachments are copied, which is useful
+/// for recurring posts written from a template. Mood, weather, location and favorite
+/// are not copied. Copied attachments share the files with the original ones,
+/// so the ids of attachments in the content still refer to the original attachments.
+/// The new post is placed after the other posts of its date.
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/:id/duplicate
+/// ```
+///
+/// ## Parameters
+///
+/// * date - RFC 3339 datetime of the new post. If omitted, it is now in the offset where the original post was written.
+///
+/// ```json
+/// {
+///     "date": "2020-04-19T21:00:00+09:00"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": 7,
+///     "error": null
+/// }
+/// ```
+#[post("/posts/{id}/duplicate")]
+pub async fn duplicate_post(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<DuplicateArgs>,
+) -> impl Responder {
+    let args = ServiceDuplicateArgs {
+        user_id: auth.user_id(),
+        date: args.into_inner().date,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url(&format!("/posts/{}/duplicate", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<u64>(response).await
+}
+
+/// Lists revisions of a post written by logged-in user
+///
+/// A revision is kept whenever the post is updated, with the title, content and date
+/// of the post before the update. Revisions are listed in desc version order.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/:id/revisions
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "version": 2,
+///             "title": "Lorem ipsum",
+///             "content": "Lorem ipsum dolor sit amet",
+///             "date": "2020-04-12T16:43:03+09:00",
+///             "created_at": "2020-05-09T16:07:41Z",
+///             "encrypted": true,
+///             "encryption_scheme": "aes-256-gcm",
+///             "nonce": "bm9uY2Vub25jZQ==",
+///             "key_version": 1
+///         },
+///         {
+///             "version": 1,
+///             "title": "Lorem ipsum",
+///             "content": "Lorem ipsum",
+///             "date": "2020-04-12T16:43:03+09:00",
+///             "created_at": "2020-05-07T07:43:03Z",
+///             "encrypted": true,
+///             "encryption_scheme": null,
+///             "nonce": null,
+///             "key_version": null
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/{id}/revisions")]
+pub async fn get_post_revisions(
+    auth: Authorized<CanReadPosts>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/{}/revisions",
+        auth.user_id(),
+        id
+    )))
+    .await;
+    http_util::pass_response::<Vec<PostRevisionDTO>>(response).await
+}
+
+/// Restores a post to a revision
+///
+/// Title, content and date of the post are restored, and its version is increased
+/// like any other update. The post before the restoration is kept as a new revision.
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/:id/revisions/:version/restore
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/posts/{id}/revisions/{version}/restore")]
+pub async fn restore_post_revision(
+    auth: Authorized<CanWritePosts>,
+    web::Path((id, version)): web::Path<(u64, u32)>,
+) -> impl Responder {
+    let args = ServiceRestoreArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/posts/{}/revisions/{}/restore",
+            id, version
+        )))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Lists audit entries of posts written by logged-in user
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/audit?since=2020-04-01T00:00:00&page=1&per_page=20
+/// ```
+///
+/// ## Parameters
+///
+/// * since - Lists entries recorded since the datetime. (optional)
+/// * page - A page number starting from 1. (optional)
+/// * per_page - A number of entries in a page, up to 100. (optional)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 2,
+///             "post_id": 1,
+///             "action": "update",
+///             "ip": "127.0.0.1",
+///             "user_agent": "Mozilla/5.0",
+///             "session_id": "a1lam9cBko",
+///             "created_at": "2020-04-13T16:31:09Z"
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/audit")]
+pub async fn get_post_audits(
+    auth: Authorized<CanReadPosts>,
+    args: web::Query<AuditListArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/audit?{}",
+        auth.user_id(),
+        query
+    )))
+    .await;
+    http_util::pass_response::<Vec<PostAuditDTO>>(response).await
+}
+
+/// Lists audit entries of a post written by logged-in user
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/:id/audit?page=1&per_page=20
+/// ```
+///
+/// ## Parameters
+///
+/// * page - A page number starting from 1. (optional)
+/// * per_page - A number of entries in a page, up to 100. (optional)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "post_id": 1,
+///             "action": "create",
+///             "ip": "127.0.0.1",
+///             "user_agent": "Mozilla/5.0",
+///             "session_id": "a1lam9cBko",
+///             "created_at": "2020-04-12T07:43:03Z"
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/{id}/audit")]
+pub async fn get_post_audit(
+    auth: Authorized<CanReadPosts>,
+    id: web::Path<u64>,
+    args: web::Query<AuditListArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/{}/audit?{}",
+        auth.user_id(),
+        id,
+        query
+    )))
+    .await;
+    http_util::pass_response::<Vec<PostAuditDTO>>(response).await
+}
+
+/// Initializes the post routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_post_audits);
+    cfg.service(get_post_audit);
+    cfg.service(get_trash);
+    cfg.service(get_calendar);
+    cfg.service(get_archive);
+    cfg.service(get_archive_year);
+    cfg.service(get_heatmap);
+    cfg.service(get_on_this_day);
+    cfg.service(get_changes);
+    cfg.service(get_mood_stats);
+    cfg.service(get_entry_stats);
+    cfg.service(get_key_versions);
+    cfg.service(get_post);
+    cfg.service(get_posts);
+    cfg.service(get_summarized_posts);
+    cfg.service(create_post);
+    // Operations in bulk exceed the default payload limit, so the route is configured with its own.
+    cfg.service(
+        web::resource("/posts/bulk")
+            .app_data(http_util::get_json_config().limit(MAX_BULK_PAYLOAD_SIZE))
+            .route(web::post().to(execute_bulk)),
+    );
+    cfg.service(delete_posts_by_date);
+    cfg.service(delete_post);
+    cfg.service(update_post);
+    cfg.service(autosave_post);
+    cfg.service(reorder_post);
+    cfg.service(publish_post);
+    cfg.service(favorite_post);
+    cfg.service(unfavorite_post);
+    cfg.service(lock_post);
+    cfg.service(unlock_post);
+    cfg.service(restore_post);
+    cfg.service(duplicate_post);
+    cfg.service(get_post_revisions);
+    cfg.service(restore_post_revision);
+
+    cfg.service(http_util::get_options_resource(
+        "/posts",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/audit",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/calendar/{year}/{month}",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/archive",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/archive/{year}",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/heatmap",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/trash",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/on-this-day",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/changes",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/stats/moods",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/stats/entries",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/key-versions",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/bulk",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/by-date/{date}",
+        &[Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}",
+        &[Method::GET, Method::PATCH, Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/autosave",
+        &[Method::PUT],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/reorder",
+        &[Method::PATCH],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/publish",
+        &[Method::PATCH],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/favorite",
+        &[Method::PUT, Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/lock",
+        &[Method::POST, Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/restore",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/duplicate",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/revisions",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/revisions/{version}/restore",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/audit",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/summarized_posts",
+        &[Method::GET],
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_session::CookieSession;
+    use actix_web::dev::Service;
+    use actix_web::{test, App};
+    use http::StatusCode;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_head_on_posts() {
+        let mut app = test::init_service(
+            App::new()
+                .wrap_fn(|mut req, srv| {
+                    http_util::serve_head_by_get(&mut req);
+                    srv.call(req)
+                })
+                .wrap(CookieSession::signed(&[0; 64]))
+                .configure(init_routes),
+        )
+        .await;
+
+        let response = test::call_service(
+            &mut app,
+            test::TestRequest::with_uri("/posts")
+                .method(Method::HEAD)
+                .to_request(),
+        )
+        .await;
+
+        // The GET route handles the request, and rejects it since it has no session.
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
 }
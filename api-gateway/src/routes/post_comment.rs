@@ -0,0 +1,263 @@
+use actix_web::{delete, get, post, web, HttpRequest, Responder};
+use http::header::HeaderName;
+use http::Method;
+use reqwest::{Client, RequestBuilder};
+
+use crate::models::post_comment::*;
+use crate::routes::post_share::SHARE_PASSPHRASE_HEADER;
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanReadPosts, CanWritePosts};
+
+/// Forwards the passphrase of a share in the request to the service.
+fn forward_passphrase(req: &HttpRequest, request: RequestBuilder) -> RequestBuilder {
+    match req.headers().get(SHARE_PASSPHRASE_HEADER) {
+        Some(passphrase) => request.header(
+            HeaderName::from_static(SHARE_PASSPHRASE_HEADER),
+            passphrase.clone(),
+        ),
+        None => request,
+    }
+}
+
+/// Lists comments of a post written by logged-in user
+///
+/// Comments are plaintext, written by the writer of the post or readers of its share.
+/// They are listed in the order of writing.
+///
+/// # Request
+///
+/// ```text
+/// GET /posts/:id/comments
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "writer_name": "Park",
+///             "content": "What a lovely day",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "is_mine": false
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/posts/{id}/comments")]
+pub async fn get_comments(auth: Authorized<CanReadPosts>, id: web::Path<u64>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/posts/{}/{}/comments",
+        auth.user_id(),
+        id
+    )))
+    .await;
+    http_util::pass_response::<Vec<PostCommentDTO>>(response).await
+}
+
+/// Comments on a post written by logged-in user
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/:id/comments
+/// ```
+///
+/// ## Parameters
+///
+/// * content - Plaintext content of the comment, which is not blank and at most 2000 bytes.
+///
+/// ```json
+/// {
+///     "content": "What a lovely day"
+/// }
+/// ```
+///
+/// # Response
+///
+/// Id of the created comment.
+///
+/// ```json
+/// {
+///     "data": 1,
+///     "error": null
+/// }
+/// ```
+#[post("/posts/{id}/comments")]
+pub async fn create_comment(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<CreateArgs>,
+) -> impl Responder {
+    let args = ServiceCreateArgs {
+        user_id: auth.user_id(),
+        content: args.into_inner().content,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url(&format!("/posts/{}/comments", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<u64>(response).await
+}
+
+/// Lists comments of a post shared by a token
+///
+/// Logged-in users who can read the share can read its comments, until the share expires
+/// or is revoked. A share protected by a passphrase responds `401 Unauthorized`
+/// unless the passphrase is given by `X-Share-Passphrase` header.
+///
+/// # Request
+///
+/// ```text
+/// GET /shared/:token/comments
+/// X-Share-Passphrase: open sesame
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "writer_name": "Park",
+///             "content": "What a lovely day",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "is_mine": true
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/shared/{token}/comments")]
+pub async fn get_shared_comments(
+    req: HttpRequest,
+    auth: Authorized<CanReadPosts>,
+    token: web::Path<String>,
+) -> impl Responder {
+    let request = Client::new().get(&http_util::get_url(&format!(
+        "/shared/{}/comments/{}",
+        token.into_inner(),
+        auth.user_id()
+    )));
+    let response = forward_passphrase(&req, request).send().await;
+
+    http_util::pass_response::<Vec<PostCommentDTO>>(response).await
+}
+
+/// Comments on a post shared by a token
+///
+/// Logged-in users who can read the share can comment on it. A share protected
+/// by a passphrase responds `401 Unauthorized` unless the passphrase is given
+/// by `X-Share-Passphrase` header.
+///
+/// # Request
+///
+/// ```text
+/// POST /shared/:token/comments
+/// X-Share-Passphrase: open sesame
+/// ```
+///
+/// ## Parameters
+///
+/// * content - Plaintext content of the comment, which is not blank and at most 2000 bytes.
+///
+/// ```json
+/// {
+///     "content": "What a lovely day"
+/// }
+/// ```
+///
+/// # Response
+///
+/// Id of the created comment.
+///
+/// ```json
+/// {
+///     "data": 1,
+///     "error": null
+/// }
+/// ```
+#[post("/shared/{token}/comments")]
+pub async fn create_shared_comment(
+    req: HttpRequest,
+    auth: Authorized<CanWritePosts>,
+    token: web::Path<String>,
+    args: web::Json<CreateArgs>,
+) -> impl Responder {
+    let args = ServiceCreateArgs {
+        user_id: auth.user_id(),
+        content: args.into_inner().content,
+    };
+
+    let request = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/shared/{}/comments",
+            token.into_inner()
+        )))
+        .headers(auth.forwarded_headers())
+        .json(&args);
+    let response = forward_passphrase(&req, request).send().await;
+
+    http_util::pass_response::<u64>(response).await
+}
+
+/// Deletes a comment
+///
+/// Only the writer of the comment or the writer of the post can delete it.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /comments/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/comments/{id}")]
+pub async fn delete_comment(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/comments/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the post comment routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_comments);
+    cfg.service(create_comment);
+    cfg.service(get_shared_comments);
+    cfg.service(create_shared_comment);
+    cfg.service(delete_comment);
+
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/comments",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/shared/{token}/comments",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/comments/{id}",
+        &[Method::DELETE],
+    ));
+}
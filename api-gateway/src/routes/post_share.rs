@@ -0,0 +1,261 @@
+use actix_web::{delete, get, post, web, HttpRequest, Responder};
+use http::header::{HeaderName, HeaderValue, VARY};
+use http::Method;
+use reqwest::Client;
+
+use crate::models::post_share::*;
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanWritePosts};
+
+/// Header carrying the passphrase of a share protected by a passphrase.
+pub const SHARE_PASSPHRASE_HEADER: &str = "x-share-passphrase";
+
+/// Responds a post shared by a token
+///
+/// Anyone with the token can read the post without logging in, until the share expires
+/// or is revoked. A share protected by a passphrase responds `401 Unauthorized`
+/// unless the passphrase is given by `X-Share-Passphrase` header.
+///
+/// The content is responded as stored, so the client must decrypt it
+/// with a key which is not sent to the server.
+///
+/// Browsers opening the link with `Accept: text/html` get the page of
+/// `GET /shared/:token/page` instead, and an expired or revoked link renders
+/// a page telling the link is no longer available with `404 Not Found`.
+///
+/// # Request
+///
+/// ```text
+/// GET /shared/:token
+/// X-Share-Passphrase: open sesame
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "title": "Lorem ipsum",
+///         "content": "Lorem ipsum dolor sit amet",
+///         "date": "2020-04-12T16:43:03+09:00",
+///         "mood": 4,
+///         "weather": "sunny",
+///         "created_at": "2020-04-13T16:31:09Z",
+///         "updated_at": null,
+///         "is_plaintext": false
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/shared/{token}")]
+pub async fn get_shared_post(req: HttpRequest, token: web::Path<String>) -> impl Responder {
+    let token = token.into_inner();
+    let mut response = if http_util::accepts_html(req.headers()) {
+        let response = reqwest::get(&http_util::get_url(&format!("/shared/{}/page", token))).await;
+        http_util::pass_page(response).await
+    } else {
+        let mut request = Client::new().get(&http_util::get_url(&format!("/shared/{}", token)));
+        if let Some(passphrase) = req.headers().get(SHARE_PASSPHRASE_HEADER) {
+            request = request.header(
+                HeaderName::from_static(SHARE_PASSPHRASE_HEADER),
+                passphrase.clone(),
+            );
+        }
+        let response = request.send().await;
+        http_util::pass_response::<SharedPostDTO>(response).await
+    };
+
+    // Caches must not serve the page to the client, or the JSON to browsers.
+    response
+        .headers_mut()
+        .insert(VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// Renders a post shared by a token as an HTML page
+///
+/// The page is readable in a browser without the client. It renders the plaintext copy
+/// of the post as sanitized HTML from markdown, or tells the post is encrypted
+/// if the writer shared no plaintext copy. A share protected by a passphrase renders a form
+/// which submits the passphrase to `POST /shared/:token/page`.
+///
+/// # Request
+///
+/// ```text
+/// GET /shared/:token/page
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// 200 OK
+/// Content-Type: text/html; charset=utf-8
+/// ```
+#[get("/shared/{token}/page")]
+pub async fn get_shared_post_page(token: web::Path<String>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/shared/{}/page",
+        token.into_inner()
+    )))
+    .await;
+    http_util::pass_page(response).await
+}
+
+/// Renders a post shared by a token and protected by a passphrase as an HTML page
+///
+/// # Request
+///
+/// ```text
+/// POST /shared/:token/page
+/// Content-Type: application/x-www-form-urlencoded
+///
+/// passphrase=open+sesame
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// 200 OK
+/// Content-Type: text/html; charset=utf-8
+/// ```
+#[post("/shared/{token}/page")]
+pub async fn post_shared_post_page(
+    token: web::Path<String>,
+    form: web::Form<PassphraseForm>,
+) -> impl Responder {
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/shared/{}/page",
+            token.into_inner()
+        )))
+        .form(&form.into_inner())
+        .send()
+        .await;
+    http_util::pass_page(response).await
+}
+
+/// Shares a post by a public link
+///
+/// A post has one share at most, so sharing a post again revokes the previous link.
+/// Posts in the trash cannot be shared, and shares of trashed posts are not found
+/// until the posts are restored.
+///
+/// # Request
+///
+/// ```text
+/// POST /posts/:id/share
+/// ```
+///
+/// ## Parameters
+///
+/// * expires_at - RFC 3339 datetime in the future when the share expires. The share never expires without it.
+/// * passphrase - A passphrase which readers of the share must give. The share is not protected without it.
+/// * title - Plaintext copy of the title for readers without the key. It must be given with `content`.
+/// * content - Plaintext copy of the content in markdown for readers without the key. It must be given with `title`.
+///
+/// ```json
+/// {
+///     "expires_at": "2020-05-01T00:00:00+09:00",
+///     "passphrase": "open sesame",
+///     "title": "Lorem ipsum",
+///     "content": "Lorem ipsum **dolor** sit amet"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "token": "Xq3Jd9KbT2mWcR7pLz4NvA8sYe1GhU6o",
+///         "url": "https://patic.app/share/Xq3Jd9KbT2mWcR7pLz4NvA8sYe1GhU6o",
+///         "expires_at": "2020-04-30T15:00:00Z",
+///         "has_passphrase": true
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/posts/{id}/share")]
+pub async fn share_post(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<ShareArgs>,
+) -> impl Responder {
+    let ShareArgs {
+        expires_at,
+        passphrase,
+        title,
+        content,
+    } = args.into_inner();
+    let args = ServiceShareArgs {
+        user_id: auth.user_id(),
+        expires_at,
+        passphrase,
+        title,
+        content,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url(&format!("/posts/{}/share", id)))
+        .headers(auth.forwarded_headers())
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<PostShareDTO>(response).await
+}
+
+/// Revokes the public link of a post
+///
+/// # Request
+///
+/// ```text
+/// DELETE /posts/:id/share
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/posts/{id}/share")]
+pub async fn revoke_post_share(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/posts/{}/{}/share",
+            auth.user_id(),
+            id
+        )))
+        .headers(auth.forwarded_headers())
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the post share routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_shared_post);
+    cfg.service(get_shared_post_page);
+    cfg.service(post_shared_post_page);
+    cfg.service(share_post);
+    cfg.service(revoke_post_share);
+
+    cfg.service(http_util::get_options_resource(
+        "/shared/{token}",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/shared/{token}/page",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/posts/{id}/share",
+        &[Method::POST, Method::DELETE],
+    ));
+}
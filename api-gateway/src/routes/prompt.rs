@@ -0,0 +1,122 @@
+use actix_web::{delete, get, post, web, Responder};
+use http::Method;
+use reqwest::Client;
+
+use crate::models::prompt::*;
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanManageAccount, CanReadPosts};
+
+/// Responds the writing prompt of today
+///
+/// Prompts rotate every day, and every user is served the same prompt on a date.
+/// Unlike posts, prompts are written in plaintext.
+///
+/// # Request
+///
+/// ```text
+/// GET /prompts/today?date=2020-04-12
+/// ```
+///
+/// ## Parameters
+///
+/// * date - Local date of the client in `YYYY-MM-DD` format. (optional, default: today in UTC)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "id": 3,
+///         "text": "Who did you spend the most time with today?",
+///         "date": "2020-04-12"
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/prompts/today")]
+pub async fn get_today(
+    _auth: Authorized<CanReadPosts>,
+    args: web::Query<TodayArgs>,
+) -> impl Responder {
+    let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+    let response = reqwest::get(&http_util::get_url(&format!("/prompts/today?{}", query))).await;
+    http_util::pass_response::<PromptDTO>(response).await
+}
+
+/// Subscribes logged-in user to daily prompt emails
+///
+/// A prompt is sent to the email of the user once a day in UTC, with a link unsubscribing
+/// the emails without logging in by `GET /unsubscribe/:token`.
+/// Subscribing again keeps the subscription as it is.
+///
+/// # Request
+///
+/// ```text
+/// POST /prompts/subscription
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/prompts/subscription")]
+pub async fn subscribe(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let args = ServiceSubscribeArgs {
+        user_id: auth.user_id(),
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/prompts/subscription"))
+        .json(&args)
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Unsubscribes logged-in user from daily prompt emails
+///
+/// # Request
+///
+/// ```text
+/// DELETE /prompts/subscription
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/prompts/subscription")]
+pub async fn unsubscribe(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/prompts/subscription/{}",
+            auth.user_id()
+        )))
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the prompt routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_today);
+    cfg.service(subscribe);
+    cfg.service(unsubscribe);
+
+    cfg.service(http_util::get_options_resource(
+        "/prompts/today",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/prompts/subscription",
+        &[Method::POST, Method::DELETE],
+    ));
+}
@@ -0,0 +1,218 @@
+use actix_web::{delete, get, patch, post, web, Responder};
+use http::Method;
+use reqwest::Client;
+
+use crate::models::tag::*;
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanReadPosts, CanWritePosts};
+
+/// Lists tags of logged-in user
+///
+/// Tags are listed in the order of creation.
+///
+/// # Request
+///
+/// ```text
+/// GET /tags
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 2,
+///             "name": "U2FsdGVkX1+Wc2FsdA==",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "updated_at": null
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/tags")]
+pub async fn get_tags(auth: Authorized<CanReadPosts>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!("/tags/{}", auth.user_id()))).await;
+    http_util::pass_response::<Vec<TagDTO>>(response).await
+}
+
+/// Lists tags of logged-in user with the number of posts with each tag
+///
+/// Tags are listed with the most used first, and tags used equally are listed in the order
+/// of creation. Posts in the trash are not counted. Names are encrypted, so autocompletion
+/// filters the decrypted names on the client.
+///
+/// # Request
+///
+/// ```text
+/// GET /tags/cloud
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 2,
+///             "name": "U2FsdGVkX1+Wc2FsdA==",
+///             "post_count": 12
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/tags/cloud")]
+pub async fn get_tag_cloud(auth: Authorized<CanReadPosts>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/tags/{}/cloud",
+        auth.user_id()
+    )))
+    .await;
+    http_util::pass_response::<Vec<TagUsageDTO>>(response).await
+}
+
+/// Creates a new tag
+///
+/// # Request
+///
+/// ```text
+/// POST /tags
+/// ```
+///
+/// ## Parameters
+///
+/// * name - A name of the tag, encrypted by the client like titles of posts.
+///
+/// ```json
+/// {
+///     "name": "U2FsdGVkX1+Wc2FsdA=="
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": 2,
+///     "error": null
+/// }
+/// ```
+#[post("/tags")]
+pub async fn create_tag(
+    auth: Authorized<CanWritePosts>,
+    args: web::Json<TagArgs>,
+) -> impl Responder {
+    let args = ServiceTagArgs {
+        user_id: auth.user_id(),
+        name: args.into_inner().name,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/tags"))
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<u64>(response).await
+}
+
+/// Renames a tag
+///
+/// # Request
+///
+/// ```text
+/// PATCH /tags/:id
+/// ```
+///
+/// ## Parameters
+///
+/// * name - A new name of the tag, encrypted by the client like titles of posts.
+///
+/// ```json
+/// {
+///     "name": "U2FsdGVkX1+Wc2FsdA=="
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[patch("/tags/{id}")]
+pub async fn update_tag(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<TagArgs>,
+) -> impl Responder {
+    let args = ServiceTagArgs {
+        user_id: auth.user_id(),
+        name: args.into_inner().name,
+    };
+
+    let response = Client::new()
+        .patch(&http_util::get_url(&format!("/tags/{}", id)))
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Deletes a tag
+///
+/// The tag is removed from all posts, but the posts are kept.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /tags/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/tags/{id}")]
+pub async fn delete_tag(auth: Authorized<CanWritePosts>, id: web::Path<u64>) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/tags/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the tag routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_tags);
+    cfg.service(get_tag_cloud);
+    cfg.service(create_tag);
+    cfg.service(update_tag);
+    cfg.service(delete_tag);
+
+    cfg.service(http_util::get_options_resource(
+        "/tags",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/tags/cloud",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/tags/{id}",
+        &[Method::PATCH, Method::DELETE],
+    ));
+}
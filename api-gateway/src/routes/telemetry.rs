@@ -0,0 +1,72 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use http::{Method, StatusCode};
+use reqwest::Client;
+
+use crate::models::telemetry::*;
+use crate::utils::http_util;
+use crate::utils::session_util::CurrentUser;
+
+/// Counts a usage event
+///
+/// The event is dropped if telemetry is disabled on the server or the user has not opted in
+/// with `telemetry_opt_in` of the settings, and an admin impersonating the user is not counted.
+/// Either way, it responds 204 No Content.
+///
+/// # Request
+///
+/// ```text
+/// POST /telemetry
+/// ```
+///
+/// ## Parameters
+///
+/// * event - A name of the event. It must be one of the events allowed by the server.
+///
+/// ```json
+/// {
+///     "event": "calendar_view"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// 204 No Content
+/// ```
+#[post("/telemetry")]
+pub async fn record_event(
+    current_user: CurrentUser,
+    args: web::Json<RecordArgs>,
+) -> impl Responder {
+    if current_user.0.impersonator.is_some() {
+        return HttpResponse::NoContent().finish();
+    }
+
+    let args = ServiceRecordArgs {
+        user_id: current_user.0.user_id,
+        event: args.into_inner().event,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/telemetry"))
+        .json(&args)
+        .send()
+        .await;
+
+    match response {
+        Ok(response) if response.status() == StatusCode::NO_CONTENT => {
+            HttpResponse::NoContent().finish()
+        }
+        response => http_util::pass_response::<bool>(response).await,
+    }
+}
+
+/// Initializes the telemetry routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(record_event);
+
+    cfg.service(http_util::get_options_resource(
+        "/telemetry",
+        &[Method::POST],
+    ));
+}
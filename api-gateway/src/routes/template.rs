@@ -0,0 +1,212 @@
+use actix_web::{delete, get, patch, post, web, Responder};
+use http::Method;
+use reqwest::Client;
+
+use crate::models::template::*;
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanReadPosts, CanWritePosts};
+
+/// Lists templates of logged-in user
+///
+/// Templates are listed in the order of creation. Name, title and content of templates
+/// are encrypted by the client like posts, and placeholders such as `{{date}}`
+/// in them are expanded by the client.
+///
+/// # Request
+///
+/// ```text
+/// GET /templates
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 2,
+///             "name": "U2FsdGVkX1+Wc2FsdA==",
+///             "title": "U2FsdGVkX1+Wc2FsdB==",
+///             "content": "U2FsdGVkX1+Wc2FsdC==",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "updated_at": null
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/templates")]
+pub async fn get_templates(auth: Authorized<CanReadPosts>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/templates/{}",
+        auth.user_id()
+    )))
+    .await;
+    http_util::pass_response::<Vec<TemplateDTO>>(response).await
+}
+
+/// Creates a new template
+///
+/// Posts are created from the template by `template_id` of `POST /posts`.
+///
+/// # Request
+///
+/// ```text
+/// POST /templates
+/// ```
+///
+/// ## Parameters
+///
+/// * name - A name of the template, encrypted by the client.
+/// * title - A title of posts created from the template, encrypted by the client. (optional, default: empty)
+/// * content - A content of posts created from the template, encrypted by the client.
+///
+/// ```json
+/// {
+///     "name": "U2FsdGVkX1+Wc2FsdA==",
+///     "title": "U2FsdGVkX1+Wc2FsdB==",
+///     "content": "U2FsdGVkX1+Wc2FsdC=="
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": 2,
+///     "error": null
+/// }
+/// ```
+#[post("/templates")]
+pub async fn create_template(
+    auth: Authorized<CanWritePosts>,
+    args: web::Json<CreateArgs>,
+) -> impl Responder {
+    let CreateArgs {
+        name,
+        title,
+        content,
+    } = args.into_inner();
+    let args = ServiceCreateArgs {
+        user_id: auth.user_id(),
+        name,
+        title,
+        content,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url("/templates"))
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<u64>(response).await
+}
+
+/// Updates a template
+///
+/// Posts already created from the template are not changed.
+///
+/// # Request
+///
+/// ```text
+/// PATCH /templates/:id
+/// ```
+///
+/// ## Parameters
+///
+/// * name - A new name of the template, encrypted by the client. (optional)
+/// * title - A new title of posts created from the template, encrypted by the client. (optional)
+/// * content - A new content of posts created from the template, encrypted by the client. (optional)
+///
+/// ```json
+/// {
+///     "content": "U2FsdGVkX1+Wc2FsdD=="
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[patch("/templates/{id}")]
+pub async fn update_template(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+    args: web::Json<UpdateArgs>,
+) -> impl Responder {
+    let UpdateArgs {
+        name,
+        title,
+        content,
+    } = args.into_inner();
+    let args = ServiceUpdateArgs {
+        user_id: auth.user_id(),
+        name,
+        title,
+        content,
+    };
+
+    let response = Client::new()
+        .patch(&http_util::get_url(&format!("/templates/{}", id)))
+        .json(&args)
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Deletes a template
+///
+/// Posts created from the template are kept.
+///
+/// # Request
+///
+/// ```text
+/// DELETE /templates/:id
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[delete("/templates/{id}")]
+pub async fn delete_template(
+    auth: Authorized<CanWritePosts>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let response = Client::new()
+        .delete(&http_util::get_url(&format!(
+            "/templates/{}/{}",
+            auth.user_id(),
+            id
+        )))
+        .send()
+        .await;
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Initializes the template routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_templates);
+    cfg.service(create_template);
+    cfg.service(update_template);
+    cfg.service(delete_template);
+
+    cfg.service(http_util::get_options_resource(
+        "/templates",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/templates/{id}",
+        &[Method::PATCH, Method::DELETE],
+    ));
+}
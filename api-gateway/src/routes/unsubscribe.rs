@@ -0,0 +1,68 @@
+use actix_web::{get, post, web, HttpResponse, Responder};
+use reqwest::Client;
+
+use crate::utils::http_util;
+
+/// Unsubscribes from emails by the link in an email, and renders the result as an HTML page
+///
+/// The token of the link is signed by the service with the user and the category of the email,
+/// and expires in 90 days. It works without logging in, and unsubscribing again renders
+/// the same page. A forged or expired link renders a page telling the link is no longer available
+/// with `404 Not Found`, which does not tell whether the user exists.
+///
+/// # Request
+///
+/// ```text
+/// GET /unsubscribe/:token
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// 200 OK
+/// Content-Type: text/html; charset=utf-8
+/// ```
+#[get("/unsubscribe/{token}")]
+pub async fn get_unsubscribe(token: web::Path<String>) -> impl Responder {
+    unsubscribe(&token.into_inner()).await
+}
+
+/// Unsubscribes from emails by one click of a mail client
+///
+/// The link is also given by `List-Unsubscribe` header of the email with
+/// `List-Unsubscribe-Post: List-Unsubscribe=One-Click`, so that a mail client posts to it
+/// as RFC 8058. It is the same as `GET /unsubscribe/:token`, and the body is ignored.
+///
+/// # Request
+///
+/// ```text
+/// POST /unsubscribe/:token
+/// Content-Type: application/x-www-form-urlencoded
+///
+/// List-Unsubscribe=One-Click
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// 200 OK
+/// Content-Type: text/html; charset=utf-8
+/// ```
+#[post("/unsubscribe/{token}")]
+pub async fn post_unsubscribe(token: web::Path<String>) -> impl Responder {
+    unsubscribe(&token.into_inner()).await
+}
+
+async fn unsubscribe(token: &str) -> HttpResponse {
+    let response = Client::new()
+        .post(&http_util::get_url(&format!("/unsubscribe/{}", token)))
+        .send()
+        .await;
+    http_util::pass_page(response).await
+}
+
+/// Initializes the unsubscribe routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_unsubscribe);
+    cfg.service(post_unsubscribe);
+}
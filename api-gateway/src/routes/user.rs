@@ -1,31 +1,655 @@
-use actix_session::Session;
-use actix_web::{delete, patch, post, web, Responder};
-use http::StatusCode;
+use actix_multipart::Multipart;
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, Responder};
+use http::header::{CONTENT_TYPE, IF_NONE_MATCH};
+use http::{Method, StatusCode};
 use reqwest::Client;
 
 use crate::models::error::*;
 use crate::models::user::*;
-use crate::utils::{http_util, session_util};
+use crate::utils::http_util;
+use crate::utils::permission_util::{Authorized, CanManageAccount, CanReadPosts};
+
+/// Maximum size of an image to be an avatar.
+const MAX_AVATAR_SIZE: usize = 5 * 1024 * 1024;
+
+/// Content type of images forwarded to the service, which detects the actual type by the content.
+const UPLOAD_CONTENT_TYPE: &str = "application/octet-stream";
 
 /// Creates a new user
 ///
 /// # Request
 ///
 /// ```text
-/// POST /users
+/// POST /users
+/// ```
+///
+/// ## Parameters
+///
+/// * user_public_key - A user's public key
+/// * token_key - A key for token search
+/// * token_pin - A pin for verifying
+///
+/// ```json
+/// {
+///     "user_public_key": "d63ee429"
+///     "token_key": "71I3Qz9u",
+///     "token_pin": "P9d82Jc5"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/users")]
+pub async fn create_user(args: web::Json<CreateArgs>) -> impl Responder {
+    let response = Client::new()
+        .post(&http_util::get_url("/users"))
+        .json(&args.into_inner())
+        .send()
+        .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Responds the profile of logged-in user
+///
+/// The user is taken from the session, so that the client does not need to know the id.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/me
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "id": 1,
+///         "name": "park",
+///         "email": "park@email.com",
+///         "avatar_url": "avatar.jpg",
+///         "created_at": "2020-04-13T16:31:09Z",
+///         "public_key_fingerprint": "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+///         "settings": {
+///             "daily_word_goal": 500,
+///             "monthly_word_goal": null
+///         }
+///     },
+///     "meta": {
+///         "posts_in_trash_purging_soon": 3
+///     },
+///     "error": null
+/// }
+/// ```
+///
+/// `meta` warns about limits to be reached soon, such as posts in the trash permanently
+/// deleted within 3 days. It is omitted if there is nothing to warn about.
+#[get("/users/me")]
+pub async fn get_me(auth: Authorized<CanManageAccount>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/users/{}/profile",
+        auth.user_id()
+    )))
+    .await;
+
+    http_util::pass_response::<ProfileDTO>(response).await
+}
+
+/// Deletes a user
+///
+/// # Request
+///
+/// ```text
+/// DELETE /users/:id?dry_run=true
+/// ```
+///
+/// ## Parameters
+///
+/// * dry_run - If true, responds the data to be removed without removing anything.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "dry_run": true,
+///         "user_id": 1,
+///         "post_ids": [1, 2, 3],
+///         "tag_count": 2,
+///         "user_key_count": 1
+///     },
+///     "error": null
+/// }
+/// ```
+#[delete("/users/{id}")]
+pub async fn delete_user(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    args: web::Query<DeleteArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let dry_run = args.dry_run.unwrap_or(false);
+        let response = Client::new()
+            .delete(&http_util::get_url(&format!(
+                "/users/{}?dry_run={}",
+                id_in_path, dry_run
+            )))
+            .send()
+            .await;
+
+        http_util::pass_response::<UserDeletionDTO>(response).await
+    } else {
+        http_util::get_err_response::<UserDeletionDTO>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Updates a user
+///
+/// # Request
+///
+/// ```text
+/// PATCH /users/:id
+/// ```
+///
+/// ## Parameters
+///
+/// * name - A name of the user.
+/// * password - A password of the user. It must be at least 8 characters and hard to guess.
+///
+/// ```json
+/// {
+///     "name": "park",
+///     "password": "Ir5c7y8dS3"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+///
+/// If the new password is too weak or has appeared in data breaches, it responds
+/// 422 Unprocessable Entity with each reason on the field of the password.
+///
+/// ```json
+/// {
+///     "data": null,
+///     "error": "invalid fields",
+///     "fields": [
+///         {
+///             "field": "password",
+///             "message": "is too easy to guess"
+///         },
+///         {
+///             "field": "password",
+///             "message": "This is similar to a commonly used password"
+///         }
+///     ]
+/// }
+/// ```
+#[patch("/users/{id}")]
+pub async fn update_user(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    args: web::Json<UpdateArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = Client::new()
+            .patch(&http_util::get_url(&format!("/users/{}", id_in_path)))
+            .json(&args.into_inner())
+            .send()
+            .await;
+
+        http_util::pass_response::<bool>(response).await
+    } else {
+        http_util::get_err_response::<bool>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Replaces the avatar of logged-in user
+///
+/// The image is uploaded as `file` field of `multipart/form-data`. Its type is detected
+/// by the content regardless of `Content-Type`, and responds `415 Unsupported Media Type`
+/// if it is not JPEG, PNG, GIF, or WebP. The image is cropped to a square of 256 pixels
+/// and stored as JPEG without metadata such as EXIF, and `avatar_url` of the user is set
+/// to the URL of the avatar.
+///
+/// # Request
+///
+/// ```text
+/// POST /users/:id/avatar
+/// Content-Type: multipart/form-data; boundary=boundary
+///
+/// --boundary
+/// Content-Disposition: form-data; name="file"; filename="avatar.png"
+/// Content-Type: image/png
+///
+/// ...
+/// --boundary--
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+/// * file - An image, up to 5 MiB.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": "/users/1/avatar?v=9f86d081884c7d65",
+///     "error": null
+/// }
+/// ```
+#[post("/users/{id}/avatar")]
+pub async fn upload_avatar(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    payload: Multipart,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path != auth.user_id() {
+        return http_util::get_err_response::<String>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        );
+    }
+
+    let file = match http_util::read_file_field(payload, MAX_AVATAR_SIZE).await {
+        Ok(file) => file,
+        Err(response) => return response,
+    };
+
+    let response = Client::new()
+        .post(&http_util::get_url(&format!(
+            "/users/{}/avatar",
+            id_in_path
+        )))
+        .header(CONTENT_TYPE, UPLOAD_CONTENT_TYPE)
+        .body(file.data)
+        .send()
+        .await;
+
+    http_util::pass_response::<String>(response).await
+}
+
+/// Downloads the avatar of logged-in user
+///
+/// The URL is `avatar_url` of the user. `ETag` is the hash of the avatar, and
+/// `304 Not Modified` is responded if it matches `If-None-Match` of the request.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/:id/avatar
+/// If-None-Match: "9f86d081884c7d65..."
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+///
+/// # Response
+///
+/// ```text
+/// Content-Type: image/jpeg
+/// Content-Disposition: inline; filename="avatar.jpg"
+/// ETag: "9f86d081884c7d65..."
+/// Cache-Control: private, no-cache
+/// ```
+#[get("/users/{id}/avatar")]
+pub async fn download_avatar(
+    req: HttpRequest,
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path != auth.user_id() {
+        return http_util::get_err_response::<()>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        );
+    }
+
+    let mut request = Client::new().get(&http_util::get_url(&format!(
+        "/users/{}/avatar",
+        id_in_path
+    )));
+    if let Some(if_none_match) = req.headers().get(IF_NONE_MATCH) {
+        request = request.header(IF_NONE_MATCH, if_none_match.clone());
+    }
+
+    http_util::pass_stream(request.send().await).await
+}
+
+/// Responds key metadata of logged-in user
+///
+/// It is `null` if the client has never set it.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/:id/key-metadata
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "keys": [
+///             {
+///                 "key_id": "k1",
+///                 "algorithm": "AES-256",
+///                 "created_at": "2020-04-12T07:43:03"
+///             }
+///         ],
+///         "checksum": "a1b2c3"
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/users/{id}/key-metadata")]
+pub async fn get_key_metadata(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = reqwest::get(&http_util::get_url(&format!(
+            "/users/{}/key-metadata",
+            id_in_path
+        )))
+        .await;
+
+        http_util::pass_response::<Option<KeyMetadata>>(response).await
+    } else {
+        http_util::get_err_response::<Option<KeyMetadata>>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Replaces key metadata of logged-in user
+///
+/// The metadata must not contain keys themselves. Fields other than below are rejected.
+///
+/// # Request
+///
+/// ```text
+/// PUT /users/:id/key-metadata
+/// ```
+///
+/// ## Parameters
+///
+/// * keys - Up to 32 keys, each with a unique id, an algorithm, and a creation date.
+/// * checksum - A checksum of the key material defined by the client. (optional)
+///
+/// ```json
+/// {
+///     "keys": [
+///         {
+///             "key_id": "k1",
+///             "algorithm": "AES-256",
+///             "created_at": "2020-04-12T07:43:03"
+///         }
+///     ],
+///     "checksum": "a1b2c3"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[put("/users/{id}/key-metadata")]
+pub async fn set_key_metadata(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    args: web::Json<KeyMetadata>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = Client::new()
+            .put(&http_util::get_url(&format!(
+                "/users/{}/key-metadata",
+                id_in_path
+            )))
+            .json(&args.into_inner())
+            .send()
+            .await;
+
+        http_util::pass_response::<bool>(response).await
+    } else {
+        http_util::get_err_response::<bool>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Gets writing streaks of logged-in user
+///
+/// A streak is the number of consecutive days with published posts, where the day of each post
+/// is the local date where it was written. `current_streak` is kept until the end of the next
+/// day, so it is not broken before the user writes today.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/:id/streak?date=2020-04-13
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+/// * date - The local date of the client like `2020-04-13`, today in the time zone of the user
+///   by default. (optional)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "current_streak": 3,
+///         "longest_streak": 12,
+///         "last_entry_date": "2020-04-13"
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/users/{id}/streak")]
+pub async fn get_streak(
+    auth: Authorized<CanReadPosts>,
+    id: web::Path<u64>,
+    args: web::Query<StreakArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+        let response = reqwest::get(&http_util::get_url(&format!(
+            "/users/{}/streak?{}",
+            id_in_path, query
+        )))
+        .await;
+
+        http_util::pass_response::<StreakDTO>(response).await
+    } else {
+        http_util::get_err_response::<StreakDTO>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Sets word goals of logged-in user
+///
+/// Goals not given are kept.
+///
+/// # Request
+///
+/// ```text
+/// PATCH /users/:id/goals
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+/// * daily - A number of words to write in a day, or 0 to remove the goal. (optional)
+/// * monthly - A number of words to write in a month, or 0 to remove the goal. (optional)
+///
+/// ```json
+/// {
+///     "daily": 500,
+///     "monthly": 10000
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[patch("/users/{id}/goals")]
+pub async fn set_word_goals(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    args: web::Json<WordGoalsArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = Client::new()
+            .patch(&http_util::get_url(&format!("/users/{}/goals", id_in_path)))
+            .json(&args.into_inner())
+            .send()
+            .await;
+
+        http_util::pass_response::<bool>(response).await
+    } else {
+        http_util::get_err_response::<bool>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Gets settings of logged-in user
+///
+/// Settings never changed by the user are the defaults.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/:id/settings
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "timezone": "Asia/Seoul",
+///         "locale": "ko-KR",
+///         "week_start_day": "sunday",
+///         "editor_preferences": {
+///             "font_size": 16,
+///             "font_family": "serif",
+///             "spell_check": false,
+///             "show_word_count": null
+///         },
+///         "default_journal_id": 1,
+///         "telemetry_opt_in": false
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/users/{id}/settings")]
+pub async fn get_settings(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = reqwest::get(&http_util::get_url(&format!(
+            "/users/{}/settings",
+            id_in_path
+        )))
+        .await;
+
+        http_util::pass_response::<UserSettingsDTO>(response).await
+    } else {
+        http_util::get_err_response::<UserSettingsDTO>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Updates settings of logged-in user
+///
+/// Settings not given are kept. Streaks, word goal progress, and posts on this day count
+/// dates in `timezone` unless the client sends its local date.
+///
+/// # Request
+///
+/// ```text
+/// PATCH /users/:id/settings
 /// ```
 ///
 /// ## Parameters
 ///
-/// * user_public_key - A user's public key
-/// * token_key - A key for token search
-/// * token_pin - A pin for verifying
+/// * id - An id of the user.
+/// * timezone - An IANA time zone like `Asia/Seoul`. (optional)
+/// * locale - A BCP 47 language tag like `ko-KR`. (optional)
+/// * week_start_day - `monday`, `sunday`, or `saturday`. (optional)
+/// * editor_preferences - Preferences of the editor, which replace the current ones. (optional)
+///   * font_size - A font size from 10 to 32 pixels. (optional)
+///   * font_family - `serif`, `sans_serif`, or `monospace`. (optional)
+///   * spell_check - Whether to check spelling. (optional)
+///   * show_word_count - Whether to show the word count. (optional)
+/// * default_journal_id - An id of the journal of the user, to which posts go by default. (optional)
+/// * telemetry_opt_in - Whether the user allows anonymous usage counting, which is not allowed
+///   by default. (optional)
 ///
 /// ```json
 /// {
-///     "user_public_key": "d63ee429"
-///     "token_key": "71I3Qz9u",
-///     "token_pin": "P9d82Jc5"
+///     "timezone": "Asia/Seoul",
+///     "week_start_day": "sunday",
+///     "editor_preferences": {
+///         "font_size": 16,
+///         "font_family": "serif"
+///     }
 /// }
 /// ```
 ///
@@ -37,25 +661,282 @@ use crate::utils::{http_util, session_util};
 ///     "error": null
 /// }
 /// ```
-#[post("/users")]
-pub async fn create_user(args: web::Json<CreateArgs>) -> impl Responder {
-    let response = Client::new()
-        .post(&http_util::get_url("/users"))
-        .json(&args.into_inner())
-        .send()
+#[patch("/users/{id}/settings")]
+pub async fn update_settings(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    args: web::Json<UpdateSettingsArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = Client::new()
+            .patch(&http_util::get_url(&format!(
+                "/users/{}/settings",
+                id_in_path
+            )))
+            .json(&args.into_inner())
+            .send()
+            .await;
+
+        http_util::pass_response::<bool>(response).await
+    } else {
+        http_util::get_err_response::<bool>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Gets progress of logged-in user toward word goals
+///
+/// Word counts are sent by the client with posts, since the server cannot read encrypted
+/// contents. Words of each post are counted on its local date, including drafts, and posts
+/// without word counts are not counted. `goal` is `null` if the goal is not set.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/:id/goals/progress?date=2020-04-13
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+/// * date - The local date of the client like `2020-04-13`, today in the time zone of the user
+///   by default. (optional)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "date": "2020-04-13",
+///         "daily": {
+///             "goal": 500,
+///             "word_count": 620,
+///             "is_achieved": true
+///         },
+///         "monthly": {
+///             "goal": null,
+///             "word_count": 8400,
+///             "is_achieved": false
+///         }
+///     },
+///     "error": null
+/// }
+/// ```
+#[get("/users/{id}/goals/progress")]
+pub async fn get_word_goal_progress(
+    auth: Authorized<CanReadPosts>,
+    id: web::Path<u64>,
+    args: web::Query<WordGoalProgressArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+        let response = reqwest::get(&http_util::get_url(&format!(
+            "/users/{}/goals/progress?{}",
+            id_in_path, query
+        )))
         .await;
 
-    http_util::pass_response::<bool>(response).await
+        http_util::pass_response::<WordGoalProgressDTO>(response).await
+    } else {
+        http_util::get_err_response::<WordGoalProgressDTO>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
 }
 
-/// Deletes a user
+/// Lists logins of logged-in user, the most recent first
+///
+/// Every sign-in is recorded, and the user is notified by email when a device which has never
+/// signed in before does. `country` is reported by the proxy in front of the api gateway,
+/// and `null` if it is not.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/:id/logins?page=1&per_page=20
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+/// * page - A page number starting from 1. (optional)
+/// * per_page - A number of logins in a page, 20 by default and 100 at most. (optional)
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "ip": "127.0.0.1",
+///             "country": "KR",
+///             "user_agent": "Mozilla/5.0",
+///             "created_at": "2020-04-13T16:31:09Z"
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/users/{id}/logins")]
+pub async fn get_login_history(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    args: web::Query<LoginHistoryArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let query = serde_urlencoded::to_string(&args.into_inner()).unwrap_or_default();
+        let response = reqwest::get(&http_util::get_url(&format!(
+            "/users/{}/logins?{}",
+            id_in_path, query
+        )))
+        .await;
+
+        http_util::pass_response::<Vec<LoginHistoryDTO>>(response).await
+    } else {
+        http_util::get_err_response::<Vec<LoginHistoryDTO>>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Lists personal access tokens of logged-in user
+///
+/// Tokens themselves are not responded, since only their hashes are stored.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/:id/tokens
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": [
+///         {
+///             "id": 1,
+///             "name": "deploy script",
+///             "scope": "write",
+///             "created_at": "2020-04-13T16:31:09Z",
+///             "last_used_at": null
+///         }
+///     ],
+///     "error": null
+/// }
+/// ```
+#[get("/users/{id}/tokens")]
+pub async fn get_personal_access_tokens(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = reqwest::get(&http_util::get_url(&format!(
+            "/users/{}/tokens",
+            id_in_path
+        )))
+        .await;
+
+        http_util::pass_response::<Vec<PersonalAccessTokenDTO>>(response).await
+    } else {
+        http_util::get_err_response::<Vec<PersonalAccessTokenDTO>>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Creates a personal access token of logged-in user
+///
+/// The token authenticates scripts by `Authorization: Bearer <token>` header without a browser
+/// session. A `read` token is allowed to read posts, and a `write` token is also allowed to
+/// create, update, and delete them. Neither is allowed to manage the account.
+/// The token is responded only once.
+///
+/// # Request
+///
+/// ```text
+/// POST /users/:id/tokens
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+/// * name - A name of the token.
+/// * scope - `read` or `write`.
+///
+/// ```json
+/// {
+///     "name": "deploy script",
+///     "scope": "write"
+/// }
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": {
+///         "id": 1,
+///         "name": "deploy script",
+///         "scope": "write",
+///         "token": "darim_pat_2Rz0hN7kq3vXbF9sLc1WmA8yTg4pJd6eUo5iKn0B"
+///     },
+///     "error": null
+/// }
+/// ```
+#[post("/users/{id}/tokens")]
+pub async fn create_personal_access_token(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    args: web::Json<CreatePersonalAccessTokenArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = Client::new()
+            .post(&http_util::get_url(&format!(
+                "/users/{}/tokens",
+                id_in_path
+            )))
+            .json(&args.into_inner())
+            .send()
+            .await;
+
+        http_util::pass_response::<CreatedPersonalAccessTokenDTO>(response).await
+    } else {
+        http_util::get_err_response::<CreatedPersonalAccessTokenDTO>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Deletes a personal access token of logged-in user, which is not accepted anymore
 ///
 /// # Request
 ///
 /// ```text
-/// DELETE /users/:id
+/// DELETE /users/:id/tokens/:token_id
 /// ```
 ///
+/// ## Parameters
+///
+/// * id - An id of the user.
+/// * token_id - An id of the token.
+///
 /// # Response
 ///
 /// ```json
@@ -64,23 +945,21 @@ pub async fn create_user(args: web::Json<CreateArgs>) -> impl Responder {
 ///     "error": null
 /// }
 /// ```
-#[delete("/users/{id}")]
-pub async fn delete_user(session: Session, id: web::Path<u64>) -> impl Responder {
-    if let Some(user_session) = session_util::get_session(&session) {
-        let id_in_path = id.into_inner();
-        if id_in_path == user_session.user_id {
-            let response = Client::new()
-                .delete(&http_util::get_url(&format!("/users/{}", id_in_path)))
-                .send()
-                .await;
+#[delete("/users/{id}/tokens/{token_id}")]
+pub async fn delete_personal_access_token(
+    auth: Authorized<CanManageAccount>,
+    web::Path((id, token_id)): web::Path<(u64, u64)>,
+) -> impl Responder {
+    if id == auth.user_id() {
+        let response = Client::new()
+            .delete(&http_util::get_url(&format!(
+                "/users/{}/tokens/{}",
+                id, token_id
+            )))
+            .send()
+            .await;
 
-            http_util::pass_response::<bool>(response).await
-        } else {
-            http_util::get_err_response::<bool>(
-                StatusCode::UNAUTHORIZED,
-                &get_api_error_message(ApiGatewayError::Unauthorized),
-            )
-        }
+        http_util::pass_response::<bool>(response).await
     } else {
         http_util::get_err_response::<bool>(
             StatusCode::UNAUTHORIZED,
@@ -89,25 +968,26 @@ pub async fn delete_user(session: Session, id: web::Path<u64>) -> impl Responder
     }
 }
 
-/// Updates a user
+/// Requests changing the email of logged-in user
+///
+/// A confirmation link is emailed to the new address, and the current address is notified.
+/// The current address keeps signing in until the link is confirmed by
+/// `GET /users/email/confirm/:token`. It responds `409 Conflict` if another user has the email.
 ///
 /// # Request
 ///
 /// ```text
-/// PATCH /users/:id
+/// POST /users/:id/email
 /// ```
 ///
 /// ## Parameters
 ///
-/// * name - A name of the user.
-/// * password - A password of the user.
-/// * avatar_url - An avatar image url of the user.
+/// * id - An id of the user.
+/// * email - A new email of the user.
 ///
 /// ```json
 /// {
-///     "name": "park",
-///     "password": "Ir5c7y8dS3",
-///     "avatar_url": "avatar.jpg"
+///     "email": "park@new-email.com"
 /// }
 /// ```
 ///
@@ -119,27 +999,129 @@ pub async fn delete_user(session: Session, id: web::Path<u64>) -> impl Responder
 ///     "error": null
 /// }
 /// ```
-#[patch("/users/{id}")]
-pub async fn update_user(
-    session: Session,
+#[post("/users/{id}/email")]
+pub async fn change_email(
+    auth: Authorized<CanManageAccount>,
     id: web::Path<u64>,
-    args: web::Json<UpdateArgs>,
+    args: web::Json<ChangeEmailArgs>,
+) -> impl Responder {
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let response = Client::new()
+            .post(&http_util::get_url(&format!("/users/{}/email", id_in_path)))
+            .json(&args.into_inner())
+            .send()
+            .await;
+
+        http_util::pass_response::<bool>(response).await
+    } else {
+        http_util::get_err_response::<bool>(
+            StatusCode::UNAUTHORIZED,
+            &get_api_error_message(ApiGatewayError::Unauthorized),
+        )
+    }
+}
+
+/// Confirms a new email with the token of a confirmation link
+///
+/// The token is used once, and the user signs in with the new email after that.
+/// It responds `404 Not Found` if the token is unknown or expired.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/email/confirm/:token
+/// ```
+///
+/// # Response
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[get("/users/email/confirm/{token}")]
+pub async fn confirm_email(web::Path(token): web::Path<String>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!(
+        "/users/email/confirm/{}",
+        token
+    )))
+    .await;
+
+    http_util::pass_response::<bool>(response).await
+}
+
+/// Exports everything of logged-in user as a machine-readable archive
+///
+/// The archive is a gzipped tar of `GET /export?include_trash=true`, which also contains:
+///
+/// * `account.json` - The account with the settings and key metadata, without the password.
+/// * `avatar.jpg` - The avatar, if it has been uploaded.
+/// * `attachments/:id-:filename` - A file per attachment, as it is encrypted by the client.
+/// * `attachments.json` - Metadata of the attachments, with the posts they belong to.
+/// * `login_history.json` - Every sign-in of the user.
+///
+/// If `delivery` is `email`, a link downloading the archive is emailed instead.
+/// The link is valid for a day at `GET /users/export/:token`.
+///
+/// # Request
+///
+/// ```text
+/// POST /users/:id/export
+/// ```
+///
+/// ## Parameters
+///
+/// * id - An id of the user.
+/// * delivery - `download` or `email`. (optional, default: `download`)
+///
+/// ```json
+/// {
+///     "delivery": "email"
+/// }
+/// ```
+///
+/// # Response
+///
+/// If `delivery` is `download`,
+///
+/// ```text
+/// Content-Type: application/gzip
+/// Content-Disposition: attachment; filename="darim-account.tar.gz"
+/// ```
+///
+/// If `delivery` is `email`,
+///
+/// ```json
+/// {
+///     "data": true,
+///     "error": null
+/// }
+/// ```
+#[post("/users/{id}/export")]
+pub async fn export_account(
+    auth: Authorized<CanManageAccount>,
+    id: web::Path<u64>,
+    args: web::Json<ExportAccountArgs>,
 ) -> impl Responder {
-    if let Some(user_session) = session_util::get_session(&session) {
-        let id_in_path = id.into_inner();
-        if id_in_path == user_session.user_id {
-            let response = Client::new()
-                .patch(&http_util::get_url(&format!("/users/{}", id_in_path)))
-                .json(&args.into_inner())
-                .send()
-                .await;
+    let id_in_path = id.into_inner();
+    if id_in_path == auth.user_id() {
+        let args = args.into_inner();
+        let is_email = args.delivery.as_deref() == Some("email");
+        let response = Client::new()
+            .post(&http_util::get_url(&format!(
+                "/users/{}/export",
+                id_in_path
+            )))
+            .json(&args)
+            .send()
+            .await;
 
+        if is_email {
             http_util::pass_response::<bool>(response).await
         } else {
-            http_util::get_err_response::<bool>(
-                StatusCode::UNAUTHORIZED,
-                &get_api_error_message(ApiGatewayError::Unauthorized),
-            )
+            http_util::pass_stream(response).await
         }
     } else {
         http_util::get_err_response::<bool>(
@@ -149,6 +1131,30 @@ pub async fn update_user(
     }
 }
 
+/// Downloads an archive of a user with the token of an emailed link
+///
+/// It doesn't require login, since the link is opened from the email.
+/// The token can be used until it expires, so that a failed download can be started again.
+/// It responds `404 Not Found` if the token is unknown or expired.
+///
+/// # Request
+///
+/// ```text
+/// GET /users/export/:token
+/// ```
+///
+/// # Response
+///
+/// ```text
+/// Content-Type: application/gzip
+/// Content-Disposition: attachment; filename="darim-account.tar.gz"
+/// ```
+#[get("/users/export/{token}")]
+pub async fn download_account_export(web::Path(token): web::Path<String>) -> impl Responder {
+    let response = reqwest::get(&http_util::get_url(&format!("/users/export/{}", token))).await;
+    http_util::pass_stream(response).await
+}
+
 /// Resets the password.
 ///
 /// # Request
@@ -162,14 +1168,14 @@ pub async fn update_user(
 /// * email - An email of the user.
 /// * token_id - A password token ID.
 /// * temporary_password - A temporary password.
-/// * new_password - A new password.
+/// * new_password - A new password. It must be at least 8 characters and hard to guess.
 ///
 /// ```json
 /// {
 ///     "email": "park@email.com",
 ///     "token_id": "d63ee429",
 ///     "temporary_password": "P9d82Jc5",
-///     "new_password": "71I3Qz9u"
+///     "new_password": "71I3Qz9u-wK4v"
 /// }
 /// ```
 ///
@@ -181,10 +1187,30 @@ pub async fn update_user(
 ///     "error": null
 /// }
 /// ```
+///
+/// If the new password is too weak or has appeared in data breaches, it responds
+/// 422 Unprocessable Entity with each reason on the field of the password.
+///
+/// ```json
+/// {
+///     "data": null,
+///     "error": "invalid fields",
+///     "fields": [
+///         {
+///             "field": "new_password",
+///             "message": "is too easy to guess"
+///         },
+///         {
+///             "field": "new_password",
+///             "message": "This is similar to a commonly used password"
+///         }
+///     ]
+/// }
+/// ```
 #[post("/users/password")]
 pub async fn reset_password(args: web::Json<ResetPasswordArgs>) -> impl Responder {
     let response = Client::new()
-        .post(&http_util::get_url("/users/passwword"))
+        .post(&http_util::get_url("/users/password"))
         .json(&args.into_inner())
         .send()
         .await;
@@ -195,7 +1221,131 @@ pub async fn reset_password(args: web::Json<ResetPasswordArgs>) -> impl Responde
 /// Initializes the user routes.
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(create_user);
+    cfg.service(get_me);
     cfg.service(delete_user);
     cfg.service(update_user);
+    cfg.service(upload_avatar);
+    cfg.service(download_avatar);
     cfg.service(reset_password);
+    cfg.service(get_key_metadata);
+    cfg.service(set_key_metadata);
+    cfg.service(get_streak);
+    cfg.service(set_word_goals);
+    cfg.service(get_word_goal_progress);
+    cfg.service(get_login_history);
+    cfg.service(get_personal_access_tokens);
+    cfg.service(create_personal_access_token);
+    cfg.service(delete_personal_access_token);
+    cfg.service(change_email);
+    cfg.service(confirm_email);
+    cfg.service(get_settings);
+    cfg.service(update_settings);
+    cfg.service(export_account);
+    cfg.service(download_account_export);
+
+    cfg.service(http_util::get_options_resource("/users", &[Method::POST]));
+    cfg.service(http_util::get_options_resource(
+        "/users/password",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource("/users/me", &[Method::GET]));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}",
+        &[Method::PATCH, Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/avatar",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/key-metadata",
+        &[Method::GET, Method::PUT],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/streak",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/goals",
+        &[Method::PATCH],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/goals/progress",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/settings",
+        &[Method::GET, Method::PATCH],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/logins",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/tokens",
+        &[Method::GET, Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/tokens/{token_id}",
+        &[Method::DELETE],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/email",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/email/confirm/{token}",
+        &[Method::GET],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/{id}/export",
+        &[Method::POST],
+    ));
+    cfg.service(http_util::get_options_resource(
+        "/users/export/{token}",
+        &[Method::GET],
+    ));
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::{test, App};
+    use http::header::ALLOW;
+
+    use super::*;
+
+    #[actix_rt::test]
+    async fn test_options_on_me() {
+        let mut app = test::init_service(App::new().configure(init_routes)).await;
+
+        let response = test::call_service(
+            &mut app,
+            test::TestRequest::with_uri("/users/me")
+                .method(Method::OPTIONS)
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(response.headers().get(ALLOW).unwrap(), "GET, OPTIONS");
+    }
+
+    #[actix_rt::test]
+    async fn test_options_on_user() {
+        let mut app = test::init_service(App::new().configure(init_routes)).await;
+
+        let response = test::call_service(
+            &mut app,
+            test::TestRequest::with_uri("/users/3")
+                .method(Method::OPTIONS)
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            response.headers().get(ALLOW).unwrap(),
+            "PATCH, DELETE, OPTIONS"
+        );
+    }
 }
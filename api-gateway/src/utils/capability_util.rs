@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use crate::models::capability::CapabilitiesDTO;
+
+/// Registry of features the API supports, which the client uses to negotiate capabilities.
+///
+/// A feature registers its flag in `get_capabilities` in the same change that adds the feature.
+/// The client must treat a feature missing from the registry as unsupported.
+pub struct Capabilities {
+    features: BTreeMap<&'static str, bool>,
+}
+
+impl Capabilities {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self {
+            features: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a feature and whether it is enabled.
+    ///
+    /// It panics if the feature is already registered, so that a flag is never overwritten silently.
+    pub fn register(mut self, name: &'static str, enabled: bool) -> Self {
+        if self.features.insert(name, enabled).is_some() {
+            panic!("feature `{}` is registered twice", name);
+        }
+        self
+    }
+
+    /// Converts the registry to the DTO with the version of the api gateway.
+    pub fn to_dto(&self) -> CapabilitiesDTO {
+        CapabilitiesDTO {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            features: self
+                .features
+                .iter()
+                .map(|(name, enabled)| (name.to_string(), *enabled))
+                .collect(),
+        }
+    }
+}
+
+impl Default for Capabilities {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns every feature the API supports.
+pub fn get_capabilities() -> Capabilities {
+    Capabilities::new()
+        // `PATCH /posts/:id` and `PATCH /users/:id` update only the given fields.
+        .register("partial_update", true)
+        // `PATCH /posts/:id` accepts `version` and responds 409 Conflict on a stale version.
+        .register("post_versioning", true)
+        // `GET /posts/:id/revisions` lists revisions kept on every update of a post,
+        // and `POST /posts/:id/revisions/:version/restore` restores one.
+        .register("post_revisions", true)
+        // `GET /posts` accepts `page` and `per_page`, and responds the total count in `meta`.
+        .register("post_pagination", true)
+        // `GET /posts` accepts `from` and `to` dates, and `sort_by` and `order`.
+        .register("post_list_filters", true)
+        // `GET /posts/calendar/:year/:month` summarizes posts of a month by the day.
+        .register("post_calendar", true)
+        // Post dates keep the offset they were written in.
+        .register("post_date_offset", true)
+        // `DELETE /posts/by-date/:date` permanently deletes posts of a day.
+        .register("delete_posts_by_date", true)
+        // `PATCH /posts/:id/reorder` orders posts of the same date.
+        .register("intra_day_order", true)
+        // `/tags` APIs manage tags, which posts accept in `tags` and `GET /posts` filters by `tag`.
+        .register("tags", true)
+        // `GET /users/:id/key-metadata` and `PUT /users/:id/key-metadata` keep key metadata.
+        .register("key_metadata", true)
+        // `POST /telemetry` counts usage events of opted-in users.
+        .register("telemetry", true)
+        // `DELETE /posts/:id` moves the post to the trash, which `GET /posts/trash` lists
+        // and `POST /posts/:id/restore` restores from.
+        .register("trash", true)
+        // `GET /export` streams an archive of posts.
+        .register("export", true)
+        // `POST /import` creates posts from an archive of `GET /export`.
+        .register("import", true)
+        // `POST /posts` saves drafts with `status`, which `PATCH /posts/:id/publish` publishes.
+        .register("drafts", true)
+        // `POST /posts/:id/attachments` attaches photos to a post,
+        // and `GET /attachments/:id` serves them to the owner.
+        .register("attachments", true)
+        // `GET /posts/on-this-day` lists posts of the same day in previous years.
+        .register("on_this_day", true)
+        // `GET /users/:id/streak` responds writing streaks of the user.
+        .register("writing_streak", true)
+        // `POST /posts/bulk` creates, updates, and deletes posts at once.
+        .register("bulk_operations", true)
+        // `PATCH /posts/:id` accepts `If-Unmodified-Since` header instead of `version`.
+        .register("conditional_update", true)
+        // `PUT /posts/:id/autosave` saves posts being edited without flooding revisions.
+        .register("autosave", true)
+        // `GET /posts/changes` lists changes of posts since the last sync.
+        .register("delta_sync", true)
+        // `PUT /posts/:id/favorite` marks posts, and `GET /posts` accepts `favorite` filter.
+        .register("favorites", true)
+        // Posts have `mood` and `weather`, and `GET /posts/stats/moods` lists mood trends.
+        .register("moods", true)
+        // Posts have a location, and `GET /posts` accepts `near` filter for a map view.
+        .register("locations", true)
+        // `/journals` groups posts into journals, and `GET /posts` accepts `journal` filter.
+        .register("journals", true)
+        // `POST /posts/:id/share` shares a post by a public link read by `GET /shared/:token`.
+        .register("share_links", true)
+        // `GET /shared/:token/page` renders plaintext copies of shared posts as HTML pages,
+        // which `GET /shared/:token` also responds to browsers accepting HTML.
+        .register("shared_post_pages", true)
+        // `POST /posts/:id/duplicate` copies posts with their tags and attachments.
+        .register("post_duplication", true)
+        // `/templates` stores templates, and `POST /posts` accepts `template_id`.
+        .register("templates", true)
+        // `GET /posts` lists posts without their contents by `fields=summary`.
+        .register("post_summaries", true)
+        // `GET /posts/archive` counts posts by the year and month.
+        .register("post_archive", true)
+        // `/users/:id/goals` sets word goals, summing word counts sent with posts.
+        .register("word_goals", true)
+        // `GET /tags/cloud` counts posts with each tag.
+        .register("tag_cloud", true)
+        // `GET /export/calendar.ics` serves dates of posts to calendar apps by a token.
+        .register("calendar_feed", true)
+        // `GET /prompts/today` serves a rotating prompt, and subscribers get it by email.
+        .register("writing_prompts", true)
+        // `GET /posts/heatmap` counts posts on each day of a year.
+        .register("activity_heatmap", true)
+        // `POST /posts/:id/lock` locks a post against updates and deletion.
+        .register("post_locks", true)
+        // `/posts/:id/comments` and `/shared/:token/comments` let the writer and readers
+        // of a shared post comment on it.
+        .register("post_comments", true)
+        // `POST /auth/2fa/setup` sets up TOTP two-factor authentication,
+        // which `POST /auth/login/2fa` requires after the password.
+        .register("two_factor_auth", true)
+        // `POST /auth/webauthn/login` signs in with a passkey instead of the password.
+        .register("passkeys", true)
+        // `GET /auth/oauth/:provider` signs in with Google or GitHub accounts
+        // linked to users by their verified emails.
+        .register("oauth_login", true)
+        // `POST /auth/magic-link` emails a single-use link signing in without the password.
+        .register("magic_link_login", true)
+        // `GET /auth/sessions` lists devices signed in, and `DELETE /auth/sessions/:id` signs them out.
+        .register("session_management", true)
+        // `POST /auth/token` issues access tokens sent in `Authorization: Bearer` header
+        // instead of the session cookie, refreshed by `POST /auth/token/refresh`.
+        .register("token_auth", true)
+        // `POST /users/:id/tokens` mints API keys scoped to read or write posts for scripts.
+        .register("personal_access_tokens", true)
+        // `POST /auth/login` and the other ways of signing in respond `429 Too Many Requests`
+        // with `Retry-After` header after repeated failures, and `RateLimit-*` headers on each.
+        .register("login_rate_limit", true)
+        // `GET /users/:id/logins` lists sign-ins, and new devices are notified by email.
+        .register("login_history", true)
+        // `PATCH /users/:id` and `POST /users/password` reject weak or breached passwords
+        // with `422 Unprocessable Entity` explaining each reason.
+        .register("password_policy", true)
+        // `POST /users/:id/email` changes the email once a link sent to the new address is confirmed.
+        .register("email_change", true)
+        // `GET /users/me` responds the profile of logged-in user.
+        .register("profile", true)
+        // `GET/PATCH /users/:id/settings` and dates counted in the time zone of the user.
+        .register("user_settings", true)
+        // `POST /users/:id/avatar` uploads an avatar instead of setting `avatar_url`.
+        .register("avatar_upload", true)
+        // `POST /users/:id/export` archives the account, or emails a link downloading it.
+        .register("account_export", true)
+        // `/admin` APIs let admins list, suspend, and delete accounts, and count signups and posts.
+        .register("admin", true)
+        // `POST /admin/invites` mints invite codes, which `POST /auth/token/sign_up` accepts
+        // in `invite_code`, required on instances in invite mode.
+        .register("invites", true)
+        // Posts record `encryption_scheme`, `nonce`, and `key_version` of their encryption,
+        // counted by `GET /posts/key-versions` for key rotation.
+        .register("post_encryption_metadata", true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capabilities() {
+        let capabilities = Capabilities::new()
+            .register("partial_update", true)
+            .register("websocket_sync", false);
+
+        let dto = capabilities.to_dto();
+        assert_eq!(dto.version, env!("CARGO_PKG_VERSION"));
+        assert_eq!(dto.features.get("partial_update"), Some(&true));
+        assert_eq!(dto.features.get("websocket_sync"), Some(&false));
+        assert_eq!(dto.features.get("tags"), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "feature `tags` is registered twice")]
+    fn test_register_twice() {
+        Capabilities::new()
+            .register("tags", true)
+            .register("tags", false);
+    }
+}
@@ -0,0 +1,75 @@
+use reqwest::Client;
+use std::env;
+use std::time::Duration;
+
+use crate::utils::http_util;
+use crate::utils::meta_util::{self, MetaInfo};
+
+/// Time limit of each probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Result of a probe on an external dependency.
+pub struct CheckResult {
+    pub name: &'static str,
+    pub result: Result<String, String>,
+}
+
+/// Requests the health check of the back-end service.
+async fn check_service() -> Result<String, String> {
+    env::var("BACK_END_SERVICE_ADDRESS").map_err(|_| "BACK_END_SERVICE_ADDRESS not found")?;
+
+    let client = Client::builder()
+        .timeout(PROBE_TIMEOUT)
+        .build()
+        .map_err(|error| format!("Failed to build http client: {}", error))?;
+    let response = client
+        .get(&http_util::get_url("/"))
+        .send()
+        .await
+        .map_err(|error| format!("Failed to reach the service: {}", error))?;
+
+    if response.status().is_success() {
+        Ok(format!("responded {}", response.status()))
+    } else {
+        Err(format!("responded {}", response.status()))
+    }
+}
+
+/// Loads TLS certificate and private key as the server does in production.
+fn check_tls(meta_info: &MetaInfo) -> Result<String, String> {
+    if !meta_info.is_production() {
+        return Ok(String::from("not used in this environment"));
+    }
+
+    meta_util::get_tls_config()?;
+    Ok(String::from("certificate and private key loaded"))
+}
+
+/// Runs every probe, prints the results as a table, and returns the exit code.
+///
+/// It returns non-zero if any probe fails.
+pub async fn run(meta_info: &MetaInfo) -> i32 {
+    let results = vec![
+        CheckResult {
+            name: "service",
+            result: check_service().await,
+        },
+        CheckResult {
+            name: "tls",
+            result: check_tls(meta_info),
+        },
+    ];
+
+    for CheckResult { name, result } in &results {
+        match result {
+            Ok(message) => println!("PASS  {:<10} {}", name, message),
+            Err(message) => println!("FAIL  {:<10} {}", name, message),
+        }
+    }
+
+    if results.iter().all(|check| check.result.is_ok()) {
+        0
+    } else {
+        1
+    }
+}
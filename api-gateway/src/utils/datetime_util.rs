@@ -0,0 +1,63 @@
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Formats naive datetime in UTC as RFC 3339 UTC datetime.
+pub fn to_rfc3339(datetime: &NaiveDateTime) -> String {
+    DateTime::<Utc>::from_utc(*datetime, Utc).to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parses RFC 3339 datetime to naive datetime in UTC.
+/// Naive datetime serialized before RFC 3339 is accepted as it is.
+pub fn parse(value: &str) -> Option<NaiveDateTime> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(datetime) => Some(datetime.naive_utc()),
+        Err(_) => NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok(),
+    }
+}
+
+/// Serializes naive datetime in UTC as RFC 3339, with `#[serde(with = "datetime_util::rfc3339")]`.
+pub mod rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        datetime: &NaiveDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_rfc3339(datetime))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<NaiveDateTime, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse(&value).ok_or_else(|| D::Error::custom(format!("invalid datetime `{}`", value)))
+    }
+}
+
+/// Serializes optional naive datetime in UTC as RFC 3339,
+/// with `#[serde(default, with = "datetime_util::option_rfc3339")]`.
+pub mod option_rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        datetime: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match datetime {
+            Some(datetime) => serializer.serialize_some(&to_rfc3339(datetime)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveDateTime>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) => parse(&value)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("invalid datetime `{}`", value))),
+            None => Ok(None),
+        }
+    }
+}
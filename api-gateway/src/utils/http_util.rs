@@ -1,33 +1,87 @@
-use actix_web::HttpResponse;
-use http::StatusCode;
+use actix_multipart::Multipart;
+use actix_web::body::{Body, ResponseBody};
+use actix_web::dev::{ServiceRequest, ServiceResponse as ActixServiceResponse, SizedStream};
+use actix_web::error::{ErrorBadGateway, InternalError, JsonPayloadError};
+use actix_web::web::{self, Bytes, BytesMut};
+use actix_web::{guard, Error, HttpRequest, HttpResponse, Resource};
+use futures::{StreamExt, TryStreamExt};
+use http::header::{
+    HeaderMap, HeaderValue, ACCEPT, ACCEPT_RANGES, ALLOW, CACHE_CONTROL, CONTENT_DISPOSITION,
+    CONTENT_RANGE, CONTENT_SECURITY_POLICY, CONTENT_TYPE, ETAG, RETRY_AFTER,
+};
+use http::{Method, StatusCode};
 use reqwest::Response;
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
 use std::env;
 
-use crate::models::error::ApiGatewayError;
+use crate::models::error::{get_api_error_message, ApiGatewayError};
+
+/// Content type of JSON responses.
+const JSON_CONTENT_TYPE: &str = "application/json; charset=utf-8";
+
+/// Headers of the rate limit of a rate-limited scope, which are passed to the client
+/// on every response of the scope.
+const RATE_LIMIT_HEADERS: [&str; 3] = ["ratelimit-limit", "ratelimit-remaining", "ratelimit-reset"];
 
 /// HTTP response of the API.
 #[derive(Deserialize, Serialize)]
 pub struct ServiceResponse<T> {
     data: Option<T>,
+    /// Metadata of the data such as pagination, passed as it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    meta: Option<Value>,
     error: Option<String>,
+    /// Errors of each field of the request, passed as it is.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    fields: Option<Value>,
+    /// Seconds to wait before retrying, if there have been too many attempts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    retry_after: Option<u64>,
+    /// Unix timestamp when the limit is reset, if there have been too many attempts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reset_at: Option<i64>,
+    /// Current version of the item, if the request conflicts with it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    current_version: Option<u32>,
 }
 
 impl<T> ServiceResponse<T> {
     /// Creates a response containing normal data.
     fn ok(data: Option<T>) -> Self {
-        ServiceResponse { data, error: None }
+        ServiceResponse {
+            data,
+            meta: None,
+            error: None,
+            fields: None,
+            retry_after: None,
+            reset_at: None,
+            current_version: None,
+        }
     }
 
     /// Creates a response containing error.
     fn err(error: Option<String>) -> Self {
-        ServiceResponse { data: None, error }
+        ServiceResponse {
+            data: None,
+            meta: None,
+            error,
+            fields: None,
+            retry_after: None,
+            reset_at: None,
+            current_version: None,
+        }
     }
 }
 
 /// Returns HttpResponse by status code.
 ///
+/// Any success or client error status of the service is passed as it is, such as `201 Created`,
+/// and any other status is responded as `500 Internal Server Error` except
+/// `503 Service Unavailable`.
+///
 /// # Arguments
 ///
 /// * `status_code` - HTTP status code.
@@ -36,20 +90,78 @@ fn get_response_by_status_code<T: DeserializeOwned + Serialize>(
     status_code: StatusCode,
     service_response: ServiceResponse<T>,
 ) -> HttpResponse {
-    let ServiceResponse { data, error } = service_response;
+    let ServiceResponse {
+        data,
+        meta,
+        error,
+        fields,
+        retry_after,
+        reset_at,
+        current_version,
+    } = service_response;
 
-    match status_code {
-        StatusCode::OK => HttpResponse::Ok().json(ServiceResponse::<T>::ok(data)),
-        StatusCode::NOT_FOUND => HttpResponse::NotFound().json(ServiceResponse::<T>::err(error)),
-        StatusCode::BAD_REQUEST => {
-            HttpResponse::BadRequest().json(ServiceResponse::<T>::err(error))
-        }
-        StatusCode::CONFLICT => HttpResponse::Conflict().json(ServiceResponse::<T>::err(error)),
-        StatusCode::UNAUTHORIZED => {
-            HttpResponse::Unauthorized().json(ServiceResponse::<T>::err(error))
+    let (status_code, service_response) = match status_code {
+        status_code if status_code.is_success() => (
+            status_code,
+            ServiceResponse::<T> {
+                data,
+                meta,
+                error: None,
+                fields: None,
+                retry_after: None,
+                reset_at: None,
+                current_version: None,
+            },
+        ),
+        StatusCode::UNPROCESSABLE_ENTITY => (
+            status_code,
+            ServiceResponse::<T> {
+                data: None,
+                meta: None,
+                error,
+                fields,
+                retry_after: None,
+                reset_at: None,
+                current_version: None,
+            },
+        ),
+        StatusCode::TOO_MANY_REQUESTS => (
+            status_code,
+            ServiceResponse::<T> {
+                data: None,
+                meta: None,
+                error,
+                fields: None,
+                retry_after,
+                reset_at,
+                current_version: None,
+            },
+        ),
+        StatusCode::CONFLICT => (
+            status_code,
+            ServiceResponse::<T> {
+                current_version,
+                ..ServiceResponse::err(error)
+            },
+        ),
+        status_code
+            if status_code.is_client_error() || status_code == StatusCode::SERVICE_UNAVAILABLE =>
+        {
+            (status_code, ServiceResponse::<T>::err(error))
         }
-        _ => HttpResponse::InternalServerError().json(ServiceResponse::<T>::err(error)),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceResponse::<T>::err(error),
+        ),
+    };
+
+    let mut response = HttpResponse::build(status_code);
+    if let Some(seconds) = service_response.retry_after {
+        response.header(RETRY_AFTER, seconds.to_string());
     }
+    response
+        .content_type(JSON_CONTENT_TYPE)
+        .json(service_response)
 }
 
 /// Parses JSON body in service response.
@@ -68,36 +180,266 @@ pub async fn parse_data_from_service_response<T: DeserializeOwned + Serialize>(
 
 /// Converts http response from back-end service to .
 ///
+/// `RateLimit-*` headers of a rate-limited scope are passed as they are.
+///
 /// # Arguments
 ///
 /// * `response` - HTTP response received from back-end service.
 pub async fn pass_response<T: DeserializeOwned + Serialize>(
     response: reqwest::Result<Response>,
 ) -> HttpResponse {
+    let rate_limit_headers = get_rate_limit_headers(&response);
     match response {
         Ok(response) => {
             let status_code = response.status();
-            match response.json::<ServiceResponse<T>>().await {
+            let http_response = match response.json::<ServiceResponse<T>>().await {
                 Ok(service_response) => {
                     get_response_by_status_code::<T>(status_code, service_response)
                 }
-                Err(_) => {
-                    HttpResponse::InternalServerError().json(ServiceResponse::<T>::err(Some(
-                        format!("{}", ApiGatewayError::ServiceResponseParsingFailure),
-                    )))
-                }
-            }
+                Err(_) => get_response_by_status_code::<T>(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ServiceResponse::<T>::err(Some(format!(
+                        "{}",
+                        ApiGatewayError::ServiceResponseParsingFailure
+                    ))),
+                ),
+            };
+            with_headers(http_response, &rate_limit_headers)
         }
         Err(error) => get_response_by_status_code::<T>(
             error.status().unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
             ServiceResponse {
                 data: None,
+                meta: None,
                 error: None,
+                fields: None,
+                retry_after: None,
+                reset_at: None,
+                current_version: None,
             },
         ),
     }
 }
 
+/// Returns `RateLimit-*` headers of the response from back-end service.
+///
+/// # Arguments
+///
+/// * `response` - HTTP response received from back-end service.
+pub fn get_rate_limit_headers(response: &reqwest::Result<Response>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+    if let Ok(response) = response {
+        for name in RATE_LIMIT_HEADERS.iter() {
+            if let Some(value) = response.headers().get(*name) {
+                headers.insert(*name, value.clone());
+            }
+        }
+    }
+    headers
+}
+
+/// Adds the headers the response does not have yet, and returns it.
+///
+/// # Arguments
+///
+/// * `response` - HTTP response to the client.
+/// * `headers` - Headers to be added, such as the ones of `get_rate_limit_headers`.
+pub fn with_headers(mut response: HttpResponse, headers: &HeaderMap) -> HttpResponse {
+    for (name, value) in headers {
+        if !response.headers().contains_key(name) {
+            response.headers_mut().insert(name.clone(), value.clone());
+        }
+    }
+    response
+}
+
+/// Converts file response from back-end service to HTTP response streaming the file.
+///
+/// The file is passed chunk by chunk without being buffered, and `304 Not Modified`
+/// is passed with its validators. A range of the file is passed as `206 Partial Content`
+/// with `Content-Range`, and the length of the file is kept if it is known, so that the client
+/// can tell a broken download. `416 Range Not Satisfiable` is passed with the size of the file
+/// in `Content-Range`. Any other error response of the service is passed like `pass_response`.
+///
+/// # Arguments
+///
+/// * `response` - HTTP response received from back-end service.
+pub async fn pass_stream(response: reqwest::Result<Response>) -> HttpResponse {
+    match response {
+        Ok(response) if response.status() == StatusCode::NOT_MODIFIED => {
+            let mut http_response = HttpResponse::NotModified();
+            for name in &[ETAG, CACHE_CONTROL] {
+                if let Some(value) = response.headers().get(name) {
+                    http_response.header(name.clone(), value.clone());
+                }
+            }
+            http_response.finish()
+        }
+        Ok(response) if response.status() == StatusCode::RANGE_NOT_SATISFIABLE => {
+            let mut http_response = HttpResponse::RangeNotSatisfiable();
+            if let Some(value) = response.headers().get(CONTENT_RANGE) {
+                http_response.header(CONTENT_RANGE, value.clone());
+            }
+            http_response.finish()
+        }
+        Ok(response)
+            if response.status() == StatusCode::OK
+                || response.status() == StatusCode::PARTIAL_CONTENT =>
+        {
+            let mut http_response = HttpResponse::build(response.status());
+            for name in &[
+                CONTENT_TYPE,
+                CONTENT_DISPOSITION,
+                CONTENT_RANGE,
+                ACCEPT_RANGES,
+                ETAG,
+                CACHE_CONTROL,
+            ] {
+                if let Some(value) = response.headers().get(name) {
+                    http_response.header(name.clone(), value.clone());
+                }
+            }
+
+            let length = response.content_length();
+            let body = response.bytes_stream().map_err(|_| {
+                ErrorBadGateway(get_api_error_message(
+                    ApiGatewayError::ServiceResponseParsingFailure,
+                ))
+            });
+            match length {
+                Some(length) => http_response.body(SizedStream::new(length, body)),
+                None => http_response.streaming(body),
+            }
+        }
+        response => pass_response::<()>(response).await,
+    }
+}
+
+/// Converts HTML page response from back-end service to HTTP response.
+///
+/// Unlike `pass_response`, error pages are passed with their status codes as they are,
+/// since they are read by browsers rather than the client.
+///
+/// # Arguments
+///
+/// * `response` - HTTP response received from back-end service.
+pub async fn pass_page(response: reqwest::Result<Response>) -> HttpResponse {
+    match response {
+        Ok(response) if is_html(response.headers()) => {
+            let mut http_response = HttpResponse::build(response.status());
+            for name in &[CONTENT_TYPE, CONTENT_SECURITY_POLICY, CACHE_CONTROL] {
+                if let Some(value) = response.headers().get(name) {
+                    http_response.header(name.clone(), value.clone());
+                }
+            }
+
+            match response.bytes().await {
+                Ok(body) => http_response.body(body),
+                Err(_) => HttpResponse::BadGateway().finish(),
+            }
+        }
+        response => pass_response::<()>(response).await,
+    }
+}
+
+/// Returns whether the request accepts HTML, as browsers do on opening a link.
+///
+/// API clients accepting JSON or anything by `*/*` are not regarded as accepting HTML.
+pub fn accepts_html(headers: &HeaderMap) -> bool {
+    accepts_media_type(headers, "text/html")
+}
+
+/// Returns whether the request accepts CSV explicitly, rather than by `*/*`.
+pub fn accepts_csv(headers: &HeaderMap) -> bool {
+    accepts_media_type(headers, "text/csv")
+}
+
+/// Returns whether `Accept` header of the request names `media_type` without `q=0`.
+fn accepts_media_type(headers: &HeaderMap, media_type: &str) -> bool {
+    let accept = match headers.get(ACCEPT).and_then(|value| value.to_str().ok()) {
+        Some(accept) => accept,
+        None => return false,
+    };
+
+    accept.split(',').any(|media_range| {
+        let mut params = media_range.split(';').map(str::trim);
+        let accepted_type = params.next().unwrap_or_default();
+        let is_rejected = params.any(|param| {
+            param.starts_with("q=") && param[2..].parse::<f32>().map_or(false, |q| q <= 0.0)
+        });
+        accepted_type.eq_ignore_ascii_case(media_type) && !is_rejected
+    })
+}
+
+/// Returns whether the headers have HTML content type.
+fn is_html(headers: &HeaderMap) -> bool {
+    headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.starts_with("text/html"))
+}
+
+/// File uploaded as a field of `multipart/form-data` request.
+pub struct UploadedFile {
+    pub filename: Option<String>,
+    pub data: Vec<u8>,
+}
+
+/// Reads `file` field of a multipart request, or returns an error response.
+///
+/// The other fields are ignored.
+///
+/// # Arguments
+///
+/// * `payload` - A payload of `multipart/form-data` request.
+/// * `max_size` - Maximum size of the file in bytes.
+pub async fn read_file_field(
+    mut payload: Multipart,
+    max_size: usize,
+) -> Result<UploadedFile, HttpResponse> {
+    let bad_request = |message: String| get_err_response::<()>(StatusCode::BAD_REQUEST, &message);
+
+    let mut file = None;
+    while let Some(mut field) = payload
+        .try_next()
+        .await
+        .map_err(|error| bad_request(format!("{}", error)))?
+    {
+        let disposition = field.content_disposition();
+        let is_file = disposition
+            .as_ref()
+            .map_or(false, |disposition| disposition.get_name() == Some("file"));
+        let filename = disposition
+            .as_ref()
+            .and_then(|disposition| disposition.get_filename())
+            .map(|filename| filename.to_string());
+
+        let mut data = Vec::new();
+        while let Some(chunk) = field
+            .try_next()
+            .await
+            .map_err(|error| bad_request(format!("{}", error)))?
+        {
+            if !is_file {
+                continue;
+            }
+            if data.len() + chunk.len() > max_size {
+                return Err(get_err_response::<()>(
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    &get_api_error_message(ApiGatewayError::PayloadTooLarge),
+                ));
+            }
+            data.extend_from_slice(&chunk);
+        }
+
+        if is_file && file.is_none() {
+            file = Some(UploadedFile { filename, data });
+        }
+    }
+
+    file.ok_or_else(|| bad_request(get_api_error_message(ApiGatewayError::MissingFile)))
+}
+
 /// Returns 200 OK HTTP response that contains `data`.
 ///
 /// # Arguments
@@ -120,6 +462,35 @@ pub fn get_err_response<T: DeserializeOwned + Serialize>(
     get_response_by_status_code::<T>(status_code, ServiceResponse::err(Some(error.to_string())))
 }
 
+/// Returns an error that responds HTTP error response, used when an extractor fails.
+///
+/// # Arguments
+///
+/// * `status_code` - HTTP status code.
+/// * `error` - An error to be contained in response.
+pub fn get_extraction_error(status_code: StatusCode, error: ApiGatewayError) -> Error {
+    let message = get_api_error_message(error);
+    let response = get_err_response::<()>(status_code, &message);
+    InternalError::from_response(message, response).into()
+}
+
+/// Returns configuration of JSON extractor, which responds `415 Unsupported Media Type`
+/// if the request is not `application/json` or `+json`, and `400 Bad Request` if the body is invalid.
+pub fn get_json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|error, _| {
+        let (status_code, message) = match error {
+            JsonPayloadError::ContentType => (
+                StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                get_api_error_message(ApiGatewayError::UnsupportedMediaType),
+            ),
+            JsonPayloadError::Overflow => (StatusCode::PAYLOAD_TOO_LARGE, format!("{}", error)),
+            _ => (StatusCode::BAD_REQUEST, format!("{}", error)),
+        };
+        let response = get_err_response::<()>(status_code, &message);
+        InternalError::from_response(message, response).into()
+    })
+}
+
 /// Returns back-end service url.
 ///
 /// # Arguments
@@ -129,3 +500,683 @@ pub fn get_url(resource: &str) -> String {
     let base_url = env::var("BACK_END_SERVICE_ADDRESS").unwrap();
     format!("{}{}", base_url, resource)
 }
+
+/// Naming convention of JSON responses. Datetimes are RFC 3339 UTC datetimes in any convention.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Convention {
+    /// snake_case keys. (default)
+    Snake,
+    /// camelCase keys.
+    Camel,
+}
+
+/// Query parameters to negotiate the convention.
+#[derive(Deserialize)]
+struct ConventionQuery {
+    convention: Option<String>,
+}
+
+impl Convention {
+    /// Returns convention requested by `X-Api-Convention` header or `convention` query parameter.
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - An HTTP request from the client.
+    pub fn from_request(req: &HttpRequest) -> Self {
+        let header = req
+            .headers()
+            .get("X-Api-Convention")
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string());
+        let query = serde_urlencoded::from_str::<ConventionQuery>(req.query_string())
+            .ok()
+            .and_then(|query| query.convention);
+
+        match header.or(query) {
+            Some(convention) if convention.eq_ignore_ascii_case("camel") => Self::Camel,
+            _ => Self::Snake,
+        }
+    }
+}
+
+/// Converts snake_case key to camelCase.
+fn to_camel_case(key: &str) -> String {
+    let mut camel_case_key = String::with_capacity(key.len());
+    let mut capitalize_next = false;
+
+    for c in key.chars() {
+        if c == '_' {
+            capitalize_next = !camel_case_key.is_empty();
+        } else if capitalize_next {
+            camel_case_key.extend(c.to_uppercase());
+            capitalize_next = false;
+        } else {
+            camel_case_key.push(c);
+        }
+    }
+
+    camel_case_key
+}
+
+/// Keys of JSON defined by clients or by WebAuthn, which is passed as it is.
+/// Key metadata in `keys` is checksummed by the client, for example.
+const OPAQUE_KEYS: &[&str] = &["credential", "editor_preferences", "keys", "options"];
+
+/// Converts JSON value serialized in the default convention to follow the `convention`.
+///
+/// Keys are converted except in the values of `OPAQUE_KEYS`. Datetimes are already RFC 3339
+/// in any convention, which `datetime_util` serializes.
+///
+/// # Arguments
+///
+/// * `value` - A JSON value serialized with snake_case keys.
+/// * `convention` - A convention to be followed.
+pub fn to_convention(value: Value, convention: Convention) -> Value {
+    if convention == Convention::Snake {
+        return value;
+    }
+
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| {
+                    let value = if OPAQUE_KEYS.contains(&key.as_str()) {
+                        value
+                    } else {
+                        to_convention(value, convention)
+                    };
+                    (to_camel_case(&key), value)
+                })
+                .collect::<Map<String, Value>>(),
+        ),
+        Value::Array(values) => Value::Array(
+            values
+                .into_iter()
+                .map(|value| to_convention(value, convention))
+                .collect(),
+        ),
+        _ => value,
+    }
+}
+
+/// Returns whether the body of the response is JSON.
+fn is_json(response: &ActixServiceResponse<Body>) -> bool {
+    response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map_or(false, |value| value.starts_with("application/json"))
+}
+
+/// Takes the whole body out of the response.
+async fn read_body(response: &mut ActixServiceResponse<Body>) -> Result<Bytes, Error> {
+    let mut body = response.take_body();
+    let mut bytes = BytesMut::new();
+    while let Some(chunk) = body.next().await {
+        bytes.extend_from_slice(&chunk?);
+    }
+    Ok(bytes.freeze())
+}
+
+/// Rewrites JSON body of the response to follow the `convention`.
+///
+/// # Arguments
+///
+/// * `convention` - A convention requested by the client.
+/// * `response` - A response to be rewritten.
+pub async fn apply_convention(
+    convention: Convention,
+    mut response: ActixServiceResponse<Body>,
+) -> Result<ActixServiceResponse<Body>, Error> {
+    if convention == Convention::Snake || !is_json(&response) {
+        return Ok(response);
+    }
+
+    let bytes = read_body(&mut response).await?;
+    let converted_body = match serde_json::from_slice::<Value>(&bytes) {
+        Ok(value) => {
+            serde_json::to_vec(&to_convention(value, convention)).unwrap_or_else(|_| bytes.to_vec())
+        }
+        Err(_) => bytes.to_vec(),
+    };
+
+    Ok(response.map_body(|_, _| ResponseBody::Body(Body::from(converted_body))))
+}
+
+/// Routes HEAD request to the GET route of the same path.
+///
+/// The body of the response is still generated to keep `Content-Length` correct,
+/// and it is dropped by the HTTP codec before it is sent to the client.
+///
+/// # Arguments
+///
+/// * `req` - A request from the client.
+pub fn serve_head_by_get(req: &mut ServiceRequest) {
+    if req.method() == Method::HEAD {
+        req.head_mut().method = Method::GET;
+    }
+}
+
+/// Adds strong `ETag` header derived from the body to successful JSON response of GET request.
+///
+/// Other responses such as streamed files are passed without reading the body.
+///
+/// # Arguments
+///
+/// * `response` - A response to be tagged.
+pub async fn apply_etag(
+    mut response: ActixServiceResponse<Body>,
+) -> Result<ActixServiceResponse<Body>, Error> {
+    if response.request().method() != Method::GET
+        || response.status() != StatusCode::OK
+        || response.headers().contains_key(ETAG)
+        || !is_json(&response)
+    {
+        return Ok(response);
+    }
+
+    let bytes = read_body(&mut response).await?;
+    let digest = Sha256::digest(&bytes);
+    let etag = format!(
+        "\"{}\"",
+        digest
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()
+    );
+
+    if let Ok(etag) = HeaderValue::from_str(&etag) {
+        response.headers_mut().insert(ETAG, etag);
+    }
+
+    Ok(response.map_body(|_, _| ResponseBody::Body(Body::from(bytes))))
+}
+
+/// Returns a resource that responds `204 No Content` with `Allow` header to OPTIONS request.
+///
+/// # Arguments
+///
+/// * `path` - A path pattern of the resource.
+/// * `methods` - Methods allowed on the path. HEAD and OPTIONS are added implicitly.
+pub fn get_options_resource(path: &str, methods: &[Method]) -> Resource {
+    let mut allowed_methods: Vec<&str> = methods.iter().map(Method::as_str).collect();
+    if methods.contains(&Method::GET) {
+        allowed_methods.push(Method::HEAD.as_str());
+    }
+    allowed_methods.push(Method::OPTIONS.as_str());
+    let allow = allowed_methods.join(", ");
+
+    web::resource(path).guard(guard::Options()).to(move || {
+        let allow = allow.clone();
+        async move { HttpResponse::NoContent().header(ALLOW, allow).finish() }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::dev::Service;
+    use actix_web::{test, App};
+    use chrono::{DateTime, NaiveDate};
+    use serde_json::json;
+
+    use super::*;
+    use crate::models::post::PostDTO;
+
+    fn post() -> PostDTO {
+        PostDTO {
+            id: 1,
+            title: String::from("Lorem ipsum"),
+            content: String::from("Lorem ipsum dolor sit amet"),
+            date: String::from("2020-04-12T16:43:03+09:00"),
+            intra_day_order: 0,
+            tags: vec![2],
+            journal_id: 1,
+            status: String::from("published"),
+            created_at: NaiveDate::from_ymd(2020, 4, 13).and_hms(16, 31, 9),
+            updated_at: None,
+            version: 1,
+            is_favorite: false,
+            mood: None,
+            weather: None,
+            latitude: None,
+            longitude: None,
+            place_name: None,
+            word_count: None,
+            is_locked: false,
+            encrypted: false,
+            encryption_scheme: None,
+            nonce: None,
+            key_version: None,
+        }
+    }
+
+    #[test]
+    fn test_snake_convention_is_unchanged() {
+        let value = serde_json::to_value(ServiceResponse::ok(Some(post()))).unwrap();
+        let converted = to_convention(value.clone(), Convention::Snake);
+
+        assert_eq!(converted, value);
+        let post: PostDTO = serde_json::from_value(converted["data"].clone()).unwrap();
+        assert_eq!(
+            post.created_at,
+            NaiveDate::from_ymd(2020, 4, 13).and_hms(16, 31, 9)
+        );
+    }
+
+    #[test]
+    fn test_camel_convention_on_post() {
+        let value = serde_json::to_value(ServiceResponse::ok(Some(post()))).unwrap();
+        let converted = to_convention(value, Convention::Camel);
+
+        assert_eq!(
+            converted,
+            json!({
+                "data": {
+                    "id": 1,
+                    "title": "Lorem ipsum",
+                    "content": "Lorem ipsum dolor sit amet",
+                    "date": "2020-04-12T16:43:03+09:00",
+                    "intraDayOrder": 0,
+                    "tags": [2],
+                    "journalId": 1,
+                    "status": "published",
+                    "createdAt": "2020-04-13T16:31:09Z",
+                    "updatedAt": null,
+                    "version": 1,
+                    "isFavorite": false,
+                    "mood": null,
+                    "weather": null,
+                    "latitude": null,
+                    "longitude": null,
+                    "placeName": null,
+                    "wordCount": null,
+                    "isLocked": false,
+                    "encrypted": false,
+                    "encryptionScheme": null,
+                    "nonce": null,
+                    "keyVersion": null
+                },
+                "error": null
+            })
+        );
+
+        let created_at = converted["data"]["createdAt"].as_str().unwrap();
+        assert_eq!(
+            DateTime::parse_from_rfc3339(created_at)
+                .unwrap()
+                .naive_utc(),
+            post().created_at
+        );
+    }
+
+    #[test]
+    fn test_camel_convention_keeps_data_of_users() {
+        let post = PostDTO {
+            title: String::from("2020-04-12T07:43:03"),
+            ..post()
+        };
+        let value = serde_json::to_value(ServiceResponse::ok(Some(post))).unwrap();
+        let converted = to_convention(value, Convention::Camel);
+
+        assert_eq!(converted["data"]["title"], json!("2020-04-12T07:43:03"));
+        assert_eq!(
+            converted["data"]["createdAt"],
+            json!("2020-04-13T16:31:09Z")
+        );
+
+        let value = json!({
+            "data": {
+                "time_zone": "Asia/Seoul",
+                "editor_preferences": {
+                    "font_size": 14,
+                    "last_opened_at": "2020-04-12T07:43:03"
+                }
+            },
+            "error": null
+        });
+        assert_eq!(
+            to_convention(value, Convention::Camel),
+            json!({
+                "data": {
+                    "timeZone": "Asia/Seoul",
+                    "editorPreferences": {
+                        "font_size": 14,
+                        "last_opened_at": "2020-04-12T07:43:03"
+                    }
+                },
+                "error": null
+            })
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_convention_is_not_applied_to_key_metadata() {
+        let mut app = test::init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    let response = srv.call(req);
+                    async move { apply_convention(Convention::Camel, response.await?).await }
+                })
+                .route(
+                    "/users/{id}/key-metadata",
+                    web::get().to(|| async {
+                        get_ok_response(json!({
+                            "keys": [{ "key_id": "k1", "created_at": "2020-04-12T07:43:03" }],
+                            "checksum": "a1b2c3"
+                        }))
+                    }),
+                ),
+        )
+        .await;
+
+        let response = test::call_service(
+            &mut app,
+            test::TestRequest::get()
+                .uri("/users/1/key-metadata")
+                .to_request(),
+        )
+        .await;
+        let body: Value = test::read_body_json(response).await;
+
+        assert_eq!(
+            body["data"]["keys"][0],
+            json!({ "key_id": "k1", "created_at": "2020-04-12T07:43:03" })
+        );
+    }
+
+    #[test]
+    fn test_camel_convention_on_meta() {
+        let service_response: ServiceResponse<Vec<PostDTO>> = serde_json::from_value(json!({
+            "data": [],
+            "meta": { "total_count": 0, "page": 1, "per_page": 20 },
+            "error": null
+        }))
+        .unwrap();
+        let value = serde_json::to_value(service_response).unwrap();
+
+        assert_eq!(
+            to_convention(value, Convention::Camel),
+            json!({
+                "data": [],
+                "meta": { "totalCount": 0, "page": 1, "perPage": 20 },
+                "error": null
+            })
+        );
+    }
+
+    #[test]
+    fn test_camel_convention_on_error() {
+        let value = serde_json::to_value(ServiceResponse::<PostDTO>::err(Some(String::from(
+            "unauthorized",
+        ))))
+        .unwrap();
+
+        assert_eq!(
+            to_convention(value, Convention::Camel),
+            json!({ "data": null, "error": "unauthorized" })
+        );
+    }
+
+    #[test]
+    fn test_status_code_is_passed() {
+        let response =
+            get_response_by_status_code(StatusCode::CREATED, ServiceResponse::ok(Some(1)));
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body: Value = match response.body().as_ref() {
+            Some(Body::Bytes(bytes)) => serde_json::from_slice(bytes).unwrap(),
+            _ => panic!("response body is not bytes"),
+        };
+        assert_eq!(body, json!({ "data": 1, "error": null }));
+
+        let response = get_response_by_status_code(
+            StatusCode::METHOD_NOT_ALLOWED,
+            ServiceResponse::<bool>::err(Some(String::from("method not allowed"))),
+        );
+        assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let response = get_response_by_status_code(
+            StatusCode::BAD_GATEWAY,
+            ServiceResponse::<bool>::err(None),
+        );
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn test_conflict_has_current_version() {
+        let service_response = ServiceResponse::<PostDTO> {
+            current_version: Some(4),
+            ..ServiceResponse::err(Some(String::from("conflict with current version `4`")))
+        };
+        let response = get_response_by_status_code(StatusCode::CONFLICT, service_response);
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body: Value = match response.body().as_ref() {
+            Some(Body::Bytes(bytes)) => serde_json::from_slice(bytes).unwrap(),
+            _ => panic!("response body is not bytes"),
+        };
+        assert_eq!(
+            body,
+            json!({
+                "data": null,
+                "error": "conflict with current version `4`",
+                "current_version": 4
+            })
+        );
+    }
+
+    #[test]
+    fn test_too_many_requests_has_retry_after() {
+        let service_response = ServiceResponse::<PostDTO> {
+            retry_after: Some(120),
+            reset_at: Some(1586795589),
+            ..ServiceResponse::err(Some(String::from(
+                "too many attempts, retry after 120 seconds",
+            )))
+        };
+        let response = get_response_by_status_code(StatusCode::TOO_MANY_REQUESTS, service_response);
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get(RETRY_AFTER).unwrap(), "120");
+        let body: Value = match response.body().as_ref() {
+            Some(Body::Bytes(bytes)) => serde_json::from_slice(bytes).unwrap(),
+            _ => panic!("response body is not bytes"),
+        };
+        assert_eq!(
+            body,
+            json!({
+                "data": null,
+                "error": "too many attempts, retry after 120 seconds",
+                "retry_after": 120,
+                "reset_at": 1586795589
+            })
+        );
+    }
+
+    #[test]
+    fn test_with_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert("ratelimit-limit", HeaderValue::from(5));
+        headers.insert("ratelimit-remaining", HeaderValue::from(4));
+        headers.insert("ratelimit-reset", HeaderValue::from(900));
+
+        let response = with_headers(get_ok_response::<bool>(true), &headers);
+
+        assert_eq!(response.headers().get("RateLimit-Limit").unwrap(), "5");
+        assert_eq!(response.headers().get("RateLimit-Remaining").unwrap(), "4");
+        assert_eq!(response.headers().get("RateLimit-Reset").unwrap(), "900");
+
+        // The headers the response already has are kept.
+        let mut remaining_headers = HeaderMap::new();
+        remaining_headers.insert("ratelimit-remaining", HeaderValue::from(3));
+        let response = with_headers(response, &remaining_headers);
+        assert_eq!(response.headers().get("RateLimit-Remaining").unwrap(), "4");
+    }
+
+    #[test]
+    fn test_to_camel_case() {
+        assert_eq!(to_camel_case("user_public_key"), "userPublicKey");
+        assert_eq!(to_camel_case("id"), "id");
+        assert_eq!(to_camel_case("_private"), "private");
+    }
+
+    #[actix_rt::test]
+    async fn test_head_is_served_by_get_route_with_etag() {
+        let mut app = test::init_service(
+            App::new()
+                .wrap_fn(|mut req, srv| {
+                    serve_head_by_get(&mut req);
+                    let response = srv.call(req);
+                    async move { apply_etag(response.await?).await }
+                })
+                .route(
+                    "/posts",
+                    web::get().to(|| async { get_ok_response(vec![1, 2]) }),
+                ),
+        )
+        .await;
+
+        let get_response = test::call_service(
+            &mut app,
+            test::TestRequest::get().uri("/posts").to_request(),
+        )
+        .await;
+        let head_response = test::call_service(
+            &mut app,
+            test::TestRequest::with_uri("/posts")
+                .method(Method::HEAD)
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(head_response.status(), StatusCode::OK);
+        assert!(head_response.headers().contains_key(ETAG));
+        assert_eq!(
+            head_response.headers().get(ETAG),
+            get_response.headers().get(ETAG)
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_etag_is_not_applied_to_file() {
+        let mut app = test::init_service(
+            App::new()
+                .wrap_fn(|req, srv| {
+                    let response = srv.call(req);
+                    async move { apply_etag(response.await?).await }
+                })
+                .route(
+                    "/export",
+                    web::get().to(|| async {
+                        HttpResponse::Ok()
+                            .content_type("application/gzip")
+                            .body(vec![0x1f, 0x8b])
+                    }),
+                ),
+        )
+        .await;
+
+        let response = test::call_service(
+            &mut app,
+            test::TestRequest::get().uri("/export").to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!response.headers().contains_key(ETAG));
+    }
+
+    #[actix_rt::test]
+    async fn test_options_resource() {
+        let mut app = test::init_service(
+            App::new().service(get_options_resource("/posts", &[Method::GET, Method::POST])),
+        )
+        .await;
+
+        let response = test::call_service(
+            &mut app,
+            test::TestRequest::with_uri("/posts")
+                .method(Method::OPTIONS)
+                .to_request(),
+        )
+        .await;
+
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        assert_eq!(
+            response.headers().get(ALLOW).unwrap(),
+            "GET, POST, HEAD, OPTIONS"
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_json_content_type() {
+        let mut app = test::init_service(
+            App::new().app_data(get_json_config()).route(
+                "/posts",
+                web::post()
+                    .to(|args: web::Json<Value>| async move { get_ok_response(args.into_inner()) }),
+            ),
+        )
+        .await;
+
+        let response = test::call_service(
+            &mut app,
+            test::TestRequest::post()
+                .uri("/posts")
+                .header(CONTENT_TYPE, "text/plain")
+                .set_payload(r#"{"title":"Lorem ipsum"}"#)
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNSUPPORTED_MEDIA_TYPE);
+
+        let response = test::call_service(
+            &mut app,
+            test::TestRequest::post()
+                .uri("/posts")
+                .set_json(&json!({ "title": "Lorem ipsum" }))
+                .to_request(),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            "application/json; charset=utf-8"
+        );
+    }
+
+    #[test]
+    fn test_convention_from_request() {
+        let req = test::TestRequest::default()
+            .header("X-Api-Convention", "camel")
+            .to_http_request();
+        assert_eq!(Convention::from_request(&req), Convention::Camel);
+
+        let req = test::TestRequest::with_uri("/posts?convention=camel").to_http_request();
+        assert_eq!(Convention::from_request(&req), Convention::Camel);
+
+        let req = test::TestRequest::with_uri("/posts").to_http_request();
+        assert_eq!(Convention::from_request(&req), Convention::Snake);
+    }
+
+    #[test]
+    fn test_accepts_html() {
+        let headers_of = |accept: &'static str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(ACCEPT, HeaderValue::from_static(accept));
+            headers
+        };
+
+        assert!(accepts_html(&headers_of(
+            "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8"
+        )));
+        assert!(!accepts_html(&headers_of("application/json")));
+        assert!(!accepts_html(&headers_of("*/*")));
+        assert!(!accepts_html(&headers_of(
+            "text/html;q=0, application/json"
+        )));
+        assert!(!accepts_html(&HeaderMap::new()));
+
+        assert!(accepts_csv(&headers_of("text/csv")));
+        assert!(!accepts_csv(&headers_of("text/html,*/*;q=0.8")));
+    }
+}
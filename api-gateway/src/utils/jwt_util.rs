@@ -0,0 +1,170 @@
+use actix_web::HttpRequest;
+use chrono::Utc;
+use http::header::AUTHORIZATION;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+use crate::models::auth::{get_default_user_role, UserSession};
+use crate::models::error::ApiGatewayError;
+
+/// Seconds an access token is valid, after which the client refreshes it by the refresh token.
+pub const ACCESS_TOKEN_TTL_SECONDS: i64 = 900; // 15 min
+
+/// Claims of an access token, which has the user session and the id of the login session.
+#[derive(Debug, Serialize, Deserialize)]
+struct Claims {
+    /// Id of the user.
+    sub: u64,
+    /// Id of the login session, which is checked with the service on each request.
+    sid: String,
+    email: String,
+    name: String,
+    public_key: String,
+    avatar_url: Option<String>,
+    #[serde(default = "get_default_user_role")]
+    role: String,
+    iat: i64,
+    exp: i64,
+}
+
+/// Returns the secret signing access tokens.
+fn get_secret() -> String {
+    env::var("JWT_SECRET").expect("JWT_SECRET not found")
+}
+
+/// Issues an access token of the user session and the login session.
+///
+/// # Arguments
+///
+/// * `user_session` - A session of the user who has signed in
+/// * `session_id` - An id of the login session stored in the service
+pub fn issue_access_token(
+    user_session: &UserSession,
+    session_id: &str,
+) -> Result<String, ApiGatewayError> {
+    issue_access_token_with_secret(user_session, session_id, &get_secret())
+}
+
+fn issue_access_token_with_secret(
+    user_session: &UserSession,
+    session_id: &str,
+    secret: &str,
+) -> Result<String, ApiGatewayError> {
+    let now = Utc::now().timestamp();
+    let claims = Claims {
+        sub: user_session.user_id,
+        sid: session_id.to_string(),
+        email: user_session.user_email.clone(),
+        name: user_session.user_name.clone(),
+        public_key: user_session.user_public_key.clone(),
+        avatar_url: user_session.user_avatar_url.clone(),
+        role: user_session.user_role.clone(),
+        iat: now,
+        exp: now + ACCESS_TOKEN_TTL_SECONDS,
+    };
+
+    encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_bytes()),
+    )
+    .map_err(|_| ApiGatewayError::InternalServerError)
+}
+
+/// Returns the user session and the id of the login session of an access token,
+/// or `None` if it is invalid or expired.
+///
+/// # Arguments
+///
+/// * `access_token` - An access token issued by `issue_access_token`
+pub fn verify_access_token(access_token: &str) -> Option<(UserSession, String)> {
+    verify_access_token_with_secret(access_token, &get_secret())
+}
+
+fn verify_access_token_with_secret(
+    access_token: &str,
+    secret: &str,
+) -> Option<(UserSession, String)> {
+    let claims = decode::<Claims>(
+        access_token,
+        &DecodingKey::from_secret(secret.as_bytes()),
+        &Validation::default(),
+    )
+    .ok()?
+    .claims;
+
+    Some((
+        UserSession {
+            user_id: claims.sub,
+            user_email: claims.email,
+            user_name: claims.name,
+            user_public_key: claims.public_key,
+            user_avatar_url: claims.avatar_url,
+            user_role: claims.role,
+        },
+        claims.sid,
+    ))
+}
+
+/// Returns the access token of `Authorization: Bearer` header, if the request has it.
+///
+/// # Arguments
+///
+/// * `req` - An HTTP request from the client.
+pub fn get_bearer_token(req: &HttpRequest) -> Option<String> {
+    let authorization = req.headers().get(AUTHORIZATION)?.to_str().ok()?;
+    let mut parts = authorization.splitn(2, ' ');
+    match (parts.next(), parts.next()) {
+        (Some(scheme), Some(token)) if scheme.eq_ignore_ascii_case("Bearer") => {
+            Some(token.trim().to_string())
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::test::TestRequest;
+
+    use super::*;
+
+    fn user_session() -> UserSession {
+        UserSession {
+            user_id: 10,
+            user_email: String::from("user@email.com"),
+            user_name: String::from("park"),
+            user_public_key: String::from("d63ee429"),
+            user_avatar_url: None,
+            user_role: String::from("user"),
+        }
+    }
+
+    #[test]
+    fn test_verify_access_token() {
+        let access_token =
+            issue_access_token_with_secret(&user_session(), "a1b2c3", "secret").unwrap();
+
+        let (user_session, session_id) =
+            verify_access_token_with_secret(&access_token, "secret").unwrap();
+        assert_eq!(user_session.user_id, 10);
+        assert_eq!(user_session.user_public_key, "d63ee429");
+        assert_eq!(session_id, "a1b2c3");
+
+        assert!(verify_access_token_with_secret(&access_token, "another secret").is_none());
+        assert!(verify_access_token_with_secret("a1b2c3", "secret").is_none());
+    }
+
+    #[test]
+    fn test_get_bearer_token() {
+        let req = TestRequest::default()
+            .header(AUTHORIZATION, "Bearer a1b2c3")
+            .to_http_request();
+        assert_eq!(get_bearer_token(&req), Some(String::from("a1b2c3")));
+
+        let req = TestRequest::default()
+            .header(AUTHORIZATION, "Basic a1b2c3")
+            .to_http_request();
+        assert_eq!(get_bearer_token(&req), None);
+    }
+}
@@ -1,3 +1,9 @@
+use rustls::internal::pemfile::{certs, pkcs8_private_keys};
+use rustls::{NoClientAuth, ServerConfig};
+use std::env;
+use std::fs::File;
+use std::io::BufReader;
+
 #[derive(PartialEq)]
 pub enum ENV {
     LOCAL,
@@ -27,3 +33,30 @@ impl MetaInfo {
         self.env == ENV::PRODUCTION
     }
 }
+
+/// Loads TLS certificate chain and private key from `TLS_CERT_FILE_PATH` and `TLS_KEY_FILE_PATH`.
+pub fn get_tls_config() -> Result<ServerConfig, String> {
+    let cert_file_path =
+        env::var("TLS_CERT_FILE_PATH").map_err(|_| "TLS_CERT_FILE_PATH not found")?;
+    let key_file_path = env::var("TLS_KEY_FILE_PATH").map_err(|_| "TLS_KEY_FILE_PATH not found")?;
+
+    let cert_file = &mut BufReader::new(
+        File::open(&cert_file_path)
+            .map_err(|error| format!("Failed to open {}: {}", cert_file_path, error))?,
+    );
+    let key_file = &mut BufReader::new(
+        File::open(&key_file_path)
+            .map_err(|error| format!("Failed to open {}: {}", key_file_path, error))?,
+    );
+    let cert_chain = certs(cert_file).map_err(|_| "Failed to parse TLS certificate")?;
+    let mut keys = pkcs8_private_keys(key_file).map_err(|_| "Failed to parse TLS private key")?;
+    if cert_chain.is_empty() || keys.is_empty() {
+        return Err(String::from("TLS certificate or private key not found"));
+    }
+
+    let mut config = ServerConfig::new(NoClientAuth::new());
+    config
+        .set_single_cert(cert_chain, keys.remove(0))
+        .map_err(|error| format!("Invalid TLS certificate: {}", error))?;
+    Ok(config)
+}
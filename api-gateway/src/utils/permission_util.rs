@@ -0,0 +1,502 @@
+use actix_web::dev::Payload;
+use actix_web::{Error, FromRequest, HttpRequest};
+use futures::future::{FutureExt, LocalBoxFuture};
+use http::header::{HeaderMap, HeaderValue, USER_AGENT};
+use http::StatusCode;
+use std::env;
+use std::marker::PhantomData;
+use std::net::IpAddr;
+
+use crate::models::auth::{Permission, Principal, UserSession};
+use crate::models::error::ApiGatewayError;
+use crate::utils::{http_util, jwt_util, session_util};
+
+/// A permission that a route requires to be accessed.
+pub trait RequiredPermission {
+    const PERMISSION: Permission;
+}
+
+/// Requires a permission to read posts.
+pub struct CanReadPosts;
+
+/// Requires a permission to create, update, and delete posts.
+pub struct CanWritePosts;
+
+/// Requires a permission to manage the user account.
+pub struct CanManageAccount;
+
+/// Requires an administrator permission.
+pub struct CanAdmin;
+
+impl RequiredPermission for CanReadPosts {
+    const PERMISSION: Permission = Permission::ReadPosts;
+}
+
+impl RequiredPermission for CanWritePosts {
+    const PERMISSION: Permission = Permission::WritePosts;
+}
+
+impl RequiredPermission for CanManageAccount {
+    const PERMISSION: Permission = Permission::ManageAccount;
+}
+
+impl RequiredPermission for CanAdmin {
+    const PERMISSION: Permission = Permission::Admin;
+}
+
+/// Authenticated principal who is granted the permission required by `P`.
+///
+/// Routes declare the permission they require by the type parameter:
+///
+/// ```ignore
+/// #[post("/posts")]
+/// pub async fn create_post(auth: Authorized<CanWritePosts>) -> impl Responder { ... }
+/// ```
+///
+/// The principal is authenticated by a personal access token, an access token,
+/// or the session cookie.
+/// The extraction fails with `401 Unauthorized` if the request is not authenticated,
+/// and with `403 Forbidden` if the principal is not granted the permission.
+pub struct Authorized<P: RequiredPermission> {
+    pub principal: Principal,
+    forwarded_headers: HeaderMap,
+    permission: PhantomData<P>,
+}
+
+impl<P: RequiredPermission> Authorized<P> {
+    /// Returns id of the authorized user.
+    pub fn user_id(&self) -> u64 {
+        self.principal.user_session.user_id
+    }
+
+    /// Returns headers describing the client, to be forwarded to the back-end service.
+    pub fn forwarded_headers(&self) -> HeaderMap {
+        self.forwarded_headers.clone()
+    }
+}
+
+impl Authorized<CanAdmin> {
+    /// Returns the forwarded headers with `X-Admin-Id` header, by which the back-end service
+    /// checks the admin again and audits the action.
+    pub fn admin_headers(&self) -> HeaderMap {
+        let mut headers = self.forwarded_headers();
+        headers.insert("X-Admin-Id", HeaderValue::from(self.user_id()));
+        headers
+    }
+}
+
+/// Returns IPs of the proxies in `TRUSTED_PROXIES`, separated by commas.
+fn get_trusted_proxies() -> Vec<IpAddr> {
+    env::var("TRUSTED_PROXIES")
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|ip| ip.trim().parse().ok())
+        .collect()
+}
+
+/// Returns the IP of the client.
+///
+/// It is the IP of the peer, since headers such as `X-Forwarded-For` are chosen by the client.
+/// If the peer is one of `trusted_proxies`, `X-Forwarded-For` header is read from the right,
+/// and the first IP which is not a trusted proxy is the client.
+///
+/// # Arguments
+///
+/// * `req` - An HTTP request from the client.
+/// * `trusted_proxies` - IPs of the proxies in front of the api gateway.
+pub fn get_client_ip(req: &HttpRequest, trusted_proxies: &[IpAddr]) -> Option<IpAddr> {
+    let mut client_ip = req.peer_addr()?.ip();
+    if !trusted_proxies.contains(&client_ip) {
+        return Some(client_ip);
+    }
+
+    let forwarded_ips = req
+        .headers()
+        .get_all("X-Forwarded-For")
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(|ip| ip.trim().parse::<IpAddr>())
+        .collect::<Vec<_>>();
+    for ip in forwarded_ips.into_iter().rev() {
+        match ip {
+            Ok(ip) => {
+                client_ip = ip;
+                if !trusted_proxies.contains(&ip) {
+                    break;
+                }
+            }
+            Err(_) => break,
+        }
+    }
+
+    Some(client_ip)
+}
+
+/// Returns `X-Forwarded-For`, `User-Agent`, and `X-Session-Id` headers of the request.
+///
+/// `X-Forwarded-For` has the single IP of the client, which is found by `get_client_ip`
+/// with the proxies in `TRUSTED_PROXIES`.
+///
+/// If the proxy in front of the api gateway reports the country of the client in the header
+/// named by `GEO_COUNTRY_HEADER` (e.g. `CF-IPCountry`), it is also forwarded in `X-Geo-Country`.
+///
+/// # Arguments
+///
+/// * `req` - An HTTP request from the client.
+/// * `session_id` - An id of the session of the request.
+pub fn get_forwarded_headers(req: &HttpRequest, session_id: &Option<String>) -> HeaderMap {
+    let mut headers = HeaderMap::new();
+
+    if let Some(ip) = get_client_ip(req, &get_trusted_proxies()) {
+        if let Ok(ip) = HeaderValue::from_str(&ip.to_string()) {
+            headers.insert("X-Forwarded-For", ip);
+        }
+    }
+
+    if let Some(user_agent) = req.headers().get(USER_AGENT) {
+        headers.insert(USER_AGENT, user_agent.clone());
+    }
+
+    if let Some(session_id) = session_id {
+        if let Ok(session_id) = HeaderValue::from_str(session_id) {
+            headers.insert("X-Session-Id", session_id);
+        }
+    }
+
+    if let Ok(geo_country_header) = env::var("GEO_COUNTRY_HEADER") {
+        if let Some(country) = req.headers().get(geo_country_header.as_str()) {
+            headers.insert("X-Geo-Country", country.clone());
+        }
+    }
+
+    headers
+}
+
+/// Checks whether the principal is granted the permission.
+///
+/// # Arguments
+///
+/// * `principal` - An authenticated principal, or `None` if the request is not authenticated.
+/// * `permission` - A permission to be required.
+pub fn authorize(
+    principal: Option<Principal>,
+    permission: Permission,
+) -> Result<Principal, ApiGatewayError> {
+    match principal {
+        Some(principal) => {
+            if principal.has_permission(permission) {
+                Ok(principal)
+            } else {
+                Err(ApiGatewayError::MissingPermission)
+            }
+        }
+        None => Err(ApiGatewayError::Unauthorized),
+    }
+}
+
+/// Checks whether the principal of a session verified by the service is granted the permission.
+///
+/// # Arguments
+///
+/// * `verified` - A user session verified by `session_util::verify_session`, or its error.
+/// * `permission` - A permission to be required.
+pub fn authorize_verified_session(
+    verified: Result<UserSession, ApiGatewayError>,
+    permission: Permission,
+) -> Result<Principal, ApiGatewayError> {
+    match verified {
+        Ok(user_session) => authorize(Some(Principal::from_session(user_session)), permission),
+        Err(ApiGatewayError::Unauthorized) => authorize(None, permission),
+        Err(error) => Err(error),
+    }
+}
+
+impl<P: RequiredPermission> FromRequest for Authorized<P> {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let personal_access_token = jwt_util::get_bearer_token(req)
+            .filter(|token| session_util::is_personal_access_token(token));
+        let session_id = match personal_access_token {
+            Some(_) => None,
+            None => session_util::get_request_session_id(req),
+        };
+        let forwarded_headers = get_forwarded_headers(req, &session_id);
+        let session_request = session_util::SessionRequest::new(req, &session_id);
+        let req = req.clone();
+
+        async move {
+            let authorized = match personal_access_token {
+                Some(token) => match session_util::verify_personal_access_token(token).await {
+                    Ok(principal) => authorize(Some(principal), P::PERMISSION),
+                    Err(error) => Err(error),
+                },
+                None => {
+                    let verified = session_util::verify_session(session_id, session_request).await;
+                    if let Ok(user_session) = &verified {
+                        session_util::mark_impersonation(&req, user_session);
+                    }
+                    authorize_verified_session(verified, P::PERMISSION)
+                }
+            };
+            match authorized {
+                Ok(principal) => Ok(Authorized {
+                    principal,
+                    forwarded_headers,
+                    permission: PhantomData,
+                }),
+                Err(error) => {
+                    let status_code = match error {
+                        ApiGatewayError::MissingPermission => StatusCode::FORBIDDEN,
+                        ApiGatewayError::Unauthorized => StatusCode::UNAUTHORIZED,
+                        _ => StatusCode::INTERNAL_SERVER_ERROR,
+                    };
+                    Err(http_util::get_extraction_error(status_code, error))
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_session::UserSession as _;
+    use actix_web::test;
+
+    use super::*;
+    use crate::models::auth::Impersonator;
+
+    fn user_session() -> UserSession {
+        UserSession {
+            user_id: 10,
+            user_email: String::from("user@email.com"),
+            user_name: String::from("park"),
+            user_public_key: String::from("d63ee429"),
+            user_avatar_url: None,
+            user_role: String::from("user"),
+            impersonator: None,
+        }
+    }
+
+    #[test]
+    fn test_authorize_session() {
+        let principal = Principal::from_session(user_session());
+
+        assert!(authorize(Some(principal), Permission::WritePosts).is_ok());
+    }
+
+    #[test]
+    fn test_authorize_read_only_principal() {
+        let read_only_principal = || Principal {
+            user_session: user_session(),
+            permissions: vec![Permission::ReadPosts],
+        };
+
+        assert!(authorize(Some(read_only_principal()), Permission::ReadPosts).is_ok());
+        assert!(matches!(
+            authorize(Some(read_only_principal()), Permission::WritePosts),
+            Err(ApiGatewayError::MissingPermission)
+        ));
+    }
+
+    #[test]
+    fn test_authorize_personal_access_token() {
+        let read_principal = Principal::from_personal_access_token(user_session(), "read");
+        let write_principal = Principal::from_personal_access_token(user_session(), "write");
+
+        assert!(matches!(
+            authorize(Some(read_principal), Permission::WritePosts),
+            Err(ApiGatewayError::MissingPermission)
+        ));
+        assert!(authorize(Some(write_principal), Permission::WritePosts).is_ok());
+        assert!(matches!(
+            authorize(
+                Some(Principal::from_personal_access_token(
+                    user_session(),
+                    "write"
+                )),
+                Permission::ManageAccount
+            ),
+            Err(ApiGatewayError::MissingPermission)
+        ));
+    }
+
+    #[test]
+    fn test_authorize_session_without_admin() {
+        let principal = Principal::from_session(user_session());
+
+        assert!(matches!(
+            authorize(Some(principal), Permission::Admin),
+            Err(ApiGatewayError::MissingPermission)
+        ));
+    }
+
+    #[test]
+    fn test_authorize_admin_session() {
+        let mut admin_session = user_session();
+        admin_session.user_role = String::from("admin");
+        let principal = Principal::from_session(admin_session);
+
+        assert!(authorize(Some(principal), Permission::Admin).is_ok());
+    }
+
+    #[test]
+    fn test_authorize_impersonated_session() {
+        let impersonated_session = |allow_writes| {
+            let mut user_session = user_session();
+            user_session.impersonator = Some(Impersonator {
+                admin_id: 1,
+                allow_writes,
+            });
+            user_session
+        };
+
+        let read_only = || Principal::from_session(impersonated_session(false));
+        assert!(authorize(Some(read_only()), Permission::ReadPosts).is_ok());
+        assert!(matches!(
+            authorize(Some(read_only()), Permission::WritePosts),
+            Err(ApiGatewayError::MissingPermission)
+        ));
+
+        let writable = || Principal::from_session(impersonated_session(true));
+        assert!(authorize(Some(writable()), Permission::WritePosts).is_ok());
+        assert!(matches!(
+            authorize(Some(writable()), Permission::ManageAccount),
+            Err(ApiGatewayError::MissingPermission)
+        ));
+
+        // An admin impersonating another admin is refused by the service, but the session
+        // would not be granted to administer anyway.
+        let mut admin_session = impersonated_session(true);
+        admin_session.user_role = String::from("admin");
+        assert!(matches!(
+            authorize(
+                Some(Principal::from_session(admin_session)),
+                Permission::Admin
+            ),
+            Err(ApiGatewayError::MissingPermission)
+        ));
+    }
+
+    #[test]
+    fn test_authorize_anonymous() {
+        assert!(matches!(
+            authorize(None, Permission::ReadPosts),
+            Err(ApiGatewayError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn test_authorize_verified_session() {
+        assert!(authorize_verified_session(Ok(user_session()), Permission::ReadPosts).is_ok());
+        assert!(matches!(
+            authorize_verified_session(Ok(user_session()), Permission::Admin),
+            Err(ApiGatewayError::MissingPermission)
+        ));
+        assert!(matches!(
+            authorize_verified_session(Err(ApiGatewayError::Unauthorized), Permission::ReadPosts),
+            Err(ApiGatewayError::Unauthorized)
+        ));
+        assert!(matches!(
+            authorize_verified_session(
+                Err(ApiGatewayError::InternalServerError),
+                Permission::ReadPosts
+            ),
+            Err(ApiGatewayError::InternalServerError)
+        ));
+    }
+
+    #[test]
+    fn test_get_client_ip() {
+        let proxy: IpAddr = "10.0.0.2".parse().unwrap();
+        let req = test::TestRequest::default()
+            .peer_addr("10.0.0.2:50000".parse().unwrap())
+            .header("X-Forwarded-For", "6.6.6.6, 203.0.113.7, 10.0.0.3")
+            .to_http_request();
+
+        // The header is chosen by the client unless the peer is a trusted proxy.
+        assert_eq!(get_client_ip(&req, &[]), Some(proxy));
+        assert_eq!(
+            get_client_ip(&req, &[proxy]),
+            Some("10.0.0.3".parse().unwrap())
+        );
+        assert_eq!(
+            get_client_ip(&req, &[proxy, "10.0.0.3".parse().unwrap()]),
+            Some("203.0.113.7".parse().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_forwarded_ip_is_the_peer() {
+        let req = test::TestRequest::default()
+            .peer_addr("203.0.113.7:50000".parse().unwrap())
+            .header("X-Forwarded-For", "6.6.6.6")
+            .header("Forwarded", "for=6.6.6.6")
+            .to_http_request();
+
+        let headers = get_forwarded_headers(&req, &None);
+        assert_eq!(headers.get("X-Forwarded-For").unwrap(), "203.0.113.7");
+    }
+
+    #[test]
+    fn test_client_forwarded_for_does_not_change_login_throttle_key() {
+        let forwarded_ip = |forwarded_for: &str| {
+            let req = test::TestRequest::post()
+                .uri("/auth/login")
+                .peer_addr("203.0.113.7:50000".parse().unwrap())
+                .header("X-Forwarded-For", forwarded_for)
+                .to_http_request();
+            get_forwarded_headers(&req, &None)
+                .get("X-Forwarded-For")
+                .cloned()
+        };
+
+        // The service throttles logins per forwarded IP, which rotating the header cannot reset.
+        assert_eq!(forwarded_ip("198.51.100.1"), forwarded_ip("198.51.100.2"));
+        assert_eq!(
+            forwarded_ip("198.51.100.1").unwrap(),
+            HeaderValue::from_static("203.0.113.7")
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_session_without_id() {
+        let req = test::TestRequest::default().to_http_request();
+        let session = req.get_session();
+        session.set("user_id", 10).unwrap();
+        session.set("user_email", "user@email.com").unwrap();
+        session.set("user_name", "park").unwrap();
+        session.set("user_public_key", "d63ee429").unwrap();
+
+        // The session has not been stored in the service, so it is not verified.
+        let result = Authorized::<CanReadPosts>::from_request(&req, &mut Payload::None).await;
+        assert_eq!(
+            result
+                .err()
+                .unwrap()
+                .as_response_error()
+                .error_response()
+                .status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[actix_rt::test]
+    async fn test_extract_unauthenticated() {
+        let req = test::TestRequest::default().to_http_request();
+
+        let result = Authorized::<CanReadPosts>::from_request(&req, &mut Payload::None).await;
+        assert_eq!(
+            result
+                .err()
+                .unwrap()
+                .as_response_error()
+                .error_response()
+                .status(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
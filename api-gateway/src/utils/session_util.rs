@@ -1,95 +1,476 @@
-use actix_session::Session;
+#[cfg(feature = "redis-session")]
+use actix_redis::RedisSession;
+#[cfg(not(feature = "redis-session"))]
+use actix_session::CookieSession;
+use actix_session::{Session, UserSession as _};
+use actix_web::body::Body;
+use actix_web::dev::{Payload, ServiceResponse};
+use actix_web::{Error, FromRequest, HttpMessage, HttpRequest};
+use futures::future::{FutureExt, LocalBoxFuture};
+use http::header::{HeaderMap, HeaderValue};
+use http::StatusCode;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use reqwest::Client;
+use std::env;
+use time::Duration;
 
-use crate::models::auth::UserSession;
+use crate::models::auth::{
+    Principal, ServicePersonalAccessTokenArgs, ServicePersonalAccessTokenSessionDTO,
+    ServiceVerifyLoginSessionArgs, UserSession,
+};
+use crate::models::error::ApiGatewayError;
+use crate::utils::{http_util, jwt_util, permission_util};
 
-/// Sets user session.
+/// Minimum length of `SESSION_SECRET` in bytes, which the cookie middleware requires to derive
+/// the signing key.
+const MIN_SESSION_SECRET_LENGTH: usize = 64;
+
+/// Prefix of personal access tokens issued by the service.
+const PERSONAL_ACCESS_TOKEN_PREFIX: &str = "darim_pat_";
+
+/// Days a session lasts since the user has signed in.
+const SESSION_MAX_AGE_DAYS: i64 = 30;
+
+/// Header of responses to requests made by an admin impersonating a user, whose value is id
+/// of the user.
+pub const IMPERSONATING_HEADER: &str = "X-Impersonating";
+
+/// Id of the user impersonated by an admin in a request, kept in the extensions of the request.
+pub struct Impersonating(pub u64);
+
+/// Returns the secret signing session cookies, which must be shared by every instance.
+fn get_session_secret() -> Vec<u8> {
+    let secret = env::var("SESSION_SECRET").expect("SESSION_SECRET not found");
+    if secret.len() < MIN_SESSION_SECRET_LENGTH {
+        panic!(
+            "SESSION_SECRET must be at least {} bytes",
+            MIN_SESSION_SECRET_LENGTH
+        );
+    }
+    secret.into_bytes()
+}
+
+/// Returns the middleware storing sessions in redis at `SESSION_REDIS_ADDRESS`,
+/// so that instances of the api gateway behind a load balancer share them.
+///
+/// It is enabled by `redis-session` feature.
+#[cfg(feature = "redis-session")]
+pub fn get_session_store() -> RedisSession {
+    let address = env::var("SESSION_REDIS_ADDRESS").expect("SESSION_REDIS_ADDRESS not found");
+    RedisSession::new(address, &get_session_secret())
+        .ttl((SESSION_MAX_AGE_DAYS * 24 * 60 * 60) as u32)
+        .cookie_secure(true)
+        .cookie_http_only(true)
+        .cookie_max_age(Duration::days(SESSION_MAX_AGE_DAYS))
+}
+
+/// Returns the middleware storing sessions in signed cookies, which needs no other storage
+/// for a single instance of the api gateway.
+///
+/// The cookie is signed but not encrypted, so it keeps only opaque ids such as the session id
+/// and the device id. The user of the session is read from the service.
+#[cfg(not(feature = "redis-session"))]
+pub fn get_session_store() -> CookieSession {
+    CookieSession::signed(&get_session_secret())
+        .secure(true)
+        .http_only(true)
+        .max_age_time(Duration::days(SESSION_MAX_AGE_DAYS))
+}
+
+/// Logged-in user of the request.
+///
+/// The user is authenticated by an access token or the session cookie.
+/// The extraction fails with `401 Unauthorized` if the request has no user session,
+/// or the session has been revoked.
+pub struct CurrentUser(pub UserSession);
+
+impl FromRequest for CurrentUser {
+    type Error = Error;
+    type Future = LocalBoxFuture<'static, Result<Self, Self::Error>>;
+    type Config = ();
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let req = req.clone();
+        let session_id = get_request_session_id(&req);
+        let session_request = SessionRequest::new(&req, &session_id);
+
+        async move {
+            match verify_session(session_id, session_request).await {
+                Ok(user_session) => {
+                    mark_impersonation(&req, &user_session);
+                    Ok(CurrentUser(user_session))
+                }
+                Err(ApiGatewayError::Unauthorized) => Err(http_util::get_extraction_error(
+                    StatusCode::UNAUTHORIZED,
+                    ApiGatewayError::Unauthorized,
+                )),
+                Err(error) => Err(http_util::get_extraction_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    error,
+                )),
+            }
+        }
+        .boxed_local()
+    }
+}
+
+/// Returns the session id of the request.
+///
+/// It is read from the access token of `Authorization: Bearer` header if the request has it,
+/// or from the session cookie. An invalid or expired access token has no session id.
 ///
 /// # Arguments
 ///
-/// * `session` - An session object
-/// * `user_id` - A record id of the user account
-/// * `user_email` -  An email of the user account
-/// * `user_name` - A name of the user account
-/// * `user_public_key` - A public key of the user account
-/// * `user_avatar_url` - A avatar image url of the user account
-pub fn set_session(
-    session: &mut Session,
-    user_id: u64,
-    user_email: &str,
-    user_name: &str,
-    user_public_key: &str,
-    user_avatar_url: &Option<String>,
-) -> bool {
-    let is_set_user_id = session.set("user_id", user_id);
-    let is_set_user_email = session.set("user_email", user_email);
-    let is_set_user_name = session.set("user_name", user_name);
-    let is_set_user_public_key = session.set("user_public_key", user_public_key);
+/// * `req` - An HTTP request from the client.
+pub fn get_request_session_id(req: &HttpRequest) -> Option<String> {
+    if let Some(access_token) = jwt_util::get_bearer_token(req) {
+        return jwt_util::verify_access_token(&access_token).map(|(_, session_id)| session_id);
+    }
+
+    get_session_id(&req.get_session())
+}
+
+/// Request made with a session, which the service audits if the session is impersonated.
+pub struct SessionRequest {
+    pub method: String,
+    /// Path of the request, with its query.
+    pub path: String,
+    pub forwarded_headers: HeaderMap,
+}
+
+impl SessionRequest {
+    /// Describes a request from the client made with a session id.
+    ///
+    /// # Arguments
+    ///
+    /// * `req` - An HTTP request from the client.
+    /// * `session_id` - An id of the session of the request.
+    pub fn new(req: &HttpRequest, session_id: &Option<String>) -> Self {
+        let path = match req.uri().path_and_query() {
+            Some(path_and_query) => path_and_query.as_str().to_string(),
+            None => req.path().to_string(),
+        };
+        Self {
+            method: req.method().to_string(),
+            path,
+            forwarded_headers: permission_util::get_forwarded_headers(req, session_id),
+        }
+    }
+}
+
+/// Checks the login session of a session id is still active in the service, and returns
+/// the session of its user.
+///
+/// Sessions are stored in the service when users sign in, so that they can be revoked
+/// by `DELETE /auth/sessions/:id` from another device, and expire after a while.
+/// It fails with `Unauthorized` if there is no session id or its session is not active.
+///
+/// The user is always read from the service, rather than the cookie or the access token,
+/// so that a user revoked from admins loses the admin permission at once.
+///
+/// # Arguments
+///
+/// * `session_id` - An id of the session of the request
+/// * `session_request` - The request made with the session, which the service audits if
+/// an admin is impersonating the user
+pub async fn verify_session(
+    session_id: Option<String>,
+    session_request: SessionRequest,
+) -> Result<UserSession, ApiGatewayError> {
+    let session_id = match session_id {
+        Some(session_id) => session_id,
+        None => return Err(ApiGatewayError::Unauthorized),
+    };
+
+    let args = ServiceVerifyLoginSessionArgs {
+        session_id,
+        method: Some(session_request.method),
+        path: Some(session_request.path),
+    };
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/sessions/verify"))
+        .headers(session_request.forwarded_headers)
+        .json(&args)
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) if !response.status().is_server_error() => response,
+        _ => return Err(ApiGatewayError::InternalServerError),
+    };
+
+    match http_util::parse_data_from_service_response::<UserSession>(response).await {
+        Ok(Some(user_session)) => Ok(user_session),
+        Ok(None) => Err(ApiGatewayError::Unauthorized),
+        Err(_) => Err(ApiGatewayError::ServiceResponseParsingFailure),
+    }
+}
+
+/// Marks the request as made by an admin impersonating the user of the session, so that
+/// `X-Impersonating` header is added to the response.
+///
+/// # Arguments
+///
+/// * `req` - An HTTP request from the client.
+/// * `user_session` - A user session verified by `verify_session`.
+pub fn mark_impersonation(req: &HttpRequest, user_session: &UserSession) {
+    if user_session.impersonator.is_some() {
+        req.extensions_mut()
+            .insert(Impersonating(user_session.user_id));
+    }
+}
+
+/// Adds `X-Impersonating` header to the response, if the request has been marked by
+/// `mark_impersonation`.
+///
+/// # Arguments
+///
+/// * `response` - A response to the request.
+pub fn apply_impersonating_header(mut response: ServiceResponse<Body>) -> ServiceResponse<Body> {
+    let user_id = response
+        .request()
+        .extensions()
+        .get::<Impersonating>()
+        .map(|impersonating| impersonating.0);
+    if let Some(user_id) = user_id {
+        response
+            .headers_mut()
+            .insert(IMPERSONATING_HEADER, HeaderValue::from(user_id));
+    }
+    response
+}
 
-    let is_set_user_avatar_url = if let Some(user_avatar_url) = user_avatar_url {
-        session.set("user_avatar_url", user_avatar_url)
-    } else {
-        Ok(())
+/// Returns whether a bearer token is a personal access token rather than an access token.
+pub fn is_personal_access_token(token: &str) -> bool {
+    token.starts_with(PERSONAL_ACCESS_TOKEN_PREFIX)
+}
+
+/// Checks a personal access token in the service, and returns the principal of it.
+///
+/// It fails with `Unauthorized` if the token does not exist or has been deleted.
+///
+/// # Arguments
+///
+/// * `token` - A personal access token of the request
+pub async fn verify_personal_access_token(token: String) -> Result<Principal, ApiGatewayError> {
+    let args = ServicePersonalAccessTokenArgs { token };
+    let response = Client::new()
+        .post(&http_util::get_url("/auth/personal-access-tokens/verify"))
+        .json(&args)
+        .send()
+        .await;
+    let response = match response {
+        Ok(response) if !response.status().is_server_error() => response,
+        _ => return Err(ApiGatewayError::InternalServerError),
     };
 
-    !(is_set_user_id.is_err()
-        || is_set_user_email.is_err()
-        || is_set_user_name.is_err()
-        || is_set_user_public_key.is_err()
-        || is_set_user_avatar_url.is_err())
+    match http_util::parse_data_from_service_response::<ServicePersonalAccessTokenSessionDTO>(
+        response,
+    )
+    .await
+    {
+        Ok(Some(ServicePersonalAccessTokenSessionDTO { session, scope })) => {
+            Ok(Principal::from_personal_access_token(session, &scope))
+        }
+        Ok(None) => Err(ApiGatewayError::Unauthorized),
+        Err(_) => Err(ApiGatewayError::ServiceResponseParsingFailure),
+    }
+}
+
+/// Returns a random id identifying a session.
+pub fn generate_session_id() -> String {
+    thread_rng().sample_iter(&Alphanumeric).take(32).collect()
 }
 
-/// Clears session.
+/// Sets a random id identifying the session, and returns it.
 ///
 /// # Arguments
 ///
 /// * `session` - An session object
-pub fn unset_session(session: &mut Session) {
-    session.clear();
+pub fn set_session_id(session: &mut Session) -> Option<String> {
+    let session_id = generate_session_id();
+    session
+        .set("session_id", &session_id)
+        .ok()
+        .map(|_| session_id)
 }
 
-/// Returns user session.
+/// Sets the id identifying the session, of a login session the device has switched to.
 ///
 /// # Arguments
 ///
 /// * `session` - An session object
-pub fn get_session(session: &Session) -> Option<UserSession> {
-    let user_id = if let Ok(id) = session.get::<u64>("user_id") {
-        id?
-    } else {
-        return None;
-    };
+/// * `session_id` - A new session id of the login session
+pub fn switch_session_id(session: &mut Session, session_id: &str) -> bool {
+    session.set("session_id", session_id).is_ok()
+}
 
-    let user_email = if let Ok(email) = session.get::<String>("user_email") {
-        email?
-    } else {
-        return None;
-    };
+/// Returns the id identifying the session.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+pub fn get_session_id(session: &Session) -> Option<String> {
+    session.get::<String>("session_id").ok().flatten()
+}
 
-    let user_name = if let Ok(name) = session.get::<String>("user_name") {
-        name?
-    } else {
-        return None;
-    };
+/// Keeps the session id of an admin impersonating a user, to switch back to it after.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+/// * `session_id` - A session id of the admin
+pub fn set_impersonator_session_id(session: &mut Session, session_id: &str) -> bool {
+    session.set("impersonator_session_id", session_id).is_ok()
+}
 
-    let user_public_key = if let Ok(public_key) = session.get::<String>("user_public_key") {
-        public_key?
-    } else {
-        return None;
-    };
+/// Removes the session id of the admin impersonating a user, and returns it.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+pub fn take_impersonator_session_id(session: &mut Session) -> Option<String> {
+    let session_id = session.get::<String>("impersonator_session_id").ok()?;
+    session.remove("impersonator_session_id");
+    session_id
+}
 
-    let user_avatar_url = if let Ok(avatar_url) = session.get::<String>("user_avatar_url") {
-        avatar_url
-    } else {
-        return None;
-    };
+/// Returns the id identifying the device, setting a random one if it has no one yet.
+///
+/// The id is kept after signing out, so that the device can switch between the sessions
+/// signed in on it.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+pub fn get_or_set_device_id(session: &mut Session) -> Option<String> {
+    if let Some(device_id) = get_device_id(session) {
+        return Some(device_id);
+    }
+
+    let device_id = generate_session_id();
+    session.set("device_id", &device_id).ok().map(|_| device_id)
+}
+
+/// Returns the id identifying the device, if it has signed in before.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+pub fn get_device_id(session: &Session) -> Option<String> {
+    session.get::<String>("device_id").ok().flatten()
+}
+
+/// Sets the label of the session signing in, which is kept until the device signs in,
+/// including by two-factor authentication.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+/// * `label` - A label given by the client, or `None` to remove it
+pub fn set_session_label(session: &mut Session, label: &Option<String>) -> bool {
+    match label {
+        Some(label) => session.set("session_label", label).is_ok(),
+        None => {
+            session.remove("session_label");
+            true
+        }
+    }
+}
+
+/// Removes the label of the session signing in, and returns it.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+pub fn take_session_label(session: &mut Session) -> Option<String> {
+    let label = session.get::<String>("session_label").ok()?;
+    session.remove("session_label");
+    label
+}
+
+/// Sets a login token waiting for a two-factor code, while the user session is not set.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+/// * `token` - A login token issued by the service
+pub fn set_two_factor_token(session: &mut Session, token: &str) -> bool {
+    session.set("two_factor_token", token).is_ok()
+}
+
+/// Removes the login token waiting for a two-factor code, and returns it.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+pub fn take_two_factor_token(session: &mut Session) -> Option<String> {
+    let token = session.get::<String>("two_factor_token").ok()?;
+    session.remove("two_factor_token");
+    token
+}
+
+/// Sets a token of the ceremony of signing in with a passkey, while the user session is not set.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+/// * `token` - A token of the ceremony issued by the service
+pub fn set_webauthn_token(session: &mut Session, token: &str) -> bool {
+    session.set("webauthn_token", token).is_ok()
+}
+
+/// Removes the token of the ceremony of signing in with a passkey, and returns it.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+pub fn take_webauthn_token(session: &mut Session) -> Option<String> {
+    let token = session.get::<String>("webauthn_token").ok()?;
+    session.remove("webauthn_token");
+    token
+}
+
+/// Sets a random state of signing in with an OAuth provider, and returns it.
+///
+/// The provider redirects back with the state, which must be the one in the session,
+/// so that another site cannot sign in the browser to an account of the site.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+/// * `provider` - A name of the OAuth provider
+pub fn set_oauth_state(session: &mut Session, provider: &str) -> Option<String> {
+    let state: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
+    session
+        .set("oauth_state", format!("{}:{}", provider, state))
+        .ok()
+        .map(|_| state)
+}
+
+/// Removes the state of signing in with an OAuth provider, and returns whether it is `state`.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+/// * `provider` - A name of the OAuth provider
+/// * `state` - A state given back by the provider
+pub fn take_oauth_state(session: &mut Session, provider: &str, state: &str) -> bool {
+    let saved_state = session.get::<String>("oauth_state").ok().flatten();
+    session.remove("oauth_state");
+    saved_state == Some(format!("{}:{}", provider, state))
+}
 
-    Some(UserSession {
-        user_id,
-        user_email,
-        user_name,
-        user_public_key,
-        user_avatar_url,
-    })
+/// Clears session, except the id identifying the device.
+///
+/// # Arguments
+///
+/// * `session` - An session object
+pub fn unset_session(session: &mut Session) {
+    let device_id = get_device_id(session);
+    session.clear();
+    if let Some(device_id) = device_id {
+        let _ = session.set("device_id", device_id);
+    }
 }
 
 #[cfg(test)]
@@ -100,40 +481,42 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_set_session() {
+    fn test_set_session_id() {
         let req = test::TestRequest::default().to_srv_request();
         let mut session = req.get_session();
 
-        let user_id = 10;
-        let user_email = String::from("user@email.com");
-        let user_name = String::from("park");
-        let user_public_key = String::from("d63ee429");
-        let user_avatar_url = String::from("image.jpg");
-
-        let is_set_session = set_session(
-            &mut session,
-            user_id,
-            &user_email,
-            &user_name,
-            &user_public_key,
-            &Some(user_avatar_url.clone()),
-        );
+        let session_id = set_session_id(&mut session);
 
-        assert_eq!(is_set_session, true);
-        assert_eq!(session.get::<u64>("user_id").unwrap(), Some(user_id));
-        assert_eq!(
-            session.get::<String>("user_email").unwrap(),
-            Some(user_email)
-        );
-        assert_eq!(session.get::<String>("user_name").unwrap(), Some(user_name));
-        assert_eq!(
-            session.get::<String>("user_public_key").unwrap(),
-            Some(user_public_key)
-        );
+        assert_eq!(session_id.as_ref().map(|id| id.len()), Some(32));
+        assert_eq!(session.get::<String>("session_id").unwrap(), session_id);
+        assert_eq!(get_session_id(&session), session_id);
+    }
+
+    #[test]
+    fn test_take_two_factor_token() {
+        let req = test::TestRequest::default().to_srv_request();
+        let mut session = req.get_session();
+
+        assert!(set_two_factor_token(&mut session, "a1b2c3"));
+        assert_eq!(get_session_id(&session), None);
         assert_eq!(
-            session.get::<String>("user_avatar_url").unwrap(),
-            Some(user_avatar_url)
+            take_two_factor_token(&mut session),
+            Some(String::from("a1b2c3"))
         );
+        assert_eq!(take_two_factor_token(&mut session), None);
+    }
+
+    #[test]
+    fn test_take_oauth_state() {
+        let req = test::TestRequest::default().to_srv_request();
+        let mut session = req.get_session();
+
+        let state = set_oauth_state(&mut session, "github").unwrap();
+        assert!(!take_oauth_state(&mut session, "google", &state));
+
+        let state = set_oauth_state(&mut session, "github").unwrap();
+        assert!(take_oauth_state(&mut session, "github", &state));
+        assert!(!take_oauth_state(&mut session, "github", &state));
     }
 
     #[test]
@@ -148,37 +531,46 @@ mod tests {
     }
 
     #[test]
-    fn test_get_session() {
+    fn test_unset_session_keeps_device_id() {
         let req = test::TestRequest::default().to_srv_request();
-        let session = req.get_session();
-
-        let user_id = 10;
-        let user_email = "user@email.com";
-        let user_name = "park";
-        let user_public_key = "d63ee429";
-        let user_avatar_url = String::from("image.jpg");
-
-        session.set("user_id", user_id).unwrap();
-        session.set("user_email", user_email).unwrap();
-        session.set("user_name", user_name).unwrap();
-        session.set("user_public_key", user_public_key).unwrap();
-        session
-            .set("user_avatar_url", &Some(user_avatar_url.clone()))
-            .unwrap();
-
-        let user_session = get_session(&session);
-
-        assert!(user_session.is_some());
-        assert_eq!(user_session.as_ref().unwrap().user_id, user_id);
-        assert_eq!(user_session.as_ref().unwrap().user_email, user_email);
-        assert_eq!(user_session.as_ref().unwrap().user_name, user_name);
-        assert_eq!(
-            user_session.as_ref().unwrap().user_public_key,
-            user_public_key
-        );
+        let mut session = req.get_session();
+
+        let device_id = get_or_set_device_id(&mut session);
+        assert_eq!(device_id.as_ref().map(|id| id.len()), Some(32));
+        assert_eq!(get_or_set_device_id(&mut session), device_id);
+
+        session.set("user_id", 10).unwrap();
+        unset_session(&mut session);
+
+        assert_eq!(session.get::<u64>("user_id").unwrap(), None);
+        assert_eq!(get_device_id(&session), device_id);
+    }
+
+    #[test]
+    fn test_take_impersonator_session_id() {
+        let req = test::TestRequest::default().to_srv_request();
+        let mut session = req.get_session();
+
+        assert_eq!(take_impersonator_session_id(&mut session), None);
+        assert!(set_impersonator_session_id(&mut session, "a1b2c3"));
         assert_eq!(
-            user_session.as_ref().unwrap().user_avatar_url,
-            Some(user_avatar_url)
+            take_impersonator_session_id(&mut session),
+            Some(String::from("a1b2c3"))
         );
+        assert_eq!(take_impersonator_session_id(&mut session), None);
+    }
+
+    #[test]
+    fn test_take_session_label() {
+        let req = test::TestRequest::default().to_srv_request();
+        let mut session = req.get_session();
+
+        assert!(set_session_label(&mut session, &Some(String::from("Mom"))));
+        assert_eq!(take_session_label(&mut session), Some(String::from("Mom")));
+        assert_eq!(take_session_label(&mut session), None);
+
+        assert!(set_session_label(&mut session, &Some(String::from("Mom"))));
+        assert!(set_session_label(&mut session, &None));
+        assert_eq!(take_session_label(&mut session), None);
     }
 }
@@ -0,0 +1,159 @@
+use actix_web::body::Body;
+use actix_web::dev::ServiceResponse as ActixServiceResponse;
+use actix_web::{Error, HttpRequest};
+use chrono::Utc;
+use http::StatusCode;
+use std::env;
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+use crate::models::error::{get_api_error_message, ApiGatewayError};
+use crate::utils::http_util;
+
+/// Default time limit of a request.
+const DEFAULT_TIMEOUT_SECONDS: u64 = 10;
+
+/// Time limits of requests.
+///
+/// A request is limited by the override of the longest path prefix matching it,
+/// or by the default limit if no override matches.
+#[derive(Clone, Debug)]
+pub struct RequestTimeout {
+    default: Duration,
+    overrides: Vec<(String, Duration)>,
+}
+
+impl RequestTimeout {
+    /// Creates time limits applying `default` to every request.
+    pub fn new(default: Duration) -> Self {
+        Self {
+            default,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Overrides the time limit of requests whose path starts with `path_prefix`.
+    pub fn with_override(mut self, path_prefix: &str, timeout: Duration) -> Self {
+        self.overrides.push((path_prefix.to_string(), timeout));
+        self
+    }
+
+    /// Reads time limits from `REQUEST_TIMEOUT_SECONDS` and `REQUEST_TIMEOUT_OVERRIDES`.
+    ///
+    /// Overrides are comma-separated pairs of path prefix and seconds,
+    /// e.g. `/posts/export=120,/auth=5`.
+    pub fn from_env() -> Self {
+        let default = env::var("REQUEST_TIMEOUT_SECONDS")
+            .ok()
+            .and_then(|seconds| seconds.parse::<u64>().ok())
+            .unwrap_or(DEFAULT_TIMEOUT_SECONDS);
+        let overrides = env::var("REQUEST_TIMEOUT_OVERRIDES").unwrap_or_default();
+
+        overrides
+            .split(',')
+            .filter_map(|pair| {
+                let mut pair = pair.splitn(2, '=');
+                let path_prefix = pair.next()?.trim();
+                let seconds = pair.next()?.trim().parse::<u64>().ok()?;
+                Some((path_prefix, seconds))
+            })
+            .fold(
+                Self::new(Duration::from_secs(default)),
+                |timeout, (path_prefix, seconds)| {
+                    timeout.with_override(path_prefix, Duration::from_secs(seconds))
+                },
+            )
+    }
+
+    /// Returns the time limit of the path.
+    pub fn get(&self, path: &str) -> Duration {
+        self.overrides
+            .iter()
+            .filter(|(path_prefix, _)| path.starts_with(path_prefix.as_str()))
+            .max_by_key(|(path_prefix, _)| path_prefix.len())
+            .map(|(_, timeout)| *timeout)
+            .unwrap_or(self.default)
+    }
+}
+
+/// Races the response against the time limit, and responds `503 Service Unavailable` on breach.
+///
+/// The pending response is dropped on breach, which also cancels the request to the back-end service.
+///
+/// # Arguments
+///
+/// * `req` - A request from the client.
+/// * `timeout` - A time limit of the request.
+/// * `response` - A response to be limited.
+pub async fn apply_timeout<F>(
+    req: HttpRequest,
+    timeout: Duration,
+    response: F,
+) -> Result<ActixServiceResponse<Body>, Error>
+where
+    F: Future<Output = Result<ActixServiceResponse<Body>, Error>>,
+{
+    let started_at = Instant::now();
+    match actix_rt::time::timeout(timeout, response).await {
+        Ok(response) => response,
+        Err(_) => {
+            println!(
+                "[{}] {} {} timed out after {}ms",
+                Utc::now(),
+                req.method(),
+                req.path(),
+                started_at.elapsed().as_millis()
+            );
+            let message = get_api_error_message(ApiGatewayError::Timeout);
+            let response =
+                http_util::get_err_response::<()>(StatusCode::SERVICE_UNAVAILABLE, &message);
+            Ok(ActixServiceResponse::new(req, response))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::dev::Service;
+    use actix_web::{test, web, App, HttpResponse};
+
+    use super::*;
+
+    #[test]
+    fn test_get_timeout_by_longest_prefix() {
+        let timeout = RequestTimeout::new(Duration::from_secs(10))
+            .with_override("/posts", Duration::from_secs(20))
+            .with_override("/posts/export", Duration::from_secs(120));
+
+        assert_eq!(timeout.get("/users/1"), Duration::from_secs(10));
+        assert_eq!(timeout.get("/posts/1"), Duration::from_secs(20));
+        assert_eq!(timeout.get("/posts/export"), Duration::from_secs(120));
+    }
+
+    #[actix_rt::test]
+    async fn test_timeout_responds_service_unavailable() {
+        let timeout = RequestTimeout::new(Duration::from_millis(10));
+        let mut app = test::init_service(
+            App::new()
+                .wrap_fn(move |req, srv| {
+                    let http_req = req.request().clone();
+                    let limit = timeout.get(req.path());
+                    let response = srv.call(req);
+                    apply_timeout(http_req, limit, response)
+                })
+                .route(
+                    "/slow",
+                    web::get().to(|| async {
+                        actix_rt::time::delay_for(Duration::from_millis(200)).await;
+                        HttpResponse::Ok().finish()
+                    }),
+                ),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/slow").to_request();
+        let response = test::call_service(&mut app, req).await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -14,7 +14,6 @@ interface CreateUserBody {
 interface UpdateUserBody {
   name?: string;
   password?: string;
-  avatar_url?: string;
 }
 
 interface ResetPasswordBody {
@@ -50,13 +49,12 @@ async function createUser(user_public_key: string, token_key: string, token_pin:
   return null;
 }
 
-async function updateUser(userId: string, password?: string, name?: string, avatar?: string): Promise<boolean | null> {
+async function updateUser(userId: string, password?: string, name?: string): Promise<boolean | null> {
   const url = `${serverBaseUrl}/users/${userId}`;
 
   const body: UpdateUserBody = {
     password: password ? SHA3(password, { outputLength: 512 }).toString() : undefined,
     name: name,
-    avatar_url: avatar,
   };
 
   try {
@@ -75,6 +73,38 @@ async function updateUser(userId: string, password?: string, name?: string, avat
   return null;
 }
 
+async function uploadAvatar(userId: string, file: File): Promise<string | null> {
+  const url = `${serverBaseUrl}/users/${userId}/avatar`;
+
+  const body = new FormData();
+  body.append('file', file);
+
+  try {
+    const response = await fetch(url, { method: 'POST', credentials: 'include', body });
+    if (response.ok) {
+      const { data } = await response.json();
+      return data;
+    }
+  } catch (e) {
+    // Falls through to the alert below.
+  }
+
+  const i18n = getI18n({
+    error: {
+      ko: '프로필 사진을 올리지 못했습니다',
+      en: 'Failed to upload the avatar',
+    },
+  });
+
+  alert(i18n.text('error'));
+  return null;
+}
+
+// Uploaded avatars have paths on the server, while old ones have absolute URLs.
+function getAvatarSrc(avatarUrl: string | null): string | null {
+  return avatarUrl?.startsWith('/') ? `${serverBaseUrl}${avatarUrl}` : avatarUrl;
+}
+
 async function resetPassword(email: string, tokenId: string, temporaryPassword: string, newPassword: string): Promise<boolean | null> {
   const url = `${serverBaseUrl}/users/password`;
 
@@ -101,4 +131,4 @@ async function resetPassword(email: string, tokenId: string, temporaryPassword:
   return null;
 }
 
-export { createUser, updateUser, resetPassword };
+export { createUser, updateUser, uploadAvatar, getAvatarSrc, resetPassword };
@@ -3,6 +3,7 @@ import { Link } from 'react-router-dom';
 import styled from 'styled-components';
 
 import { getI18n } from '../utils/i18n';
+import { getAvatarSrc } from '../api/user';
 import { Session } from '../models';
 
 interface Props {
@@ -57,7 +58,7 @@ const Header: React.FC<Props> = ({ session }) => {
     </StyledLink>
     {session && (
       <Link to='/settings'>
-        <UserAvatar src={session.user_avatar_url} alt="Your profile"/>
+        <UserAvatar src={getAvatarSrc(session.user_avatar_url)} alt="Your profile"/>
       </Link>
     )}
   </HeaderContainer>
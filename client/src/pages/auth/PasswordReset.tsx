@@ -51,8 +51,8 @@ const PasswordReset: React.FC = () => {
       en: 'Confirm ↗',
     },
     info: {
-      ko: `새 비밀번호를 만들기 위한 토큰과 임시 비밀번호가 발송되었습니다. 이메일(${email})을 확인해주세요.`,
-      en: `Please check your email (${email}) to reset your password`,
+      ko: `새 비밀번호를 만들기 위한 토큰과 임시 비밀번호가 발송되었습니다. 이메일(${email})을 확인해주세요. 메일이 도착하기까지 몇 분 정도 걸릴 수 있습니다.`,
+      en: `Please check your email (${email}) to reset your password. It may take a few minutes to arrive.`,
     },
   });
 
@@ -69,8 +69,8 @@ const Verification: React.FC<Props> = ({ tokenKey, privateKeyState, publicKeySta
       en: 'Key',
     },
     verificationGuide: {
-      ko: '📧 이메일로 계정을 활성화할 수 있는 인증키가 발송되었습니다. 메일에 포함된 인증키를 복사, 붙여넣기해주세요.',
-      en: '📧 The email containing a key to activate your account is sent. Please copy and paste the key',
+      ko: '📧 이메일로 계정을 활성화할 수 있는 인증키가 발송되었습니다. 메일에 포함된 인증키를 복사, 붙여넣기해주세요. 메일이 도착하기까지 몇 분 정도 걸릴 수 있습니다.',
+      en: '📧 The email containing a key to activate your account is sent. Please copy and paste the key. It may take a few minutes to arrive.',
     },
     termsOfService: {
       ko: '서비스 이용약관',
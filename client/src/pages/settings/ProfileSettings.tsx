@@ -31,7 +31,7 @@ const SaveStatusText = styled.span`
 
 const ProfileSettings: React.FC<Props> = ({ userId, setSession }) => {
   const [newName, setNewName] = useState('');
-  const [newAvatar, setNewAvatar] = useState('');
+  const [newAvatar, setNewAvatar] = useState<File | null>(null);
 
   const [newNameSaveStatus, setNewNameSaveStatus] = useState(SaveStatus.NONE);
   const [newAvatarSaveStatus, setNewAvatarSaveStatus] = useState(SaveStatus.NONE);
@@ -42,8 +42,8 @@ const ProfileSettings: React.FC<Props> = ({ userId, setSession }) => {
       en: 'New name',
     },
     newAvatar: {
-      ko: '새 프로필 사진',
-      en: 'New avatar',
+      ko: 'JPEG, PNG, GIF, WebP (최대 5MB)',
+      en: 'JPEG, PNG, GIF, or WebP (up to 5 MB)',
     },
   });
 
@@ -71,10 +71,14 @@ const ProfileSettings: React.FC<Props> = ({ userId, setSession }) => {
   };
 
   const saveNewAvatar = async () => {
+    if (!newAvatar) {
+      return;
+    }
+
     setNewAvatarSaveStatus(SaveStatus.ONGOING);
-    const result = await userApi.updateUser(userId, undefined, undefined, newAvatar);
+    const result = await userApi.uploadAvatar(userId, newAvatar);
 
-    setNewAvatar('');
+    setNewAvatar(null);
 
     if (result && await refreshSession()) {
       setNewAvatarSaveStatus(SaveStatus.SUCCESS);
@@ -101,10 +105,10 @@ const ProfileSettings: React.FC<Props> = ({ userId, setSession }) => {
       <SectionTitle>{i18n.text('avatar')}</SectionTitle>
       <Section row>
         <FullWidthTextField
-          type='text'
-          placeholder={i18n.text('newAvatar')}
-          value={newAvatar}
-          onChange={({ target: { value } }) => setNewAvatar(value)}
+          type='file'
+          accept='image/jpeg,image/png,image/gif,image/webp'
+          title={i18n.text('newAvatar')}
+          onChange={({ target: { files } }) => setNewAvatar(files?.[0] || null)}
         />
         <Button onClick={() => saveNewAvatar()}>{i18n.text('save')}</Button>
       </Section>
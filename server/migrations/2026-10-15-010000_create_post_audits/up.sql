@@ -0,0 +1,15 @@
+CREATE TABLE post_audits (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    post_id BIGINT(20) UNSIGNED NOT NULL,
+    action VARCHAR(16) NOT NULL,
+    ip VARCHAR(45),
+    user_agent VARCHAR(255),
+    session_id VARCHAR(64),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX ix_post_audits_user_id_created_at (user_id, created_at),
+    INDEX ix_post_audits_post_id (post_id),
+    CONSTRAINT fk_post_audits_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
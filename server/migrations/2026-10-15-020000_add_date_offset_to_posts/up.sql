@@ -0,0 +1 @@
+ALTER TABLE posts ADD COLUMN date_offset INT AFTER date;
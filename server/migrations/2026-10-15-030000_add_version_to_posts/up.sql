@@ -0,0 +1 @@
+ALTER TABLE posts ADD COLUMN version INT UNSIGNED NOT NULL DEFAULT 1;
@@ -0,0 +1,3 @@
+ALTER TABLE users DROP COLUMN telemetry_opt_in;
+
+DROP TABLE telemetry_events;
@@ -0,0 +1,9 @@
+CREATE TABLE telemetry_events (
+    event VARCHAR(64) NOT NULL,
+    date DATE NOT NULL,
+    count BIGINT(20) UNSIGNED NOT NULL DEFAULT 0,
+    PRIMARY KEY (event, date)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
+
+ALTER TABLE users ADD COLUMN telemetry_opt_in BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,8 @@
+CREATE TABLE scheduled_tasks (
+    name VARCHAR(64) NOT NULL,
+    last_run_at DATETIME,
+    last_status VARCHAR(255),
+    locked_until DATETIME,
+    PRIMARY KEY (name)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,15 @@
+CREATE TABLE email_jobs (
+    id BIGINT(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+    recipient VARCHAR(512) NOT NULL,
+    subject VARCHAR(255) NOT NULL,
+    body TEXT NOT NULL,
+    token_key VARCHAR(255),
+    attempts INT UNSIGNED NOT NULL DEFAULT 0,
+    next_attempt_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_error VARCHAR(255),
+    failed_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX email_jobs_next_attempt_at (failed_at, next_attempt_at)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1 @@
+ALTER TABLE posts DROP COLUMN intra_day_order;
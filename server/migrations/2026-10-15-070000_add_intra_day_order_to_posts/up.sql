@@ -0,0 +1 @@
+ALTER TABLE posts ADD COLUMN intra_day_order SMALLINT UNSIGNED NOT NULL DEFAULT 0 AFTER date_offset;
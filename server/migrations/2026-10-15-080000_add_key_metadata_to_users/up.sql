@@ -0,0 +1 @@
+ALTER TABLE users ADD COLUMN key_metadata TEXT;
@@ -0,0 +1,20 @@
+CREATE TABLE tags (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    name TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_tags_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
+
+CREATE TABLE post_tags (
+    post_id BIGINT(20) UNSIGNED NOT NULL,
+    tag_id BIGINT(20) UNSIGNED NOT NULL,
+    PRIMARY KEY (post_id, tag_id),
+    INDEX ix_post_tags_tag_id (tag_id),
+    CONSTRAINT fk_post_tags_post_id FOREIGN KEY (post_id) REFERENCES posts(id),
+    CONSTRAINT fk_post_tags_tag_id FOREIGN KEY (tag_id) REFERENCES tags(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
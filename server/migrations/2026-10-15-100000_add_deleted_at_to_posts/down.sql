@@ -0,0 +1,2 @@
+DROP INDEX ix_posts_deleted_at ON posts;
+ALTER TABLE posts DROP COLUMN deleted_at;
@@ -0,0 +1,2 @@
+ALTER TABLE posts ADD COLUMN deleted_at DATETIME;
+CREATE INDEX ix_posts_deleted_at ON posts (deleted_at);
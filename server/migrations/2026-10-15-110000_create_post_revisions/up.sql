@@ -0,0 +1,15 @@
+CREATE TABLE post_revisions (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    post_id BIGINT(20) UNSIGNED NOT NULL,
+    version INT UNSIGNED NOT NULL,
+    title TEXT NOT NULL,
+    content TEXT NOT NULL,
+    date DATETIME NOT NULL,
+    date_offset INT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    UNIQUE INDEX ux_post_revisions_post_id_version (post_id, version),
+    CONSTRAINT fk_post_revisions_post_id FOREIGN KEY (post_id) REFERENCES posts(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,24 @@
+CREATE TABLE attachment_blobs (
+    hash CHAR(64) NOT NULL,
+    size BIGINT(20) UNSIGNED NOT NULL,
+    mime_type VARCHAR(127) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (hash)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
+
+CREATE TABLE attachments (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    post_id BIGINT(20) UNSIGNED NOT NULL,
+    blob_hash CHAR(64) NOT NULL,
+    filename VARCHAR(255) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX ix_attachments_post_id (post_id),
+    INDEX ix_attachments_blob_hash (blob_hash),
+    CONSTRAINT fk_attachments_user_id FOREIGN KEY (user_id) REFERENCES users(id),
+    CONSTRAINT fk_attachments_post_id FOREIGN KEY (post_id) REFERENCES posts(id),
+    CONSTRAINT fk_attachments_blob_hash FOREIGN KEY (blob_hash) REFERENCES attachment_blobs(hash)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
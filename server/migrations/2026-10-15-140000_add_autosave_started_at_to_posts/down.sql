@@ -0,0 +1 @@
+ALTER TABLE posts DROP COLUMN autosave_started_at;
@@ -0,0 +1 @@
+ALTER TABLE posts ADD COLUMN autosave_started_at DATETIME NULL;
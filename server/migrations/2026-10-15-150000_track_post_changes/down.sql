@@ -0,0 +1,3 @@
+DROP TABLE post_tombstones;
+DROP INDEX ix_posts_user_id_changed_at ON posts;
+ALTER TABLE posts DROP COLUMN changed_at;
@@ -0,0 +1,14 @@
+ALTER TABLE posts ADD COLUMN changed_at DATETIME(6) NOT NULL
+    DEFAULT CURRENT_TIMESTAMP(6) ON UPDATE CURRENT_TIMESTAMP(6);
+UPDATE posts SET changed_at = COALESCE(updated_at, created_at);
+CREATE INDEX ix_posts_user_id_changed_at ON posts (user_id, changed_at);
+
+CREATE TABLE post_tombstones (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    post_id BIGINT(20) UNSIGNED NOT NULL,
+    deleted_at DATETIME(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+    PRIMARY KEY (id),
+    INDEX ix_post_tombstones_user_id_deleted_at (user_id, deleted_at)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
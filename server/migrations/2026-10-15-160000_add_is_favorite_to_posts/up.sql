@@ -0,0 +1 @@
+ALTER TABLE posts ADD COLUMN is_favorite BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1,2 @@
+ALTER TABLE posts DROP COLUMN weather;
+ALTER TABLE posts DROP COLUMN mood;
@@ -0,0 +1,2 @@
+ALTER TABLE posts ADD COLUMN mood TINYINT UNSIGNED NULL;
+ALTER TABLE posts ADD COLUMN weather VARCHAR(16) NULL;
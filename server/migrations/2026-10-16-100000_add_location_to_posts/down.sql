@@ -0,0 +1,3 @@
+ALTER TABLE posts DROP COLUMN place_name;
+ALTER TABLE posts DROP COLUMN longitude;
+ALTER TABLE posts DROP COLUMN latitude;
@@ -0,0 +1,3 @@
+ALTER TABLE posts ADD COLUMN latitude DOUBLE NULL;
+ALTER TABLE posts ADD COLUMN longitude DOUBLE NULL;
+ALTER TABLE posts ADD COLUMN place_name VARCHAR(255) NULL;
@@ -0,0 +1,3 @@
+ALTER TABLE posts DROP FOREIGN KEY fk_posts_journal_id;
+ALTER TABLE posts DROP COLUMN journal_id;
+DROP TABLE journals;
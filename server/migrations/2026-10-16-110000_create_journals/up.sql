@@ -0,0 +1,22 @@
+CREATE TABLE journals (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    name TEXT NOT NULL,
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME,
+    PRIMARY KEY (id),
+    CONSTRAINT fk_journals_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
+
+-- The default journal has no name, which is labeled by clients.
+INSERT INTO journals (user_id, name, is_default) SELECT id, '', TRUE FROM users;
+
+ALTER TABLE posts ADD COLUMN journal_id BIGINT(20) UNSIGNED NULL;
+UPDATE posts
+    INNER JOIN journals ON journals.user_id = posts.user_id AND journals.is_default
+    SET posts.journal_id = journals.id;
+ALTER TABLE posts MODIFY COLUMN journal_id BIGINT(20) UNSIGNED NOT NULL;
+ALTER TABLE posts
+    ADD CONSTRAINT fk_posts_journal_id FOREIGN KEY (journal_id) REFERENCES journals(id);
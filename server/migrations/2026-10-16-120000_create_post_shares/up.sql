@@ -0,0 +1,16 @@
+CREATE TABLE post_shares (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    post_id BIGINT(20) UNSIGNED NOT NULL,
+    -- Tokens are compared case-sensitively, since they are mixed-case.
+    token CHAR(32) CHARACTER SET 'ascii' COLLATE 'ascii_bin' NOT NULL,
+    passphrase VARCHAR(255),
+    expires_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    UNIQUE INDEX ux_post_shares_token (token),
+    UNIQUE INDEX ux_post_shares_post_id (post_id),
+    CONSTRAINT fk_post_shares_user_id FOREIGN KEY (user_id) REFERENCES users(id),
+    CONSTRAINT fk_post_shares_post_id FOREIGN KEY (post_id) REFERENCES posts(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
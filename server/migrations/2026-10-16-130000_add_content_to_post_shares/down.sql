@@ -0,0 +1,2 @@
+ALTER TABLE post_shares DROP COLUMN content;
+ALTER TABLE post_shares DROP COLUMN title;
@@ -0,0 +1,3 @@
+-- Plaintext copies given by the sharer, since the server cannot decrypt posts.
+ALTER TABLE post_shares ADD COLUMN title TEXT NULL;
+ALTER TABLE post_shares ADD COLUMN content TEXT NULL;
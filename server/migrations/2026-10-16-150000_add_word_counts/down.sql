@@ -0,0 +1,3 @@
+ALTER TABLE users DROP COLUMN monthly_word_goal;
+ALTER TABLE users DROP COLUMN daily_word_goal;
+ALTER TABLE posts DROP COLUMN word_count;
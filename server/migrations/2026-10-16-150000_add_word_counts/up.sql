@@ -0,0 +1,3 @@
+ALTER TABLE posts ADD COLUMN word_count INT UNSIGNED NULL;
+ALTER TABLE users ADD COLUMN daily_word_goal INT UNSIGNED NULL;
+ALTER TABLE users ADD COLUMN monthly_word_goal INT UNSIGNED NULL;
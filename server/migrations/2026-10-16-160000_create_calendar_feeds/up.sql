@@ -0,0 +1,10 @@
+CREATE TABLE calendar_feeds (
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    -- Tokens are compared case-sensitively, since they are mixed-case.
+    token CHAR(32) CHARACTER SET 'ascii' COLLATE 'ascii_bin' NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id),
+    UNIQUE INDEX ux_calendar_feeds_token (token),
+    CONSTRAINT fk_calendar_feeds_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
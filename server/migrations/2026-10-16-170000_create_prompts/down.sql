@@ -0,0 +1,2 @@
+DROP TABLE prompt_subscriptions;
+DROP TABLE prompts;
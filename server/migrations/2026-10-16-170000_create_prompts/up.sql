@@ -0,0 +1,28 @@
+CREATE TABLE prompts (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    text VARCHAR(1000) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
+
+CREATE TABLE prompt_subscriptions (
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    -- Tokens are compared case-sensitively, since they are mixed-case.
+    token CHAR(32) CHARACTER SET 'ascii' COLLATE 'ascii_bin' NOT NULL,
+    last_sent_on DATE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id),
+    UNIQUE INDEX ux_prompt_subscriptions_token (token),
+    CONSTRAINT fk_prompt_subscriptions_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
+
+INSERT INTO prompts (text) VALUES
+    ('What made you smile today?'),
+    ('Describe a place where you feel at ease.'),
+    ('What is something you learned this week?'),
+    ('Write about a person you are grateful for, and why.'),
+    ('What would you tell yourself a year ago?'),
+    ('What small thing would make tomorrow better?'),
+    ('Describe a sound, smell or taste from today.');
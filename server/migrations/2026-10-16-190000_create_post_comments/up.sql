@@ -0,0 +1,13 @@
+CREATE TABLE post_comments (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    post_id BIGINT(20) UNSIGNED NOT NULL,
+    -- The writer of the comment, who may not be the writer of the post.
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    content TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX ix_post_comments_post_id (post_id),
+    CONSTRAINT fk_post_comments_post_id FOREIGN KEY (post_id) REFERENCES posts(id),
+    CONSTRAINT fk_post_comments_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
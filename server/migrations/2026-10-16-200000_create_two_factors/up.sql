@@ -0,0 +1,25 @@
+CREATE TABLE two_factors (
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    -- Base32 TOTP secret shared with the authenticator app of the user.
+    secret VARCHAR(64) CHARACTER SET 'ascii' NOT NULL,
+    -- Two-factor authentication is pending until the first code is verified.
+    enabled_at DATETIME,
+    -- The last time step whose code was accepted, so that a code is not used twice.
+    last_used_step BIGINT(20) UNSIGNED,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (user_id),
+    CONSTRAINT fk_two_factors_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
+
+CREATE TABLE two_factor_recovery_codes (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    -- SHA-256 hash of the code in hex. Codes are deleted once used.
+    code_hash CHAR(64) CHARACTER SET 'ascii' NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX ix_two_factor_recovery_codes_user_id (user_id),
+    CONSTRAINT fk_two_factor_recovery_codes_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,15 @@
+CREATE TABLE webauthn_credentials (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    -- SHA-256 hash of the credential id in hex, so that a passkey is registered once.
+    credential_id_hash CHAR(64) CHARACTER SET 'ascii' NOT NULL,
+    -- The credential serialized in JSON, which has the public key and the signature counter.
+    credential TEXT NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at DATETIME,
+    PRIMARY KEY (id),
+    UNIQUE INDEX ux_webauthn_credentials_credential_id_hash (credential_id_hash),
+    INDEX ix_webauthn_credentials_user_id (user_id),
+    CONSTRAINT fk_webauthn_credentials_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,14 @@
+CREATE TABLE oauth_accounts (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    -- Name of the OAuth provider, such as `google` and `github`.
+    provider VARCHAR(32) CHARACTER SET 'ascii' NOT NULL,
+    -- Id of the account in the provider, which does not change even if its email changes.
+    subject VARCHAR(255) NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    UNIQUE INDEX ux_oauth_accounts_provider_subject (provider, subject),
+    INDEX ix_oauth_accounts_user_id (user_id),
+    CONSTRAINT fk_oauth_accounts_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
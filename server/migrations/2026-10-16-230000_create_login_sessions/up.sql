@@ -0,0 +1,15 @@
+CREATE TABLE login_sessions (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    -- SHA-256 hash of the session id in the cookie in hex, so that the table does not leak sessions.
+    session_id_hash CHAR(64) CHARACTER SET 'ascii' NOT NULL,
+    user_agent VARCHAR(512),
+    ip VARCHAR(45),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_seen_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    UNIQUE INDEX ux_login_sessions_session_id_hash (session_id_hash),
+    INDEX ix_login_sessions_user_id (user_id),
+    CONSTRAINT fk_login_sessions_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,2 @@
+DROP INDEX ux_login_sessions_refresh_token_hash ON login_sessions;
+ALTER TABLE login_sessions DROP COLUMN refresh_token_hash;
@@ -0,0 +1,3 @@
+-- SHA-256 hash of the refresh token in hex, if the session has signed in by `POST /auth/token`.
+ALTER TABLE login_sessions ADD COLUMN refresh_token_hash CHAR(64) CHARACTER SET 'ascii';
+CREATE UNIQUE INDEX ux_login_sessions_refresh_token_hash ON login_sessions (refresh_token_hash);
@@ -0,0 +1,16 @@
+CREATE TABLE personal_access_tokens (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    name VARCHAR(100) NOT NULL,
+    -- SHA-256 hash of the token in hex, which is shown to the user only once.
+    token_hash CHAR(64) CHARACTER SET 'ascii' NOT NULL,
+    -- `read` or `write`
+    scope VARCHAR(10) CHARACTER SET 'ascii' NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    last_used_at DATETIME,
+    PRIMARY KEY (id),
+    UNIQUE INDEX ux_personal_access_tokens_token_hash (token_hash),
+    INDEX ix_personal_access_tokens_user_id (user_id),
+    CONSTRAINT fk_personal_access_tokens_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,13 @@
+CREATE TABLE login_history (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    ip VARCHAR(45),
+    -- ISO 3166-1 alpha-2 code of the country of the IP, reported by the proxy in front of the api gateway.
+    country CHAR(2) CHARACTER SET 'ascii',
+    user_agent VARCHAR(512),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX ix_login_history_user_id_created_at (user_id, created_at),
+    CONSTRAINT fk_login_history_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
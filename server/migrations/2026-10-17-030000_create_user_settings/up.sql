@@ -0,0 +1,14 @@
+CREATE TABLE user_settings (
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    -- IANA time zone, e.g. `Asia/Seoul`, in which the dates of the user are counted.
+    timezone VARCHAR(64) NOT NULL DEFAULT 'UTC',
+    -- BCP 47 language tag, e.g. `ko-KR`.
+    locale VARCHAR(35) NOT NULL DEFAULT 'en',
+    week_start_day VARCHAR(9) NOT NULL DEFAULT 'monday',
+    editor_preferences TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME,
+    PRIMARY KEY (user_id),
+    CONSTRAINT fk_user_settings_user_id FOREIGN KEY (user_id) REFERENCES users(id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,4 @@
+-- `user` or `admin`. Admins manage accounts by `/admin` APIs.
+ALTER TABLE users ADD COLUMN role VARCHAR(16) CHARACTER SET 'ascii' NOT NULL DEFAULT 'user';
+-- Time the account was suspended by an admin, if it is suspended. A suspended user cannot sign in.
+ALTER TABLE users ADD COLUMN suspended_at DATETIME;
@@ -0,0 +1,16 @@
+CREATE TABLE invites (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    code CHAR(12) CHARACTER SET 'ascii' NOT NULL,
+    -- The admin who minted the code. It is kept after the admin is deleted.
+    created_by BIGINT(20) UNSIGNED,
+    -- The user who signed up with the code, set once the account is created.
+    redeemed_by BIGINT(20) UNSIGNED,
+    -- Set when a signup claims the code, so that a code is never redeemed twice.
+    redeemed_at DATETIME,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    UNIQUE INDEX ux_invites_code (code),
+    CONSTRAINT fk_invites_created_by FOREIGN KEY (created_by) REFERENCES users(id) ON DELETE SET NULL,
+    CONSTRAINT fk_invites_redeemed_by FOREIGN KEY (redeemed_by) REFERENCES users(id) ON DELETE SET NULL
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
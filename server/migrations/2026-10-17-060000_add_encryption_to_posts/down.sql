@@ -0,0 +1,10 @@
+ALTER TABLE post_revisions
+    DROP COLUMN is_encrypted,
+    DROP COLUMN encryption_scheme,
+    DROP COLUMN encryption_nonce,
+    DROP COLUMN key_version;
+ALTER TABLE posts
+    DROP COLUMN is_encrypted,
+    DROP COLUMN encryption_scheme,
+    DROP COLUMN encryption_nonce,
+    DROP COLUMN key_version;
@@ -0,0 +1,12 @@
+-- Titles and contents are stored as the client sent them. Existing posts were encrypted
+-- by the client before the scheme was recorded, so they have no scheme and no key version.
+ALTER TABLE posts
+    ADD COLUMN is_encrypted BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN encryption_scheme VARCHAR(32) CHARACTER SET 'ascii',
+    ADD COLUMN encryption_nonce VARCHAR(64) CHARACTER SET 'ascii',
+    ADD COLUMN key_version INT UNSIGNED;
+ALTER TABLE post_revisions
+    ADD COLUMN is_encrypted BOOLEAN NOT NULL DEFAULT TRUE,
+    ADD COLUMN encryption_scheme VARCHAR(32) CHARACTER SET 'ascii',
+    ADD COLUMN encryption_nonce VARCHAR(64) CHARACTER SET 'ascii',
+    ADD COLUMN key_version INT UNSIGNED;
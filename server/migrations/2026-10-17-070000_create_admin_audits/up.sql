@@ -0,0 +1,15 @@
+CREATE TABLE admin_audits (
+    id BIGINT(20) UNSIGNED AUTO_INCREMENT NOT NULL,
+    -- Admin who took the action, or NULL if it was taken by a command of the server.
+    admin_id BIGINT(20) UNSIGNED,
+    -- User the action was taken on. It is not a foreign key, since entries outlive deleted users.
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    action VARCHAR(32) NOT NULL,
+    ip VARCHAR(45),
+    user_agent VARCHAR(512),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (id),
+    INDEX ix_admin_audits_created_at (created_at),
+    INDEX ix_admin_audits_user_id (user_id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,3 @@
+DROP INDEX ix_login_sessions_device_id_hash ON login_sessions;
+ALTER TABLE login_sessions DROP COLUMN label;
+ALTER TABLE login_sessions DROP COLUMN device_id_hash;
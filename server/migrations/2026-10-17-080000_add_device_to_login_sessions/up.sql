@@ -0,0 +1,6 @@
+-- SHA-256 hash of the device id in the cookie in hex, if the session has signed in with the cookie.
+-- Sessions of the same device can be switched to each other.
+ALTER TABLE login_sessions ADD COLUMN device_id_hash CHAR(64) CHARACTER SET 'ascii';
+-- Label the client has given to the session, such as the name of a family member.
+ALTER TABLE login_sessions ADD COLUMN label VARCHAR(64);
+CREATE INDEX ix_login_sessions_device_id_hash ON login_sessions (device_id_hash);
@@ -0,0 +1,3 @@
+DROP TABLE rotated_refresh_tokens;
+DROP INDEX ix_login_sessions_expires_at ON login_sessions;
+ALTER TABLE login_sessions DROP COLUMN expires_at;
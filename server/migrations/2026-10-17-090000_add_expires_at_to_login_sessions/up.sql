@@ -0,0 +1,17 @@
+-- Time the session expires at, after which it cannot be refreshed or used anymore.
+ALTER TABLE login_sessions ADD COLUMN expires_at DATETIME;
+UPDATE login_sessions SET expires_at = DATE_ADD(created_at, INTERVAL 30 DAY);
+ALTER TABLE login_sessions MODIFY COLUMN expires_at DATETIME NOT NULL;
+CREATE INDEX ix_login_sessions_expires_at ON login_sessions (expires_at);
+
+-- Refresh tokens rotated out of login sessions, so that a leaked one coming back revokes the session.
+-- Rows are kept until the session would have expired.
+CREATE TABLE rotated_refresh_tokens (
+    -- SHA-256 hash of the rotated refresh token in hex.
+    refresh_token_hash CHAR(64) CHARACTER SET 'ascii' NOT NULL,
+    login_session_id BIGINT(20) UNSIGNED NOT NULL,
+    expires_at DATETIME NOT NULL,
+    PRIMARY KEY (refresh_token_hash),
+    INDEX ix_rotated_refresh_tokens_expires_at (expires_at)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
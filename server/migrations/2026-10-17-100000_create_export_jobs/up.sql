@@ -0,0 +1,27 @@
+-- Exports of posts generated in background, whose archives are kept in `EXPORT_ARTIFACT_DIRECTORY`.
+CREATE TABLE export_jobs (
+    id BIGINT(20) UNSIGNED NOT NULL AUTO_INCREMENT,
+    user_id BIGINT(20) UNSIGNED NOT NULL,
+    format VARCHAR(16) NOT NULL,
+    include_trash BOOLEAN NOT NULL DEFAULT FALSE,
+    -- One of `pending`, `running`, `done`, `failed`, and `downloaded`.
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',
+    -- Number of posts written to the archive, out of `total`.
+    progress INT UNSIGNED NOT NULL DEFAULT 0,
+    total INT UNSIGNED NOT NULL DEFAULT 0,
+    -- Size of the archive in bytes, once it is written.
+    size BIGINT(20) UNSIGNED,
+    -- Token of the download URL, which is issued once the archive is written.
+    download_token CHAR(32) CHARACTER SET 'ascii',
+    -- Time the job and its archive are deleted at.
+    expires_at DATETIME NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    finished_at DATETIME,
+    PRIMARY KEY (id),
+    UNIQUE INDEX ux_export_jobs_download_token (download_token),
+    INDEX ix_export_jobs_user_id (user_id),
+    INDEX ix_export_jobs_status (status),
+    INDEX ix_export_jobs_expires_at (expires_at),
+    FOREIGN KEY (user_id) REFERENCES users (id)
+) CHARACTER SET 'utf8mb4'
+  COLLATE 'utf8mb4_general_ci';
@@ -0,0 +1,2 @@
+-- URL put in `List-Unsubscribe` header of emails which can be unsubscribed.
+ALTER TABLE email_jobs ADD COLUMN unsubscribe_url VARCHAR(512) AFTER token_key;
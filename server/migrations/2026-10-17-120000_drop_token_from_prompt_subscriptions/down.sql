@@ -0,0 +1,6 @@
+ALTER TABLE prompt_subscriptions
+    ADD COLUMN token CHAR(32) CHARACTER SET 'ascii' COLLATE 'ascii_bin' AFTER user_id;
+UPDATE prompt_subscriptions SET token = REPLACE(UUID(), '-', '');
+ALTER TABLE prompt_subscriptions
+    MODIFY COLUMN token CHAR(32) CHARACTER SET 'ascii' COLLATE 'ascii_bin' NOT NULL,
+    ADD UNIQUE INDEX ux_prompt_subscriptions_token (token);
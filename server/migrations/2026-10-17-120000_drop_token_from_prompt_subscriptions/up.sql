@@ -0,0 +1,4 @@
+-- Unsubscribe links are signed instead, so that nothing is looked up by them.
+ALTER TABLE prompt_subscriptions
+    DROP INDEX ux_prompt_subscriptions_token,
+    DROP COLUMN token;
@@ -0,0 +1,7 @@
+ALTER TABLE users ADD COLUMN telemetry_opt_in BOOLEAN NOT NULL DEFAULT FALSE AFTER updated_at;
+
+UPDATE users
+INNER JOIN user_settings ON user_settings.user_id = users.id
+SET users.telemetry_opt_in = user_settings.telemetry_opt_in;
+
+ALTER TABLE user_settings DROP COLUMN telemetry_opt_in;
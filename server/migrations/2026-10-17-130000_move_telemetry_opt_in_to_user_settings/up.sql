@@ -0,0 +1,8 @@
+ALTER TABLE user_settings ADD COLUMN telemetry_opt_in BOOLEAN NOT NULL DEFAULT FALSE AFTER editor_preferences;
+
+-- Users who opted in without settings get the default settings.
+INSERT INTO user_settings (user_id, telemetry_opt_in)
+SELECT id, TRUE FROM users WHERE telemetry_opt_in
+ON DUPLICATE KEY UPDATE telemetry_opt_in = TRUE;
+
+ALTER TABLE users DROP COLUMN telemetry_opt_in;
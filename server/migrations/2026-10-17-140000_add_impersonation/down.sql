@@ -0,0 +1,7 @@
+ALTER TABLE admin_audits DROP COLUMN path;
+ALTER TABLE admin_audits DROP COLUMN method;
+
+DELETE FROM login_sessions WHERE impersonator_id IS NOT NULL;
+ALTER TABLE login_sessions DROP FOREIGN KEY fk_login_sessions_impersonator_id;
+ALTER TABLE login_sessions DROP COLUMN allow_writes;
+ALTER TABLE login_sessions DROP COLUMN impersonator_id;
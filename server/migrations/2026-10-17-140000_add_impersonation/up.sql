@@ -0,0 +1,10 @@
+-- Admin impersonating the user of the session, or NULL if the user has signed in by themselves.
+ALTER TABLE login_sessions ADD COLUMN impersonator_id BIGINT(20) UNSIGNED AFTER expires_at;
+-- Whether the admin impersonating the user may write posts, which is ignored unless impersonated.
+ALTER TABLE login_sessions ADD COLUMN allow_writes BOOLEAN NOT NULL DEFAULT FALSE AFTER impersonator_id;
+ALTER TABLE login_sessions ADD CONSTRAINT fk_login_sessions_impersonator_id
+    FOREIGN KEY (impersonator_id) REFERENCES users(id) ON DELETE CASCADE;
+
+-- Request made under impersonation, which is recorded with the admin and the user.
+ALTER TABLE admin_audits ADD COLUMN method VARCHAR(10) AFTER action;
+ALTER TABLE admin_audits ADD COLUMN path VARCHAR(255) AFTER method;
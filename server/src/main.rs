@@ -1,5 +1,6 @@
 use actix_web::{get, App, HttpResponse, HttpServer, Responder};
-use std::collections::HashMap;
+use chrono::Duration;
+use serde_json::json;
 use std::env;
 
 #[macro_use]
@@ -10,77 +11,345 @@ extern crate diesel;
 
 /// A data layer that can access the database and define data structures.
 pub mod models {
+    /// Model related to administration.
+    pub mod admin;
+    /// Model related to attachment.
+    pub mod attachment;
     /// Model related to authentication.
     pub mod auth;
+    /// Model related to calendar feed.
+    pub mod calendar_feed;
     /// Model related to Database connection.
     pub mod connection;
+    /// Model related to email job.
+    pub mod email_job;
     /// Model related to error.
     pub mod error;
+    /// Model related to export job.
+    pub mod export_job;
+    /// Model related to invite.
+    pub mod invite;
+    /// Model related to journal.
+    pub mod journal;
+    /// Model related to login attempt.
+    pub mod login_attempt;
+    /// Model related to login history.
+    pub mod login_history;
+    /// Model related to login session.
+    pub mod login_session;
+    /// Model related to account of OAuth provider.
+    pub mod oauth_account;
+    /// Model related to OAuth provider.
+    pub mod oauth_provider;
+    /// Model related to personal access token.
+    pub mod personal_access_token;
     /// Model related to post.
     pub mod post;
+    /// Model related to post audit.
+    pub mod post_audit;
+    /// Model related to post comment.
+    pub mod post_comment;
+    /// Model related to post revision.
+    pub mod post_revision;
+    /// Model related to post share.
+    pub mod post_share;
+    /// Model related to post tombstone.
+    pub mod post_tombstone;
+    /// Model related to writing prompt.
+    pub mod prompt;
+    /// Model related to scheduled task.
+    pub mod scheduled_task;
+    /// Model related to storage of files.
+    pub mod storage;
+    /// Model related to tag.
+    pub mod tag;
+    /// Model related to telemetry.
+    pub mod telemetry;
+    /// Model related to template.
+    pub mod template;
+    /// Model related to two-factor authentication.
+    pub mod two_factor;
     /// Model related to user.
     pub mod user;
     /// Model related to user key.
     pub mod user_key;
+    /// Model related to user settings.
+    pub mod user_settings;
+    /// Model related to WebAuthn credential.
+    pub mod webauthn;
 }
 
 /// A presentation layer that makes API public and passes request/response data to other layers.
 pub mod routes {
+    /// API related to administration.
+    pub mod admin;
+    /// API related to attachment.
+    pub mod attachment;
     /// API related to authentication.
     pub mod auth;
+    /// API related to export.
+    pub mod export;
+    /// API related to import.
+    pub mod import;
+    /// API related to journal.
+    pub mod journal;
     /// API related to post.
     pub mod post;
+    /// API related to post comment.
+    pub mod post_comment;
+    /// API related to post share.
+    pub mod post_share;
+    /// API related to writing prompt.
+    pub mod prompt;
+    /// API related to tag.
+    pub mod tag;
+    /// API related to telemetry.
+    pub mod telemetry;
+    /// API related to template.
+    pub mod template;
+    /// API related to unsubscribe links in emails.
+    pub mod unsubscribe;
     /// API related to user.
     pub mod user;
 }
 
 /// A business layer that processes the transaction.
 pub mod services {
+    /// Service related to administration.
+    pub mod admin;
+    /// Service related to attachment.
+    pub mod attachment;
     /// Service related to authentication.
     pub mod auth;
+    /// Service related to calendar feed.
+    pub mod calendar_feed;
+    /// Service related to email.
+    pub mod email;
+    /// Service related to export.
+    pub mod export;
+    /// Service related to import.
+    pub mod import;
+    /// Service related to invite.
+    pub mod invite;
+    /// Service related to journal.
+    pub mod journal;
+    /// Service related to login history.
+    pub mod login_history;
+    /// Service related to login session.
+    pub mod login_session;
+    /// Service related to login throttling.
+    pub mod login_throttle;
+    /// Service related to OAuth.
+    pub mod oauth;
+    /// Service related to personal access token.
+    pub mod personal_access_token;
     /// Service related to post.
     pub mod post;
+    /// Service related to post audit.
+    pub mod post_audit;
+    /// Service related to post comment.
+    pub mod post_comment;
+    /// Service related to post share.
+    pub mod post_share;
+    /// Service related to writing prompt.
+    pub mod prompt;
+    /// Service related to periodic tasks.
+    pub mod scheduler;
+    /// Service related to tag.
+    pub mod tag;
+    /// Service related to telemetry.
+    pub mod telemetry;
+    /// Service related to template.
+    pub mod template;
+    /// Service related to two-factor authentication.
+    pub mod two_factor;
+    /// Service related to unsubscribe links in emails.
+    pub mod unsubscribe;
     /// Service related to user.
     pub mod user;
+    /// Service related to user settings.
+    pub mod user_settings;
+    /// Service related to WebAuthn.
+    pub mod webauthn;
 }
 
 /// Reusable functions for multiple modules.
 pub mod utils {
+    /// Utilities related to self-test of external dependencies.
+    pub mod check_util;
+    /// Utilities related to the current time.
+    pub mod clock_util;
+    /// Utilities related to CSV.
+    pub mod csv_util;
+    /// Utilities related to datetimes in JSON.
+    pub mod datetime_util;
     /// Utilities related to email.
     pub mod email_util;
+    /// Utilities related to HTML pages.
+    pub mod html_util;
     /// Utilities related to HTTP.
     pub mod http_util;
+    /// Utilities related to images.
+    pub mod image_util;
+    /// Utilities related to warnings in responses.
+    pub mod meta_util;
+    /// Utilities related to pagination.
+    pub mod pagination_util;
     /// Utilities related to password.
     pub mod password_util;
+    /// Utilities related to signing requests to object storage.
+    pub mod signature_util;
+    /// Utilities related to statistics of posts.
+    pub mod stats_util;
+    /// Utilities related to time-based one-time passwords.
+    pub mod totp_util;
+    /// Utilities related to unsubscribe links in emails.
+    pub mod unsubscribe_util;
+    /// Utilities related to public URLs.
+    pub mod url_util;
 }
 
 /// A database schema.
 pub mod schema;
 
+use models::user::UserRole;
+use services::admin::AdminService;
+use services::attachment::AttachmentService;
+use services::email::EmailService;
+use services::export::ExportService;
+use services::login_session::LoginSessionService;
+use services::post::PostService;
+use services::post_audit::PostAuditService;
+use services::prompt::PromptService;
+use services::scheduler::SchedulerService;
+
 /// Health check
 #[get("/")]
 async fn health_check() -> impl Responder {
-    let mut response = HashMap::new();
-    response.insert("version", env!("CARGO_PKG_VERSION"));
-    HttpResponse::Ok().json(response)
+    HttpResponse::Ok().json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "tasks": SchedulerService::new().get_list().ok(),
+        "emails": EmailService::new().get_count().ok(),
+    }))
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     dotenv::dotenv().expect("Failed to read .env file");
 
+    // `darim-server check` tests external dependencies and exits instead of running the server.
+    if env::args().nth(1).as_deref() == Some("check") {
+        std::process::exit(utils::check_util::run());
+    }
+
+    // `darim-server prune-blobs [--dry-run]` deletes or lists files no attachment refers to.
+    if env::args().nth(1).as_deref() == Some("prune-blobs") {
+        let dry_run = env::args().nth(2).as_deref() == Some("--dry-run");
+        let hashes = AttachmentService::new()
+            .prune_blobs(dry_run)
+            .expect("Failed to prune attachment blobs");
+        for hash in &hashes {
+            println!("{}", hash);
+        }
+        println!(
+            "{} {} blobs",
+            if dry_run { "Found" } else { "Deleted" },
+            hashes.len()
+        );
+        return Ok(());
+    }
+
+    // `darim-server backfill-date-offsets` sets the offset of dates of posts written before it was recorded,
+    // in the time zone set by each writer.
+    if env::args().nth(1).as_deref() == Some("backfill-date-offsets") {
+        let count = PostService::new()
+            .backfill_date_offsets()
+            .expect("Failed to backfill date offsets");
+        println!("Backfilled date offsets of {} posts", count);
+        return Ok(());
+    }
+
+    // `darim-server grant-admin <email>` makes the user an admin, who manages accounts by `/admin` APIs.
+    // `revoke-admin` makes the user a regular user again.
+    let role = match env::args().nth(1).as_deref() {
+        Some("grant-admin") => Some(UserRole::Admin),
+        Some("revoke-admin") => Some(UserRole::User),
+        _ => None,
+    };
+    if let Some(role) = role {
+        let email = env::args().nth(2).expect("Email not given");
+        AdminService::new()
+            .set_role(&email, role)
+            .expect("Failed to set the role");
+        println!("{} is now {}", email, role.as_str());
+        return Ok(());
+    }
+
+    utils::url_util::PublicUrl::from_env().expect("Invalid PUBLIC_BASE_URL");
+
     let host = env::var("HOST").expect("HOST not found"); // 0.0.0.0
     let port = env!("PORT"); // 0000
     let address = format!("{}:{}", host, port);
 
     println!("Server running at {}", address);
 
+    // Tasks due at the same tick run in the order of registration.
+    let mut scheduler = SchedulerService::new();
+    scheduler.register("delete_orphaned_attachments", Duration::hours(1), || {
+        AttachmentService::new().delete_orphaned()
+    });
+    scheduler.register("prune_attachment_blobs", Duration::hours(1), || {
+        AttachmentService::new()
+            .prune_blobs(false)
+            .map(|hashes| hashes.len())
+    });
+    scheduler.register("prune_export_jobs", Duration::hours(1), || {
+        ExportService::new().prune_jobs()
+    });
+    scheduler.register("prune_login_sessions", Duration::hours(1), || {
+        LoginSessionService::new().prune()
+    });
+    scheduler.register("prune_post_audits", Duration::hours(1), || {
+        PostAuditService::new().prune()
+    });
+    scheduler.register("prune_post_tombstones", Duration::hours(1), || {
+        PostService::new().prune_tombstones()
+    });
+    scheduler.register("purge_trash", Duration::hours(1), || {
+        PostService::new().purge_trash()
+    });
+    // Archives are written in background, so that a large one does not hold up the other tasks.
+    scheduler.register("run_export_jobs", Duration::minutes(1), || {
+        ExportService::run_soon();
+        Ok(0)
+    });
+    scheduler.register("send_prompt_emails", Duration::hours(1), || {
+        PromptService::new().send_daily_prompts()
+    });
+    scheduler.register("send_emails", Duration::minutes(1), || {
+        EmailService::new().send_due_emails()
+    });
+    scheduler.spawn();
+
     HttpServer::new(|| {
         App::new()
+            .app_data(utils::http_util::get_json_config())
             .service(health_check)
             .configure(routes::post::init_routes)
+            .configure(routes::post_share::init_routes)
+            .configure(routes::post_comment::init_routes)
+            .configure(routes::tag::init_routes)
+            .configure(routes::journal::init_routes)
+            .configure(routes::template::init_routes)
+            .configure(routes::prompt::init_routes)
+            .configure(routes::unsubscribe::init_routes)
             .configure(routes::user::init_routes)
             .configure(routes::auth::init_routes)
+            .configure(routes::telemetry::init_routes)
+            .configure(routes::export::init_routes)
+            .configure(routes::import::init_routes)
+            .configure(routes::attachment::init_routes)
+            .configure(routes::admin::init_routes)
     })
     .bind(address)?
     .run()
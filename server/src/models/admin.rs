@@ -0,0 +1,564 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::sql_types::{Bigint, Date, Datetime, Nullable, Unsigned, Varchar};
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::post_audit::AuditContext;
+use crate::models::user::User;
+use crate::schema::{admin_audits, posts, users::dsl};
+use crate::utils::datetime_util;
+
+/// Maximum length of the path of a request kept in `admin_audits` table.
+const MAX_AUDIT_PATH_LENGTH: usize = 255;
+
+/// Number of accounts or posts created on a date, which is a row of the daily counts.
+#[derive(QueryableByName)]
+struct DateCount {
+    #[sql_type = "Date"]
+    date: NaiveDate,
+    #[sql_type = "Bigint"]
+    count: i64,
+}
+
+/// User DTO of the admin API using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdminUserDTO {
+    pub id: u64,
+    pub name: String,
+    pub email: String,
+    pub role: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub suspended_at: Option<NaiveDateTime>,
+}
+
+/// Number of accounts or posts created on a date.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DailyCountDTO {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+/// Statistics of the instance using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AdminStatsDTO {
+    pub user_count: i64,
+    pub suspended_user_count: i64,
+    pub post_count: i64,
+    /// Signups on each day of the period, in asc order of the dates. Days without signups are omitted.
+    pub signups: Vec<DailyCountDTO>,
+    /// Posts created on each day of the period, in asc order of the dates.
+    /// Days without posts are omitted.
+    pub posts: Vec<DailyCountDTO>,
+}
+
+/// Login session issued to an admin impersonating a user, using between routes layer and
+/// service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ImpersonationDTO {
+    pub user_id: u64,
+    pub allow_writes: bool,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub expires_at: NaiveDateTime,
+}
+
+/// Actions of admins recorded in the admin audit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AdminAuditAction {
+    Suspend,
+    Unsuspend,
+    Delete,
+    GrantAdmin,
+    RevokeAdmin,
+    /// Reading the audit log, of which the user is the one filtered by, or the admin.
+    ReadAudit,
+    /// Exporting the audit log as CSV, of which the user is the same as `ReadAudit`.
+    ExportAudit,
+    Impersonate,
+    StopImpersonation,
+    /// A request made by an admin impersonating the user, with its method and path.
+    ImpersonatedRequest,
+}
+
+impl AdminAuditAction {
+    /// Returns the name of the action stored in `admin_audits` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Suspend => "suspend",
+            Self::Unsuspend => "unsuspend",
+            Self::Delete => "delete",
+            Self::GrantAdmin => "grant_admin",
+            Self::RevokeAdmin => "revoke_admin",
+            Self::ReadAudit => "read_audit",
+            Self::ExportAudit => "export_audit",
+            Self::Impersonate => "impersonate",
+            Self::StopImpersonation => "stop_impersonation",
+            Self::ImpersonatedRequest => "impersonated_request",
+        }
+    }
+}
+
+/// Admin audit DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "admin_audits"]
+struct AdminAuditDAO {
+    admin_id: Option<u64>,
+    user_id: u64,
+    action: String,
+    method: Option<String>,
+    path: Option<String>,
+    ip: Option<String>,
+    user_agent: Option<String>,
+}
+
+/// An entry of the audit log, which is a row of `admin_audits`, `login_history`,
+/// or `post_audits` table.
+#[derive(Clone, Debug, PartialEq, QueryableByName)]
+pub struct AuditEvent {
+    /// `admin`, `auth`, or `post`, which is the table of the entry.
+    #[sql_type = "Varchar"]
+    pub source: String,
+    /// An id of the entry in the table.
+    #[sql_type = "Unsigned<Bigint>"]
+    pub id: u64,
+    #[sql_type = "Unsigned<Bigint>"]
+    pub user_id: u64,
+    /// An id of the admin who took the action, only for `admin` entries.
+    #[sql_type = "Nullable<Unsigned<Bigint>>"]
+    pub admin_id: Option<u64>,
+    /// The action prefixed with the source, like `admin.suspend`, `auth.login`, or `post.update`.
+    #[sql_type = "Varchar"]
+    pub event: String,
+    /// Method and path of a request made under impersonation, like `GET /posts`.
+    #[sql_type = "Nullable<Varchar>"]
+    pub request: Option<String>,
+    #[sql_type = "Nullable<Varchar>"]
+    pub ip: Option<String>,
+    #[sql_type = "Nullable<Varchar>"]
+    pub user_agent: Option<String>,
+    #[sql_type = "Datetime"]
+    pub created_at: NaiveDateTime,
+}
+
+/// Entry of the audit log using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditEventDTO {
+    pub source: String,
+    pub id: u64,
+    pub user_id: u64,
+    pub admin_id: Option<u64>,
+    pub event: String,
+    pub request: Option<String>,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// A page of the audit log using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AuditPageDTO {
+    pub events: Vec<AuditEventDTO>,
+    /// Cursor of the next page given to `cursor` argument, or `None` if it is the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// Conditions of entries of the audit log. Entries meet every condition which is not `None`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AuditFilter {
+    pub user_id: Option<u64>,
+    pub event: Option<String>,
+    /// The first UTC date of the entries.
+    pub from: Option<NaiveDate>,
+    /// The last UTC date of the entries.
+    pub to: Option<NaiveDate>,
+    pub ip: Option<String>,
+}
+
+/// Position in the audit log, after which the entries of the next page are.
+///
+/// Entries are in desc order of `created_at`, `source`, and `id`, which are unique together,
+/// so that an entry written while paging does not shift the next page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct AuditCursor {
+    pub created_at: NaiveDateTime,
+    pub source: String,
+    pub id: u64,
+}
+
+impl AuditCursor {
+    /// Returns the cursor of the position of an entry.
+    pub fn of(event: &AuditEvent) -> Self {
+        Self {
+            created_at: event.created_at,
+            source: event.source.clone(),
+            id: event.id,
+        }
+    }
+
+    /// Parses a cursor formatted by `to_string`, which is `{timestamp}-{source}-{id}`.
+    pub fn parse(cursor: &str) -> Option<Self> {
+        let mut parts = cursor.splitn(3, '-');
+        let timestamp = parts.next()?.parse::<i64>().ok()?;
+        let source = parts.next()?;
+        let id = parts.next()?.parse::<u64>().ok()?;
+        if !["admin", "auth", "post"].contains(&source) {
+            return None;
+        }
+
+        Some(Self {
+            created_at: NaiveDateTime::from_timestamp_opt(timestamp, 0)?,
+            source: source.to_string(),
+            id,
+        })
+    }
+}
+
+impl fmt::Display for AuditCursor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}",
+            self.created_at.timestamp(),
+            self.source,
+            self.id
+        )
+    }
+}
+
+/// Escapes `%`, `_`, and `\` of a keyword to be matched literally by `LIKE`.
+fn escape_like(keyword: &str) -> String {
+    keyword
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// A core data repository for administration of accounts.
+pub struct AdminRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait AdminRepositoryTrait {
+    fn find_users(
+        &self,
+        keyword: &Option<String>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<User>, ServiceError>;
+    fn count_users(&self, keyword: &Option<String>) -> Result<i64, ServiceError>;
+    fn count_suspended_users(&self) -> Result<i64, ServiceError>;
+    fn count_posts(&self) -> Result<i64, ServiceError>;
+    fn count_signups_by_date(
+        &self,
+        from: &NaiveDateTime,
+    ) -> Result<Vec<DailyCountDTO>, ServiceError>;
+    fn count_posts_by_date(&self, from: &NaiveDateTime)
+        -> Result<Vec<DailyCountDTO>, ServiceError>;
+    fn update_suspended_at(
+        &self,
+        id: u64,
+        suspended_at: &Option<NaiveDateTime>,
+    ) -> Result<bool, ServiceError>;
+    fn update_role(&self, email: &str, role: &str) -> Result<bool, ServiceError>;
+    fn create_audit(
+        &self,
+        admin_id: Option<u64>,
+        user_id: u64,
+        action: AdminAuditAction,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError>;
+    fn create_request_audit(
+        &self,
+        admin_id: u64,
+        user_id: u64,
+        method: &str,
+        path: &str,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError>;
+    fn find_audit_events(
+        &self,
+        filter: &AuditFilter,
+        after: &Option<AuditCursor>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, ServiceError>;
+}
+
+impl AdminRepository {
+    /// Creates a new admin repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds users whose names or emails contain `keyword`, or all users if it is `None`,
+    /// the most recent signup first.
+    pub fn find_users(
+        &self,
+        keyword: &Option<String>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<User>, ServiceError> {
+        let mut query = dsl::users.into_boxed();
+        if let Some(keyword) = keyword {
+            let pattern = format!("%{}%", escape_like(keyword));
+            query = query.filter(dsl::name.like(pattern.clone()).or(dsl::email.like(pattern)));
+        }
+
+        let user_list = query
+            .order((dsl::created_at.desc(), dsl::id.desc()))
+            .offset(offset)
+            .limit(limit)
+            .load::<User>(&self.conn);
+
+        match user_list {
+            Ok(user_list) => Ok(user_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts users whose names or emails contain `keyword`, or all users if it is `None`.
+    pub fn count_users(&self, keyword: &Option<String>) -> Result<i64, ServiceError> {
+        let mut query = dsl::users.into_boxed();
+        if let Some(keyword) = keyword {
+            let pattern = format!("%{}%", escape_like(keyword));
+            query = query.filter(dsl::name.like(pattern.clone()).or(dsl::email.like(pattern)));
+        }
+
+        match query.count().get_result::<i64>(&self.conn) {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts suspended users.
+    pub fn count_suspended_users(&self) -> Result<i64, ServiceError> {
+        let count = dsl::users
+            .filter(dsl::suspended_at.is_not_null())
+            .count()
+            .get_result::<i64>(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts posts of all users, including posts in the trash.
+    pub fn count_posts(&self) -> Result<i64, ServiceError> {
+        match posts::table.count().get_result::<i64>(&self.conn) {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts users by the UTC date of the signup since `from`.
+    ///
+    /// Users are counted in a query grouped by the date, which the query builder cannot express.
+    pub fn count_signups_by_date(
+        &self,
+        from: &NaiveDateTime,
+    ) -> Result<Vec<DailyCountDTO>, ServiceError> {
+        self.count_by_date("users", from)
+    }
+
+    /// Counts posts of all users by the UTC date of the creation since `from`.
+    pub fn count_posts_by_date(
+        &self,
+        from: &NaiveDateTime,
+    ) -> Result<Vec<DailyCountDTO>, ServiceError> {
+        self.count_by_date("posts", from)
+    }
+
+    /// Counts rows of `table` by the date of `created_at` since `from`.
+    fn count_by_date(
+        &self,
+        table: &str,
+        from: &NaiveDateTime,
+    ) -> Result<Vec<DailyCountDTO>, ServiceError> {
+        let count_list = diesel::sql_query(format!(
+            "SELECT DATE(created_at) AS date, COUNT(*) AS count FROM {} \
+             WHERE created_at >= ? GROUP BY date ORDER BY date ASC",
+            table,
+        ))
+        .bind::<Datetime, _>(from)
+        .load::<DateCount>(&self.conn);
+
+        match count_list {
+            Ok(count_list) => Ok(count_list
+                .into_iter()
+                .map(|date_count| DailyCountDTO {
+                    date: date_count.date,
+                    count: date_count.count,
+                })
+                .collect()),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Suspends a user at `suspended_at`, or lifts the suspension if it is `None`.
+    pub fn update_suspended_at(
+        &self,
+        id: u64,
+        suspended_at: &Option<NaiveDateTime>,
+    ) -> Result<bool, ServiceError> {
+        let count = diesel::update(dsl::users.find(id))
+            .set(dsl::suspended_at.eq(suspended_at))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) if count > 0 => Ok(true),
+            Ok(_) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Sets the role of the user specified by email.
+    pub fn update_role(&self, email: &str, role: &str) -> Result<bool, ServiceError> {
+        let count = diesel::update(dsl::users.filter(dsl::email.eq(email)))
+            .set(dsl::role.eq(role))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) if count > 0 => Ok(true),
+            Ok(_) => Err(get_service_error(ServiceError::NotFound(email.to_string()))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Appends an entry of an action taken on a user to `admin_audits` table.
+    ///
+    /// `admin_id` is `None` if the action was taken by a command of the server.
+    pub fn create_audit(
+        &self,
+        admin_id: Option<u64>,
+        user_id: u64,
+        action: AdminAuditAction,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let audit_to_create = AdminAuditDAO {
+            admin_id,
+            user_id,
+            action: action.as_str().to_string(),
+            method: None,
+            path: None,
+            ip: context.ip.clone(),
+            user_agent: context.user_agent.clone(),
+        };
+
+        let count = diesel::insert_into(admin_audits::table)
+            .values(audit_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Appends an entry of a request made by an admin impersonating a user
+    /// to `admin_audits` table.
+    pub fn create_request_audit(
+        &self,
+        admin_id: u64,
+        user_id: u64,
+        method: &str,
+        path: &str,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let audit_to_create = AdminAuditDAO {
+            admin_id: Some(admin_id),
+            user_id,
+            action: AdminAuditAction::ImpersonatedRequest.as_str().to_string(),
+            method: Some(method.to_string()),
+            path: Some(path.chars().take(MAX_AUDIT_PATH_LENGTH).collect()),
+            ip: context.ip.clone(),
+            user_agent: context.user_agent.clone(),
+        };
+
+        let count = diesel::insert_into(admin_audits::table)
+            .values(audit_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds entries of the audit log meeting `filter` after the cursor, in desc order.
+    ///
+    /// Admin actions, logins, and post actions are in different tables, which are read in
+    /// a union the query builder cannot express. Conditions which are `None` are bound as `NULL`
+    /// and skipped, so that the query is the same whichever conditions are given.
+    pub fn find_audit_events(
+        &self,
+        filter: &AuditFilter,
+        after: &Option<AuditCursor>,
+        limit: i64,
+    ) -> Result<Vec<AuditEvent>, ServiceError> {
+        let from = filter.from.map(|from| from.and_hms(0, 0, 0));
+        let until = filter
+            .to
+            .map(|to| (to + Duration::days(1)).and_hms(0, 0, 0));
+        let after_created_at = after.as_ref().map(|after| after.created_at);
+        let after_source = after.as_ref().map(|after| after.source.clone());
+        let after_id = after.as_ref().map(|after| after.id);
+
+        let event_list = diesel::sql_query(
+            "SELECT source, id, user_id, admin_id, event, request, ip, user_agent, created_at \
+             FROM ( \
+             SELECT 'admin' AS source, id, user_id, admin_id, CONCAT('admin.', action) AS event, \
+             CONCAT(method, ' ', path) AS request, ip, user_agent, created_at FROM admin_audits \
+             UNION ALL SELECT 'auth', id, user_id, NULL, 'auth.login', NULL, ip, user_agent, \
+             created_at FROM login_history \
+             UNION ALL SELECT 'post', id, user_id, NULL, CONCAT('post.', action), NULL, ip, \
+             user_agent, created_at FROM post_audits \
+             ) AS audit_events \
+             WHERE (? IS NULL OR user_id = ?) \
+             AND (? IS NULL OR event = ?) \
+             AND (? IS NULL OR created_at >= ?) \
+             AND (? IS NULL OR created_at < ?) \
+             AND (? IS NULL OR ip = ?) \
+             AND (? IS NULL OR created_at < ? \
+             OR (created_at = ? AND (source < ? OR (source = ? AND id < ?)))) \
+             ORDER BY created_at DESC, source DESC, id DESC LIMIT ?",
+        )
+        .bind::<Nullable<Unsigned<Bigint>>, _>(filter.user_id)
+        .bind::<Nullable<Unsigned<Bigint>>, _>(filter.user_id)
+        .bind::<Nullable<Varchar>, _>(filter.event.clone())
+        .bind::<Nullable<Varchar>, _>(filter.event.clone())
+        .bind::<Nullable<Datetime>, _>(from)
+        .bind::<Nullable<Datetime>, _>(from)
+        .bind::<Nullable<Datetime>, _>(until)
+        .bind::<Nullable<Datetime>, _>(until)
+        .bind::<Nullable<Varchar>, _>(filter.ip.clone())
+        .bind::<Nullable<Varchar>, _>(filter.ip.clone())
+        .bind::<Nullable<Datetime>, _>(after_created_at)
+        .bind::<Nullable<Datetime>, _>(after_created_at)
+        .bind::<Nullable<Datetime>, _>(after_created_at)
+        .bind::<Nullable<Varchar>, _>(after_source.clone())
+        .bind::<Nullable<Varchar>, _>(after_source)
+        .bind::<Nullable<Unsigned<Bigint>>, _>(after_id)
+        .bind::<Bigint, _>(limit)
+        .load::<AuditEvent>(&self.conn);
+
+        match event_list {
+            Ok(event_list) => Ok(event_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for AdminRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,387 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::{exists, not};
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::storage::Storage;
+use crate::schema::{attachment_blobs, attachments, attachments::dsl, posts};
+use crate::utils::datetime_util;
+
+no_arg_sql_function!(
+    last_insert_id,
+    diesel::sql_types::Unsigned<diesel::sql_types::Bigint>
+);
+
+/// Attachment representing `attachments` table.
+///
+/// The file is stored once per content as a blob named by its SHA-256 hash,
+/// so attachments of the same content share a blob.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct Attachment {
+    pub id: u64,
+    pub user_id: u64,
+    pub post_id: u64,
+    pub blob_hash: String,
+    pub filename: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Attachment blob representing `attachment_blobs` table.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct AttachmentBlob {
+    pub hash: String,
+    pub size: u64,
+    pub mime_type: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Attachment DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct AttachmentDTO {
+    pub id: u64,
+    pub post_id: u64,
+    pub filename: String,
+    pub mime_type: String,
+    pub size: u64,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// File of an attachment to be downloaded.
+///
+/// `data` is `None` if the client already has the file of `hash`.
+pub struct AttachmentFile {
+    pub filename: String,
+    pub mime_type: String,
+    pub hash: String,
+    pub data: Option<Vec<u8>>,
+}
+
+/// Attachment DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "attachments"]
+struct AttachmentDAO {
+    user_id: u64,
+    post_id: u64,
+    blob_hash: String,
+    filename: String,
+}
+
+/// Attachment blob DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "attachment_blobs"]
+struct AttachmentBlobDAO {
+    hash: String,
+    size: u64,
+    mime_type: String,
+}
+
+/// Deletes attachments of posts, which must be done before deleting the posts,
+/// and returns the number of deleted attachments.
+///
+/// Blobs are kept, and the ones no longer referenced are removed by pruning.
+pub fn delete_by_post_ids(conn: &MysqlConnection, post_ids: &[u64]) -> Result<usize, Error> {
+    diesel::delete(dsl::attachments.filter(dsl::post_id.eq_any(post_ids))).execute(conn)
+}
+
+/// Copies attachments of a post to another post, which share the blobs with the originals,
+/// and returns the number of copied attachments.
+pub fn copy_to_post(
+    conn: &MysqlConnection,
+    from_post_id: u64,
+    to_post_id: u64,
+) -> Result<usize, Error> {
+    let attachments_to_create: Vec<AttachmentDAO> = dsl::attachments
+        .filter(dsl::post_id.eq(from_post_id))
+        .order(dsl::id.asc())
+        .load::<Attachment>(conn)?
+        .into_iter()
+        .map(|attachment| AttachmentDAO {
+            user_id: attachment.user_id,
+            post_id: to_post_id,
+            blob_hash: attachment.blob_hash,
+            filename: attachment.filename,
+        })
+        .collect();
+    diesel::insert_into(dsl::attachments)
+        .values(&attachments_to_create)
+        .execute(conn)
+}
+
+/// A core data repository for attachment.
+pub struct AttachmentRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait AttachmentRepositoryTrait {
+    fn find_all(
+        &self,
+        user_id: u64,
+        post_id: u64,
+    ) -> Result<Vec<(Attachment, AttachmentBlob)>, ServiceError>;
+    fn find_all_by_user_id(
+        &self,
+        user_id: u64,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<(Attachment, AttachmentBlob)>, ServiceError>;
+    fn find(
+        &self,
+        user_id: u64,
+        attachment_id: u64,
+    ) -> Result<(Attachment, AttachmentBlob), ServiceError>;
+    fn find_orphaned_blobs(&self) -> Result<Vec<String>, ServiceError>;
+    fn delete_orphaned(&self) -> Result<usize, ServiceError>;
+    fn create(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        filename: &str,
+        hash: &str,
+        size: u64,
+        mime_type: &str,
+    ) -> Result<(u64, bool), ServiceError>;
+    fn delete(&self, user_id: u64, attachment_id: u64) -> Result<String, ServiceError>;
+    fn delete_blob_if_orphaned(
+        &self,
+        hash: &str,
+        storage: &dyn Storage,
+    ) -> Result<bool, ServiceError>;
+}
+
+impl AttachmentRepository {
+    /// Creates a new attachment repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds attachments of a post written by specific user in the order of upload.
+    pub fn find_all(
+        &self,
+        user_id: u64,
+        post_id: u64,
+    ) -> Result<Vec<(Attachment, AttachmentBlob)>, ServiceError> {
+        let attachment_list = dsl::attachments
+            .inner_join(attachment_blobs::table)
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::post_id.eq(post_id))
+            .order(dsl::id.asc())
+            .load::<(Attachment, AttachmentBlob)>(&self.conn);
+
+        match attachment_list {
+            Ok(attachment_list) => Ok(attachment_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds attachments of all posts written by specific user in the order of upload.
+    pub fn find_all_by_user_id(
+        &self,
+        user_id: u64,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<(Attachment, AttachmentBlob)>, ServiceError> {
+        let attachment_list = dsl::attachments
+            .inner_join(attachment_blobs::table)
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::id.asc())
+            .offset(offset)
+            .limit(limit)
+            .load::<(Attachment, AttachmentBlob)>(&self.conn);
+
+        match attachment_list {
+            Ok(attachment_list) => Ok(attachment_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds an attachment of specific user with its blob.
+    pub fn find(
+        &self,
+        user_id: u64,
+        attachment_id: u64,
+    ) -> Result<(Attachment, AttachmentBlob), ServiceError> {
+        let attachment = dsl::attachments
+            .inner_join(attachment_blobs::table)
+            .filter(dsl::id.eq(attachment_id))
+            .filter(dsl::user_id.eq(user_id))
+            .get_result::<(Attachment, AttachmentBlob)>(&self.conn);
+
+        match attachment {
+            Ok(attachment) => Ok(attachment),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    attachment_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Finds hashes of blobs which no attachment refers to.
+    pub fn find_orphaned_blobs(&self) -> Result<Vec<String>, ServiceError> {
+        let references = dsl::attachments.filter(dsl::blob_hash.eq(attachment_blobs::dsl::hash));
+        let hashes = attachment_blobs::dsl::attachment_blobs
+            .select(attachment_blobs::dsl::hash)
+            .filter(not(exists(references)))
+            .load::<String>(&self.conn);
+
+        match hashes {
+            Ok(hashes) => Ok(hashes),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes attachments of posts which no longer exist, and returns the count.
+    ///
+    /// Blobs are kept, and the ones no longer referenced are removed by pruning.
+    pub fn delete_orphaned(&self) -> Result<usize, ServiceError> {
+        let posts = posts::dsl::posts.filter(posts::dsl::id.eq(dsl::post_id));
+        let count = diesel::delete(dsl::attachments.filter(not(exists(posts)))).execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new attachment of a post, and returns id of the created attachment
+    /// and whether the blob has been created for it.
+    ///
+    /// The blob of `hash` is created if there is none, or shared otherwise.
+    /// Posts in the trash cannot have new attachments.
+    pub fn create(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        filename: &str,
+        hash: &str,
+        size: u64,
+        mime_type: &str,
+    ) -> Result<(u64, bool), ServiceError> {
+        let result = self.conn.transaction::<(u64, bool), Error, _>(|| {
+            let owned_post = posts::dsl::posts
+                .find(post_id)
+                .filter(posts::dsl::user_id.eq(user_id))
+                .filter(posts::dsl::deleted_at.is_null());
+            let is_owned = diesel::select(exists(owned_post)).get_result::<bool>(&self.conn)?;
+            if !is_owned {
+                return Err(Error::NotFound);
+            }
+
+            let blob_to_create = AttachmentBlobDAO {
+                hash: hash.to_string(),
+                size,
+                mime_type: mime_type.to_string(),
+            };
+            let created_blob_count = diesel::insert_or_ignore_into(attachment_blobs::table)
+                .values(blob_to_create)
+                .execute(&self.conn)?;
+
+            let attachment_to_create = AttachmentDAO {
+                user_id,
+                post_id,
+                blob_hash: hash.to_string(),
+                filename: filename.to_string(),
+            };
+            diesel::insert_into(dsl::attachments)
+                .values(attachment_to_create)
+                .execute(&self.conn)?;
+            let attachment_id = diesel::select(last_insert_id).get_result::<u64>(&self.conn)?;
+            Ok((attachment_id, created_blob_count > 0))
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    post_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Deletes an attachment of specific user, and returns the hash of its blob.
+    ///
+    /// The blob is kept, since other attachments may share it.
+    pub fn delete(&self, user_id: u64, attachment_id: u64) -> Result<String, ServiceError> {
+        let result = self.conn.transaction::<String, Error, _>(|| {
+            let target_attachment = dsl::attachments
+                .find(attachment_id)
+                .filter(dsl::user_id.eq(user_id));
+            let hash = target_attachment
+                .select(dsl::blob_hash)
+                .get_result::<String>(&self.conn)?;
+            diesel::delete(target_attachment).execute(&self.conn)?;
+            Ok(hash)
+        });
+
+        match result {
+            Ok(hash) => Ok(hash),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    attachment_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Deletes a blob and its file from `storage` if no attachment refers to it,
+    /// and returns whether it has been deleted.
+    ///
+    /// The blob is locked while the file is deleted, so an attachment created at the same time
+    /// either keeps the blob, or waits and creates the blob again after it is deleted.
+    pub fn delete_blob_if_orphaned(
+        &self,
+        hash: &str,
+        storage: &dyn Storage,
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let target_blob = attachment_blobs::dsl::attachment_blobs.find(hash);
+            let is_found = target_blob
+                .select(attachment_blobs::dsl::hash)
+                .for_update()
+                .get_result::<String>(&self.conn)
+                .optional()?
+                .is_some();
+            let is_referred = dsl::attachments
+                .select(dsl::id)
+                .filter(dsl::blob_hash.eq(hash))
+                .for_update()
+                .first::<u64>(&self.conn)
+                .optional()?
+                .is_some();
+            if !is_found || is_referred {
+                return Ok(false);
+            }
+
+            storage
+                .delete(hash)
+                .map_err(|_| Error::RollbackTransaction)?;
+            diesel::delete(target_blob).execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for AttachmentRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -6,6 +6,22 @@ use serde::{Deserialize, Serialize};
 use crate::models::connection;
 use crate::models::error::{get_service_error, ServiceError};
 
+/// Seconds a token is valid after the email containing it has been sent.
+pub const TOKEN_TTL_SECONDS: usize = 180; // 3 min
+
+/// Seconds a token is kept while the email containing it waits to be sent.
+///
+/// It covers every retry of the email, and is shortened to `TOKEN_TTL_SECONDS` once the email is sent.
+pub const UNSENT_TOKEN_TTL_SECONDS: usize = 3600; // 1 hour
+
+/// Seconds a login token is valid, in which the user enters a two-factor code.
+pub const LOGIN_TOKEN_TTL_SECONDS: usize = 300; // 5 min
+
+/// Seconds a link downloading an account export is valid.
+///
+/// It is longer than `TOKEN_TTL_SECONDS`, since the user downloads the archive whenever convenient.
+pub const ACCOUNT_EXPORT_TOKEN_TTL_SECONDS: usize = 86400; // 1 day
+
 /// Session containing information of the logged-in user.
 #[derive(Serialize, Deserialize)]
 pub struct UserSession {
@@ -14,6 +30,19 @@ pub struct UserSession {
     pub user_name: String,
     pub user_public_key: String,
     pub user_avatar_url: Option<String>,
+    /// `user` or `admin`
+    pub user_role: String,
+    /// Admin impersonating the user, if the session has been issued to the admin.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub impersonator: Option<ImpersonatorDTO>,
+}
+
+/// Admin impersonating the user of a session.
+#[derive(Serialize, Deserialize)]
+pub struct ImpersonatorDTO {
+    pub admin_id: u64,
+    /// Whether the admin may write posts. Otherwise, the session can only read them.
+    pub allow_writes: bool,
 }
 
 /// Sign up token that represents data in redis.
@@ -26,6 +55,8 @@ pub struct SignUpToken {
     pub email: String,
     pub password: String,
     pub avatar_url: Option<String>,
+    /// Invite code verified on instances in invite mode, redeemed when the user is created.
+    pub invite_code: Option<String>,
 }
 
 /// A core data repository for token.
@@ -65,9 +96,11 @@ impl SignUpTokenRepository {
     }
 
     /// Creates a new token and returns key.
+    ///
+    /// The token expires `UNSENT_TOKEN_TTL_SECONDS` later, until the email containing it is sent.
     pub fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError> {
         let key: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
-        let ttl_seconds = 180; // 3 min
+        let ttl_seconds = UNSENT_TOKEN_TTL_SECONDS;
 
         let result: Result<bool, RedisError> =
             self.client.set::<&str, &str, _>(&key, &serialized_token);
@@ -95,6 +128,11 @@ pub struct PasswordToken {
     pub password: String,
 }
 
+/// Returns key of the password token of the user.
+pub fn get_password_token_key(user_id: u64) -> String {
+    format!("password_token:{}", user_id)
+}
+
 /// A core data repository for password token.
 pub struct PasswordTokenRepository {
     key: String,
@@ -113,7 +151,7 @@ impl PasswordTokenRepository {
     /// Creates a new token repository.
     pub fn new(user_id: u64) -> Self {
         Self {
-            key: format!("password_token:{}", user_id),
+            key: get_password_token_key(user_id),
             client: connection::connect_redis(),
         }
     }
@@ -127,8 +165,10 @@ impl PasswordTokenRepository {
     }
 
     /// Creates a new token.
+    ///
+    /// The token expires `UNSENT_TOKEN_TTL_SECONDS` later, until the email containing it is sent.
     pub fn save(&mut self, serialized_token: &str) -> Result<bool, ServiceError> {
-        let ttl_seconds = 180; // 3 min
+        let ttl_seconds = UNSENT_TOKEN_TTL_SECONDS;
 
         let result: Result<bool, RedisError> = self
             .client
@@ -150,3 +190,361 @@ impl PasswordTokenRepository {
         }
     }
 }
+
+/// Login token that represents data in redis.
+/// The token is issued when the password of a user with two-factor authentication is verified,
+/// and it is exchanged with a session by a two-factor code.
+#[derive(Serialize, Deserialize)]
+pub struct LoginToken {
+    pub user_id: u64,
+}
+
+/// Result of signing in with email and password.
+///
+/// It has a session if the user has signed in, or a login token
+/// if the user has to enter a two-factor code to finish signing in.
+#[derive(Serialize, Deserialize)]
+pub struct LoginDTO {
+    pub session: Option<UserSession>,
+    pub two_factor_token: Option<String>,
+}
+
+/// Session of a user who uses access tokens, with the refresh token issuing them.
+#[derive(Serialize, Deserialize)]
+pub struct TokenDTO {
+    pub session: UserSession,
+    pub refresh_token: String,
+}
+
+/// Session of a user who uses a personal access token, with the scope of the token.
+#[derive(Serialize, Deserialize)]
+pub struct PersonalAccessTokenSessionDTO {
+    pub session: UserSession,
+    pub scope: String,
+}
+
+/// Returns key of a login token in redis, which is separated from keys of sign up tokens.
+fn get_login_token_key(key: &str) -> String {
+    format!("login_token:{}", key)
+}
+
+/// A core data repository for login token.
+pub struct LoginTokenRepository {
+    client: redis::Connection,
+}
+
+#[automock]
+pub trait LoginTokenRepositoryTrait {
+    fn find(&mut self, key: &str) -> Result<String, ServiceError>;
+    fn delete(&mut self, key: &str) -> Result<bool, ServiceError>;
+    fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError>;
+}
+
+impl LoginTokenRepository {
+    /// Creates a new token repository.
+    pub fn new() -> Self {
+        Self {
+            client: connection::connect_redis(),
+        }
+    }
+
+    /// Finds a token by key.
+    pub fn find(&mut self, key: &str) -> Result<String, ServiceError> {
+        match self
+            .client
+            .get::<&str, Option<String>>(&get_login_token_key(key))
+        {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => Err(get_service_error(ServiceError::Unauthorized)),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a token by key.
+    pub fn delete(&mut self, key: &str) -> Result<bool, ServiceError> {
+        match self.client.del::<&str, _>(&get_login_token_key(key)) {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new token and returns key.
+    ///
+    /// The token expires `LOGIN_TOKEN_TTL_SECONDS` later.
+    pub fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError> {
+        let key: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
+
+        let result: Result<bool, RedisError> = self.client.set_ex::<&str, &str, _>(
+            &get_login_token_key(&key),
+            &serialized_token,
+            LOGIN_TOKEN_TTL_SECONDS,
+        );
+        match result {
+            Ok(_) => Ok(key),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for LoginTokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Magic link token that represents data in redis.
+/// The token is emailed in a link, which signs in the user without the password once.
+#[derive(Serialize, Deserialize)]
+pub struct MagicLinkToken {
+    pub user_id: u64,
+}
+
+/// Returns key of a magic link token in redis, which is separated from keys of sign up tokens.
+pub fn get_magic_link_token_key(key: &str) -> String {
+    format!("magic_link:{}", key)
+}
+
+/// A core data repository for magic link token.
+pub struct MagicLinkTokenRepository {
+    client: redis::Connection,
+}
+
+#[automock]
+pub trait MagicLinkTokenRepositoryTrait {
+    fn find(&mut self, key: &str) -> Result<String, ServiceError>;
+    fn delete(&mut self, key: &str) -> Result<bool, ServiceError>;
+    fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError>;
+}
+
+impl MagicLinkTokenRepository {
+    /// Creates a new token repository.
+    pub fn new() -> Self {
+        Self {
+            client: connection::connect_redis(),
+        }
+    }
+
+    /// Finds a token by key.
+    pub fn find(&mut self, key: &str) -> Result<String, ServiceError> {
+        match self
+            .client
+            .get::<&str, Option<String>>(&get_magic_link_token_key(key))
+        {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => Err(get_service_error(ServiceError::Unauthorized)),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a token by key, and returns whether it existed.
+    pub fn delete(&mut self, key: &str) -> Result<bool, ServiceError> {
+        match self.client.del::<&str, _>(&get_magic_link_token_key(key)) {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new token and returns key.
+    ///
+    /// The token expires `UNSENT_TOKEN_TTL_SECONDS` later, until the email containing it is sent.
+    pub fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError> {
+        let key: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
+
+        let result: Result<bool, RedisError> = self.client.set_ex::<&str, &str, _>(
+            &get_magic_link_token_key(&key),
+            &serialized_token,
+            UNSENT_TOKEN_TTL_SECONDS,
+        );
+        match result {
+            Ok(_) => Ok(key),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for MagicLinkTokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Email change token that represents data in redis.
+/// The new email is kept pending in the token, and the current one is used until it is confirmed.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct EmailChangeToken {
+    pub user_id: u64,
+    pub email: String,
+}
+
+/// Returns key of an email change token in redis, which is separated from keys of sign up tokens.
+pub fn get_email_change_token_key(key: &str) -> String {
+    format!("email_change:{}", key)
+}
+
+/// A core data repository for email change token.
+pub struct EmailChangeTokenRepository {
+    client: redis::Connection,
+}
+
+#[automock]
+pub trait EmailChangeTokenRepositoryTrait {
+    fn find(&mut self, key: &str) -> Result<String, ServiceError>;
+    fn delete(&mut self, key: &str) -> Result<bool, ServiceError>;
+    fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError>;
+}
+
+impl EmailChangeTokenRepository {
+    /// Creates a new token repository.
+    pub fn new() -> Self {
+        Self {
+            client: connection::connect_redis(),
+        }
+    }
+
+    /// Finds a token by key.
+    pub fn find(&mut self, key: &str) -> Result<String, ServiceError> {
+        match self
+            .client
+            .get::<&str, Option<String>>(&get_email_change_token_key(key))
+        {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => Err(get_service_error(ServiceError::NotFound(key.to_string()))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a token by key, and returns whether it existed.
+    pub fn delete(&mut self, key: &str) -> Result<bool, ServiceError> {
+        match self.client.del::<&str, _>(&get_email_change_token_key(key)) {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new token and returns key.
+    ///
+    /// The token expires `UNSENT_TOKEN_TTL_SECONDS` later, until the email containing it is sent.
+    pub fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError> {
+        let key: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
+
+        let result: Result<bool, RedisError> = self.client.set_ex::<&str, &str, _>(
+            &get_email_change_token_key(&key),
+            &serialized_token,
+            UNSENT_TOKEN_TTL_SECONDS,
+        );
+        match result {
+            Ok(_) => Ok(key),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for EmailChangeTokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Account export token that represents data in redis.
+/// It allows the user to download the archive of the account by an emailed link.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct AccountExportToken {
+    pub user_id: u64,
+}
+
+/// Returns key of an account export token in redis, which is separated from keys of other tokens.
+pub fn get_account_export_token_key(key: &str) -> String {
+    format!("account_export:{}", key)
+}
+
+/// A core data repository for account export token.
+pub struct AccountExportTokenRepository {
+    client: redis::Connection,
+}
+
+#[automock]
+pub trait AccountExportTokenRepositoryTrait {
+    fn find(&mut self, key: &str) -> Result<String, ServiceError>;
+    fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError>;
+}
+
+impl AccountExportTokenRepository {
+    /// Creates a new token repository.
+    pub fn new() -> Self {
+        Self {
+            client: connection::connect_redis(),
+        }
+    }
+
+    /// Finds a token by key.
+    pub fn find(&mut self, key: &str) -> Result<String, ServiceError> {
+        match self
+            .client
+            .get::<&str, Option<String>>(&get_account_export_token_key(key))
+        {
+            Ok(Some(token)) => Ok(token),
+            Ok(None) => Err(get_service_error(ServiceError::NotFound(key.to_string()))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new token and returns key.
+    ///
+    /// The token expires `ACCOUNT_EXPORT_TOKEN_TTL_SECONDS` later, and can be used until then,
+    /// so that a failed download can be started again.
+    pub fn save(&mut self, serialized_token: &str) -> Result<String, ServiceError> {
+        let key: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
+
+        let result: Result<bool, RedisError> = self.client.set_ex::<&str, &str, _>(
+            &get_account_export_token_key(&key),
+            &serialized_token,
+            ACCOUNT_EXPORT_TOKEN_TTL_SECONDS,
+        );
+        match result {
+            Ok(_) => Ok(key),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for AccountExportTokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A core data repository for expiration of tokens.
+pub struct TokenRepository {
+    client: redis::Connection,
+}
+
+#[automock]
+pub trait TokenRepositoryTrait {
+    fn start_expiry(&mut self, key: &str) -> Result<bool, ServiceError>;
+}
+
+impl TokenRepository {
+    /// Creates a new token repository.
+    pub fn new() -> Self {
+        Self {
+            client: connection::connect_redis(),
+        }
+    }
+
+    /// Makes a token expire `TOKEN_TTL_SECONDS` from now, and returns whether the token exists.
+    ///
+    /// It is called when the email containing the token is sent,
+    /// so that the validity of the token starts from the time the email is sent.
+    pub fn start_expiry(&mut self, key: &str) -> Result<bool, ServiceError> {
+        match self.client.expire::<&str, bool>(key, TOKEN_TTL_SECONDS) {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for TokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,117 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{calendar_feeds, calendar_feeds::dsl};
+use crate::utils::datetime_util;
+
+/// Calendar feed representing `calendar_feeds` table.
+///
+/// A user has one feed at most, and anyone with the token can read the dates of the posts
+/// of the user, so that calendar apps can subscribe to it without logging in.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct CalendarFeed {
+    pub user_id: u64,
+    pub token: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Calendar feed DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct CalendarFeedDTO {
+    pub token: String,
+}
+
+/// Calendar feed DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "calendar_feeds"]
+struct CalendarFeedDAO {
+    user_id: u64,
+    token: String,
+}
+
+/// Deletes the calendar feed of specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::calendar_feeds.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for calendar feed.
+pub struct CalendarFeedRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait CalendarFeedRepositoryTrait {
+    fn find_by_token(&self, token: &str) -> Result<CalendarFeed, ServiceError>;
+    fn create(&self, user_id: u64, token: &str) -> Result<bool, ServiceError>;
+    fn delete(&self, user_id: u64) -> Result<bool, ServiceError>;
+}
+
+impl CalendarFeedRepository {
+    /// Creates a new calendar feed repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds a calendar feed by its token.
+    pub fn find_by_token(&self, token: &str) -> Result<CalendarFeed, ServiceError> {
+        let feed = dsl::calendar_feeds
+            .filter(dsl::token.eq(token))
+            .get_result::<CalendarFeed>(&self.conn);
+
+        match feed {
+            Ok(feed) => Ok(feed),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(String::from(
+                    "calendar feed",
+                )))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Creates a calendar feed of specific user with `token`, replacing the previous feed.
+    pub fn create(&self, user_id: u64, token: &str) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            delete_by_user_id(&self.conn, user_id)?;
+
+            let feed_to_create = CalendarFeedDAO {
+                user_id,
+                token: token.to_string(),
+            };
+            diesel::insert_into(dsl::calendar_feeds)
+                .values(feed_to_create)
+                .execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Revokes the calendar feed of specific user.
+    pub fn delete(&self, user_id: u64) -> Result<bool, ServiceError> {
+        match delete_by_user_id(&self.conn, user_id) {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(
+                user_id.to_string(),
+            ))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for CalendarFeedRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,19 +1,72 @@
 use diesel::{mysql::MysqlConnection, prelude::*};
 use std::env;
 
+use crate::models::storage::{LocalStorage, S3Storage, Storage};
+use crate::utils::signature_util::Credentials;
+
 /// Get established MySQL connection.
 pub fn connect_rdb() -> MysqlConnection {
-    dotenv::dotenv().expect("Failed to read .env file");
-    let rdb_url = env::var("DATABASE_URL").expect("DATABASE_URL not found");
-    MysqlConnection::establish(&rdb_url).expect("Failed to establish a db connection")
+    try_connect_rdb().unwrap_or_else(|error| panic!("{}", error))
 }
 
 /// Get established redis connection.
 pub fn connect_redis() -> redis::Connection {
-    dotenv::dotenv().expect("Failed to read .env file");
-    let redis_url = env::var("REDIS_URL").expect("REDIS_URL not found");
-    let client = redis::Client::open(redis_url).expect("Failed to connect to redis");
+    try_connect_redis().unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Tries to establish MySQL connection, and returns the reason if it fails.
+pub fn try_connect_rdb() -> Result<MysqlConnection, String> {
+    dotenv::dotenv().map_err(|_| "Failed to read .env file")?;
+    let rdb_url = env::var("DATABASE_URL").map_err(|_| "DATABASE_URL not found")?;
+    MysqlConnection::establish(&rdb_url)
+        .map_err(|error| format!("Failed to establish a db connection: {}", error))
+}
+
+/// Tries to establish redis connection, and returns the reason if it fails.
+pub fn try_connect_redis() -> Result<redis::Connection, String> {
+    dotenv::dotenv().map_err(|_| "Failed to read .env file")?;
+    let redis_url = env::var("REDIS_URL").map_err(|_| "REDIS_URL not found")?;
+    let client = redis::Client::open(redis_url)
+        .map_err(|error| format!("Failed to connect to redis: {}", error))?;
     client
         .get_connection()
-        .expect("Failed to get redis connection")
+        .map_err(|error| format!("Failed to get redis connection: {}", error))
+}
+
+/// Get storage of files.
+pub fn connect_storage() -> Box<dyn Storage> {
+    try_connect_storage().unwrap_or_else(|error| panic!("{}", error))
+}
+
+/// Tries to open storage of files selected by `STORAGE_BACKEND`, and returns the reason if it fails.
+///
+/// * `local` (default) - Keeps files in `STORAGE_DIRECTORY`.
+/// * `s3` - Keeps files in `S3_BUCKET` of `S3_ENDPOINT`, under `S3_PREFIX` if it is set.
+///   It signs requests with `S3_ACCESS_KEY_ID` and `S3_SECRET_ACCESS_KEY` for `S3_REGION`.
+pub fn try_connect_storage() -> Result<Box<dyn Storage>, String> {
+    dotenv::dotenv().map_err(|_| "Failed to read .env file")?;
+    let get_var = |name: &str| env::var(name).map_err(|_| format!("{} not found", name));
+    let backend = env::var("STORAGE_BACKEND").unwrap_or_else(|_| String::from("local"));
+    match backend.as_str() {
+        "local" => {
+            let directory = get_var("STORAGE_DIRECTORY")?;
+            Ok(Box::new(LocalStorage::new(&directory)))
+        }
+        "s3" => {
+            let credentials = Credentials {
+                access_key_id: get_var("S3_ACCESS_KEY_ID")?,
+                secret_access_key: get_var("S3_SECRET_ACCESS_KEY")?,
+                region: env::var("S3_REGION").unwrap_or_else(|_| String::from("us-east-1")),
+            };
+            let prefix = env::var("S3_PREFIX").unwrap_or_default();
+            let storage = S3Storage::new(
+                &get_var("S3_ENDPOINT")?,
+                &get_var("S3_BUCKET")?,
+                &prefix,
+                credentials,
+            )?;
+            Ok(Box::new(storage))
+        }
+        _ => Err(format!("Unknown STORAGE_BACKEND: {}", backend)),
+    }
 }
@@ -0,0 +1,244 @@
+use chrono::{Duration, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{email_jobs, email_jobs::dsl};
+use crate::utils::datetime_util;
+
+/// Email job representing `email_jobs` table.
+///
+/// A job stays in the table until the email is sent, or until it fails too many times.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct EmailJob {
+    pub id: u64,
+    pub recipient: String,
+    pub subject: String,
+    pub body: String,
+    pub token_key: Option<String>,
+    /// URL unsubscribing the recipient from emails like this, if it can be unsubscribed.
+    pub unsubscribe_url: Option<String>,
+    pub attempts: u32,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub next_attempt_at: NaiveDateTime,
+    pub last_error: Option<String>,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub failed_at: Option<NaiveDateTime>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Email job count DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct EmailJobCountDTO {
+    pub pending: i64,
+    pub failed: i64,
+}
+
+/// Email job DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "email_jobs"]
+struct EmailJobDAO {
+    recipient: String,
+    subject: String,
+    body: String,
+    token_key: Option<String>,
+    unsubscribe_url: Option<String>,
+}
+
+/// A core data repository for email job.
+pub struct EmailJobRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait EmailJobRepositoryTrait {
+    fn create(
+        &self,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+        token_key: &Option<String>,
+        unsubscribe_url: &Option<String>,
+    ) -> Result<bool, ServiceError>;
+    fn find_due(&self, now: &NaiveDateTime, limit: i64) -> Result<Vec<EmailJob>, ServiceError>;
+    fn claim(&self, id: u64, now: &NaiveDateTime, lease: Duration) -> Result<bool, ServiceError>;
+    fn delete(&self, id: u64) -> Result<bool, ServiceError>;
+    fn retry_later(
+        &self,
+        id: u64,
+        attempts: u32,
+        next_attempt_at: &NaiveDateTime,
+        error: &str,
+    ) -> Result<bool, ServiceError>;
+    fn give_up(
+        &self,
+        id: u64,
+        attempts: u32,
+        error: &str,
+        failed_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn count(&self) -> Result<EmailJobCountDTO, ServiceError>;
+}
+
+impl EmailJobRepository {
+    /// Creates a new email job repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Creates an email job to be sent as soon as possible.
+    pub fn create(
+        &self,
+        recipient: &str,
+        subject: &str,
+        body: &str,
+        token_key: &Option<String>,
+        unsubscribe_url: &Option<String>,
+    ) -> Result<bool, ServiceError> {
+        let job_to_create = EmailJobDAO {
+            recipient: recipient.to_string(),
+            subject: subject.to_string(),
+            body: body.to_string(),
+            token_key: token_key.clone(),
+            unsubscribe_url: unsubscribe_url.clone(),
+        };
+
+        let count = diesel::insert_into(dsl::email_jobs)
+            .values(job_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds email jobs due at `now`, in the order of creation.
+    pub fn find_due(&self, now: &NaiveDateTime, limit: i64) -> Result<Vec<EmailJob>, ServiceError> {
+        let job_list: Result<Vec<EmailJob>, Error> = dsl::email_jobs
+            .filter(dsl::failed_at.is_null())
+            .filter(dsl::next_attempt_at.le(now))
+            .order(dsl::id.asc())
+            .limit(limit)
+            .load::<EmailJob>(&self.conn);
+
+        match job_list {
+            Ok(job_list) => Ok(job_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Postpones an email job due at `now` by `lease`, and returns whether it was claimed.
+    ///
+    /// The job is claimed only if it is still due, so that another process
+    /// sending emails at the same time does not send it twice.
+    pub fn claim(
+        &self,
+        id: u64,
+        now: &NaiveDateTime,
+        lease: Duration,
+    ) -> Result<bool, ServiceError> {
+        let target_job = dsl::email_jobs
+            .find(id)
+            .filter(dsl::failed_at.is_null())
+            .filter(dsl::next_attempt_at.le(now));
+
+        let count = diesel::update(target_job)
+            .set(dsl::next_attempt_at.eq(*now + lease))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes an email job that has been sent.
+    pub fn delete(&self, id: u64) -> Result<bool, ServiceError> {
+        let count = diesel::delete(dsl::email_jobs.find(id)).execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Records a failed attempt of an email job, and schedules the next attempt.
+    pub fn retry_later(
+        &self,
+        id: u64,
+        attempts: u32,
+        next_attempt_at: &NaiveDateTime,
+        error: &str,
+    ) -> Result<bool, ServiceError> {
+        let count = diesel::update(dsl::email_jobs.find(id))
+            .set((
+                dsl::attempts.eq(attempts),
+                dsl::next_attempt_at.eq(next_attempt_at),
+                dsl::last_error.eq(error),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Marks an email job as failed, so that it is no longer attempted.
+    ///
+    /// The body is cleared, since it may contain a temporary password.
+    pub fn give_up(
+        &self,
+        id: u64,
+        attempts: u32,
+        error: &str,
+        failed_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let count = diesel::update(dsl::email_jobs.find(id))
+            .set((
+                dsl::attempts.eq(attempts),
+                dsl::last_error.eq(error),
+                dsl::body.eq(""),
+                dsl::failed_at.eq(failed_at),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts email jobs waiting to be sent and ones given up.
+    pub fn count(&self) -> Result<EmailJobCountDTO, ServiceError> {
+        let count = self.conn.transaction::<EmailJobCountDTO, Error, _>(|| {
+            let pending = dsl::email_jobs
+                .filter(dsl::failed_at.is_null())
+                .count()
+                .get_result::<i64>(&self.conn)?;
+            let failed = dsl::email_jobs
+                .filter(dsl::failed_at.is_not_null())
+                .count()
+                .get_result::<i64>(&self.conn)?;
+            Ok(EmailJobCountDTO { pending, failed })
+        });
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for EmailJobRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
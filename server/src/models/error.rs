@@ -22,15 +22,39 @@ pub enum ServiceError {
     #[error("invalid format")]
     InvalidFormat,
 
+    #[error("invalid fields")]
+    InvalidFields(Vec<FieldError>),
+
     #[error("duplicated key")]
     DuplicatedKey,
 
+    #[error("conflict with current version `{0}`")]
+    Conflict(u32),
+
+    #[error("post `{0}` is locked")]
+    Locked(String),
+
     #[error("query execution failure")]
     QueryExecutionFailure,
 
     #[error("unauthorized")]
     Unauthorized,
 
+    #[error("account `{0}` is suspended")]
+    Suspended(String),
+
+    #[error("too many attempts, retry after {0} seconds")]
+    TooManyRequests(u64),
+
+    #[error("payload too large")]
+    PayloadTooLarge,
+
+    #[error("unsupported media type")]
+    UnsupportedMediaType,
+
+    #[error("range not satisfiable in `{0}` bytes")]
+    RangeNotSatisfiable(u64),
+
     #[error("internal server error")]
     InternalServerError,
 
@@ -41,6 +65,13 @@ pub enum ServiceError {
     EmailFailure(String),
 }
 
+/// Error of a field of the request, reported in `fields` of the response.
+#[derive(Debug, Serialize, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
 /// Logs and returns service error passed by parameter.
 pub fn get_service_error(error: ServiceError) -> ServiceError {
     println!("[{}] {}", Utc::now(), error);
@@ -0,0 +1,426 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{export_jobs, export_jobs::dsl};
+use crate::utils::datetime_util;
+
+no_arg_sql_function!(
+    last_insert_id,
+    diesel::sql_types::Unsigned<diesel::sql_types::Bigint>
+);
+
+/// Export job representing `export_jobs` table.
+///
+/// The archive of a job is written to a file in the artifact directory once it is run,
+/// and the job is deleted with the file when it expires.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct ExportJob {
+    pub id: u64,
+    pub user_id: u64,
+    pub format: String,
+    pub include_trash: bool,
+    pub status: String,
+    pub progress: u32,
+    pub total: u32,
+    pub size: Option<u64>,
+    pub download_token: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub expires_at: NaiveDateTime,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+/// Export job DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct ExportJobDTO {
+    pub id: u64,
+    pub format: String,
+    pub include_trash: bool,
+    pub status: String,
+    pub progress: u32,
+    pub total: u32,
+    pub size: Option<u64>,
+    /// URL downloading the archive, which is given only until the archive is downloaded.
+    pub download_url: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub expires_at: NaiveDateTime,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub finished_at: Option<NaiveDateTime>,
+}
+
+/// Formats of archives made by export jobs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportFormat {
+    /// A gzipped tar, which is the format of `GET /export`.
+    TarGz,
+}
+
+impl ExportFormat {
+    /// Returns the name of the format stored in `export_jobs` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TarGz => "tar.gz",
+        }
+    }
+
+    /// Parses the name of the format used in `format` argument.
+    pub fn parse(format: &str) -> Result<Self, ServiceError> {
+        match format {
+            "tar.gz" => Ok(Self::TarGz),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
+}
+
+/// Statuses of export jobs.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExportJobStatus {
+    /// Waiting to be run.
+    Pending,
+    /// Writing the archive.
+    Running,
+    /// The archive is ready to be downloaded.
+    Done,
+    Failed,
+    /// The archive has been downloaded and deleted.
+    Downloaded,
+}
+
+impl ExportJobStatus {
+    /// Returns the name of the status stored in `export_jobs` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Pending => "pending",
+            Self::Running => "running",
+            Self::Done => "done",
+            Self::Failed => "failed",
+            Self::Downloaded => "downloaded",
+        }
+    }
+}
+
+/// Range of bytes of an archive requested by `Range` header, to resume a broken download.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ByteRange {
+    /// From the first position to the last position inclusive, or to the end without the last.
+    From(u64, Option<u64>),
+    /// The given number of bytes at the end.
+    Suffix(u64),
+}
+
+impl ByteRange {
+    /// Parses `Range` header of a single range such as `bytes=0-499`, `bytes=500-`, or `bytes=-500`.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut positions = value.trim().strip_prefix("bytes=")?.splitn(2, '-');
+        let first = positions.next()?.trim();
+        let last = positions.next()?.trim();
+        if first.is_empty() {
+            return last.parse().ok().map(Self::Suffix);
+        }
+
+        let first = first.parse().ok()?;
+        if last.is_empty() {
+            return Some(Self::From(first, None));
+        }
+        let last = last.parse().ok()?;
+        if last < first {
+            return None;
+        }
+        Some(Self::From(first, Some(last)))
+    }
+
+    /// Returns the first and the last positions of the range in `size` bytes inclusive,
+    /// or `None` if no byte is in the range.
+    pub fn resolve(&self, size: u64) -> Option<(u64, u64)> {
+        match *self {
+            Self::From(first, _) if first >= size => None,
+            Self::From(first, last) => {
+                let last = last.map_or(size - 1, |last| last.min(size - 1));
+                Some((first, last))
+            }
+            Self::Suffix(0) => None,
+            Self::Suffix(_) if size == 0 => None,
+            Self::Suffix(length) => Some((size.saturating_sub(length), size - 1)),
+        }
+    }
+}
+
+/// Export job DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "export_jobs"]
+struct ExportJobDAO {
+    user_id: u64,
+    format: String,
+    include_trash: bool,
+    total: u32,
+    expires_at: NaiveDateTime,
+}
+
+/// Deletes export jobs of specific user.
+///
+/// Their archives are deleted by the next pruning, since no job refers to them.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::export_jobs.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for export job.
+pub struct ExportJobRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait ExportJobRepositoryTrait {
+    fn find_by_id(&self, user_id: u64, id: u64) -> Result<ExportJob, ServiceError>;
+    fn find_by_download_token(&self, download_token: &str) -> Result<ExportJob, ServiceError>;
+    fn find_pending(&self, limit: i64) -> Result<Vec<ExportJob>, ServiceError>;
+    fn find_artifact_ids(&self) -> Result<Vec<u64>, ServiceError>;
+    fn create(
+        &self,
+        user_id: u64,
+        format: ExportFormat,
+        include_trash: bool,
+        total: u32,
+        expires_at: &NaiveDateTime,
+    ) -> Result<u64, ServiceError>;
+    fn claim(&self, id: u64) -> Result<bool, ServiceError>;
+    fn update_progress(&self, id: u64, progress: u32) -> Result<bool, ServiceError>;
+    fn finish(
+        &self,
+        id: u64,
+        size: u64,
+        download_token: &str,
+        finished_at: &NaiveDateTime,
+        expires_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn fail(&self, id: u64, finished_at: &NaiveDateTime) -> Result<bool, ServiceError>;
+    fn mark_downloaded(&self, id: u64, download_token: &str) -> Result<bool, ServiceError>;
+    fn delete_all_expired(&self, now: &NaiveDateTime) -> Result<usize, ServiceError>;
+}
+
+impl ExportJobRepository {
+    /// Creates a new export job repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds an export job of specific user.
+    pub fn find_by_id(&self, user_id: u64, id: u64) -> Result<ExportJob, ServiceError> {
+        let job: Result<ExportJob, Error> = dsl::export_jobs
+            .find(id)
+            .filter(dsl::user_id.eq(user_id))
+            .get_result::<ExportJob>(&self.conn);
+
+        match job {
+            Ok(job) => Ok(job),
+            Err(Error::NotFound) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds an export job whose archive is downloaded with `download_token`.
+    pub fn find_by_download_token(&self, download_token: &str) -> Result<ExportJob, ServiceError> {
+        let job: Result<ExportJob, Error> = dsl::export_jobs
+            .filter(dsl::download_token.eq(download_token))
+            .get_result::<ExportJob>(&self.conn);
+
+        match job {
+            Ok(job) => Ok(job),
+            Err(Error::NotFound) => Err(get_service_error(ServiceError::NotFound(
+                download_token.to_string(),
+            ))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds export jobs waiting to be run, in the order of creation.
+    pub fn find_pending(&self, limit: i64) -> Result<Vec<ExportJob>, ServiceError> {
+        let job_list: Result<Vec<ExportJob>, Error> = dsl::export_jobs
+            .filter(dsl::status.eq(ExportJobStatus::Pending.as_str()))
+            .order(dsl::id.asc())
+            .limit(limit)
+            .load::<ExportJob>(&self.conn);
+
+        match job_list {
+            Ok(job_list) => Ok(job_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds ids of export jobs whose archives are being written or waiting to be downloaded.
+    pub fn find_artifact_ids(&self) -> Result<Vec<u64>, ServiceError> {
+        let ids: Result<Vec<u64>, Error> = dsl::export_jobs
+            .select(dsl::id)
+            .filter(dsl::status.eq_any(vec![
+                ExportJobStatus::Running.as_str(),
+                ExportJobStatus::Done.as_str(),
+            ]))
+            .load::<u64>(&self.conn);
+
+        match ids {
+            Ok(ids) => Ok(ids),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a pending export job and returns id of the created job.
+    pub fn create(
+        &self,
+        user_id: u64,
+        format: ExportFormat,
+        include_trash: bool,
+        total: u32,
+        expires_at: &NaiveDateTime,
+    ) -> Result<u64, ServiceError> {
+        let job_to_create = ExportJobDAO {
+            user_id,
+            format: format.as_str().to_string(),
+            include_trash,
+            total,
+            expires_at: *expires_at,
+        };
+
+        let job_id = self.conn.transaction::<u64, Error, _>(|| {
+            diesel::insert_into(dsl::export_jobs)
+                .values(job_to_create)
+                .execute(&self.conn)?;
+            diesel::select(last_insert_id).get_result::<u64>(&self.conn)
+        });
+
+        match job_id {
+            Ok(job_id) => Ok(job_id),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Marks a pending export job as running, and returns whether it was claimed.
+    ///
+    /// The job is claimed only if it is still pending, so that another process
+    /// running jobs at the same time does not run it twice.
+    pub fn claim(&self, id: u64) -> Result<bool, ServiceError> {
+        let target_job = dsl::export_jobs
+            .find(id)
+            .filter(dsl::status.eq(ExportJobStatus::Pending.as_str()));
+
+        let count = diesel::update(target_job)
+            .set(dsl::status.eq(ExportJobStatus::Running.as_str()))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Sets the number of posts written to the archive of a running export job.
+    pub fn update_progress(&self, id: u64, progress: u32) -> Result<bool, ServiceError> {
+        let target_job = dsl::export_jobs
+            .find(id)
+            .filter(dsl::status.eq(ExportJobStatus::Running.as_str()));
+
+        let count = diesel::update(target_job)
+            .set(dsl::progress.eq(progress))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Marks a running export job as done, with the token downloading its archive.
+    pub fn finish(
+        &self,
+        id: u64,
+        size: u64,
+        download_token: &str,
+        finished_at: &NaiveDateTime,
+        expires_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let target_job = dsl::export_jobs
+            .find(id)
+            .filter(dsl::status.eq(ExportJobStatus::Running.as_str()));
+
+        let count = diesel::update(target_job)
+            .set((
+                dsl::status.eq(ExportJobStatus::Done.as_str()),
+                dsl::size.eq(size),
+                dsl::download_token.eq(download_token),
+                dsl::finished_at.eq(finished_at),
+                dsl::expires_at.eq(expires_at),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Marks a running export job as failed.
+    pub fn fail(&self, id: u64, finished_at: &NaiveDateTime) -> Result<bool, ServiceError> {
+        let target_job = dsl::export_jobs
+            .find(id)
+            .filter(dsl::status.eq(ExportJobStatus::Running.as_str()));
+
+        let count = diesel::update(target_job)
+            .set((
+                dsl::status.eq(ExportJobStatus::Failed.as_str()),
+                dsl::finished_at.eq(finished_at),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Marks an export job as downloaded and revokes its download token,
+    /// and returns whether the token was still valid.
+    pub fn mark_downloaded(&self, id: u64, download_token: &str) -> Result<bool, ServiceError> {
+        let target_job = dsl::export_jobs
+            .find(id)
+            .filter(dsl::status.eq(ExportJobStatus::Done.as_str()))
+            .filter(dsl::download_token.eq(download_token));
+
+        let count = diesel::update(target_job)
+            .set((
+                dsl::status.eq(ExportJobStatus::Downloaded.as_str()),
+                dsl::download_token.eq(None::<String>),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes export jobs expired at `now`, and returns the number of deleted jobs.
+    pub fn delete_all_expired(&self, now: &NaiveDateTime) -> Result<usize, ServiceError> {
+        let count =
+            diesel::delete(dsl::export_jobs.filter(dsl::expires_at.le(now))).execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for ExportJobRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
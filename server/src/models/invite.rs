@@ -0,0 +1,177 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{invites, invites::dsl};
+use crate::utils::datetime_util;
+
+/// Invite representing `invites` table.
+///
+/// It is a single-use code an admin mints, which signups require on instances in invite mode.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct Invite {
+    pub id: u64,
+    pub code: String,
+    pub created_by: Option<u64>,
+    pub redeemed_by: Option<u64>,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub redeemed_at: Option<NaiveDateTime>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Invite DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct InviteDTO {
+    pub id: u64,
+    pub code: String,
+    pub created_by: Option<u64>,
+    pub redeemed_by: Option<u64>,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub redeemed_at: Option<NaiveDateTime>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Invite DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "invites"]
+struct InviteDAO {
+    code: String,
+    created_by: u64,
+}
+
+/// A core data repository for invite.
+pub struct InviteRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait InviteRepositoryTrait {
+    fn find_all(&self, offset: i64, limit: i64) -> Result<Vec<Invite>, ServiceError>;
+    fn count(&self) -> Result<i64, ServiceError>;
+    fn find_by_code(&self, code: &str) -> Result<Invite, ServiceError>;
+    fn create(&self, code: &str, created_by: u64) -> Result<Invite, ServiceError>;
+    fn claim(&self, code: &str, redeemed_at: &NaiveDateTime) -> Result<bool, ServiceError>;
+    fn update_redeemed_by(&self, code: &str, redeemed_by: u64) -> Result<bool, ServiceError>;
+    fn release(&self, code: &str) -> Result<bool, ServiceError>;
+}
+
+impl InviteRepository {
+    /// Creates a new invite repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds invites, the most recently minted first.
+    pub fn find_all(&self, offset: i64, limit: i64) -> Result<Vec<Invite>, ServiceError> {
+        let invite_list = dsl::invites
+            .order(dsl::id.desc())
+            .offset(offset)
+            .limit(limit)
+            .load::<Invite>(&self.conn);
+
+        match invite_list {
+            Ok(invite_list) => Ok(invite_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts invites.
+    pub fn count(&self) -> Result<i64, ServiceError> {
+        match dsl::invites.count().get_result::<i64>(&self.conn) {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds an invite by the code.
+    pub fn find_by_code(&self, code: &str) -> Result<Invite, ServiceError> {
+        let invite = dsl::invites
+            .filter(dsl::code.eq(code))
+            .get_result::<Invite>(&self.conn);
+
+        match invite {
+            Ok(invite) => Ok(invite),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(code.to_string()))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Creates a new invite minted by an admin.
+    pub fn create(&self, code: &str, created_by: u64) -> Result<Invite, ServiceError> {
+        let invite_to_create = InviteDAO {
+            code: code.to_string(),
+            created_by,
+        };
+
+        let count = diesel::insert_into(dsl::invites)
+            .values(invite_to_create)
+            .execute(&self.conn);
+
+        if count.is_err() {
+            return Err(get_service_error(ServiceError::QueryExecutionFailure));
+        }
+
+        self.find_by_code(code)
+    }
+
+    /// Claims an invite which is not redeemed yet, and returns whether it is claimed.
+    ///
+    /// The invite is claimed in a single update, so that concurrent signups never share a code.
+    pub fn claim(&self, code: &str, redeemed_at: &NaiveDateTime) -> Result<bool, ServiceError> {
+        let target_invite = dsl::invites
+            .filter(dsl::code.eq(code))
+            .filter(dsl::redeemed_at.is_null());
+        let count = diesel::update(target_invite)
+            .set(dsl::redeemed_at.eq(redeemed_at))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Records the user who signed up with a claimed invite.
+    pub fn update_redeemed_by(&self, code: &str, redeemed_by: u64) -> Result<bool, ServiceError> {
+        let count = diesel::update(dsl::invites.filter(dsl::code.eq(code)))
+            .set(dsl::redeemed_by.eq(redeemed_by))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) if count > 0 => Ok(true),
+            Ok(_) => Err(get_service_error(ServiceError::NotFound(code.to_string()))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Releases a claimed invite whose signup failed, so that it can be used again.
+    pub fn release(&self, code: &str) -> Result<bool, ServiceError> {
+        let target_invite = dsl::invites
+            .filter(dsl::code.eq(code))
+            .filter(dsl::redeemed_by.is_null());
+        let count = diesel::update(target_invite)
+            .set(dsl::redeemed_at.eq(None::<NaiveDateTime>))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for InviteRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,253 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::{exists, sql};
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::Datetime;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{journals, journals::dsl, posts};
+use crate::utils::datetime_util;
+
+no_arg_sql_function!(
+    last_insert_id,
+    diesel::sql_types::Unsigned<diesel::sql_types::Bigint>
+);
+
+/// Journal representing `journals` table.
+///
+/// The name is encrypted by the client like names of tags, so the server never reads it.
+/// Each user has one default journal, which has an empty name until it is renamed.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct Journal {
+    pub id: u64,
+    pub user_id: u64,
+    pub name: String,
+    /// Whether posts are written in the journal unless another journal is given.
+    pub is_default: bool,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Journal DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct JournalDTO {
+    pub id: u64,
+    pub name: String,
+    pub is_default: bool,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Journal DAO using between models layer and RDB.
+#[derive(Insertable, AsChangeset)]
+#[table_name = "journals"]
+struct JournalDAO {
+    user_id: Option<u64>,
+    name: Option<String>,
+    is_default: Option<bool>,
+    updated_at: Option<NaiveDateTime>,
+}
+
+/// Creates the default journal of a new user.
+pub fn create_default(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    let journal_to_create = JournalDAO {
+        user_id: Some(user_id),
+        name: Some(String::new()),
+        is_default: Some(true),
+        updated_at: None,
+    };
+    diesel::insert_into(dsl::journals)
+        .values(journal_to_create)
+        .execute(conn)
+}
+
+/// Finds id of the default journal of specific user.
+pub fn find_default_id(conn: &MysqlConnection, user_id: u64) -> Result<u64, Error> {
+    dsl::journals
+        .select(dsl::id)
+        .filter(dsl::user_id.eq(user_id))
+        .filter(dsl::is_default.eq(true))
+        .first::<u64>(conn)
+}
+
+/// Returns whether a journal belongs to specific user.
+pub fn is_owned(conn: &MysqlConnection, user_id: u64, journal_id: u64) -> Result<bool, Error> {
+    let owned_journal = dsl::journals
+        .find(journal_id)
+        .filter(dsl::user_id.eq(user_id));
+    diesel::select(exists(owned_journal)).get_result::<bool>(conn)
+}
+
+/// Deletes journals of specific user, which must be done after deleting the posts of the user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::journals.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for journal.
+pub struct JournalRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait JournalRepositoryTrait {
+    fn find_all(&self, user_id: u64) -> Result<Vec<Journal>, ServiceError>;
+    fn create(&self, user_id: u64, name: &str) -> Result<u64, ServiceError>;
+    fn update(&self, user_id: u64, journal_id: u64, name: &str) -> Result<bool, ServiceError>;
+    fn delete(&self, user_id: u64, journal_id: u64) -> Result<bool, ServiceError>;
+    fn set_default(&self, user_id: u64, journal_id: u64) -> Result<bool, ServiceError>;
+}
+
+impl JournalRepository {
+    /// Creates a new journal repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds all journals of specific user, the default journal first
+    /// and the others in the order of creation.
+    pub fn find_all(&self, user_id: u64) -> Result<Vec<Journal>, ServiceError> {
+        let journal_list: Result<Vec<Journal>, Error> = dsl::journals
+            .filter(dsl::user_id.eq(user_id))
+            .order((dsl::is_default.desc(), dsl::id.asc()))
+            .load::<Journal>(&self.conn);
+
+        match journal_list {
+            Ok(journal_list) => Ok(journal_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new journal and returns id of the created journal.
+    pub fn create(&self, user_id: u64, name: &str) -> Result<u64, ServiceError> {
+        let journal_to_create = JournalDAO {
+            user_id: Some(user_id),
+            name: Some(name.to_string()),
+            is_default: Some(false),
+            updated_at: None,
+        };
+
+        let journal_id = self.conn.transaction::<u64, Error, _>(|| {
+            diesel::insert_into(dsl::journals)
+                .values(journal_to_create)
+                .execute(&self.conn)?;
+            diesel::select(last_insert_id).get_result::<u64>(&self.conn)
+        });
+
+        match journal_id {
+            Ok(journal_id) => Ok(journal_id),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Renames a journal of specific user.
+    pub fn update(&self, user_id: u64, journal_id: u64, name: &str) -> Result<bool, ServiceError> {
+        let journal_to_update = JournalDAO {
+            user_id: None,
+            name: Some(name.to_string()),
+            is_default: None,
+            updated_at: Some(Utc::now().naive_utc()),
+        };
+
+        let target_journal = dsl::journals
+            .find(journal_id)
+            .filter(dsl::user_id.eq(user_id));
+        let count = diesel::update(target_journal)
+            .set(journal_to_update)
+            .execute(&self.conn);
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(
+                journal_id.to_string(),
+            ))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a journal of specific user, and moves its posts to the default journal.
+    ///
+    /// The default journal cannot be deleted.
+    pub fn delete(&self, user_id: u64, journal_id: u64) -> Result<bool, ServiceError> {
+        let mut is_default_journal = false;
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let is_default = dsl::journals
+                .find(journal_id)
+                .filter(dsl::user_id.eq(user_id))
+                .select(dsl::is_default)
+                .get_result::<bool>(&self.conn)?;
+            if is_default {
+                is_default_journal = true;
+                return Err(Error::RollbackTransaction);
+            }
+
+            // Posts in the trash are moved too, so that they can be restored.
+            let default_journal_id = find_default_id(&self.conn, user_id)?;
+            diesel::update(posts::dsl::posts.filter(posts::dsl::journal_id.eq(journal_id)))
+                .set((
+                    posts::dsl::journal_id.eq(default_journal_id),
+                    posts::dsl::changed_at.eq(sql::<Datetime>("CURRENT_TIMESTAMP(6)")),
+                ))
+                .execute(&self.conn)?;
+
+            diesel::delete(dsl::journals.find(journal_id)).execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::RollbackTransaction if is_default_journal => {
+                    Err(get_service_error(ServiceError::InvalidArgument))
+                }
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    journal_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Makes a journal of specific user the default journal, to which posts go
+    /// unless their journal is given.
+    pub fn set_default(&self, user_id: u64, journal_id: u64) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            if !is_owned(&self.conn, user_id, journal_id)? {
+                return Err(Error::NotFound);
+            }
+
+            diesel::update(dsl::journals.filter(dsl::user_id.eq(user_id)))
+                .set(dsl::is_default.eq(false))
+                .execute(&self.conn)?;
+            diesel::update(dsl::journals.find(journal_id))
+                .set((
+                    dsl::is_default.eq(true),
+                    dsl::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(Error::NotFound) => Err(get_service_error(ServiceError::NotFound(
+                journal_id.to_string(),
+            ))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for JournalRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,104 @@
+use mockall::automock;
+use redis::{Commands, RedisError};
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+
+/// Failed login attempts of an account or an IP, that represents data in redis.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LoginAttempts {
+    /// Unix timestamps of the failures in the current window.
+    pub failures: Vec<i64>,
+    /// Number of lockouts in a row, which doubles the next lockout.
+    pub lockout_count: u32,
+    /// Unix timestamp until which signing in is locked.
+    pub locked_until: Option<i64>,
+}
+
+/// Rate limit of signing in, which is exposed in `RateLimit-*` headers.
+#[derive(Debug, PartialEq)]
+pub struct RateLimit {
+    /// Failures allowed in the window.
+    pub limit: usize,
+    /// Failures remaining before being locked.
+    pub remaining: usize,
+    /// Seconds until the limit is reset.
+    pub reset_after: u64,
+    /// Unix timestamp when the limit is reset.
+    pub reset_at: i64,
+}
+
+/// Returns key of login attempts in redis, which is separated from keys of tokens.
+fn get_login_attempts_key(key: &str) -> String {
+    format!("login_attempts:{}", key)
+}
+
+/// A core data repository for login attempts.
+pub struct LoginAttemptRepository {
+    client: redis::Connection,
+}
+
+#[automock]
+pub trait LoginAttemptRepositoryTrait {
+    fn find(&mut self, key: &str) -> Result<Option<String>, ServiceError>;
+    fn save(
+        &mut self,
+        key: &str,
+        serialized_attempts: &str,
+        ttl_seconds: usize,
+    ) -> Result<bool, ServiceError>;
+    fn delete(&mut self, key: &str) -> Result<bool, ServiceError>;
+}
+
+impl LoginAttemptRepository {
+    /// Creates a new login attempt repository.
+    pub fn new() -> Self {
+        Self {
+            client: connection::connect_redis(),
+        }
+    }
+
+    /// Finds login attempts by key, or `None` if there has been no failure recently.
+    pub fn find(&mut self, key: &str) -> Result<Option<String>, ServiceError> {
+        match self
+            .client
+            .get::<&str, Option<String>>(&get_login_attempts_key(key))
+        {
+            Ok(attempts) => Ok(attempts),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Saves login attempts by key, which expire `ttl_seconds` later.
+    pub fn save(
+        &mut self,
+        key: &str,
+        serialized_attempts: &str,
+        ttl_seconds: usize,
+    ) -> Result<bool, ServiceError> {
+        let result: Result<bool, RedisError> = self.client.set_ex::<&str, &str, _>(
+            &get_login_attempts_key(key),
+            serialized_attempts,
+            ttl_seconds,
+        );
+        match result {
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes login attempts by key.
+    pub fn delete(&mut self, key: &str) -> Result<bool, ServiceError> {
+        match self.client.del::<&str, _>(&get_login_attempts_key(key)) {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for LoginAttemptRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
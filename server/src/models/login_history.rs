@@ -0,0 +1,172 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{login_history, login_history::dsl};
+use crate::utils::datetime_util;
+
+/// Login history entry representing `login_history` table.
+///
+/// It is appended whenever a user signs in, and kept after the login session is deleted.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct LoginHistory {
+    pub id: u64,
+    pub user_id: u64,
+    pub ip: Option<String>,
+    pub country: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Login history DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoginHistoryDTO {
+    pub id: u64,
+    pub ip: Option<String>,
+    pub country: Option<String>,
+    pub user_agent: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Login history DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "login_history"]
+struct LoginHistoryDAO {
+    user_id: u64,
+    ip: Option<String>,
+    country: Option<String>,
+    user_agent: Option<String>,
+}
+
+/// Deletes login history of specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::login_history.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for login history.
+pub struct LoginHistoryRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait LoginHistoryRepositoryTrait {
+    fn find_all_by_user_id(
+        &self,
+        user_id: u64,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<LoginHistory>, ServiceError>;
+    fn count_by_user_id(&self, user_id: u64) -> Result<i64, ServiceError>;
+    fn count_by_user_agent(
+        &self,
+        user_id: u64,
+        user_agent: &Option<String>,
+    ) -> Result<i64, ServiceError>;
+    fn create(
+        &self,
+        user_id: u64,
+        ip: &Option<String>,
+        country: &Option<String>,
+        user_agent: &Option<String>,
+    ) -> Result<bool, ServiceError>;
+}
+
+impl LoginHistoryRepository {
+    /// Creates a new login history repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds login history of specific user in desc order.
+    pub fn find_all_by_user_id(
+        &self,
+        user_id: u64,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<LoginHistory>, ServiceError> {
+        let history = dsl::login_history
+            .filter(dsl::user_id.eq(user_id))
+            .order((dsl::created_at.desc(), dsl::id.desc()))
+            .offset(offset)
+            .limit(limit)
+            .load::<LoginHistory>(&self.conn);
+
+        match history {
+            Ok(history) => Ok(history),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts logins of specific user.
+    pub fn count_by_user_id(&self, user_id: u64) -> Result<i64, ServiceError> {
+        let count = dsl::login_history
+            .filter(dsl::user_id.eq(user_id))
+            .count()
+            .get_result::<i64>(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts logins of specific user from the device of `user_agent`.
+    pub fn count_by_user_agent(
+        &self,
+        user_id: u64,
+        user_agent: &Option<String>,
+    ) -> Result<i64, ServiceError> {
+        let mut query = dsl::login_history
+            .filter(dsl::user_id.eq(user_id))
+            .into_boxed();
+
+        query = match user_agent {
+            Some(user_agent) => query.filter(dsl::user_agent.eq(user_agent)),
+            None => query.filter(dsl::user_agent.is_null()),
+        };
+
+        match query.count().get_result::<i64>(&self.conn) {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Appends a login of specific user.
+    pub fn create(
+        &self,
+        user_id: u64,
+        ip: &Option<String>,
+        country: &Option<String>,
+        user_agent: &Option<String>,
+    ) -> Result<bool, ServiceError> {
+        let history_to_create = LoginHistoryDAO {
+            user_id,
+            ip: ip.clone(),
+            country: country.clone(),
+            user_agent: user_agent.clone(),
+        };
+
+        let count = diesel::insert_into(dsl::login_history)
+            .values(history_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for LoginHistoryRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,565 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::user::User;
+use crate::schema::{login_sessions, login_sessions::dsl, rotated_refresh_tokens, users};
+use crate::utils::datetime_util;
+
+/// Login session representing `login_sessions` table.
+///
+/// It is created when a user signs in on a device, and the api gateway accepts the session cookie
+/// of the device only while it exists.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct LoginSession {
+    pub id: u64,
+    pub user_id: u64,
+    /// SHA-256 hash of the session id in hex.
+    pub session_id_hash: String,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub last_seen_at: NaiveDateTime,
+    /// SHA-256 hash of the refresh token in hex, if the device uses access tokens
+    /// instead of the session cookie.
+    pub refresh_token_hash: Option<String>,
+    /// SHA-256 hash of the device id in hex, if the device uses the session cookie.
+    pub device_id_hash: Option<String>,
+    /// Label the client has given to the session.
+    pub label: Option<String>,
+    /// Time the session expires at, after which it cannot be refreshed anymore.
+    #[serde(with = "datetime_util::rfc3339")]
+    pub expires_at: NaiveDateTime,
+    /// Admin impersonating the user, if the session has been issued to the admin.
+    pub impersonator_id: Option<u64>,
+    /// Whether the impersonating admin may write posts, which is ignored unless impersonated.
+    pub allow_writes: bool,
+}
+
+/// Admin impersonating the user of a login session.
+#[derive(Debug, PartialEq)]
+pub struct Impersonation {
+    pub impersonator_id: u64,
+    pub allow_writes: bool,
+}
+
+/// Device a login session has signed in on with the session cookie.
+///
+/// Sessions of the same device can be switched to each other.
+#[derive(Debug, Default)]
+pub struct LoginDevice {
+    /// SHA-256 hash of the device id in hex.
+    pub device_id_hash: Option<String>,
+    /// Label the client has given to the session, such as the name of a family member.
+    pub label: Option<String>,
+}
+
+/// Login session DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct LoginSessionDTO {
+    pub id: u64,
+    pub user_agent: Option<String>,
+    pub ip: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub last_seen_at: NaiveDateTime,
+    /// Whether it is the session of the device requesting.
+    pub is_current: bool,
+}
+
+/// Login session of a device DTO using between routes layer and service layer,
+/// which the device can switch to.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct DeviceLoginSessionDTO {
+    pub id: u64,
+    pub user_id: u64,
+    pub user_name: String,
+    pub user_avatar_url: Option<String>,
+    pub label: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub last_seen_at: NaiveDateTime,
+    /// Whether it is the session the device is using.
+    pub is_current: bool,
+}
+
+/// Login session DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "login_sessions"]
+struct LoginSessionDAO {
+    user_id: u64,
+    session_id_hash: String,
+    user_agent: Option<String>,
+    ip: Option<String>,
+    refresh_token_hash: Option<String>,
+    device_id_hash: Option<String>,
+    label: Option<String>,
+    expires_at: NaiveDateTime,
+    impersonator_id: Option<u64>,
+    allow_writes: bool,
+}
+
+/// Rotated refresh token DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "rotated_refresh_tokens"]
+struct RotatedRefreshTokenDAO {
+    refresh_token_hash: String,
+    login_session_id: u64,
+    expires_at: NaiveDateTime,
+}
+
+/// Deletes login sessions of specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::login_sessions.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for login session.
+pub struct LoginSessionRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait LoginSessionRepositoryTrait {
+    fn find_all_by_user_id(&self, user_id: u64) -> Result<Vec<LoginSession>, ServiceError>;
+    fn find_all_by_device_id_hash(
+        &self,
+        device_id_hash: &str,
+    ) -> Result<Vec<(LoginSession, User)>, ServiceError>;
+    fn find_by_device_id_hash(
+        &self,
+        id: u64,
+        device_id_hash: &str,
+    ) -> Result<LoginSession, ServiceError>;
+    fn find_by_session_id_hash(&self, session_id_hash: &str) -> Result<LoginSession, ServiceError>;
+    fn find_by_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<LoginSession, ServiceError>;
+    fn find_by_rotated_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<LoginSession, ServiceError>;
+    fn create(
+        &self,
+        user_id: u64,
+        session_id_hash: &str,
+        user_agent: &Option<String>,
+        ip: &Option<String>,
+        refresh_token_hash: &Option<String>,
+        device: &LoginDevice,
+        expires_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn create_impersonated(
+        &self,
+        user_id: u64,
+        session_id_hash: &str,
+        user_agent: &Option<String>,
+        ip: &Option<String>,
+        impersonation: &Impersonation,
+        expires_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn update_session_id_hash(
+        &self,
+        id: u64,
+        session_id_hash: &str,
+        last_seen_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn rotate_refresh_token(
+        &self,
+        session: &LoginSession,
+        session_id_hash: &str,
+        refresh_token_hash: &str,
+        last_seen_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn update_last_seen_at(
+        &self,
+        id: u64,
+        last_seen_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn delete(&self, id: u64, user_id: u64) -> Result<bool, ServiceError>;
+    fn delete_by_session_id_hash(
+        &self,
+        user_id: u64,
+        session_id_hash: &str,
+    ) -> Result<bool, ServiceError>;
+    fn delete_by_refresh_token_hash(&self, refresh_token_hash: &str) -> Result<bool, ServiceError>;
+    fn delete_all_by_user_id(&self, user_id: u64) -> Result<usize, ServiceError>;
+    fn delete_all_expired(
+        &self,
+        now: &NaiveDateTime,
+        last_seen_before: &NaiveDateTime,
+    ) -> Result<usize, ServiceError>;
+}
+
+impl LoginSessionRepository {
+    /// Creates a new login session repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds all login sessions of specific user, the most recently seen first.
+    pub fn find_all_by_user_id(&self, user_id: u64) -> Result<Vec<LoginSession>, ServiceError> {
+        let sessions = dsl::login_sessions
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::last_seen_at.desc())
+            .load::<LoginSession>(&self.conn);
+
+        match sessions {
+            Ok(sessions) => Ok(sessions),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds all login sessions of a device with their users, the most recently seen first.
+    pub fn find_all_by_device_id_hash(
+        &self,
+        device_id_hash: &str,
+    ) -> Result<Vec<(LoginSession, User)>, ServiceError> {
+        let sessions = dsl::login_sessions
+            .inner_join(users::table)
+            .filter(dsl::device_id_hash.eq(device_id_hash))
+            .order(dsl::last_seen_at.desc())
+            .load::<(LoginSession, User)>(&self.conn);
+
+        match sessions {
+            Ok(sessions) => Ok(sessions),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds a login session of a device.
+    pub fn find_by_device_id_hash(
+        &self,
+        id: u64,
+        device_id_hash: &str,
+    ) -> Result<LoginSession, ServiceError> {
+        let session = dsl::login_sessions
+            .find(id)
+            .filter(dsl::device_id_hash.eq(device_id_hash))
+            .get_result::<LoginSession>(&self.conn);
+
+        match session {
+            Ok(session) => Ok(session),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Finds a login session by the hash of its session id.
+    pub fn find_by_session_id_hash(
+        &self,
+        session_id_hash: &str,
+    ) -> Result<LoginSession, ServiceError> {
+        let session = dsl::login_sessions
+            .filter(dsl::session_id_hash.eq(session_id_hash))
+            .get_result::<LoginSession>(&self.conn);
+
+        match session {
+            Ok(session) => Ok(session),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    session_id_hash.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Finds a login session by the hash of its refresh token.
+    pub fn find_by_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<LoginSession, ServiceError> {
+        let session = dsl::login_sessions
+            .filter(dsl::refresh_token_hash.eq(refresh_token_hash))
+            .get_result::<LoginSession>(&self.conn);
+
+        match session {
+            Ok(session) => Ok(session),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    refresh_token_hash.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Finds a login session by the hash of a refresh token rotated out of it.
+    pub fn find_by_rotated_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<LoginSession, ServiceError> {
+        let session = rotated_refresh_tokens::table
+            .find(refresh_token_hash)
+            .select(rotated_refresh_tokens::login_session_id)
+            .get_result::<u64>(&self.conn)
+            .and_then(|id| {
+                dsl::login_sessions
+                    .find(id)
+                    .get_result::<LoginSession>(&self.conn)
+            });
+
+        match session {
+            Ok(session) => Ok(session),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    refresh_token_hash.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Creates a new login session of specific user.
+    pub fn create(
+        &self,
+        user_id: u64,
+        session_id_hash: &str,
+        user_agent: &Option<String>,
+        ip: &Option<String>,
+        refresh_token_hash: &Option<String>,
+        device: &LoginDevice,
+        expires_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let session_to_create = LoginSessionDAO {
+            user_id,
+            session_id_hash: session_id_hash.to_string(),
+            user_agent: user_agent.clone(),
+            ip: ip.clone(),
+            refresh_token_hash: refresh_token_hash.clone(),
+            device_id_hash: device.device_id_hash.clone(),
+            label: device.label.clone(),
+            expires_at: *expires_at,
+            impersonator_id: None,
+            allow_writes: false,
+        };
+
+        let count = diesel::insert_into(dsl::login_sessions)
+            .values(session_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new login session of specific user issued to an admin impersonating the user.
+    pub fn create_impersonated(
+        &self,
+        user_id: u64,
+        session_id_hash: &str,
+        user_agent: &Option<String>,
+        ip: &Option<String>,
+        impersonation: &Impersonation,
+        expires_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let session_to_create = LoginSessionDAO {
+            user_id,
+            session_id_hash: session_id_hash.to_string(),
+            user_agent: user_agent.clone(),
+            ip: ip.clone(),
+            refresh_token_hash: None,
+            device_id_hash: None,
+            label: None,
+            expires_at: *expires_at,
+            impersonator_id: Some(impersonation.impersonator_id),
+            allow_writes: impersonation.allow_writes,
+        };
+
+        let count = diesel::insert_into(dsl::login_sessions)
+            .values(session_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Replaces the session id of a login session, which a device has switched to.
+    pub fn update_session_id_hash(
+        &self,
+        id: u64,
+        session_id_hash: &str,
+        last_seen_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let target_session = dsl::login_sessions.find(id);
+        let count = diesel::update(target_session)
+            .set((
+                dsl::session_id_hash.eq(session_id_hash),
+                dsl::last_seen_at.eq(last_seen_at),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Replaces the session id and the refresh token of a login session, which has been refreshed,
+    /// and keeps the old refresh token as rotated out until the session expires.
+    ///
+    /// It returns `false` if the refresh token of the session has been rotated meanwhile.
+    pub fn rotate_refresh_token(
+        &self,
+        session: &LoginSession,
+        session_id_hash: &str,
+        refresh_token_hash: &str,
+        last_seen_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let rotated_refresh_token_hash = match &session.refresh_token_hash {
+            Some(refresh_token_hash) => refresh_token_hash,
+            None => return Ok(false),
+        };
+
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let target_session = dsl::login_sessions
+                .find(session.id)
+                .filter(dsl::refresh_token_hash.eq(rotated_refresh_token_hash));
+            let count = diesel::update(target_session)
+                .set((
+                    dsl::session_id_hash.eq(session_id_hash),
+                    dsl::refresh_token_hash.eq(refresh_token_hash),
+                    dsl::last_seen_at.eq(last_seen_at),
+                ))
+                .execute(&self.conn)?;
+            if count == 0 {
+                return Ok(false);
+            }
+
+            let rotated_refresh_token_to_create = RotatedRefreshTokenDAO {
+                refresh_token_hash: rotated_refresh_token_hash.to_string(),
+                login_session_id: session.id,
+                expires_at: session.expires_at,
+            };
+            diesel::insert_into(rotated_refresh_tokens::table)
+                .values(rotated_refresh_token_to_create)
+                .execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Updates the time a login session has been used last.
+    pub fn update_last_seen_at(
+        &self,
+        id: u64,
+        last_seen_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let target_session = dsl::login_sessions.find(id);
+        let count = diesel::update(target_session)
+            .set(dsl::last_seen_at.eq(last_seen_at))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a login session of specific user.
+    pub fn delete(&self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let target_session = dsl::login_sessions
+            .find(id)
+            .filter(dsl::user_id.eq(user_id));
+        let count = diesel::delete(target_session).execute(&self.conn);
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a login session of specific user by the hash of its session id,
+    /// and returns whether it existed.
+    pub fn delete_by_session_id_hash(
+        &self,
+        user_id: u64,
+        session_id_hash: &str,
+    ) -> Result<bool, ServiceError> {
+        let target_session = dsl::login_sessions
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::session_id_hash.eq(session_id_hash));
+        let count = diesel::delete(target_session).execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a login session by the hash of its refresh token, and returns whether it existed.
+    pub fn delete_by_refresh_token_hash(
+        &self,
+        refresh_token_hash: &str,
+    ) -> Result<bool, ServiceError> {
+        let target_session =
+            dsl::login_sessions.filter(dsl::refresh_token_hash.eq(refresh_token_hash));
+        let count = diesel::delete(target_session).execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes all login sessions of specific user, and returns the number of them.
+    pub fn delete_all_by_user_id(&self, user_id: u64) -> Result<usize, ServiceError> {
+        match delete_by_user_id(&self.conn, user_id) {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes login sessions and rotated refresh tokens expired before `now`, and login sessions
+    /// not used since `last_seen_before`. It returns the number of the sessions.
+    pub fn delete_all_expired(
+        &self,
+        now: &NaiveDateTime,
+        last_seen_before: &NaiveDateTime,
+    ) -> Result<usize, ServiceError> {
+        let result = self.conn.transaction::<usize, Error, _>(|| {
+            diesel::delete(
+                rotated_refresh_tokens::table.filter(rotated_refresh_tokens::expires_at.lt(now)),
+            )
+            .execute(&self.conn)?;
+            diesel::delete(
+                dsl::login_sessions.filter(
+                    dsl::expires_at
+                        .lt(now)
+                        .or(dsl::last_seen_at.lt(last_seen_before)),
+                ),
+            )
+            .execute(&self.conn)
+        });
+
+        match result {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for LoginSessionRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
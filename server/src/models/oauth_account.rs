@@ -0,0 +1,106 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{oauth_accounts, oauth_accounts::dsl};
+use crate::utils::datetime_util;
+
+/// OAuth account representing `oauth_accounts` table.
+///
+/// It links an account of an OAuth provider to a user, who signs in with it instead of the password.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct OAuthAccount {
+    pub id: u64,
+    pub user_id: u64,
+    pub provider: String,
+    /// Id of the account in the provider.
+    pub subject: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// OAuth account DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "oauth_accounts"]
+struct OAuthAccountDAO {
+    user_id: u64,
+    provider: String,
+    subject: String,
+}
+
+/// Deletes OAuth accounts linked to specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::oauth_accounts.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for OAuth account.
+pub struct OAuthAccountRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait OAuthAccountRepositoryTrait {
+    fn find(&self, provider: &str, subject: &str) -> Result<OAuthAccount, ServiceError>;
+    fn create(&self, user_id: u64, provider: &str, subject: &str) -> Result<bool, ServiceError>;
+}
+
+impl OAuthAccountRepository {
+    /// Creates a new OAuth account repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds an OAuth account by its provider and id in the provider.
+    pub fn find(&self, provider: &str, subject: &str) -> Result<OAuthAccount, ServiceError> {
+        let account = dsl::oauth_accounts
+            .filter(dsl::provider.eq(provider))
+            .filter(dsl::subject.eq(subject))
+            .get_result::<OAuthAccount>(&self.conn);
+
+        match account {
+            Ok(account) => Ok(account),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(format!(
+                    "{}:{}",
+                    provider, subject
+                )))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Links an OAuth account to specific user.
+    pub fn create(
+        &self,
+        user_id: u64,
+        provider: &str,
+        subject: &str,
+    ) -> Result<bool, ServiceError> {
+        let account_to_create = OAuthAccountDAO {
+            user_id,
+            provider: provider.to_string(),
+            subject: subject.to_string(),
+        };
+
+        let count = diesel::insert_into(dsl::oauth_accounts)
+            .values(account_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for OAuthAccountRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,309 @@
+use reqwest::blocking::{Client, Response};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::env;
+use std::time::Duration;
+
+use crate::models::error::{get_service_error, ServiceError};
+
+/// Time limit of each request to OAuth providers.
+const OAUTH_PROVIDER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// User-Agent header GitHub API requires.
+const USER_AGENT: &str = "Darim";
+
+/// Account of a user in an OAuth provider.
+#[derive(Debug, PartialEq)]
+pub struct OAuthIdentity {
+    /// Id of the account in the provider, which does not change.
+    pub subject: String,
+    /// Email of the account, only if the provider has verified it.
+    pub verified_email: Option<String>,
+}
+
+/// OAuth2 provider which users sign in with by the authorization code flow.
+///
+/// The provider is selected by `get_provider`.
+pub trait OAuthProvider {
+    /// Returns the URL of the consent page of the provider, which redirects to `redirect_uri`
+    /// with a code and `state`.
+    fn get_authorization_url(&self, redirect_uri: &str, state: &str) -> String;
+    /// Exchanges a code with an access token, and returns the account it grants.
+    fn get_identity(&self, redirect_uri: &str, code: &str) -> Result<OAuthIdentity, ServiceError>;
+}
+
+/// Returns the provider of `name` whose client is configured, or `None` if it is not supported.
+///
+/// * `google` - `GOOGLE_CLIENT_ID` and `GOOGLE_CLIENT_SECRET`
+/// * `github` - `GITHUB_CLIENT_ID` and `GITHUB_CLIENT_SECRET`
+pub fn get_provider(name: &str) -> Option<Box<dyn OAuthProvider>> {
+    let get_client = |prefix: &str| -> Option<OAuthClient> {
+        Some(OAuthClient {
+            id: env::var(format!("{}_CLIENT_ID", prefix)).ok()?,
+            secret: env::var(format!("{}_CLIENT_SECRET", prefix)).ok()?,
+        })
+    };
+
+    match name {
+        "google" => Some(Box::new(GoogleProvider::new(get_client("GOOGLE")?))),
+        "github" => Some(Box::new(GitHubProvider::new(get_client("GITHUB")?))),
+        _ => None,
+    }
+}
+
+/// Returns the URI the provider of `name` redirects to with a code, which is the callback
+/// of the api gateway at `OAUTH_CALLBACK_BASE_URL`.
+///
+/// It must be registered in the client of the provider as it is.
+pub fn get_redirect_uri(name: &str) -> String {
+    let base_url = env::var("OAUTH_CALLBACK_BASE_URL").expect("OAUTH_CALLBACK_BASE_URL not found");
+    format!(
+        "{}/auth/oauth/{}/callback",
+        base_url.trim_end_matches('/'),
+        name
+    )
+}
+
+/// Client registered in an OAuth provider.
+pub struct OAuthClient {
+    pub id: String,
+    pub secret: String,
+}
+
+/// Access token response of RFC 6749.
+///
+/// GitHub responds an error without an access token in `200 OK` for a wrong code.
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: Option<String>,
+}
+
+/// Returns the URL of `base_url` with query parameters.
+fn build_url(base_url: &str, params: &[(&str, &str)]) -> String {
+    Url::parse_with_params(base_url, params)
+        .expect("Invalid OAuth provider URL")
+        .to_string()
+}
+
+/// Returns a new HTTP client requesting providers.
+fn get_http_client() -> Client {
+    Client::builder()
+        .timeout(OAUTH_PROVIDER_TIMEOUT)
+        .build()
+        .expect("Failed to build HTTP client")
+}
+
+/// Reads a JSON response of a provider.
+fn read_json<T: DeserializeOwned>(response: reqwest::Result<Response>) -> Result<T, ServiceError> {
+    match response.and_then(|response| response.error_for_status()) {
+        Ok(response) => response
+            .json()
+            .map_err(|_| get_service_error(ServiceError::InternalServerError)),
+        Err(error)
+            if error
+                .status()
+                .map_or(false, |status| status.is_client_error()) =>
+        {
+            Err(get_service_error(ServiceError::Unauthorized))
+        }
+        Err(_) => Err(get_service_error(ServiceError::InternalServerError)),
+    }
+}
+
+/// Exchanges a code with an access token at `token_url`.
+fn request_access_token(
+    http_client: &Client,
+    token_url: &str,
+    client: &OAuthClient,
+    redirect_uri: &str,
+    code: &str,
+) -> Result<String, ServiceError> {
+    let response = http_client
+        .post(token_url)
+        .header("Accept", "application/json")
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", client.id.as_str()),
+            ("client_secret", client.secret.as_str()),
+        ])
+        .send();
+    match read_json::<TokenResponse>(response)?.access_token {
+        Some(access_token) => Ok(access_token),
+        None => Err(get_service_error(ServiceError::Unauthorized)),
+    }
+}
+
+/// Google, whose accounts are identified by OpenID Connect.
+pub struct GoogleProvider {
+    http_client: Client,
+    client: OAuthClient,
+}
+
+/// Response of the userinfo endpoint of Google.
+#[derive(Deserialize)]
+struct GoogleUserInfo {
+    sub: String,
+    email: Option<String>,
+    email_verified: Option<bool>,
+}
+
+impl GoogleProvider {
+    /// Creates a new Google provider.
+    pub fn new(client: OAuthClient) -> Self {
+        Self {
+            http_client: get_http_client(),
+            client,
+        }
+    }
+}
+
+impl OAuthProvider for GoogleProvider {
+    fn get_authorization_url(&self, redirect_uri: &str, state: &str) -> String {
+        build_url(
+            "https://accounts.google.com/o/oauth2/v2/auth",
+            &[
+                ("response_type", "code"),
+                ("client_id", self.client.id.as_str()),
+                ("redirect_uri", redirect_uri),
+                ("scope", "openid email"),
+                ("state", state),
+            ],
+        )
+    }
+
+    fn get_identity(&self, redirect_uri: &str, code: &str) -> Result<OAuthIdentity, ServiceError> {
+        let access_token = request_access_token(
+            &self.http_client,
+            "https://oauth2.googleapis.com/token",
+            &self.client,
+            redirect_uri,
+            code,
+        )?;
+
+        let response = self
+            .http_client
+            .get("https://openidconnect.googleapis.com/v1/userinfo")
+            .bearer_auth(access_token)
+            .send();
+        let user_info: GoogleUserInfo = read_json(response)?;
+        let is_email_verified = user_info.email_verified == Some(true);
+
+        Ok(OAuthIdentity {
+            subject: user_info.sub,
+            verified_email: user_info.email.filter(|_| is_email_verified),
+        })
+    }
+}
+
+/// GitHub, whose accounts may have several emails.
+pub struct GitHubProvider {
+    http_client: Client,
+    client: OAuthClient,
+}
+
+/// Response of the user endpoint of GitHub.
+#[derive(Deserialize)]
+struct GitHubUser {
+    id: u64,
+}
+
+/// An email in the response of the emails endpoint of GitHub.
+#[derive(Deserialize)]
+struct GitHubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+impl GitHubProvider {
+    /// Creates a new GitHub provider.
+    pub fn new(client: OAuthClient) -> Self {
+        Self {
+            http_client: get_http_client(),
+            client,
+        }
+    }
+
+    /// Requests GitHub API with an access token.
+    fn get<T: DeserializeOwned>(&self, url: &str, access_token: &str) -> Result<T, ServiceError> {
+        let response = self
+            .http_client
+            .get(url)
+            .header("Accept", "application/vnd.github.v3+json")
+            .header("Authorization", format!("token {}", access_token))
+            .header("User-Agent", USER_AGENT)
+            .send();
+        read_json(response)
+    }
+}
+
+impl OAuthProvider for GitHubProvider {
+    fn get_authorization_url(&self, redirect_uri: &str, state: &str) -> String {
+        build_url(
+            "https://github.com/login/oauth/authorize",
+            &[
+                ("client_id", self.client.id.as_str()),
+                ("redirect_uri", redirect_uri),
+                ("scope", "user:email"),
+                ("state", state),
+                ("allow_signup", "false"),
+            ],
+        )
+    }
+
+    fn get_identity(&self, redirect_uri: &str, code: &str) -> Result<OAuthIdentity, ServiceError> {
+        let access_token = request_access_token(
+            &self.http_client,
+            "https://github.com/login/oauth/access_token",
+            &self.client,
+            redirect_uri,
+            code,
+        )?;
+
+        let user: GitHubUser = self.get("https://api.github.com/user", &access_token)?;
+        let emails: Vec<GitHubEmail> =
+            self.get("https://api.github.com/user/emails", &access_token)?;
+
+        Ok(OAuthIdentity {
+            subject: user.id.to_string(),
+            verified_email: emails
+                .into_iter()
+                .find(|email| email.primary && email.verified)
+                .map(|email| email.email),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn client() -> OAuthClient {
+        OAuthClient {
+            id: String::from("client id"),
+            secret: String::from("secret"),
+        }
+    }
+
+    #[test]
+    fn test_get_authorization_url() {
+        let redirect_uri = "https://api.darim.app/auth/oauth/google/callback";
+        assert_eq!(
+            GoogleProvider::new(client()).get_authorization_url(redirect_uri, "a1b2"),
+            "https://accounts.google.com/o/oauth2/v2/auth?response_type=code&client_id=client+id\
+            &redirect_uri=https%3A%2F%2Fapi.darim.app%2Fauth%2Foauth%2Fgoogle%2Fcallback\
+            &scope=openid+email&state=a1b2"
+        );
+
+        let redirect_uri = "https://api.darim.app/auth/oauth/github/callback";
+        assert_eq!(
+            GitHubProvider::new(client()).get_authorization_url(redirect_uri, "a1b2"),
+            "https://github.com/login/oauth/authorize?client_id=client+id\
+            &redirect_uri=https%3A%2F%2Fapi.darim.app%2Fauth%2Foauth%2Fgithub%2Fcallback\
+            &scope=user%3Aemail&state=a1b2&allow_signup=false"
+        );
+    }
+}
@@ -0,0 +1,245 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{personal_access_tokens, personal_access_tokens::dsl};
+use crate::utils::datetime_util;
+
+/// Personal access token representing `personal_access_tokens` table.
+///
+/// It is an API key a user mints for scripts, which are not able to sign in with a browser.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct PersonalAccessToken {
+    pub id: u64,
+    pub user_id: u64,
+    pub name: String,
+    /// SHA-256 hash of the token in hex.
+    pub token_hash: String,
+    pub scope: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+/// Personal access token DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PersonalAccessTokenDTO {
+    pub id: u64,
+    pub name: String,
+    pub scope: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+/// Personal access token DTO returned once when it is created, with the token itself.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct CreatedPersonalAccessTokenDTO {
+    pub id: u64,
+    pub name: String,
+    pub scope: String,
+    pub token: String,
+}
+
+/// Personal access token DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "personal_access_tokens"]
+struct PersonalAccessTokenDAO {
+    user_id: u64,
+    name: String,
+    token_hash: String,
+    scope: String,
+}
+
+/// Scopes of personal access tokens.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PersonalAccessTokenScope {
+    /// Allows reading posts only.
+    Read,
+    /// Allows reading and writing posts.
+    Write,
+}
+
+impl PersonalAccessTokenScope {
+    /// Returns the name of the scope stored in `personal_access_tokens` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+
+    /// Parses the name of the scope used in `scope` argument.
+    pub fn parse(scope: &str) -> Result<Self, ServiceError> {
+        match scope {
+            "read" => Ok(Self::Read),
+            "write" => Ok(Self::Write),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
+}
+
+/// Deletes personal access tokens of specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::personal_access_tokens.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for personal access token.
+pub struct PersonalAccessTokenRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait PersonalAccessTokenRepositoryTrait {
+    fn find_all_by_user_id(&self, user_id: u64) -> Result<Vec<PersonalAccessToken>, ServiceError>;
+    fn find_by_token_hash(&self, token_hash: &str) -> Result<PersonalAccessToken, ServiceError>;
+    fn count_by_user_id(&self, user_id: u64) -> Result<i64, ServiceError>;
+    fn create(
+        &self,
+        user_id: u64,
+        name: &str,
+        token_hash: &str,
+        scope: &str,
+    ) -> Result<u64, ServiceError>;
+    fn update_last_used_at(
+        &self,
+        id: u64,
+        last_used_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn delete(&self, id: u64, user_id: u64) -> Result<bool, ServiceError>;
+}
+
+impl PersonalAccessTokenRepository {
+    /// Creates a new personal access token repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds all personal access tokens of specific user, the most recently created first.
+    pub fn find_all_by_user_id(
+        &self,
+        user_id: u64,
+    ) -> Result<Vec<PersonalAccessToken>, ServiceError> {
+        let tokens = dsl::personal_access_tokens
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::id.desc())
+            .load::<PersonalAccessToken>(&self.conn);
+
+        match tokens {
+            Ok(tokens) => Ok(tokens),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds a personal access token by the hash of the token.
+    pub fn find_by_token_hash(
+        &self,
+        token_hash: &str,
+    ) -> Result<PersonalAccessToken, ServiceError> {
+        let token = dsl::personal_access_tokens
+            .filter(dsl::token_hash.eq(token_hash))
+            .get_result::<PersonalAccessToken>(&self.conn);
+
+        match token {
+            Ok(token) => Ok(token),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    token_hash.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Counts personal access tokens of specific user.
+    pub fn count_by_user_id(&self, user_id: u64) -> Result<i64, ServiceError> {
+        let count = dsl::personal_access_tokens
+            .filter(dsl::user_id.eq(user_id))
+            .count()
+            .get_result::<i64>(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new personal access token of specific user, and returns id of it.
+    pub fn create(
+        &self,
+        user_id: u64,
+        name: &str,
+        token_hash: &str,
+        scope: &str,
+    ) -> Result<u64, ServiceError> {
+        let token_to_create = PersonalAccessTokenDAO {
+            user_id,
+            name: name.to_string(),
+            token_hash: token_hash.to_string(),
+            scope: scope.to_string(),
+        };
+
+        let count = diesel::insert_into(dsl::personal_access_tokens)
+            .values(token_to_create)
+            .execute(&self.conn);
+
+        if count.is_err() {
+            return Err(get_service_error(ServiceError::QueryExecutionFailure));
+        }
+
+        let token = dsl::personal_access_tokens
+            .filter(dsl::token_hash.eq(token_hash))
+            .select(dsl::id)
+            .get_result::<u64>(&self.conn);
+
+        match token {
+            Ok(id) => Ok(id),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Updates the time a personal access token has been used last.
+    pub fn update_last_used_at(
+        &self,
+        id: u64,
+        last_used_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let target_token = dsl::personal_access_tokens.find(id);
+        let count = diesel::update(target_token)
+            .set(dsl::last_used_at.eq(last_used_at))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a personal access token of specific user.
+    pub fn delete(&self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let target_token = dsl::personal_access_tokens
+            .find(id)
+            .filter(dsl::user_id.eq(user_id));
+        let count = diesel::delete(target_token).execute(&self.conn);
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for PersonalAccessTokenRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,12 +1,146 @@
-use chrono::{NaiveDateTime, Utc};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset,
+    TimeZone, Utc,
+};
+use chrono_tz::Tz;
+use diesel::dsl::sql;
+use diesel::mysql::Mysql;
 use diesel::prelude::*;
 use diesel::result::Error;
+use diesel::sql_types::{
+    Bigint, Date, Datetime, Double, Integer, Nullable, Text, Unsigned, Varchar,
+};
 use mockall::automock;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
+use crate::models::attachment;
 use crate::models::connection;
 use crate::models::error::{get_service_error, ServiceError};
-use crate::schema::{posts, posts::dsl};
+use crate::models::journal;
+use crate::models::post_audit::{self, AuditContext, PostAuditAction};
+use crate::models::post_comment;
+use crate::models::post_revision::{self, PostRevision};
+use crate::models::post_share;
+use crate::models::post_tombstone;
+use crate::models::tag;
+use crate::schema::{post_audits, post_tags, posts, posts::dsl};
+use crate::utils::datetime_util;
+
+no_arg_sql_function!(
+    last_insert_id,
+    diesel::sql_types::Unsigned<diesel::sql_types::Bigint>
+);
+
+/// Local date of a post in SQL, which is the same as `PostDate::local_date`.
+const LOCAL_DATE_SQL: &str = "DATE(DATE_ADD(date, INTERVAL COALESCE(date_offset, 0) SECOND))";
+
+/// Month and day of the local date of a post in SQL, formatted as `MM-DD`.
+const MONTH_DAY_SQL: &str =
+    "DATE_FORMAT(DATE_ADD(date, INTERVAL COALESCE(date_offset, 0) SECOND), '%m-%d')";
+
+/// Distance in meters from the location of a post in SQL, to be followed by the longitude
+/// and latitude of the other point and closing parentheses.
+const DISTANCE_SQL: &str = "ST_Distance_Sphere(POINT(longitude, latitude), POINT(";
+
+/// Last modified datetime of a post in SQL, which is the created datetime if it is never updated.
+const MODIFIED_AT_SQL: &str = "COALESCE(updated_at, created_at)";
+
+/// Range of moods of posts, from the worst to the best.
+pub const MIN_MOOD: u8 = 1;
+pub const MAX_MOOD: u8 = 5;
+
+/// Maximum length of the name of a place where a post is written.
+pub const MAX_PLACE_NAME_LENGTH: usize = 255;
+
+/// Maximum lengths of the scheme and the base64 nonce of the encryption of a post.
+pub const MAX_ENCRYPTION_SCHEME_LENGTH: usize = 32;
+pub const MAX_ENCRYPTION_NONCE_LENGTH: usize = 64;
+
+/// Number of posts on a local date, which is a row of `PostRepository::count_by_date`.
+#[derive(QueryableByName)]
+struct DateCount {
+    #[sql_type = "Date"]
+    local_date: NaiveDate,
+    #[sql_type = "Bigint"]
+    count: i64,
+}
+
+/// Number of posts and revisions encrypted with a key version in a scheme, which is a row of
+/// `PostRepository::count_by_key_version`.
+#[derive(QueryableByName)]
+struct KeyVersionRow {
+    #[sql_type = "Nullable<Varchar>"]
+    encryption_scheme: Option<String>,
+    #[sql_type = "Nullable<Unsigned<Integer>>"]
+    key_version: Option<u32>,
+    #[sql_type = "Bigint"]
+    post_count: i64,
+    #[sql_type = "Bigint"]
+    revision_count: i64,
+}
+
+/// Date of a post, stored as UTC with the offset where the post was written.
+///
+/// Posts written before the offset was recorded have no offset, and their
+/// date is a local datetime of the writer rather than UTC.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PostDate {
+    pub date: NaiveDateTime,
+    pub offset: Option<i32>,
+}
+
+impl PostDate {
+    /// Parses RFC 3339 datetime with offset, or naive datetime sent by legacy clients.
+    pub fn parse(date: &str) -> Result<Self, ServiceError> {
+        if let Ok(date) = DateTime::parse_from_rfc3339(date) {
+            return Ok(Self {
+                date: date.naive_utc(),
+                offset: Some(date.offset().local_minus_utc()),
+            });
+        }
+
+        match NaiveDateTime::parse_from_str(date, "%Y-%m-%dT%H:%M:%S%.f") {
+            Ok(date) => Ok(Self { date, offset: None }),
+            Err(_) => Err(get_service_error(ServiceError::InvalidFormat)),
+        }
+    }
+
+    /// Returns the date of a post written at a local datetime in a time zone.
+    ///
+    /// A local datetime repeated by a daylight saving transition takes the earlier offset,
+    /// and one skipped by it takes the offset at the same datetime in UTC.
+    pub fn from_local(date: NaiveDateTime, timezone: &Tz) -> Self {
+        let offset = match timezone.offset_from_local_datetime(&date) {
+            LocalResult::Single(offset) | LocalResult::Ambiguous(offset, _) => offset,
+            LocalResult::None => timezone.offset_from_utc_datetime(&date),
+        }
+        .fix()
+        .local_minus_utc();
+        Self {
+            date: date - Duration::seconds(i64::from(offset)),
+            offset: Some(offset),
+        }
+    }
+
+    /// Returns the calendar date in the offset where the post was written.
+    pub fn local_date(&self) -> NaiveDate {
+        match self.offset {
+            Some(offset) => (self.date + Duration::seconds(i64::from(offset))).date(),
+            None => self.date.date(),
+        }
+    }
+
+    /// Formats the date in RFC 3339 with the original offset.
+    ///
+    /// A date without offset is formatted as naive datetime.
+    pub fn to_rfc3339(&self) -> String {
+        match self.offset.map(FixedOffset::east_opt) {
+            Some(Some(offset)) => offset.from_utc_datetime(&self.date).to_rfc3339(),
+            _ => self.date.format("%Y-%m-%dT%H:%M:%S%.f").to_string(),
+        }
+    }
+}
 
 /// Post representing `posts` table.
 #[derive(Debug, Serialize, Deserialize, Queryable)]
@@ -15,9 +149,357 @@ pub struct Post {
     pub user_id: u64,
     pub title: String,
     pub content: String,
+    #[serde(with = "datetime_util::rfc3339")]
     pub date: NaiveDateTime,
+    pub date_offset: Option<i32>,
+    pub intra_day_order: u16,
+    #[serde(with = "datetime_util::rfc3339")]
     pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
     pub updated_at: Option<NaiveDateTime>,
+    pub version: u32,
+    /// Datetime when the post was moved to the trash, or `None` if it is not in the trash.
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub deleted_at: Option<NaiveDateTime>,
+    /// Name of `PostStatus` of the post.
+    pub status: String,
+    /// Datetime when the revision of the ongoing autosaves was taken,
+    /// or `None` if the post is not autosaved since it was updated.
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub autosave_started_at: Option<NaiveDateTime>,
+    /// Datetime when the post was changed in any way, which is set by RDB.
+    #[serde(with = "datetime_util::rfc3339")]
+    pub changed_at: NaiveDateTime,
+    /// Whether the post is marked as a favorite by the user.
+    pub is_favorite: bool,
+    /// Mood of the writer from 1 (worst) to 5 (best), if it is recorded.
+    pub mood: Option<u8>,
+    /// Name of `PostWeather` of the post, if it is recorded.
+    pub weather: Option<String>,
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Name of the place where the post was written, encrypted by the client.
+    pub place_name: Option<String>,
+    /// Id of the journal the post is in.
+    pub journal_id: u64,
+    /// Number of words in the content counted by the client, or `None` if it is not given
+    /// since the content was changed.
+    pub word_count: Option<u32>,
+    /// Whether the post is locked against updates and deletion.
+    pub is_locked: bool,
+    /// Whether the title and content are ciphertexts encrypted by the client.
+    pub is_encrypted: bool,
+    /// Name of the scheme the client encrypted the post with, or `None` for posts encrypted
+    /// before the scheme was recorded.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption, if the scheme uses one.
+    pub encryption_nonce: Option<String>,
+    /// Version of the key of the user the post is encrypted with.
+    pub key_version: Option<u32>,
+}
+
+impl Post {
+    /// Returns the date of the post with its offset.
+    pub fn post_date(&self) -> PostDate {
+        PostDate {
+            date: self.date,
+            offset: self.date_offset,
+        }
+    }
+
+    /// Returns the location of the post, if it is recorded.
+    pub fn location(&self) -> Option<PostLocation> {
+        match (self.latitude, self.longitude) {
+            (Some(latitude), Some(longitude)) => Some(PostLocation {
+                latitude,
+                longitude,
+                place_name: self.place_name.clone(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns how the title and content of the post are encrypted.
+    pub fn encryption(&self) -> PostEncryption {
+        PostEncryption {
+            is_encrypted: self.is_encrypted,
+            scheme: self.encryption_scheme.clone(),
+            nonce: self.encryption_nonce.clone(),
+            key_version: self.key_version,
+        }
+    }
+}
+
+/// Encryption of the title and content of a post.
+///
+/// The server never decrypts posts. The title and content are stored as the client sent them,
+/// and the encryption is recorded for clients to decrypt them and to rotate keys.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostEncryption {
+    pub is_encrypted: bool,
+    pub scheme: Option<String>,
+    pub nonce: Option<String>,
+    pub key_version: Option<u32>,
+}
+
+impl PostEncryption {
+    /// Returns the encryption of posts written by clients which do not record it,
+    /// which are encrypted in an unknown scheme.
+    pub fn legacy() -> Self {
+        Self {
+            is_encrypted: true,
+            scheme: None,
+            nonce: None,
+            key_version: None,
+        }
+    }
+
+    /// Checks an encryption given in arguments.
+    ///
+    /// A plaintext post cannot have the other fields, and a nonce requires a scheme.
+    pub fn parse(encryption: &PostEncryptionDTO) -> Result<Option<Self>, ServiceError> {
+        if !encryption.is_given() {
+            return Ok(None);
+        }
+
+        let is_encrypted = encryption.encrypted.unwrap_or(true);
+        if !is_encrypted
+            && (encryption.encryption_scheme.is_some()
+                || encryption.nonce.is_some()
+                || encryption.key_version.is_some())
+        {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        if let Some(scheme) = &encryption.encryption_scheme {
+            if scheme.is_empty()
+                || scheme.len() > MAX_ENCRYPTION_SCHEME_LENGTH
+                || !scheme
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+            {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        if let Some(nonce) = &encryption.nonce {
+            if encryption.encryption_scheme.is_none()
+                || nonce.is_empty()
+                || nonce.len() > MAX_ENCRYPTION_NONCE_LENGTH
+                || !nonce
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '+' || c == '/' || c == '=')
+            {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        if encryption.key_version == Some(0) {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        Ok(Some(Self {
+            is_encrypted,
+            scheme: encryption.encryption_scheme.clone(),
+            nonce: encryption.nonce.clone(),
+            key_version: encryption.key_version,
+        }))
+    }
+}
+
+/// Location where a post is written.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostLocation {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub place_name: Option<String>,
+}
+
+impl PostLocation {
+    /// Checks a location given in arguments, which requires both latitude and longitude
+    /// if any of its fields is given.
+    pub fn parse(location: &PostLocationDTO) -> Result<Option<Self>, ServiceError> {
+        if !location.is_given() {
+            return Ok(None);
+        }
+
+        let (latitude, longitude) = match (location.latitude, location.longitude) {
+            (Some(latitude), Some(longitude)) => (latitude, longitude),
+            _ => return Err(get_service_error(ServiceError::InvalidArgument)),
+        };
+        if !(-90.0..=90.0).contains(&latitude) || !(-180.0..=180.0).contains(&longitude) {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        if let Some(place_name) = &location.place_name {
+            if place_name.trim().is_empty() || place_name.chars().count() > MAX_PLACE_NAME_LENGTH {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        Ok(Some(Self {
+            latitude,
+            longitude,
+            place_name: location.place_name.clone(),
+        }))
+    }
+}
+
+/// Circle on the earth to find posts written in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GeoCircle {
+    pub latitude: f64,
+    pub longitude: f64,
+    /// Radius in meters.
+    pub radius: f64,
+}
+
+/// Statuses of posts.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostStatus {
+    Published,
+    /// An unfinished post, which is not listed unless it is requested.
+    Draft,
+}
+
+impl PostStatus {
+    /// Returns the name of the status stored in `posts` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Published => "published",
+            Self::Draft => "draft",
+        }
+    }
+
+    /// Parses the name of the status used in `status` argument.
+    pub fn parse(status: &str) -> Result<Self, ServiceError> {
+        match status {
+            "published" => Ok(Self::Published),
+            "draft" => Ok(Self::Draft),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
+}
+
+/// Weathers of the days posts are written.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostWeather {
+    Sunny,
+    Cloudy,
+    Rainy,
+    Snowy,
+    Windy,
+    Foggy,
+    Stormy,
+}
+
+impl PostWeather {
+    /// Returns the name of the weather stored in `posts` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Sunny => "sunny",
+            Self::Cloudy => "cloudy",
+            Self::Rainy => "rainy",
+            Self::Snowy => "snowy",
+            Self::Windy => "windy",
+            Self::Foggy => "foggy",
+            Self::Stormy => "stormy",
+        }
+    }
+
+    /// Parses the name of the weather used in `weather` argument.
+    pub fn parse(weather: &str) -> Result<Self, ServiceError> {
+        match weather {
+            "sunny" => Ok(Self::Sunny),
+            "cloudy" => Ok(Self::Cloudy),
+            "rainy" => Ok(Self::Rainy),
+            "snowy" => Ok(Self::Snowy),
+            "windy" => Ok(Self::Windy),
+            "foggy" => Ok(Self::Foggy),
+            "stormy" => Ok(Self::Stormy),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
+}
+
+/// Conditions of posts to find.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct PostFilter {
+    /// An id of a tag the posts have.
+    pub tag_id: Option<u64>,
+    /// An id of a journal the posts are in.
+    pub journal_id: Option<u64>,
+    /// The first local date of the posts, inclusive.
+    pub from: Option<NaiveDate>,
+    /// The last local date of the posts, inclusive.
+    pub to: Option<NaiveDate>,
+    /// A status of the posts, or any status if `None`.
+    pub status: Option<PostStatus>,
+    /// Pairs of month and day of the local dates of the posts in any year, or any date if empty.
+    pub month_days: Vec<(u32, u32)>,
+    /// Whether the posts are favorites, or any post if `None`.
+    pub is_favorite: Option<bool>,
+    /// A circle the locations of the posts are in.
+    pub near: Option<GeoCircle>,
+}
+
+/// Fields of posts to be loaded in a list.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostFields {
+    All,
+    /// Every field except the content, which is loaded as empty.
+    Summary,
+}
+
+impl PostFields {
+    /// Parses the name of the fields used in `fields` argument.
+    pub fn parse(fields: &str) -> Result<Self, ServiceError> {
+        match fields {
+            "all" => Ok(Self::All),
+            "summary" => Ok(Self::Summary),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
+}
+
+/// Keys to sort posts by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostSortKey {
+    /// Local date of the posts, and the order in each day.
+    Date,
+    CreatedAt,
+    /// Updated datetime, or created datetime of posts never updated.
+    UpdatedAt,
+}
+
+impl PostSortKey {
+    /// Parses the name of the key used in `sort_by` argument.
+    pub fn parse(sort_by: &str) -> Result<Self, ServiceError> {
+        match sort_by {
+            "date" => Ok(Self::Date),
+            "created_at" => Ok(Self::CreatedAt),
+            "updated_at" => Ok(Self::UpdatedAt),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
+}
+
+/// Directions to sort posts in.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    /// Parses the name of the direction used in `order` argument.
+    pub fn parse(order: &str) -> Result<Self, ServiceError> {
+        match order {
+            "asc" => Ok(Self::Asc),
+            "desc" => Ok(Self::Desc),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
 }
 
 /// Post DTO using between routes layer and service layer.
@@ -26,9 +508,91 @@ pub struct PostDTO {
     pub id: u64,
     pub title: String,
     pub content: String,
-    pub date: NaiveDateTime,
+    pub date: String,
+    pub intra_day_order: u16,
+    /// Ids of tags of the post.
+    pub tags: Vec<u64>,
+    /// Id of the journal of the post.
+    pub journal_id: u64,
+    /// `published` or `draft`.
+    pub status: String,
+    #[serde(with = "datetime_util::rfc3339")]
     pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
     pub updated_at: Option<NaiveDateTime>,
+    pub version: u32,
+    pub is_favorite: bool,
+    /// Mood from 1 (worst) to 5 (best).
+    pub mood: Option<u8>,
+    /// `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`.
+    pub weather: Option<String>,
+    #[serde(flatten)]
+    pub location: PostLocationDTO,
+    /// Number of words in the content counted by the client.
+    pub word_count: Option<u32>,
+    pub is_locked: bool,
+    #[serde(flatten)]
+    pub encryption: PostEncryptionDTO,
+}
+
+/// Location of a post DTO using between routes layer and service layer.
+///
+/// It is flattened into the post, so that the fields are given as fields of the post.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PostLocationDTO {
+    pub latitude: Option<f64>,
+    pub longitude: Option<f64>,
+    /// Name of the place, encrypted by the client.
+    pub place_name: Option<String>,
+}
+
+impl PostLocationDTO {
+    /// Returns whether any field of the location is given.
+    pub fn is_given(&self) -> bool {
+        self.latitude.is_some() || self.longitude.is_some() || self.place_name.is_some()
+    }
+}
+
+/// Encryption of a post DTO using between routes layer and service layer.
+///
+/// It is flattened into the post, so that the fields are given as fields of the post.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PostEncryptionDTO {
+    /// Whether the title and content are encrypted by the client. The server treats them
+    /// as opaque either way.
+    pub encrypted: Option<bool>,
+    /// Name of the scheme such as `aes-256-gcm`, in lowercase letters, digits and hyphens.
+    pub encryption_scheme: Option<String>,
+    /// Base64 nonce of the encryption.
+    pub nonce: Option<String>,
+    /// Version of the key of the user, starting from 1.
+    pub key_version: Option<u32>,
+}
+
+impl PostEncryptionDTO {
+    /// Returns whether any field of the encryption is given.
+    pub fn is_given(&self) -> bool {
+        self.encrypted.is_some()
+            || self.encryption_scheme.is_some()
+            || self.nonce.is_some()
+            || self.key_version.is_some()
+    }
+}
+
+/// Post in the trash DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct TrashedPostDTO {
+    pub id: u64,
+    pub title: String,
+    pub content: String,
+    pub date: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub deleted_at: NaiveDateTime,
+    /// Datetime after which the post is permanently deleted.
+    #[serde(with = "datetime_util::rfc3339")]
+    pub purge_at: NaiveDateTime,
+    #[serde(flatten)]
+    pub encryption: PostEncryptionDTO,
 }
 
 /// Summarized post DTO using between routes layer and service layer.
@@ -36,7 +600,316 @@ pub struct PostDTO {
 pub struct SummarizedPostDTO {
     pub id: u64,
     pub title: String,
-    pub date: NaiveDateTime,
+    pub date: String,
+}
+
+/// Posts of a day in a calendar DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct CalendarDayDTO {
+    pub date: NaiveDate,
+    pub count: usize,
+    pub posts: Vec<SummarizedPostDTO>,
+}
+
+/// Year in an archive DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveYearDTO {
+    pub year: i32,
+    pub count: usize,
+    pub months: Vec<ArchiveMonthDTO>,
+}
+
+/// Month in an archive DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveMonthDTO {
+    pub month: u32,
+    pub count: usize,
+}
+
+/// Posts of a month in an archive DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct ArchiveMonthPostsDTO {
+    pub month: u32,
+    pub count: usize,
+    pub posts: Vec<SummarizedPostDTO>,
+}
+
+/// Numbers of posts on each day of a year DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct HeatmapDTO {
+    pub year: i32,
+    /// Number of posts on each day from January 1.
+    pub counts: Vec<usize>,
+}
+
+/// Writing streak DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct StreakDTO {
+    /// Number of consecutive days with posts until today, or until yesterday
+    /// if no post is written today yet.
+    pub current_streak: u32,
+    /// Number of consecutive days with posts of the longest run.
+    pub longest_streak: u32,
+    /// The local date of the last post.
+    pub last_entry_date: Option<NaiveDate>,
+}
+
+/// Progress toward word goals DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct WordGoalProgressDTO {
+    /// The local date of the user the progress is counted on.
+    pub date: NaiveDate,
+    pub daily: WordGoalDTO,
+    pub monthly: WordGoalDTO,
+}
+
+/// Progress toward a word goal DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct WordGoalDTO {
+    /// Number of words to write in the period, or `None` if the goal is not set.
+    pub goal: Option<u32>,
+    /// Number of words written in the period.
+    pub word_count: u64,
+    pub is_achieved: bool,
+}
+
+/// Number of posts in a period DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct EntryCountDTO {
+    /// The first date of the period.
+    pub date: NaiveDate,
+    pub count: usize,
+}
+
+/// Moods of posts in a period DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct MoodTrendDTO {
+    /// The first date of the period.
+    pub date: NaiveDate,
+    /// Number of posts with moods in the period.
+    pub count: usize,
+    pub average: f64,
+    /// Number of posts of each mood from 1 to 5.
+    pub distribution: [usize; 5],
+}
+
+/// Deleted post DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct DeletedPostDTO {
+    pub id: u64,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub deleted_at: NaiveDateTime,
+}
+
+/// Changes of posts DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct PostChangesDTO {
+    /// Posts created or changed since `since`, except posts in the trash.
+    pub posts: Vec<PostDTO>,
+    /// Posts moved to the trash or permanently deleted since `since`.
+    pub deleted: Vec<DeletedPostDTO>,
+    /// `since` of the next sync.
+    #[serde(with = "datetime_util::rfc3339")]
+    pub cursor: NaiveDateTime,
+}
+
+/// Returns ids of posts in a day after moving a post to `position`.
+///
+/// # Arguments
+///
+/// * `day_post_ids` - Ids of posts written on a day, in the order of the day.
+/// * `post_id` - An id of the post to be moved.
+/// * `position` - A position starting from 0. It is clamped to the last position.
+pub fn move_in_day(day_post_ids: &[u64], post_id: u64, position: usize) -> Vec<u64> {
+    let mut moved_post_ids: Vec<u64> = day_post_ids
+        .iter()
+        .copied()
+        .filter(|id| *id != post_id)
+        .collect();
+    let position = position.min(moved_post_ids.len());
+    moved_post_ids.insert(position, post_id);
+    moved_post_ids
+}
+
+/// Data removed by deleting posts of a date.
+#[derive(Debug)]
+pub struct PostDateDeletion {
+    pub post_ids: Vec<u64>,
+    pub post_audit_count: usize,
+    pub attachment_count: usize,
+}
+
+/// Post date deletion DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct PostDateDeletionDTO {
+    pub dry_run: bool,
+    pub date: NaiveDate,
+    pub post_ids: Vec<u64>,
+    pub post_audit_count: usize,
+    pub attachment_count: usize,
+}
+
+/// Changes of posts written by a user, found for clients syncing changes.
+#[derive(Debug)]
+pub struct PostChanges {
+    /// Posts created or changed since the last sync, except posts in the trash.
+    pub posts: Vec<Post>,
+    /// Pairs of id and deletion datetime of posts moved to the trash or permanently deleted
+    /// since the last sync.
+    pub deleted_posts: Vec<(u64, NaiveDateTime)>,
+    /// Datetime of RDB when the changes are found.
+    pub found_at: NaiveDateTime,
+}
+
+/// Post to be created by importing an archive.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PostToImport {
+    pub title: String,
+    pub content: String,
+    pub date: PostDate,
+    pub tag_ids: Vec<u64>,
+    pub status: PostStatus,
+    pub is_favorite: bool,
+    pub mood: Option<u8>,
+    pub weather: Option<PostWeather>,
+    pub location: Option<PostLocation>,
+    /// Datetime when the post was moved to the trash, if it is imported into the trash.
+    pub deleted_at: Option<NaiveDateTime>,
+    pub encryption: PostEncryption,
+}
+
+/// Operation on a post executed in bulk.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PostOperation {
+    Create {
+        title: String,
+        content: String,
+        date: PostDate,
+        tag_ids: Vec<u64>,
+        status: PostStatus,
+        mood: Option<u8>,
+        weather: Option<PostWeather>,
+        location: Option<PostLocation>,
+        journal_id: Option<u64>,
+        word_count: Option<u32>,
+        encryption: PostEncryption,
+    },
+    Update {
+        post_id: u64,
+        title: Option<String>,
+        content: Option<String>,
+        date: Option<PostDate>,
+        tag_ids: Option<Vec<u64>>,
+        mood: Option<u8>,
+        weather: Option<PostWeather>,
+        location: Option<PostLocation>,
+        journal_id: Option<u64>,
+        word_count: Option<u32>,
+        encryption: Option<PostEncryption>,
+        version: Option<u32>,
+    },
+    Delete {
+        post_id: u64,
+    },
+}
+
+/// Operation on a post DTO using between routes layer and service layer.
+///
+/// The kind of the operation is distinguished by `op` field.
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum PostOperationDTO {
+    Create {
+        title: String,
+        content: String,
+        /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+        date: String,
+        /// Ids of tags of the post.
+        tags: Option<Vec<u64>>,
+        /// `published` or `draft`.
+        status: Option<String>,
+        /// Mood from 1 (worst) to 5 (best).
+        mood: Option<u8>,
+        /// `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`.
+        weather: Option<String>,
+        #[serde(flatten)]
+        location: PostLocationDTO,
+        /// Id of the journal of the post, which is the default journal if omitted.
+        journal_id: Option<u64>,
+        /// Number of words in the content counted by the client.
+        word_count: Option<u32>,
+        #[serde(flatten)]
+        encryption: PostEncryptionDTO,
+    },
+    Update {
+        id: u64,
+        title: Option<String>,
+        content: Option<String>,
+        /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+        date: Option<String>,
+        /// Ids of tags replacing the tags of the post.
+        tags: Option<Vec<u64>>,
+        mood: Option<u8>,
+        weather: Option<String>,
+        /// Location replacing the location of the post.
+        #[serde(flatten)]
+        location: PostLocationDTO,
+        /// Id of the journal the post is moved to.
+        journal_id: Option<u64>,
+        /// Number of words in the new content counted by the client.
+        word_count: Option<u32>,
+        /// Encryption of the new title and content.
+        #[serde(flatten)]
+        encryption: PostEncryptionDTO,
+        /// Version of the post the edit is based on.
+        version: Option<u32>,
+    },
+    Delete {
+        id: u64,
+    },
+}
+
+/// Number of posts and revisions of a user encrypted with a key version in a scheme.
+///
+/// Posts encrypted before the scheme was recorded have neither scheme nor key version.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct KeyVersionCountDTO {
+    pub encryption_scheme: Option<String>,
+    pub key_version: Option<u32>,
+    /// Number of posts including posts in the trash.
+    pub post_count: i64,
+    pub revision_count: i64,
+}
+
+/// Results of importing a file of an archive.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportedFileStatus {
+    Created,
+    /// A post of the same date and title already exists, so the file is not imported.
+    Duplicated,
+    Failed,
+}
+
+/// Imported file DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct ImportedFileDTO {
+    pub path: String,
+    pub status: ImportedFileStatus,
+    /// An id of the created post, which is `None` unless the post is created.
+    pub post_id: Option<u64>,
+    pub error: Option<String>,
+}
+
+/// Post import DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct PostImportDTO {
+    pub dry_run: bool,
+    pub created_count: usize,
+    pub duplicated_count: usize,
+    pub failed_count: usize,
+    pub created_tag_count: usize,
+    pub files: Vec<ImportedFileDTO>,
 }
 
 /// Post DAO using between models layer and RDB.
@@ -48,7 +921,23 @@ struct PostDAO {
     title: Option<String>,
     content: Option<String>,
     date: Option<NaiveDateTime>,
+    date_offset: Option<i32>,
+    intra_day_order: Option<u16>,
     updated_at: Option<NaiveDateTime>,
+    deleted_at: Option<NaiveDateTime>,
+    status: Option<String>,
+    is_favorite: Option<bool>,
+    mood: Option<u8>,
+    weather: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    place_name: Option<String>,
+    journal_id: Option<u64>,
+    word_count: Option<u32>,
+    is_encrypted: Option<bool>,
+    encryption_scheme: Option<String>,
+    encryption_nonce: Option<String>,
+    key_version: Option<u32>,
 }
 
 /// A core data repository for post.
@@ -60,23 +949,177 @@ pub struct PostRepository {
 pub trait PostRepositoryTrait {
     fn find(&self, user_id: u64, post_id: u64) -> Result<Post, ServiceError>;
     fn find_all(&self, user_id: u64) -> Result<Vec<Post>, ServiceError>;
-    fn find_all_in_desc_date_order(&self, user_id: u64) -> Result<Vec<Post>, ServiceError>;
+    fn find_list(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+        fields: PostFields,
+        sort_key: PostSortKey,
+        sort_order: SortOrder,
+        offset_and_limit: &Option<(i64, i64)>,
+    ) -> Result<Vec<Post>, ServiceError>;
+    fn count(&self, user_id: u64, filter: &PostFilter) -> Result<i64, ServiceError>;
+    fn find_summaries(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<(u64, String, PostDate)>, ServiceError>;
+    fn find_tag_ids(&self, post_ids: &[u64]) -> Result<HashMap<u64, Vec<u64>>, ServiceError>;
+    fn find_local_dates(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<NaiveDate>, ServiceError>;
+    fn find_dates(&self, user_id: u64, filter: &PostFilter) -> Result<Vec<PostDate>, ServiceError>;
+    fn find_all_without_date_offset(
+        &self,
+        after_id: u64,
+        limit: i64,
+    ) -> Result<Vec<(u64, u64, NaiveDateTime)>, ServiceError>;
+    fn set_date_offset(&self, id: u64, date: &PostDate) -> Result<bool, ServiceError>;
+    fn count_by_month(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<(i32, u32, usize)>, ServiceError>;
+    fn count_by_date(
+        &self,
+        user_id: u64,
+        from: &NaiveDate,
+        to: &NaiveDate,
+    ) -> Result<Vec<(NaiveDate, usize)>, ServiceError>;
+    fn find_moods(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<(NaiveDate, u8)>, ServiceError>;
+    fn sum_word_counts(&self, user_id: u64, filter: &PostFilter) -> Result<u64, ServiceError>;
+    fn find_all_trashed(&self, user_id: u64) -> Result<Vec<Post>, ServiceError>;
+    fn count_trashed_before(
+        &self,
+        user_id: u64,
+        threshold: &NaiveDateTime,
+    ) -> Result<usize, ServiceError>;
+    fn find_changes(
+        &self,
+        user_id: u64,
+        since: &Option<NaiveDateTime>,
+    ) -> Result<PostChanges, ServiceError>;
+    fn find_revisions(&self, user_id: u64, post_id: u64)
+        -> Result<Vec<PostRevision>, ServiceError>;
+    fn find_revision(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        version: u32,
+    ) -> Result<PostRevision, ServiceError>;
     fn create(
         &self,
         user_id: u64,
         title: &str,
         content: &str,
-        date: &NaiveDateTime,
-    ) -> Result<bool, ServiceError>;
+        date: &PostDate,
+        tag_ids: &[u64],
+        status: PostStatus,
+        mood: Option<u8>,
+        weather: Option<PostWeather>,
+        location: &Option<PostLocation>,
+        journal_id: Option<u64>,
+        word_count: Option<u32>,
+        encryption: &PostEncryption,
+        audit_context: &AuditContext,
+    ) -> Result<u64, ServiceError>;
+    fn duplicate(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        date: &PostDate,
+        audit_context: &AuditContext,
+    ) -> Result<u64, ServiceError>;
     fn update(
         &self,
         user_id: u64,
         post_id: u64,
         title: &Option<String>,
         content: &Option<String>,
-        date: &Option<NaiveDateTime>,
+        date: &Option<PostDate>,
+        tag_ids: &Option<Vec<u64>>,
+        mood: &Option<u8>,
+        weather: &Option<PostWeather>,
+        location: &Option<PostLocation>,
+        journal_id: &Option<u64>,
+        word_count: &Option<u32>,
+        encryption: &Option<PostEncryption>,
+        version: &Option<u32>,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError>;
+    fn delete(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError>;
+    fn restore(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError>;
+    fn autosave(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        title: &Option<String>,
+        content: &str,
+        encryption: &PostEncryption,
+        version: u32,
+        revises: bool,
+        autosaved_at: &NaiveDateTime,
+        audit_context: &AuditContext,
+    ) -> Result<u32, ServiceError>;
+    fn publish(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError>;
+    fn set_favorite(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        is_favorite: bool,
+    ) -> Result<bool, ServiceError>;
+    fn set_locked(&self, user_id: u64, post_id: u64, is_locked: bool)
+        -> Result<bool, ServiceError>;
+    fn import(
+        &self,
+        user_id: u64,
+        post_list: &[PostToImport],
+        audit_context: &AuditContext,
+        dry_run: bool,
+    ) -> Result<Vec<u64>, ServiceError>;
+    fn purge_trashed(&self, threshold: &NaiveDateTime) -> Result<usize, ServiceError>;
+    fn prune_tombstones(&self, threshold: &NaiveDateTime) -> Result<usize, ServiceError>;
+    fn delete_by_date(
+        &self,
+        user_id: u64,
+        date: &NaiveDate,
+        dry_run: bool,
+    ) -> Result<PostDateDeletion, ServiceError>;
+    fn reorder(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        position: usize,
+        audit_context: &AuditContext,
     ) -> Result<bool, ServiceError>;
-    fn delete(&self, user_id: u64, post_id: u64) -> Result<bool, ServiceError>;
+    fn execute_bulk(
+        &self,
+        user_id: u64,
+        operations: &[PostOperation],
+        audit_context: &AuditContext,
+    ) -> Result<Vec<Result<u64, ServiceError>>, ServiceError>;
+    fn count_by_key_version(&self, user_id: u64) -> Result<Vec<KeyVersionCountDTO>, ServiceError>;
 }
 
 impl PostRepository {
@@ -87,13 +1130,68 @@ impl PostRepository {
         }
     }
 
-    /// Finds a post by user id and post id.
-    pub fn find(&self, user_id: u64, post_id: u64) -> Result<Post, ServiceError> {
-        let post: Result<Post, Error> = dsl::posts
-            .find(post_id)
+    /// Finds ids and orders of posts written by specific user on a date, in the order of the day.
+    ///
+    /// The date of each post is compared in the offset where the post was written.
+    /// Posts in the same order are sorted in desc date order.
+    /// Posts in the trash are found only if `include_trashed` is true.
+    fn find_day_posts(
+        &self,
+        user_id: u64,
+        date: &NaiveDate,
+        include_trashed: bool,
+    ) -> Result<Vec<(u64, u16)>, Error> {
+        let mut query = dsl::posts
+            .select((dsl::id, dsl::date, dsl::date_offset, dsl::intra_day_order))
             .filter(dsl::user_id.eq(user_id))
-            .get_result::<Post>(&self.conn);
-
+            .order((dsl::date.desc(), dsl::id.desc()))
+            .into_boxed();
+        if !include_trashed {
+            query = query.filter(dsl::deleted_at.is_null());
+        }
+
+        let mut day_posts: Vec<(u64, u16)> = query
+            .load::<(u64, NaiveDateTime, Option<i32>, u16)>(&self.conn)?
+            .into_iter()
+            .filter(|(_, post_date, offset, _)| {
+                PostDate {
+                    date: *post_date,
+                    offset: *offset,
+                }
+                .local_date()
+                    == *date
+            })
+            .map(|(id, _, _, intra_day_order)| (id, intra_day_order))
+            .collect();
+        day_posts.sort_by_key(|(_, intra_day_order)| *intra_day_order);
+        Ok(day_posts)
+    }
+
+    /// Returns the order following the last post of the date.
+    fn get_next_intra_day_order(
+        &self,
+        user_id: u64,
+        date: &NaiveDate,
+        excluded_post_id: Option<u64>,
+    ) -> Result<u16, Error> {
+        let next_intra_day_order = self
+            .find_day_posts(user_id, date, false)?
+            .into_iter()
+            .filter(|(id, _)| Some(*id) != excluded_post_id)
+            .map(|(_, intra_day_order)| intra_day_order.saturating_add(1))
+            .max()
+            .unwrap_or(0);
+        Ok(next_intra_day_order)
+    }
+
+    /// Finds a post by user id and post id.
+    pub fn find(&self, user_id: u64, post_id: u64) -> Result<Post, ServiceError> {
+        let post: Result<Post, Error> = dsl::posts
+            .find(post_id)
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::deleted_at.is_null())
+            .get_result::<Post>(&self.conn);
+
         match post {
             Ok(post) => Ok(post),
             Err(error) => match error {
@@ -105,10 +1203,11 @@ impl PostRepository {
         }
     }
 
-    /// Finds all post written by specific user.
+    /// Finds all post written by specific user, except posts in the trash.
     pub fn find_all(&self, user_id: u64) -> Result<Vec<Post>, ServiceError> {
         let post_list: Result<Vec<Post>, Error> = dsl::posts
             .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::deleted_at.is_null())
             .load::<Post>(&self.conn);
 
         match post_list {
@@ -117,11 +1216,432 @@ impl PostRepository {
         }
     }
 
-    /// Finds all post written by specific user in desc date order.
-    pub fn find_all_in_desc_date_order(&self, user_id: u64) -> Result<Vec<Post>, ServiceError> {
+    /// Returns `InvalidArgument` error if any of the tags does not belong to specific user.
+    fn check_tags_owned(&self, user_id: u64, tag_ids: &[u64]) -> Result<(), ServiceError> {
+        if tag_ids.is_empty() {
+            return Ok(());
+        }
+
+        match tag::are_owned(&self.conn, user_id, tag_ids) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(get_service_error(ServiceError::InvalidArgument)),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Returns `InvalidArgument` error if the journal does not belong to specific user.
+    fn check_journal_owned(&self, user_id: u64, journal_id: u64) -> Result<(), ServiceError> {
+        match journal::is_owned(&self.conn, user_id, journal_id) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(get_service_error(ServiceError::InvalidArgument)),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Returns `Locked` error if a post written by specific user is locked.
+    ///
+    /// A post which does not exist is not locked, so that the caller reports it as not found.
+    fn check_unlocked(&self, user_id: u64, post_id: u64) -> Result<(), ServiceError> {
+        let is_locked = dsl::posts
+            .find(post_id)
+            .filter(dsl::user_id.eq(user_id))
+            .select(dsl::is_locked)
+            .get_result::<bool>(&self.conn)
+            .optional();
+
+        match is_locked {
+            Ok(Some(true)) => Err(get_service_error(ServiceError::Locked(post_id.to_string()))),
+            Ok(_) => Ok(()),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Returns the error of an update of a post whose version did not match,
+    /// which is a conflict with the current version, or not found if there is no such post.
+    fn get_version_error(&self, user_id: u64, post_id: u64) -> ServiceError {
+        let current_version = dsl::posts
+            .find(post_id)
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::deleted_at.is_null())
+            .select(dsl::version)
+            .get_result::<u32>(&self.conn);
+
+        match current_version {
+            Ok(current_version) => get_service_error(ServiceError::Conflict(current_version)),
+            Err(Error::NotFound) => get_service_error(ServiceError::NotFound(post_id.to_string())),
+            Err(_) => get_service_error(ServiceError::QueryExecutionFailure),
+        }
+    }
+
+    /// Returns a query of posts written by specific user in `filter`, except posts in the trash.
+    fn filter_posts<'a>(user_id: u64, filter: &PostFilter) -> posts::BoxedQuery<'a, Mysql> {
+        let mut query = dsl::posts
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::deleted_at.is_null())
+            .into_boxed();
+        if let Some(tag_id) = filter.tag_id {
+            let tagged_post_ids = post_tags::dsl::post_tags
+                .select(post_tags::dsl::post_id)
+                .filter(post_tags::dsl::tag_id.eq(tag_id));
+            query = query.filter(dsl::id.eq_any(tagged_post_ids));
+        }
+        if let Some(journal_id) = filter.journal_id {
+            query = query.filter(dsl::journal_id.eq(journal_id));
+        }
+        if let Some(from) = filter.from {
+            query = query.filter(sql::<Date>(LOCAL_DATE_SQL).ge(from));
+        }
+        if let Some(to) = filter.to {
+            query = query.filter(sql::<Date>(LOCAL_DATE_SQL).le(to));
+        }
+        if let Some(status) = filter.status {
+            query = query.filter(dsl::status.eq(status.as_str()));
+        }
+        if !filter.month_days.is_empty() {
+            let month_days: Vec<String> = filter
+                .month_days
+                .iter()
+                .map(|(month, day)| format!("{:02}-{:02}", month, day))
+                .collect();
+            query = query.filter(sql::<Varchar>(MONTH_DAY_SQL).eq_any(month_days));
+        }
+        if let Some(is_favorite) = filter.is_favorite {
+            query = query.filter(dsl::is_favorite.eq(is_favorite));
+        }
+        if let Some(near) = filter.near {
+            let distance = sql::<Double>(DISTANCE_SQL)
+                .bind::<Double, _>(near.longitude)
+                .sql(", ")
+                .bind::<Double, _>(near.latitude)
+                .sql("))");
+            query = query.filter(distance.le(near.radius));
+        }
+        query
+    }
+
+    /// Finds posts written by specific user in `filter`, except posts in the trash.
+    ///
+    /// Sorting by date sorts posts by local date, and posts of the same date by `intra_day_order`.
+    /// If `offset_and_limit` is given, finds only the posts in the range.
+    /// Contents of the posts are not loaded but left empty if `fields` is `Summary`.
+    pub fn find_list(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+        fields: PostFields,
+        sort_key: PostSortKey,
+        sort_order: SortOrder,
+        offset_and_limit: &Option<(i64, i64)>,
+    ) -> Result<Vec<Post>, ServiceError> {
+        let mut query = Self::filter_posts(user_id, filter);
+        if fields == PostFields::Summary {
+            query = query.select((
+                dsl::id,
+                dsl::user_id,
+                dsl::title,
+                sql::<Text>("''"),
+                dsl::date,
+                dsl::date_offset,
+                dsl::intra_day_order,
+                dsl::created_at,
+                dsl::updated_at,
+                dsl::version,
+                dsl::deleted_at,
+                dsl::status,
+                dsl::autosave_started_at,
+                dsl::changed_at,
+                dsl::is_favorite,
+                dsl::mood,
+                dsl::weather,
+                dsl::latitude,
+                dsl::longitude,
+                dsl::place_name,
+                dsl::journal_id,
+                dsl::word_count,
+                dsl::is_locked,
+                dsl::is_encrypted,
+                dsl::encryption_scheme,
+                dsl::encryption_nonce,
+                dsl::key_version,
+            ));
+        }
+        query = match (sort_key, sort_order) {
+            (PostSortKey::Date, SortOrder::Asc) => query.order((
+                sql::<Date>(LOCAL_DATE_SQL).asc(),
+                dsl::intra_day_order.asc(),
+                dsl::date.asc(),
+                dsl::id.asc(),
+            )),
+            (PostSortKey::Date, SortOrder::Desc) => query.order((
+                sql::<Date>(LOCAL_DATE_SQL).desc(),
+                dsl::intra_day_order.asc(),
+                dsl::date.desc(),
+                dsl::id.desc(),
+            )),
+            (PostSortKey::CreatedAt, SortOrder::Asc) => {
+                query.order((dsl::created_at.asc(), dsl::id.asc()))
+            }
+            (PostSortKey::CreatedAt, SortOrder::Desc) => {
+                query.order((dsl::created_at.desc(), dsl::id.desc()))
+            }
+            (PostSortKey::UpdatedAt, SortOrder::Asc) => {
+                query.order((sql::<Datetime>(MODIFIED_AT_SQL).asc(), dsl::id.asc()))
+            }
+            (PostSortKey::UpdatedAt, SortOrder::Desc) => {
+                query.order((sql::<Datetime>(MODIFIED_AT_SQL).desc(), dsl::id.desc()))
+            }
+        };
+        if let Some((offset, limit)) = offset_and_limit {
+            query = query.offset(*offset).limit(*limit);
+        }
+
+        let post_list: Result<Vec<Post>, Error> = query.load::<Post>(&self.conn);
+
+        match post_list {
+            Ok(post_list) => Ok(post_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts posts written by specific user in `filter`, except posts in the trash.
+    pub fn count(&self, user_id: u64, filter: &PostFilter) -> Result<i64, ServiceError> {
+        let count = Self::filter_posts(user_id, filter)
+            .count()
+            .get_result::<i64>(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds ids, titles and dates of posts written by specific user in `filter`,
+    /// except posts in the trash, in asc local date order and by `intra_day_order` in each date.
+    ///
+    /// Contents of the posts are not loaded.
+    pub fn find_summaries(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<(u64, String, PostDate)>, ServiceError> {
+        let summary_list = Self::filter_posts(user_id, filter)
+            .select((dsl::id, dsl::title, dsl::date, dsl::date_offset))
+            .order((
+                sql::<Date>(LOCAL_DATE_SQL).asc(),
+                dsl::intra_day_order.asc(),
+                dsl::date.asc(),
+                dsl::id.asc(),
+            ))
+            .load::<(u64, String, NaiveDateTime, Option<i32>)>(&self.conn);
+
+        match summary_list {
+            Ok(summary_list) => Ok(summary_list
+                .into_iter()
+                .map(|(id, title, date, offset)| (id, title, PostDate { date, offset }))
+                .collect()),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds local dates of posts written by specific user in `filter` without duplicates,
+    /// except posts in the trash, in asc order.
+    pub fn find_local_dates(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<NaiveDate>, ServiceError> {
+        let date_list = Self::filter_posts(user_id, filter)
+            .select(sql::<Date>(LOCAL_DATE_SQL))
+            .distinct()
+            .order(sql::<Date>(LOCAL_DATE_SQL).asc())
+            .load::<NaiveDate>(&self.conn);
+
+        match date_list {
+            Ok(date_list) => Ok(date_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds dates of posts written by specific user in `filter`, except posts in the trash,
+    /// in asc order.
+    pub fn find_dates(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<PostDate>, ServiceError> {
+        let date_list = Self::filter_posts(user_id, filter)
+            .select((dsl::date, dsl::date_offset))
+            .order(dsl::date.asc())
+            .load::<(NaiveDateTime, Option<i32>)>(&self.conn);
+
+        match date_list {
+            Ok(date_list) => Ok(date_list
+                .into_iter()
+                .map(|(date, offset)| PostDate { date, offset })
+                .collect()),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds ids, ids of the writers and dates of posts with id greater than `after_id`,
+    /// written before the offset of dates was recorded, in asc order of the ids.
+    pub fn find_all_without_date_offset(
+        &self,
+        after_id: u64,
+        limit: i64,
+    ) -> Result<Vec<(u64, u64, NaiveDateTime)>, ServiceError> {
+        let post_list = dsl::posts
+            .select((dsl::id, dsl::user_id, dsl::date))
+            .filter(dsl::id.gt(after_id))
+            .filter(dsl::date_offset.is_null())
+            .order(dsl::id.asc())
+            .limit(limit)
+            .load::<(u64, u64, NaiveDateTime)>(&self.conn);
+
+        match post_list {
+            Ok(post_list) => Ok(post_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Sets the date with offset of a post without offset, and returns whether it is set.
+    pub fn set_date_offset(&self, id: u64, date: &PostDate) -> Result<bool, ServiceError> {
+        let target_post = dsl::posts
+            .filter(dsl::id.eq(id))
+            .filter(dsl::date_offset.is_null());
+        let count = diesel::update(target_post)
+            .set((dsl::date.eq(date.date), dsl::date_offset.eq(date.offset)))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts posts written by specific user in `filter` by the year and month of the local date,
+    /// except posts in the trash, in asc order of the months. Months without posts are omitted.
+    pub fn count_by_month(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<(i32, u32, usize)>, ServiceError> {
+        let date_list = Self::filter_posts(user_id, filter)
+            .select(sql::<Date>(LOCAL_DATE_SQL))
+            .order(sql::<Date>(LOCAL_DATE_SQL).asc())
+            .load::<NaiveDate>(&self.conn);
+
+        match date_list {
+            Ok(date_list) => {
+                let mut counts: Vec<(i32, u32, usize)> = Vec::new();
+                for date in date_list {
+                    match counts.last_mut() {
+                        Some((year, month, count))
+                            if (*year, *month) == (date.year(), date.month()) =>
+                        {
+                            *count += 1
+                        }
+                        _ => counts.push((date.year(), date.month(), 1)),
+                    }
+                }
+                Ok(counts)
+            }
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts published posts written by specific user by the local date from `from` to `to`,
+    /// except posts in the trash, in asc order of the dates. Dates without posts are omitted.
+    ///
+    /// Posts are counted in a query grouped by the date, which boxed queries cannot express.
+    pub fn count_by_date(
+        &self,
+        user_id: u64,
+        from: &NaiveDate,
+        to: &NaiveDate,
+    ) -> Result<Vec<(NaiveDate, usize)>, ServiceError> {
+        let count_list = diesel::sql_query(format!(
+            "SELECT {local_date} AS local_date, COUNT(*) AS count FROM posts \
+             WHERE user_id = ? AND deleted_at IS NULL AND status = ? \
+             AND {local_date} BETWEEN ? AND ? \
+             GROUP BY local_date ORDER BY local_date ASC",
+            local_date = LOCAL_DATE_SQL,
+        ))
+        .bind::<Unsigned<Bigint>, _>(user_id)
+        .bind::<Varchar, _>(PostStatus::Published.as_str())
+        .bind::<Date, _>(from)
+        .bind::<Date, _>(to)
+        .load::<DateCount>(&self.conn);
+
+        match count_list {
+            Ok(count_list) => Ok(count_list
+                .into_iter()
+                .map(|date_count| (date_count.local_date, date_count.count as usize))
+                .collect()),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds pairs of local date and mood of posts written by specific user in `filter`,
+    /// except posts without moods and posts in the trash, in asc order of the dates.
+    pub fn find_moods(
+        &self,
+        user_id: u64,
+        filter: &PostFilter,
+    ) -> Result<Vec<(NaiveDate, u8)>, ServiceError> {
+        let mood_list = Self::filter_posts(user_id, filter)
+            .filter(dsl::mood.is_not_null())
+            .select((sql::<Date>(LOCAL_DATE_SQL), dsl::mood))
+            .order(sql::<Date>(LOCAL_DATE_SQL).asc())
+            .load::<(NaiveDate, Option<u8>)>(&self.conn);
+
+        match mood_list {
+            Ok(mood_list) => Ok(mood_list
+                .into_iter()
+                .filter_map(|(date, mood)| mood.map(|mood| (date, mood)))
+                .collect()),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Sums word counts of posts written by specific user in `filter`, except posts in the trash.
+    ///
+    /// Posts without word counts are not counted.
+    pub fn sum_word_counts(&self, user_id: u64, filter: &PostFilter) -> Result<u64, ServiceError> {
+        let word_count = Self::filter_posts(user_id, filter)
+            .select(sql::<Unsigned<Bigint>>(
+                "CAST(COALESCE(SUM(word_count), 0) AS UNSIGNED)",
+            ))
+            .get_result::<u64>(&self.conn);
+
+        match word_count {
+            Ok(word_count) => Ok(word_count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds ids of tags of each post, keyed by post id.
+    pub fn find_tag_ids(&self, post_ids: &[u64]) -> Result<HashMap<u64, Vec<u64>>, ServiceError> {
+        let post_tag_list = tag::find_post_tags(&self.conn, post_ids);
+
+        match post_tag_list {
+            Ok(post_tag_list) => {
+                let mut tag_ids: HashMap<u64, Vec<u64>> = HashMap::new();
+                for (post_id, tag_id) in post_tag_list {
+                    tag_ids.entry(post_id).or_default().push(tag_id);
+                }
+                Ok(tag_ids)
+            }
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds all posts in the trash of specific user, recently deleted first.
+    pub fn find_all_trashed(&self, user_id: u64) -> Result<Vec<Post>, ServiceError> {
         let post_list: Result<Vec<Post>, Error> = dsl::posts
             .filter(dsl::user_id.eq(user_id))
-            .order((dsl::date.desc(), dsl::id.desc()))
+            .filter(dsl::deleted_at.is_not_null())
+            .order((dsl::deleted_at.desc(), dsl::id.desc()))
             .load::<Post>(&self.conn);
 
         match post_list {
@@ -130,69 +1650,523 @@ impl PostRepository {
         }
     }
 
-    /// Creates a new post.
+    /// Counts posts of specific user moved to the trash before `threshold`.
+    pub fn count_trashed_before(
+        &self,
+        user_id: u64,
+        threshold: &NaiveDateTime,
+    ) -> Result<usize, ServiceError> {
+        let count = dsl::posts
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::deleted_at.lt(threshold))
+            .count()
+            .get_result::<i64>(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count as usize),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds posts written by specific user changed after `since`,
+    /// and posts deleted after it if `since` is given.
+    ///
+    /// Posts in the trash are reported as deleted, and a restored post is reported as changed.
+    pub fn find_changes(
+        &self,
+        user_id: u64,
+        since: &Option<NaiveDateTime>,
+    ) -> Result<PostChanges, ServiceError> {
+        // The changes are found in a snapshot, which is taken by the first read.
+        let changes = self.conn.transaction::<PostChanges, Error, _>(|| {
+            let found_at = diesel::select(sql::<Datetime>("CURRENT_TIMESTAMP(6)"))
+                .get_result::<NaiveDateTime>(&self.conn)?;
+
+            let mut query = dsl::posts
+                .filter(dsl::user_id.eq(user_id))
+                .order((dsl::changed_at.asc(), dsl::id.asc()))
+                .into_boxed();
+            query = match since {
+                Some(since) => query.filter(dsl::changed_at.gt(since)),
+                None => query.filter(dsl::deleted_at.is_null()),
+            };
+            let (trashed_posts, posts): (Vec<Post>, Vec<Post>) = query
+                .load::<Post>(&self.conn)?
+                .into_iter()
+                .partition(|post| post.deleted_at.is_some());
+
+            let mut deleted_posts: Vec<(u64, NaiveDateTime)> = trashed_posts
+                .into_iter()
+                .filter_map(|post| post.deleted_at.map(|deleted_at| (post.id, deleted_at)))
+                .collect();
+            if let Some(since) = since {
+                let tombstones = post_tombstone::find_since(&self.conn, user_id, since)?;
+                deleted_posts.extend(
+                    tombstones
+                        .into_iter()
+                        .map(|tombstone| (tombstone.post_id, tombstone.deleted_at)),
+                );
+            }
+
+            Ok(PostChanges {
+                posts,
+                deleted_posts,
+                found_at,
+            })
+        });
+
+        match changes {
+            Ok(changes) => Ok(changes),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds revisions of a post written by specific user, recent versions first.
+    pub fn find_revisions(
+        &self,
+        user_id: u64,
+        post_id: u64,
+    ) -> Result<Vec<PostRevision>, ServiceError> {
+        let revision_list = post_revision::find_all(&self.conn, user_id, post_id);
+
+        match revision_list {
+            Ok(revision_list) => Ok(revision_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds a revision of a post written by specific user by the version.
+    pub fn find_revision(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        version: u32,
+    ) -> Result<PostRevision, ServiceError> {
+        let revision = post_revision::find(&self.conn, user_id, post_id, version);
+
+        match revision {
+            Ok(revision) => Ok(revision),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    version.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Creates a new post and returns id of the created post.
+    ///
+    /// The post is placed after the other posts of its date, and written in the default
+    /// journal if `journal_id` is not given.
     pub fn create(
         &self,
         user_id: u64,
         title: &str,
         content: &str,
-        date: &NaiveDateTime,
-    ) -> Result<bool, ServiceError> {
-        let post_to_create = PostDAO {
-            id: None,
-            user_id: Some(user_id),
-            title: Some(title.to_string()),
-            content: Some(content.to_string()),
-            date: Some(*date),
-            updated_at: None,
-        };
+        date: &PostDate,
+        tag_ids: &[u64],
+        status: PostStatus,
+        mood: Option<u8>,
+        weather: Option<PostWeather>,
+        location: &Option<PostLocation>,
+        journal_id: Option<u64>,
+        word_count: Option<u32>,
+        encryption: &PostEncryption,
+        audit_context: &AuditContext,
+    ) -> Result<u64, ServiceError> {
+        self.check_tags_owned(user_id, tag_ids)?;
+        if let Some(journal_id) = journal_id {
+            self.check_journal_owned(user_id, journal_id)?;
+        }
 
-        let count = diesel::insert_into(dsl::posts)
-            .values(post_to_create)
-            .execute(&self.conn);
+        let post_id = self.conn.transaction::<u64, Error, _>(|| {
+            let journal_id = match journal_id {
+                Some(journal_id) => journal_id,
+                None => journal::find_default_id(&self.conn, user_id)?,
+            };
+            let post_to_create = PostDAO {
+                id: None,
+                user_id: Some(user_id),
+                title: Some(title.to_string()),
+                content: Some(content.to_string()),
+                date: Some(date.date),
+                date_offset: date.offset,
+                intra_day_order: Some(self.get_next_intra_day_order(
+                    user_id,
+                    &date.local_date(),
+                    None,
+                )?),
+                updated_at: None,
+                deleted_at: None,
+                status: Some(status.as_str().to_string()),
+                is_favorite: None,
+                mood,
+                weather: weather.map(|weather| weather.as_str().to_string()),
+                latitude: location.as_ref().map(|location| location.latitude),
+                longitude: location.as_ref().map(|location| location.longitude),
+                place_name: location
+                    .as_ref()
+                    .and_then(|location| location.place_name.clone()),
+                journal_id: Some(journal_id),
+                word_count,
+                is_encrypted: Some(encryption.is_encrypted),
+                encryption_scheme: encryption.scheme.clone(),
+                encryption_nonce: encryption.nonce.clone(),
+                key_version: encryption.key_version,
+            };
 
-        if let Ok(count) = count {
-            if count > 0 {
-                Ok(true)
-            } else {
-                Err(get_service_error(ServiceError::QueryExecutionFailure))
-            }
-        } else {
-            Err(get_service_error(ServiceError::QueryExecutionFailure))
+            diesel::insert_into(dsl::posts)
+                .values(post_to_create)
+                .execute(&self.conn)?;
+            let post_id = diesel::select(last_insert_id).get_result::<u64>(&self.conn)?;
+            tag::set_post_tags(&self.conn, post_id, tag_ids)?;
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Create,
+                audit_context,
+            )?;
+            Ok(post_id)
+        });
+
+        match post_id {
+            Ok(post_id) => Ok(post_id),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Copies a post written by specific user to a new post on `date`, and returns id
+    /// of the created post.
+    ///
+    /// Title, content, word count, encryption, status, journal, tags and attachments are copied,
+    /// while the mood, weather, location and favorite of the post are not. Posts in the trash cannot be copied.
+    pub fn duplicate(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        date: &PostDate,
+        audit_context: &AuditContext,
+    ) -> Result<u64, ServiceError> {
+        let created_post_id = self.conn.transaction::<u64, Error, _>(|| {
+            let source_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null())
+                .get_result::<Post>(&self.conn)?;
+
+            let post_to_create = PostDAO {
+                id: None,
+                user_id: Some(user_id),
+                title: Some(source_post.title),
+                content: Some(source_post.content),
+                date: Some(date.date),
+                date_offset: date.offset,
+                intra_day_order: Some(self.get_next_intra_day_order(
+                    user_id,
+                    &date.local_date(),
+                    None,
+                )?),
+                updated_at: None,
+                deleted_at: None,
+                status: Some(source_post.status),
+                is_favorite: None,
+                mood: None,
+                weather: None,
+                latitude: None,
+                longitude: None,
+                place_name: None,
+                journal_id: Some(source_post.journal_id),
+                word_count: source_post.word_count,
+                is_encrypted: Some(source_post.is_encrypted),
+                encryption_scheme: source_post.encryption_scheme,
+                encryption_nonce: source_post.encryption_nonce,
+                key_version: source_post.key_version,
+            };
+
+            diesel::insert_into(dsl::posts)
+                .values(post_to_create)
+                .execute(&self.conn)?;
+            let created_post_id = diesel::select(last_insert_id).get_result::<u64>(&self.conn)?;
+
+            let tag_ids: Vec<u64> = tag::find_post_tags(&self.conn, &[post_id])?
+                .into_iter()
+                .map(|(_, tag_id)| tag_id)
+                .collect();
+            tag::set_post_tags(&self.conn, created_post_id, &tag_ids)?;
+            attachment::copy_to_post(&self.conn, post_id, created_post_id)?;
+            post_audit::append(
+                &self.conn,
+                user_id,
+                created_post_id,
+                PostAuditAction::Create,
+                audit_context,
+            )?;
+            Ok(created_post_id)
+        });
+
+        match created_post_id {
+            Ok(created_post_id) => Ok(created_post_id),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    post_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
         }
     }
 
-    /// Updates a post written by specific user.
+    /// Updates a post written by specific user, and increases its version.
+    ///
+    /// The post before the update is kept as a revision of its version.
+    ///
+    /// If `version` is given, the post is updated only when it is still in that version.
+    /// If the post is moved to another date, it is placed after the other posts of the date.
+    /// If `journal_id` is given, the post is moved to the journal.
+    /// If `content` is given without `word_count`, the word count of the post is cleared.
+    /// If `encryption` is given, it replaces the encryption of the post entirely.
+    /// A locked post is not updated.
     pub fn update(
         &self,
         user_id: u64,
         post_id: u64,
         title: &Option<String>,
         content: &Option<String>,
-        date: &Option<NaiveDateTime>,
+        date: &Option<PostDate>,
+        tag_ids: &Option<Vec<u64>>,
+        mood: &Option<u8>,
+        weather: &Option<PostWeather>,
+        location: &Option<PostLocation>,
+        journal_id: &Option<u64>,
+        word_count: &Option<u32>,
+        encryption: &Option<PostEncryption>,
+        version: &Option<u32>,
+        audit_context: &AuditContext,
     ) -> Result<bool, ServiceError> {
+        self.check_unlocked(user_id, post_id)?;
+        if let Some(tag_ids) = tag_ids {
+            self.check_tags_owned(user_id, tag_ids)?;
+        }
+        if let Some(journal_id) = journal_id {
+            self.check_journal_owned(user_id, *journal_id)?;
+        }
+
         let post_to_update = PostDAO {
             id: Some(post_id),
             user_id: None,
             title: title.clone(),
             content: content.clone(),
-            date: *date,
+            date: date.map(|date| date.date),
+            date_offset: None,
+            intra_day_order: None,
             updated_at: Some(Utc::now().naive_utc()),
+            deleted_at: None,
+            status: None,
+            is_favorite: None,
+            mood: *mood,
+            weather: weather.map(|weather| weather.as_str().to_string()),
+            latitude: location.as_ref().map(|location| location.latitude),
+            longitude: location.as_ref().map(|location| location.longitude),
+            place_name: None,
+            journal_id: *journal_id,
+            word_count: *word_count,
+            is_encrypted: None,
+            encryption_scheme: None,
+            encryption_nonce: None,
+            key_version: None,
         };
 
-        let target_post = dsl::posts.find(post_id).filter(dsl::user_id.eq(user_id));
-        let count = diesel::update(target_post)
-            .set(post_to_update)
-            .execute(&self.conn);
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let previous_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null())
+                .get_result::<Post>(&self.conn)
+                .optional()?;
 
-        match count {
-            Ok(count) => {
-                if count > 0 {
-                    Ok(true)
-                } else {
-                    Err(get_service_error(ServiceError::QueryExecutionFailure))
+            let target_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null());
+            let next_version = dsl::version.eq(dsl::version + 1);
+            // An update ends autosaves, so the next autosave takes a new revision.
+            let autosave_ended = dsl::autosave_started_at.eq(None::<NaiveDateTime>);
+            let count = match version {
+                Some(version) => diesel::update(target_post.filter(dsl::version.eq(*version)))
+                    .set((post_to_update, next_version, autosave_ended))
+                    .execute(&self.conn)?,
+                None => diesel::update(target_post)
+                    .set((post_to_update, next_version, autosave_ended))
+                    .execute(&self.conn)?,
+            };
+
+            if count == 0 {
+                return Err(Error::NotFound);
+            }
+
+            if let Some(previous_post) = &previous_post {
+                post_revision::append(&self.conn, previous_post)?;
+            }
+
+            // A date without offset clears the offset, which the changeset cannot express.
+            if let Some(date) = date {
+                diesel::update(dsl::posts.find(post_id))
+                    .set(dsl::date_offset.eq(date.offset))
+                    .execute(&self.conn)?;
+
+                let local_date = date.local_date();
+                let previous_date = previous_post.as_ref().map(|post| post.post_date());
+                if previous_date.map(|previous_date| previous_date.local_date()) != Some(local_date)
+                {
+                    let intra_day_order =
+                        self.get_next_intra_day_order(user_id, &local_date, Some(post_id))?;
+                    diesel::update(dsl::posts.find(post_id))
+                        .set(dsl::intra_day_order.eq(intra_day_order))
+                        .execute(&self.conn)?;
                 }
             }
+
+            // A location without place name clears the place name, which the changeset
+            // cannot express.
+            if let Some(location) = location {
+                diesel::update(dsl::posts.find(post_id))
+                    .set(dsl::place_name.eq(location.place_name.clone()))
+                    .execute(&self.conn)?;
+            }
+
+            // The word count of the previous content no longer matches the new content.
+            if content.is_some() && word_count.is_none() {
+                diesel::update(dsl::posts.find(post_id))
+                    .set(dsl::word_count.eq(None::<u32>))
+                    .execute(&self.conn)?;
+            }
+
+            // Fields of the encryption which are not given are cleared, which the changeset
+            // cannot express.
+            if let Some(encryption) = encryption {
+                diesel::update(dsl::posts.find(post_id))
+                    .set((
+                        dsl::is_encrypted.eq(encryption.is_encrypted),
+                        dsl::encryption_scheme.eq(encryption.scheme.clone()),
+                        dsl::encryption_nonce.eq(encryption.nonce.clone()),
+                        dsl::key_version.eq(encryption.key_version),
+                    ))
+                    .execute(&self.conn)?;
+            }
+
+            if let Some(tag_ids) = tag_ids {
+                tag::set_post_tags(&self.conn, post_id, tag_ids)?;
+            }
+
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Update,
+                audit_context,
+            )?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound if version.is_some() => {
+                    Err(self.get_version_error(user_id, post_id))
+                }
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    post_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Moves a post written by specific user to the trash, unless it is locked.
+    ///
+    /// The post keeps its tags, so that it can be restored as it was.
+    pub fn delete(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        self.check_unlocked(user_id, post_id)?;
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let target_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null());
+            let count = diesel::update(target_post)
+                .set(dsl::deleted_at.eq(Utc::now().naive_utc()))
+                .execute(&self.conn)?;
+            if count == 0 {
+                return Err(Error::NotFound);
+            }
+
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Delete,
+                audit_context,
+            )?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    post_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Restores a post written by specific user from the trash.
+    ///
+    /// The post is placed after the other posts of its date.
+    pub fn restore(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let target_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_not_null());
+            let post_date = target_post
+                .clone()
+                .select((dsl::date, dsl::date_offset))
+                .get_result::<(NaiveDateTime, Option<i32>)>(&self.conn)
+                .map(|(date, offset)| PostDate { date, offset })?;
+
+            let intra_day_order =
+                self.get_next_intra_day_order(user_id, &post_date.local_date(), None)?;
+            diesel::update(target_post)
+                .set((
+                    dsl::deleted_at.eq(None::<NaiveDateTime>),
+                    dsl::intra_day_order.eq(intra_day_order),
+                ))
+                .execute(&self.conn)?;
+
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Restore,
+                audit_context,
+            )?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
             Err(error) => match error {
                 Error::NotFound => Err(get_service_error(ServiceError::NotFound(
                     post_id.to_string(),
@@ -202,19 +2176,476 @@ impl PostRepository {
         }
     }
 
-    /// Deletes a post written by specific user.
-    pub fn delete(&self, user_id: u64, post_id: u64) -> Result<bool, ServiceError> {
-        let target_post = dsl::posts.find(post_id).filter(dsl::user_id.eq(user_id));
-        let count = diesel::delete(target_post).execute(&self.conn);
+    /// Creates posts imported from an archive at once, and returns ids of the created posts.
+    ///
+    /// Each post is placed after the other posts of its date, and posts with `deleted_at`
+    /// are created directly in the trash. Nothing is created if any of the posts fails.
+    /// If `dry_run` is true, checks that the posts can be created without creating anything.
+    pub fn import(
+        &self,
+        user_id: u64,
+        post_list: &[PostToImport],
+        audit_context: &AuditContext,
+        dry_run: bool,
+    ) -> Result<Vec<u64>, ServiceError> {
+        let tag_ids: Vec<u64> = post_list
+            .iter()
+            .flat_map(|post| post.tag_ids.iter().copied())
+            .collect();
+        self.check_tags_owned(user_id, &tag_ids)?;
+
+        let mut rolled_back_post_ids = None;
+        let post_ids = self.conn.transaction::<Vec<u64>, Error, _>(|| {
+            let journal_id = journal::find_default_id(&self.conn, user_id)?;
+            let mut post_ids = Vec::with_capacity(post_list.len());
+            for post in post_list {
+                let post_to_create = PostDAO {
+                    id: None,
+                    user_id: Some(user_id),
+                    title: Some(post.title.clone()),
+                    content: Some(post.content.clone()),
+                    date: Some(post.date.date),
+                    date_offset: post.date.offset,
+                    intra_day_order: Some(self.get_next_intra_day_order(
+                        user_id,
+                        &post.date.local_date(),
+                        None,
+                    )?),
+                    updated_at: None,
+                    deleted_at: post.deleted_at,
+                    status: Some(post.status.as_str().to_string()),
+                    is_favorite: Some(post.is_favorite),
+                    mood: post.mood,
+                    weather: post.weather.map(|weather| weather.as_str().to_string()),
+                    latitude: post.location.as_ref().map(|location| location.latitude),
+                    longitude: post.location.as_ref().map(|location| location.longitude),
+                    place_name: post
+                        .location
+                        .as_ref()
+                        .and_then(|location| location.place_name.clone()),
+                    journal_id: Some(journal_id),
+                    word_count: None,
+                    is_encrypted: Some(post.encryption.is_encrypted),
+                    encryption_scheme: post.encryption.scheme.clone(),
+                    encryption_nonce: post.encryption.nonce.clone(),
+                    key_version: post.encryption.key_version,
+                };
+
+                diesel::insert_into(dsl::posts)
+                    .values(post_to_create)
+                    .execute(&self.conn)?;
+                let post_id = diesel::select(last_insert_id).get_result::<u64>(&self.conn)?;
+                tag::set_post_tags(&self.conn, post_id, &post.tag_ids)?;
+                post_audit::append(
+                    &self.conn,
+                    user_id,
+                    post_id,
+                    PostAuditAction::Create,
+                    audit_context,
+                )?;
+                post_ids.push(post_id);
+            }
+
+            if dry_run {
+                rolled_back_post_ids = Some(post_ids);
+                Err(Error::RollbackTransaction)
+            } else {
+                Ok(post_ids)
+            }
+        });
+
+        match post_ids {
+            Ok(post_ids) => Ok(post_ids),
+            Err(error) => match error {
+                Error::RollbackTransaction if rolled_back_post_ids.is_some() => {
+                    Ok(rolled_back_post_ids.unwrap())
+                }
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Saves a post written by specific user while it is edited, and returns its version.
+    ///
+    /// Unlike `update`, `updated_at` is kept. If `revises` is true, the post before the autosave
+    /// is kept as a revision and its version is increased, which starts autosaves at `autosaved_at`.
+    /// Otherwise, the post is overwritten in the same version.
+    /// The post is saved only when it is still in `version`, and is not locked.
+    /// The encryption of the post is replaced with `encryption` of the saved content.
+    pub fn autosave(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        title: &Option<String>,
+        content: &str,
+        encryption: &PostEncryption,
+        version: u32,
+        revises: bool,
+        autosaved_at: &NaiveDateTime,
+        audit_context: &AuditContext,
+    ) -> Result<u32, ServiceError> {
+        self.check_unlocked(user_id, post_id)?;
+        let post_to_update = PostDAO {
+            id: Some(post_id),
+            user_id: None,
+            title: title.clone(),
+            content: Some(content.to_string()),
+            date: None,
+            date_offset: None,
+            intra_day_order: None,
+            updated_at: None,
+            deleted_at: None,
+            status: None,
+            is_favorite: None,
+            mood: None,
+            weather: None,
+            latitude: None,
+            longitude: None,
+            place_name: None,
+            journal_id: None,
+            word_count: None,
+            is_encrypted: None,
+            encryption_scheme: None,
+            encryption_nonce: None,
+            key_version: None,
+        };
+        // Fields of the encryption which are not given are cleared, which the changeset
+        // cannot express.
+        let encryption_to_update = (
+            dsl::is_encrypted.eq(encryption.is_encrypted),
+            dsl::encryption_scheme.eq(encryption.scheme.clone()),
+            dsl::encryption_nonce.eq(encryption.nonce.clone()),
+            dsl::key_version.eq(encryption.key_version),
+        );
+
+        let result = self.conn.transaction::<u32, Error, _>(|| {
+            let target_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null())
+                .filter(dsl::version.eq(version));
+            let previous_post = target_post.for_update().get_result::<Post>(&self.conn)?;
+
+            if !revises {
+                diesel::update(target_post)
+                    .set((post_to_update, encryption_to_update))
+                    .execute(&self.conn)?;
+                return Ok(version);
+            }
+
+            post_revision::append(&self.conn, &previous_post)?;
+            diesel::update(target_post)
+                .set((
+                    post_to_update,
+                    encryption_to_update,
+                    dsl::version.eq(version + 1),
+                    dsl::autosave_started_at.eq(Some(*autosaved_at)),
+                ))
+                .execute(&self.conn)?;
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Autosave,
+                audit_context,
+            )?;
+            Ok(version + 1)
+        });
+
+        match result {
+            Ok(version) => Ok(version),
+            Err(error) => match error {
+                Error::NotFound => Err(self.get_version_error(user_id, post_id)),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Publishes a draft written by specific user.
+    ///
+    /// Publishing a post which is already published changes nothing, and returns false.
+    pub fn publish(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let target_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null());
+            let status = target_post
+                .clone()
+                .select(dsl::status)
+                .get_result::<String>(&self.conn)?;
+            if status == PostStatus::Published.as_str() {
+                return Ok(false);
+            }
+
+            diesel::update(target_post)
+                .set(dsl::status.eq(PostStatus::Published.as_str()))
+                .execute(&self.conn)?;
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Publish,
+                audit_context,
+            )?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    post_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Marks or unmarks a post written by specific user as a favorite.
+    ///
+    /// It is not an update of the post, so neither `updated_at` nor the version changes.
+    /// Marking a post which is already marked changes nothing, and returns false.
+    pub fn set_favorite(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        is_favorite: bool,
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let target_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null());
+            let was_favorite = target_post
+                .clone()
+                .select(dsl::is_favorite)
+                .get_result::<bool>(&self.conn)?;
+            if was_favorite == is_favorite {
+                return Ok(false);
+            }
+
+            diesel::update(target_post)
+                .set(dsl::is_favorite.eq(is_favorite))
+                .execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    post_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Locks or unlocks a post written by specific user.
+    ///
+    /// Locked posts cannot be updated, autosaved, or deleted until they are unlocked.
+    /// Like marking favorites, it is not an update of the post, so the version does not change.
+    /// Locking a post which is already locked changes nothing, and returns false.
+    pub fn set_locked(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        is_locked: bool,
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let target_post = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null());
+            let was_locked = target_post
+                .clone()
+                .select(dsl::is_locked)
+                .get_result::<bool>(&self.conn)?;
+            if was_locked == is_locked {
+                return Ok(false);
+            }
+
+            diesel::update(target_post)
+                .set(dsl::is_locked.eq(is_locked))
+                .execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    post_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Permanently deletes posts of all users moved to the trash before `threshold`,
+    /// and returns the number of deleted posts.
+    ///
+    /// Audit entries of the posts are kept.
+    pub fn purge_trashed(&self, threshold: &NaiveDateTime) -> Result<usize, ServiceError> {
+        let count = self.conn.transaction::<usize, Error, _>(|| {
+            let post_ids: Vec<u64> = dsl::posts
+                .select(dsl::id)
+                .filter(dsl::deleted_at.lt(threshold))
+                .load::<u64>(&self.conn)?;
+
+            tag::delete_post_tags(&self.conn, &post_ids)?;
+            post_revision::delete_by_post_ids(&self.conn, &post_ids)?;
+            attachment::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_share::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_comment::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_tombstone::append(&self.conn, &post_ids)?;
+            diesel::delete(dsl::posts.filter(dsl::id.eq_any(&post_ids))).execute(&self.conn)
+        });
 
         match count {
-            Ok(count) => {
-                if count > 0 {
-                    Ok(true)
-                } else {
-                    Err(get_service_error(ServiceError::QueryExecutionFailure))
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes tombstones of permanently deleted posts left before `threshold`,
+    /// and returns the count.
+    pub fn prune_tombstones(&self, threshold: &NaiveDateTime) -> Result<usize, ServiceError> {
+        match post_tombstone::delete_older_than(&self.conn, threshold) {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Permanently deletes posts written by specific user on a date, with their audit entries,
+    /// revisions, and attachments.
+    ///
+    /// Posts in the trash are also deleted, and nothing is deleted if any of the posts is locked.
+    /// The date of each post is compared in the offset where the post was written.
+    /// If `dry_run` is true, reports the data to be removed without removing anything.
+    pub fn delete_by_date(
+        &self,
+        user_id: u64,
+        date: &NaiveDate,
+        dry_run: bool,
+    ) -> Result<PostDateDeletion, ServiceError> {
+        let locked_post_id = dsl::posts
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::is_locked.eq(true))
+            .filter(sql::<Date>(LOCAL_DATE_SQL).eq(date))
+            .select(dsl::id)
+            .first::<u64>(&self.conn)
+            .optional();
+        match locked_post_id {
+            Ok(Some(post_id)) => {
+                return Err(get_service_error(ServiceError::Locked(post_id.to_string())))
+            }
+            Ok(None) => (),
+            Err(_) => return Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+
+        let mut rolled_back_deletion = None;
+        let deletion = self.conn.transaction::<PostDateDeletion, Error, _>(|| {
+            let post_ids: Vec<u64> = self
+                .find_day_posts(user_id, date, true)?
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect();
+
+            let target_post_audits = post_audits::dsl::post_audits
+                .filter(post_audits::dsl::user_id.eq(user_id))
+                .filter(post_audits::dsl::post_id.eq_any(&post_ids));
+            let post_audit_count = diesel::delete(target_post_audits).execute(&self.conn)?;
+
+            tag::delete_post_tags(&self.conn, &post_ids)?;
+            post_revision::delete_by_post_ids(&self.conn, &post_ids)?;
+            let attachment_count = attachment::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_share::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_comment::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_tombstone::append(&self.conn, &post_ids)?;
+            let target_posts = dsl::posts
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::id.eq_any(&post_ids));
+            diesel::delete(target_posts).execute(&self.conn)?;
+
+            let deletion = PostDateDeletion {
+                post_ids,
+                post_audit_count,
+                attachment_count,
+            };
+
+            if dry_run {
+                rolled_back_deletion = Some(deletion);
+                Err(Error::RollbackTransaction)
+            } else {
+                Ok(deletion)
+            }
+        });
+
+        match deletion {
+            Ok(deletion) => Ok(deletion),
+            Err(error) => match error {
+                Error::RollbackTransaction if rolled_back_deletion.is_some() => {
+                    Ok(rolled_back_deletion.unwrap())
+                }
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Moves a post to `position` among the posts of its date, and shifts the others.
+    ///
+    /// Orders of the posts of the date are rewritten from 0, so that they have no gaps.
+    pub fn reorder(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        position: usize,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let post_date = dsl::posts
+                .find(post_id)
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::deleted_at.is_null())
+                .select((dsl::date, dsl::date_offset))
+                .get_result::<(NaiveDateTime, Option<i32>)>(&self.conn)
+                .map(|(date, offset)| PostDate { date, offset })?;
+
+            let day_posts = self.find_day_posts(user_id, &post_date.local_date(), false)?;
+            let day_post_ids: Vec<u64> = day_posts.iter().map(|(id, _)| *id).collect();
+            let moved_post_ids = move_in_day(&day_post_ids, post_id, position);
+
+            for (intra_day_order, id) in moved_post_ids.into_iter().enumerate() {
+                let intra_day_order = intra_day_order as u16;
+                let is_changed = day_posts.iter().any(|(day_post_id, previous_order)| {
+                    *day_post_id == id && *previous_order != intra_day_order
+                });
+                if is_changed {
+                    diesel::update(dsl::posts.find(id))
+                        .set(dsl::intra_day_order.eq(intra_day_order))
+                        .execute(&self.conn)?;
                 }
             }
+
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Update,
+                audit_context,
+            )?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
             Err(error) => match error {
                 Error::NotFound => Err(get_service_error(ServiceError::NotFound(
                     post_id.to_string(),
@@ -223,6 +2654,137 @@ impl PostRepository {
             },
         }
     }
+
+    /// Executes operations on posts written by specific user in a transaction,
+    /// and returns the result of each operation, which is id of the post.
+    ///
+    /// Each operation is executed in its own savepoint, so a failed operation is rolled back
+    /// and reported without affecting the others. Operations are executed in the given order.
+    pub fn execute_bulk(
+        &self,
+        user_id: u64,
+        operations: &[PostOperation],
+        audit_context: &AuditContext,
+    ) -> Result<Vec<Result<u64, ServiceError>>, ServiceError> {
+        // Transactions of `create`, `update`, and `delete` are nested in this transaction,
+        // which makes them savepoints.
+        let results = self
+            .conn
+            .transaction::<Vec<Result<u64, ServiceError>>, Error, _>(|| {
+                Ok(operations
+                    .iter()
+                    .map(|operation| match operation {
+                        PostOperation::Create {
+                            title,
+                            content,
+                            date,
+                            tag_ids,
+                            status,
+                            mood,
+                            weather,
+                            location,
+                            journal_id,
+                            word_count,
+                            encryption,
+                        } => self.create(
+                            user_id,
+                            title,
+                            content,
+                            date,
+                            tag_ids,
+                            *status,
+                            *mood,
+                            *weather,
+                            location,
+                            *journal_id,
+                            *word_count,
+                            encryption,
+                            audit_context,
+                        ),
+                        PostOperation::Update {
+                            post_id,
+                            title,
+                            content,
+                            date,
+                            tag_ids,
+                            mood,
+                            weather,
+                            location,
+                            journal_id,
+                            word_count,
+                            encryption,
+                            version,
+                        } => self
+                            .update(
+                                user_id,
+                                *post_id,
+                                title,
+                                content,
+                                date,
+                                tag_ids,
+                                mood,
+                                weather,
+                                location,
+                                journal_id,
+                                word_count,
+                                encryption,
+                                version,
+                                audit_context,
+                            )
+                            .map(|_| *post_id),
+                        PostOperation::Delete { post_id } => self
+                            .delete(user_id, *post_id, audit_context)
+                            .map(|_| *post_id),
+                    })
+                    .collect())
+            });
+
+        match results {
+            Ok(results) => Ok(results),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Counts encrypted posts and revisions written by specific user by the scheme
+    /// and the key version, so that clients find what is left to re-encrypt with a new key.
+    ///
+    /// Posts and revisions are counted in a query grouped by both of them, which the query
+    /// builder cannot express.
+    pub fn count_by_key_version(
+        &self,
+        user_id: u64,
+    ) -> Result<Vec<KeyVersionCountDTO>, ServiceError> {
+        let count_list = diesel::sql_query(
+            "SELECT encryption_scheme, key_version, \
+             COUNT(CASE WHEN is_post THEN 1 END) AS post_count, \
+             COUNT(CASE WHEN NOT is_post THEN 1 END) AS revision_count \
+             FROM (\
+             SELECT encryption_scheme, key_version, TRUE AS is_post FROM posts \
+             WHERE user_id = ? AND is_encrypted \
+             UNION ALL \
+             SELECT encryption_scheme, key_version, FALSE AS is_post FROM post_revisions \
+             WHERE user_id = ? AND is_encrypted\
+             ) AS encrypted \
+             GROUP BY encryption_scheme, key_version \
+             ORDER BY encryption_scheme ASC, key_version ASC",
+        )
+        .bind::<Unsigned<Bigint>, _>(user_id)
+        .bind::<Unsigned<Bigint>, _>(user_id)
+        .load::<KeyVersionRow>(&self.conn);
+
+        match count_list {
+            Ok(count_list) => Ok(count_list
+                .into_iter()
+                .map(|row| KeyVersionCountDTO {
+                    encryption_scheme: row.encryption_scheme,
+                    key_version: row.key_version,
+                    post_count: row.post_count,
+                    revision_count: row.revision_count,
+                })
+                .collect()),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
 }
 
 impl Default for PostRepository {
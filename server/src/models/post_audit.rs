@@ -0,0 +1,213 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{post_audits, post_audits::dsl};
+use crate::utils::datetime_util;
+
+/// Actions recorded in the post audit.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PostAuditAction {
+    Create,
+    Update,
+    Delete,
+    Restore,
+    Publish,
+    Autosave,
+    Share,
+    Unshare,
+}
+
+impl PostAuditAction {
+    /// Returns the name of the action stored in `post_audits` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Update => "update",
+            Self::Delete => "delete",
+            Self::Restore => "restore",
+            Self::Publish => "publish",
+            Self::Autosave => "autosave",
+            Self::Share => "share",
+            Self::Unshare => "unshare",
+        }
+    }
+}
+
+/// Information of the client who touched the post.
+#[derive(Clone, Debug, Default)]
+pub struct AuditContext {
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub session_id: Option<String>,
+    /// ISO 3166-1 alpha-2 code of the country of the client, if the proxy reports it.
+    pub country: Option<String>,
+}
+
+/// Post audit representing `post_audits` table.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct PostAudit {
+    pub id: u64,
+    pub user_id: u64,
+    pub post_id: u64,
+    pub action: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub session_id: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Post audit DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct PostAuditDTO {
+    pub id: u64,
+    pub post_id: u64,
+    pub action: String,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub session_id: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Post audit DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "post_audits"]
+struct PostAuditDAO {
+    user_id: u64,
+    post_id: u64,
+    action: String,
+    ip: Option<String>,
+    user_agent: Option<String>,
+    session_id: Option<String>,
+}
+
+/// Appends an audit entry to `post_audits` table.
+///
+/// It takes the connection of the caller, so that the entry is written
+/// in the same transaction as the mutation of the post.
+pub fn append(
+    conn: &MysqlConnection,
+    user_id: u64,
+    post_id: u64,
+    action: PostAuditAction,
+    context: &AuditContext,
+) -> Result<usize, Error> {
+    let audit_to_create = PostAuditDAO {
+        user_id,
+        post_id,
+        action: action.as_str().to_string(),
+        ip: context.ip.clone(),
+        user_agent: context.user_agent.clone(),
+        session_id: context.session_id.clone(),
+    };
+
+    diesel::insert_into(dsl::post_audits)
+        .values(audit_to_create)
+        .execute(conn)
+}
+
+/// A core data repository for post audit.
+pub struct PostAuditRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait PostAuditRepositoryTrait {
+    fn find_all_by_post_id(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<PostAudit>, ServiceError>;
+    fn find_all_since(
+        &self,
+        user_id: u64,
+        since: &Option<NaiveDateTime>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<PostAudit>, ServiceError>;
+    fn delete_older_than(&self, threshold: &NaiveDateTime) -> Result<usize, ServiceError>;
+}
+
+impl PostAuditRepository {
+    /// Creates a new post audit repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds audit entries of a post in desc order.
+    pub fn find_all_by_post_id(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<PostAudit>, ServiceError> {
+        let audit_list: Result<Vec<PostAudit>, Error> = dsl::post_audits
+            .filter(dsl::user_id.eq(user_id))
+            .filter(dsl::post_id.eq(post_id))
+            .order((dsl::created_at.desc(), dsl::id.desc()))
+            .offset(offset)
+            .limit(limit)
+            .load::<PostAudit>(&self.conn);
+
+        match audit_list {
+            Ok(audit_list) => Ok(audit_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds audit entries of all posts written by specific user in desc order.
+    pub fn find_all_since(
+        &self,
+        user_id: u64,
+        since: &Option<NaiveDateTime>,
+        offset: i64,
+        limit: i64,
+    ) -> Result<Vec<PostAudit>, ServiceError> {
+        let mut query = dsl::post_audits
+            .filter(dsl::user_id.eq(user_id))
+            .into_boxed();
+
+        if let Some(since) = since {
+            query = query.filter(dsl::created_at.ge(*since));
+        }
+
+        let audit_list: Result<Vec<PostAudit>, Error> = query
+            .order((dsl::created_at.desc(), dsl::id.desc()))
+            .offset(offset)
+            .limit(limit)
+            .load::<PostAudit>(&self.conn);
+
+        match audit_list {
+            Ok(audit_list) => Ok(audit_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes audit entries created before `threshold`.
+    pub fn delete_older_than(&self, threshold: &NaiveDateTime) -> Result<usize, ServiceError> {
+        let count = diesel::delete(dsl::post_audits.filter(dsl::created_at.lt(threshold)))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for PostAuditRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
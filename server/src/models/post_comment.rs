@@ -0,0 +1,173 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{post_comments, post_comments::dsl, posts, users};
+use crate::utils::datetime_util;
+
+no_arg_sql_function!(
+    last_insert_id,
+    diesel::sql_types::Unsigned<diesel::sql_types::Bigint>
+);
+
+/// Post comment representing `post_comments` table.
+///
+/// Comments are written in plaintext by the writer of the post or readers of its share,
+/// who do not have the key of the writer.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct PostComment {
+    pub id: u64,
+    pub post_id: u64,
+    /// Id of the writer of the comment.
+    pub user_id: u64,
+    pub content: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Post comment DTO using between routes layer and service layer.
+///
+/// It has the name of the writer of the comment instead of the id, like shared posts.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct PostCommentDTO {
+    pub id: u64,
+    pub writer_name: String,
+    pub content: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    /// Whether the comment is written by the user who requested it.
+    pub is_mine: bool,
+}
+
+/// Post comment DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "post_comments"]
+struct PostCommentDAO {
+    post_id: u64,
+    user_id: u64,
+    content: String,
+}
+
+/// Deletes comments of posts, which must be done before deleting the posts.
+pub fn delete_by_post_ids(conn: &MysqlConnection, post_ids: &[u64]) -> Result<usize, Error> {
+    diesel::delete(dsl::post_comments.filter(dsl::post_id.eq_any(post_ids))).execute(conn)
+}
+
+/// Deletes comments written by specific user on any post.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::post_comments.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for post comment.
+pub struct PostCommentRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait PostCommentRepositoryTrait {
+    fn find_by_post_id(&self, post_id: u64) -> Result<Vec<(PostComment, String)>, ServiceError>;
+    fn is_post_owned(&self, user_id: u64, post_id: u64) -> Result<bool, ServiceError>;
+    fn create(&self, post_id: u64, user_id: u64, content: &str) -> Result<u64, ServiceError>;
+    fn delete(&self, id: u64, user_id: u64) -> Result<bool, ServiceError>;
+}
+
+impl PostCommentRepository {
+    /// Creates a new post comment repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds comments of a post with the names of their writers, in the order of writing.
+    pub fn find_by_post_id(
+        &self,
+        post_id: u64,
+    ) -> Result<Vec<(PostComment, String)>, ServiceError> {
+        let comment_list = dsl::post_comments
+            .inner_join(users::table)
+            .select((
+                (
+                    dsl::id,
+                    dsl::post_id,
+                    dsl::user_id,
+                    dsl::content,
+                    dsl::created_at,
+                ),
+                users::dsl::name,
+            ))
+            .filter(dsl::post_id.eq(post_id))
+            .order(dsl::id.asc())
+            .load::<(PostComment, String)>(&self.conn);
+
+        match comment_list {
+            Ok(comment_list) => Ok(comment_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds whether a post not in the trash is written by specific user.
+    pub fn is_post_owned(&self, user_id: u64, post_id: u64) -> Result<bool, ServiceError> {
+        let owned_post = posts::dsl::posts
+            .find(post_id)
+            .filter(posts::dsl::user_id.eq(user_id))
+            .filter(posts::dsl::deleted_at.is_null());
+        let is_owned = diesel::select(exists(owned_post)).get_result::<bool>(&self.conn);
+
+        match is_owned {
+            Ok(is_owned) => Ok(is_owned),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a comment of specific user on a post, and returns id of the created comment.
+    pub fn create(&self, post_id: u64, user_id: u64, content: &str) -> Result<u64, ServiceError> {
+        let comment_to_create = PostCommentDAO {
+            post_id,
+            user_id,
+            content: content.to_string(),
+        };
+
+        let comment_id = self.conn.transaction::<u64, Error, _>(|| {
+            diesel::insert_into(dsl::post_comments)
+                .values(comment_to_create)
+                .execute(&self.conn)?;
+            diesel::select(last_insert_id).get_result::<u64>(&self.conn)
+        });
+
+        match comment_id {
+            Ok(comment_id) => Ok(comment_id),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a comment, which only its writer or the writer of the post can delete.
+    pub fn delete(&self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let owned_post_ids = posts::dsl::posts
+            .select(posts::dsl::id)
+            .filter(posts::dsl::user_id.eq(user_id));
+        let target_comment = dsl::post_comments.find(id).filter(
+            dsl::user_id
+                .eq(user_id)
+                .or(dsl::post_id.eq_any(owned_post_ids)),
+        );
+        let count = diesel::delete(target_comment).execute(&self.conn);
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for PostCommentRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
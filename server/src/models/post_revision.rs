@@ -0,0 +1,137 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::models::post::{Post, PostDate, PostEncryption, PostEncryptionDTO};
+use crate::schema::{post_revisions, post_revisions::dsl};
+use crate::utils::datetime_util;
+
+/// Post revision representing `post_revisions` table.
+///
+/// A revision is a snapshot of a post taken before it is updated,
+/// and `version` is the version of the post at that time.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct PostRevision {
+    pub id: u64,
+    pub user_id: u64,
+    pub post_id: u64,
+    pub version: u32,
+    pub title: String,
+    pub content: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub date: NaiveDateTime,
+    pub date_offset: Option<i32>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    pub is_encrypted: bool,
+    pub encryption_scheme: Option<String>,
+    pub encryption_nonce: Option<String>,
+    pub key_version: Option<u32>,
+}
+
+impl PostRevision {
+    /// Returns the date of the revision with its offset.
+    pub fn post_date(&self) -> PostDate {
+        PostDate {
+            date: self.date,
+            offset: self.date_offset,
+        }
+    }
+
+    /// Returns how the title and content of the revision are encrypted.
+    pub fn encryption(&self) -> PostEncryption {
+        PostEncryption {
+            is_encrypted: self.is_encrypted,
+            scheme: self.encryption_scheme.clone(),
+            nonce: self.encryption_nonce.clone(),
+            key_version: self.key_version,
+        }
+    }
+}
+
+/// Post revision DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct PostRevisionDTO {
+    pub version: u32,
+    pub title: String,
+    pub content: String,
+    pub date: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(flatten)]
+    pub encryption: PostEncryptionDTO,
+}
+
+/// Post revision DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "post_revisions"]
+struct PostRevisionDAO {
+    user_id: u64,
+    post_id: u64,
+    version: u32,
+    title: String,
+    content: String,
+    date: NaiveDateTime,
+    date_offset: Option<i32>,
+    is_encrypted: bool,
+    encryption_scheme: Option<String>,
+    encryption_nonce: Option<String>,
+    key_version: Option<u32>,
+}
+
+/// Appends a snapshot of the post to `post_revisions` table.
+///
+/// It takes the connection of the caller, so that the snapshot is written
+/// in the same transaction as the update of the post.
+pub fn append(conn: &MysqlConnection, post: &Post) -> Result<usize, Error> {
+    let revision_to_create = PostRevisionDAO {
+        user_id: post.user_id,
+        post_id: post.id,
+        version: post.version,
+        title: post.title.clone(),
+        content: post.content.clone(),
+        date: post.date,
+        date_offset: post.date_offset,
+        is_encrypted: post.is_encrypted,
+        encryption_scheme: post.encryption_scheme.clone(),
+        encryption_nonce: post.encryption_nonce.clone(),
+        key_version: post.key_version,
+    };
+
+    diesel::insert_into(dsl::post_revisions)
+        .values(revision_to_create)
+        .execute(conn)
+}
+
+/// Finds revisions of a post written by specific user, recent versions first.
+pub fn find_all(
+    conn: &MysqlConnection,
+    user_id: u64,
+    post_id: u64,
+) -> Result<Vec<PostRevision>, Error> {
+    dsl::post_revisions
+        .filter(dsl::user_id.eq(user_id))
+        .filter(dsl::post_id.eq(post_id))
+        .order(dsl::version.desc())
+        .load::<PostRevision>(conn)
+}
+
+/// Finds a revision of a post written by specific user by the version.
+pub fn find(
+    conn: &MysqlConnection,
+    user_id: u64,
+    post_id: u64,
+    version: u32,
+) -> Result<PostRevision, Error> {
+    dsl::post_revisions
+        .filter(dsl::user_id.eq(user_id))
+        .filter(dsl::post_id.eq(post_id))
+        .filter(dsl::version.eq(version))
+        .get_result::<PostRevision>(conn)
+}
+
+/// Deletes revisions of posts, which must be done before deleting the posts.
+pub fn delete_by_post_ids(conn: &MysqlConnection, post_ids: &[u64]) -> Result<usize, Error> {
+    diesel::delete(dsl::post_revisions.filter(dsl::post_id.eq_any(post_ids))).execute(conn)
+}
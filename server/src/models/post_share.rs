@@ -0,0 +1,240 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::post::Post;
+use crate::models::post_audit::{self, AuditContext, PostAuditAction};
+use crate::schema::{post_shares, post_shares::dsl, posts};
+use crate::utils::datetime_util;
+
+/// Post share representing `post_shares` table.
+///
+/// A post has one share at most, and anyone with the token can read the post
+/// until the share expires or is revoked.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct PostShare {
+    pub id: u64,
+    pub user_id: u64,
+    pub post_id: u64,
+    pub token: String,
+    /// Passphrase hashed by scrypt, if the share is protected by a passphrase.
+    pub passphrase: Option<String>,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub expires_at: Option<NaiveDateTime>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    /// Plaintext copy of the title given by the writer, since the server cannot decrypt posts.
+    pub title: Option<String>,
+    /// Plaintext copy of the content in markdown given by the writer.
+    pub content: Option<String>,
+}
+
+/// Post share DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct PostShareDTO {
+    pub token: String,
+    /// URL of the shared post in the client.
+    pub url: String,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub expires_at: Option<NaiveDateTime>,
+    pub has_passphrase: bool,
+}
+
+/// Shared post DTO using between routes layer and service layer.
+///
+/// It has only what readers of the post need, without ids of the post and its writer.
+#[derive(Serialize, Deserialize)]
+pub struct SharedPostDTO {
+    pub title: String,
+    pub content: String,
+    pub date: String,
+    pub mood: Option<u8>,
+    pub weather: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+    /// Whether title and content are plaintext copies given by the writer,
+    /// or encrypted ones which only the client can read.
+    pub is_plaintext: bool,
+}
+
+/// Post share DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "post_shares"]
+struct PostShareDAO {
+    user_id: u64,
+    post_id: u64,
+    token: String,
+    passphrase: Option<String>,
+    expires_at: Option<NaiveDateTime>,
+    title: Option<String>,
+    content: Option<String>,
+}
+
+/// Deletes shares of posts, which must be done before deleting the posts.
+pub fn delete_by_post_ids(conn: &MysqlConnection, post_ids: &[u64]) -> Result<usize, Error> {
+    diesel::delete(dsl::post_shares.filter(dsl::post_id.eq_any(post_ids))).execute(conn)
+}
+
+/// A core data repository for post share.
+pub struct PostShareRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait PostShareRepositoryTrait {
+    fn find_by_token(&self, token: &str) -> Result<(PostShare, Post), ServiceError>;
+    fn create(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        token: &str,
+        passphrase: &Option<String>,
+        expires_at: &Option<NaiveDateTime>,
+        title: &Option<String>,
+        content: &Option<String>,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError>;
+    fn delete(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError>;
+}
+
+impl PostShareRepository {
+    /// Creates a new post share repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds a share by its token with the shared post, except posts in the trash.
+    pub fn find_by_token(&self, token: &str) -> Result<(PostShare, Post), ServiceError> {
+        let share = dsl::post_shares
+            .inner_join(posts::table)
+            .filter(dsl::token.eq(token))
+            .filter(posts::dsl::deleted_at.is_null())
+            .get_result::<(PostShare, Post)>(&self.conn);
+
+        match share {
+            Ok(share) => Ok(share),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(String::from(
+                    "share",
+                )))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Shares a post written by specific user with `token`, replacing the previous share,
+    /// and appends an audit entry in the same transaction.
+    ///
+    /// `passphrase` must be hashed. Posts in the trash cannot be shared.
+    pub fn create(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        token: &str,
+        passphrase: &Option<String>,
+        expires_at: &Option<NaiveDateTime>,
+        title: &Option<String>,
+        content: &Option<String>,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let owned_post = posts::dsl::posts
+                .find(post_id)
+                .filter(posts::dsl::user_id.eq(user_id))
+                .filter(posts::dsl::deleted_at.is_null());
+            let is_owned = diesel::select(exists(owned_post)).get_result::<bool>(&self.conn)?;
+            if !is_owned {
+                return Err(Error::NotFound);
+            }
+
+            delete_by_post_ids(&self.conn, &[post_id])?;
+
+            let share_to_create = PostShareDAO {
+                user_id,
+                post_id,
+                token: token.to_string(),
+                passphrase: passphrase.clone(),
+                expires_at: *expires_at,
+                title: title.clone(),
+                content: content.clone(),
+            };
+            diesel::insert_into(dsl::post_shares)
+                .values(share_to_create)
+                .execute(&self.conn)?;
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Share,
+                context,
+            )?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    post_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Revokes the share of a post written by specific user, and appends an audit entry
+    /// in the same transaction.
+    pub fn delete(
+        &self,
+        user_id: u64,
+        post_id: u64,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let target_share = dsl::post_shares
+                .filter(dsl::user_id.eq(user_id))
+                .filter(dsl::post_id.eq(post_id));
+            let count = diesel::delete(target_share).execute(&self.conn)?;
+            if count == 0 {
+                return Err(Error::NotFound);
+            }
+
+            post_audit::append(
+                &self.conn,
+                user_id,
+                post_id,
+                PostAuditAction::Unshare,
+                context,
+            )?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(Error::NotFound) => Err(get_service_error(ServiceError::NotFound(
+                post_id.to_string(),
+            ))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for PostShareRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,78 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use serde::{Deserialize, Serialize};
+
+use crate::schema::{post_tombstones, post_tombstones::dsl, posts};
+use crate::utils::datetime_util;
+
+/// Post tombstone representing `post_tombstones` table.
+///
+/// A tombstone is left when a post is permanently deleted,
+/// so that clients syncing changes can remove the post as well.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct PostTombstone {
+    pub id: u64,
+    pub user_id: u64,
+    pub post_id: u64,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub deleted_at: NaiveDateTime,
+}
+
+/// Post tombstone DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "post_tombstones"]
+struct PostTombstoneDAO {
+    user_id: u64,
+    post_id: u64,
+}
+
+/// Appends tombstones of posts, which must be done before permanently deleting the posts.
+///
+/// It takes the connection of the caller, so that the tombstones are written
+/// in the same transaction as the deletion of the posts.
+pub fn append(conn: &MysqlConnection, post_ids: &[u64]) -> Result<usize, Error> {
+    let tombstones_to_create: Vec<PostTombstoneDAO> = posts::dsl::posts
+        .select((posts::dsl::user_id, posts::dsl::id))
+        .filter(posts::dsl::id.eq_any(post_ids))
+        .load::<(u64, u64)>(conn)?
+        .into_iter()
+        .map(|(user_id, post_id)| PostTombstoneDAO { user_id, post_id })
+        .collect();
+    if tombstones_to_create.is_empty() {
+        return Ok(0);
+    }
+
+    diesel::insert_into(dsl::post_tombstones)
+        .values(&tombstones_to_create)
+        .execute(conn)
+}
+
+/// Finds tombstones of posts written by specific user, left after `since`.
+pub fn find_since(
+    conn: &MysqlConnection,
+    user_id: u64,
+    since: &NaiveDateTime,
+) -> Result<Vec<PostTombstone>, Error> {
+    dsl::post_tombstones
+        .filter(dsl::user_id.eq(user_id))
+        .filter(dsl::deleted_at.gt(since))
+        .order(dsl::deleted_at.asc())
+        .load::<PostTombstone>(conn)
+}
+
+/// Deletes tombstones left before `threshold`, and returns the count.
+///
+/// Clients which have not synced since then cannot know the posts were deleted,
+/// so they must sync from scratch.
+pub fn delete_older_than(
+    conn: &MysqlConnection,
+    threshold: &NaiveDateTime,
+) -> Result<usize, Error> {
+    diesel::delete(dsl::post_tombstones.filter(dsl::deleted_at.lt(threshold))).execute(conn)
+}
+
+/// Deletes tombstones of posts written by specific user, which is done when the user is deleted.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::post_tombstones.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
@@ -0,0 +1,176 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{prompt_subscriptions, prompts, prompts::dsl, users};
+use crate::utils::datetime_util;
+
+/// Writing prompt representing `prompts` table.
+///
+/// Prompts are shared by all users and written in plaintext, unlike posts.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct Prompt {
+    pub id: u64,
+    pub text: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Prompt DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct PromptDTO {
+    pub id: u64,
+    pub text: String,
+    /// The date the prompt is served on.
+    pub date: NaiveDate,
+}
+
+/// Recipient of prompt emails, who subscribes to them.
+#[derive(Debug, Queryable)]
+pub struct PromptRecipient {
+    pub user_id: u64,
+    pub name: String,
+    pub email: String,
+}
+
+/// Prompt subscription DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "prompt_subscriptions"]
+struct PromptSubscriptionDAO {
+    user_id: u64,
+}
+
+/// Deletes the prompt subscription of specific user.
+pub fn delete_subscription_by_user_id(
+    conn: &MysqlConnection,
+    user_id: u64,
+) -> Result<usize, Error> {
+    diesel::delete(
+        prompt_subscriptions::dsl::prompt_subscriptions
+            .filter(prompt_subscriptions::dsl::user_id.eq(user_id)),
+    )
+    .execute(conn)
+}
+
+/// A core data repository for prompt.
+pub struct PromptRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait PromptRepositoryTrait {
+    fn count(&self) -> Result<i64, ServiceError>;
+    fn find_nth(&self, offset: i64) -> Result<Prompt, ServiceError>;
+    fn subscribe(&self, user_id: u64) -> Result<bool, ServiceError>;
+    fn unsubscribe(&self, user_id: u64) -> Result<bool, ServiceError>;
+    fn find_recipients(&self, date: &NaiveDate) -> Result<Vec<PromptRecipient>, ServiceError>;
+    fn mark_sent(&self, user_id: u64, date: &NaiveDate) -> Result<bool, ServiceError>;
+}
+
+impl PromptRepository {
+    /// Creates a new prompt repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Counts all prompts.
+    pub fn count(&self) -> Result<i64, ServiceError> {
+        let count = dsl::prompts.count().get_result::<i64>(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds the prompt at `offset` in the order of creation.
+    pub fn find_nth(&self, offset: i64) -> Result<Prompt, ServiceError> {
+        let prompt = dsl::prompts
+            .order(dsl::id.asc())
+            .offset(offset)
+            .first::<Prompt>(&self.conn);
+
+        match prompt {
+            Ok(prompt) => Ok(prompt),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(String::from(
+                    "prompt",
+                )))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Subscribes specific user to prompt emails, and returns whether the user newly subscribed.
+    pub fn subscribe(&self, user_id: u64) -> Result<bool, ServiceError> {
+        let subscription_to_create = PromptSubscriptionDAO { user_id };
+        let count = diesel::insert_or_ignore_into(prompt_subscriptions::dsl::prompt_subscriptions)
+            .values(subscription_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Unsubscribes specific user from prompt emails.
+    pub fn unsubscribe(&self, user_id: u64) -> Result<bool, ServiceError> {
+        match delete_subscription_by_user_id(&self.conn, user_id) {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(
+                user_id.to_string(),
+            ))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds subscribers who have not been sent a prompt on `date`.
+    pub fn find_recipients(&self, date: &NaiveDate) -> Result<Vec<PromptRecipient>, ServiceError> {
+        let recipient_list = prompt_subscriptions::dsl::prompt_subscriptions
+            .inner_join(users::table)
+            .select((
+                prompt_subscriptions::dsl::user_id,
+                users::dsl::name,
+                users::dsl::email,
+            ))
+            .filter(
+                prompt_subscriptions::dsl::last_sent_on
+                    .is_null()
+                    .or(prompt_subscriptions::dsl::last_sent_on.lt(date)),
+            )
+            .order(prompt_subscriptions::dsl::user_id.asc())
+            .load::<PromptRecipient>(&self.conn);
+
+        match recipient_list {
+            Ok(recipient_list) => Ok(recipient_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Records that a prompt has been sent to specific user on `date`.
+    pub fn mark_sent(&self, user_id: u64, date: &NaiveDate) -> Result<bool, ServiceError> {
+        let target_subscription = prompt_subscriptions::dsl::prompt_subscriptions
+            .filter(prompt_subscriptions::dsl::user_id.eq(user_id));
+        let count = diesel::update(target_subscription)
+            .set(prompt_subscriptions::dsl::last_sent_on.eq(date))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for PromptRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
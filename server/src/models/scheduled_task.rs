@@ -0,0 +1,164 @@
+use chrono::{Duration, NaiveDateTime};
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{scheduled_tasks, scheduled_tasks::dsl};
+use crate::utils::datetime_util;
+
+/// Scheduled task representing `scheduled_tasks` table.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct ScheduledTask {
+    pub name: String,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub last_run_at: Option<NaiveDateTime>,
+    pub last_status: Option<String>,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub locked_until: Option<NaiveDateTime>,
+}
+
+/// Scheduled task DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct ScheduledTaskDTO {
+    pub name: String,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub last_run_at: Option<NaiveDateTime>,
+    pub last_status: Option<String>,
+    pub running: bool,
+}
+
+/// Scheduled task DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "scheduled_tasks"]
+struct ScheduledTaskDAO {
+    name: String,
+}
+
+/// A core data repository for scheduled task.
+pub struct ScheduledTaskRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait ScheduledTaskRepositoryTrait {
+    fn find_all(&self) -> Result<Vec<ScheduledTask>, ServiceError>;
+    fn create_if_not_exists(&self, name: &str) -> Result<bool, ServiceError>;
+    fn lock(
+        &self,
+        name: &str,
+        last_run_at: &Option<NaiveDateTime>,
+        now: &NaiveDateTime,
+        lease: Duration,
+    ) -> Result<bool, ServiceError>;
+    fn unlock(
+        &self,
+        name: &str,
+        run_at: &NaiveDateTime,
+        status: &str,
+    ) -> Result<bool, ServiceError>;
+}
+
+impl ScheduledTaskRepository {
+    /// Creates a new scheduled task repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds all scheduled tasks.
+    pub fn find_all(&self) -> Result<Vec<ScheduledTask>, ServiceError> {
+        let task_list: Result<Vec<ScheduledTask>, Error> = dsl::scheduled_tasks
+            .order(dsl::name.asc())
+            .load::<ScheduledTask>(&self.conn);
+
+        match task_list {
+            Ok(task_list) => Ok(task_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a scheduled task that has never run, unless it exists.
+    pub fn create_if_not_exists(&self, name: &str) -> Result<bool, ServiceError> {
+        let task_to_create = ScheduledTaskDAO {
+            name: name.to_string(),
+        };
+
+        let count = diesel::insert_or_ignore_into(dsl::scheduled_tasks)
+            .values(task_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Locks a scheduled task for `lease` from `now`, and returns whether the lock was acquired.
+    ///
+    /// The lock is acquired only if the task has not run since `last_run_at`, so that
+    /// the task does not run twice when another process ran it in the meantime.
+    /// The lock expires after `lease`, so that a task is not locked forever
+    /// if the server stopped while running it.
+    pub fn lock(
+        &self,
+        name: &str,
+        last_run_at: &Option<NaiveDateTime>,
+        now: &NaiveDateTime,
+        lease: Duration,
+    ) -> Result<bool, ServiceError> {
+        let now = *now;
+        let target_task = dsl::scheduled_tasks
+            .find(name)
+            .filter(dsl::locked_until.is_null().or(dsl::locked_until.lt(now)));
+        let count = match last_run_at {
+            Some(last_run_at) => {
+                let target_task = target_task.filter(dsl::last_run_at.eq(last_run_at));
+                diesel::update(target_task)
+                    .set(dsl::locked_until.eq(now + lease))
+                    .execute(&self.conn)
+            }
+            None => {
+                let target_task = target_task.filter(dsl::last_run_at.is_null());
+                diesel::update(target_task)
+                    .set(dsl::locked_until.eq(now + lease))
+                    .execute(&self.conn)
+            }
+        };
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Unlocks a scheduled task, and records the result of the run.
+    pub fn unlock(
+        &self,
+        name: &str,
+        run_at: &NaiveDateTime,
+        status: &str,
+    ) -> Result<bool, ServiceError> {
+        let count = diesel::update(dsl::scheduled_tasks.find(name))
+            .set((
+                dsl::last_run_at.eq(run_at),
+                dsl::last_status.eq(status),
+                dsl::locked_until.eq(None::<NaiveDateTime>),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for ScheduledTaskRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
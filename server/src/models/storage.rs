@@ -0,0 +1,226 @@
+use chrono::Utc;
+use mockall::automock;
+use reqwest::blocking::{Client, Response};
+use reqwest::{Method, StatusCode};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::utils::signature_util::{self, Credentials};
+
+/// Time limit of each request to object storage.
+const OBJECT_STORAGE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Storage of files keyed by name, such as blobs of attachments.
+///
+/// The backend is selected by `connection::try_connect_storage`.
+#[automock]
+pub trait Storage {
+    /// Writes a file, replacing the file of the same key.
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), ServiceError>;
+    /// Reads a file, or returns `NotFound` error if there is no file of the key.
+    fn get(&self, key: &str) -> Result<Vec<u8>, ServiceError>;
+    /// Deletes a file. Deleting a file which does not exist is not an error.
+    fn delete(&self, key: &str) -> Result<(), ServiceError>;
+    /// Checks that files can be written, read, and deleted, and returns the location.
+    fn check(&self) -> Result<String, String>;
+}
+
+/// Storage keeping files in a directory of the local disk.
+///
+/// Files are spread into subdirectories by the first two characters of their keys,
+/// so that a directory does not hold too many files.
+pub struct LocalStorage {
+    directory: PathBuf,
+}
+
+impl LocalStorage {
+    /// Creates a new local storage in `directory`.
+    pub fn new(directory: &str) -> Self {
+        Self {
+            directory: PathBuf::from(directory),
+        }
+    }
+
+    /// Returns the path of the file of the key.
+    fn get_path(&self, key: &str) -> PathBuf {
+        let prefix: String = key.chars().take(2).collect();
+        self.directory.join(prefix).join(key)
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), ServiceError> {
+        let path = self.get_path(key);
+        let written = match path.parent() {
+            Some(parent) => fs::create_dir_all(parent).and_then(|_| fs::write(&path, data)),
+            None => fs::write(&path, data),
+        };
+
+        written.map_err(|_| get_service_error(ServiceError::InternalServerError))
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        match fs::read(self.get_path(key)) {
+            Ok(data) => Ok(data),
+            Err(error) => match error.kind() {
+                ErrorKind::NotFound => {
+                    Err(get_service_error(ServiceError::NotFound(key.to_string())))
+                }
+                _ => Err(get_service_error(ServiceError::InternalServerError)),
+            },
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), ServiceError> {
+        match fs::remove_file(self.get_path(key)) {
+            Ok(_) => Ok(()),
+            Err(error) => match error.kind() {
+                ErrorKind::NotFound => Ok(()),
+                _ => Err(get_service_error(ServiceError::InternalServerError)),
+            },
+        }
+    }
+
+    fn check(&self) -> Result<String, String> {
+        let key = "check";
+        let location = self.directory.display();
+        let failure = |action: &str| format!("Failed to {} a file in {}", action, location);
+
+        self.put(key, key.as_bytes())
+            .map_err(|_| failure("write"))?;
+        self.get(key).map_err(|_| failure("read"))?;
+        self.delete(key).map_err(|_| failure("delete"))?;
+        Ok(format!("writable {}", location))
+    }
+}
+
+/// Storage keeping files as objects in a bucket of S3 or an S3-compatible object storage
+/// such as MinIO, so that the server keeps no file on its own disk.
+///
+/// Objects are addressed by path style, `endpoint/bucket/prefix+key`, which both support.
+pub struct S3Storage {
+    http_client: Client,
+    endpoint: String,
+    host: String,
+    bucket: String,
+    prefix: String,
+    credentials: Credentials,
+}
+
+impl S3Storage {
+    /// Creates a new S3 storage.
+    ///
+    /// `endpoint` must have a scheme and a host without a path,
+    /// e.g. `https://s3.us-east-1.amazonaws.com` or `http://minio:9000`.
+    pub fn new(
+        endpoint: &str,
+        bucket: &str,
+        prefix: &str,
+        credentials: Credentials,
+    ) -> Result<Self, String> {
+        let endpoint = endpoint.trim_end_matches('/');
+        let host = endpoint
+            .strip_prefix("https://")
+            .or_else(|| endpoint.strip_prefix("http://"))
+            .ok_or_else(|| format!("`{}` must start with http:// or https://", endpoint))?;
+        if host.is_empty() || host.contains('/') {
+            return Err(format!("`{}` must have a host without a path", endpoint));
+        }
+
+        let http_client = Client::builder()
+            .timeout(OBJECT_STORAGE_TIMEOUT)
+            .build()
+            .map_err(|error| format!("Failed to build HTTP client: {}", error))?;
+
+        Ok(Self {
+            http_client,
+            endpoint: endpoint.to_string(),
+            host: host.to_string(),
+            bucket: bucket.to_string(),
+            prefix: prefix.to_string(),
+            credentials,
+        })
+    }
+
+    /// Sends a signed request on the object of the key.
+    /// An error status of object storage is returned as an error.
+    fn send(&self, method: Method, key: &str, data: &[u8]) -> reqwest::Result<Response> {
+        let path = signature_util::encode_path(&format!("/{}/{}{}", self.bucket, self.prefix, key));
+        let timestamp = Utc::now();
+        let amz_date = signature_util::get_amz_date(&timestamp);
+        let payload_hash = signature_util::get_payload_hash(data);
+        let authorization = signature_util::sign(
+            &self.credentials,
+            method.as_str(),
+            &path,
+            &[
+                ("host", self.host.as_str()),
+                ("x-amz-content-sha256", payload_hash.as_str()),
+                ("x-amz-date", amz_date.as_str()),
+            ],
+            &payload_hash,
+            &timestamp,
+        );
+
+        let request = self
+            .http_client
+            .request(method.clone(), &format!("{}{}", self.endpoint, path))
+            .header("x-amz-content-sha256", payload_hash.as_str())
+            .header("x-amz-date", amz_date.as_str())
+            .header("Authorization", authorization.as_str());
+        let request = match method {
+            Method::PUT => request.body(data.to_vec()),
+            _ => request,
+        };
+        request.send()?.error_for_status()
+    }
+}
+
+impl Storage for S3Storage {
+    fn put(&self, key: &str, data: &[u8]) -> Result<(), ServiceError> {
+        match self.send(Method::PUT, key, data) {
+            Ok(_) => Ok(()),
+            Err(_) => Err(get_service_error(ServiceError::InternalServerError)),
+        }
+    }
+
+    fn get(&self, key: &str) -> Result<Vec<u8>, ServiceError> {
+        match self.send(Method::GET, key, &[]) {
+            Ok(response) => match response.bytes() {
+                Ok(data) => Ok(data.to_vec()),
+                Err(_) => Err(get_service_error(ServiceError::InternalServerError)),
+            },
+            Err(error) if error.status() == Some(StatusCode::NOT_FOUND) => {
+                Err(get_service_error(ServiceError::NotFound(key.to_string())))
+            }
+            Err(_) => Err(get_service_error(ServiceError::InternalServerError)),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), ServiceError> {
+        match self.send(Method::DELETE, key, &[]) {
+            Ok(_) => Ok(()),
+            Err(error) if error.status() == Some(StatusCode::NOT_FOUND) => Ok(()),
+            Err(_) => Err(get_service_error(ServiceError::InternalServerError)),
+        }
+    }
+
+    fn check(&self) -> Result<String, String> {
+        let key = "check";
+        let location = format!("s3://{}/{}", self.bucket, self.prefix);
+        let failure = |action: &str, error: reqwest::Error| {
+            format!("Failed to {} an object in {}: {}", action, location, error)
+        };
+
+        self.send(Method::PUT, key, key.as_bytes())
+            .map_err(|error| failure("write", error))?;
+        self.send(Method::GET, key, &[])
+            .map_err(|error| failure("read", error))?;
+        self.send(Method::DELETE, key, &[])
+            .map_err(|error| failure("delete", error))?;
+        Ok(format!("writable {}", location))
+    }
+}
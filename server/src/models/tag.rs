@@ -0,0 +1,259 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::dsl::{exists, sql};
+use diesel::prelude::*;
+use diesel::result::Error;
+use diesel::sql_types::{Bigint, Datetime, Unsigned};
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{post_tags, posts, tags, tags::dsl};
+use crate::utils::datetime_util;
+
+no_arg_sql_function!(
+    last_insert_id,
+    diesel::sql_types::Unsigned<diesel::sql_types::Bigint>
+);
+
+/// Tag representing `tags` table.
+///
+/// The name is encrypted by the client like titles of posts, so the server never reads it.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct Tag {
+    pub id: u64,
+    pub user_id: u64,
+    pub name: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Tag DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct TagDTO {
+    pub id: u64,
+    pub name: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Tag with usage DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct TagUsageDTO {
+    pub id: u64,
+    pub name: String,
+    /// The number of posts with the tag, except posts in the trash.
+    pub post_count: u64,
+}
+
+/// Tag DAO using between models layer and RDB.
+#[derive(Insertable, AsChangeset)]
+#[table_name = "tags"]
+struct TagDAO {
+    user_id: Option<u64>,
+    name: Option<String>,
+    updated_at: Option<NaiveDateTime>,
+}
+
+/// Post tag DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "post_tags"]
+struct PostTagDAO {
+    post_id: u64,
+    tag_id: u64,
+}
+
+/// Returns tag ids without duplicates.
+fn get_distinct_tag_ids(tag_ids: &[u64]) -> Vec<u64> {
+    let mut distinct_tag_ids = tag_ids.to_vec();
+    distinct_tag_ids.sort_unstable();
+    distinct_tag_ids.dedup();
+    distinct_tag_ids
+}
+
+/// Returns whether all tags in `tag_ids` belong to specific user.
+pub fn are_owned(conn: &MysqlConnection, user_id: u64, tag_ids: &[u64]) -> Result<bool, Error> {
+    let distinct_tag_ids = get_distinct_tag_ids(tag_ids);
+    let count = dsl::tags
+        .filter(dsl::user_id.eq(user_id))
+        .filter(dsl::id.eq_any(&distinct_tag_ids))
+        .count()
+        .get_result::<i64>(conn)?;
+    Ok(count == distinct_tag_ids.len() as i64)
+}
+
+/// Finds pairs of post id and tag id of posts.
+pub fn find_post_tags(conn: &MysqlConnection, post_ids: &[u64]) -> Result<Vec<(u64, u64)>, Error> {
+    post_tags::dsl::post_tags
+        .filter(post_tags::dsl::post_id.eq_any(post_ids))
+        .order((post_tags::dsl::post_id.asc(), post_tags::dsl::tag_id.asc()))
+        .load::<(u64, u64)>(conn)
+}
+
+/// Replaces tags of a post with `tag_ids`.
+pub fn set_post_tags(conn: &MysqlConnection, post_id: u64, tag_ids: &[u64]) -> Result<(), Error> {
+    delete_post_tags(conn, &[post_id])?;
+
+    let post_tags_to_create: Vec<PostTagDAO> = get_distinct_tag_ids(tag_ids)
+        .into_iter()
+        .map(|tag_id| PostTagDAO { post_id, tag_id })
+        .collect();
+    diesel::insert_into(post_tags::dsl::post_tags)
+        .values(&post_tags_to_create)
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Deletes tags of posts, which must be done before deleting the posts.
+pub fn delete_post_tags(conn: &MysqlConnection, post_ids: &[u64]) -> Result<usize, Error> {
+    diesel::delete(post_tags::dsl::post_tags.filter(post_tags::dsl::post_id.eq_any(post_ids)))
+        .execute(conn)
+}
+
+/// A core data repository for tag.
+pub struct TagRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait TagRepositoryTrait {
+    fn find_all(&self, user_id: u64) -> Result<Vec<Tag>, ServiceError>;
+    fn find_all_with_post_count(&self, user_id: u64) -> Result<Vec<(Tag, u64)>, ServiceError>;
+    fn create(&self, user_id: u64, name: &str) -> Result<u64, ServiceError>;
+    fn update(&self, user_id: u64, tag_id: u64, name: &str) -> Result<bool, ServiceError>;
+    fn delete(&self, user_id: u64, tag_id: u64) -> Result<bool, ServiceError>;
+}
+
+impl TagRepository {
+    /// Creates a new tag repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds all tags of specific user in the order of creation.
+    pub fn find_all(&self, user_id: u64) -> Result<Vec<Tag>, ServiceError> {
+        let tag_list: Result<Vec<Tag>, Error> = dsl::tags
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::id.asc())
+            .load::<Tag>(&self.conn);
+
+        match tag_list {
+            Ok(tag_list) => Ok(tag_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds all tags of specific user with the number of posts with each tag,
+    /// in the order of creation.
+    ///
+    /// Posts are counted per tag through the index on `post_tags.tag_id`,
+    /// and posts in the trash are not counted.
+    pub fn find_all_with_post_count(&self, user_id: u64) -> Result<Vec<(Tag, u64)>, ServiceError> {
+        let tag_list = dsl::tags
+            .select((
+                tags::all_columns,
+                sql::<Unsigned<Bigint>>(
+                    "CAST((SELECT COUNT(*) FROM post_tags \
+                     INNER JOIN posts ON posts.id = post_tags.post_id \
+                     WHERE post_tags.tag_id = tags.id AND posts.deleted_at IS NULL) AS UNSIGNED)",
+                ),
+            ))
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::id.asc())
+            .load::<(Tag, u64)>(&self.conn);
+
+        match tag_list {
+            Ok(tag_list) => Ok(tag_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new tag and returns id of the created tag.
+    pub fn create(&self, user_id: u64, name: &str) -> Result<u64, ServiceError> {
+        let tag_to_create = TagDAO {
+            user_id: Some(user_id),
+            name: Some(name.to_string()),
+            updated_at: None,
+        };
+
+        let tag_id = self.conn.transaction::<u64, Error, _>(|| {
+            diesel::insert_into(dsl::tags)
+                .values(tag_to_create)
+                .execute(&self.conn)?;
+            diesel::select(last_insert_id).get_result::<u64>(&self.conn)
+        });
+
+        match tag_id {
+            Ok(tag_id) => Ok(tag_id),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Renames a tag of specific user.
+    pub fn update(&self, user_id: u64, tag_id: u64, name: &str) -> Result<bool, ServiceError> {
+        let tag_to_update = TagDAO {
+            user_id: None,
+            name: Some(name.to_string()),
+            updated_at: Some(Utc::now().naive_utc()),
+        };
+
+        let target_tag = dsl::tags.find(tag_id).filter(dsl::user_id.eq(user_id));
+        let count = diesel::update(target_tag)
+            .set(tag_to_update)
+            .execute(&self.conn);
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(
+                tag_id.to_string(),
+            ))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a tag of specific user, and removes it from posts.
+    pub fn delete(&self, user_id: u64, tag_id: u64) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let owned_tag = dsl::tags.find(tag_id).filter(dsl::user_id.eq(user_id));
+            let is_owned = diesel::select(exists(owned_tag)).get_result::<bool>(&self.conn)?;
+            if !is_owned {
+                return Err(Error::NotFound);
+            }
+
+            // Tags are a part of posts, so the posts are changed for clients syncing changes.
+            let tagged_post_ids = post_tags::dsl::post_tags
+                .select(post_tags::dsl::post_id)
+                .filter(post_tags::dsl::tag_id.eq(tag_id));
+            diesel::update(posts::dsl::posts.filter(posts::dsl::id.eq_any(tagged_post_ids)))
+                .set(posts::dsl::changed_at.eq(sql::<Datetime>("CURRENT_TIMESTAMP(6)")))
+                .execute(&self.conn)?;
+
+            diesel::delete(post_tags::dsl::post_tags.filter(post_tags::dsl::tag_id.eq(tag_id)))
+                .execute(&self.conn)?;
+            diesel::delete(dsl::tags.find(tag_id)).execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    tag_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+}
+
+impl Default for TagRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
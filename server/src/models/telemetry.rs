@@ -0,0 +1,112 @@
+use chrono::NaiveDate;
+use diesel::prelude::*;
+use diesel::sql_types::{Date, Varchar};
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{telemetry_events, user_settings};
+
+/// Names of the events the client is allowed to send.
+pub const TELEMETRY_EVENTS: [&str; 6] = [
+    "calendar_view",
+    "list_view",
+    "post_view",
+    "post_write",
+    "profile_settings_view",
+    "security_settings_view",
+];
+
+/// Count of an event on a day representing `telemetry_events` table.
+#[derive(Debug, Queryable)]
+pub struct TelemetryEvent {
+    pub event: String,
+    pub date: NaiveDate,
+    pub count: u64,
+}
+
+/// Count of an event on a day using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct TelemetryCountDTO {
+    pub event: String,
+    pub date: NaiveDate,
+    pub count: u64,
+}
+
+/// A core data repository for telemetry.
+///
+/// Events are counted by name and date only, and never linked to the user who sent them.
+pub struct TelemetryRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait TelemetryRepositoryTrait {
+    fn find_opt_in(&self, user_id: u64) -> Result<bool, ServiceError>;
+    fn increase(&self, event: &str, date: &NaiveDate) -> Result<bool, ServiceError>;
+    fn find_all_since(&self, from: &NaiveDate) -> Result<Vec<TelemetryEvent>, ServiceError>;
+}
+
+impl TelemetryRepository {
+    /// Creates a new telemetry repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds whether the user opted in to telemetry in the settings.
+    ///
+    /// A user who has never changed the settings has not opted in.
+    pub fn find_opt_in(&self, user_id: u64) -> Result<bool, ServiceError> {
+        let opt_in = user_settings::dsl::user_settings
+            .find(user_id)
+            .select(user_settings::dsl::telemetry_opt_in)
+            .get_result::<bool>(&self.conn)
+            .optional();
+
+        match opt_in {
+            Ok(opt_in) => Ok(opt_in.unwrap_or(false)),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Increases the count of the event on the date.
+    pub fn increase(&self, event: &str, date: &NaiveDate) -> Result<bool, ServiceError> {
+        let count = diesel::sql_query(
+            "INSERT INTO telemetry_events (event, date, count) VALUES (?, ?, 1) \
+             ON DUPLICATE KEY UPDATE count = count + 1",
+        )
+        .bind::<Varchar, _>(event)
+        .bind::<Date, _>(date)
+        .execute(&self.conn);
+
+        match count {
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Finds counts of events on `from` and later, in asc order of the dates and the events.
+    pub fn find_all_since(&self, from: &NaiveDate) -> Result<Vec<TelemetryEvent>, ServiceError> {
+        let event_list = telemetry_events::dsl::telemetry_events
+            .filter(telemetry_events::dsl::date.ge(from))
+            .order((
+                telemetry_events::dsl::date.asc(),
+                telemetry_events::dsl::event.asc(),
+            ))
+            .load::<TelemetryEvent>(&self.conn);
+
+        match event_list {
+            Ok(event_list) => Ok(event_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for TelemetryRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
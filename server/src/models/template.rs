@@ -0,0 +1,212 @@
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{templates, templates::dsl};
+use crate::utils::datetime_util;
+
+no_arg_sql_function!(
+    last_insert_id,
+    diesel::sql_types::Unsigned<diesel::sql_types::Bigint>
+);
+
+/// Template representing `templates` table.
+///
+/// Name, title and content are encrypted by the client like posts, so the server never reads them.
+/// Placeholders in a template such as `{{date}}` are expanded by the client.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct Template {
+    pub id: u64,
+    pub user_id: u64,
+    pub name: String,
+    pub title: String,
+    pub content: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Template DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct TemplateDTO {
+    pub id: u64,
+    pub name: String,
+    pub title: String,
+    pub content: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// Template DAO using between models layer and RDB.
+#[derive(Insertable, AsChangeset)]
+#[table_name = "templates"]
+struct TemplateDAO {
+    user_id: Option<u64>,
+    name: Option<String>,
+    title: Option<String>,
+    content: Option<String>,
+    updated_at: Option<NaiveDateTime>,
+}
+
+/// Deletes templates of specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::templates.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for template.
+pub struct TemplateRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait TemplateRepositoryTrait {
+    fn find(&self, user_id: u64, template_id: u64) -> Result<Template, ServiceError>;
+    fn find_all(&self, user_id: u64) -> Result<Vec<Template>, ServiceError>;
+    fn create(
+        &self,
+        user_id: u64,
+        name: &str,
+        title: &str,
+        content: &str,
+    ) -> Result<u64, ServiceError>;
+    fn update(
+        &self,
+        user_id: u64,
+        template_id: u64,
+        name: &Option<String>,
+        title: &Option<String>,
+        content: &Option<String>,
+    ) -> Result<bool, ServiceError>;
+    fn delete(&self, user_id: u64, template_id: u64) -> Result<bool, ServiceError>;
+}
+
+impl TemplateRepository {
+    /// Creates a new template repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds a template by user id and template id.
+    pub fn find(&self, user_id: u64, template_id: u64) -> Result<Template, ServiceError> {
+        let template: Result<Template, Error> = dsl::templates
+            .find(template_id)
+            .filter(dsl::user_id.eq(user_id))
+            .get_result::<Template>(&self.conn);
+
+        match template {
+            Ok(template) => Ok(template),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    template_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Finds all templates of specific user in the order of creation.
+    pub fn find_all(&self, user_id: u64) -> Result<Vec<Template>, ServiceError> {
+        let template_list: Result<Vec<Template>, Error> = dsl::templates
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::id.asc())
+            .load::<Template>(&self.conn);
+
+        match template_list {
+            Ok(template_list) => Ok(template_list),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new template and returns id of the created template.
+    pub fn create(
+        &self,
+        user_id: u64,
+        name: &str,
+        title: &str,
+        content: &str,
+    ) -> Result<u64, ServiceError> {
+        let template_to_create = TemplateDAO {
+            user_id: Some(user_id),
+            name: Some(name.to_string()),
+            title: Some(title.to_string()),
+            content: Some(content.to_string()),
+            updated_at: None,
+        };
+
+        let template_id = self.conn.transaction::<u64, Error, _>(|| {
+            diesel::insert_into(dsl::templates)
+                .values(template_to_create)
+                .execute(&self.conn)?;
+            diesel::select(last_insert_id).get_result::<u64>(&self.conn)
+        });
+
+        match template_id {
+            Ok(template_id) => Ok(template_id),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Updates a template of specific user.
+    pub fn update(
+        &self,
+        user_id: u64,
+        template_id: u64,
+        name: &Option<String>,
+        title: &Option<String>,
+        content: &Option<String>,
+    ) -> Result<bool, ServiceError> {
+        let template_to_update = TemplateDAO {
+            user_id: None,
+            name: name.clone(),
+            title: title.clone(),
+            content: content.clone(),
+            updated_at: Some(Utc::now().naive_utc()),
+        };
+
+        let target_template = dsl::templates
+            .find(template_id)
+            .filter(dsl::user_id.eq(user_id));
+        let count = diesel::update(target_template)
+            .set(template_to_update)
+            .execute(&self.conn);
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(
+                template_id.to_string(),
+            ))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a template of specific user. Posts created from the template are kept.
+    pub fn delete(&self, user_id: u64, template_id: u64) -> Result<bool, ServiceError> {
+        let target_template = dsl::templates
+            .find(template_id)
+            .filter(dsl::user_id.eq(user_id));
+        let count = diesel::delete(target_template).execute(&self.conn);
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(
+                template_id.to_string(),
+            ))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for TemplateRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
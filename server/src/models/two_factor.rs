@@ -0,0 +1,233 @@
+use chrono::NaiveDateTime;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{two_factor_recovery_codes, two_factors, two_factors::dsl};
+use crate::utils::datetime_util;
+
+/// Two-factor authentication representing `two_factors` table.
+///
+/// A user has one at most, which is pending until `enabled_at` is set.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct TwoFactor {
+    pub user_id: u64,
+    /// TOTP secret encoded in base32.
+    pub secret: String,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub enabled_at: Option<NaiveDateTime>,
+    /// The last time step whose code was accepted.
+    pub last_used_step: Option<u64>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+}
+
+/// Two-factor setup DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorSetupDTO {
+    /// TOTP secret encoded in base32, for authenticator apps without a QR code scanner.
+    pub secret: String,
+    /// `otpauth` URI of the secret, which the client shows as a QR code.
+    pub uri: String,
+}
+
+/// Two-factor DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "two_factors"]
+struct TwoFactorDAO {
+    user_id: u64,
+    secret: String,
+}
+
+/// Recovery code DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "two_factor_recovery_codes"]
+struct RecoveryCodeDAO {
+    user_id: u64,
+    code_hash: String,
+}
+
+/// Deletes two-factor authentication and recovery codes of specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(
+        two_factor_recovery_codes::dsl::two_factor_recovery_codes
+            .filter(two_factor_recovery_codes::dsl::user_id.eq(user_id)),
+    )
+    .execute(conn)?;
+    diesel::delete(dsl::two_factors.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for two-factor authentication.
+pub struct TwoFactorRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait TwoFactorRepositoryTrait {
+    fn find_by_user_id(&self, user_id: u64) -> Result<TwoFactor, ServiceError>;
+    fn create(&self, user_id: u64, secret: &str) -> Result<bool, ServiceError>;
+    fn enable(
+        &self,
+        user_id: u64,
+        step: u64,
+        enabled_at: &NaiveDateTime,
+        recovery_code_hashes: &[String],
+    ) -> Result<bool, ServiceError>;
+    fn mark_used(&self, user_id: u64, step: u64) -> Result<bool, ServiceError>;
+    fn use_recovery_code(&self, user_id: u64, code_hash: &str) -> Result<bool, ServiceError>;
+    fn delete(&self, user_id: u64) -> Result<bool, ServiceError>;
+}
+
+impl TwoFactorRepository {
+    /// Creates a new two-factor repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds two-factor authentication of specific user, which may be pending.
+    pub fn find_by_user_id(&self, user_id: u64) -> Result<TwoFactor, ServiceError> {
+        let two_factor = dsl::two_factors
+            .find(user_id)
+            .get_result::<TwoFactor>(&self.conn);
+
+        match two_factor {
+            Ok(two_factor) => Ok(two_factor),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    user_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Creates pending two-factor authentication of specific user with `secret`,
+    /// replacing the previous pending one. It fails if the user has enabled one.
+    pub fn create(&self, user_id: u64, secret: &str) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let pending_two_factor = dsl::two_factors
+                .find(user_id)
+                .filter(dsl::enabled_at.is_null());
+            diesel::delete(pending_two_factor).execute(&self.conn)?;
+
+            let two_factor_to_create = TwoFactorDAO {
+                user_id,
+                secret: secret.to_string(),
+            };
+            diesel::insert_into(dsl::two_factors)
+                .values(two_factor_to_create)
+                .execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Enables pending two-factor authentication of specific user whose code of `step`
+    /// is verified, replacing recovery codes with `recovery_code_hashes`.
+    pub fn enable(
+        &self,
+        user_id: u64,
+        step: u64,
+        enabled_at: &NaiveDateTime,
+        recovery_code_hashes: &[String],
+    ) -> Result<bool, ServiceError> {
+        let result = self.conn.transaction::<bool, Error, _>(|| {
+            let pending_two_factor = dsl::two_factors
+                .find(user_id)
+                .filter(dsl::enabled_at.is_null());
+            let count = diesel::update(pending_two_factor)
+                .set((dsl::enabled_at.eq(enabled_at), dsl::last_used_step.eq(step)))
+                .execute(&self.conn)?;
+            if count == 0 {
+                return Err(Error::NotFound);
+            }
+
+            let recovery_codes = two_factor_recovery_codes::dsl::two_factor_recovery_codes
+                .filter(two_factor_recovery_codes::dsl::user_id.eq(user_id));
+            diesel::delete(recovery_codes).execute(&self.conn)?;
+
+            let recovery_codes_to_create: Vec<RecoveryCodeDAO> = recovery_code_hashes
+                .iter()
+                .map(|code_hash| RecoveryCodeDAO {
+                    user_id,
+                    code_hash: code_hash.clone(),
+                })
+                .collect();
+            diesel::insert_into(two_factor_recovery_codes::dsl::two_factor_recovery_codes)
+                .values(&recovery_codes_to_create)
+                .execute(&self.conn)?;
+            Ok(true)
+        });
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(error) => match error {
+                Error::NotFound => Err(get_service_error(ServiceError::NotFound(
+                    user_id.to_string(),
+                ))),
+                _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+            },
+        }
+    }
+
+    /// Records that a code of `step` has been accepted, and returns false
+    /// if a code of the step or a later one has already been accepted.
+    pub fn mark_used(&self, user_id: u64, step: u64) -> Result<bool, ServiceError> {
+        let target_two_factor = dsl::two_factors.find(user_id).filter(
+            dsl::last_used_step
+                .is_null()
+                .or(dsl::last_used_step.lt(step)),
+        );
+        let count = diesel::update(target_two_factor)
+            .set(dsl::last_used_step.eq(step))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a recovery code of specific user by its hash, and returns whether it existed.
+    pub fn use_recovery_code(&self, user_id: u64, code_hash: &str) -> Result<bool, ServiceError> {
+        let target_recovery_code = two_factor_recovery_codes::dsl::two_factor_recovery_codes
+            .filter(two_factor_recovery_codes::dsl::user_id.eq(user_id))
+            .filter(two_factor_recovery_codes::dsl::code_hash.eq(code_hash));
+        let count = diesel::delete(target_recovery_code).execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Disables two-factor authentication of specific user.
+    pub fn delete(&self, user_id: u64) -> Result<bool, ServiceError> {
+        let count = self
+            .conn
+            .transaction::<usize, Error, _>(|| delete_by_user_id(&self.conn, user_id));
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(
+                user_id.to_string(),
+            ))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for TwoFactorRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
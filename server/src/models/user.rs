@@ -1,12 +1,36 @@
 use chrono::{NaiveDateTime, Utc};
 use diesel::prelude::*;
-use diesel::result::Error;
+use diesel::result::{DatabaseErrorKind, Error};
 use mockall::automock;
 use serde::{Deserialize, Serialize};
 
+use crate::models::attachment;
+use crate::models::calendar_feed;
 use crate::models::connection;
 use crate::models::error::{get_service_error, ServiceError};
-use crate::schema::{users, users::dsl};
+use crate::models::export_job;
+use crate::models::journal;
+use crate::models::login_history;
+use crate::models::login_session;
+use crate::models::oauth_account;
+use crate::models::personal_access_token;
+use crate::models::post_comment;
+use crate::models::post_revision;
+use crate::models::post_share;
+use crate::models::post_tombstone;
+use crate::models::prompt;
+use crate::models::tag;
+use crate::models::template;
+use crate::models::two_factor;
+use crate::models::user_settings;
+use crate::models::webauthn;
+use crate::schema::{post_audits, posts, tags, user_keys, users, users::dsl};
+use crate::utils::datetime_util;
+
+no_arg_sql_function!(
+    last_insert_id,
+    diesel::sql_types::Unsigned<diesel::sql_types::Bigint>
+);
 
 /// User representing `users` table.
 #[derive(Debug, Serialize, Deserialize, Queryable)]
@@ -16,8 +40,47 @@ pub struct User {
     pub email: String,
     pub password: String,
     pub avatar_url: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
     pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
     pub updated_at: Option<NaiveDateTime>,
+    pub key_metadata: Option<String>,
+    /// Number of words the user aims to write in a day, if it is set.
+    pub daily_word_goal: Option<u32>,
+    /// Number of words the user aims to write in a month, if it is set.
+    pub monthly_word_goal: Option<u32>,
+    /// `user` or `admin`
+    pub role: String,
+    /// Time the user was suspended by an admin, if the user is suspended.
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub suspended_at: Option<NaiveDateTime>,
+}
+
+/// Roles of users.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum UserRole {
+    /// Writes own posts only.
+    User,
+    /// Also manages accounts of other users.
+    Admin,
+}
+
+impl UserRole {
+    /// Returns the name of the role stored in `users` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Admin => "admin",
+        }
+    }
+
+    /// Parses the name of the role, which is `user` if it is unknown.
+    pub fn parse(role: &str) -> Self {
+        match role {
+            "admin" => Self::Admin,
+            _ => Self::User,
+        }
+    }
 }
 
 /// User DTO using between routes layer and service layer.
@@ -27,10 +90,117 @@ pub struct UserDTO {
     pub name: String,
     pub email: String,
     pub avatar_url: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
     pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
     pub updated_at: Option<NaiveDateTime>,
 }
 
+/// Profile DTO of the logged-in user using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct ProfileDTO {
+    pub id: u64,
+    pub name: String,
+    pub email: String,
+    pub avatar_url: Option<String>,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    /// Fingerprint of the public key, or `None` if the user has not finished signing up.
+    pub public_key_fingerprint: Option<String>,
+    pub settings: ProfileSettingsDTO,
+}
+
+/// Settings of a user in the profile.
+#[derive(Serialize, Deserialize)]
+pub struct ProfileSettingsDTO {
+    pub daily_word_goal: Option<u32>,
+    pub monthly_word_goal: Option<u32>,
+}
+
+/// Data removed by deleting a user.
+#[derive(Debug)]
+pub struct UserDeletion {
+    pub post_ids: Vec<u64>,
+    pub tag_count: usize,
+    pub user_key_count: usize,
+}
+
+/// User deletion DTO using between routes layer and service layer.
+#[derive(Serialize, Deserialize)]
+pub struct UserDeletionDTO {
+    pub dry_run: bool,
+    pub user_id: u64,
+    pub post_ids: Vec<u64>,
+    pub tag_count: usize,
+    pub user_key_count: usize,
+}
+
+/// Maximum size of serialized key metadata in bytes.
+const MAX_KEY_METADATA_SIZE: usize = 8192;
+
+/// Maximum number of keys in key metadata.
+const MAX_KEY_METADATA_KEYS: usize = 32;
+
+/// Maximum length of each field in key metadata.
+const MAX_KEY_METADATA_FIELD_LENGTH: usize = 128;
+
+/// Metadata of the key material kept by the client.
+///
+/// It lets a new device discover which keys exist before pulling posts.
+/// It never contains keys themselves, so unknown fields are rejected.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyMetadata {
+    pub keys: Vec<KeyMetadataEntry>,
+    /// Checksum of the key material defined by the client.
+    pub checksum: Option<String>,
+}
+
+/// Metadata of a key kept by the client.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct KeyMetadataEntry {
+    pub key_id: String,
+    pub algorithm: String,
+    /// Datetime defined by the client, which is kept in the format the client has sent.
+    pub created_at: NaiveDateTime,
+}
+
+impl KeyMetadata {
+    /// Validates the metadata and returns it serialized.
+    ///
+    /// Ids and algorithms must be non-empty, ids must be unique,
+    /// and the fields and the serialized metadata must be within their size limits.
+    pub fn to_validated_json(&self) -> Result<String, ServiceError> {
+        let is_valid_field = |field: &str| -> bool {
+            !field.trim().is_empty() && field.len() <= MAX_KEY_METADATA_FIELD_LENGTH
+        };
+
+        if self.keys.len() > MAX_KEY_METADATA_KEYS
+            || !self
+                .keys
+                .iter()
+                .all(|key| is_valid_field(&key.key_id) && is_valid_field(&key.algorithm))
+            || !self.checksum.as_deref().map(is_valid_field).unwrap_or(true)
+        {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let mut key_ids: Vec<&str> = self.keys.iter().map(|key| key.key_id.as_str()).collect();
+        key_ids.sort_unstable();
+        key_ids.dedup();
+        if key_ids.len() != self.keys.len() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        match serde_json::to_string(self) {
+            Ok(serialized) if serialized.len() <= MAX_KEY_METADATA_SIZE => Ok(serialized),
+            Ok(_) => Err(get_service_error(ServiceError::InvalidArgument)),
+            Err(_) => Err(get_service_error(ServiceError::InvalidFormat)),
+        }
+    }
+}
+
 /// User DAO using between models layer and RDB.
 #[derive(Insertable, AsChangeset)]
 #[table_name = "users"]
@@ -73,7 +243,15 @@ pub trait UserRepositoryTrait {
         password: &Option<String>,
         avatar_url: &Option<String>,
     ) -> Result<bool, ServiceError>;
-    fn delete(&self, id: u64) -> Result<bool, ServiceError>;
+    fn update_email(&self, id: u64, email: &str) -> Result<bool, ServiceError>;
+    fn delete(&self, id: u64, dry_run: bool) -> Result<UserDeletion, ServiceError>;
+    fn update_key_metadata(&self, id: u64, key_metadata: &str) -> Result<bool, ServiceError>;
+    fn update_word_goals(
+        &self,
+        id: u64,
+        daily_word_goal: &Option<u32>,
+        monthly_word_goal: &Option<u32>,
+    ) -> Result<bool, ServiceError>;
 }
 
 impl UserRepository {
@@ -142,7 +320,7 @@ impl UserRepository {
         }
     }
 
-    /// Creates a new user.
+    /// Creates a new user with the default journal.
     pub fn create(
         &self,
         name: &str,
@@ -159,9 +337,14 @@ impl UserRepository {
             updated_at: None,
         };
 
-        let count = diesel::insert_into(dsl::users)
-            .values(user_to_create)
-            .execute(&self.conn);
+        let count = self.conn.transaction::<usize, Error, _>(|| {
+            let count = diesel::insert_into(dsl::users)
+                .values(user_to_create)
+                .execute(&self.conn)?;
+            let user_id = diesel::select(last_insert_id).get_result::<u64>(&self.conn)?;
+            journal::create_default(&self.conn, user_id)?;
+            Ok(count)
+        });
 
         if let Ok(count) = count {
             if count > 0 {
@@ -211,26 +394,149 @@ impl UserRepository {
         }
     }
 
-    /// Deletes a user.
-    pub fn delete(&self, id: u64) -> Result<bool, ServiceError> {
-        let target_user = dsl::users.find(id);
-        // Consider also logical deletion
-        let count = diesel::delete(target_user).execute(&self.conn);
+    /// Changes the email of a user, which fails with `DuplicatedKey` if another user has it.
+    pub fn update_email(&self, id: u64, email: &str) -> Result<bool, ServiceError> {
+        let count = diesel::update(dsl::users.find(id))
+            .set((
+                dsl::email.eq(email),
+                dsl::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(&self.conn);
 
         match count {
-            Ok(count) => {
-                if count > 0 {
-                    Ok(true)
-                } else {
-                    Err(get_service_error(ServiceError::QueryExecutionFailure))
-                }
+            Ok(count) if count > 0 => Ok(true),
+            Ok(_) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Err(Error::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => {
+                Err(get_service_error(ServiceError::DuplicatedKey))
             }
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a user with the posts, the comments, the settings, the journals, the templates,
+    /// the calendar feed, the export jobs, the prompt subscription, the two-factor authentication,
+    /// the passkeys, the linked OAuth accounts, and the key of the user.
+    ///
+    /// If `dry_run` is true, the deletion runs in a transaction that is always rolled back,
+    /// so that it reports the data to be removed without removing anything.
+    pub fn delete(&self, id: u64, dry_run: bool) -> Result<UserDeletion, ServiceError> {
+        let mut rolled_back_deletion = None;
+        let deletion = self.conn.transaction::<UserDeletion, Error, _>(|| {
+            let post_ids = posts::dsl::posts
+                .select(posts::dsl::id)
+                .filter(posts::dsl::user_id.eq(id))
+                .load::<u64>(&self.conn)?;
+            let target_post_audits =
+                post_audits::dsl::post_audits.filter(post_audits::dsl::user_id.eq(id));
+            diesel::delete(target_post_audits).execute(&self.conn)?;
+
+            tag::delete_post_tags(&self.conn, &post_ids)?;
+            post_revision::delete_by_post_ids(&self.conn, &post_ids)?;
+            attachment::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_share::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_comment::delete_by_post_ids(&self.conn, &post_ids)?;
+            post_comment::delete_by_user_id(&self.conn, id)?;
+            let target_tags = tags::dsl::tags.filter(tags::dsl::user_id.eq(id));
+            let tag_count = diesel::delete(target_tags).execute(&self.conn)?;
+
+            let target_posts = posts::dsl::posts.filter(posts::dsl::user_id.eq(id));
+            diesel::delete(target_posts).execute(&self.conn)?;
+            post_tombstone::delete_by_user_id(&self.conn, id)?;
+            user_settings::delete_by_user_id(&self.conn, id)?;
+            journal::delete_by_user_id(&self.conn, id)?;
+            template::delete_by_user_id(&self.conn, id)?;
+            calendar_feed::delete_by_user_id(&self.conn, id)?;
+            export_job::delete_by_user_id(&self.conn, id)?;
+            prompt::delete_subscription_by_user_id(&self.conn, id)?;
+            two_factor::delete_by_user_id(&self.conn, id)?;
+            webauthn::delete_by_user_id(&self.conn, id)?;
+            oauth_account::delete_by_user_id(&self.conn, id)?;
+            login_history::delete_by_user_id(&self.conn, id)?;
+            login_session::delete_by_user_id(&self.conn, id)?;
+            personal_access_token::delete_by_user_id(&self.conn, id)?;
+
+            let target_user_keys = user_keys::dsl::user_keys.filter(user_keys::dsl::user_id.eq(id));
+            let user_key_count = diesel::delete(target_user_keys).execute(&self.conn)?;
+
+            let target_user = dsl::users.find(id);
+            if diesel::delete(target_user).execute(&self.conn)? == 0 {
+                return Err(Error::NotFound);
+            }
+
+            let deletion = UserDeletion {
+                post_ids,
+                tag_count,
+                user_key_count,
+            };
+
+            if dry_run {
+                rolled_back_deletion = Some(deletion);
+                Err(Error::RollbackTransaction)
+            } else {
+                Ok(deletion)
+            }
+        });
+
+        match deletion {
+            Ok(deletion) => Ok(deletion),
             Err(error) => match error {
+                Error::RollbackTransaction if rolled_back_deletion.is_some() => {
+                    Ok(rolled_back_deletion.unwrap())
+                }
                 Error::NotFound => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
                 _ => Err(get_service_error(ServiceError::QueryExecutionFailure)),
             },
         }
     }
+
+    /// Replaces key metadata of a user.
+    pub fn update_key_metadata(&self, id: u64, key_metadata: &str) -> Result<bool, ServiceError> {
+        let count = diesel::update(dsl::users.find(id))
+            .set((
+                dsl::key_metadata.eq(key_metadata),
+                dsl::updated_at.eq(Utc::now().naive_utc()),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) if count > 0 => Ok(true),
+            Ok(_) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Sets word goals of a user. Goals not given are kept, and a goal of 0 removes the goal.
+    pub fn update_word_goals(
+        &self,
+        id: u64,
+        daily_word_goal: &Option<u32>,
+        monthly_word_goal: &Option<u32>,
+    ) -> Result<bool, ServiceError> {
+        let to_goal = |goal: u32| Some(goal).filter(|goal| *goal > 0);
+
+        let count = self.conn.transaction::<usize, Error, _>(|| {
+            let count = diesel::update(dsl::users.find(id))
+                .set(dsl::updated_at.eq(Utc::now().naive_utc()))
+                .execute(&self.conn)?;
+            if let Some(daily_word_goal) = daily_word_goal {
+                diesel::update(dsl::users.find(id))
+                    .set(dsl::daily_word_goal.eq(to_goal(*daily_word_goal)))
+                    .execute(&self.conn)?;
+            }
+            if let Some(monthly_word_goal) = monthly_word_goal {
+                diesel::update(dsl::users.find(id))
+                    .set(dsl::monthly_word_goal.eq(to_goal(*monthly_word_goal)))
+                    .execute(&self.conn)?;
+            }
+            Ok(count)
+        });
+
+        match count {
+            Ok(count) if count > 0 => Ok(true),
+            Ok(_) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
 }
 
 impl Default for UserRepository {
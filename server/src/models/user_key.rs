@@ -3,10 +3,12 @@ use diesel::prelude::*;
 use diesel::result::Error;
 use mockall::automock;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 
 use crate::models::connection;
 use crate::models::error::{get_service_error, ServiceError};
 use crate::schema::{user_keys, user_keys::dsl};
+use crate::utils::datetime_util;
 
 /// User key representing `user_keys` table.
 /// One user must have only one public key.
@@ -16,10 +18,20 @@ pub struct UserKey {
     pub id: u64,
     pub user_id: u64,
     pub public_key: String,
+    #[serde(with = "datetime_util::rfc3339")]
     pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
     pub updated_at: Option<NaiveDateTime>,
 }
 
+impl UserKey {
+    /// Returns the SHA-256 fingerprint of the public key in lowercase hex,
+    /// which lets the user tell the key without comparing it whole.
+    pub fn fingerprint(&self) -> String {
+        format!("{:x}", Sha256::digest(self.public_key.as_bytes()))
+    }
+}
+
 /// User DAO using between models layer and RDB.
 #[derive(Insertable, AsChangeset)]
 #[table_name = "user_keys"]
@@ -0,0 +1,273 @@
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc, Weekday};
+use chrono_tz::Tz;
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use serde::{Deserialize, Serialize};
+use std::ops::RangeInclusive;
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{user_settings, user_settings::dsl};
+use crate::utils::datetime_util;
+
+/// Time zone of users who have never set it.
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// Locale of users who have never set it.
+pub const DEFAULT_LOCALE: &str = "en";
+
+/// Maximum length of a locale, which is the capacity of the column.
+const MAX_LOCALE_LENGTH: usize = 35;
+
+/// Range of font sizes of the editor in pixels.
+const FONT_SIZE_RANGE: RangeInclusive<u8> = 10..=32;
+
+/// Font families of the editor.
+const FONT_FAMILIES: [&str; 3] = ["serif", "sans_serif", "monospace"];
+
+/// Settings of a user representing `user_settings` table.
+///
+/// A row is created when the user changes the settings at first,
+/// and the defaults are used until then.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct UserSettings {
+    pub user_id: u64,
+    pub timezone: String,
+    pub locale: String,
+    pub week_start_day: String,
+    /// Serialized `EditorPreferences`.
+    pub editor_preferences: Option<String>,
+    /// Whether the user allows anonymous usage counting.
+    pub telemetry_opt_in: bool,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+/// User settings DTO using between routes layer and service layer.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct UserSettingsDTO {
+    pub timezone: String,
+    pub locale: String,
+    pub week_start_day: String,
+    pub editor_preferences: EditorPreferences,
+    /// Id of the journal to which posts go unless their journal is given.
+    pub default_journal_id: Option<u64>,
+    pub telemetry_opt_in: bool,
+}
+
+/// User settings DAO using between models layer and RDB.
+#[derive(Insertable, AsChangeset)]
+#[table_name = "user_settings"]
+#[primary_key(user_id)]
+struct UserSettingsDAO {
+    user_id: u64,
+    timezone: String,
+    locale: String,
+    week_start_day: String,
+    editor_preferences: Option<String>,
+    telemetry_opt_in: bool,
+    updated_at: Option<NaiveDateTime>,
+}
+
+/// Days on which weeks start in calendars of the client.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum WeekStartDay {
+    Monday,
+    Sunday,
+    Saturday,
+}
+
+impl WeekStartDay {
+    /// Returns the name of the day stored in `user_settings` table.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Monday => "monday",
+            Self::Sunday => "sunday",
+            Self::Saturday => "saturday",
+        }
+    }
+
+    /// Parses the name of the day used in `week_start_day` argument.
+    pub fn parse(day: &str) -> Result<Self, ServiceError> {
+        match day {
+            "monday" => Ok(Self::Monday),
+            "sunday" => Ok(Self::Sunday),
+            "saturday" => Ok(Self::Saturday),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
+
+    /// Returns the weekday on which weeks start.
+    pub fn weekday(&self) -> Weekday {
+        match self {
+            Self::Monday => Weekday::Mon,
+            Self::Sunday => Weekday::Sun,
+            Self::Saturday => Weekday::Sat,
+        }
+    }
+}
+
+/// Preferences of the editor of the client. Preferences not set are up to the client.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct EditorPreferences {
+    /// Font size in pixels.
+    pub font_size: Option<u8>,
+    /// `serif`, `sans_serif`, or `monospace`.
+    pub font_family: Option<String>,
+    pub spell_check: Option<bool>,
+    pub show_word_count: Option<bool>,
+}
+
+impl EditorPreferences {
+    /// Validates the preferences and returns them serialized.
+    pub fn to_validated_json(&self) -> Result<String, ServiceError> {
+        let is_valid_font_size = self
+            .font_size
+            .map_or(true, |font_size| FONT_SIZE_RANGE.contains(&font_size));
+        let is_valid_font_family = self.font_family.as_ref().map_or(true, |font_family| {
+            FONT_FAMILIES.contains(&font_family.as_str())
+        });
+        if !is_valid_font_size || !is_valid_font_family {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        serde_json::to_string(self).map_err(|_| get_service_error(ServiceError::InvalidFormat))
+    }
+}
+
+/// Parses an IANA time zone such as `Asia/Seoul`.
+pub fn parse_timezone(timezone: &str) -> Result<Tz, ServiceError> {
+    timezone
+        .parse::<Tz>()
+        .map_err(|_| get_service_error(ServiceError::InvalidArgument))
+}
+
+/// Returns whether a locale looks like a BCP 47 language tag such as `ko-KR`.
+pub fn is_valid_locale(locale: &str) -> bool {
+    !locale.is_empty()
+        && locale.len() <= MAX_LOCALE_LENGTH
+        && locale
+            .split('-')
+            .all(|subtag| !subtag.is_empty() && subtag.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// Returns the time zone of the settings, or UTC without settings.
+pub fn get_timezone(settings: &Option<UserSettings>) -> Tz {
+    settings
+        .as_ref()
+        .and_then(|settings| settings.timezone.parse::<Tz>().ok())
+        .unwrap_or(Tz::UTC)
+}
+
+/// Returns the day on which weeks start in the settings, or Monday without settings.
+pub fn get_week_start_day(settings: &Option<UserSettings>) -> WeekStartDay {
+    settings
+        .as_ref()
+        .and_then(|settings| WeekStartDay::parse(&settings.week_start_day).ok())
+        .unwrap_or(WeekStartDay::Monday)
+}
+
+/// Returns the date of `now` in the time zone of the settings, or in UTC without settings.
+pub fn get_local_date(settings: &Option<UserSettings>, now: &DateTime<Utc>) -> NaiveDate {
+    now.with_timezone(&get_timezone(settings))
+        .date()
+        .naive_local()
+}
+
+/// Deletes settings of specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::user_settings.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for user settings.
+pub struct UserSettingsRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait UserSettingsRepositoryTrait {
+    fn find_by_user_id(&self, user_id: u64) -> Result<Option<UserSettings>, ServiceError>;
+    fn save(
+        &self,
+        user_id: u64,
+        timezone: &str,
+        locale: &str,
+        week_start_day: &str,
+        editor_preferences: &Option<String>,
+        telemetry_opt_in: bool,
+    ) -> Result<bool, ServiceError>;
+}
+
+impl UserSettingsRepository {
+    /// Creates a new user settings repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds settings of specific user, or `None` if the user has never changed them.
+    pub fn find_by_user_id(&self, user_id: u64) -> Result<Option<UserSettings>, ServiceError> {
+        let settings = dsl::user_settings
+            .find(user_id)
+            .get_result::<UserSettings>(&self.conn)
+            .optional();
+
+        match settings {
+            Ok(settings) => Ok(settings),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates settings of specific user, or updates them if they exist.
+    pub fn save(
+        &self,
+        user_id: u64,
+        timezone: &str,
+        locale: &str,
+        week_start_day: &str,
+        editor_preferences: &Option<String>,
+        telemetry_opt_in: bool,
+    ) -> Result<bool, ServiceError> {
+        let settings_to_save = UserSettingsDAO {
+            user_id,
+            timezone: timezone.to_string(),
+            locale: locale.to_string(),
+            week_start_day: week_start_day.to_string(),
+            editor_preferences: editor_preferences.clone(),
+            telemetry_opt_in,
+            updated_at: Some(Utc::now().naive_utc()),
+        };
+
+        let count = self.conn.transaction::<usize, Error, _>(|| {
+            let target_settings = dsl::user_settings.find(user_id);
+            let has_settings =
+                diesel::select(exists(target_settings)).get_result::<bool>(&self.conn)?;
+            if has_settings {
+                diesel::update(target_settings)
+                    .set(&settings_to_save)
+                    .execute(&self.conn)
+            } else {
+                diesel::insert_into(dsl::user_settings)
+                    .values(&settings_to_save)
+                    .execute(&self.conn)
+            }
+        });
+
+        match count {
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for UserSettingsRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
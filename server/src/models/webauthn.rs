@@ -0,0 +1,254 @@
+use chrono::NaiveDateTime;
+use diesel::dsl::exists;
+use diesel::prelude::*;
+use diesel::result::Error;
+use mockall::automock;
+use redis::{Commands, RedisError};
+use serde::{Deserialize, Serialize};
+
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::schema::{webauthn_credentials, webauthn_credentials::dsl};
+use crate::utils::datetime_util;
+
+/// Seconds a registration or an authentication ceremony is valid, in which the user
+/// touches the authenticator.
+pub const CEREMONY_TTL_SECONDS: usize = 300; // 5 min
+
+/// WebAuthn credential representing `webauthn_credentials` table.
+///
+/// A user may register several passkeys, each of which signs in without the password.
+#[derive(Debug, Serialize, Deserialize, Queryable)]
+pub struct WebauthnCredential {
+    pub id: u64,
+    pub user_id: u64,
+    /// SHA-256 hash of the credential id in hex.
+    pub credential_id_hash: String,
+    /// The credential serialized in JSON, having the public key and the signature counter.
+    pub credential: String,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+/// WebAuthn credential DTO using between routes layer and service layer.
+///
+/// It has no public key, which the client does not need to manage passkeys.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub struct WebauthnCredentialDTO {
+    pub id: u64,
+    #[serde(with = "datetime_util::rfc3339")]
+    pub created_at: NaiveDateTime,
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub last_used_at: Option<NaiveDateTime>,
+}
+
+/// WebAuthn credential DAO using between models layer and RDB.
+#[derive(Insertable)]
+#[table_name = "webauthn_credentials"]
+struct WebauthnCredentialDAO {
+    user_id: u64,
+    credential_id_hash: String,
+    credential: String,
+}
+
+/// Deletes passkeys of specific user.
+pub fn delete_by_user_id(conn: &MysqlConnection, user_id: u64) -> Result<usize, Error> {
+    diesel::delete(dsl::webauthn_credentials.filter(dsl::user_id.eq(user_id))).execute(conn)
+}
+
+/// A core data repository for WebAuthn credential.
+pub struct WebauthnCredentialRepository {
+    conn: MysqlConnection,
+}
+
+#[automock]
+pub trait WebauthnCredentialRepositoryTrait {
+    fn find_all_by_user_id(&self, user_id: u64) -> Result<Vec<WebauthnCredential>, ServiceError>;
+    fn exists_by_credential_id_hash(&self, credential_id_hash: &str) -> Result<bool, ServiceError>;
+    fn create(
+        &self,
+        user_id: u64,
+        credential_id_hash: &str,
+        credential: &str,
+    ) -> Result<bool, ServiceError>;
+    fn update_credential(
+        &self,
+        id: u64,
+        credential: &str,
+        last_used_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError>;
+    fn delete(&self, id: u64, user_id: u64) -> Result<bool, ServiceError>;
+}
+
+impl WebauthnCredentialRepository {
+    /// Creates a new WebAuthn credential repository.
+    pub fn new() -> Self {
+        Self {
+            conn: connection::connect_rdb(),
+        }
+    }
+
+    /// Finds all passkeys of specific user in the order of registration.
+    pub fn find_all_by_user_id(
+        &self,
+        user_id: u64,
+    ) -> Result<Vec<WebauthnCredential>, ServiceError> {
+        let credentials = dsl::webauthn_credentials
+            .filter(dsl::user_id.eq(user_id))
+            .order(dsl::id.asc())
+            .load::<WebauthnCredential>(&self.conn);
+
+        match credentials {
+            Ok(credentials) => Ok(credentials),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Returns whether a passkey of any user has the credential id.
+    pub fn exists_by_credential_id_hash(
+        &self,
+        credential_id_hash: &str,
+    ) -> Result<bool, ServiceError> {
+        let result = diesel::select(exists(
+            dsl::webauthn_credentials.filter(dsl::credential_id_hash.eq(credential_id_hash)),
+        ))
+        .get_result::<bool>(&self.conn);
+
+        match result {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Creates a new passkey of specific user.
+    pub fn create(
+        &self,
+        user_id: u64,
+        credential_id_hash: &str,
+        credential: &str,
+    ) -> Result<bool, ServiceError> {
+        let credential_to_create = WebauthnCredentialDAO {
+            user_id,
+            credential_id_hash: credential_id_hash.to_string(),
+            credential: credential.to_string(),
+        };
+
+        let count = diesel::insert_into(dsl::webauthn_credentials)
+            .values(credential_to_create)
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Updates a passkey whose signature counter has changed by signing in.
+    pub fn update_credential(
+        &self,
+        id: u64,
+        credential: &str,
+        last_used_at: &NaiveDateTime,
+    ) -> Result<bool, ServiceError> {
+        let target_credential = dsl::webauthn_credentials.find(id);
+        let count = diesel::update(target_credential)
+            .set((
+                dsl::credential.eq(credential),
+                dsl::last_used_at.eq(last_used_at),
+            ))
+            .execute(&self.conn);
+
+        match count {
+            Ok(count) => Ok(count > 0),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a passkey of specific user.
+    pub fn delete(&self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let target_credential = dsl::webauthn_credentials
+            .find(id)
+            .filter(dsl::user_id.eq(user_id));
+        let count = diesel::delete(target_credential).execute(&self.conn);
+
+        match count {
+            Ok(0) => Err(get_service_error(ServiceError::NotFound(id.to_string()))),
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for WebauthnCredentialRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Returns key of the state of a registration ceremony of the user in redis.
+pub fn get_registration_state_key(user_id: u64) -> String {
+    format!("webauthn_registration:{}", user_id)
+}
+
+/// Returns key of the state of an authentication ceremony in redis.
+pub fn get_login_state_key(token: &str) -> String {
+    format!("webauthn_login:{}", token)
+}
+
+/// A core data repository for states of WebAuthn ceremonies, which are kept
+/// between the challenge and the response of the authenticator.
+pub struct WebauthnStateRepository {
+    client: redis::Connection,
+}
+
+#[automock]
+pub trait WebauthnStateRepositoryTrait {
+    fn find(&mut self, key: &str) -> Result<String, ServiceError>;
+    fn delete(&mut self, key: &str) -> Result<bool, ServiceError>;
+    fn save(&mut self, key: &str, serialized_state: &str) -> Result<bool, ServiceError>;
+}
+
+impl WebauthnStateRepository {
+    /// Creates a new WebAuthn state repository.
+    pub fn new() -> Self {
+        Self {
+            client: connection::connect_redis(),
+        }
+    }
+
+    /// Finds a state by key, and fails with `Unauthorized` if the ceremony has expired.
+    pub fn find(&mut self, key: &str) -> Result<String, ServiceError> {
+        match self.client.get::<&str, Option<String>>(key) {
+            Ok(Some(state)) => Ok(state),
+            Ok(None) => Err(get_service_error(ServiceError::Unauthorized)),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Deletes a state by key.
+    pub fn delete(&mut self, key: &str) -> Result<bool, ServiceError> {
+        match self.client.del::<&str, _>(key) {
+            Ok(result) => Ok(result),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+
+    /// Saves a state, which expires `CEREMONY_TTL_SECONDS` later.
+    pub fn save(&mut self, key: &str, serialized_state: &str) -> Result<bool, ServiceError> {
+        let result: Result<bool, RedisError> =
+            self.client
+                .set_ex::<&str, &str, _>(key, serialized_state, CEREMONY_TTL_SECONDS);
+        match result {
+            Ok(_) => Ok(true),
+            Err(_) => Err(get_service_error(ServiceError::QueryExecutionFailure)),
+        }
+    }
+}
+
+impl Default for WebauthnStateRepository {
+    fn default() -> Self {
+        Self::new()
+    }
+}
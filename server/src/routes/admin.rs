@@ -0,0 +1,249 @@
+use actix_web::{delete, get, post, web, HttpRequest, Responder};
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::models::admin::AuditFilter;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::services::admin::AdminService;
+use crate::services::invite::InviteService;
+use crate::services::telemetry::TelemetryService;
+use crate::utils::{check_util, http_util};
+
+/// Arguments for `GET /admin/users` API.
+#[derive(Serialize, Deserialize)]
+pub struct UserListArgs {
+    /// Keyword contained in names or emails of users.
+    pub q: Option<String>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Arguments for `GET /admin/stats` API.
+#[derive(Serialize, Deserialize)]
+pub struct StatsArgs {
+    /// Number of days counted, up to today.
+    pub days: Option<u32>,
+}
+
+/// Arguments for `GET /admin/telemetry` API.
+#[derive(Serialize, Deserialize)]
+pub struct TelemetryArgs {
+    /// Number of days counted, up to today.
+    pub days: Option<u32>,
+}
+
+/// Content type of the audit log exported as CSV.
+const CSV_CONTENT_TYPE: &str = "text/csv; charset=utf-8";
+
+/// Arguments for `GET /admin/audit` API.
+#[derive(Serialize, Deserialize)]
+pub struct AuditArgs {
+    pub user_id: Option<u64>,
+    pub event: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub ip: Option<String>,
+    pub cursor: Option<String>,
+    pub per_page: Option<u32>,
+    /// `json`, or `csv` to stream the whole log as CSV.
+    pub format: Option<String>,
+}
+
+/// Arguments for `POST /admin/impersonate/{user_id}` API.
+#[derive(Serialize, Deserialize)]
+pub struct ImpersonateArgs {
+    /// Whether the admin may write posts as the user. It is false by default.
+    pub allow_writes: Option<bool>,
+}
+
+/// Arguments for `GET /admin/invites` API.
+#[derive(Serialize, Deserialize)]
+pub struct InviteListArgs {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Responds users found by a keyword
+///
+/// Every admin API checks the admin forwarded in `X-Admin-Id` header is still an admin.
+#[get("/admin/users")]
+pub async fn get_users(req: HttpRequest, args: web::Query<UserListArgs>) -> impl Responder {
+    let UserListArgs { q, page, per_page } = args.into_inner();
+    let mut admin_service = AdminService::new();
+    let users = admin_service
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|_| admin_service.get_users(&q, &page, &per_page));
+    http_util::respond_page(users)
+}
+
+/// Suspends a user
+#[post("/admin/users/{id}/suspend")]
+pub async fn suspend_user(req: HttpRequest, id: web::Path<u64>) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let mut admin_service = AdminService::new();
+    let result = admin_service
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|admin_id| admin_service.suspend(admin_id, id.into_inner(), &audit_context));
+    http_util::respond(result)
+}
+
+/// Lifts the suspension of a user
+#[delete("/admin/users/{id}/suspend")]
+pub async fn unsuspend_user(req: HttpRequest, id: web::Path<u64>) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let mut admin_service = AdminService::new();
+    let result = admin_service
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|admin_id| admin_service.unsuspend(admin_id, id.into_inner(), &audit_context));
+    http_util::respond(result)
+}
+
+/// Deletes a user
+#[delete("/admin/users/{id}")]
+pub async fn delete_user(req: HttpRequest, id: web::Path<u64>) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let mut admin_service = AdminService::new();
+    let result = admin_service
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|admin_id| admin_service.delete(admin_id, id.into_inner(), &audit_context));
+    http_util::respond(result)
+}
+
+/// Issues a login session of a user to the admin impersonating the user
+///
+/// The session id forwarded in `X-Session-Id` header is the new one for the impersonation.
+#[post("/admin/impersonate/{user_id}")]
+pub async fn impersonate(
+    req: HttpRequest,
+    user_id: web::Path<u64>,
+    args: web::Json<ImpersonateArgs>,
+) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let allow_writes = args.allow_writes.unwrap_or(false);
+    let mut admin_service = AdminService::new();
+    let result = admin_service
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|admin_id| {
+            admin_service.impersonate(admin_id, user_id.into_inner(), allow_writes, &audit_context)
+        });
+    http_util::respond(result)
+}
+
+/// Ends the impersonation of the session forwarded in `X-Session-Id` header
+///
+/// It is requested with the impersonated session, so that no admin is forwarded.
+#[post("/admin/impersonate/stop")]
+pub async fn stop_impersonation(req: HttpRequest) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result = AdminService::new().stop_impersonation(&audit_context);
+    http_util::respond(result)
+}
+
+/// Responds statistics of signups and posts
+#[get("/admin/stats")]
+pub async fn get_stats(req: HttpRequest, args: web::Query<StatsArgs>) -> impl Responder {
+    let mut admin_service = AdminService::new();
+    let stats = admin_service
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|_| admin_service.get_stats(&args.into_inner().days));
+    http_util::respond(stats)
+}
+
+/// Responds a page of the audit log, or streams the whole log as CSV if `format` is `csv`
+#[get("/admin/audit")]
+pub async fn get_audit(req: HttpRequest, args: web::Query<AuditArgs>) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let AuditArgs {
+        user_id,
+        event,
+        from,
+        to,
+        ip,
+        cursor,
+        per_page,
+        format,
+    } = args.into_inner();
+    let filter = AuditFilter {
+        user_id,
+        event,
+        from,
+        to,
+        ip,
+    };
+
+    let mut admin_service = AdminService::new();
+    let admin_id = match admin_service.authorize(http_util::get_admin_id(&req)) {
+        Ok(admin_id) => admin_id,
+        Err(error) => return http_util::err(error),
+    };
+    match format.as_deref() {
+        None | Some("json") => http_util::respond(admin_service.get_audit(
+            admin_id,
+            &filter,
+            &cursor,
+            &per_page,
+            &audit_context,
+        )),
+        Some("csv") => match admin_service.export_audit(admin_id, filter, &audit_context) {
+            Ok(csv) => http_util::respond_stream(csv, CSV_CONTENT_TYPE, "darim-audit.csv"),
+            Err(error) => http_util::err(error),
+        },
+        Some(_) => http_util::err(get_service_error(ServiceError::InvalidArgument)),
+    }
+}
+
+/// Probes external dependencies like `darim-server check`, and responds the results
+///
+/// It responds 200 OK even if a probe fails, with `passed` of the failed probe false.
+#[post("/admin/selftest")]
+pub async fn selftest(req: HttpRequest) -> impl Responder {
+    let results = AdminService::new()
+        .authorize(http_util::get_admin_id(&req))
+        .map(|_| check_util::run_selftest());
+    http_util::respond(results)
+}
+
+/// Responds counts of usage events on each day
+#[get("/admin/telemetry")]
+pub async fn get_telemetry(req: HttpRequest, args: web::Query<TelemetryArgs>) -> impl Responder {
+    let counts = AdminService::new()
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|_| TelemetryService::new().get_counts(&args.into_inner().days));
+    http_util::respond(counts)
+}
+
+/// Responds invites with who redeemed them
+#[get("/admin/invites")]
+pub async fn get_invites(req: HttpRequest, args: web::Query<InviteListArgs>) -> impl Responder {
+    let InviteListArgs { page, per_page } = args.into_inner();
+    let invites = AdminService::new()
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|_| InviteService::new().get_invites(&page, &per_page));
+    http_util::respond_page(invites)
+}
+
+/// Mints an invite code
+#[post("/admin/invites")]
+pub async fn create_invite(req: HttpRequest) -> impl Responder {
+    let invite = AdminService::new()
+        .authorize(http_util::get_admin_id(&req))
+        .and_then(|admin_id| InviteService::new().create(admin_id));
+    http_util::respond(invite)
+}
+
+/// Initializes the admin routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_users);
+    cfg.service(suspend_user);
+    cfg.service(unsuspend_user);
+    cfg.service(delete_user);
+    // `stop` is registered first, since `{user_id}` matches it as well.
+    cfg.service(stop_impersonation);
+    cfg.service(impersonate);
+    cfg.service(get_stats);
+    cfg.service(get_telemetry);
+    cfg.service(selftest);
+    cfg.service(get_audit);
+    cfg.service(get_invites);
+    cfg.service(create_invite);
+}
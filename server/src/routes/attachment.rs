@@ -0,0 +1,63 @@
+use actix_web::{delete, get, web, HttpRequest, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::services::attachment::{AttachmentService, MAX_ATTACHMENT_SIZE};
+use crate::utils::http_util;
+
+/// Arguments for `POST /posts/:id/attachments` API.
+#[derive(Serialize, Deserialize)]
+pub struct UploadArgs {
+    pub user_id: u64,
+    pub filename: String,
+}
+
+/// Lists attachments of a post
+#[get("/posts/{user_id}/{post_id}/attachments")]
+pub async fn get_attachments(
+    web::Path((user_id, post_id)): web::Path<(u64, u64)>,
+) -> impl Responder {
+    let attachments = AttachmentService::new().get_list(user_id, post_id);
+    http_util::respond(attachments)
+}
+
+/// Attaches a photo in the request body to a post
+pub async fn upload_attachment(
+    post_id: web::Path<u64>,
+    args: web::Query<UploadArgs>,
+    file: web::Bytes,
+) -> impl Responder {
+    let UploadArgs { user_id, filename } = args.into_inner();
+    let result = AttachmentService::new().upload(user_id, post_id.into_inner(), &filename, &file);
+    http_util::respond(result)
+}
+
+/// Downloads the file of an attachment
+#[get("/attachments/{user_id}/{id}")]
+pub async fn download_attachment(
+    req: HttpRequest,
+    web::Path((user_id, id)): web::Path<(u64, u64)>,
+) -> impl Responder {
+    let result =
+        AttachmentService::new().download(user_id, id, &http_util::get_cached_hashes(&req));
+    http_util::respond_file(result)
+}
+
+/// Deletes an attachment
+#[delete("/attachments/{user_id}/{id}")]
+pub async fn delete_attachment(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let result = AttachmentService::new().delete(id, user_id);
+    http_util::respond(result)
+}
+
+/// Initializes the attachment routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_attachments);
+    // A photo exceeds the default payload limit, so the route is configured with its own.
+    cfg.service(
+        web::resource("/posts/{id}/attachments")
+            .app_data(web::PayloadConfig::new(MAX_ATTACHMENT_SIZE))
+            .route(web::post().to(upload_attachment)),
+    );
+    cfg.service(download_attachment);
+    cfg.service(delete_attachment);
+}
@@ -1,8 +1,14 @@
-use actix_web::{post, web, Responder};
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
 use serde::{Deserialize, Serialize};
+use webauthn_rs::proto::{PublicKeyCredential, RegisterPublicKeyCredential};
 
-use crate::models::auth::*;
+use crate::models::error::ServiceError;
 use crate::services::auth::AuthService;
+use crate::services::login_session::LoginSessionService;
+use crate::services::login_throttle::{LoginThrottleService, ThrottleScope};
+use crate::services::oauth::OAuthService;
+use crate::services::two_factor::TwoFactorService;
+use crate::services::webauthn::WebauthnService;
 use crate::utils::http_util;
 
 /// Arguments for `GET /auth` API.
@@ -19,6 +25,7 @@ pub struct SetSignUpTokenArgs {
     pub email: String,
     pub password: String,
     pub avatar_url: Option<String>,
+    pub invite_code: Option<String>,
 }
 
 /// Arguments for `POST /auth/token/password` API.
@@ -27,6 +34,119 @@ pub struct SetPasswordTokenArgs {
     pub email: String,
 }
 
+/// Arguments for `POST /auth/magic-link` API.
+#[derive(Serialize, Deserialize)]
+pub struct MagicLinkArgs {
+    pub email: String,
+}
+
+/// Arguments for `POST /auth/magic-link/login` API.
+#[derive(Serialize, Deserialize)]
+pub struct MagicLinkLoginArgs {
+    pub token: String,
+}
+
+/// Arguments for `POST /auth/login/2fa` API.
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorLoginArgs {
+    pub token: String,
+    pub code: String,
+}
+
+/// Arguments for `POST /auth/2fa/setup` API.
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorSetupArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /auth/2fa/verify` and `POST /auth/2fa/disable` API.
+#[derive(Serialize, Deserialize)]
+pub struct TwoFactorCodeArgs {
+    pub user_id: u64,
+    pub code: String,
+}
+
+/// Arguments for `GET /auth/oauth/{provider}/url` API.
+#[derive(Serialize, Deserialize)]
+pub struct OAuthUrlArgs {
+    pub state: String,
+}
+
+/// Arguments for `POST /auth/oauth/{provider}/login` API.
+#[derive(Serialize, Deserialize)]
+pub struct OAuthLoginArgs {
+    pub code: String,
+}
+
+/// Arguments for `POST /auth/sessions` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateLoginSessionArgs {
+    pub user_id: u64,
+    /// Label the client has given to the session.
+    pub label: Option<String>,
+}
+
+/// Arguments for `POST /auth/sessions/verify` API.
+#[derive(Serialize, Deserialize)]
+pub struct VerifyLoginSessionArgs {
+    pub session_id: String,
+    /// Method of the request made with the session, audited if the session is impersonated.
+    pub method: Option<String>,
+    /// Path of the request made with the session, audited if the session is impersonated.
+    pub path: Option<String>,
+}
+
+/// Arguments for `POST /auth/sessions/logout` API.
+#[derive(Serialize, Deserialize)]
+pub struct LoginSessionArgs {
+    pub user_id: u64,
+    pub session_id: String,
+}
+
+/// Arguments for `POST /auth/tokens` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateTokenArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /auth/tokens/refresh` and `POST /auth/tokens/revoke` API.
+#[derive(Serialize, Deserialize)]
+pub struct RefreshTokenArgs {
+    pub refresh_token: String,
+}
+
+/// Arguments for `POST /auth/personal-access-tokens/verify` API.
+#[derive(Serialize, Deserialize)]
+pub struct PersonalAccessTokenArgs {
+    pub token: String,
+}
+
+/// Arguments for `POST /auth/webauthn/register/challenge` API.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnRegisterChallengeArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /auth/webauthn/register` API.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnRegisterArgs {
+    pub user_id: u64,
+    pub credential: RegisterPublicKeyCredential,
+}
+
+/// Arguments for `POST /auth/webauthn/login/challenge` API.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnLoginChallengeArgs {
+    pub email: String,
+}
+
+/// Arguments for `POST /auth/webauthn/login` API.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnLoginArgs {
+    pub token: String,
+    pub credential: PublicKeyCredential,
+}
+
 /// Sets token for creating user.
 #[post("/auth/token/sign_up")]
 pub async fn set_sign_up_token(args: web::Json<SetSignUpTokenArgs>) -> impl Responder {
@@ -35,25 +155,310 @@ pub async fn set_sign_up_token(args: web::Json<SetSignUpTokenArgs>) -> impl Resp
         email,
         password,
         avatar_url,
+        invite_code,
     } = args.into_inner();
-    let result = AuthService::new().set_sign_up_token(&name, &email, &password, &avatar_url);
-    http_util::get_response::<String>(result)
+    let result =
+        AuthService::new().set_sign_up_token(&name, &email, &password, &avatar_url, &invite_code);
+    http_util::respond(result)
+}
+
+/// Runs an attempt in the scope of signing in, which is throttled per account and per IP
+/// forwarded in `X-Forwarded-For` header, and responds the result with the stricter limit
+/// of them in `RateLimit-*` headers.
+fn respond_throttled<T: Serialize>(
+    req: &HttpRequest,
+    scope: ThrottleScope,
+    account: Option<&str>,
+    attempt: impl FnOnce() -> Result<T, ServiceError>,
+) -> HttpResponse {
+    let audit_context = http_util::get_audit_context(req);
+    let mut login_throttle_service = LoginThrottleService::new().with_scope(scope);
+    let result = login_throttle_service.throttle(account, &audit_context.ip, attempt);
+    let rate_limit = login_throttle_service.rate_limit(account, &audit_context.ip);
+    http_util::respond_with_rate_limit(result, rate_limit.ok())
 }
 
 /// Sets token for resetting password.
 #[post("/auth/token/password")]
-pub async fn set_password_token(args: web::Json<SetPasswordTokenArgs>) -> impl Responder {
+pub async fn set_password_token(
+    req: HttpRequest,
+    args: web::Json<SetPasswordTokenArgs>,
+) -> impl Responder {
     let SetPasswordTokenArgs { email } = args.into_inner();
-    let result = AuthService::new().set_password_token(&email);
-    http_util::get_response::<bool>(result)
+    respond_throttled(&req, ThrottleScope::PasswordToken, Some(&email), || {
+        AuthService::new().set_password_token(&email)
+    })
 }
 
 /// Signs in to set user session.
 #[post("/auth/login")]
-pub async fn login(args: web::Json<LoginArgs>) -> impl Responder {
+pub async fn login(req: HttpRequest, args: web::Json<LoginArgs>) -> impl Responder {
     let LoginArgs { email, password } = args.into_inner();
-    let result = AuthService::new().login(&email, &password);
-    http_util::get_response::<UserSession>(result)
+    respond_throttled(&req, ThrottleScope::Login, Some(&email), || {
+        AuthService::new().login(&email, &password)
+    })
+}
+
+/// Emails a link signing in without the password.
+#[post("/auth/magic-link")]
+pub async fn send_magic_link(req: HttpRequest, args: web::Json<MagicLinkArgs>) -> impl Responder {
+    let MagicLinkArgs { email } = args.into_inner();
+    respond_throttled(&req, ThrottleScope::MagicLink, Some(&email), || {
+        AuthService::new().send_magic_link(&email)
+    })
+}
+
+/// Signs in with a magic link token.
+#[post("/auth/magic-link/login")]
+pub async fn login_with_magic_link(args: web::Json<MagicLinkLoginArgs>) -> impl Responder {
+    let result = AuthService::new().login_with_magic_link(&args.token);
+    http_util::respond(result)
+}
+
+/// Finishes signing in with a two-factor code.
+#[post("/auth/login/2fa")]
+pub async fn login_with_two_factor(
+    req: HttpRequest,
+    args: web::Json<TwoFactorLoginArgs>,
+) -> impl Responder {
+    let TwoFactorLoginArgs { token, code } = args.into_inner();
+    respond_throttled(&req, ThrottleScope::TwoFactor, None, || {
+        AuthService::new().login_with_two_factor(&token, &code)
+    })
+}
+
+/// Starts to set up two-factor authentication.
+#[post("/auth/2fa/setup")]
+pub async fn setup_two_factor(args: web::Json<TwoFactorSetupArgs>) -> impl Responder {
+    let result = TwoFactorService::new().setup(args.user_id);
+    http_util::respond(result)
+}
+
+/// Enables two-factor authentication by a code.
+#[post("/auth/2fa/verify")]
+pub async fn verify_two_factor(args: web::Json<TwoFactorCodeArgs>) -> impl Responder {
+    let TwoFactorCodeArgs { user_id, code } = args.into_inner();
+    let result = TwoFactorService::new().enable(user_id, &code);
+    http_util::respond(result)
+}
+
+/// Disables two-factor authentication by a code.
+#[post("/auth/2fa/disable")]
+pub async fn disable_two_factor(args: web::Json<TwoFactorCodeArgs>) -> impl Responder {
+    let TwoFactorCodeArgs { user_id, code } = args.into_inner();
+    let result = TwoFactorService::new().disable(user_id, &code);
+    http_util::respond(result)
+}
+
+/// Returns the URL of the consent page of an OAuth provider.
+#[get("/auth/oauth/{provider}/url")]
+pub async fn get_oauth_url(
+    provider: web::Path<String>,
+    args: web::Query<OAuthUrlArgs>,
+) -> impl Responder {
+    let result = OAuthService::new().get_authorization_url(&provider, &args.state);
+    http_util::respond(result)
+}
+
+/// Signs in with a code of an OAuth provider.
+#[post("/auth/oauth/{provider}/login")]
+pub async fn login_with_oauth(
+    provider: web::Path<String>,
+    args: web::Json<OAuthLoginArgs>,
+) -> impl Responder {
+    let result = AuthService::new().login_with_oauth(&provider, &args.code);
+    http_util::respond(result)
+}
+
+/// Starts to register a passkey.
+#[post("/auth/webauthn/register/challenge")]
+pub async fn start_webauthn_registration(
+    args: web::Json<WebauthnRegisterChallengeArgs>,
+) -> impl Responder {
+    let result = WebauthnService::new().start_registration(args.user_id);
+    http_util::respond(result)
+}
+
+/// Finishes registering a passkey.
+#[post("/auth/webauthn/register")]
+pub async fn finish_webauthn_registration(args: web::Json<WebauthnRegisterArgs>) -> impl Responder {
+    let WebauthnRegisterArgs {
+        user_id,
+        credential,
+    } = args.into_inner();
+    let result = WebauthnService::new().finish_registration(user_id, &credential);
+    http_util::respond(result)
+}
+
+/// Starts to sign in with a passkey.
+#[post("/auth/webauthn/login/challenge")]
+pub async fn start_webauthn_login(args: web::Json<WebauthnLoginChallengeArgs>) -> impl Responder {
+    let result = WebauthnService::new().start_login(&args.email);
+    http_util::respond(result)
+}
+
+/// Signs in with a passkey.
+#[post("/auth/webauthn/login")]
+pub async fn login_with_webauthn(
+    req: HttpRequest,
+    args: web::Json<WebauthnLoginArgs>,
+) -> impl Responder {
+    let WebauthnLoginArgs { token, credential } = args.into_inner();
+    respond_throttled(&req, ThrottleScope::Passkey, None, || {
+        AuthService::new().login_with_webauthn(&token, &credential)
+    })
+}
+
+/// Lists passkeys of the user.
+#[get("/auth/webauthn/credentials/{user_id}")]
+pub async fn get_webauthn_credentials(user_id: web::Path<u64>) -> impl Responder {
+    let result = WebauthnService::new().get_list(user_id.into_inner());
+    http_util::respond(result)
+}
+
+/// Deletes a passkey of the user.
+#[delete("/auth/webauthn/credentials/{user_id}/{id}")]
+pub async fn delete_webauthn_credential(
+    web::Path((user_id, id)): web::Path<(u64, u64)>,
+) -> impl Responder {
+    let result = WebauthnService::new().delete(id, user_id);
+    http_util::respond(result)
+}
+
+/// Creates a login session of the device which has signed in.
+///
+/// The session id, `User-Agent`, and the IP of the device are forwarded in headers.
+/// A device signed in with the session cookie is identified by `X-Device-Id` header.
+#[post("/auth/sessions")]
+pub async fn create_login_session(
+    req: HttpRequest,
+    args: web::Json<CreateLoginSessionArgs>,
+) -> impl Responder {
+    let CreateLoginSessionArgs { user_id, label } = args.into_inner();
+    let audit_context = http_util::get_audit_context(&req);
+    let device_id = http_util::get_device_id(&req);
+    let result = LoginSessionService::new().create(user_id, &audit_context, &device_id, &label);
+    http_util::respond(result)
+}
+
+/// Responds the session of the user if a login session is active, or `null` if it is not.
+#[post("/auth/sessions/verify")]
+pub async fn verify_login_session(
+    req: HttpRequest,
+    args: web::Json<VerifyLoginSessionArgs>,
+) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result = AuthService::new().verify_login_session(
+        &args.session_id,
+        &args.method,
+        &args.path,
+        &audit_context,
+    );
+    http_util::respond(result)
+}
+
+/// Deletes the login session of the device which signs out.
+#[post("/auth/sessions/logout")]
+pub async fn logout_login_session(args: web::Json<LoginSessionArgs>) -> impl Responder {
+    let result = LoginSessionService::new().delete_by_session_id(args.user_id, &args.session_id);
+    http_util::respond(result)
+}
+
+/// Lists login sessions of the user.
+///
+/// The session of the requesting device is marked by `X-Session-Id` header.
+#[get("/auth/sessions/{user_id}")]
+pub async fn get_login_sessions(req: HttpRequest, user_id: web::Path<u64>) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result =
+        LoginSessionService::new().get_list(user_id.into_inner(), &audit_context.session_id);
+    http_util::respond(result)
+}
+
+/// Deletes a login session of the user.
+#[delete("/auth/sessions/{user_id}/{id}")]
+pub async fn delete_login_session(
+    web::Path((user_id, id)): web::Path<(u64, u64)>,
+) -> impl Responder {
+    let result = LoginSessionService::new().delete(id, user_id);
+    http_util::respond(result)
+}
+
+/// Deletes all login sessions of the user.
+#[delete("/auth/sessions/{user_id}")]
+pub async fn delete_login_sessions(user_id: web::Path<u64>) -> impl Responder {
+    let result = LoginSessionService::new().delete_all(user_id.into_inner());
+    http_util::respond(result)
+}
+
+/// Lists login sessions of a device, which it can switch to.
+///
+/// The device is identified by `X-Device-Id` header, and the session it is using
+/// is marked by `X-Session-Id` header.
+#[get("/auth/devices/sessions")]
+pub async fn get_device_login_sessions(req: HttpRequest) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result = match http_util::get_device_id(&req) {
+        Some(device_id) => {
+            LoginSessionService::new().get_device_list(&device_id, &audit_context.session_id)
+        }
+        None => Err(ServiceError::InvalidArgument),
+    };
+    http_util::respond(result)
+}
+
+/// Switches a device to its login session, and responds the session of the user.
+///
+/// The device is identified by `X-Device-Id` header, and its new session id is forwarded
+/// in `X-Session-Id` header.
+#[post("/auth/devices/sessions/{id}/switch")]
+pub async fn switch_login_session(req: HttpRequest, id: web::Path<u64>) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result = match http_util::get_device_id(&req) {
+        Some(device_id) => {
+            AuthService::new().switch_session(id.into_inner(), &device_id, &audit_context)
+        }
+        None => Err(ServiceError::InvalidArgument),
+    };
+    http_util::respond(result)
+}
+
+/// Creates a login session of the device which has signed in to use access tokens,
+/// and returns its refresh token.
+///
+/// The session id of the first access token, `User-Agent`, and the IP of the device
+/// are forwarded in headers.
+#[post("/auth/tokens")]
+pub async fn create_token(req: HttpRequest, args: web::Json<CreateTokenArgs>) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result = LoginSessionService::new().create_with_refresh_token(args.user_id, &audit_context);
+    http_util::respond(result)
+}
+
+/// Refreshes a login session by its refresh token.
+///
+/// The session id of the new access token is forwarded in `X-Session-Id` header.
+#[post("/auth/tokens/refresh")]
+pub async fn refresh_token(req: HttpRequest, args: web::Json<RefreshTokenArgs>) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result = AuthService::new().refresh_token(&args.refresh_token, &audit_context);
+    http_util::respond(result)
+}
+
+/// Deletes the login session of a refresh token.
+#[post("/auth/tokens/revoke")]
+pub async fn revoke_token(args: web::Json<RefreshTokenArgs>) -> impl Responder {
+    let result = LoginSessionService::new().revoke_refresh_token(&args.refresh_token);
+    http_util::respond(result)
+}
+
+/// Responds the session of the user who owns a personal access token, with the scope of it.
+#[post("/auth/personal-access-tokens/verify")]
+pub async fn verify_personal_access_token(
+    args: web::Json<PersonalAccessTokenArgs>,
+) -> impl Responder {
+    let result = AuthService::new().login_with_personal_access_token(&args.token);
+    http_util::respond(result)
 }
 
 /// Initializes the auth routes.
@@ -61,4 +466,30 @@ pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(set_sign_up_token);
     cfg.service(set_password_token);
     cfg.service(login);
+    cfg.service(send_magic_link);
+    cfg.service(login_with_magic_link);
+    cfg.service(login_with_two_factor);
+    cfg.service(setup_two_factor);
+    cfg.service(verify_two_factor);
+    cfg.service(disable_two_factor);
+    cfg.service(get_oauth_url);
+    cfg.service(login_with_oauth);
+    cfg.service(start_webauthn_registration);
+    cfg.service(finish_webauthn_registration);
+    cfg.service(start_webauthn_login);
+    cfg.service(login_with_webauthn);
+    cfg.service(get_webauthn_credentials);
+    cfg.service(delete_webauthn_credential);
+    cfg.service(create_login_session);
+    cfg.service(verify_login_session);
+    cfg.service(logout_login_session);
+    cfg.service(get_login_sessions);
+    cfg.service(delete_login_session);
+    cfg.service(delete_login_sessions);
+    cfg.service(get_device_login_sessions);
+    cfg.service(switch_login_session);
+    cfg.service(create_token);
+    cfg.service(refresh_token);
+    cfg.service(revoke_token);
+    cfg.service(verify_personal_access_token);
 }
@@ -0,0 +1,132 @@
+use actix_web::{delete, get, post, web, HttpRequest, Responder};
+use serde::{Deserialize, Serialize};
+use std::iter;
+
+use crate::services::calendar_feed::CalendarFeedService;
+use crate::services::export::ExportService;
+use crate::utils::http_util;
+
+/// Content type of export archives.
+const ARCHIVE_CONTENT_TYPE: &str = "application/gzip";
+
+/// Content type of calendar feeds.
+const CALENDAR_CONTENT_TYPE: &str = "text/calendar; charset=utf-8";
+
+/// Arguments for `GET /export/:user_id` API.
+#[derive(Serialize, Deserialize)]
+pub struct ExportArgs {
+    pub include_trash: Option<bool>,
+}
+
+/// Arguments for `POST /export/jobs` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateExportJobArgs {
+    pub user_id: u64,
+    pub format: String,
+    pub include_trash: Option<bool>,
+}
+
+/// Arguments for `GET /export/calendar.ics` API.
+#[derive(Serialize, Deserialize)]
+pub struct CalendarArgs {
+    pub token: String,
+}
+
+/// Arguments for `POST /export/calendar` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateCalendarFeedArgs {
+    pub user_id: u64,
+}
+
+/// Responds an iCalendar feed of the dates of posts, for the user of the token
+#[get("/export/calendar.ics")]
+pub async fn get_calendar(args: web::Query<CalendarArgs>) -> impl Responder {
+    let calendar = CalendarFeedService::new().get_calendar(&args.into_inner().token);
+    http_util::respond_stream(
+        iter::once(calendar.map(String::into_bytes)),
+        CALENDAR_CONTENT_TYPE,
+        "darim.ics",
+    )
+}
+
+/// Creates a calendar feed of logged-in user with a new token
+#[post("/export/calendar")]
+pub async fn create_calendar_feed(args: web::Json<CreateCalendarFeedArgs>) -> impl Responder {
+    let feed = CalendarFeedService::new().create(args.into_inner().user_id);
+    http_util::respond(feed)
+}
+
+/// Revokes the calendar feed of logged-in user
+#[delete("/export/calendar/{user_id}")]
+pub async fn delete_calendar_feed(user_id: web::Path<u64>) -> impl Responder {
+    let result = CalendarFeedService::new().delete(user_id.into_inner());
+    http_util::respond(result)
+}
+
+/// Enqueues an export job of logged-in user, and runs it in background
+#[post("/export/jobs")]
+pub async fn create_export_job(args: web::Json<CreateExportJobArgs>) -> impl Responder {
+    let args = args.into_inner();
+    let job = ExportService::new().create_job(
+        args.user_id,
+        &args.format,
+        args.include_trash.unwrap_or(false),
+    );
+    if job.is_ok() {
+        ExportService::run_soon();
+    }
+    http_util::respond(job)
+}
+
+/// Responds an export job of logged-in user
+#[get("/export/jobs/{user_id}/{id}")]
+pub async fn get_export_job(path: web::Path<(u64, u64)>) -> impl Responder {
+    let (user_id, id) = path.into_inner();
+    let job = ExportService::new().get_job(user_id, id);
+    http_util::respond(job)
+}
+
+/// Streams the archive of an export job with the download token, or the range of it in `Range` header
+#[get("/export/downloads/{token}")]
+pub async fn download_export_job(req: HttpRequest, token: web::Path<String>) -> impl Responder {
+    let range = http_util::get_byte_range(&req);
+    match ExportService::new().download(&token.into_inner(), range) {
+        Ok(artifact) => {
+            let filename = artifact.filename();
+            let length = artifact.length();
+            let content_range = artifact.content_range();
+            http_util::respond_file_stream(
+                artifact,
+                ARCHIVE_CONTENT_TYPE,
+                &filename,
+                length,
+                content_range,
+            )
+        }
+        Err(error) => http_util::err(error),
+    }
+}
+
+/// Streams an archive of posts written by logged-in user
+#[get("/export/{user_id}")]
+pub async fn export(user_id: web::Path<u64>, args: web::Query<ExportArgs>) -> impl Responder {
+    let include_trash = args.into_inner().include_trash.unwrap_or(false);
+    match ExportService::new().export_now(user_id.into_inner(), include_trash) {
+        Ok(archive) => {
+            http_util::respond_stream(archive, ARCHIVE_CONTENT_TYPE, "darim-export.tar.gz")
+        }
+        Err(error) => http_util::err(error),
+    }
+}
+
+/// Initializes the export routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    // These routes are registered before `export`, which takes any path as `user_id`.
+    cfg.service(get_calendar);
+    cfg.service(create_calendar_feed);
+    cfg.service(delete_calendar_feed);
+    cfg.service(create_export_job);
+    cfg.service(get_export_job);
+    cfg.service(download_export_job);
+    cfg.service(export);
+}
@@ -0,0 +1,38 @@
+use actix_web::{web, HttpRequest, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::services::import::ImportService;
+use crate::utils::http_util;
+
+/// Maximum size of an archive to import.
+const MAX_ARCHIVE_SIZE: usize = 32 * 1024 * 1024;
+
+/// Arguments for `POST /import/:user_id` API.
+#[derive(Serialize, Deserialize)]
+pub struct ImportArgs {
+    pub dry_run: Option<bool>,
+}
+
+/// Creates posts of logged-in user from an archive in the request body
+pub async fn import_posts(
+    req: HttpRequest,
+    user_id: web::Path<u64>,
+    args: web::Query<ImportArgs>,
+    archive: web::Bytes,
+) -> impl Responder {
+    let dry_run = args.into_inner().dry_run.unwrap_or(false);
+    let audit_context = http_util::get_audit_context(&req);
+    let result =
+        ImportService::new().import(user_id.into_inner(), &archive, &audit_context, dry_run);
+    http_util::respond(result)
+}
+
+/// Initializes the import routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    // The archive exceeds the default payload limit, so the route is configured with its own.
+    cfg.service(
+        web::resource("/import/{user_id}")
+            .app_data(web::PayloadConfig::new(MAX_ARCHIVE_SIZE))
+            .route(web::post().to(import_posts)),
+    );
+}
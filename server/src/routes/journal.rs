@@ -0,0 +1,100 @@
+use actix_web::{delete, get, patch, post, web, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::routes::post::ListArgs;
+use crate::services::journal::JournalService;
+use crate::services::post::PostService;
+use crate::utils::http_util;
+
+/// Arguments for `POST /journals` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateArgs {
+    pub user_id: u64,
+    pub name: String,
+}
+
+/// Arguments for `PATCH /journals/:id` API.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateArgs {
+    pub user_id: u64,
+    pub name: String,
+}
+
+/// Lists journals of logged-in user
+#[get("/journals/{user_id}")]
+pub async fn get_journals(user_id: web::Path<u64>) -> impl Responder {
+    let journals = JournalService::new().get_list(user_id.into_inner());
+    http_util::respond(journals)
+}
+
+/// Lists posts in a journal of logged-in user
+///
+/// It takes the same arguments as `GET /posts/:user_id`, except that `journal` is ignored.
+#[get("/journals/{user_id}/{id}/posts")]
+pub async fn get_journal_posts(
+    web::Path((user_id, id)): web::Path<(u64, u64)>,
+    args: web::Query<ListArgs>,
+) -> impl Responder {
+    let ListArgs {
+        tag,
+        from,
+        to,
+        status,
+        favorite,
+        near,
+        radius,
+        sort_by,
+        order,
+        page,
+        per_page,
+        ..
+    } = args.into_inner();
+    let posts = PostService::new().get_list(
+        user_id,
+        &tag,
+        &Some(id),
+        &from,
+        &to,
+        &status,
+        &favorite,
+        &near,
+        &radius,
+        &sort_by,
+        &order,
+        &page,
+        &per_page,
+    );
+    http_util::respond_page(posts)
+}
+
+/// Creates a new journal
+#[post("/journals")]
+pub async fn create_journal(args: web::Json<CreateArgs>) -> impl Responder {
+    let CreateArgs { user_id, name } = args.into_inner();
+    let result = JournalService::new().create(user_id, &name);
+    http_util::respond(result)
+}
+
+/// Renames a journal
+#[patch("/journals/{id}")]
+pub async fn update_journal(id: web::Path<u64>, args: web::Json<UpdateArgs>) -> impl Responder {
+    let UpdateArgs { user_id, name } = args.into_inner();
+    let result = JournalService::new().update(id.into_inner(), user_id, &name);
+    http_util::respond(result)
+}
+
+/// Deletes a journal, moving its posts to the default journal
+#[delete("/journals/{user_id}/{id}")]
+pub async fn delete_journal(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let result = JournalService::new().delete(id, user_id);
+    http_util::respond(result)
+}
+
+/// Initializes the journal routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_journals);
+    cfg.service(get_journal_posts);
+    cfg.service(create_journal);
+    cfg.service(update_journal);
+    cfg.service(delete_journal);
+}
@@ -1,18 +1,45 @@
-use actix_web::{delete, get, patch, post, web, Responder};
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, Responder};
 use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 
-use crate::models::post::*;
+use crate::models::post::{PostEncryptionDTO, PostFields, PostLocationDTO, PostOperationDTO};
 use crate::services::post::PostService;
+use crate::services::post_audit::PostAuditService;
+use crate::utils::datetime_util;
 use crate::utils::http_util;
 
+/// Maximum size of the body of `POST /posts/bulk` API.
+const MAX_BULK_PAYLOAD_SIZE: usize = 8 * 1024 * 1024;
+
 /// Arguments for `POST /posts` API.
 #[derive(Serialize, Deserialize)]
 pub struct CreateArgs {
     pub user_id: u64,
-    pub title: String,
-    pub content: String,
-    pub date: NaiveDateTime,
+    /// Title of the post, which is taken from the template if omitted with `template_id`.
+    pub title: Option<String>,
+    /// Content of the post, which is taken from the template if omitted with `template_id`.
+    pub content: Option<String>,
+    /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+    pub date: String,
+    /// Ids of tags of the post.
+    pub tags: Option<Vec<u64>>,
+    /// `published` or `draft`.
+    pub status: Option<String>,
+    /// Mood from 1 (worst) to 5 (best).
+    pub mood: Option<u8>,
+    /// `sunny`, `cloudy`, `rainy`, `snowy`, `windy`, `foggy` or `stormy`.
+    pub weather: Option<String>,
+    #[serde(flatten)]
+    pub location: PostLocationDTO,
+    /// Id of the journal of the post, which is the default journal if omitted.
+    pub journal_id: Option<u64>,
+    /// Number of words in the content, counted by the client before encryption.
+    pub word_count: Option<u32>,
+    /// Encryption of the title and content.
+    #[serde(flatten)]
+    pub encryption: PostEncryptionDTO,
+    /// Id of the template the post is created from.
+    pub template_id: Option<u64>,
 }
 
 /// Arguments for `PATCH /posts/:id` API.
@@ -21,69 +48,648 @@ pub struct UpdateArgs {
     pub user_id: u64,
     pub title: Option<String>,
     pub content: Option<String>,
-    pub date: Option<NaiveDateTime>,
+    /// RFC 3339 datetime with offset. Naive datetime is accepted for legacy clients.
+    pub date: Option<String>,
+    /// Ids of tags replacing the tags of the post.
+    pub tags: Option<Vec<u64>>,
+    pub mood: Option<u8>,
+    pub weather: Option<String>,
+    /// Location replacing the location of the post.
+    #[serde(flatten)]
+    pub location: PostLocationDTO,
+    /// Id of the journal the post is moved to.
+    pub journal_id: Option<u64>,
+    /// Number of words in the new content, counted by the client before encryption.
+    pub word_count: Option<u32>,
+    /// Encryption of the new title and content.
+    #[serde(flatten)]
+    pub encryption: PostEncryptionDTO,
+    /// Version of the post the edit is based on.
+    pub version: Option<u32>,
+}
+
+/// Arguments for `POST /posts/bulk` API.
+#[derive(Serialize, Deserialize)]
+pub struct BulkArgs {
+    pub user_id: u64,
+    pub operations: Vec<PostOperationDTO>,
+    /// Index of the first operation to execute, which is `next_cursor` of the last batch.
+    pub continue_from: Option<usize>,
+}
+
+/// Arguments for `PUT /posts/:id/autosave` API.
+#[derive(Serialize, Deserialize)]
+pub struct AutosaveArgs {
+    pub user_id: u64,
+    pub title: Option<String>,
+    pub content: String,
+    /// Encryption of the saved title and content.
+    #[serde(flatten)]
+    pub encryption: PostEncryptionDTO,
+    /// Version of the post the edit is based on.
+    pub version: Option<u32>,
+}
+
+/// Arguments for `PATCH /posts/:id/reorder` API.
+#[derive(Serialize, Deserialize)]
+pub struct ReorderArgs {
+    pub user_id: u64,
+    /// Position among the posts of the date, starting from 0.
+    pub position: usize,
+}
+
+/// Arguments for `PATCH /posts/:id/publish` API.
+#[derive(Serialize, Deserialize)]
+pub struct PublishArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `PUT /posts/:id/favorite` API.
+#[derive(Serialize, Deserialize)]
+pub struct FavoriteArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /posts/:id/lock` API.
+#[derive(Serialize, Deserialize)]
+pub struct LockArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /posts/:id/restore` and `POST /posts/:id/revisions/:version/restore` API.
+#[derive(Serialize, Deserialize)]
+pub struct RestoreArgs {
+    pub user_id: u64,
+}
+
+/// Arguments for `POST /posts/:id/duplicate` API.
+#[derive(Serialize, Deserialize)]
+pub struct DuplicateArgs {
+    pub user_id: u64,
+    /// RFC 3339 datetime of the new post, which is now if omitted.
+    pub date: Option<String>,
+}
+
+/// Arguments for `DELETE /posts/:user_id/by-date/:date` API.
+#[derive(Serialize, Deserialize)]
+pub struct DeleteByDateArgs {
+    pub password: String,
+    pub permanent: Option<bool>,
+    pub dry_run: Option<bool>,
+}
+
+/// Arguments for `GET /posts/:user_id` API.
+#[derive(Serialize, Deserialize)]
+pub struct ListArgs {
+    pub tag: Option<u64>,
+    pub journal: Option<u64>,
+    /// The first local date in `YYYY-MM-DD` format, inclusive.
+    pub from: Option<String>,
+    /// The last local date in `YYYY-MM-DD` format, inclusive.
+    pub to: Option<String>,
+    /// `published` or `draft`.
+    pub status: Option<String>,
+    /// Whether to list only favorites, or only the others.
+    pub favorite: Option<bool>,
+    /// A location in `latitude,longitude` format the posts are written near.
+    pub near: Option<String>,
+    /// Radius in meters around `near`.
+    pub radius: Option<f64>,
+    pub sort_by: Option<String>,
+    pub order: Option<String>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+    /// `all`, or `summary` to list only ids, titles and dates of the posts.
+    pub fields: Option<String>,
+}
+
+/// Arguments for `GET /posts/:user_id/on-this-day` API.
+#[derive(Serialize, Deserialize)]
+pub struct OnThisDayArgs {
+    /// The local date of the client in `YYYY-MM-DD` format.
+    pub date: Option<String>,
+}
+
+/// Arguments for `GET /posts/:user_id/heatmap` API.
+#[derive(Serialize, Deserialize)]
+pub struct HeatmapArgs {
+    pub year: Option<i32>,
+}
+
+/// Arguments for `GET /posts/:user_id/stats/moods` API.
+#[derive(Serialize, Deserialize)]
+pub struct MoodStatsArgs {
+    /// The first local date in `YYYY-MM-DD` format, inclusive.
+    pub from: Option<String>,
+    /// The last local date in `YYYY-MM-DD` format, inclusive.
+    pub to: Option<String>,
+    /// `day`, `week` starting on the week start day of the user, or `month`.
+    pub interval: Option<String>,
+}
+
+/// Arguments for `GET /posts/:user_id/stats/entries` API.
+#[derive(Serialize, Deserialize)]
+pub struct EntryStatsArgs {
+    /// The first local date in `YYYY-MM-DD` format, inclusive.
+    pub from: Option<String>,
+    /// The last local date in `YYYY-MM-DD` format, inclusive.
+    pub to: Option<String>,
+    /// `daily`, `weekly` starting on the week start day of the user, or `monthly`.
+    pub granularity: Option<String>,
+}
+
+/// Arguments for `GET /posts/:user_id/changes` API.
+#[derive(Serialize, Deserialize)]
+pub struct ChangesArgs {
+    /// `cursor` of the last sync, or an RFC 3339 datetime.
+    pub since: Option<String>,
+}
+
+/// Arguments for `GET /posts/:user_id/audit` and `GET /posts/:user_id/:id/audit` API.
+#[derive(Serialize, Deserialize)]
+pub struct AuditListArgs {
+    #[serde(default, with = "datetime_util::option_rfc3339")]
+    pub since: Option<NaiveDateTime>,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
 }
 
 /// Responds a post written by logged-in user
 #[get("/posts/{user_id}")]
-pub async fn get_posts(user_id: web::Path<u64>) -> impl Responder {
-    let posts = PostService::new().get_list(user_id.into_inner());
-    http_util::get_response::<Vec<PostDTO>>(posts)
+pub async fn get_posts(user_id: web::Path<u64>, args: web::Query<ListArgs>) -> impl Responder {
+    let ListArgs {
+        tag,
+        journal,
+        from,
+        to,
+        status,
+        favorite,
+        near,
+        radius,
+        sort_by,
+        order,
+        page,
+        per_page,
+        fields,
+    } = args.into_inner();
+    let fields = match fields {
+        Some(fields) => PostFields::parse(&fields),
+        None => Ok(PostFields::All),
+    };
+
+    match fields {
+        Ok(PostFields::All) => http_util::respond_page(PostService::new().get_list(
+            user_id.into_inner(),
+            &tag,
+            &journal,
+            &from,
+            &to,
+            &status,
+            &favorite,
+            &near,
+            &radius,
+            &sort_by,
+            &order,
+            &page,
+            &per_page,
+        )),
+        Ok(PostFields::Summary) => http_util::respond_page(PostService::new().get_summarized_page(
+            user_id.into_inner(),
+            &tag,
+            &journal,
+            &from,
+            &to,
+            &status,
+            &favorite,
+            &near,
+            &radius,
+            &sort_by,
+            &order,
+            &page,
+            &per_page,
+        )),
+        Err(error) => http_util::err(error),
+    }
 }
 
 /// Responds a summarized post written by logged-in user
 #[get("/summarized_posts/{user_id}")]
 pub async fn get_summarized_posts(user_id: web::Path<u64>) -> impl Responder {
     let posts = PostService::new().get_summarized_list(user_id.into_inner());
-    http_util::get_response::<Vec<SummarizedPostDTO>>(posts)
+    http_util::respond(posts)
+}
+
+/// Lists ids and titles of posts written by logged-in user in a month by the day
+#[get("/posts/{user_id}/calendar/{year}/{month}")]
+pub async fn get_calendar(
+    web::Path((user_id, year, month)): web::Path<(u64, i32, u32)>,
+) -> impl Responder {
+    let calendar = PostService::new().get_calendar(user_id, year, month);
+    http_util::respond(calendar)
+}
+
+/// Lists numbers of posts written by logged-in user by the year and month
+#[get("/posts/{user_id}/archive")]
+pub async fn get_archive(user_id: web::Path<u64>) -> impl Responder {
+    let archive = PostService::new().get_archive(user_id.into_inner());
+    http_util::respond(archive)
+}
+
+/// Lists ids and titles of posts written by logged-in user in a year by the month
+#[get("/posts/{user_id}/archive/{year}")]
+pub async fn get_archive_year(web::Path((user_id, year)): web::Path<(u64, i32)>) -> impl Responder {
+    let archive = PostService::new().get_archive_year(user_id, year);
+    http_util::respond(archive)
+}
+
+/// Lists numbers of posts written by logged-in user on each day of a year
+#[get("/posts/{user_id}/heatmap")]
+pub async fn get_heatmap(user_id: web::Path<u64>, args: web::Query<HeatmapArgs>) -> impl Responder {
+    let heatmap = PostService::new().get_heatmap(user_id.into_inner(), &args.into_inner().year);
+    http_util::respond(heatmap)
+}
+
+/// Lists posts written by logged-in user on the same day in previous years
+#[get("/posts/{user_id}/on-this-day")]
+pub async fn get_on_this_day(
+    user_id: web::Path<u64>,
+    args: web::Query<OnThisDayArgs>,
+) -> impl Responder {
+    let posts = PostService::new().get_on_this_day(user_id.into_inner(), &args.into_inner().date);
+    http_util::respond(posts)
+}
+
+/// Lists changes of posts written by logged-in user since the last sync
+#[get("/posts/{user_id}/changes")]
+pub async fn get_changes(user_id: web::Path<u64>, args: web::Query<ChangesArgs>) -> impl Responder {
+    let changes = PostService::new().get_changes(user_id.into_inner(), &args.into_inner().since);
+    http_util::respond(changes)
+}
+
+/// Lists numbers of encrypted posts and revisions written by logged-in user by the key version
+#[get("/posts/{user_id}/key-versions")]
+pub async fn get_key_versions(user_id: web::Path<u64>) -> impl Responder {
+    let key_versions = PostService::new().get_key_versions(user_id.into_inner());
+    http_util::respond(key_versions)
+}
+
+/// Lists mood trends of posts written by logged-in user
+#[get("/posts/{user_id}/stats/moods")]
+pub async fn get_mood_stats(
+    user_id: web::Path<u64>,
+    args: web::Query<MoodStatsArgs>,
+) -> impl Responder {
+    let MoodStatsArgs { from, to, interval } = args.into_inner();
+    let trends = PostService::new().get_mood_trends(user_id.into_inner(), &from, &to, &interval);
+    http_util::respond(trends)
+}
+
+/// Counts posts written by logged-in user in each period
+#[get("/posts/{user_id}/stats/entries")]
+pub async fn get_entry_stats(
+    user_id: web::Path<u64>,
+    args: web::Query<EntryStatsArgs>,
+) -> impl Responder {
+    let EntryStatsArgs {
+        from,
+        to,
+        granularity,
+    } = args.into_inner();
+    let counts =
+        PostService::new().get_entry_counts(user_id.into_inner(), &from, &to, &granularity);
+    http_util::respond(counts)
 }
 
 /// Lists posts written by logged-in user
 #[get("/posts/{user_id}/{id}")]
 pub async fn get_post(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
     let post = PostService::new().get(user_id, id);
-    http_util::get_response::<PostDTO>(post)
+    http_util::respond(post)
 }
 
 /// Creates a new post
 #[post("/posts")]
-pub async fn create_post(args: web::Json<CreateArgs>) -> impl Responder {
+pub async fn create_post(req: HttpRequest, args: web::Json<CreateArgs>) -> impl Responder {
     let CreateArgs {
         user_id,
         title,
         content,
         date,
+        tags,
+        status,
+        mood,
+        weather,
+        location,
+        journal_id,
+        word_count,
+        encryption,
+        template_id,
+    } = args.into_inner();
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().create(
+        user_id,
+        &title,
+        &content,
+        &date,
+        &tags.unwrap_or_default(),
+        &status,
+        &mood,
+        &weather,
+        &location,
+        &journal_id,
+        &word_count,
+        &encryption,
+        &template_id,
+        &audit_context,
+    );
+    http_util::respond_with_meta(result)
+}
+
+/// Creates, updates, and deletes posts at once, batch by batch
+pub async fn execute_bulk(req: HttpRequest, args: web::Json<BulkArgs>) -> impl Responder {
+    let BulkArgs {
+        user_id,
+        operations,
+        continue_from,
     } = args.into_inner();
-    let result = PostService::new().create(user_id, &title, &content, &date);
-    http_util::get_response::<u64>(result)
+    let audit_context = http_util::get_audit_context(&req);
+    let batch =
+        PostService::new().execute_bulk(user_id, &operations, &continue_from, &audit_context);
+    http_util::respond_batch(batch)
 }
 
-/// Deletes a post
+/// Permanently deletes all posts written on a date
+#[delete("/posts/{user_id}/by-date/{date}")]
+pub async fn delete_posts_by_date(
+    web::Path((user_id, date)): web::Path<(u64, String)>,
+    args: web::Json<DeleteByDateArgs>,
+) -> impl Responder {
+    let DeleteByDateArgs {
+        password,
+        permanent,
+        dry_run,
+    } = args.into_inner();
+    let result = PostService::new().delete_by_date(
+        user_id,
+        &date,
+        &password,
+        permanent.unwrap_or(false),
+        dry_run.unwrap_or(false),
+    );
+    http_util::respond(result)
+}
+
+/// Moves a post to the trash
 #[delete("/posts/{user_id}/{id}")]
-pub async fn delete_post(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
-    let result = PostService::new().delete(id, user_id);
-    http_util::get_response::<bool>(result)
+pub async fn delete_post(
+    req: HttpRequest,
+    web::Path((user_id, id)): web::Path<(u64, u64)>,
+) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().delete(id, user_id, &audit_context);
+    http_util::respond(result)
 }
 
 /// Updates a post
 #[patch("/posts/{id}")]
-pub async fn update_post(id: web::Path<u64>, args: web::Json<UpdateArgs>) -> impl Responder {
+pub async fn update_post(
+    req: HttpRequest,
+    id: web::Path<u64>,
+    args: web::Json<UpdateArgs>,
+) -> impl Responder {
     let UpdateArgs {
         user_id,
         title,
         content,
         date,
+        tags,
+        mood,
+        weather,
+        location,
+        journal_id,
+        word_count,
+        encryption,
+        version,
     } = args.into_inner();
-    let result = PostService::new().update(id.into_inner(), user_id, &title, &content, &date);
-    http_util::get_response::<bool>(result)
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().update(
+        id.into_inner(),
+        user_id,
+        &title,
+        &content,
+        &date,
+        &tags,
+        &mood,
+        &weather,
+        &location,
+        &journal_id,
+        &word_count,
+        &encryption,
+        &version,
+        &http_util::get_unmodified_since(&req),
+        &audit_context,
+    );
+    http_util::respond(result)
+}
+
+/// Saves a post while it is edited
+#[put("/posts/{id}/autosave")]
+pub async fn autosave_post(
+    req: HttpRequest,
+    id: web::Path<u64>,
+    args: web::Json<AutosaveArgs>,
+) -> impl Responder {
+    let AutosaveArgs {
+        user_id,
+        title,
+        content,
+        encryption,
+        version,
+    } = args.into_inner();
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().autosave(
+        id.into_inner(),
+        user_id,
+        &title,
+        &content,
+        &encryption,
+        &version,
+        &audit_context,
+    );
+    http_util::respond(result)
+}
+
+/// Moves a post among the posts of its date
+#[patch("/posts/{id}/reorder")]
+pub async fn reorder_post(
+    req: HttpRequest,
+    id: web::Path<u64>,
+    args: web::Json<ReorderArgs>,
+) -> impl Responder {
+    let ReorderArgs { user_id, position } = args.into_inner();
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().reorder(id.into_inner(), user_id, position, &audit_context);
+    http_util::respond(result)
+}
+
+/// Publishes a draft
+#[patch("/posts/{id}/publish")]
+pub async fn publish_post(
+    req: HttpRequest,
+    id: web::Path<u64>,
+    args: web::Json<PublishArgs>,
+) -> impl Responder {
+    let PublishArgs { user_id } = args.into_inner();
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().publish(id.into_inner(), user_id, &audit_context);
+    http_util::respond(result)
+}
+
+/// Marks a post as a favorite
+#[put("/posts/{id}/favorite")]
+pub async fn favorite_post(id: web::Path<u64>, args: web::Json<FavoriteArgs>) -> impl Responder {
+    let FavoriteArgs { user_id } = args.into_inner();
+    let result = PostService::new().set_favorite(id.into_inner(), user_id, true);
+    http_util::respond(result)
+}
+
+/// Unmarks a post as a favorite
+#[delete("/posts/{user_id}/{id}/favorite")]
+pub async fn unfavorite_post(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let result = PostService::new().set_favorite(id, user_id, false);
+    http_util::respond(result)
+}
+
+/// Copies a post into a new post
+#[post("/posts/{id}/duplicate")]
+pub async fn duplicate_post(
+    req: HttpRequest,
+    id: web::Path<u64>,
+    args: web::Json<DuplicateArgs>,
+) -> impl Responder {
+    let DuplicateArgs { user_id, date } = args.into_inner();
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().duplicate(id.into_inner(), user_id, &date, &audit_context);
+    http_util::respond(result)
+}
+
+/// Locks a post against updates and deletion
+#[post("/posts/{id}/lock")]
+pub async fn lock_post(id: web::Path<u64>, args: web::Json<LockArgs>) -> impl Responder {
+    let LockArgs { user_id } = args.into_inner();
+    let result = PostService::new().set_locked(id.into_inner(), user_id, true);
+    http_util::respond(result)
+}
+
+/// Unlocks a post
+#[delete("/posts/{user_id}/{id}/lock")]
+pub async fn unlock_post(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let result = PostService::new().set_locked(id, user_id, false);
+    http_util::respond(result)
+}
+
+/// Lists posts in the trash of logged-in user
+#[get("/posts/{user_id}/trash")]
+pub async fn get_trash(user_id: web::Path<u64>) -> impl Responder {
+    let posts = PostService::new().get_trash(user_id.into_inner());
+    http_util::respond(posts)
+}
+
+/// Restores a post from the trash
+#[post("/posts/{id}/restore")]
+pub async fn restore_post(
+    req: HttpRequest,
+    id: web::Path<u64>,
+    args: web::Json<RestoreArgs>,
+) -> impl Responder {
+    let RestoreArgs { user_id } = args.into_inner();
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().restore(id.into_inner(), user_id, &audit_context);
+    http_util::respond(result)
+}
+
+/// Lists revisions of a post written by logged-in user
+#[get("/posts/{user_id}/{id}/revisions")]
+pub async fn get_post_revisions(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let revisions = PostService::new().get_revisions(id, user_id);
+    http_util::respond(revisions)
+}
+
+/// Restores a post to a revision
+#[post("/posts/{id}/revisions/{version}/restore")]
+pub async fn restore_post_revision(
+    req: HttpRequest,
+    web::Path((id, version)): web::Path<(u64, u32)>,
+    args: web::Json<RestoreArgs>,
+) -> impl Responder {
+    let RestoreArgs { user_id } = args.into_inner();
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostService::new().restore_revision(id, user_id, version, &audit_context);
+    http_util::respond(result)
+}
+
+/// Lists audit entries of posts written by logged-in user
+#[get("/posts/{user_id}/audit")]
+pub async fn get_post_audits(
+    user_id: web::Path<u64>,
+    args: web::Query<AuditListArgs>,
+) -> impl Responder {
+    let AuditListArgs {
+        since,
+        page,
+        per_page,
+    } = args.into_inner();
+    let audits = PostAuditService::new().get_list(user_id.into_inner(), &since, &page, &per_page);
+    http_util::respond(audits)
+}
+
+/// Lists audit entries of a post written by logged-in user
+#[get("/posts/{user_id}/{id}/audit")]
+pub async fn get_post_audit(
+    web::Path((user_id, id)): web::Path<(u64, u64)>,
+    args: web::Query<AuditListArgs>,
+) -> impl Responder {
+    let AuditListArgs { page, per_page, .. } = args.into_inner();
+    let audits = PostAuditService::new().get_list_by_post(user_id, id, &page, &per_page);
+    http_util::respond(audits)
 }
 
 /// Initializes the post routes.
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_post_audits);
+    cfg.service(get_post_audit);
+    cfg.service(get_trash);
+    cfg.service(get_calendar);
+    cfg.service(get_archive);
+    cfg.service(get_archive_year);
+    cfg.service(get_heatmap);
+    cfg.service(get_on_this_day);
+    cfg.service(get_changes);
+    cfg.service(get_mood_stats);
+    cfg.service(get_entry_stats);
+    cfg.service(get_key_versions);
     cfg.service(get_post);
     cfg.service(get_posts);
     cfg.service(get_summarized_posts);
     cfg.service(create_post);
+    // Operations in bulk exceed the default payload limit, so the route is configured with its own.
+    cfg.service(
+        web::resource("/posts/bulk")
+            .app_data(http_util::get_json_config().limit(MAX_BULK_PAYLOAD_SIZE))
+            .route(web::post().to(execute_bulk)),
+    );
+    cfg.service(delete_posts_by_date);
     cfg.service(delete_post);
     cfg.service(update_post);
+    cfg.service(autosave_post);
+    cfg.service(reorder_post);
+    cfg.service(publish_post);
+    cfg.service(favorite_post);
+    cfg.service(unfavorite_post);
+    cfg.service(lock_post);
+    cfg.service(unlock_post);
+    cfg.service(restore_post);
+    cfg.service(duplicate_post);
+    cfg.service(get_post_revisions);
+    cfg.service(restore_post_revision);
 }
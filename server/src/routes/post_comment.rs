@@ -0,0 +1,80 @@
+use actix_web::{delete, get, post, web, HttpRequest, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::routes::post_share::SHARE_PASSPHRASE_HEADER;
+use crate::services::post_comment::PostCommentService;
+use crate::utils::http_util;
+
+/// Arguments for `POST /posts/:id/comments` and `POST /shared/:token/comments` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateArgs {
+    pub user_id: u64,
+    pub content: String,
+}
+
+/// Reads the passphrase of a share from the request.
+fn get_passphrase(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get(SHARE_PASSPHRASE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+/// Lists comments of a post of logged-in user
+#[get("/posts/{user_id}/{id}/comments")]
+pub async fn get_comments(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let comments = PostCommentService::new().get_list(user_id, id);
+    http_util::respond(comments)
+}
+
+/// Comments on a post of logged-in user
+#[post("/posts/{id}/comments")]
+pub async fn create_comment(id: web::Path<u64>, args: web::Json<CreateArgs>) -> impl Responder {
+    let CreateArgs { user_id, content } = args.into_inner();
+    let result = PostCommentService::new().create(user_id, id.into_inner(), &content);
+    http_util::respond(result)
+}
+
+/// Lists comments of a post shared by a token for logged-in user
+#[get("/shared/{token}/comments/{user_id}")]
+pub async fn get_shared_comments(
+    req: HttpRequest,
+    web::Path((token, user_id)): web::Path<(String, u64)>,
+) -> impl Responder {
+    let comments =
+        PostCommentService::new().get_shared_list(user_id, &token, &get_passphrase(&req));
+    http_util::respond(comments)
+}
+
+/// Comments on a post shared by a token as logged-in user
+#[post("/shared/{token}/comments")]
+pub async fn create_shared_comment(
+    req: HttpRequest,
+    token: web::Path<String>,
+    args: web::Json<CreateArgs>,
+) -> impl Responder {
+    let CreateArgs { user_id, content } = args.into_inner();
+    let result = PostCommentService::new().create_shared(
+        user_id,
+        &token.into_inner(),
+        &get_passphrase(&req),
+        &content,
+    );
+    http_util::respond(result)
+}
+
+/// Deletes a comment written by logged-in user or on a post of logged-in user
+#[delete("/comments/{user_id}/{id}")]
+pub async fn delete_comment(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let result = PostCommentService::new().delete(id, user_id);
+    http_util::respond(result)
+}
+
+/// Initializes the post comment routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_comments);
+    cfg.service(create_comment);
+    cfg.service(get_shared_comments);
+    cfg.service(create_shared_comment);
+    cfg.service(delete_comment);
+}
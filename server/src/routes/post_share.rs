@@ -0,0 +1,173 @@
+use actix_web::http::StatusCode;
+use actix_web::{delete, get, post, web, HttpRequest, HttpResponse, Responder};
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::models::error::ServiceError;
+use crate::models::post_share::SharedPostDTO;
+use crate::services::post_share::PostShareService;
+use crate::utils::url_util::PublicUrl;
+use crate::utils::{html_util, http_util};
+
+/// Header carrying the passphrase of a share protected by a passphrase.
+///
+/// It is not a query parameter, so that the passphrase is not left in logs and histories.
+pub const SHARE_PASSPHRASE_HEADER: &str = "x-share-passphrase";
+
+/// Arguments for `POST /posts/:id/share` API.
+#[derive(Serialize, Deserialize)]
+pub struct ShareArgs {
+    pub user_id: u64,
+    /// RFC 3339 datetime when the share expires.
+    pub expires_at: Option<String>,
+    pub passphrase: Option<String>,
+    /// Plaintext copy of the title for readers without the key.
+    pub title: Option<String>,
+    /// Plaintext copy of the content in markdown for readers without the key.
+    pub content: Option<String>,
+}
+
+/// Form submitted from the page asking the passphrase of a share.
+#[derive(Serialize, Deserialize)]
+pub struct PassphraseForm {
+    pub passphrase: String,
+}
+
+/// Responds a post shared by a token
+#[get("/shared/{token}")]
+pub async fn get_shared_post(req: HttpRequest, token: web::Path<String>) -> impl Responder {
+    let passphrase = req
+        .headers()
+        .get(SHARE_PASSPHRASE_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let post = PostShareService::new().get(&token.into_inner(), &passphrase);
+    http_util::respond(post)
+}
+
+/// Renders a post shared by a token as an HTML page
+#[get("/shared/{token}/page")]
+pub async fn get_shared_post_page(token: web::Path<String>) -> impl Responder {
+    respond_shared_post_page(&token.into_inner(), &None)
+}
+
+/// Renders a post shared by a token with the passphrase submitted from the page
+#[post("/shared/{token}/page")]
+pub async fn post_shared_post_page(
+    token: web::Path<String>,
+    form: web::Form<PassphraseForm>,
+) -> impl Responder {
+    respond_shared_post_page(&token.into_inner(), &Some(form.into_inner().passphrase))
+}
+
+/// Renders the shared post, or a page asking the passphrase or explaining the error.
+fn respond_shared_post_page(token: &str, passphrase: &Option<String>) -> HttpResponse {
+    match PostShareService::new().get(token, passphrase) {
+        Ok(post) if post.is_plaintext => http_util::html(StatusCode::OK, render_post(&post)),
+        Ok(_) => {
+            let url = PublicUrl::from_env()
+                .expect("Invalid PUBLIC_BASE_URL")
+                .share_post_url(token);
+            let body = format!(
+                "<h1>Encrypted post</h1>\n<p>This post is encrypted. <a href=\"{}\">Open it in Darim</a> to read it.</p>",
+                html_util::escape(&url)
+            );
+            http_util::html(
+                StatusCode::OK,
+                html_util::render_page("Encrypted post", &body),
+            )
+        }
+        Err(ServiceError::Unauthorized) => {
+            let message = if passphrase.is_some() {
+                "<p>The passphrase is wrong.</p>\n"
+            } else {
+                ""
+            };
+            // The action is explicit, since the page is also rendered at `/shared/:token`.
+            let body = format!(
+                "<h1>Protected post</h1>\n{}<form method=\"post\" action=\"/shared/{}/page\">\n<input type=\"password\" name=\"passphrase\" placeholder=\"Passphrase\" autofocus required>\n<button type=\"submit\">Read</button>\n</form>",
+                message,
+                html_util::escape(token)
+            );
+            http_util::html(
+                StatusCode::UNAUTHORIZED,
+                html_util::render_page("Protected post", &body),
+            )
+        }
+        Err(ServiceError::NotFound(_)) => http_util::html(
+            StatusCode::NOT_FOUND,
+            html_util::render_page(
+                "Link no longer available",
+                "<h1>This link is no longer available</h1>\n<p>It may have expired or been revoked by the writer.</p>",
+            ),
+        ),
+        Err(_) => http_util::html(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            html_util::render_page(
+                "Something went wrong",
+                "<h1>Something went wrong</h1>\n<p>Please try again later.</p>",
+            ),
+        ),
+    }
+}
+
+/// Renders the plaintext copy of a shared post, whose content is markdown.
+fn render_post(post: &SharedPostDTO) -> String {
+    let date = DateTime::parse_from_rfc3339(&post.date)
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let body = format!(
+        "<h1>{}</h1>\n<p class=\"meta\">{}</p>\n<article>\n{}</article>",
+        html_util::escape(&post.title),
+        date,
+        html_util::render_markdown(&post.content)
+    );
+    html_util::render_page(&post.title, &body)
+}
+
+/// Shares a post by a new token
+#[post("/posts/{id}/share")]
+pub async fn share_post(
+    req: HttpRequest,
+    id: web::Path<u64>,
+    args: web::Json<ShareArgs>,
+) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let ShareArgs {
+        user_id,
+        expires_at,
+        passphrase,
+        title,
+        content,
+    } = args.into_inner();
+    let result = PostShareService::new().share(
+        id.into_inner(),
+        user_id,
+        &expires_at,
+        &passphrase,
+        &title,
+        &content,
+        &audit_context,
+    );
+    http_util::respond(result)
+}
+
+/// Revokes the share of a post
+#[delete("/posts/{user_id}/{id}/share")]
+pub async fn revoke_post_share(
+    req: HttpRequest,
+    web::Path((user_id, id)): web::Path<(u64, u64)>,
+) -> impl Responder {
+    let audit_context = http_util::get_audit_context(&req);
+    let result = PostShareService::new().revoke(id, user_id, &audit_context);
+    http_util::respond(result)
+}
+
+/// Initializes the post share routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_shared_post);
+    cfg.service(get_shared_post_page);
+    cfg.service(post_shared_post_page);
+    cfg.service(share_post);
+    cfg.service(revoke_post_share);
+}
@@ -0,0 +1,46 @@
+use actix_web::{delete, get, post, web, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::services::prompt::PromptService;
+use crate::utils::http_util;
+
+/// Arguments for `GET /prompts/today` API.
+#[derive(Serialize, Deserialize)]
+pub struct TodayArgs {
+    /// Local date of the client in `YYYY-MM-DD` format, which is today in UTC by default.
+    pub date: Option<String>,
+}
+
+/// Arguments for `POST /prompts/subscription` API.
+#[derive(Serialize, Deserialize)]
+pub struct SubscribeArgs {
+    pub user_id: u64,
+}
+
+/// Responds the writing prompt of today
+#[get("/prompts/today")]
+pub async fn get_today(args: web::Query<TodayArgs>) -> impl Responder {
+    let prompt = PromptService::new().get_today(&args.into_inner().date);
+    http_util::respond(prompt)
+}
+
+/// Subscribes logged-in user to daily prompt emails
+#[post("/prompts/subscription")]
+pub async fn subscribe(args: web::Json<SubscribeArgs>) -> impl Responder {
+    let result = PromptService::new().subscribe(args.into_inner().user_id);
+    http_util::respond(result)
+}
+
+/// Unsubscribes logged-in user from daily prompt emails
+#[delete("/prompts/subscription/{user_id}")]
+pub async fn unsubscribe(user_id: web::Path<u64>) -> impl Responder {
+    let result = PromptService::new().unsubscribe(user_id.into_inner());
+    http_util::respond(result)
+}
+
+/// Initializes the prompt routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_today);
+    cfg.service(subscribe);
+    cfg.service(unsubscribe);
+}
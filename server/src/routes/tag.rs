@@ -0,0 +1,65 @@
+use actix_web::{delete, get, patch, post, web, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::services::tag::TagService;
+use crate::utils::http_util;
+
+/// Arguments for `POST /tags` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateArgs {
+    pub user_id: u64,
+    pub name: String,
+}
+
+/// Arguments for `PATCH /tags/:id` API.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateArgs {
+    pub user_id: u64,
+    pub name: String,
+}
+
+/// Lists tags of logged-in user
+#[get("/tags/{user_id}")]
+pub async fn get_tags(user_id: web::Path<u64>) -> impl Responder {
+    let tags = TagService::new().get_list(user_id.into_inner());
+    http_util::respond(tags)
+}
+
+/// Lists tags of logged-in user with the number of posts with each tag
+#[get("/tags/{user_id}/cloud")]
+pub async fn get_tag_cloud(user_id: web::Path<u64>) -> impl Responder {
+    let tags = TagService::new().get_cloud(user_id.into_inner());
+    http_util::respond(tags)
+}
+
+/// Creates a new tag
+#[post("/tags")]
+pub async fn create_tag(args: web::Json<CreateArgs>) -> impl Responder {
+    let CreateArgs { user_id, name } = args.into_inner();
+    let result = TagService::new().create(user_id, &name);
+    http_util::respond(result)
+}
+
+/// Renames a tag
+#[patch("/tags/{id}")]
+pub async fn update_tag(id: web::Path<u64>, args: web::Json<UpdateArgs>) -> impl Responder {
+    let UpdateArgs { user_id, name } = args.into_inner();
+    let result = TagService::new().update(id.into_inner(), user_id, &name);
+    http_util::respond(result)
+}
+
+/// Deletes a tag
+#[delete("/tags/{user_id}/{id}")]
+pub async fn delete_tag(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let result = TagService::new().delete(id, user_id);
+    http_util::respond(result)
+}
+
+/// Initializes the tag routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_tags);
+    cfg.service(get_tag_cloud);
+    cfg.service(create_tag);
+    cfg.service(update_tag);
+    cfg.service(delete_tag);
+}
@@ -0,0 +1,33 @@
+use actix_web::{post, web, HttpResponse, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::services::telemetry::TelemetryService;
+use crate::utils::http_util;
+
+/// Arguments for `POST /telemetry` API.
+#[derive(Serialize, Deserialize)]
+pub struct RecordArgs {
+    pub user_id: u64,
+    pub event: String,
+}
+
+/// Counts a usage event sent by the client
+///
+/// It responds 204 No Content whether the event is counted or dropped.
+#[post("/telemetry")]
+pub async fn record_event(args: web::Json<RecordArgs>) -> impl Responder {
+    if !TelemetryService::is_enabled() {
+        return HttpResponse::NoContent().finish();
+    }
+
+    let RecordArgs { user_id, event } = args.into_inner();
+    match TelemetryService::new().record(user_id, &event) {
+        Ok(_) => HttpResponse::NoContent().finish(),
+        Err(error) => http_util::err(error),
+    }
+}
+
+/// Initializes the telemetry routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(record_event);
+}
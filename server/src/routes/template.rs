@@ -0,0 +1,73 @@
+use actix_web::{delete, get, patch, post, web, Responder};
+use serde::{Deserialize, Serialize};
+
+use crate::services::template::TemplateService;
+use crate::utils::http_util;
+
+/// Arguments for `POST /templates` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreateArgs {
+    pub user_id: u64,
+    pub name: String,
+    /// Title of posts created from the template, which may be empty.
+    #[serde(default)]
+    pub title: String,
+    pub content: String,
+}
+
+/// Arguments for `PATCH /templates/:id` API.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateArgs {
+    pub user_id: u64,
+    pub name: Option<String>,
+    pub title: Option<String>,
+    pub content: Option<String>,
+}
+
+/// Lists templates of logged-in user
+#[get("/templates/{user_id}")]
+pub async fn get_templates(user_id: web::Path<u64>) -> impl Responder {
+    let templates = TemplateService::new().get_list(user_id.into_inner());
+    http_util::respond(templates)
+}
+
+/// Creates a new template
+#[post("/templates")]
+pub async fn create_template(args: web::Json<CreateArgs>) -> impl Responder {
+    let CreateArgs {
+        user_id,
+        name,
+        title,
+        content,
+    } = args.into_inner();
+    let result = TemplateService::new().create(user_id, &name, &title, &content);
+    http_util::respond(result)
+}
+
+/// Updates a template
+#[patch("/templates/{id}")]
+pub async fn update_template(id: web::Path<u64>, args: web::Json<UpdateArgs>) -> impl Responder {
+    let UpdateArgs {
+        user_id,
+        name,
+        title,
+        content,
+    } = args.into_inner();
+    let result = TemplateService::new().update(id.into_inner(), user_id, &name, &title, &content);
+    http_util::respond(result)
+}
+
+/// Deletes a template
+#[delete("/templates/{user_id}/{id}")]
+pub async fn delete_template(web::Path((user_id, id)): web::Path<(u64, u64)>) -> impl Responder {
+    let result = TemplateService::new().delete(id, user_id);
+    http_util::respond(result)
+}
+
+/// Initializes the template routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(get_templates);
+    cfg.service(create_template);
+    cfg.service(update_template);
+    cfg.service(delete_template);
+}
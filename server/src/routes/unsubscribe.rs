@@ -0,0 +1,44 @@
+use actix_web::http::StatusCode;
+use actix_web::{post, web, Responder};
+
+use crate::models::error::ServiceError;
+use crate::services::unsubscribe::UnsubscribeService;
+use crate::utils::{html_util, http_util};
+
+/// Unsubscribes the user of the token in an email, and renders the result as an HTML page
+///
+/// A forged or expired token renders the same page whether its user exists or not.
+#[post("/unsubscribe/{token}")]
+pub async fn unsubscribe(token: web::Path<String>) -> impl Responder {
+    match UnsubscribeService::new().unsubscribe(&token.into_inner()) {
+        Ok(category) => {
+            let body = format!(
+                "<h1>You are unsubscribed</h1>\n<p>You will no longer receive {} by email.</p>",
+                category.description()
+            );
+            http_util::html(
+                StatusCode::OK,
+                html_util::render_page("Unsubscribed", &body),
+            )
+        }
+        Err(ServiceError::NotFound(_)) => http_util::html(
+            StatusCode::NOT_FOUND,
+            html_util::render_page(
+                "Link no longer available",
+                "<h1>This link is no longer available</h1>\n<p>It may be mistyped or have expired. You can still unsubscribe in the settings of Darim.</p>",
+            ),
+        ),
+        Err(_) => http_util::html(
+            StatusCode::INTERNAL_SERVER_ERROR,
+            html_util::render_page(
+                "Something went wrong",
+                "<h1>Something went wrong</h1>\n<p>Please try again later.</p>",
+            ),
+        ),
+    }
+}
+
+/// Initializes the unsubscribe routes.
+pub fn init_routes(cfg: &mut web::ServiceConfig) {
+    cfg.service(unsubscribe);
+}
@@ -1,9 +1,24 @@
-use actix_web::{delete, get, patch, post, web, Responder};
+use actix_web::{delete, get, patch, post, put, web, HttpRequest, Responder};
 use serde::{Deserialize, Serialize};
 
-use crate::models::user::UserDTO;
+use crate::models::error::ServiceError;
+use crate::models::user::KeyMetadata;
+use crate::models::user_settings::EditorPreferences;
+use crate::services::export::ExportService;
+use crate::services::login_history::LoginHistoryService;
+use crate::services::personal_access_token::PersonalAccessTokenService;
+use crate::services::post::PostService;
 use crate::services::user::UserService;
+use crate::services::user_settings::UserSettingsService;
 use crate::utils::http_util;
+use crate::utils::image_util::MAX_AVATAR_SIZE;
+use crate::utils::meta_util::WithMeta;
+
+/// Content type of account archives.
+const ARCHIVE_CONTENT_TYPE: &str = "application/gzip";
+
+/// File name of account archives.
+const ACCOUNT_ARCHIVE_FILENAME: &str = "darim-account.tar.gz";
 
 /// Arguments for `POST /users` API.
 #[derive(Serialize, Deserialize)]
@@ -19,7 +34,12 @@ pub struct CreateArgs {
 pub struct UpdateArgs {
     pub name: Option<String>,
     pub password: Option<String>,
-    pub avatar_url: Option<String>,
+}
+
+/// Arguments for `DELETE /users/:id` API.
+#[derive(Serialize, Deserialize)]
+pub struct DeleteArgs {
+    pub dry_run: Option<bool>,
 }
 
 /// Arguments for `POST /users/password` API.
@@ -35,7 +55,21 @@ pub struct ResetPasswordArgs {
 #[get("/users/{id}")]
 pub async fn get_user(id: web::Path<u64>) -> impl Responder {
     let user = UserService::new().get_one(id.into_inner());
-    http_util::get_response::<UserDTO>(user)
+    http_util::respond(user)
+}
+
+/// Responds the profile of a user with the fingerprint of the public key and the settings,
+/// with warnings such as posts in the trash permanently deleted soon
+#[get("/users/{id}/profile")]
+pub async fn get_profile(id: web::Path<u64>) -> impl Responder {
+    let id = id.into_inner();
+    let profile = UserService::new().get_profile(id).and_then(|profile| {
+        Ok(WithMeta {
+            data: profile,
+            meta: PostService::new().get_response_meta(id)?,
+        })
+    });
+    http_util::respond_with_meta(profile)
 }
 
 /// Creates a new user
@@ -50,26 +84,39 @@ pub async fn create_user(args: web::Json<CreateArgs>) -> impl Responder {
     let result = UserService::new()
         .create(&user_public_key, &token_key, &token_pin, &recaptcha_token)
         .await;
-    http_util::get_response::<bool>(result)
+    http_util::respond(result)
 }
 
 /// Deletes a user
 #[delete("/users/{id}")]
-pub async fn delete_user(id: web::Path<u64>) -> impl Responder {
-    let result = UserService::new().delete(id.into_inner());
-    http_util::get_response::<bool>(result)
+pub async fn delete_user(id: web::Path<u64>, args: web::Query<DeleteArgs>) -> impl Responder {
+    let dry_run = args.dry_run.unwrap_or(false);
+    let result = UserService::new().delete(id.into_inner(), dry_run);
+    http_util::respond(result)
 }
 
 /// Updates a user
 #[patch("/users/{id}")]
 pub async fn update_user(id: web::Path<u64>, args: web::Json<UpdateArgs>) -> impl Responder {
-    let UpdateArgs {
-        name,
-        password,
-        avatar_url,
-    } = args.into_inner();
-    let result = UserService::new().update(id.into_inner(), &name, &password, &avatar_url);
-    http_util::get_response::<bool>(result)
+    let UpdateArgs { name, password } = args.into_inner();
+    let result = UserService::new()
+        .update(id.into_inner(), &name, &password)
+        .await;
+    http_util::respond(result)
+}
+
+/// Replaces the avatar of a user with an image in the request body
+pub async fn upload_avatar(id: web::Path<u64>, file: web::Bytes) -> impl Responder {
+    let result = UserService::new().upload_avatar(id.into_inner(), &file);
+    http_util::respond(result)
+}
+
+/// Downloads the avatar of a user
+#[get("/users/{id}/avatar")]
+pub async fn download_avatar(req: HttpRequest, id: web::Path<u64>) -> impl Responder {
+    let result =
+        UserService::new().download_avatar(id.into_inner(), &http_util::get_cached_hashes(&req));
+    http_util::respond_file(result)
 }
 
 /// Resets the password.
@@ -81,16 +128,259 @@ pub async fn reset_password(args: web::Json<ResetPasswordArgs>) -> impl Responde
         temporary_password,
         new_password,
     } = args.into_inner();
-    let result =
-        UserService::new().reset_password(&email, &token_id, &temporary_password, &new_password);
-    http_util::get_response::<bool>(result)
+    let result = UserService::new()
+        .reset_password(&email, &token_id, &temporary_password, &new_password)
+        .await;
+    http_util::respond(result)
+}
+
+/// Responds key metadata of a user
+#[get("/users/{id}/key-metadata")]
+pub async fn get_key_metadata(id: web::Path<u64>) -> impl Responder {
+    let key_metadata = UserService::new().get_key_metadata(id.into_inner());
+    http_util::respond(key_metadata)
+}
+
+/// Replaces key metadata of a user
+#[put("/users/{id}/key-metadata")]
+pub async fn set_key_metadata(id: web::Path<u64>, args: web::Json<KeyMetadata>) -> impl Responder {
+    let result = UserService::new().set_key_metadata(id.into_inner(), &args.into_inner());
+    http_util::respond(result)
+}
+
+/// Arguments for `GET /users/:id/streak` API.
+#[derive(Serialize, Deserialize)]
+pub struct StreakArgs {
+    pub date: Option<String>,
+}
+
+/// Responds writing streaks of a user
+#[get("/users/{id}/streak")]
+pub async fn get_streak(id: web::Path<u64>, args: web::Query<StreakArgs>) -> impl Responder {
+    let streak = PostService::new().get_streak(id.into_inner(), &args.into_inner().date);
+    http_util::respond(streak)
+}
+
+/// Arguments for `PATCH /users/:id/goals` API.
+#[derive(Serialize, Deserialize)]
+pub struct WordGoalsArgs {
+    /// Number of words to write in a day, or 0 to remove the goal.
+    pub daily: Option<u32>,
+    /// Number of words to write in a month, or 0 to remove the goal.
+    pub monthly: Option<u32>,
+}
+
+/// Sets word goals of a user
+#[patch("/users/{id}/goals")]
+pub async fn set_word_goals(id: web::Path<u64>, args: web::Json<WordGoalsArgs>) -> impl Responder {
+    let WordGoalsArgs { daily, monthly } = args.into_inner();
+    let result = UserService::new().set_word_goals(id.into_inner(), &daily, &monthly);
+    http_util::respond(result)
+}
+
+/// Arguments for `GET /users/:id/goals/progress` API.
+#[derive(Serialize, Deserialize)]
+pub struct WordGoalProgressArgs {
+    /// The local date of the client in `YYYY-MM-DD` format.
+    pub date: Option<String>,
+}
+
+/// Responds progress of a user toward word goals
+#[get("/users/{id}/goals/progress")]
+pub async fn get_word_goal_progress(
+    id: web::Path<u64>,
+    args: web::Query<WordGoalProgressArgs>,
+) -> impl Responder {
+    let progress =
+        PostService::new().get_word_goal_progress(id.into_inner(), &args.into_inner().date);
+    http_util::respond(progress)
+}
+
+/// Arguments for `GET /users/:id/logins` API.
+#[derive(Serialize, Deserialize)]
+pub struct LoginHistoryArgs {
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// Responds login history of a user
+#[get("/users/{id}/logins")]
+pub async fn get_login_history(
+    id: web::Path<u64>,
+    args: web::Query<LoginHistoryArgs>,
+) -> impl Responder {
+    let LoginHistoryArgs { page, per_page } = args.into_inner();
+    let history = LoginHistoryService::new().get_list(id.into_inner(), &page, &per_page);
+    http_util::respond(history)
+}
+
+/// Responds personal access tokens of a user
+#[get("/users/{id}/tokens")]
+pub async fn get_personal_access_tokens(id: web::Path<u64>) -> impl Responder {
+    let tokens = PersonalAccessTokenService::new().get_list(id.into_inner());
+    http_util::respond(tokens)
+}
+
+/// Arguments for `POST /users/:id/tokens` API.
+#[derive(Serialize, Deserialize)]
+pub struct CreatePersonalAccessTokenArgs {
+    pub name: String,
+    /// `read` or `write`
+    pub scope: String,
+}
+
+/// Creates a personal access token of a user
+#[post("/users/{id}/tokens")]
+pub async fn create_personal_access_token(
+    id: web::Path<u64>,
+    args: web::Json<CreatePersonalAccessTokenArgs>,
+) -> impl Responder {
+    let result = PersonalAccessTokenService::new().create(id.into_inner(), &args.name, &args.scope);
+    http_util::respond(result)
+}
+
+/// Deletes a personal access token of a user
+#[delete("/users/{id}/tokens/{token_id}")]
+pub async fn delete_personal_access_token(
+    web::Path((id, token_id)): web::Path<(u64, u64)>,
+) -> impl Responder {
+    let result = PersonalAccessTokenService::new().delete(token_id, id);
+    http_util::respond(result)
+}
+
+/// Arguments for `POST /users/:id/email` API.
+#[derive(Serialize, Deserialize)]
+pub struct ChangeEmailArgs {
+    pub email: String,
+}
+
+/// Requests changing the email of a user, confirmed by a link emailed to the new address
+#[post("/users/{id}/email")]
+pub async fn change_email(id: web::Path<u64>, args: web::Json<ChangeEmailArgs>) -> impl Responder {
+    let result = UserService::new().request_email_change(id.into_inner(), &args.email);
+    http_util::respond(result)
+}
+
+/// Confirms a new email of a user with an email change token
+#[get("/users/email/confirm/{token}")]
+pub async fn confirm_email(token: web::Path<String>) -> impl Responder {
+    let result = UserService::new().confirm_email_change(&token.into_inner());
+    http_util::respond(result)
+}
+
+/// Responds settings of a user
+#[get("/users/{id}/settings")]
+pub async fn get_settings(id: web::Path<u64>) -> impl Responder {
+    let settings = UserSettingsService::new().get(id.into_inner());
+    http_util::respond(settings)
+}
+
+/// Arguments for `PATCH /users/:id/settings` API.
+#[derive(Serialize, Deserialize)]
+pub struct UpdateSettingsArgs {
+    /// IANA time zone such as `Asia/Seoul`.
+    pub timezone: Option<String>,
+    /// BCP 47 language tag such as `ko-KR`.
+    pub locale: Option<String>,
+    /// `monday`, `sunday`, or `saturday`
+    pub week_start_day: Option<String>,
+    pub editor_preferences: Option<EditorPreferences>,
+    pub default_journal_id: Option<u64>,
+    /// Whether the user allows anonymous usage counting.
+    pub telemetry_opt_in: Option<bool>,
+}
+
+/// Updates settings of a user
+#[patch("/users/{id}/settings")]
+pub async fn update_settings(
+    id: web::Path<u64>,
+    args: web::Json<UpdateSettingsArgs>,
+) -> impl Responder {
+    let UpdateSettingsArgs {
+        timezone,
+        locale,
+        week_start_day,
+        editor_preferences,
+        default_journal_id,
+        telemetry_opt_in,
+    } = args.into_inner();
+    let result = UserSettingsService::new().update(
+        id.into_inner(),
+        &timezone,
+        &locale,
+        &week_start_day,
+        &editor_preferences,
+        &default_journal_id,
+        &telemetry_opt_in,
+    );
+    http_util::respond(result)
+}
+
+/// Arguments for `POST /users/:id/export` API.
+#[derive(Serialize, Deserialize)]
+pub struct ExportAccountArgs {
+    /// `download` or `email`
+    pub delivery: Option<String>,
+}
+
+/// Streams an archive of a user, or emails a link downloading it
+#[post("/users/{id}/export")]
+pub async fn export_account(
+    id: web::Path<u64>,
+    args: web::Json<ExportAccountArgs>,
+) -> impl Responder {
+    match args.delivery.as_deref() {
+        None | Some("download") => {
+            let archive = ExportService::new().export_account(id.into_inner());
+            http_util::respond_stream(archive, ARCHIVE_CONTENT_TYPE, ACCOUNT_ARCHIVE_FILENAME)
+        }
+        Some("email") => {
+            let result = ExportService::new().request_account_export(id.into_inner());
+            http_util::respond(result)
+        }
+        Some(_) => http_util::err(ServiceError::InvalidArgument),
+    }
+}
+
+/// Streams an archive of a user with an account export token
+#[get("/users/export/{token}")]
+pub async fn download_account_export(token: web::Path<String>) -> impl Responder {
+    match ExportService::new().export_account_by_token(&token.into_inner()) {
+        Ok(archive) => {
+            http_util::respond_stream(archive, ARCHIVE_CONTENT_TYPE, ACCOUNT_ARCHIVE_FILENAME)
+        }
+        Err(error) => http_util::err(error),
+    }
 }
 
 /// Initializes the user routes.
 pub fn init_routes(cfg: &mut web::ServiceConfig) {
     cfg.service(get_user);
+    cfg.service(get_profile);
     cfg.service(create_user);
     cfg.service(delete_user);
     cfg.service(update_user);
+    // An image exceeds the default payload limit, so the route is configured with its own.
+    cfg.service(
+        web::resource("/users/{id}/avatar")
+            .app_data(web::PayloadConfig::new(MAX_AVATAR_SIZE))
+            .route(web::post().to(upload_avatar)),
+    );
+    cfg.service(download_avatar);
+    cfg.service(get_key_metadata);
+    cfg.service(set_key_metadata);
+    cfg.service(get_streak);
+    cfg.service(set_word_goals);
+    cfg.service(get_word_goal_progress);
     cfg.service(reset_password);
+    cfg.service(get_login_history);
+    cfg.service(get_personal_access_tokens);
+    cfg.service(create_personal_access_token);
+    cfg.service(delete_personal_access_token);
+    cfg.service(change_email);
+    cfg.service(confirm_email);
+    cfg.service(get_settings);
+    cfg.service(update_settings);
+    cfg.service(export_account);
+    cfg.service(download_account_export);
 }
@@ -1,3 +1,222 @@
+table! {
+    admin_audits (id) {
+        id -> Unsigned<Bigint>,
+        admin_id -> Nullable<Unsigned<Bigint>>,
+        user_id -> Unsigned<Bigint>,
+        action -> Varchar,
+        method -> Nullable<Varchar>,
+        path -> Nullable<Varchar>,
+        ip -> Nullable<Varchar>,
+        user_agent -> Nullable<Varchar>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    attachment_blobs (hash) {
+        hash -> Char,
+        size -> Unsigned<Bigint>,
+        mime_type -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    attachments (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        post_id -> Unsigned<Bigint>,
+        blob_hash -> Char,
+        filename -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    calendar_feeds (user_id) {
+        user_id -> Unsigned<Bigint>,
+        token -> Char,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    email_jobs (id) {
+        id -> Unsigned<Bigint>,
+        recipient -> Varchar,
+        subject -> Varchar,
+        body -> Text,
+        token_key -> Nullable<Varchar>,
+        unsubscribe_url -> Nullable<Varchar>,
+        attempts -> Unsigned<Integer>,
+        next_attempt_at -> Datetime,
+        last_error -> Nullable<Varchar>,
+        failed_at -> Nullable<Datetime>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    export_jobs (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        format -> Varchar,
+        include_trash -> Bool,
+        status -> Varchar,
+        progress -> Unsigned<Integer>,
+        total -> Unsigned<Integer>,
+        size -> Nullable<Unsigned<Bigint>>,
+        download_token -> Nullable<Char>,
+        expires_at -> Datetime,
+        created_at -> Datetime,
+        finished_at -> Nullable<Datetime>,
+    }
+}
+
+table! {
+    invites (id) {
+        id -> Unsigned<Bigint>,
+        code -> Char,
+        created_by -> Nullable<Unsigned<Bigint>>,
+        redeemed_by -> Nullable<Unsigned<Bigint>>,
+        redeemed_at -> Nullable<Datetime>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    journals (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        name -> Text,
+        is_default -> Bool,
+        created_at -> Datetime,
+        updated_at -> Nullable<Datetime>,
+    }
+}
+
+table! {
+    login_history (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        ip -> Nullable<Varchar>,
+        country -> Nullable<Char>,
+        user_agent -> Nullable<Varchar>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    login_sessions (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        session_id_hash -> Char,
+        user_agent -> Nullable<Varchar>,
+        ip -> Nullable<Varchar>,
+        created_at -> Datetime,
+        last_seen_at -> Datetime,
+        refresh_token_hash -> Nullable<Char>,
+        device_id_hash -> Nullable<Char>,
+        label -> Nullable<Varchar>,
+        expires_at -> Datetime,
+        impersonator_id -> Nullable<Unsigned<Bigint>>,
+        allow_writes -> Bool,
+    }
+}
+
+table! {
+    oauth_accounts (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        provider -> Varchar,
+        subject -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    personal_access_tokens (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        name -> Varchar,
+        token_hash -> Char,
+        scope -> Varchar,
+        created_at -> Datetime,
+        last_used_at -> Nullable<Datetime>,
+    }
+}
+
+table! {
+    post_audits (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        post_id -> Unsigned<Bigint>,
+        action -> Varchar,
+        ip -> Nullable<Varchar>,
+        user_agent -> Nullable<Varchar>,
+        session_id -> Nullable<Varchar>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    post_comments (id) {
+        id -> Unsigned<Bigint>,
+        post_id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        content -> Text,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    post_revisions (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        post_id -> Unsigned<Bigint>,
+        version -> Unsigned<Integer>,
+        title -> Text,
+        content -> Text,
+        date -> Datetime,
+        date_offset -> Nullable<Integer>,
+        created_at -> Datetime,
+        is_encrypted -> Bool,
+        encryption_scheme -> Nullable<Varchar>,
+        encryption_nonce -> Nullable<Varchar>,
+        key_version -> Nullable<Unsigned<Integer>>,
+    }
+}
+
+table! {
+    post_tombstones (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        post_id -> Unsigned<Bigint>,
+        deleted_at -> Datetime,
+    }
+}
+
+table! {
+    post_shares (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        post_id -> Unsigned<Bigint>,
+        token -> Char,
+        passphrase -> Nullable<Varchar>,
+        expires_at -> Nullable<Datetime>,
+        created_at -> Datetime,
+        title -> Nullable<Text>,
+        content -> Nullable<Text>,
+    }
+}
+
+table! {
+    post_tags (post_id, tag_id) {
+        post_id -> Unsigned<Bigint>,
+        tag_id -> Unsigned<Bigint>,
+    }
+}
+
 table! {
     posts (id) {
         id -> Unsigned<Bigint>,
@@ -5,11 +224,113 @@ table! {
         title -> Text,
         content -> Text,
         date -> Datetime,
+        date_offset -> Nullable<Integer>,
+        intra_day_order -> Unsigned<Smallint>,
+        created_at -> Datetime,
+        updated_at -> Nullable<Datetime>,
+        version -> Unsigned<Integer>,
+        deleted_at -> Nullable<Datetime>,
+        status -> Varchar,
+        autosave_started_at -> Nullable<Datetime>,
+        changed_at -> Datetime,
+        is_favorite -> Bool,
+        mood -> Nullable<Unsigned<Tinyint>>,
+        weather -> Nullable<Varchar>,
+        latitude -> Nullable<Double>,
+        longitude -> Nullable<Double>,
+        place_name -> Nullable<Varchar>,
+        journal_id -> Unsigned<Bigint>,
+        word_count -> Nullable<Unsigned<Integer>>,
+        is_locked -> Bool,
+        is_encrypted -> Bool,
+        encryption_scheme -> Nullable<Varchar>,
+        encryption_nonce -> Nullable<Varchar>,
+        key_version -> Nullable<Unsigned<Integer>>,
+    }
+}
+
+table! {
+    prompt_subscriptions (user_id) {
+        user_id -> Unsigned<Bigint>,
+        last_sent_on -> Nullable<Date>,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    prompts (id) {
+        id -> Unsigned<Bigint>,
+        text -> Varchar,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    rotated_refresh_tokens (refresh_token_hash) {
+        refresh_token_hash -> Char,
+        login_session_id -> Unsigned<Bigint>,
+        expires_at -> Datetime,
+    }
+}
+
+table! {
+    scheduled_tasks (name) {
+        name -> Varchar,
+        last_run_at -> Nullable<Datetime>,
+        last_status -> Nullable<Varchar>,
+        locked_until -> Nullable<Datetime>,
+    }
+}
+
+table! {
+    tags (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        name -> Text,
+        created_at -> Datetime,
+        updated_at -> Nullable<Datetime>,
+    }
+}
+
+table! {
+    telemetry_events (event, date) {
+        event -> Varchar,
+        date -> Date,
+        count -> Unsigned<Bigint>,
+    }
+}
+
+table! {
+    templates (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        name -> Text,
+        title -> Text,
+        content -> Text,
         created_at -> Datetime,
         updated_at -> Nullable<Datetime>,
     }
 }
 
+table! {
+    two_factor_recovery_codes (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        code_hash -> Char,
+        created_at -> Datetime,
+    }
+}
+
+table! {
+    two_factors (user_id) {
+        user_id -> Unsigned<Bigint>,
+        secret -> Varchar,
+        enabled_at -> Nullable<Datetime>,
+        last_used_step -> Nullable<Unsigned<Bigint>>,
+        created_at -> Datetime,
+    }
+}
+
 table! {
     users (id) {
         id -> Unsigned<Bigint>,
@@ -19,6 +340,11 @@ table! {
         avatar_url -> Nullable<Varchar>,
         created_at -> Datetime,
         updated_at -> Nullable<Datetime>,
+        key_metadata -> Nullable<Text>,
+        daily_word_goal -> Nullable<Unsigned<Integer>>,
+        monthly_word_goal -> Nullable<Unsigned<Integer>>,
+        role -> Varchar,
+        suspended_at -> Nullable<Datetime>,
     }
 }
 
@@ -32,7 +358,86 @@ table! {
     }
 }
 
+table! {
+    user_settings (user_id) {
+        user_id -> Unsigned<Bigint>,
+        timezone -> Varchar,
+        locale -> Varchar,
+        week_start_day -> Varchar,
+        editor_preferences -> Nullable<Text>,
+        telemetry_opt_in -> Bool,
+        created_at -> Datetime,
+        updated_at -> Nullable<Datetime>,
+    }
+}
+
+table! {
+    webauthn_credentials (id) {
+        id -> Unsigned<Bigint>,
+        user_id -> Unsigned<Bigint>,
+        credential_id_hash -> Char,
+        credential -> Text,
+        created_at -> Datetime,
+        last_used_at -> Nullable<Datetime>,
+    }
+}
+
+joinable!(attachments -> attachment_blobs (blob_hash));
+joinable!(attachments -> posts (post_id));
+joinable!(attachments -> users (user_id));
+joinable!(calendar_feeds -> users (user_id));
+joinable!(export_jobs -> users (user_id));
+joinable!(journals -> users (user_id));
+joinable!(login_history -> users (user_id));
+joinable!(login_sessions -> users (user_id));
+joinable!(oauth_accounts -> users (user_id));
+joinable!(personal_access_tokens -> users (user_id));
+joinable!(post_audits -> users (user_id));
+joinable!(post_comments -> posts (post_id));
+joinable!(post_comments -> users (user_id));
+joinable!(post_revisions -> posts (post_id));
+joinable!(post_shares -> posts (post_id));
+joinable!(post_shares -> users (user_id));
+joinable!(post_tags -> posts (post_id));
+joinable!(post_tags -> tags (tag_id));
+joinable!(posts -> journals (journal_id));
 joinable!(posts -> users (user_id));
+joinable!(prompt_subscriptions -> users (user_id));
+joinable!(tags -> users (user_id));
+joinable!(templates -> users (user_id));
+joinable!(two_factor_recovery_codes -> users (user_id));
+joinable!(two_factors -> users (user_id));
 joinable!(user_keys -> users (user_id));
+joinable!(user_settings -> users (user_id));
+joinable!(webauthn_credentials -> users (user_id));
 
-allow_tables_to_appear_in_same_query!(posts, users,);
+allow_tables_to_appear_in_same_query!(
+    admin_audits,
+    attachment_blobs,
+    attachments,
+    calendar_feeds,
+    export_jobs,
+    invites,
+    journals,
+    login_history,
+    login_sessions,
+    oauth_accounts,
+    personal_access_tokens,
+    post_audits,
+    post_comments,
+    post_revisions,
+    post_shares,
+    post_tags,
+    post_tombstones,
+    posts,
+    prompt_subscriptions,
+    prompts,
+    rotated_refresh_tokens,
+    tags,
+    templates,
+    two_factor_recovery_codes,
+    two_factors,
+    user_settings,
+    users,
+    webauthn_credentials,
+);
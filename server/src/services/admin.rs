@@ -0,0 +1,998 @@
+use chrono::Duration;
+use std::sync::Arc;
+
+use crate::models::admin::*;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::login_session::Impersonation;
+use crate::models::post_audit::AuditContext;
+use crate::models::user::*;
+use crate::services::login_session::LoginSessionService;
+use crate::services::user::UserService;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::csv_util;
+use crate::utils::pagination_util::{
+    get_offset_and_limit, Page, PageMeta, DEFAULT_PER_PAGE, MAX_PER_PAGE,
+};
+
+/// Default number of days counted by `get_stats`.
+const DEFAULT_STATS_DAYS: u32 = 30;
+
+/// Maximum number of days counted by `get_stats`.
+const MAX_STATS_DAYS: u32 = 365;
+
+/// Number of entries of the audit log read at once while it is streamed as CSV.
+const AUDIT_CSV_BATCH_SIZE: i64 = 500;
+
+/// Columns of the audit log streamed as CSV.
+const AUDIT_CSV_COLUMNS: [&str; 9] = [
+    "created_at",
+    "event",
+    "user_id",
+    "admin_id",
+    "request",
+    "ip",
+    "user_agent",
+    "source",
+    "id",
+];
+
+pub struct AdminService {
+    admin_repository: Option<AdminRepository>,
+    user_repository: Option<UserRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl AdminService {
+    pub fn new() -> Self {
+        Self {
+            admin_repository: None,
+            user_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn admin_repository(&mut self, new_repository: Option<AdminRepository>) -> &AdminRepository {
+        match new_repository {
+            Some(_) => {
+                self.admin_repository = new_repository;
+                self.admin_repository.as_ref().unwrap()
+            }
+            None => self.admin_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_repository = new_repository;
+                self.user_repository.as_ref().unwrap()
+            }
+            None => self.user_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Checks the user forwarded by the api gateway as the one requesting an admin API is still
+    /// an admin, and returns the id.
+    ///
+    /// The api gateway authorizes admins as well, which this guards against a session
+    /// whose role is stale or a route which forgot to require the permission.
+    pub fn authorize(&mut self, admin_id: Option<u64>) -> Result<u64, ServiceError> {
+        let admin_id = match admin_id {
+            Some(admin_id) => admin_id,
+            None => return Err(get_service_error(ServiceError::Unauthorized)),
+        };
+
+        let fallback_repository =
+            some_if_true!(self.user_repository.is_none() => UserRepository::new());
+        match self
+            .user_repository(fallback_repository)
+            .find_by_id(admin_id)
+        {
+            Ok(user) if UserRole::parse(&user.role) == UserRole::Admin => Ok(admin_id),
+            Ok(_) | Err(ServiceError::NotFound(_)) => {
+                Err(get_service_error(ServiceError::Unauthorized))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    fn create_audit(
+        &mut self,
+        admin_id: Option<u64>,
+        user_id: u64,
+        action: AdminAuditAction,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.admin_repository.is_none() => AdminRepository::new());
+        self.admin_repository(fallback_repository)
+            .create_audit(admin_id, user_id, action, context)
+    }
+
+    /// Finds a user who is not an admin, since admins are not managed by other admins.
+    fn find_managed_user(&mut self, id: u64) -> Result<User, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository).find_by_id(id)?
+        };
+
+        if UserRole::parse(&user.role) == UserRole::Admin {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+        Ok(user)
+    }
+
+    /// Finds users whose names or emails contain `keyword` with the total count,
+    /// the most recent signup first.
+    pub fn get_users(
+        &mut self,
+        keyword: &Option<String>,
+        page: &Option<u32>,
+        per_page: &Option<u32>,
+    ) -> Result<Page<AdminUserDTO>, ServiceError> {
+        let (offset, limit) = get_offset_and_limit(page, per_page)?;
+        let keyword = keyword
+            .as_ref()
+            .map(|keyword| keyword.trim().to_string())
+            .filter(|keyword| !keyword.is_empty());
+
+        let (user_list, total_count) = {
+            let fallback_repository =
+                some_if_true!(self.admin_repository.is_none() => AdminRepository::new());
+            let admin_repository = self.admin_repository(fallback_repository);
+            (
+                admin_repository.find_users(&keyword, offset, limit)?,
+                admin_repository.count_users(&keyword)?,
+            )
+        };
+
+        Ok(Page {
+            items: user_list
+                .into_iter()
+                .map(|user| AdminUserDTO {
+                    id: user.id,
+                    name: user.name,
+                    email: user.email,
+                    role: user.role,
+                    created_at: user.created_at,
+                    suspended_at: user.suspended_at,
+                })
+                .collect(),
+            meta: PageMeta {
+                total_count,
+                page: Some(page.unwrap_or(1)),
+                per_page: Some(per_page.unwrap_or(DEFAULT_PER_PAGE)),
+            },
+        })
+    }
+
+    /// Suspends a user, who is signed out of every device and cannot sign in until it is lifted.
+    pub fn suspend(
+        &mut self,
+        admin_id: u64,
+        id: u64,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        self.find_managed_user(id)?;
+
+        let now = self.clock.now().naive_utc();
+        let fallback_repository =
+            some_if_true!(self.admin_repository.is_none() => AdminRepository::new());
+        self.admin_repository(fallback_repository)
+            .update_suspended_at(id, &Some(now))?;
+        self.create_audit(Some(admin_id), id, AdminAuditAction::Suspend, context)?;
+
+        LoginSessionService::new().delete_all(id)
+    }
+
+    /// Lifts the suspension of a user.
+    pub fn unsuspend(
+        &mut self,
+        admin_id: u64,
+        id: u64,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        self.find_managed_user(id)?;
+
+        let fallback_repository =
+            some_if_true!(self.admin_repository.is_none() => AdminRepository::new());
+        self.admin_repository(fallback_repository)
+            .update_suspended_at(id, &None)?;
+        self.create_audit(Some(admin_id), id, AdminAuditAction::Unsuspend, context)
+    }
+
+    /// Deletes a user with every data of the user, in the same way as the user deletes the account.
+    pub fn delete(
+        &mut self,
+        admin_id: u64,
+        id: u64,
+        context: &AuditContext,
+    ) -> Result<UserDeletionDTO, ServiceError> {
+        self.find_managed_user(id)?;
+        let deletion = UserService::new().delete(id, false)?;
+        self.create_audit(Some(admin_id), id, AdminAuditAction::Delete, context)?;
+        Ok(deletion)
+    }
+
+    /// Issues a login session of a user to an admin impersonating the user, with the session id
+    /// in `context`.
+    ///
+    /// The session is read-only unless `allow_writes` is true, and it expires in 30 minutes.
+    /// Admins cannot be impersonated.
+    pub fn impersonate(
+        &mut self,
+        admin_id: u64,
+        id: u64,
+        allow_writes: bool,
+        context: &AuditContext,
+    ) -> Result<ImpersonationDTO, ServiceError> {
+        self.find_managed_user(id)?;
+
+        let impersonation = Impersonation {
+            impersonator_id: admin_id,
+            allow_writes,
+        };
+        let expires_at =
+            LoginSessionService::new().create_impersonated(id, &impersonation, context)?;
+        self.create_audit(Some(admin_id), id, AdminAuditAction::Impersonate, context)?;
+
+        Ok(ImpersonationDTO {
+            user_id: id,
+            allow_writes,
+            expires_at,
+        })
+    }
+
+    /// Ends the impersonation of the login session of the session id in `context`.
+    ///
+    /// It is requested with the impersonated session, rather than as the admin.
+    pub fn stop_impersonation(&mut self, context: &AuditContext) -> Result<bool, ServiceError> {
+        let session_id = match &context.session_id {
+            Some(session_id) => session_id,
+            None => return Err(get_service_error(ServiceError::Unauthorized)),
+        };
+
+        let session = LoginSessionService::new().delete_impersonated(session_id)?;
+        self.create_audit(
+            session.impersonator_id,
+            session.user_id,
+            AdminAuditAction::StopImpersonation,
+            context,
+        )
+    }
+
+    /// Audits a request made by an admin impersonating a user, with both of them.
+    pub fn audit_impersonated_request(
+        &mut self,
+        admin_id: u64,
+        user_id: u64,
+        method: &str,
+        path: &str,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.admin_repository.is_none() => AdminRepository::new());
+        self.admin_repository(fallback_repository)
+            .create_request_audit(admin_id, user_id, method, path, context)
+    }
+
+    /// Counts users and posts, and signups and posts on each day of the last `days` days.
+    pub fn get_stats(&mut self, days: &Option<u32>) -> Result<AdminStatsDTO, ServiceError> {
+        let days = days.unwrap_or(DEFAULT_STATS_DAYS);
+        if days == 0 || days > MAX_STATS_DAYS {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let from = (self.clock.now().date() - Duration::days(i64::from(days) - 1))
+            .naive_utc()
+            .and_hms(0, 0, 0);
+
+        let fallback_repository =
+            some_if_true!(self.admin_repository.is_none() => AdminRepository::new());
+        let admin_repository = self.admin_repository(fallback_repository);
+        Ok(AdminStatsDTO {
+            user_count: admin_repository.count_users(&None)?,
+            suspended_user_count: admin_repository.count_suspended_users()?,
+            post_count: admin_repository.count_posts()?,
+            signups: admin_repository.count_signups_by_date(&from)?,
+            posts: admin_repository.count_posts_by_date(&from)?,
+        })
+    }
+
+    /// Checks the conditions of the audit log, and audits reading it by the admin.
+    fn audit_read(
+        &mut self,
+        admin_id: u64,
+        filter: &AuditFilter,
+        action: AdminAuditAction,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        if let (Some(from), Some(to)) = (filter.from, filter.to) {
+            if from > to {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        let user_id = filter.user_id.unwrap_or(admin_id);
+        self.create_audit(Some(admin_id), user_id, action, context)
+    }
+
+    fn to_audit_event_dto(event: AuditEvent) -> AuditEventDTO {
+        AuditEventDTO {
+            source: event.source,
+            id: event.id,
+            user_id: event.user_id,
+            admin_id: event.admin_id,
+            event: event.event,
+            request: event.request,
+            ip: event.ip,
+            user_agent: event.user_agent,
+            created_at: event.created_at,
+        }
+    }
+
+    /// Finds a page of the audit log meeting `filter` after `cursor`, the most recent first.
+    ///
+    /// The audit log contains admin actions, logins, and post actions. Reading it is audited
+    /// as well, before the page is read.
+    pub fn get_audit(
+        &mut self,
+        admin_id: u64,
+        filter: &AuditFilter,
+        cursor: &Option<String>,
+        per_page: &Option<u32>,
+        context: &AuditContext,
+    ) -> Result<AuditPageDTO, ServiceError> {
+        let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+        if per_page == 0 || per_page > MAX_PER_PAGE {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+        let after = match cursor {
+            Some(cursor) => Some(
+                AuditCursor::parse(cursor)
+                    .ok_or_else(|| get_service_error(ServiceError::InvalidArgument))?,
+            ),
+            None => None,
+        };
+
+        self.audit_read(admin_id, filter, AdminAuditAction::ReadAudit, context)?;
+
+        let mut event_list = {
+            let fallback_repository =
+                some_if_true!(self.admin_repository.is_none() => AdminRepository::new());
+            self.admin_repository(fallback_repository)
+                .find_audit_events(filter, &after, i64::from(per_page) + 1)?
+        };
+
+        let next_cursor = if event_list.len() > per_page as usize {
+            event_list.truncate(per_page as usize);
+            event_list
+                .last()
+                .map(|event| AuditCursor::of(event).to_string())
+        } else {
+            None
+        };
+
+        Ok(AuditPageDTO {
+            events: event_list
+                .into_iter()
+                .map(Self::to_audit_event_dto)
+                .collect(),
+            next_cursor,
+        })
+    }
+
+    /// Returns the audit log meeting `filter` as CSV, which is read batch by batch
+    /// as it is iterated.
+    ///
+    /// Exporting it is audited before anything is read.
+    pub fn export_audit(
+        mut self,
+        admin_id: u64,
+        filter: AuditFilter,
+        context: &AuditContext,
+    ) -> Result<AuditCsv, ServiceError> {
+        self.audit_read(admin_id, &filter, AdminAuditAction::ExportAudit, context)?;
+
+        Ok(AuditCsv {
+            admin_repository: self
+                .admin_repository
+                .take()
+                .unwrap_or_else(AdminRepository::new),
+            filter,
+            after: None,
+            is_started: false,
+            is_done: false,
+        })
+    }
+
+    /// Sets the role of the user specified by email, which is audited as taken by the server.
+    pub fn set_role(&mut self, email: &str, role: UserRole) -> Result<bool, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_email(email)?
+        };
+
+        let fallback_repository =
+            some_if_true!(self.admin_repository.is_none() => AdminRepository::new());
+        self.admin_repository(fallback_repository)
+            .update_role(email, role.as_str())?;
+
+        let action = match role {
+            UserRole::Admin => AdminAuditAction::GrantAdmin,
+            UserRole::User => AdminAuditAction::RevokeAdmin,
+        };
+        self.create_audit(None, user.id, action, &AuditContext::default())
+    }
+}
+
+impl Default for AdminService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The audit log as CSV, of which entries are read as it is iterated.
+///
+/// Each chunk contains rows of a batch of `AUDIT_CSV_BATCH_SIZE` entries, and the first one
+/// starts with the header row, so that the whole log is never held in memory.
+pub struct AuditCsv {
+    admin_repository: AdminRepository,
+    filter: AuditFilter,
+    after: Option<AuditCursor>,
+    is_started: bool,
+    is_done: bool,
+}
+
+impl AuditCsv {
+    fn to_row(event: &AuditEvent) -> String {
+        csv_util::to_row(&[
+            &event.created_at.format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+            &event.event,
+            &event.user_id.to_string(),
+            &event.admin_id.map(|id| id.to_string()).unwrap_or_default(),
+            event.request.as_deref().unwrap_or_default(),
+            event.ip.as_deref().unwrap_or_default(),
+            event.user_agent.as_deref().unwrap_or_default(),
+            &event.source,
+            &event.id.to_string(),
+        ])
+    }
+}
+
+impl Iterator for AuditCsv {
+    type Item = Result<Vec<u8>, ServiceError>;
+
+    /// Returns rows of the next batch, or `None` after the last batch.
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.is_done {
+            return None;
+        }
+
+        let mut chunk = String::new();
+        if !self.is_started {
+            self.is_started = true;
+            chunk.push_str(&csv_util::to_row(&AUDIT_CSV_COLUMNS));
+        }
+
+        let event_list = match self.admin_repository.find_audit_events(
+            &self.filter,
+            &self.after,
+            AUDIT_CSV_BATCH_SIZE,
+        ) {
+            Ok(event_list) => event_list,
+            Err(error) => {
+                self.is_done = true;
+                return Some(Err(error));
+            }
+        };
+
+        if (event_list.len() as i64) < AUDIT_CSV_BATCH_SIZE {
+            self.is_done = true;
+        }
+        if event_list.is_empty() && chunk.is_empty() {
+            return None;
+        }
+
+        for event in &event_list {
+            chunk.push_str(&Self::to_row(event));
+        }
+        self.after = event_list.last().map(AuditCursor::of);
+
+        Some(Ok(chunk.into_bytes()))
+    }
+}
+
+#[cfg(test)]
+use crate::models::admin::MockAdminRepositoryTrait as AdminRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime, TimeZone, Utc};
+    use mockall::predicate::*;
+    use mockall::Sequence;
+
+    use super::*;
+    use crate::models::admin::MockAdminRepositoryTrait;
+    use crate::models::user::MockUserRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl AdminService {
+        pub fn new_with_repository(
+            admin_repository: AdminRepository,
+            user_repository: UserRepository,
+        ) -> Self {
+            Self {
+                admin_repository: Some(admin_repository),
+                user_repository: Some(user_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    fn user(id: u64, role: &str) -> User {
+        User {
+            id,
+            name: String::from("park"),
+            email: String::from("park@email.com"),
+            password: String::from("password"),
+            avatar_url: None,
+            created_at: Utc::now().naive_utc(),
+            updated_at: None,
+            key_metadata: None,
+            daily_word_goal: None,
+            monthly_word_goal: None,
+            role: role.to_string(),
+            suspended_at: None,
+        }
+    }
+
+    #[test]
+    fn test_get_users() {
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        let keyword = Some(String::from("park"));
+
+        mocked_admin_repository
+            .expect_find_users()
+            .with(eq(keyword.clone()), eq(20), eq(10))
+            .times(1)
+            .returning(|_, _, _| Ok(vec![user(5, "user")]));
+        mocked_admin_repository
+            .expect_count_users()
+            .with(eq(keyword))
+            .times(1)
+            .returning(|_| Ok(21));
+
+        let page = AdminService::new_with_repository(
+            mocked_admin_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .get_users(&Some(String::from(" park ")), &Some(3), &Some(10))
+        .unwrap();
+        assert_eq!(page.items.len(), 1);
+        assert_eq!(page.items[0].role, "user");
+        assert_eq!(page.meta.total_count, 21);
+    }
+
+    #[test]
+    fn test_suspend_admin() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(1))
+            .times(1)
+            .returning(|id| Ok(user(id, "admin")));
+
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        mocked_admin_repository.expect_update_suspended_at().never();
+
+        let result =
+            AdminService::new_with_repository(mocked_admin_repository, mocked_user_repository)
+                .suspend(2, 1, &AuditContext::default());
+        assert!(matches!(result, Err(ServiceError::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_impersonate_admin() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(1))
+            .times(1)
+            .returning(|id| Ok(user(id, "admin")));
+
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        mocked_admin_repository.expect_create_audit().never();
+
+        let context = AuditContext {
+            session_id: Some(String::from("a1b2")),
+            ..AuditContext::default()
+        };
+        let result =
+            AdminService::new_with_repository(mocked_admin_repository, mocked_user_repository)
+                .impersonate(2, 1, false, &context);
+        assert!(matches!(result, Err(ServiceError::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_audit_impersonated_request() {
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        mocked_admin_repository
+            .expect_create_request_audit()
+            .with(
+                eq(1),
+                eq(5),
+                function(|method: &str| method == "GET"),
+                function(|path: &str| path == "/posts?page=2"),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _| Ok(true));
+
+        assert!(AdminService::new_with_repository(
+            mocked_admin_repository,
+            MockUserRepositoryTrait::new()
+        )
+        .audit_impersonated_request(1, 5, "GET", "/posts?page=2", &AuditContext::default())
+        .unwrap());
+    }
+
+    #[test]
+    fn test_unsuspend() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|id| Ok(user(id, "user")));
+
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        mocked_admin_repository
+            .expect_update_suspended_at()
+            .with(eq(5), eq(None))
+            .times(1)
+            .returning(|_, _| Ok(true));
+        mocked_admin_repository
+            .expect_create_audit()
+            .with(
+                eq(Some(1)),
+                eq(5),
+                eq(AdminAuditAction::Unsuspend),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+
+        let context = AuditContext {
+            ip: Some(String::from("127.0.0.1")),
+            ..AuditContext::default()
+        };
+        assert!(
+            AdminService::new_with_repository(mocked_admin_repository, mocked_user_repository)
+                .unsuspend(1, 5, &context)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_authorize() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(1))
+            .times(1)
+            .returning(|id| Ok(user(id, "admin")));
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|id| Ok(user(id, "user")));
+
+        let mut admin_service = AdminService::new_with_repository(
+            MockAdminRepositoryTrait::new(),
+            mocked_user_repository,
+        );
+        assert_eq!(admin_service.authorize(Some(1)).unwrap(), 1);
+        assert!(matches!(
+            admin_service.authorize(Some(5)),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(matches!(
+            admin_service.authorize(None),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn test_set_role() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_email()
+            .with(eq("park@email.com"))
+            .times(1)
+            .returning(|_| Ok(user(5, "admin")));
+
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        mocked_admin_repository
+            .expect_update_role()
+            .with(eq("park@email.com"), eq("user"))
+            .times(1)
+            .returning(|_, _| Ok(true));
+        mocked_admin_repository
+            .expect_create_audit()
+            .with(eq(None), eq(5), eq(AdminAuditAction::RevokeAdmin), always())
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+
+        assert!(
+            AdminService::new_with_repository(mocked_admin_repository, mocked_user_repository)
+                .set_role("park@email.com", UserRole::User)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_get_stats() {
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        let from = NaiveDate::from_ymd(2020, 5, 3).and_hms(0, 0, 0);
+
+        mocked_admin_repository
+            .expect_count_users()
+            .with(eq(None))
+            .times(1)
+            .returning(|_| Ok(12));
+        mocked_admin_repository
+            .expect_count_suspended_users()
+            .times(1)
+            .returning(|| Ok(1));
+        mocked_admin_repository
+            .expect_count_posts()
+            .times(1)
+            .returning(|| Ok(340));
+        mocked_admin_repository
+            .expect_count_signups_by_date()
+            .with(eq(from))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![DailyCountDTO {
+                    date: NaiveDate::from_ymd(2020, 5, 9),
+                    count: 2,
+                }])
+            });
+        mocked_admin_repository
+            .expect_count_posts_by_date()
+            .with(eq(from))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 5, 9).and_hms(15, 0, 0)));
+        let stats = AdminService::new_with_repository(
+            mocked_admin_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(clock)
+        .get_stats(&Some(7))
+        .unwrap();
+        assert_eq!(stats.user_count, 12);
+        assert_eq!(stats.signups[0].count, 2);
+
+        assert!(matches!(
+            AdminService::new_with_repository(
+                MockAdminRepositoryTrait::new(),
+                MockUserRepositoryTrait::new(),
+            )
+            .get_stats(&Some(0)),
+            Err(ServiceError::InvalidArgument)
+        ));
+    }
+
+    fn audit_event(source: &str, id: u64, created_at: NaiveDateTime) -> AuditEvent {
+        AuditEvent {
+            source: source.to_string(),
+            id,
+            user_id: 5,
+            admin_id: None,
+            event: format!("{}.update", source),
+            request: None,
+            ip: Some(String::from("127.0.0.1")),
+            user_agent: Some(String::from("Mozilla/5.0 (X11, Linux)")),
+            created_at,
+        }
+    }
+
+    #[test]
+    fn test_get_audit() {
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+        let created_at = NaiveDate::from_ymd(2020, 5, 9).and_hms(12, 0, 0);
+        let filter = AuditFilter {
+            user_id: Some(5),
+            ..AuditFilter::default()
+        };
+        let after = AuditCursor {
+            created_at: created_at + Duration::hours(1),
+            source: String::from("admin"),
+            id: 3,
+        };
+
+        // Reading the log is audited before the page is read.
+        mocked_admin_repository
+            .expect_create_audit()
+            .with(
+                eq(Some(1)),
+                eq(5),
+                eq(AdminAuditAction::ReadAudit),
+                always(),
+            )
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _, _| Ok(true));
+        mocked_admin_repository
+            .expect_find_audit_events()
+            .with(eq(filter.clone()), eq(Some(after.clone())), eq(3))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_, _, _| {
+                Ok(vec![
+                    audit_event("post", 9, created_at),
+                    audit_event("auth", 9, created_at),
+                    audit_event("admin", 2, created_at),
+                ])
+            });
+
+        let page = AdminService::new_with_repository(
+            mocked_admin_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .get_audit(
+            1,
+            &filter,
+            &Some(after.to_string()),
+            &Some(2),
+            &AuditContext::default(),
+        )
+        .unwrap();
+        assert_eq!(page.events.len(), 2);
+        assert_eq!(page.events[1].source, "auth");
+        assert_eq!(
+            page.next_cursor,
+            Some(format!("{}-auth-9", created_at.timestamp()))
+        );
+        assert_eq!(
+            AuditCursor::parse(&page.next_cursor.unwrap()),
+            Some(AuditCursor::of(&audit_event("auth", 9, created_at)))
+        );
+    }
+
+    #[test]
+    fn test_get_audit_with_invalid_arguments() {
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        mocked_admin_repository.expect_create_audit().times(0);
+        mocked_admin_repository.expect_find_audit_events().times(0);
+        let mut admin_service = AdminService::new_with_repository(
+            mocked_admin_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        let reversed = AuditFilter {
+            from: Some(NaiveDate::from_ymd(2020, 5, 9)),
+            to: Some(NaiveDate::from_ymd(2020, 5, 8)),
+            ..AuditFilter::default()
+        };
+        let context = AuditContext::default();
+        assert!(matches!(
+            admin_service.get_audit(1, &reversed, &None, &None, &context),
+            Err(ServiceError::InvalidArgument)
+        ));
+        assert!(matches!(
+            admin_service.get_audit(
+                1,
+                &AuditFilter::default(),
+                &Some(String::from("1588996800-users-1")),
+                &None,
+                &context,
+            ),
+            Err(ServiceError::InvalidArgument)
+        ));
+        assert!(matches!(
+            admin_service.get_audit(1, &AuditFilter::default(), &None, &Some(0), &context),
+            Err(ServiceError::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn test_export_audit_without_entries() {
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        let filter = AuditFilter {
+            ip: Some(String::from("10.0.0.1")),
+            ..AuditFilter::default()
+        };
+
+        mocked_admin_repository
+            .expect_create_audit()
+            .with(
+                eq(Some(1)),
+                eq(1),
+                eq(AdminAuditAction::ExportAudit),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+        mocked_admin_repository
+            .expect_find_audit_events()
+            .with(eq(filter.clone()), eq(None), eq(AUDIT_CSV_BATCH_SIZE))
+            .times(1)
+            .returning(|_, _, _| Ok(vec![]));
+
+        let chunks: Vec<Vec<u8>> = AdminService::new_with_repository(
+            mocked_admin_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .export_audit(1, filter, &AuditContext::default())
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+        assert_eq!(
+            chunks,
+            vec![b"created_at,event,user_id,admin_id,request,ip,user_agent,source,id\r\n".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_export_audit_in_batches() {
+        let mut mocked_admin_repository = MockAdminRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+        let created_at = NaiveDate::from_ymd(2020, 5, 9).and_hms(12, 0, 0);
+
+        mocked_admin_repository
+            .expect_create_audit()
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+        mocked_admin_repository
+            .expect_find_audit_events()
+            .with(always(), eq(None), eq(AUDIT_CSV_BATCH_SIZE))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_, _, limit| {
+                Ok((0..limit as u64)
+                    .map(|id| audit_event("post", 1000 - id, created_at))
+                    .collect())
+            });
+        let last = AuditCursor {
+            created_at,
+            source: String::from("post"),
+            id: 1000 - (AUDIT_CSV_BATCH_SIZE as u64 - 1),
+        };
+        mocked_admin_repository
+            .expect_find_audit_events()
+            .with(always(), eq(Some(last)), eq(AUDIT_CSV_BATCH_SIZE))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_, _, _| Ok(vec![audit_event("admin", 7, created_at)]));
+
+        let chunks: Vec<String> = AdminService::new_with_repository(
+            mocked_admin_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .export_audit(1, AuditFilter::default(), &AuditContext::default())
+        .unwrap()
+        .map(|chunk| String::from_utf8(chunk.unwrap()).unwrap())
+        .collect();
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks[0].lines().count(), 1 + AUDIT_CSV_BATCH_SIZE as usize);
+        assert_eq!(
+            chunks[1],
+            "2020-05-09T12:00:00Z,admin.update,5,,,127.0.0.1,\"Mozilla/5.0 (X11, Linux)\",admin,7\r\n"
+        );
+    }
+}
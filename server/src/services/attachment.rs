@@ -0,0 +1,519 @@
+use sha2::{Digest, Sha256};
+
+use crate::models::attachment::*;
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::storage::Storage;
+
+/// Maximum size of an attachment in bytes.
+pub const MAX_ATTACHMENT_SIZE: usize = 10 * 1024 * 1024;
+
+/// Maximum length of the name of an attachment in characters.
+const MAX_FILENAME_LENGTH: usize = 255;
+
+/// Returns the MIME type of a photo detected by its leading bytes,
+/// or `None` if it is not a supported type.
+///
+/// The content type sent by the client is not trusted, since the file is served back as it is.
+pub fn detect_mime_type(data: &[u8]) -> Option<&'static str> {
+    let is_heic = data.len() >= 12
+        && &data[4..8] == b"ftyp"
+        && [&b"heic"[..], &b"heix"[..], &b"mif1"[..], &b"msf1"[..]].contains(&&data[8..12]);
+
+    if data.starts_with(&[0xFF, 0xD8, 0xFF]) {
+        Some("image/jpeg")
+    } else if data.starts_with(&[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A]) {
+        Some("image/png")
+    } else if data.starts_with(b"GIF87a") || data.starts_with(b"GIF89a") {
+        Some("image/gif")
+    } else if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+        Some("image/webp")
+    } else if is_heic {
+        Some("image/heic")
+    } else {
+        None
+    }
+}
+
+/// Returns the name of a file without directories, quotes, and control characters,
+/// which is safe to be written in `Content-Disposition` header.
+pub fn sanitize_filename(filename: &str) -> String {
+    let basename = filename
+        .rsplit(|c: char| c == '/' || c == '\\')
+        .next()
+        .unwrap_or("");
+    let sanitized: String = basename
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(MAX_FILENAME_LENGTH)
+        .collect();
+
+    match sanitized.trim() {
+        "" => String::from("attachment"),
+        sanitized => sanitized.to_string(),
+    }
+}
+
+/// Returns the SHA-256 hash of a file in hex, which is the key of its blob.
+fn get_hash(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+pub struct AttachmentService {
+    attachment_repository: Option<AttachmentRepository>,
+    storage: Option<Box<dyn Storage>>,
+}
+
+impl AttachmentService {
+    pub fn new() -> Self {
+        Self {
+            attachment_repository: None,
+            storage: None,
+        }
+    }
+
+    fn attachment_repository(
+        &mut self,
+        new_repository: Option<AttachmentRepository>,
+    ) -> &AttachmentRepository {
+        match new_repository {
+            Some(_) => {
+                self.attachment_repository = new_repository;
+                self.attachment_repository.as_ref().unwrap()
+            }
+            None => self.attachment_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn storage(&mut self) -> &dyn Storage {
+        if self.storage.is_none() {
+            self.storage = Some(connection::connect_storage());
+        }
+        self.storage.as_deref().unwrap()
+    }
+
+    /// Deletes a blob and its file if no attachment refers to it.
+    fn delete_blob(&mut self, hash: &str) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.attachment_repository.is_none() => AttachmentRepository::new());
+        self.attachment_repository(fallback_repository);
+        self.storage();
+
+        let storage = self.storage.as_deref().unwrap();
+        self.attachment_repository
+            .as_ref()
+            .unwrap()
+            .delete_blob_if_orphaned(hash, storage)
+    }
+
+    /// Finds attachments of a post written by specific user.
+    pub fn get_list(
+        &mut self,
+        user_id: u64,
+        post_id: u64,
+    ) -> Result<Vec<AttachmentDTO>, ServiceError> {
+        let attachment_list = {
+            let fallback_repository =
+                some_if_true!(self.attachment_repository.is_none() => AttachmentRepository::new());
+            self.attachment_repository(fallback_repository)
+                .find_all(user_id, post_id)?
+        };
+
+        Ok(attachment_list
+            .into_iter()
+            .map(|(attachment, blob)| AttachmentDTO {
+                id: attachment.id,
+                post_id: attachment.post_id,
+                filename: attachment.filename,
+                mime_type: blob.mime_type,
+                size: blob.size,
+                created_at: attachment.created_at,
+            })
+            .collect())
+    }
+
+    /// Attaches a photo to a post written by specific user, and returns id of the attachment.
+    ///
+    /// The file is stored only if no attachment has the same content yet.
+    pub fn upload(
+        &mut self,
+        user_id: u64,
+        post_id: u64,
+        filename: &str,
+        data: &[u8],
+    ) -> Result<u64, ServiceError> {
+        if data.is_empty() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+        if data.len() > MAX_ATTACHMENT_SIZE {
+            return Err(get_service_error(ServiceError::PayloadTooLarge));
+        }
+        let mime_type = detect_mime_type(data)
+            .ok_or_else(|| get_service_error(ServiceError::UnsupportedMediaType))?;
+
+        let hash = get_hash(data);
+        let (attachment_id, is_blob_created) = {
+            let fallback_repository =
+                some_if_true!(self.attachment_repository.is_none() => AttachmentRepository::new());
+            self.attachment_repository(fallback_repository).create(
+                user_id,
+                post_id,
+                &sanitize_filename(filename),
+                &hash,
+                data.len() as u64,
+                mime_type,
+            )?
+        };
+
+        if is_blob_created {
+            if let Err(error) = self.storage().put(&hash, data) {
+                self.attachment_repository(None)
+                    .delete(user_id, attachment_id)?;
+                self.delete_blob(&hash)?;
+                return Err(error);
+            }
+        }
+
+        Ok(attachment_id)
+    }
+
+    /// Returns the file of an attachment of specific user.
+    ///
+    /// The content is omitted if the hash of the file is in `cached_hashes`,
+    /// which the client has from `ETag` of a previous response.
+    pub fn download(
+        &mut self,
+        user_id: u64,
+        id: u64,
+        cached_hashes: &[String],
+    ) -> Result<AttachmentFile, ServiceError> {
+        let (attachment, blob) = {
+            let fallback_repository =
+                some_if_true!(self.attachment_repository.is_none() => AttachmentRepository::new());
+            self.attachment_repository(fallback_repository)
+                .find(user_id, id)?
+        };
+
+        let data = if cached_hashes.contains(&blob.hash) {
+            None
+        } else {
+            Some(self.storage().get(&blob.hash)?)
+        };
+
+        Ok(AttachmentFile {
+            filename: attachment.filename,
+            mime_type: blob.mime_type,
+            hash: blob.hash,
+            data,
+        })
+    }
+
+    /// Deletes an attachment of specific user, and its file if no other attachment shares it.
+    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let hash = {
+            let fallback_repository =
+                some_if_true!(self.attachment_repository.is_none() => AttachmentRepository::new());
+            self.attachment_repository(fallback_repository)
+                .delete(user_id, id)?
+        };
+
+        self.delete_blob(&hash)?;
+        Ok(true)
+    }
+
+    /// Deletes attachments of posts which no longer exist, and returns the count.
+    ///
+    /// Their blobs are deleted by the next pruning of blobs.
+    pub fn delete_orphaned(&mut self) -> Result<usize, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.attachment_repository.is_none() => AttachmentRepository::new());
+        self.attachment_repository(fallback_repository)
+            .delete_orphaned()
+    }
+
+    /// Deletes blobs which no attachment refers to, such as the ones of deleted posts,
+    /// and returns their hashes.
+    ///
+    /// If `dry_run` is true, reports the blobs to be deleted without deleting anything.
+    pub fn prune_blobs(&mut self, dry_run: bool) -> Result<Vec<String>, ServiceError> {
+        let hashes = {
+            let fallback_repository =
+                some_if_true!(self.attachment_repository.is_none() => AttachmentRepository::new());
+            self.attachment_repository(fallback_repository)
+                .find_orphaned_blobs()?
+        };
+
+        if dry_run {
+            return Ok(hashes);
+        }
+
+        let mut deleted_hashes = Vec::new();
+        for hash in hashes {
+            if self.delete_blob(&hash)? {
+                deleted_hashes.push(hash);
+            }
+        }
+        Ok(deleted_hashes)
+    }
+}
+
+impl Default for AttachmentService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::attachment::MockAttachmentRepositoryTrait as AttachmentRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::attachment::MockAttachmentRepositoryTrait;
+    use crate::models::storage::MockStorage;
+
+    impl AttachmentService {
+        pub fn new_with_repository(
+            attachment_repository: AttachmentRepository,
+            storage: MockStorage,
+        ) -> Self {
+            Self {
+                attachment_repository: Some(attachment_repository),
+                storage: Some(Box::new(storage)),
+            }
+        }
+    }
+
+    const PNG: &[u8] = &[0x89, b'P', b'N', b'G', 0x0D, 0x0A, 0x1A, 0x0A, 0x00];
+
+    fn attachment(id: u64, post_id: u64, hash: &str) -> (Attachment, AttachmentBlob) {
+        let created_at = Utc::now().naive_utc();
+        (
+            Attachment {
+                id,
+                user_id: 1,
+                post_id,
+                blob_hash: hash.to_string(),
+                filename: String::from("photo.png"),
+                created_at,
+            },
+            AttachmentBlob {
+                hash: hash.to_string(),
+                size: PNG.len() as u64,
+                mime_type: String::from("image/png"),
+                created_at,
+            },
+        )
+    }
+
+    #[test]
+    fn test_detect_mime_type() {
+        assert_eq!(
+            detect_mime_type(&[0xFF, 0xD8, 0xFF, 0xE0]),
+            Some("image/jpeg")
+        );
+        assert_eq!(detect_mime_type(PNG), Some("image/png"));
+        assert_eq!(detect_mime_type(b"GIF89a;"), Some("image/gif"));
+        assert_eq!(
+            detect_mime_type(b"RIFF\x00\x00\x00\x00WEBPVP8 "),
+            Some("image/webp")
+        );
+        assert_eq!(
+            detect_mime_type(b"\x00\x00\x00\x18ftypheic"),
+            Some("image/heic")
+        );
+        assert_eq!(detect_mime_type(b"<svg></svg>"), None);
+        assert_eq!(detect_mime_type(b""), None);
+    }
+
+    #[test]
+    fn test_sanitize_filename() {
+        assert_eq!(sanitize_filename("photo.png"), "photo.png");
+        assert_eq!(sanitize_filename("../../etc/passwd"), "passwd");
+        assert_eq!(sanitize_filename("C:\\photos\\a\"b\n.png"), "ab.png");
+        assert_eq!(sanitize_filename("  "), "attachment");
+    }
+
+    #[test]
+    fn test_upload() {
+        let mut mocked_attachment_repository = MockAttachmentRepositoryTrait::new();
+        let mut mocked_storage = MockStorage::new();
+
+        let user_id = 1;
+        let hash = get_hash(PNG);
+
+        mocked_attachment_repository
+            .expect_create()
+            .with(
+                eq(user_id),
+                eq(3),
+                eq("photo.png"),
+                eq(hash.clone()),
+                eq(PNG.len() as u64),
+                eq("image/png"),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok((1, true)));
+        mocked_attachment_repository
+            .expect_create()
+            .with(
+                eq(user_id),
+                eq(4),
+                eq("photo.png"),
+                eq(hash.clone()),
+                eq(PNG.len() as u64),
+                eq("image/png"),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok((2, false)));
+        mocked_storage
+            .expect_put()
+            .withf(move |key, data| key == hash && data == PNG)
+            .times(1)
+            .returning(|_, _| Ok(()));
+
+        let mut attachment_service =
+            AttachmentService::new_with_repository(mocked_attachment_repository, mocked_storage);
+
+        assert_eq!(
+            attachment_service
+                .upload(user_id, 3, "photo.png", PNG)
+                .unwrap(),
+            1
+        );
+        assert_eq!(
+            attachment_service
+                .upload(user_id, 4, "photo.png", PNG)
+                .unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_upload_with_invalid_file() {
+        let mut attachment_service = AttachmentService::new_with_repository(
+            MockAttachmentRepositoryTrait::new(),
+            MockStorage::new(),
+        );
+
+        let large_file = [PNG, &vec![0; MAX_ATTACHMENT_SIZE][..]].concat();
+
+        assert!(matches!(
+            attachment_service.upload(1, 3, "empty.png", b""),
+            Err(ServiceError::InvalidArgument)
+        ));
+        assert!(matches!(
+            attachment_service.upload(1, 3, "large.png", &large_file),
+            Err(ServiceError::PayloadTooLarge)
+        ));
+        assert!(matches!(
+            attachment_service.upload(1, 3, "image.svg", b"<svg></svg>"),
+            Err(ServiceError::UnsupportedMediaType)
+        ));
+    }
+
+    #[test]
+    fn test_download() {
+        let mut mocked_attachment_repository = MockAttachmentRepositoryTrait::new();
+        let mut mocked_storage = MockStorage::new();
+
+        let hash = get_hash(PNG);
+
+        mocked_attachment_repository
+            .expect_find()
+            .with(eq(1), eq(2))
+            .times(2)
+            .returning(|_, _| Ok(attachment(2, 3, &get_hash(PNG))));
+        mocked_storage
+            .expect_get()
+            .with(eq(hash.clone()))
+            .times(1)
+            .returning(|_| Ok(PNG.to_vec()));
+
+        let mut attachment_service =
+            AttachmentService::new_with_repository(mocked_attachment_repository, mocked_storage);
+
+        let file = attachment_service.download(1, 2, &[]).unwrap();
+        assert_eq!(file.mime_type, "image/png");
+        assert_eq!(file.data.unwrap(), PNG);
+
+        let file = attachment_service.download(1, 2, &[hash]).unwrap();
+        assert!(file.data.is_none());
+    }
+
+    #[test]
+    fn test_delete_keeps_shared_blob() {
+        let mut mocked_attachment_repository = MockAttachmentRepositoryTrait::new();
+        let mut mocked_storage = MockStorage::new();
+
+        let hash = get_hash(PNG);
+
+        // Attachments 1 and 2 of different posts share a blob, and attachment 1 is deleted.
+        mocked_attachment_repository
+            .expect_delete()
+            .with(eq(1), eq(1))
+            .times(1)
+            .returning(|_, _| Ok(get_hash(PNG)));
+        let deleted_hash = hash.clone();
+        mocked_attachment_repository
+            .expect_delete_blob_if_orphaned()
+            .withf(move |hash, _| hash == deleted_hash)
+            .times(1)
+            .returning(|_, _| Ok(false));
+        mocked_attachment_repository
+            .expect_find()
+            .with(eq(1), eq(2))
+            .times(1)
+            .returning(|_, _| Ok(attachment(2, 4, &get_hash(PNG))));
+        mocked_storage.expect_delete().times(0);
+        mocked_storage
+            .expect_get()
+            .with(eq(hash))
+            .times(1)
+            .returning(|_| Ok(PNG.to_vec()));
+
+        let mut attachment_service =
+            AttachmentService::new_with_repository(mocked_attachment_repository, mocked_storage);
+
+        assert!(attachment_service.delete(1, 1).unwrap());
+        assert_eq!(
+            attachment_service
+                .download(1, 2, &[])
+                .unwrap()
+                .data
+                .unwrap(),
+            PNG
+        );
+    }
+
+    #[test]
+    fn test_prune_blobs() {
+        let mut mocked_attachment_repository = MockAttachmentRepositoryTrait::new();
+
+        mocked_attachment_repository
+            .expect_find_orphaned_blobs()
+            .times(2)
+            .returning(|| Ok(vec![String::from("a1"), String::from("b2")]));
+        mocked_attachment_repository
+            .expect_delete_blob_if_orphaned()
+            .times(2)
+            .returning(|hash, _| Ok(hash == "a1"));
+
+        let mut attachment_service = AttachmentService::new_with_repository(
+            mocked_attachment_repository,
+            MockStorage::new(),
+        );
+
+        assert_eq!(
+            attachment_service.prune_blobs(true).unwrap(),
+            vec![String::from("a1"), String::from("b2")]
+        );
+        assert_eq!(
+            attachment_service.prune_blobs(false).unwrap(),
+            vec![String::from("a1")]
+        );
+    }
+}
@@ -1,17 +1,30 @@
 use rand::{distributions::Alphanumeric, thread_rng, Rng};
-use std::env;
+use webauthn_rs::proto::PublicKeyCredential;
 
 use crate::models::auth::*;
 use crate::models::error::{get_service_error, ServiceError};
-use crate::models::user::UserRepository;
+use crate::models::post_audit::AuditContext;
+use crate::models::user::*;
 use crate::models::user_key::UserKeyRepository;
-use crate::utils::{email_util, password_util};
+use crate::services::admin::AdminService;
+use crate::services::email::EmailService;
+use crate::services::invite::InviteService;
+use crate::services::login_session::LoginSessionService;
+use crate::services::oauth::OAuthService;
+use crate::services::personal_access_token::PersonalAccessTokenService;
+use crate::services::two_factor::TwoFactorService;
+use crate::services::webauthn::WebauthnService;
+use crate::utils::password_util;
+use crate::utils::url_util::PublicUrl;
 
 pub struct AuthService {
     sign_up_token_repository: Option<SignUpTokenRepository>,
     password_token_repository: Option<PasswordTokenRepository>,
+    login_token_repository: Option<LoginTokenRepository>,
+    magic_link_token_repository: Option<MagicLinkTokenRepository>,
     user_key_repository: Option<UserKeyRepository>,
     user_repository: Option<UserRepository>,
+    two_factor_service: Option<TwoFactorService>,
 }
 
 impl AuthService {
@@ -19,8 +32,11 @@ impl AuthService {
         Self {
             sign_up_token_repository: None,
             password_token_repository: None,
+            login_token_repository: None,
+            magic_link_token_repository: None,
             user_key_repository: None,
             user_repository: None,
+            two_factor_service: None,
         }
     }
 
@@ -50,6 +66,32 @@ impl AuthService {
         }
     }
 
+    fn login_token_repository(
+        &mut self,
+        new_repository: Option<LoginTokenRepository>,
+    ) -> &mut LoginTokenRepository {
+        match new_repository {
+            Some(_) => {
+                self.login_token_repository = new_repository;
+                self.login_token_repository.as_mut().unwrap()
+            }
+            None => self.login_token_repository.as_mut().unwrap(),
+        }
+    }
+
+    fn magic_link_token_repository(
+        &mut self,
+        new_repository: Option<MagicLinkTokenRepository>,
+    ) -> &mut MagicLinkTokenRepository {
+        match new_repository {
+            Some(_) => {
+                self.magic_link_token_repository = new_repository;
+                self.magic_link_token_repository.as_mut().unwrap()
+            }
+            None => self.magic_link_token_repository.as_mut().unwrap(),
+        }
+    }
+
     fn user_key_repository(
         &mut self,
         new_repository: Option<UserKeyRepository>,
@@ -73,12 +115,83 @@ impl AuthService {
         }
     }
 
+    fn two_factor_service(&mut self) -> &mut TwoFactorService {
+        self.two_factor_service
+            .get_or_insert_with(TwoFactorService::new)
+    }
+
+    /// Returns the session of a user, which fails with `Suspended` if the user is suspended.
+    fn get_user_session(&mut self, user: User) -> Result<UserSession, ServiceError> {
+        if user.suspended_at.is_some() {
+            return Err(get_service_error(ServiceError::Suspended(
+                user.id.to_string(),
+            )));
+        }
+
+        let user_public_key = {
+            let fallback_repository =
+                some_if_true!(self.user_key_repository.is_none() => UserKeyRepository::new());
+            self.user_key_repository(fallback_repository)
+                .find_by_user_id(user.id)?
+                .public_key
+        };
+
+        Ok(UserSession {
+            user_id: user.id,
+            user_email: user.email,
+            user_name: user.name,
+            user_public_key,
+            user_avatar_url: user.avatar_url,
+            user_role: user.role,
+            impersonator: None,
+        })
+    }
+
+    /// Returns the session of a user who has been verified by a credential,
+    /// or a login token if the user has enabled two-factor authentication.
+    fn get_login(&mut self, user: User) -> Result<LoginDTO, ServiceError> {
+        if user.suspended_at.is_some() {
+            return Err(get_service_error(ServiceError::Suspended(
+                user.id.to_string(),
+            )));
+        }
+
+        if self.two_factor_service().is_enabled(user.id)? {
+            let serialized_token = serde_json::to_string(&LoginToken { user_id: user.id });
+            let serialized_token = if let Ok(serialized_token) = serialized_token {
+                serialized_token
+            } else {
+                return Err(get_service_error(ServiceError::InvalidFormat));
+            };
+
+            let fallback_repository =
+                some_if_true!(self.login_token_repository.is_none() => LoginTokenRepository::new());
+            let two_factor_token = self
+                .login_token_repository(fallback_repository)
+                .save(&serialized_token)?;
+            return Ok(LoginDTO {
+                session: None,
+                two_factor_token: Some(two_factor_token),
+            });
+        }
+
+        Ok(LoginDTO {
+            session: Some(self.get_user_session(user)?),
+            two_factor_token: None,
+        })
+    }
+
     /// Signs in to set user session.
     ///
     /// 1. Finds password of the user by email from arguments.
     /// 2. Compares password from the found user and it from the arguments.
-    /// 3. If the passwords are equal, returns the found user.
-    pub fn login(&mut self, email: &str, password: &str) -> Result<UserSession, ServiceError> {
+    /// 3. If the passwords are equal, returns the session of the found user.
+    ///    If the user has enabled two-factor authentication, returns a login token instead,
+    ///    which `login_with_two_factor` exchanges with the session.
+    ///
+    /// A password hashed by scrypt or with outdated cost parameters is hashed again
+    /// with the current ones, since the plain-text password is only known here.
+    pub fn login(&mut self, email: &str, password: &str) -> Result<LoginDTO, ServiceError> {
         let user = {
             let fallback_repository =
                 some_if_true!(self.user_repository.is_none() => UserRepository::new());
@@ -86,50 +199,315 @@ impl AuthService {
                 .user_repository(fallback_repository)
                 .find_password_by_email(email)?;
 
-            if password_util::check_password(password, &found_password) {
-                self.user_repository(None).find_by_email(email)?
-            } else {
+            if !password_util::check_password(password, &found_password) {
                 return Err(ServiceError::Unauthorized);
             }
+
+            let user = self.user_repository(None).find_by_email(email)?;
+            if password_util::needs_rehash(&found_password) {
+                self.user_repository(None).update(
+                    user.id,
+                    &None,
+                    &Some(password_util::get_hashed_password(password)),
+                    &None,
+                )?;
+            }
+            user
         };
 
-        let logged_in_user_session = {
-            let user_public_key = {
-                let fallback_repository =
-                    some_if_true!(self.user_key_repository.is_none() => UserKeyRepository::new());
-                self.user_key_repository(fallback_repository)
-                    .find_by_user_id(user.id)?
-                    .public_key
-            };
+        self.get_login(user)
+    }
+
+    /// Finishes signing in with a login token and a TOTP code or a recovery code.
+    ///
+    /// The token is deleted on the first attempt, so that codes cannot be guessed
+    /// without the password. A wrong code requires signing in again.
+    pub fn login_with_two_factor(
+        &mut self,
+        token_key: &str,
+        code: &str,
+    ) -> Result<UserSession, ServiceError> {
+        let token: LoginToken = {
+            let fallback_repository =
+                some_if_true!(self.login_token_repository.is_none() => LoginTokenRepository::new());
+            let login_token_repository = self.login_token_repository(fallback_repository);
+            let serialized_token = login_token_repository.find(token_key)?;
+            login_token_repository.delete(token_key)?;
+
+            if let Ok(deserialized_token) = serde_json::from_str(&serialized_token) {
+                deserialized_token
+            } else {
+                return Err(get_service_error(ServiceError::InvalidFormat));
+            }
+        };
+
+        self.two_factor_service().check_code(token.user_id, code)?;
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(token.user_id)?
+        };
+        self.get_user_session(user)
+    }
+
+    /// Emails a link signing in without the password.
+    ///
+    /// The link points to the client, which signs in by `login_with_magic_link`,
+    /// so that email scanners visiting the link do not use the token.
+    ///
+    /// It answers `true` for an email without a user as well, without sending anything,
+    /// so that it does not tell which emails have accounts.
+    pub fn send_magic_link(&mut self, email: &str) -> Result<bool, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            match self
+                .user_repository(fallback_repository)
+                .find_by_email(email)
+            {
+                Ok(user) => user,
+                Err(ServiceError::NotFound(_)) => return Ok(true),
+                Err(error) => return Err(error),
+            }
+        };
+
+        let serialized_token = serde_json::to_string(&MagicLinkToken { user_id: user.id });
+        let serialized_token = if let Ok(serialized_token) = serialized_token {
+            serialized_token
+        } else {
+            return Err(get_service_error(ServiceError::InvalidFormat));
+        };
+
+        let key = {
+            let fallback_repository = some_if_true!(self.magic_link_token_repository.is_none() => MagicLinkTokenRepository::new());
+            self.magic_link_token_repository(fallback_repository)
+                .save(&serialized_token)?
+        };
+
+        let magic_link_url = PublicUrl::from_env()
+            .expect("Invalid PUBLIC_BASE_URL")
+            .magic_link_url(&key);
+        let email_content = format!(
+            "Hello :)<br/><br/>\
+            Please visit the link to sign in to Darim:<br/><br/>\
+            <a href=\"{}\">{}</a><br/><br/>\
+            The link is valid for 3 minutes and can be used once.",
+            magic_link_url, magic_link_url,
+        );
+
+        EmailService::new().enqueue(
+            &format!("{} <{}>", user.name, email),
+            &String::from("Sign in to Darim 🔑"),
+            &email_content,
+            &Some(get_magic_link_token_key(&key)),
+        )?;
+        EmailService::send_soon();
+
+        Ok(true)
+    }
+
+    /// Signs in with a magic link token, in the same way as the password.
+    ///
+    /// The token is deleted when it is used, and only the request which has deleted it signs in.
+    pub fn login_with_magic_link(&mut self, token_key: &str) -> Result<LoginDTO, ServiceError> {
+        let token: MagicLinkToken = {
+            let fallback_repository = some_if_true!(self.magic_link_token_repository.is_none() => MagicLinkTokenRepository::new());
+            let magic_link_token_repository = self.magic_link_token_repository(fallback_repository);
+            let serialized_token = magic_link_token_repository.find(token_key)?;
+            if !magic_link_token_repository.delete(token_key)? {
+                return Err(get_service_error(ServiceError::Unauthorized));
+            }
+
+            if let Ok(deserialized_token) = serde_json::from_str(&serialized_token) {
+                deserialized_token
+            } else {
+                return Err(get_service_error(ServiceError::InvalidFormat));
+            }
+        };
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(token.user_id)?
+        };
+        self.get_login(user)
+    }
+
+    /// Signs in with a code of an OAuth provider, in the same way as the password.
+    ///
+    /// The account of the provider is linked to the user by its verified email at first.
+    pub fn login_with_oauth(
+        &mut self,
+        provider: &str,
+        code: &str,
+    ) -> Result<LoginDTO, ServiceError> {
+        let user_id = OAuthService::new().login(provider, code)?;
 
-            UserSession {
-                user_id: user.id,
-                user_email: user.email,
-                user_name: user.name,
-                user_public_key,
-                user_avatar_url: user.avatar_url,
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+        self.get_login(user)
+    }
+
+    /// Signs in with a passkey by the response of the authenticator to the challenge of `token_key`.
+    ///
+    /// A passkey verifies the user by itself, so two-factor authentication is not required.
+    pub fn login_with_webauthn(
+        &mut self,
+        token_key: &str,
+        response: &PublicKeyCredential,
+    ) -> Result<UserSession, ServiceError> {
+        let user_id = WebauthnService::new().finish_login(token_key, response)?;
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+        self.get_user_session(user)
+    }
+
+    /// Refreshes the login session of a refresh token, and returns the session of the user
+    /// with a new refresh token.
+    pub fn refresh_token(
+        &mut self,
+        refresh_token: &str,
+        context: &AuditContext,
+    ) -> Result<TokenDTO, ServiceError> {
+        let (user_id, refresh_token) =
+            LoginSessionService::new().refresh(refresh_token, context)?;
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+        Ok(TokenDTO {
+            session: self.get_user_session(user)?,
+            refresh_token,
+        })
+    }
+
+    /// Switches a device to its login session of `id`, and returns the session of the user.
+    pub fn switch_session(
+        &mut self,
+        id: u64,
+        device_id: &str,
+        context: &AuditContext,
+    ) -> Result<UserSession, ServiceError> {
+        let user_id = LoginSessionService::new().switch(id, device_id, context)?;
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+        self.get_user_session(user)
+    }
+
+    /// Returns the session of the user if the login session of `session_id` is active.
+    ///
+    /// The device keeps only the session id, and the session of the user is read
+    /// from the service on every request. A suspended user has no session.
+    ///
+    /// A session issued to an admin impersonating the user ends once the admin is no longer
+    /// an admin, and each request of it, whose `method` and `path` are given, is audited with
+    /// both of them.
+    pub fn verify_login_session(
+        &mut self,
+        session_id: &str,
+        method: &Option<String>,
+        path: &Option<String>,
+        context: &AuditContext,
+    ) -> Result<Option<UserSession>, ServiceError> {
+        let (user, impersonation) = match LoginSessionService::new().verify(session_id)? {
+            Some(session) => session,
+            None => return Ok(None),
+        };
+
+        let impersonator = match impersonation {
+            Some(impersonation) => {
+                let mut admin_service = AdminService::new();
+                match admin_service.authorize(Some(impersonation.impersonator_id)) {
+                    Ok(_) => {}
+                    Err(ServiceError::Unauthorized) => return Ok(None),
+                    Err(error) => return Err(error),
+                }
+                if let (Some(method), Some(path)) = (method, path) {
+                    admin_service.audit_impersonated_request(
+                        impersonation.impersonator_id,
+                        user.id,
+                        method,
+                        path,
+                        context,
+                    )?;
+                }
+                Some(ImpersonatorDTO {
+                    admin_id: impersonation.impersonator_id,
+                    allow_writes: impersonation.allow_writes,
+                })
             }
+            None => None,
         };
 
-        Ok(logged_in_user_session)
+        match self.get_user_session(user) {
+            Ok(mut user_session) => {
+                user_session.impersonator = impersonator;
+                Ok(Some(user_session))
+            }
+            Err(ServiceError::Suspended(_)) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns the session of the user who owns a personal access token, with the scope of it.
+    pub fn login_with_personal_access_token(
+        &mut self,
+        token: &str,
+    ) -> Result<PersonalAccessTokenSessionDTO, ServiceError> {
+        let (user_id, scope) = PersonalAccessTokenService::new().verify(token)?;
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+        Ok(PersonalAccessTokenSessionDTO {
+            session: self.get_user_session(user)?,
+            scope: scope.as_str().to_string(),
+        })
     }
 
     /// Sets token for sign up process.
     ///
-    /// 1. Generates a random string called pin.
-    /// 2. Creates a new token containing the pin and information of the user from arguments.
-    /// 3. Serializes the token and inserts it to redis.
+    /// 1. Verifies the invite code, which is required on instances in invite mode.
+    /// 2. Generates a random string called pin.
+    /// 3. Creates a new token containing the pin and information of the user from arguments.
+    /// 4. Serializes the token and inserts it to redis.
     pub fn set_sign_up_token(
         &mut self,
         name: &str,
         email: &str,
         password: &str,
         avatar_url: &Option<String>,
+        invite_code: &Option<String>,
     ) -> Result<String, ServiceError> {
         if name.trim().is_empty() || email.trim().is_empty() || password.trim().is_empty() {
             return Err(get_service_error(ServiceError::InvalidArgument));
         }
 
+        let invite_code = InviteService::new().verify(invite_code)?;
+
         let pin: String = thread_rng().sample_iter(&Alphanumeric).take(8).collect();
         let hashed_password = password_util::get_hashed_password(password);
 
@@ -139,6 +517,7 @@ impl AuthService {
             email: email.to_string(),
             password: hashed_password,
             avatar_url: avatar_url.clone(),
+            invite_code,
         };
 
         let serialized_token = serde_json::to_string(&token);
@@ -165,11 +544,13 @@ impl AuthService {
             token.name, token.pin,
         );
 
-        let _ = email_util::send_email(
+        EmailService::new().enqueue(
             &format!("{} <{}>", &token.name, &token.email),
             &String::from("Welcome to Darim 🎉"),
             &email_content,
-        );
+            &Some(result.clone()),
+        )?;
+        EmailService::send_soon();
 
         Ok(result)
     }
@@ -201,21 +582,25 @@ impl AuthService {
                 .save(&serialized_token)?
         };
 
-        let client_address = env::var("CLIENT_ADDRESS").expect("CLIENT_ADDRESS not found");
+        let password_reset_url = PublicUrl::from_env()
+            .expect("Invalid PUBLIC_BASE_URL")
+            .password_reset_url(&token.id);
         let email_content = format!(
             "Hello :)<br/><br/>\
             Please copy the temporary password:<br/><br/>\
             <div style=\"background-color: #f0f0f0; padding: 10px; font-weight: bold\">{}</div><br/><br/>\
             and visit the link to reset your password:<br/><br/>\
-            <a href=\"{}/password_reset/{}\">{}/password_reset/{}</a>",
-            token.password, client_address, token.id, client_address, token.id,
+            <a href=\"{}\">{}</a>",
+            token.password, password_reset_url, password_reset_url,
         );
 
-        let _ = email_util::send_email(
+        EmailService::new().enqueue(
             &format!("{} <{}>", user.name, email),
             &String::from("Please reset your password 🔒"),
             &email_content,
-        );
+            &Some(get_password_token_key(user.id)),
+        )?;
+        EmailService::send_soon();
 
         Ok(result)
     }
@@ -227,23 +612,267 @@ impl Default for AuthService {
     }
 }
 
+#[cfg(test)]
+use crate::models::auth::MockLoginTokenRepositoryTrait as LoginTokenRepository;
+#[cfg(test)]
+use crate::models::auth::MockMagicLinkTokenRepositoryTrait as MagicLinkTokenRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+#[cfg(test)]
+use crate::models::user_key::MockUserKeyRepositoryTrait as UserKeyRepository;
+
 #[cfg(test)]
 mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+    use mockall::Sequence;
+
     use super::*;
+    use crate::models::auth::{MockLoginTokenRepositoryTrait, MockMagicLinkTokenRepositoryTrait};
+    use crate::models::two_factor::{MockTwoFactorRepositoryTrait, TwoFactor};
+    use crate::models::user::MockUserRepositoryTrait;
+    use crate::models::user_key::{MockUserKeyRepositoryTrait, UserKey};
 
     impl AuthService {
         pub fn new_with_repository(
-            sign_up_token_repository: SignUpTokenRepository,
-            password_token_repository: PasswordTokenRepository,
+            login_token_repository: LoginTokenRepository,
+            magic_link_token_repository: MagicLinkTokenRepository,
             user_key_repository: UserKeyRepository,
             user_repository: UserRepository,
         ) -> Self {
             Self {
-                sign_up_token_repository: Some(sign_up_token_repository),
-                password_token_repository: Some(password_token_repository),
+                sign_up_token_repository: None,
+                password_token_repository: None,
+                login_token_repository: Some(login_token_repository),
+                magic_link_token_repository: Some(magic_link_token_repository),
                 user_key_repository: Some(user_key_repository),
                 user_repository: Some(user_repository),
+                two_factor_service: None,
             }
         }
+
+        pub fn with_two_factor_service(mut self, two_factor_service: TwoFactorService) -> Self {
+            self.two_factor_service = Some(two_factor_service);
+            self
+        }
+    }
+
+    fn user(id: u64) -> User {
+        User {
+            id,
+            name: String::from("park"),
+            email: String::from("park@email.com"),
+            password: String::from("password"),
+            avatar_url: None,
+            created_at: Utc::now().naive_utc(),
+            updated_at: None,
+            key_metadata: None,
+            daily_word_goal: None,
+            monthly_word_goal: None,
+            role: String::from("user"),
+            suspended_at: None,
+        }
+    }
+
+    fn user_key(user_id: u64) -> UserKey {
+        UserKey {
+            id: 1,
+            user_id,
+            public_key: String::from("d63ee429"),
+            created_at: Utc::now().naive_utc(),
+            updated_at: None,
+        }
+    }
+
+    /// Returns a two-factor service whose user has enabled two-factor authentication or not.
+    fn two_factor_service(user_id: u64, is_enabled: bool) -> TwoFactorService {
+        let mut mocked_two_factor_repository = MockTwoFactorRepositoryTrait::new();
+        mocked_two_factor_repository
+            .expect_find_by_user_id()
+            .with(eq(user_id))
+            .returning(move |user_id| {
+                let created_at = Utc.ymd(2020, 4, 13).and_hms(16, 31, 9).naive_utc();
+                Ok(TwoFactor {
+                    user_id,
+                    secret: String::from("GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"),
+                    enabled_at: if is_enabled { Some(created_at) } else { None },
+                    last_used_step: None,
+                    created_at,
+                })
+            });
+        TwoFactorService::new_with_repository(
+            mocked_two_factor_repository,
+            MockUserRepositoryTrait::new(),
+        )
+    }
+
+    #[test]
+    fn test_send_magic_link_to_unknown_email() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_email()
+            .with(function(|email: &str| email == "nobody@email.com"))
+            .times(1)
+            .returning(|email| Err(ServiceError::NotFound(email.to_string())));
+
+        let mut mocked_magic_link_token_repository = MockMagicLinkTokenRepositoryTrait::new();
+        mocked_magic_link_token_repository.expect_save().never();
+
+        let result = AuthService::new_with_repository(
+            MockLoginTokenRepositoryTrait::new(),
+            mocked_magic_link_token_repository,
+            MockUserKeyRepositoryTrait::new(),
+            mocked_user_repository,
+        )
+        .send_magic_link("nobody@email.com");
+        assert!(result.unwrap());
+    }
+
+    #[test]
+    fn test_login_with_magic_link_once() {
+        let mut mocked_magic_link_token_repository = MockMagicLinkTokenRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+        mocked_magic_link_token_repository
+            .expect_find()
+            .with(function(|key: &str| key == "a1b2"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(String::from("{\"user_id\":5}")));
+        mocked_magic_link_token_repository
+            .expect_delete()
+            .with(function(|key: &str| key == "a1b2"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(true));
+        mocked_magic_link_token_repository
+            .expect_find()
+            .with(function(|key: &str| key == "a1b2"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Err(ServiceError::Unauthorized));
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|id| Ok(user(id)));
+
+        let mut mocked_user_key_repository = MockUserKeyRepositoryTrait::new();
+        mocked_user_key_repository
+            .expect_find_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|user_id| Ok(user_key(user_id)));
+
+        let mut auth_service = AuthService::new_with_repository(
+            MockLoginTokenRepositoryTrait::new(),
+            mocked_magic_link_token_repository,
+            mocked_user_key_repository,
+            mocked_user_repository,
+        )
+        .with_two_factor_service(two_factor_service(5, false));
+
+        let login = auth_service.login_with_magic_link("a1b2").unwrap();
+        assert_eq!(login.session.map(|session| session.user_id), Some(5));
+        assert!(login.two_factor_token.is_none());
+
+        assert!(matches!(
+            auth_service.login_with_magic_link("a1b2"),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn test_login_with_magic_link_used_meanwhile() {
+        let mut mocked_magic_link_token_repository = MockMagicLinkTokenRepositoryTrait::new();
+        mocked_magic_link_token_repository
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(String::from("{\"user_id\":5}")));
+        // Another request has deleted the token between finding and deleting it.
+        mocked_magic_link_token_repository
+            .expect_delete()
+            .times(1)
+            .returning(|_| Ok(false));
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository.expect_find_by_id().never();
+
+        let result = AuthService::new_with_repository(
+            MockLoginTokenRepositoryTrait::new(),
+            mocked_magic_link_token_repository,
+            MockUserKeyRepositoryTrait::new(),
+            mocked_user_repository,
+        )
+        .login_with_magic_link("a1b2");
+        assert!(matches!(result, Err(ServiceError::Unauthorized)));
+    }
+
+    #[test]
+    fn test_login_with_unknown_or_expired_magic_link() {
+        // An expired token has been removed from redis, as if it had never been issued.
+        let mut mocked_magic_link_token_repository = MockMagicLinkTokenRepositoryTrait::new();
+        mocked_magic_link_token_repository
+            .expect_find()
+            .with(function(|key: &str| key == "c3d4"))
+            .times(1)
+            .returning(|_| Err(ServiceError::Unauthorized));
+        mocked_magic_link_token_repository.expect_delete().never();
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository.expect_find_by_id().never();
+
+        let result = AuthService::new_with_repository(
+            MockLoginTokenRepositoryTrait::new(),
+            mocked_magic_link_token_repository,
+            MockUserKeyRepositoryTrait::new(),
+            mocked_user_repository,
+        )
+        .login_with_magic_link("c3d4");
+        assert!(matches!(result, Err(ServiceError::Unauthorized)));
+    }
+
+    #[test]
+    fn test_login_with_magic_link_and_two_factor() {
+        let mut mocked_magic_link_token_repository = MockMagicLinkTokenRepositoryTrait::new();
+        mocked_magic_link_token_repository
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(String::from("{\"user_id\":5}")));
+        mocked_magic_link_token_repository
+            .expect_delete()
+            .times(1)
+            .returning(|_| Ok(true));
+
+        let mut mocked_login_token_repository = MockLoginTokenRepositoryTrait::new();
+        mocked_login_token_repository
+            .expect_save()
+            .with(function(|token: &str| token == "{\"user_id\":5}"))
+            .times(1)
+            .returning(|_| Ok(String::from("e5f6")));
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|id| Ok(user(id)));
+
+        // The session is not issued until `login_with_two_factor` checks a code.
+        let mut mocked_user_key_repository = MockUserKeyRepositoryTrait::new();
+        mocked_user_key_repository.expect_find_by_user_id().never();
+
+        let login = AuthService::new_with_repository(
+            mocked_login_token_repository,
+            mocked_magic_link_token_repository,
+            mocked_user_key_repository,
+            mocked_user_repository,
+        )
+        .with_two_factor_service(two_factor_service(5, true))
+        .login_with_magic_link("a1b2")
+        .unwrap();
+        assert!(login.session.is_none());
+        assert_eq!(login.two_factor_token, Some(String::from("e5f6")));
     }
 }
@@ -0,0 +1,272 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use std::sync::Arc;
+
+use crate::models::calendar_feed::*;
+use crate::models::error::ServiceError;
+use crate::models::post::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+
+/// Length of tokens of calendar feeds, which is long enough not to be guessed.
+const CALENDAR_FEED_TOKEN_LENGTH: usize = 32;
+
+/// Summary of the events in calendar feeds, since titles of posts are encrypted by the client.
+const CALENDAR_EVENT_SUMMARY: &str = "Diary entry";
+
+/// Returns an iCalendar feed with an all-day event on each date.
+///
+/// # Arguments
+///
+/// * `user_id` - An id of the user, which makes ids of the events unique.
+/// * `dates` - Local dates of posts without duplicates.
+/// * `now` - When the feed is generated.
+fn to_icalendar(user_id: u64, dates: &[NaiveDate], now: &NaiveDateTime) -> String {
+    let mut lines = vec![
+        String::from("BEGIN:VCALENDAR"),
+        String::from("VERSION:2.0"),
+        String::from("PRODID:-//Darim//Diary dates//EN"),
+        String::from("CALSCALE:GREGORIAN"),
+        String::from("X-WR-CALNAME:Darim"),
+    ];
+    for date in dates {
+        lines.extend(vec![
+            String::from("BEGIN:VEVENT"),
+            format!("UID:{}-{}@darim", user_id, date.format("%Y%m%d")),
+            format!("DTSTAMP:{}", now.format("%Y%m%dT%H%M%SZ")),
+            format!("DTSTART;VALUE=DATE:{}", date.format("%Y%m%d")),
+            format!("DTEND;VALUE=DATE:{}", date.succ().format("%Y%m%d")),
+            format!("SUMMARY:{}", CALENDAR_EVENT_SUMMARY),
+            String::from("TRANSP:TRANSPARENT"),
+            String::from("END:VEVENT"),
+        ]);
+    }
+    lines.push(String::from("END:VCALENDAR"));
+
+    // Lines of iCalendar end with CRLF.
+    lines.into_iter().map(|line| line + "\r\n").collect()
+}
+
+pub struct CalendarFeedService {
+    calendar_feed_repository: Option<CalendarFeedRepository>,
+    post_repository: Option<PostRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl CalendarFeedService {
+    pub fn new() -> Self {
+        Self {
+            calendar_feed_repository: None,
+            post_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn calendar_feed_repository(
+        &mut self,
+        new_repository: Option<CalendarFeedRepository>,
+    ) -> &CalendarFeedRepository {
+        match new_repository {
+            Some(_) => {
+                self.calendar_feed_repository = new_repository;
+                self.calendar_feed_repository.as_ref().unwrap()
+            }
+            None => self.calendar_feed_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn post_repository(&mut self, new_repository: Option<PostRepository>) -> &PostRepository {
+        match new_repository {
+            Some(_) => {
+                self.post_repository = new_repository;
+                self.post_repository.as_ref().unwrap()
+            }
+            None => self.post_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Returns an iCalendar feed of the dates of posts written by the user of `token`.
+    ///
+    /// Each date with published posts is an all-day event. Titles of posts are encrypted,
+    /// so every event has the same summary.
+    pub fn get_calendar(&mut self, token: &str) -> Result<String, ServiceError> {
+        let feed = {
+            let fallback_repository = some_if_true!(self.calendar_feed_repository.is_none() => CalendarFeedRepository::new());
+            self.calendar_feed_repository(fallback_repository)
+                .find_by_token(token)?
+        };
+
+        let filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+        let date_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .find_local_dates(feed.user_id, &filter)?
+        };
+
+        Ok(to_icalendar(
+            feed.user_id,
+            &date_list,
+            &self.clock.now().naive_utc(),
+        ))
+    }
+
+    /// Creates a calendar feed of specific user with a new token, and returns the token.
+    ///
+    /// The previous feed of the user is revoked.
+    pub fn create(&mut self, user_id: u64) -> Result<CalendarFeedDTO, ServiceError> {
+        let token: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(CALENDAR_FEED_TOKEN_LENGTH)
+            .collect();
+
+        let fallback_repository =
+            some_if_true!(self.calendar_feed_repository.is_none() => CalendarFeedRepository::new());
+        self.calendar_feed_repository(fallback_repository)
+            .create(user_id, &token)?;
+        Ok(CalendarFeedDTO { token })
+    }
+
+    /// Revokes the calendar feed of specific user.
+    pub fn delete(&mut self, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.calendar_feed_repository.is_none() => CalendarFeedRepository::new());
+        self.calendar_feed_repository(fallback_repository)
+            .delete(user_id)
+    }
+}
+
+impl Default for CalendarFeedService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::calendar_feed::MockCalendarFeedRepositoryTrait as CalendarFeedRepository;
+#[cfg(test)]
+use crate::models::post::MockPostRepositoryTrait as PostRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::calendar_feed::MockCalendarFeedRepositoryTrait;
+    use crate::models::post::MockPostRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl CalendarFeedService {
+        pub fn new_with_repository(
+            calendar_feed_repository: CalendarFeedRepository,
+            post_repository: PostRepository,
+        ) -> Self {
+            Self {
+                calendar_feed_repository: Some(calendar_feed_repository),
+                post_repository: Some(post_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    #[test]
+    fn test_get_calendar() {
+        let mut mocked_calendar_feed_repository = MockCalendarFeedRepositoryTrait::new();
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let now = Utc.ymd(2020, 5, 1).and_hms(9, 0, 0);
+
+        mocked_calendar_feed_repository
+            .expect_find_by_token()
+            .with(eq("a1b2c3"))
+            .times(1)
+            .returning(move |token| {
+                Ok(CalendarFeed {
+                    user_id: 5,
+                    token: token.to_string(),
+                    created_at: now.naive_utc(),
+                })
+            });
+        mocked_post_repository
+            .expect_find_local_dates()
+            .with(
+                eq(5),
+                eq(PostFilter {
+                    status: Some(PostStatus::Published),
+                    ..PostFilter::default()
+                }),
+            )
+            .times(1)
+            .returning(|_, _| Ok(vec![NaiveDate::from_ymd(2020, 4, 30)]));
+
+        let mut calendar_feed_service = CalendarFeedService::new_with_repository(
+            mocked_calendar_feed_repository,
+            mocked_post_repository,
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(
+            calendar_feed_service.get_calendar("a1b2c3").unwrap(),
+            concat!(
+                "BEGIN:VCALENDAR\r\n",
+                "VERSION:2.0\r\n",
+                "PRODID:-//Darim//Diary dates//EN\r\n",
+                "CALSCALE:GREGORIAN\r\n",
+                "X-WR-CALNAME:Darim\r\n",
+                "BEGIN:VEVENT\r\n",
+                "UID:5-20200430@darim\r\n",
+                "DTSTAMP:20200501T090000Z\r\n",
+                "DTSTART;VALUE=DATE:20200430\r\n",
+                "DTEND;VALUE=DATE:20200501\r\n",
+                "SUMMARY:Diary entry\r\n",
+                "TRANSP:TRANSPARENT\r\n",
+                "END:VEVENT\r\n",
+                "END:VCALENDAR\r\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_calendar_with_unknown_token() {
+        let mut mocked_calendar_feed_repository = MockCalendarFeedRepositoryTrait::new();
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        mocked_calendar_feed_repository
+            .expect_find_by_token()
+            .returning(|_| Err(ServiceError::NotFound(String::from("calendar feed"))));
+        mocked_post_repository.expect_find_local_dates().times(0);
+
+        let mut calendar_feed_service = CalendarFeedService::new_with_repository(
+            mocked_calendar_feed_repository,
+            mocked_post_repository,
+        );
+
+        assert!(calendar_feed_service.get_calendar("unknown").is_err());
+    }
+
+    #[test]
+    fn test_create() {
+        let mut mocked_calendar_feed_repository = MockCalendarFeedRepositoryTrait::new();
+
+        mocked_calendar_feed_repository
+            .expect_create()
+            .with(eq(5), function(|token: &str| token.len() == 32))
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let mut calendar_feed_service = CalendarFeedService::new_with_repository(
+            mocked_calendar_feed_repository,
+            MockPostRepositoryTrait::new(),
+        );
+
+        assert_eq!(calendar_feed_service.create(5).unwrap().token.len(), 32);
+    }
+}
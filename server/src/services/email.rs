@@ -0,0 +1,323 @@
+use chrono::Duration;
+use std::sync::Arc;
+use std::thread;
+
+use crate::models::auth::*;
+use crate::models::email_job::*;
+use crate::models::error::ServiceError;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::email_util::{self, EmailSender};
+
+/// Maximum number of emails sent in a run.
+const BATCH_SIZE: i64 = 50;
+
+/// Number of attempts before an email is given up.
+const MAX_ATTEMPTS: u32 = 6;
+
+/// Minutes an email job is claimed while being sent.
+const CLAIM_LEASE_MINUTES: i64 = 5;
+
+/// Maximum length of the error recorded in `email_jobs` table.
+const MAX_ERROR_LENGTH: usize = 255;
+
+pub struct EmailService {
+    email_job_repository: Option<EmailJobRepository>,
+    token_repository: Option<TokenRepository>,
+    sender: Box<dyn EmailSender>,
+    clock: Arc<dyn Clock>,
+}
+
+impl EmailService {
+    pub fn new() -> Self {
+        Self {
+            email_job_repository: None,
+            token_repository: None,
+            sender: email_util::get_sender(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn email_job_repository(
+        &mut self,
+        new_repository: Option<EmailJobRepository>,
+    ) -> &EmailJobRepository {
+        match new_repository {
+            Some(_) => {
+                self.email_job_repository = new_repository;
+                self.email_job_repository.as_ref().unwrap()
+            }
+            None => self.email_job_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn token_repository(
+        &mut self,
+        new_repository: Option<TokenRepository>,
+    ) -> &mut TokenRepository {
+        match new_repository {
+            Some(_) => {
+                self.token_repository = new_repository;
+                self.token_repository.as_mut().unwrap()
+            }
+            None => self.token_repository.as_mut().unwrap(),
+        }
+    }
+
+    /// Returns minutes to wait before the next attempt, doubling on each failure.
+    fn get_backoff(attempts: u32) -> Duration {
+        Duration::minutes(1 << attempts.saturating_sub(1).min(10))
+    }
+
+    /// Enqueues an email, and returns whether it was enqueued.
+    ///
+    /// The email is sent in background and retried if the sender is unavailable,
+    /// so that the caller does not fail while the sender is down.
+    /// If the email contains a token, `token_key` is the key of the token,
+    /// whose validity starts when the email is sent.
+    ///
+    /// The email cannot be unsubscribed, so it must be one the user needs to receive.
+    pub fn enqueue(
+        &mut self,
+        to: &str,
+        subject: &str,
+        body: &str,
+        token_key: &Option<String>,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.email_job_repository.is_none() => EmailJobRepository::new());
+        self.email_job_repository(fallback_repository)
+            .create(to, subject, body, token_key, &None)
+    }
+
+    /// Sends enqueued emails in background without waiting for the next run of the scheduler.
+    pub fn send_soon() {
+        thread::spawn(|| {
+            let _ = EmailService::new().send_due_emails();
+        });
+    }
+
+    /// Sends enqueued emails that are due, and returns the number of sent emails.
+    ///
+    /// 1. Claims each due email, so that it is not sent twice by concurrent runs.
+    /// 2. If it is sent, starts expiry of its token and deletes it.
+    /// 3. If it fails, retries it later with backoff, or gives it up after `MAX_ATTEMPTS` attempts.
+    pub fn send_due_emails(&mut self) -> Result<usize, ServiceError> {
+        let now = self.clock.now().naive_utc();
+        let job_list = {
+            let fallback_repository =
+                some_if_true!(self.email_job_repository.is_none() => EmailJobRepository::new());
+            self.email_job_repository(fallback_repository)
+                .find_due(&now, BATCH_SIZE)?
+        };
+
+        let mut sent_count = 0;
+        for job in job_list {
+            let claimed = self.email_job_repository(None).claim(
+                job.id,
+                &now,
+                Duration::minutes(CLAIM_LEASE_MINUTES),
+            )?;
+            if !claimed {
+                continue;
+            }
+
+            match self.sender.send(
+                &job.recipient,
+                &job.subject,
+                &job.body,
+                &job.unsubscribe_url,
+            ) {
+                Ok(_) => {
+                    if let Some(token_key) = &job.token_key {
+                        let fallback_repository = some_if_true!(self.token_repository.is_none() => TokenRepository::new());
+                        let _ = self
+                            .token_repository(fallback_repository)
+                            .start_expiry(token_key);
+                    }
+                    self.email_job_repository(None).delete(job.id)?;
+                    sent_count += 1;
+                }
+                Err(error) => {
+                    let attempts = job.attempts + 1;
+                    let error: String = format!("{}", error)
+                        .chars()
+                        .take(MAX_ERROR_LENGTH)
+                        .collect();
+                    println!(
+                        "[{}] Failed to send email #{} (attempt {}): {}",
+                        now, job.id, attempts, error
+                    );
+
+                    if attempts >= MAX_ATTEMPTS {
+                        self.email_job_repository(None)
+                            .give_up(job.id, attempts, &error, &now)?;
+                    } else {
+                        let next_attempt_at = now + Self::get_backoff(attempts);
+                        self.email_job_repository(None).retry_later(
+                            job.id,
+                            attempts,
+                            &next_attempt_at,
+                            &error,
+                        )?;
+                    }
+                }
+            }
+        }
+
+        Ok(sent_count)
+    }
+
+    /// Counts emails waiting to be sent and ones given up.
+    pub fn get_count(&mut self) -> Result<EmailJobCountDTO, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.email_job_repository.is_none() => EmailJobRepository::new());
+        self.email_job_repository(fallback_repository).count()
+    }
+}
+
+impl Default for EmailService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::auth::MockTokenRepositoryTrait as TokenRepository;
+#[cfg(test)]
+use crate::models::email_job::MockEmailJobRepositoryTrait as EmailJobRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use super::*;
+    use crate::models::auth::MockTokenRepositoryTrait;
+    use crate::models::email_job::MockEmailJobRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl EmailService {
+        pub fn new_with_repository(
+            email_job_repository: EmailJobRepository,
+            token_repository: TokenRepository,
+            sender: Box<dyn EmailSender>,
+        ) -> Self {
+            Self {
+                email_job_repository: Some(email_job_repository),
+                token_repository: Some(token_repository),
+                sender,
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    /// Sender failing the first `failures` attempts.
+    struct FlakySender {
+        failures: u32,
+        attempts: AtomicU32,
+    }
+
+    impl EmailSender for FlakySender {
+        fn send(
+            &self,
+            to: &str,
+            _subject: &str,
+            _body: &str,
+            _unsubscribe_url: &Option<String>,
+        ) -> Result<bool, ServiceError> {
+            if self.attempts.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(ServiceError::EmailFailure(to.to_string()))
+            } else {
+                Ok(true)
+            }
+        }
+    }
+
+    #[test]
+    fn test_send_due_emails_with_retries() {
+        let mut mocked_email_job_repository = MockEmailJobRepositoryTrait::new();
+        let mut mocked_token_repository = MockTokenRepositoryTrait::new();
+
+        let started_at = Utc.ymd(2026, 10, 15).and_hms(9, 0, 0);
+        let clock = Arc::new(TestClock::new(started_at));
+        let attempts = Arc::new(AtomicU32::new(0));
+
+        let found_attempts = attempts.clone();
+        mocked_email_job_repository
+            .expect_find_due()
+            .times(3)
+            .returning(move |now, _| {
+                Ok(vec![EmailJob {
+                    id: 1,
+                    recipient: String::from("Park <park@example.com>"),
+                    subject: String::from("Please reset your password 🔒"),
+                    body: String::from("Hello :)"),
+                    token_key: Some(String::from("password_token:3")),
+                    unsubscribe_url: None,
+                    attempts: found_attempts.load(Ordering::SeqCst),
+                    next_attempt_at: *now,
+                    last_error: None,
+                    failed_at: None,
+                    created_at: *now,
+                }])
+            });
+        mocked_email_job_repository
+            .expect_claim()
+            .times(3)
+            .returning(|_, _, _| Ok(true));
+        let retried_attempts = attempts.clone();
+        mocked_email_job_repository
+            .expect_retry_later()
+            .times(2)
+            .returning(move |_, attempts, next_attempt_at, _| {
+                let expected_next_attempt_at = match attempts {
+                    1 => started_at + Duration::minutes(1),
+                    _ => started_at + Duration::minutes(3),
+                };
+                assert_eq!(*next_attempt_at, expected_next_attempt_at.naive_utc());
+                retried_attempts.store(attempts, Ordering::SeqCst);
+                Ok(true)
+            });
+        mocked_email_job_repository.expect_give_up().times(0);
+        mocked_email_job_repository
+            .expect_delete()
+            .with(eq(1))
+            .times(1)
+            .returning(|_| Ok(true));
+        mocked_token_repository
+            .expect_start_expiry()
+            .with(eq("password_token:3"))
+            .times(1)
+            .returning(|_| Ok(true));
+
+        let mut email_service = EmailService::new_with_repository(
+            mocked_email_job_repository,
+            mocked_token_repository,
+            Box::new(FlakySender {
+                failures: 2,
+                attempts: AtomicU32::new(0),
+            }),
+        )
+        .with_clock(clock.clone());
+
+        assert_eq!(email_service.send_due_emails().unwrap(), 0);
+        clock.advance(Duration::minutes(1));
+        assert_eq!(email_service.send_due_emails().unwrap(), 0);
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+        clock.advance(Duration::minutes(2));
+        assert_eq!(email_service.send_due_emails().unwrap(), 1);
+    }
+
+    #[test]
+    fn test_get_backoff() {
+        assert_eq!(EmailService::get_backoff(1), Duration::minutes(1));
+        assert_eq!(EmailService::get_backoff(2), Duration::minutes(2));
+        assert_eq!(EmailService::get_backoff(5), Duration::minutes(16));
+    }
+}
@@ -0,0 +1,1603 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs::{self, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use tar::{Builder, Header};
+
+use crate::models::attachment::*;
+use crate::models::auth::*;
+use crate::models::connection;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::export_job::*;
+use crate::models::login_history::*;
+use crate::models::post::*;
+use crate::models::storage::Storage;
+use crate::models::tag::*;
+use crate::models::user::*;
+use crate::models::user_settings::*;
+use crate::services::email::EmailService;
+use crate::services::user::get_avatar_key;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::url_util::PublicUrl;
+
+/// Number of posts loaded at once while writing an archive.
+const EXPORT_BATCH_SIZE: i64 = 100;
+
+/// Number of attachments loaded at once while writing an archive,
+/// which is small since the files are kept in memory until they are written.
+const ATTACHMENT_EXPORT_BATCH_SIZE: i64 = 5;
+
+/// Default number of posts that can be exported at once by `GET /export`,
+/// beyond which the archive is made by an export job instead.
+const DEFAULT_EXPORT_SYNC_MAX_POSTS: i64 = 1000;
+
+/// Default hours an export job and its archive are kept after it is created or finished.
+const DEFAULT_EXPORT_ARTIFACT_TTL_HOURS: i64 = 24;
+
+/// Number of export jobs run at once.
+const EXPORT_JOB_BATCH_SIZE: i64 = 5;
+
+/// Length of download tokens of export jobs.
+const DOWNLOAD_TOKEN_LENGTH: usize = 32;
+
+/// Maximum number of bytes of an archive sent at once while it is streamed or downloaded.
+const STREAM_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Name of the file ending an archive that failed in the middle.
+const INCOMPLETE_FILENAME: &str = "INCOMPLETE.txt";
+
+/// Steps of writing an archive, in order.
+enum ExportStep {
+    Account,
+    Tags,
+    Posts { offset: i64 },
+    Trash,
+    Attachments { offset: i64 },
+    LoginHistory,
+    Finish,
+    Done,
+}
+
+/// Account of a user in `account.json` of an account archive.
+///
+/// The password hash is not exported.
+#[derive(Serialize)]
+struct AccountRecord {
+    id: u64,
+    name: String,
+    email: String,
+    avatar_url: Option<String>,
+    created_at: NaiveDateTime,
+    updated_at: Option<NaiveDateTime>,
+    daily_word_goal: Option<u32>,
+    monthly_word_goal: Option<u32>,
+    key_metadata: Option<KeyMetadata>,
+    settings: Option<UserSettings>,
+}
+
+/// Sources of the data of an account besides posts and tags, which are archived by `export_account`.
+struct AccountSources {
+    user_repository: UserRepository,
+    user_settings_repository: UserSettingsRepository,
+    attachment_repository: AttachmentRepository,
+    login_history_repository: LoginHistoryRepository,
+    storage: Box<dyn Storage>,
+}
+
+/// Formats a value in the front matter, which is JSON and also valid YAML.
+fn to_front_matter_value<T: Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_else(|_| String::from("null"))
+}
+
+/// Returns a Markdown document of a post, whose front matter contains the metadata.
+///
+/// Title and content are written as they are stored, encrypted by the client.
+pub fn to_markdown(post: &Post, tag_ids: &[u64]) -> String {
+    let mut front_matter = vec![
+        format!("id: {}", post.id),
+        format!("title: {}", to_front_matter_value(&post.title)),
+        format!(
+            "date: {}",
+            to_front_matter_value(&post.post_date().to_rfc3339())
+        ),
+        format!("intra_day_order: {}", post.intra_day_order),
+        format!("tags: {}", to_front_matter_value(&tag_ids)),
+        format!("status: {}", to_front_matter_value(&post.status)),
+        format!("favorite: {}", post.is_favorite),
+        format!("mood: {}", to_front_matter_value(&post.mood)),
+        format!("weather: {}", to_front_matter_value(&post.weather)),
+        format!("latitude: {}", to_front_matter_value(&post.latitude)),
+        format!("longitude: {}", to_front_matter_value(&post.longitude)),
+        format!("place_name: {}", to_front_matter_value(&post.place_name)),
+        format!("encrypted: {}", post.is_encrypted),
+        format!(
+            "encryption_scheme: {}",
+            to_front_matter_value(&post.encryption_scheme)
+        ),
+        format!("nonce: {}", to_front_matter_value(&post.encryption_nonce)),
+        format!("key_version: {}", to_front_matter_value(&post.key_version)),
+        format!("created_at: {}", to_front_matter_value(&post.created_at)),
+        format!("updated_at: {}", to_front_matter_value(&post.updated_at)),
+        format!("version: {}", post.version),
+    ];
+    if let Some(deleted_at) = post.deleted_at {
+        front_matter.push(format!(
+            "deleted_at: {}",
+            to_front_matter_value(&deleted_at)
+        ));
+    }
+
+    format!(
+        "---\n{}\n---\n\n{}\n",
+        front_matter.join("\n"),
+        post.content
+    )
+}
+
+/// Gzipped tar archive of posts written by a user, which is written step by step as it is iterated.
+///
+/// The archive contains `tags.json`, a Markdown document per post in `posts/`,
+/// and posts in the trash in `trash/` if they are included.
+/// An archive of an account also contains `account.json`, `avatar.jpg` if there is an avatar,
+/// a file per attachment in `attachments/` with `attachments.json`, and `login_history.json`.
+/// Each step loads a bounded number of posts and yields the compressed bytes written so far
+/// in chunks of `STREAM_CHUNK_SIZE` at most, so that the memory use does not grow with the number
+/// of posts.
+pub struct PostArchive {
+    post_repository: PostRepository,
+    tag_repository: TagRepository,
+    user_id: u64,
+    include_trash: bool,
+    account: Option<AccountSources>,
+    attachment_list: Vec<AttachmentDTO>,
+    builder: Option<Builder<GzEncoder<Vec<u8>>>>,
+    step: ExportStep,
+    post_count: u32,
+    /// Bytes written by the last step, from `sent_length` on are not yielded yet.
+    written_bytes: Vec<u8>,
+    sent_length: usize,
+    is_started: bool,
+    /// Error of the step the archive failed at.
+    error: Option<ServiceError>,
+}
+
+impl PostArchive {
+    /// Returns the number of posts written so far, including posts in the trash.
+    pub fn post_count(&self) -> u32 {
+        self.post_count
+    }
+
+    /// Takes the error the archive failed at, if it is incomplete.
+    pub fn take_error(&mut self) -> Option<ServiceError> {
+        self.error.take()
+    }
+
+    /// Appends a file to the archive.
+    fn append(
+        &mut self,
+        path: &str,
+        data: &[u8],
+        mtime: NaiveDateTime,
+    ) -> Result<(), ServiceError> {
+        let mut header = Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(mtime.timestamp().max(0) as u64);
+        header.set_cksum();
+
+        let builder = self
+            .builder
+            .as_mut()
+            .ok_or_else(|| get_service_error(ServiceError::InternalServerError))?;
+        builder
+            .append_data(&mut header, path, data)
+            .map_err(|_| get_service_error(ServiceError::InternalServerError))
+    }
+
+    /// Appends posts to the archive as Markdown documents in `directory`.
+    fn append_posts(&mut self, directory: &str, post_list: &[Post]) -> Result<(), ServiceError> {
+        let post_ids: Vec<u64> = post_list.iter().map(|post| post.id).collect();
+        let mut tag_ids: HashMap<u64, Vec<u64>> = self.post_repository.find_tag_ids(&post_ids)?;
+
+        for post in post_list {
+            let path = format!(
+                "{}/{}-{}.md",
+                directory,
+                post.post_date().local_date(),
+                post.id
+            );
+            let markdown = to_markdown(post, &tag_ids.remove(&post.id).unwrap_or_default());
+            let mtime = post.updated_at.unwrap_or(post.created_at);
+            self.append(&path, markdown.as_bytes(), mtime)?;
+            self.post_count += 1;
+        }
+        Ok(())
+    }
+
+    /// Returns the sources of the account, which exist only in an archive of an account.
+    fn account(&self) -> Result<&AccountSources, ServiceError> {
+        self.account
+            .as_ref()
+            .ok_or_else(|| get_service_error(ServiceError::InternalServerError))
+    }
+
+    /// Returns the step after posts in the trash.
+    fn step_after_trash(&self) -> ExportStep {
+        if self.account.is_some() {
+            ExportStep::Attachments { offset: 0 }
+        } else {
+            ExportStep::Finish
+        }
+    }
+
+    /// Flushes the compressor and takes the compressed bytes written so far.
+    ///
+    /// The bytes end at a flush point, so that the files sent before a broken connection
+    /// can be decompressed, while the missing end of the archive tells that it is incomplete.
+    fn take_written_bytes(&mut self) -> Result<Vec<u8>, ServiceError> {
+        match self.builder.as_mut() {
+            Some(builder) => {
+                let encoder = builder.get_mut();
+                encoder
+                    .flush()
+                    .map_err(|_| get_service_error(ServiceError::InternalServerError))?;
+                Ok(std::mem::take(encoder.get_mut()))
+            }
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Ends the archive after a step failed, with `INCOMPLETE_FILENAME` telling the failure,
+    /// so that the archive stays valid but is told incomplete.
+    fn finish_incomplete(&mut self, error: &ServiceError) -> Vec<u8> {
+        let message = format!(
+            "The export failed in the middle, and the files after this are missing: {}\n",
+            error
+        );
+        let _ = self.append(
+            INCOMPLETE_FILENAME,
+            message.as_bytes(),
+            Utc::now().naive_utc(),
+        );
+
+        match self.builder.take() {
+            Some(mut builder) => {
+                let mut bytes = std::mem::take(builder.get_mut().get_mut());
+                if let Ok(rest) = builder.into_inner().and_then(|encoder| encoder.finish()) {
+                    bytes.extend(rest);
+                }
+                bytes
+            }
+            None => Vec::new(),
+        }
+    }
+
+    /// Writes the current step, moves to the next step, and returns the bytes written.
+    fn write_step(&mut self) -> Result<Vec<u8>, ServiceError> {
+        match self.step {
+            ExportStep::Account => {
+                let (record, avatar) = {
+                    let account = self.account()?;
+                    let user = account.user_repository.find_by_id(self.user_id)?;
+                    let settings = account
+                        .user_settings_repository
+                        .find_by_user_id(self.user_id)?;
+                    let avatar = match account.storage.get(&get_avatar_key(self.user_id)) {
+                        Ok(avatar) => Some(avatar),
+                        Err(ServiceError::NotFound(_)) => None,
+                        Err(error) => return Err(error),
+                    };
+                    let record = AccountRecord {
+                        id: user.id,
+                        name: user.name,
+                        email: user.email,
+                        avatar_url: user.avatar_url,
+                        created_at: user.created_at,
+                        updated_at: user.updated_at,
+                        daily_word_goal: user.daily_word_goal,
+                        monthly_word_goal: user.monthly_word_goal,
+                        key_metadata: user
+                            .key_metadata
+                            .and_then(|key_metadata| serde_json::from_str(&key_metadata).ok()),
+                        settings,
+                    };
+                    (record, avatar)
+                };
+
+                let data = serde_json::to_vec_pretty(&record)
+                    .map_err(|_| get_service_error(ServiceError::InternalServerError))?;
+                let now = Utc::now().naive_utc();
+                self.append("account.json", &data, now)?;
+                if let Some(avatar) = avatar {
+                    self.append("avatar.jpg", &avatar, now)?;
+                }
+                self.step = ExportStep::Tags;
+            }
+            ExportStep::Tags => {
+                let tag_list: Vec<TagDTO> = self
+                    .tag_repository
+                    .find_all(self.user_id)?
+                    .into_iter()
+                    .map(|tag| TagDTO {
+                        id: tag.id,
+                        name: tag.name,
+                        created_at: tag.created_at,
+                        updated_at: tag.updated_at,
+                    })
+                    .collect();
+                let data = serde_json::to_vec_pretty(&tag_list)
+                    .map_err(|_| get_service_error(ServiceError::InternalServerError))?;
+                self.append("tags.json", &data, Utc::now().naive_utc())?;
+                self.step = ExportStep::Posts { offset: 0 };
+            }
+            ExportStep::Posts { offset } => {
+                let post_list = self.post_repository.find_list(
+                    self.user_id,
+                    &PostFilter::default(),
+                    PostFields::All,
+                    PostSortKey::Date,
+                    SortOrder::Asc,
+                    &Some((offset, EXPORT_BATCH_SIZE)),
+                )?;
+                self.append_posts("posts", &post_list)?;
+                self.step = if (post_list.len() as i64) < EXPORT_BATCH_SIZE {
+                    if self.include_trash {
+                        ExportStep::Trash
+                    } else {
+                        self.step_after_trash()
+                    }
+                } else {
+                    ExportStep::Posts {
+                        offset: offset + EXPORT_BATCH_SIZE,
+                    }
+                };
+            }
+            ExportStep::Trash => {
+                let post_list = self.post_repository.find_all_trashed(self.user_id)?;
+                self.append_posts("trash", &post_list)?;
+                self.step = self.step_after_trash();
+            }
+            ExportStep::Attachments { offset } => {
+                let file_list = {
+                    let account = self.account()?;
+                    let mut file_list = vec![];
+                    for (attachment, blob) in account.attachment_repository.find_all_by_user_id(
+                        self.user_id,
+                        offset,
+                        ATTACHMENT_EXPORT_BATCH_SIZE,
+                    )? {
+                        let data = account.storage.get(&blob.hash)?;
+                        file_list.push((attachment, blob, data));
+                    }
+                    file_list
+                };
+
+                let is_last_batch = (file_list.len() as i64) < ATTACHMENT_EXPORT_BATCH_SIZE;
+                for (attachment, blob, data) in file_list {
+                    let path = format!("attachments/{}-{}", attachment.id, attachment.filename);
+                    self.append(&path, &data, attachment.created_at)?;
+                    self.attachment_list.push(AttachmentDTO {
+                        id: attachment.id,
+                        post_id: attachment.post_id,
+                        filename: attachment.filename,
+                        mime_type: blob.mime_type,
+                        size: blob.size,
+                        created_at: attachment.created_at,
+                    });
+                }
+
+                if is_last_batch {
+                    let data = serde_json::to_vec_pretty(&self.attachment_list)
+                        .map_err(|_| get_service_error(ServiceError::InternalServerError))?;
+                    self.append("attachments.json", &data, Utc::now().naive_utc())?;
+                    self.step = ExportStep::LoginHistory;
+                } else {
+                    self.step = ExportStep::Attachments {
+                        offset: offset + ATTACHMENT_EXPORT_BATCH_SIZE,
+                    };
+                }
+            }
+            ExportStep::LoginHistory => {
+                let history: Vec<LoginHistoryDTO> = {
+                    let login_history_repository = &self.account()?.login_history_repository;
+                    let count = login_history_repository.count_by_user_id(self.user_id)?;
+                    login_history_repository
+                        .find_all_by_user_id(self.user_id, 0, count)?
+                        .into_iter()
+                        .map(|login| LoginHistoryDTO {
+                            id: login.id,
+                            ip: login.ip,
+                            country: login.country,
+                            user_agent: login.user_agent,
+                            created_at: login.created_at,
+                        })
+                        .collect()
+                };
+                let data = serde_json::to_vec_pretty(&history)
+                    .map_err(|_| get_service_error(ServiceError::InternalServerError))?;
+                self.append("login_history.json", &data, Utc::now().naive_utc())?;
+                self.step = ExportStep::Finish;
+            }
+            ExportStep::Finish => {
+                self.step = ExportStep::Done;
+                let builder = self
+                    .builder
+                    .take()
+                    .ok_or_else(|| get_service_error(ServiceError::InternalServerError))?;
+                return builder
+                    .into_inner()
+                    .and_then(|encoder| encoder.finish())
+                    .map_err(|_| get_service_error(ServiceError::InternalServerError));
+            }
+            ExportStep::Done => return Ok(Vec::new()),
+        }
+
+        self.take_written_bytes()
+    }
+}
+
+impl Iterator for PostArchive {
+    type Item = Result<Vec<u8>, ServiceError>;
+
+    /// Returns the next chunk of the compressed bytes, writing the next step if all are yielded.
+    ///
+    /// If the first step fails, it returns the error, so that nothing is sent.
+    /// If a later step fails, the archive ends without the remaining steps
+    /// with `INCOMPLETE_FILENAME`, and the error is kept for `take_error`.
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.sent_length >= self.written_bytes.len() {
+            if let ExportStep::Done = self.step {
+                return None;
+            }
+
+            self.written_bytes = match self.write_step() {
+                Ok(bytes) => bytes,
+                Err(error) if !self.is_started => {
+                    self.step = ExportStep::Done;
+                    return Some(Err(error));
+                }
+                Err(error) => {
+                    self.step = ExportStep::Done;
+                    let bytes = self.finish_incomplete(&error);
+                    self.error = Some(error);
+                    bytes
+                }
+            };
+            self.sent_length = 0;
+        }
+
+        let end = (self.sent_length + STREAM_CHUNK_SIZE).min(self.written_bytes.len());
+        let chunk = self.written_bytes[self.sent_length..end].to_vec();
+        self.sent_length = end;
+        self.is_started = true;
+        Some(Ok(chunk))
+    }
+}
+
+/// Archive written by an export job or a range of it, which is read from the artifact directory
+/// as it is iterated.
+///
+/// Once the last byte of the archive is read, the download token is revoked and the file is deleted,
+/// so that the archive is downloaded only once. Until then, a broken download can be resumed
+/// by requesting the rest of the archive as a range.
+pub struct ExportArtifact {
+    export_job_repository: ExportJobRepository,
+    job_id: u64,
+    download_token: String,
+    format: ExportFormat,
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+    /// The first position and the number of bytes of the range being read.
+    first: u64,
+    length: u64,
+    is_range: bool,
+    remaining_length: u64,
+}
+
+impl ExportArtifact {
+    /// Returns the name of the file the archive is downloaded as.
+    pub fn filename(&self) -> String {
+        format!("darim-export.{}", self.format.as_str())
+    }
+
+    /// Returns the number of bytes to be read.
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+
+    /// Returns `Content-Range` header of the range, if only a range of the archive is read.
+    pub fn content_range(&self) -> Option<String> {
+        if !self.is_range {
+            return None;
+        }
+        Some(format!(
+            "bytes {}-{}/{}",
+            self.first,
+            self.first + self.length - 1,
+            self.size
+        ))
+    }
+
+    /// Reads the next chunk of the range, and finishes the download once the archive ends.
+    fn read_chunk(&mut self) -> Result<Vec<u8>, ServiceError> {
+        let file = self
+            .file
+            .as_mut()
+            .ok_or_else(|| get_service_error(ServiceError::InternalServerError))?;
+        let mut chunk = vec![0; self.remaining_length.min(STREAM_CHUNK_SIZE as u64) as usize];
+        file.read_exact(&mut chunk)
+            .map_err(|_| get_service_error(ServiceError::InternalServerError))?;
+        self.remaining_length -= chunk.len() as u64;
+
+        if self.remaining_length == 0 {
+            self.file = None;
+            if self.first + self.length == self.size {
+                // The whole archive has been sent, so a failure here leaves the file to expire instead.
+                let downloaded = self
+                    .export_job_repository
+                    .mark_downloaded(self.job_id, &self.download_token)
+                    .unwrap_or(false);
+                if downloaded {
+                    let _ = fs::remove_file(&self.path);
+                }
+            }
+        }
+        Ok(chunk)
+    }
+}
+
+impl Iterator for ExportArtifact {
+    type Item = Result<Vec<u8>, ServiceError>;
+
+    /// Reads the next chunk of the archive.
+    /// The download ends without the remaining chunks if reading fails.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.file.as_ref()?;
+
+        match self.read_chunk() {
+            Ok(chunk) if chunk.is_empty() => None,
+            Ok(chunk) => Some(Ok(chunk)),
+            Err(error) => {
+                self.file = None;
+                Some(Err(error))
+            }
+        }
+    }
+}
+
+/// Returns the directory archives of export jobs are kept in, set by `EXPORT_ARTIFACT_DIRECTORY`.
+fn get_artifact_directory() -> PathBuf {
+    env::var("EXPORT_ARTIFACT_DIRECTORY")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| env::temp_dir().join("darim-exports"))
+}
+
+/// Returns the path of the archive of an export job in `directory`.
+fn get_artifact_path(directory: &Path, job: &ExportJob) -> PathBuf {
+    directory.join(format!("{}.{}", job.id, job.format))
+}
+
+pub struct ExportService {
+    post_repository: Option<PostRepository>,
+    tag_repository: Option<TagRepository>,
+    user_repository: Option<UserRepository>,
+    account_export_token_repository: Option<AccountExportTokenRepository>,
+    export_job_repository: Option<ExportJobRepository>,
+    clock: Arc<dyn Clock>,
+    artifact_directory: PathBuf,
+}
+
+impl ExportService {
+    pub fn new() -> Self {
+        Self {
+            post_repository: None,
+            tag_repository: None,
+            user_repository: None,
+            account_export_token_repository: None,
+            export_job_repository: None,
+            clock: Arc::new(SystemClock),
+            artifact_directory: get_artifact_directory(),
+        }
+    }
+
+    /// Returns the number of posts that can be exported at once, set by `EXPORT_SYNC_MAX_POSTS`.
+    fn get_sync_max_posts() -> i64 {
+        env::var("EXPORT_SYNC_MAX_POSTS")
+            .ok()
+            .and_then(|count| count.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_EXPORT_SYNC_MAX_POSTS)
+    }
+
+    /// Returns how long export jobs and their archives are kept, set by `EXPORT_ARTIFACT_TTL_HOURS`.
+    fn get_artifact_ttl() -> Duration {
+        let hours = env::var("EXPORT_ARTIFACT_TTL_HOURS")
+            .ok()
+            .and_then(|hours| hours.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_EXPORT_ARTIFACT_TTL_HOURS);
+        Duration::hours(hours)
+    }
+
+    fn post_repository(&mut self, new_repository: Option<PostRepository>) -> &PostRepository {
+        match new_repository {
+            Some(_) => {
+                self.post_repository = new_repository;
+                self.post_repository.as_ref().unwrap()
+            }
+            None => self.post_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn export_job_repository(
+        &mut self,
+        new_repository: Option<ExportJobRepository>,
+    ) -> &ExportJobRepository {
+        match new_repository {
+            Some(_) => {
+                self.export_job_repository = new_repository;
+                self.export_job_repository.as_ref().unwrap()
+            }
+            None => self.export_job_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_repository = new_repository;
+                self.user_repository.as_ref().unwrap()
+            }
+            None => self.user_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn account_export_token_repository(
+        &mut self,
+        new_repository: Option<AccountExportTokenRepository>,
+    ) -> &mut AccountExportTokenRepository {
+        match new_repository {
+            Some(_) => {
+                self.account_export_token_repository = new_repository;
+                self.account_export_token_repository.as_mut().unwrap()
+            }
+            None => self.account_export_token_repository.as_mut().unwrap(),
+        }
+    }
+
+    /// Returns an archive of all posts written by specific user, which is written as it is iterated.
+    ///
+    /// If `include_trash` is true, posts in the trash are also archived with `deleted_at`.
+    pub fn export(mut self, user_id: u64, include_trash: bool) -> PostArchive {
+        self.new_archive(user_id, include_trash)
+    }
+
+    /// Returns an archive like `export`, if the posts are few enough to be exported at once.
+    ///
+    /// If there are more than `EXPORT_SYNC_MAX_POSTS` posts, it fails with `PayloadTooLarge`,
+    /// and the archive should be made by `create_job` instead.
+    pub fn export_now(
+        mut self,
+        user_id: u64,
+        include_trash: bool,
+    ) -> Result<PostArchive, ServiceError> {
+        if self.count_posts(user_id, include_trash)? > Self::get_sync_max_posts() {
+            return Err(get_service_error(ServiceError::PayloadTooLarge));
+        }
+        Ok(self.export(user_id, include_trash))
+    }
+
+    /// Returns the number of posts of specific user to be archived.
+    fn count_posts(&mut self, user_id: u64, include_trash: bool) -> Result<i64, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        let post_repository = self.post_repository(fallback_repository);
+        let mut count = post_repository.count(user_id, &PostFilter::default())?;
+        if include_trash {
+            count += post_repository.find_all_trashed(user_id)?.len() as i64;
+        }
+        Ok(count)
+    }
+
+    /// Returns an archive taking the repositories of the service, or new ones if they are taken.
+    fn new_archive(&mut self, user_id: u64, include_trash: bool) -> PostArchive {
+        PostArchive {
+            post_repository: self
+                .post_repository
+                .take()
+                .unwrap_or_else(PostRepository::new),
+            tag_repository: self
+                .tag_repository
+                .take()
+                .unwrap_or_else(TagRepository::new),
+            user_id,
+            include_trash,
+            account: None,
+            attachment_list: Vec::new(),
+            builder: Some(Builder::new(GzEncoder::new(
+                Vec::new(),
+                Compression::default(),
+            ))),
+            step: ExportStep::Tags,
+            post_count: 0,
+            written_bytes: Vec::new(),
+            sent_length: 0,
+            is_started: false,
+            error: None,
+        }
+    }
+
+    /// Returns an archive of everything of specific user, which is written as it is iterated.
+    ///
+    /// Besides posts including the trash, it contains the account with the settings,
+    /// the avatar, attachments, and login history.
+    pub fn export_account(mut self, user_id: u64) -> PostArchive {
+        let user_repository = self
+            .user_repository
+            .take()
+            .unwrap_or_else(UserRepository::new);
+        let mut archive = self.export(user_id, true);
+        archive.account = Some(AccountSources {
+            user_repository,
+            user_settings_repository: UserSettingsRepository::new(),
+            attachment_repository: AttachmentRepository::new(),
+            login_history_repository: LoginHistoryRepository::new(),
+            storage: connection::connect_storage(),
+        });
+        archive.step = ExportStep::Account;
+        archive
+    }
+
+    /// Emails a link downloading the archive of the account to specific user.
+    ///
+    /// The email is sent by the email job in background, and the link is valid for
+    /// `ACCOUNT_EXPORT_TOKEN_TTL_SECONDS` from the request.
+    pub fn request_account_export(&mut self, user_id: u64) -> Result<bool, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+
+        let token = AccountExportToken { user_id: user.id };
+        let serialized_token = if let Ok(serialized_token) = serde_json::to_string(&token) {
+            serialized_token
+        } else {
+            return Err(get_service_error(ServiceError::InvalidFormat));
+        };
+
+        let key = {
+            let fallback_repository = some_if_true!(self.account_export_token_repository.is_none() => AccountExportTokenRepository::new());
+            self.account_export_token_repository(fallback_repository)
+                .save(&serialized_token)?
+        };
+
+        let account_export_url = PublicUrl::from_env()
+            .expect("Invalid PUBLIC_BASE_URL")
+            .account_export_url(&key);
+        let content = format!(
+            "Hello :)<br/><br/>\
+            The archive of your Darim account is ready. Please visit the link to download it:<br/><br/>\
+            <a href=\"{}\">{}</a><br/><br/>\
+            The link is valid for a day.",
+            account_export_url, account_export_url,
+        );
+        // The token is not passed to the email job, which would shorten its validity once sent.
+        EmailService::new().enqueue(
+            &format!("{} <{}>", user.name, user.email),
+            &String::from("Your Darim archive is ready 📦"),
+            &content,
+            &None,
+        )?;
+        EmailService::send_soon();
+
+        Ok(true)
+    }
+
+    /// Returns an archive of the account of an account export token, which is written as it is iterated.
+    pub fn export_account_by_token(mut self, key: &str) -> Result<PostArchive, ServiceError> {
+        let serialized_token = {
+            let fallback_repository = some_if_true!(self.account_export_token_repository.is_none() => AccountExportTokenRepository::new());
+            self.account_export_token_repository(fallback_repository)
+                .find(key)?
+        };
+        let token: AccountExportToken = serde_json::from_str(&serialized_token)
+            .map_err(|_| get_service_error(ServiceError::InvalidFormat))?;
+
+        Ok(self.export_account(token.user_id))
+    }
+
+    /// Returns export job DTO of a job, with the download URL if the archive can be downloaded.
+    fn to_job_dto(job: ExportJob) -> ExportJobDTO {
+        let download_url = job.download_token.as_ref().map(|download_token| {
+            PublicUrl::from_env()
+                .expect("Invalid PUBLIC_BASE_URL")
+                .export_download_url(download_token)
+        });
+        ExportJobDTO {
+            id: job.id,
+            format: job.format,
+            include_trash: job.include_trash,
+            status: job.status,
+            progress: job.progress,
+            total: job.total,
+            size: job.size,
+            download_url,
+            expires_at: job.expires_at,
+            created_at: job.created_at,
+            finished_at: job.finished_at,
+        }
+    }
+
+    /// Enqueues an export job making an archive of posts written by specific user in `format`.
+    ///
+    /// The job is run by `run_jobs` in background, and kept for `EXPORT_ARTIFACT_TTL_HOURS`.
+    pub fn create_job(
+        &mut self,
+        user_id: u64,
+        format: &str,
+        include_trash: bool,
+    ) -> Result<ExportJobDTO, ServiceError> {
+        let format = ExportFormat::parse(format)?;
+        let total = self.count_posts(user_id, include_trash)? as u32;
+        let expires_at = self.clock.now().naive_utc() + Self::get_artifact_ttl();
+
+        let fallback_repository =
+            some_if_true!(self.export_job_repository.is_none() => ExportJobRepository::new());
+        let export_job_repository = self.export_job_repository(fallback_repository);
+        let id =
+            export_job_repository.create(user_id, format, include_trash, total, &expires_at)?;
+        let job = export_job_repository.find_by_id(user_id, id)?;
+        Ok(Self::to_job_dto(job))
+    }
+
+    /// Returns an export job of specific user.
+    pub fn get_job(&mut self, user_id: u64, id: u64) -> Result<ExportJobDTO, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.export_job_repository.is_none() => ExportJobRepository::new());
+        let job = self
+            .export_job_repository(fallback_repository)
+            .find_by_id(user_id, id)?;
+        Ok(Self::to_job_dto(job))
+    }
+
+    /// Runs pending export jobs in background without waiting for the next run of the scheduler.
+    pub fn run_soon() {
+        thread::spawn(|| {
+            let _ = ExportService::new().run_jobs();
+        });
+    }
+
+    /// Runs pending export jobs, and returns the number of finished jobs.
+    ///
+    /// 1. Claims each pending job, so that it is not run twice by concurrent runs.
+    /// 2. Writes the archive to the artifact directory, updating the progress as posts are written.
+    /// 3. Issues the download token, or marks the job as failed if the archive is not written.
+    pub fn run_jobs(&mut self) -> Result<usize, ServiceError> {
+        let job_list = {
+            let fallback_repository =
+                some_if_true!(self.export_job_repository.is_none() => ExportJobRepository::new());
+            self.export_job_repository(fallback_repository)
+                .find_pending(EXPORT_JOB_BATCH_SIZE)?
+        };
+
+        let mut finished_count = 0;
+        for job in job_list {
+            if !self.export_job_repository(None).claim(job.id)? {
+                continue;
+            }
+
+            let size = self.write_artifact(&job);
+            let now = self.clock.now().naive_utc();
+            match size {
+                Ok(size) => {
+                    let download_token: String = thread_rng()
+                        .sample_iter(&Alphanumeric)
+                        .take(DOWNLOAD_TOKEN_LENGTH)
+                        .collect();
+                    self.export_job_repository(None).finish(
+                        job.id,
+                        size,
+                        &download_token,
+                        &now,
+                        &(now + Self::get_artifact_ttl()),
+                    )?;
+                    finished_count += 1;
+                }
+                Err(error) => {
+                    println!("[{}] Failed to run export job #{}: {}", now, job.id, error);
+                    self.export_job_repository(None).fail(job.id, &now)?;
+                }
+            }
+        }
+
+        Ok(finished_count)
+    }
+
+    /// Writes the archive of an export job to the artifact directory, and returns the size of it.
+    ///
+    /// It is written to a temporary file first, so that a partial archive is never downloaded.
+    fn write_artifact(&mut self, job: &ExportJob) -> Result<u64, ServiceError> {
+        let path = get_artifact_path(&self.artifact_directory, job);
+        let temporary_path = PathBuf::from(format!("{}.part", path.display()));
+
+        let result = self.write_archive(job, &temporary_path).and_then(|size| {
+            fs::rename(&temporary_path, &path)
+                .map(|_| size)
+                .map_err(|_| get_service_error(ServiceError::InternalServerError))
+        });
+        if result.is_err() {
+            let _ = fs::remove_file(&temporary_path);
+        }
+        result
+    }
+
+    /// Writes the archive of an export job to `path`, and returns the size of it.
+    fn write_archive(&mut self, job: &ExportJob, path: &Path) -> Result<u64, ServiceError> {
+        let to_internal_error = |_| get_service_error(ServiceError::InternalServerError);
+        fs::create_dir_all(&self.artifact_directory).map_err(to_internal_error)?;
+        let mut file = File::create(path).map_err(to_internal_error)?;
+
+        let mut archive = self.new_archive(job.user_id, job.include_trash);
+        let mut size = 0;
+        let mut progress = 0;
+        while let Some(chunk) = archive.next() {
+            let chunk = chunk?;
+            file.write_all(&chunk).map_err(to_internal_error)?;
+            size += chunk.len() as u64;
+
+            if archive.post_count() != progress {
+                progress = archive.post_count();
+                self.export_job_repository(None)
+                    .update_progress(job.id, progress)?;
+            }
+        }
+        if let Some(error) = archive.take_error() {
+            return Err(error);
+        }
+        file.sync_all().map_err(to_internal_error)?;
+
+        Ok(size)
+    }
+
+    /// Returns the archive of an export job downloaded with `download_token`, or `range` of it.
+    ///
+    /// The token is valid until the last byte of the archive is downloaded, or until the job expires.
+    /// If no byte of the archive is in `range`, it fails with `RangeNotSatisfiable`.
+    pub fn download(
+        mut self,
+        download_token: &str,
+        range: Option<ByteRange>,
+    ) -> Result<ExportArtifact, ServiceError> {
+        let job = {
+            let fallback_repository =
+                some_if_true!(self.export_job_repository.is_none() => ExportJobRepository::new());
+            self.export_job_repository(fallback_repository)
+                .find_by_download_token(download_token)?
+        };
+        let not_found = || get_service_error(ServiceError::NotFound(download_token.to_string()));
+        if job.status != ExportJobStatus::Done.as_str()
+            || job.expires_at <= self.clock.now().naive_utc()
+        {
+            return Err(not_found());
+        }
+
+        let format = ExportFormat::parse(&job.format)?;
+        let path = get_artifact_path(&self.artifact_directory, &job);
+        let mut file = File::open(&path).map_err(|_| not_found())?;
+        let size = file.metadata().map_err(|_| not_found())?.len();
+
+        let (first, length) = match range {
+            Some(range) => match range.resolve(size) {
+                Some((first, last)) => (first, last - first + 1),
+                None => return Err(get_service_error(ServiceError::RangeNotSatisfiable(size))),
+            },
+            None => (0, size),
+        };
+        file.seek(SeekFrom::Start(first))
+            .map_err(|_| get_service_error(ServiceError::InternalServerError))?;
+
+        Ok(ExportArtifact {
+            export_job_repository: self.export_job_repository.take().unwrap(),
+            job_id: job.id,
+            download_token: download_token.to_string(),
+            format,
+            path,
+            file: Some(file),
+            size,
+            first,
+            length,
+            is_range: range.is_some(),
+            remaining_length: length,
+        })
+    }
+
+    /// Deletes expired export jobs with their archives, and archives no job refers to anymore.
+    ///
+    /// It returns the number of deleted jobs.
+    pub fn prune_jobs(&mut self) -> Result<usize, ServiceError> {
+        // Files are listed before the jobs, so that the archive of a job claimed meanwhile is kept.
+        let path_list: Vec<PathBuf> = match fs::read_dir(&self.artifact_directory) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+
+        let now = self.clock.now().naive_utc();
+        let fallback_repository =
+            some_if_true!(self.export_job_repository.is_none() => ExportJobRepository::new());
+        let export_job_repository = self.export_job_repository(fallback_repository);
+        let count = export_job_repository.delete_all_expired(&now)?;
+        let ids: HashSet<u64> = export_job_repository
+            .find_artifact_ids()?
+            .into_iter()
+            .collect();
+
+        for path in path_list {
+            let id = path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .and_then(|name| name.split('.').next())
+                .and_then(|id| id.parse::<u64>().ok());
+            if !id.map_or(false, |id| ids.contains(&id)) {
+                let _ = fs::remove_file(&path);
+            }
+        }
+
+        Ok(count)
+    }
+}
+
+impl Default for ExportService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::auth::MockAccountExportTokenRepositoryTrait as AccountExportTokenRepository;
+#[cfg(test)]
+use crate::models::export_job::MockExportJobRepositoryTrait as ExportJobRepository;
+#[cfg(test)]
+use crate::models::post::MockPostRepositoryTrait as PostRepository;
+#[cfg(test)]
+use crate::models::tag::MockTagRepositoryTrait as TagRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use flate2::read::GzDecoder;
+    use mockall::predicate::*;
+    use tar::Archive;
+
+    use super::*;
+    use crate::models::auth::MockAccountExportTokenRepositoryTrait;
+    use crate::models::export_job::MockExportJobRepositoryTrait;
+    use crate::models::post::MockPostRepositoryTrait;
+    use crate::models::tag::MockTagRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl ExportService {
+        pub fn new_with_repository(
+            post_repository: PostRepository,
+            tag_repository: TagRepository,
+        ) -> Self {
+            Self {
+                post_repository: Some(post_repository),
+                tag_repository: Some(tag_repository),
+                user_repository: None,
+                account_export_token_repository: None,
+                export_job_repository: None,
+                clock: Arc::new(SystemClock),
+                artifact_directory: get_artifact_directory(),
+            }
+        }
+
+        pub fn with_export_job_repository(
+            mut self,
+            export_job_repository: ExportJobRepository,
+        ) -> Self {
+            self.export_job_repository = Some(export_job_repository);
+            self
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+
+        pub fn with_artifact_directory(mut self, artifact_directory: PathBuf) -> Self {
+            self.artifact_directory = artifact_directory;
+            self
+        }
+
+        pub fn with_account_export_token_repository(
+            mut self,
+            account_export_token_repository: AccountExportTokenRepository,
+        ) -> Self {
+            self.account_export_token_repository = Some(account_export_token_repository);
+            self
+        }
+    }
+
+    fn post(id: u64, user_id: u64, deleted_at: Option<NaiveDateTime>) -> Post {
+        let date = PostDate::parse("2020-04-12T16:43:03+09:00").unwrap();
+        Post {
+            id,
+            user_id,
+            title: String::from("U2FsdGVkX1"),
+            content: String::from("U2FsdGVkX2"),
+            date: date.date,
+            date_offset: date.offset,
+            intra_day_order: 0,
+            created_at: date.date,
+            updated_at: None,
+            version: 1,
+            deleted_at,
+            status: String::from("published"),
+            autosave_started_at: None,
+            changed_at: date.date,
+            is_favorite: false,
+            mood: None,
+            weather: None,
+            latitude: None,
+            longitude: None,
+            place_name: None,
+            journal_id: 1,
+            word_count: None,
+            is_locked: false,
+            is_encrypted: true,
+            encryption_scheme: None,
+            encryption_nonce: None,
+            key_version: None,
+        }
+    }
+
+    fn export_job(id: u64, status: ExportJobStatus, expires_at: NaiveDateTime) -> ExportJob {
+        ExportJob {
+            id,
+            user_id: 5,
+            format: String::from("tar.gz"),
+            include_trash: false,
+            status: status.as_str().to_string(),
+            progress: 0,
+            total: 1,
+            size: None,
+            download_token: None,
+            expires_at,
+            created_at: expires_at - Duration::hours(24),
+            finished_at: None,
+        }
+    }
+
+    /// Returns an empty directory for archives of a test.
+    fn artifact_directory(name: &str) -> PathBuf {
+        let directory = env::temp_dir().join(format!("darim-export-test-{}", name));
+        let _ = fs::remove_dir_all(&directory);
+        fs::create_dir_all(&directory).unwrap();
+        directory
+    }
+
+    /// Unpacks an archive, and returns the content of each file by the path.
+    fn unpack(bytes: &[u8]) -> HashMap<String, String> {
+        let mut files: HashMap<String, String> = HashMap::new();
+        let mut archive = Archive::new(GzDecoder::new(bytes));
+        for entry in archive.entries().unwrap() {
+            let mut entry = entry.unwrap();
+            let path = entry.path().unwrap().to_string_lossy().to_string();
+            let mut data = String::new();
+            entry.read_to_string(&mut data).unwrap();
+            files.insert(path, data);
+        }
+        files
+    }
+
+    #[test]
+    fn test_export() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_tag_repository = MockTagRepositoryTrait::new();
+
+        let user_id = 5;
+
+        mocked_tag_repository
+            .expect_find_all()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+        mocked_post_repository
+            .expect_find_list()
+            .times(1)
+            .returning(|user_id, _, _, _, _, _| Ok(vec![post(1, user_id, None)]));
+        mocked_post_repository
+            .expect_find_all_trashed()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|user_id| {
+                let deleted_at = Utc::now().naive_utc();
+                Ok(vec![post(2, user_id, Some(deleted_at))])
+            });
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(2)
+            .returning(|_| Ok(vec![(1, vec![3])].into_iter().collect()));
+
+        let archive =
+            ExportService::new_with_repository(mocked_post_repository, mocked_tag_repository)
+                .export(user_id, true);
+        let bytes: Vec<u8> = archive
+            .collect::<Result<Vec<Vec<u8>>, ServiceError>>()
+            .unwrap()
+            .concat();
+
+        let files = unpack(&bytes);
+        assert_eq!(files.len(), 3);
+        assert_eq!(files.get("tags.json").unwrap(), "[]");
+
+        let markdown = files.get("posts/2020-04-12-1.md").unwrap();
+        assert!(markdown.starts_with("---\nid: 1\ntitle: \"U2FsdGVkX1\"\n"));
+        assert!(markdown.contains("\ntags: [3]\n"));
+        assert!(markdown.contains("\nfavorite: false\n"));
+        assert!(markdown.ends_with("---\n\nU2FsdGVkX2\n"));
+
+        assert!(files
+            .get("trash/2020-04-12-2.md")
+            .unwrap()
+            .contains("\ndeleted_at: "));
+    }
+
+    #[test]
+    fn test_export_account_by_unknown_token() {
+        let mut mocked_account_export_token_repository =
+            MockAccountExportTokenRepositoryTrait::new();
+        mocked_account_export_token_repository
+            .expect_find()
+            .with(eq("a1b2"))
+            .times(1)
+            .returning(|key| Err(get_service_error(ServiceError::NotFound(key.to_string()))));
+
+        let result = ExportService::new_with_repository(
+            MockPostRepositoryTrait::new(),
+            MockTagRepositoryTrait::new(),
+        )
+        .with_account_export_token_repository(mocked_account_export_token_repository)
+        .export_account_by_token("a1b2");
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_export_failed_in_the_middle() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_tag_repository = MockTagRepositoryTrait::new();
+        mocked_tag_repository
+            .expect_find_all()
+            .times(1)
+            .returning(|_| Ok(vec![]));
+        mocked_post_repository
+            .expect_find_list()
+            .times(1)
+            .returning(|_, _, _, _, _, _| {
+                Err(get_service_error(ServiceError::QueryExecutionFailure))
+            });
+
+        let mut archive =
+            ExportService::new_with_repository(mocked_post_repository, mocked_tag_repository)
+                .export(5, false);
+        let bytes: Vec<u8> = archive
+            .by_ref()
+            .collect::<Result<Vec<Vec<u8>>, ServiceError>>()
+            .unwrap()
+            .concat();
+        assert!(matches!(
+            archive.take_error(),
+            Some(ServiceError::QueryExecutionFailure)
+        ));
+
+        let files = unpack(&bytes);
+        assert_eq!(files.len(), 2);
+        assert_eq!(files.get("tags.json").unwrap(), "[]");
+        assert!(files
+            .get(INCOMPLETE_FILENAME)
+            .unwrap()
+            .contains("query execution failure"));
+    }
+
+    #[test]
+    fn test_export_now_too_many_posts() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        mocked_post_repository
+            .expect_count()
+            .with(eq(5), eq(PostFilter::default()))
+            .times(1)
+            .returning(|_, _| Ok(DEFAULT_EXPORT_SYNC_MAX_POSTS + 1));
+
+        let result = ExportService::new_with_repository(
+            mocked_post_repository,
+            MockTagRepositoryTrait::new(),
+        )
+        .export_now(5, false);
+        assert!(matches!(result, Err(ServiceError::PayloadTooLarge)));
+    }
+
+    #[test]
+    fn test_create_job() {
+        env::set_var("PUBLIC_BASE_URL", "https://darim.vercel.app");
+        let now = Utc.ymd(2020, 5, 1).and_hms(9, 0, 0);
+        let expires_at = now.naive_utc() + Duration::hours(DEFAULT_EXPORT_ARTIFACT_TTL_HOURS);
+
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        mocked_post_repository
+            .expect_count()
+            .times(1)
+            .returning(|_, _| Ok(1));
+        let mut mocked_export_job_repository = MockExportJobRepositoryTrait::new();
+        mocked_export_job_repository
+            .expect_create()
+            .with(
+                eq(5),
+                eq(ExportFormat::TarGz),
+                eq(false),
+                eq(1),
+                eq(expires_at),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _| Ok(3));
+        mocked_export_job_repository
+            .expect_find_by_id()
+            .with(eq(5), eq(3))
+            .times(1)
+            .returning(move |_, id| Ok(export_job(id, ExportJobStatus::Pending, expires_at)));
+
+        let job = ExportService::new_with_repository(
+            mocked_post_repository,
+            MockTagRepositoryTrait::new(),
+        )
+        .with_export_job_repository(mocked_export_job_repository)
+        .with_clock(Arc::new(TestClock::new(now)))
+        .create_job(5, "tar.gz", false)
+        .unwrap();
+        assert_eq!(job.id, 3);
+        assert_eq!(job.status, "pending");
+        assert!(job.download_url.is_none());
+
+        let result = ExportService::new_with_repository(
+            MockPostRepositoryTrait::new(),
+            MockTagRepositoryTrait::new(),
+        )
+        .create_job(5, "zip", false);
+        assert!(matches!(result, Err(ServiceError::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_run_jobs() {
+        let directory = artifact_directory("run");
+        let now = Utc.ymd(2020, 5, 1).and_hms(9, 0, 0);
+        let expires_at = now.naive_utc() + Duration::hours(DEFAULT_EXPORT_ARTIFACT_TTL_HOURS);
+
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_tag_repository = MockTagRepositoryTrait::new();
+        mocked_tag_repository
+            .expect_find_all()
+            .times(1)
+            .returning(|_| Ok(vec![]));
+        mocked_post_repository
+            .expect_find_list()
+            .times(1)
+            .returning(|user_id, _, _, _, _, _| Ok(vec![post(1, user_id, None)]));
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(1)
+            .returning(|_| Ok(HashMap::new()));
+
+        let mut mocked_export_job_repository = MockExportJobRepositoryTrait::new();
+        mocked_export_job_repository
+            .expect_find_pending()
+            .times(1)
+            .returning(move |_| Ok(vec![export_job(3, ExportJobStatus::Pending, expires_at)]));
+        mocked_export_job_repository
+            .expect_claim()
+            .with(eq(3))
+            .times(1)
+            .returning(|_| Ok(true));
+        mocked_export_job_repository
+            .expect_update_progress()
+            .with(eq(3), eq(1))
+            .times(1)
+            .returning(|_, _| Ok(true));
+        let artifact_path = directory.join("3.tar.gz");
+        mocked_export_job_repository
+            .expect_finish()
+            .withf(move |id, size, download_token, _, job_expires_at| {
+                *id == 3
+                    && *size == fs::metadata(&artifact_path).unwrap().len()
+                    && download_token.len() == DOWNLOAD_TOKEN_LENGTH
+                    && *job_expires_at == expires_at
+            })
+            .times(1)
+            .returning(|_, _, _, _, _| Ok(true));
+
+        let finished_count =
+            ExportService::new_with_repository(mocked_post_repository, mocked_tag_repository)
+                .with_export_job_repository(mocked_export_job_repository)
+                .with_clock(Arc::new(TestClock::new(now)))
+                .with_artifact_directory(directory.clone())
+                .run_jobs()
+                .unwrap();
+        assert_eq!(finished_count, 1);
+
+        let files = unpack(&fs::read(directory.join("3.tar.gz")).unwrap());
+        assert!(files.contains_key("posts/2020-04-12-1.md"));
+        assert!(!directory.join("3.tar.gz.part").exists());
+    }
+
+    /// Returns a service downloading the archive of job `id` with token `a1b2`,
+    /// which expects the archive to be downloaded to the end `finished_times` times.
+    fn download_service(directory: &Path, id: u64, finished_times: usize) -> ExportService {
+        let now = Utc.ymd(2020, 5, 1).and_hms(9, 0, 0);
+        let expires_at = now.naive_utc() + Duration::hours(1);
+
+        let mut mocked_export_job_repository = MockExportJobRepositoryTrait::new();
+        mocked_export_job_repository
+            .expect_find_by_download_token()
+            .with(eq("a1b2"))
+            .times(1)
+            .returning(move |download_token| {
+                let mut job = export_job(id, ExportJobStatus::Done, expires_at);
+                job.download_token = Some(download_token.to_string());
+                Ok(job)
+            });
+        mocked_export_job_repository
+            .expect_mark_downloaded()
+            .with(eq(id), eq("a1b2"))
+            .times(finished_times)
+            .returning(|_, _| Ok(true));
+
+        ExportService::new_with_repository(
+            MockPostRepositoryTrait::new(),
+            MockTagRepositoryTrait::new(),
+        )
+        .with_export_job_repository(mocked_export_job_repository)
+        .with_clock(Arc::new(TestClock::new(now)))
+        .with_artifact_directory(directory.to_path_buf())
+    }
+
+    /// Reads a download to the end, checking that each chunk is bounded.
+    fn read_download(download: ExportArtifact) -> Vec<u8> {
+        download
+            .map(|chunk| chunk.unwrap())
+            .inspect(|chunk| assert!(chunk.len() <= STREAM_CHUNK_SIZE))
+            .collect::<Vec<Vec<u8>>>()
+            .concat()
+    }
+
+    #[test]
+    fn test_download() {
+        let directory = artifact_directory("download");
+        let artifact = b"darim archive".to_vec();
+        fs::write(directory.join("3.tar.gz"), &artifact).unwrap();
+
+        let download = download_service(&directory, 3, 1)
+            .download("a1b2", None)
+            .unwrap();
+        assert_eq!(download.filename(), "darim-export.tar.gz");
+        assert_eq!(read_download(download), artifact);
+        assert!(!directory.join("3.tar.gz").exists());
+    }
+
+    #[test]
+    fn test_download_in_ranges() {
+        let directory = artifact_directory("range");
+        let artifact: Vec<u8> = (0..150_000u32).map(|i| (i * 31 % 251) as u8).collect();
+        fs::write(directory.join("3.tar.gz"), &artifact).unwrap();
+        fs::write(directory.join("4.tar.gz"), &artifact).unwrap();
+
+        let full_download = download_service(&directory, 3, 1)
+            .download("a1b2", None)
+            .unwrap();
+        assert_eq!(full_download.length(), 150_000);
+        assert!(full_download.content_range().is_none());
+        let full_bytes = read_download(full_download);
+        assert_eq!(full_bytes, artifact);
+
+        let result = download_service(&directory, 4, 0)
+            .download("a1b2", Some(ByteRange::From(150_000, None)));
+        assert!(matches!(
+            result,
+            Err(ServiceError::RangeNotSatisfiable(150_000))
+        ));
+
+        let first_download = download_service(&directory, 4, 0)
+            .download("a1b2", Some(ByteRange::From(0, Some(99_999))))
+            .unwrap();
+        assert_eq!(first_download.length(), 100_000);
+        assert_eq!(
+            first_download.content_range().unwrap(),
+            "bytes 0-99999/150000"
+        );
+        let first_bytes = read_download(first_download);
+        assert!(directory.join("4.tar.gz").exists());
+
+        let rest_download = download_service(&directory, 4, 1)
+            .download("a1b2", Some(ByteRange::From(100_000, None)))
+            .unwrap();
+        assert_eq!(
+            rest_download.content_range().unwrap(),
+            "bytes 100000-149999/150000"
+        );
+        let rest_bytes = read_download(rest_download);
+
+        assert_eq!([first_bytes, rest_bytes].concat(), full_bytes);
+        assert!(!directory.join("3.tar.gz").exists());
+        assert!(!directory.join("4.tar.gz").exists());
+    }
+
+    #[test]
+    fn test_download_expired() {
+        let now = Utc.ymd(2020, 5, 1).and_hms(9, 0, 0);
+        let expires_at = now.naive_utc();
+
+        let mut mocked_export_job_repository = MockExportJobRepositoryTrait::new();
+        mocked_export_job_repository
+            .expect_find_by_download_token()
+            .times(1)
+            .returning(move |download_token| {
+                let mut job = export_job(3, ExportJobStatus::Done, expires_at);
+                job.download_token = Some(download_token.to_string());
+                Ok(job)
+            });
+
+        let result = ExportService::new_with_repository(
+            MockPostRepositoryTrait::new(),
+            MockTagRepositoryTrait::new(),
+        )
+        .with_export_job_repository(mocked_export_job_repository)
+        .with_clock(Arc::new(TestClock::new(now)))
+        .download("a1b2", None);
+        assert!(matches!(result, Err(ServiceError::NotFound(_))));
+    }
+
+    #[test]
+    fn test_prune_jobs() {
+        let directory = artifact_directory("prune");
+        fs::write(directory.join("3.tar.gz"), b"kept").unwrap();
+        fs::write(directory.join("4.tar.gz"), b"expired").unwrap();
+        fs::write(directory.join("5.tar.gz.part"), b"deleted user").unwrap();
+        let now = Utc.ymd(2020, 5, 1).and_hms(9, 0, 0);
+
+        let mut mocked_export_job_repository = MockExportJobRepositoryTrait::new();
+        mocked_export_job_repository
+            .expect_delete_all_expired()
+            .with(eq(now.naive_utc()))
+            .times(1)
+            .returning(|_| Ok(1));
+        mocked_export_job_repository
+            .expect_find_artifact_ids()
+            .times(1)
+            .returning(|| Ok(vec![3]));
+
+        let count = ExportService::new_with_repository(
+            MockPostRepositoryTrait::new(),
+            MockTagRepositoryTrait::new(),
+        )
+        .with_export_job_repository(mocked_export_job_repository)
+        .with_clock(Arc::new(TestClock::new(now)))
+        .with_artifact_directory(directory.clone())
+        .prune_jobs()
+        .unwrap();
+        assert_eq!(count, 1);
+
+        assert!(directory.join("3.tar.gz").exists());
+        assert!(!directory.join("4.tar.gz").exists());
+        assert!(!directory.join("5.tar.gz.part").exists());
+    }
+}
@@ -0,0 +1,576 @@
+use chrono::NaiveDateTime;
+use flate2::read::GzDecoder;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use tar::Archive;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::post::*;
+use crate::models::post_audit::AuditContext;
+use crate::models::tag::*;
+
+/// Number of posts created in a transaction while importing an archive.
+const IMPORT_BATCH_SIZE: usize = 100;
+
+/// Front matter of a Markdown document written by `export::to_markdown`.
+///
+/// The other fields such as `id` and `version` are ignored, because they are given again.
+#[derive(Deserialize)]
+struct FrontMatter {
+    title: String,
+    date: String,
+    #[serde(default)]
+    tags: Vec<u64>,
+    /// Status of the post, which is missing in archives written before drafts.
+    status: Option<String>,
+    /// Whether the post is a favorite, which is missing in archives written before favorites.
+    #[serde(default)]
+    favorite: bool,
+    mood: Option<u8>,
+    weather: Option<String>,
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    place_name: Option<String>,
+    /// Encryption of the post, which is missing in archives written before it was recorded.
+    encrypted: Option<bool>,
+    encryption_scheme: Option<String>,
+    nonce: Option<String>,
+    key_version: Option<u32>,
+    deleted_at: Option<NaiveDateTime>,
+}
+
+/// Tag in `tags.json` of an archive.
+#[derive(Deserialize)]
+struct ArchivedTag {
+    id: u64,
+    name: String,
+}
+
+/// Parses a Markdown document written by `export::to_markdown` into a post.
+///
+/// Tag ids of the post are the ids in the archive.
+pub fn from_markdown(markdown: &str) -> Result<PostToImport, ServiceError> {
+    let invalid_format = || get_service_error(ServiceError::InvalidFormat);
+
+    let document = markdown.strip_prefix("---\n").ok_or_else(invalid_format)?;
+    let front_matter_end = document.find("\n---\n").ok_or_else(invalid_format)?;
+    let front_matter = &document[..front_matter_end];
+    let content = &document[front_matter_end + "\n---\n".len()..];
+
+    let mut fields = Map::new();
+    for line in front_matter.lines() {
+        let mut key_and_value = line.splitn(2, ": ");
+        match (key_and_value.next(), key_and_value.next()) {
+            (Some(key), Some(value)) => {
+                let value: Value = serde_json::from_str(value).map_err(|_| invalid_format())?;
+                fields.insert(key.to_string(), value);
+            }
+            _ => return Err(invalid_format()),
+        }
+    }
+    let front_matter: FrontMatter =
+        serde_json::from_value(Value::Object(fields)).map_err(|_| invalid_format())?;
+
+    let content = content.strip_prefix('\n').unwrap_or(content);
+    let content = content.strip_suffix('\n').unwrap_or(content);
+
+    Ok(PostToImport {
+        title: front_matter.title,
+        content: content.to_string(),
+        date: PostDate::parse(&front_matter.date)?,
+        tag_ids: front_matter.tags,
+        status: match front_matter.status {
+            Some(status) => PostStatus::parse(&status)?,
+            None => PostStatus::Published,
+        },
+        is_favorite: front_matter.favorite,
+        mood: match front_matter.mood {
+            Some(mood) if mood < MIN_MOOD || mood > MAX_MOOD => return Err(invalid_format()),
+            mood => mood,
+        },
+        weather: match front_matter.weather {
+            Some(weather) => Some(PostWeather::parse(&weather)?),
+            None => None,
+        },
+        location: PostLocation::parse(&PostLocationDTO {
+            latitude: front_matter.latitude,
+            longitude: front_matter.longitude,
+            place_name: front_matter.place_name,
+        })?,
+        deleted_at: front_matter.deleted_at,
+        encryption: PostEncryption::parse(&PostEncryptionDTO {
+            encrypted: front_matter.encrypted,
+            encryption_scheme: front_matter.encryption_scheme,
+            nonce: front_matter.nonce,
+            key_version: front_matter.key_version,
+        })?
+        .unwrap_or_else(PostEncryption::legacy),
+    })
+}
+
+/// Reads paths and contents of the files in a gzipped tar archive.
+///
+/// A file which is not UTF-8 text has an error instead of its content.
+fn read_archive(
+    archive: &[u8],
+) -> Result<Vec<(String, Result<String, ServiceError>)>, ServiceError> {
+    let mut archive = Archive::new(GzDecoder::new(archive));
+    let entries = archive
+        .entries()
+        .map_err(|_| get_service_error(ServiceError::InvalidFormat))?;
+
+    let mut files = Vec::new();
+    for entry in entries {
+        let mut entry = entry.map_err(|_| get_service_error(ServiceError::InvalidFormat))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let path = entry
+            .path()
+            .map_err(|_| get_service_error(ServiceError::InvalidFormat))?
+            .to_string_lossy()
+            .to_string();
+        let mut data = String::new();
+        let data = match entry.read_to_string(&mut data) {
+            Ok(_) => Ok(data),
+            Err(_) => Err(get_service_error(ServiceError::InvalidFormat)),
+        };
+        files.push((path, data));
+    }
+    Ok(files)
+}
+
+/// Parses a file of posts in an archive, which is in `posts/` or in `trash/` with `deleted_at`.
+fn parse_post_file(
+    path: &str,
+    data: Result<String, ServiceError>,
+) -> Result<PostToImport, ServiceError> {
+    if !path.ends_with(".md") {
+        return Err(get_service_error(ServiceError::InvalidFormat));
+    }
+
+    let mut post = from_markdown(&data?)?;
+    if path.starts_with("posts/") {
+        post.deleted_at = None;
+    } else if !path.starts_with("trash/") || post.deleted_at.is_none() {
+        return Err(get_service_error(ServiceError::InvalidFormat));
+    }
+    Ok(post)
+}
+
+pub struct ImportService {
+    post_repository: Option<PostRepository>,
+    tag_repository: Option<TagRepository>,
+}
+
+impl ImportService {
+    pub fn new() -> Self {
+        Self {
+            post_repository: None,
+            tag_repository: None,
+        }
+    }
+
+    fn post_repository(&mut self, new_repository: Option<PostRepository>) -> &PostRepository {
+        match new_repository {
+            Some(_) => {
+                self.post_repository = new_repository;
+                self.post_repository.as_ref().unwrap()
+            }
+            None => self.post_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn tag_repository(&mut self, new_repository: Option<TagRepository>) -> &TagRepository {
+        match new_repository {
+            Some(_) => {
+                self.tag_repository = new_repository;
+                self.tag_repository.as_ref().unwrap()
+            }
+            None => self.tag_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Maps ids of tags in an archive to ids of tags of specific user, and returns the map
+    /// with the number of tags created.
+    ///
+    /// A tag is mapped to the tag of the same name, or created if there is no such tag.
+    /// If `dry_run` is true, tags to be created are counted but not mapped.
+    fn map_tags(
+        &mut self,
+        user_id: u64,
+        archived_tags: &[ArchivedTag],
+        dry_run: bool,
+    ) -> Result<(HashMap<u64, u64>, usize), ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.tag_repository.is_none() => TagRepository::new());
+        let tag_repository = self.tag_repository(fallback_repository);
+
+        let mut tag_ids: HashMap<String, u64> = tag_repository
+            .find_all(user_id)?
+            .into_iter()
+            .map(|tag| (tag.name, tag.id))
+            .collect();
+
+        let mut tag_id_map = HashMap::new();
+        let mut created_tag_count = 0;
+        for archived_tag in archived_tags {
+            if let Some(tag_id) = tag_ids.get(&archived_tag.name) {
+                tag_id_map.insert(archived_tag.id, *tag_id);
+                continue;
+            }
+
+            created_tag_count += 1;
+            if !dry_run {
+                let tag_id = tag_repository.create(user_id, &archived_tag.name)?;
+                tag_ids.insert(archived_tag.name.clone(), tag_id);
+                tag_id_map.insert(archived_tag.id, tag_id);
+            }
+        }
+        Ok((tag_id_map, created_tag_count))
+    }
+
+    /// Creates posts of specific user from an archive written by `ExportService::export`,
+    /// and reports the result of each file.
+    ///
+    /// 1. Reads `tags.json`, and maps the tags to tags of the user by name, creating missing ones.
+    /// 2. Reads Markdown documents in `posts/`, and in `trash/` which are created in the trash.
+    ///    Other files fail.
+    /// 3. Skips posts of the same date and title as a post of the user, including posts in the
+    ///    trash, or as a previous post in the archive.
+    /// 4. Creates the rest in batches. If a batch fails, all posts of the batch fail.
+    ///
+    /// Tags of posts missing in `tags.json` are dropped.
+    /// If `dry_run` is true, reports the result without creating anything, and without ids of posts.
+    pub fn import(
+        &mut self,
+        user_id: u64,
+        archive: &[u8],
+        audit_context: &AuditContext,
+        dry_run: bool,
+    ) -> Result<PostImportDTO, ServiceError> {
+        let mut archived_tags = Vec::new();
+        let mut files = Vec::new();
+        let mut archived_posts = Vec::new();
+        for (path, data) in read_archive(archive)? {
+            if path == "tags.json" {
+                archived_tags = serde_json::from_str::<Vec<ArchivedTag>>(&data?)
+                    .map_err(|_| get_service_error(ServiceError::InvalidFormat))?;
+                continue;
+            }
+
+            let (status, error) = match parse_post_file(&path, data) {
+                Ok(post) => {
+                    archived_posts.push((files.len(), post));
+                    (ImportedFileStatus::Created, None)
+                }
+                Err(error) => (ImportedFileStatus::Failed, Some(format!("{}", error))),
+            };
+            files.push(ImportedFileDTO {
+                path,
+                status,
+                post_id: None,
+                error,
+            });
+        }
+
+        let (tag_id_map, created_tag_count) = self.map_tags(user_id, &archived_tags, dry_run)?;
+
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        let post_repository = self.post_repository(fallback_repository);
+
+        let mut post_keys: HashSet<(NaiveDateTime, String)> = post_repository
+            .find_all(user_id)?
+            .into_iter()
+            .chain(post_repository.find_all_trashed(user_id)?)
+            .map(|post| (post.date, post.title))
+            .collect();
+
+        let mut posts_to_import = Vec::new();
+        for (index, mut post) in archived_posts {
+            if !post_keys.insert((post.date.date, post.title.clone())) {
+                files[index].status = ImportedFileStatus::Duplicated;
+                continue;
+            }
+
+            post.tag_ids = post
+                .tag_ids
+                .iter()
+                .filter_map(|tag_id| tag_id_map.get(tag_id).copied())
+                .collect();
+            posts_to_import.push((index, post));
+        }
+
+        for batch in posts_to_import.chunks(IMPORT_BATCH_SIZE) {
+            let post_list: Vec<PostToImport> = batch.iter().map(|(_, post)| post.clone()).collect();
+            match post_repository.import(user_id, &post_list, audit_context, dry_run) {
+                Ok(post_ids) => {
+                    for ((index, _), post_id) in batch.iter().zip(post_ids) {
+                        files[*index].post_id = some_if_true!(!dry_run => post_id);
+                    }
+                }
+                Err(error) => {
+                    for (index, _) in batch {
+                        files[*index].status = ImportedFileStatus::Failed;
+                        files[*index].error = Some(format!("{}", error));
+                    }
+                }
+            }
+        }
+
+        let count =
+            |status: ImportedFileStatus| files.iter().filter(|file| file.status == status).count();
+        Ok(PostImportDTO {
+            dry_run,
+            created_count: count(ImportedFileStatus::Created),
+            duplicated_count: count(ImportedFileStatus::Duplicated),
+            failed_count: count(ImportedFileStatus::Failed),
+            created_tag_count,
+            files,
+        })
+    }
+}
+
+impl Default for ImportService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::post::MockPostRepositoryTrait as PostRepository;
+#[cfg(test)]
+use crate::models::tag::MockTagRepositoryTrait as TagRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use mockall::predicate::*;
+    use tar::{Builder, Header};
+
+    use super::*;
+    use crate::models::post::MockPostRepositoryTrait;
+    use crate::models::tag::MockTagRepositoryTrait;
+    use crate::services::export::to_markdown;
+
+    impl ImportService {
+        pub fn new_with_repository(
+            post_repository: PostRepository,
+            tag_repository: TagRepository,
+        ) -> Self {
+            Self {
+                post_repository: Some(post_repository),
+                tag_repository: Some(tag_repository),
+            }
+        }
+    }
+
+    fn post(id: u64, user_id: u64, title: &str, deleted_at: Option<NaiveDateTime>) -> Post {
+        let date = PostDate::parse("2020-04-12T16:43:03+09:00").unwrap();
+        Post {
+            id,
+            user_id,
+            title: String::from(title),
+            content: String::from("U2FsdGVkX2"),
+            date: date.date,
+            date_offset: date.offset,
+            intra_day_order: 0,
+            created_at: date.date,
+            updated_at: None,
+            version: 1,
+            deleted_at,
+            status: String::from("published"),
+            autosave_started_at: None,
+            changed_at: date.date,
+            is_favorite: false,
+            mood: None,
+            weather: None,
+            latitude: None,
+            longitude: None,
+            place_name: None,
+            journal_id: 1,
+            word_count: None,
+            is_locked: false,
+            is_encrypted: true,
+            encryption_scheme: None,
+            encryption_nonce: None,
+            key_version: None,
+        }
+    }
+
+    fn archive(files: &[(&str, String)]) -> Vec<u8> {
+        let mut builder = Builder::new(GzEncoder::new(Vec::new(), Compression::default()));
+        for (path, data) in files {
+            let mut header = Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, data.as_bytes())
+                .unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap()
+    }
+
+    #[test]
+    fn test_from_markdown() {
+        let deleted_at = Utc::now().naive_utc();
+        let mut exported_post = post(1, 5, "U2FsdGVkX1", Some(deleted_at));
+        exported_post.is_favorite = true;
+        exported_post.mood = Some(4);
+        exported_post.weather = Some(String::from("rainy"));
+        exported_post.latitude = Some(37.5665);
+        exported_post.longitude = Some(126.978);
+        exported_post.place_name = Some(String::from("U2FsdGVkX3"));
+        exported_post.encryption_scheme = Some(String::from("aes-256-gcm"));
+        exported_post.encryption_nonce = Some(String::from("bm9uY2Vub25jZQ=="));
+        exported_post.key_version = Some(2);
+
+        let post = from_markdown(&to_markdown(&exported_post, &[3])).unwrap();
+
+        assert_eq!(
+            post,
+            PostToImport {
+                date: exported_post.post_date(),
+                title: exported_post.title,
+                content: exported_post.content,
+                tag_ids: vec![3],
+                status: PostStatus::Published,
+                is_favorite: true,
+                mood: Some(4),
+                weather: Some(PostWeather::Rainy),
+                location: Some(PostLocation {
+                    latitude: 37.5665,
+                    longitude: 126.978,
+                    place_name: Some(String::from("U2FsdGVkX3")),
+                }),
+                deleted_at: Some(deleted_at),
+                encryption: PostEncryption {
+                    is_encrypted: true,
+                    scheme: Some(String::from("aes-256-gcm")),
+                    nonce: Some(String::from("bm9uY2Vub25jZQ==")),
+                    key_version: Some(2),
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_import() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_tag_repository = MockTagRepositoryTrait::new();
+
+        let user_id = 5;
+        let deleted_at = Utc::now().naive_utc();
+        let archive = archive(&[
+            (
+                "tags.json",
+                String::from(r#"[{"id": 3, "name": "U2FsdGVkX3"}]"#),
+            ),
+            (
+                "posts/2020-04-12-1.md",
+                to_markdown(&post(1, user_id, "U2FsdGVkX1", None), &[3]),
+            ),
+            (
+                "posts/2020-04-12-2.md",
+                to_markdown(&post(2, user_id, "U2FsdGVkX4", None), &[]),
+            ),
+            (
+                "trash/2020-04-12-3.md",
+                to_markdown(&post(3, user_id, "U2FsdGVkX5", Some(deleted_at)), &[]),
+            ),
+            ("notes.txt", String::from("Lorem ipsum")),
+        ]);
+
+        mocked_tag_repository
+            .expect_find_all()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+        mocked_tag_repository
+            .expect_create()
+            .with(eq(user_id), eq("U2FsdGVkX3"))
+            .times(1)
+            .returning(|_, _| Ok(7));
+        mocked_post_repository
+            .expect_find_all()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|user_id| Ok(vec![post(9, user_id, "U2FsdGVkX4", None)]));
+        mocked_post_repository
+            .expect_find_all_trashed()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+        mocked_post_repository
+            .expect_import()
+            .withf(move |passed_user_id, post_list, _, dry_run| {
+                *passed_user_id == user_id
+                    && post_list.len() == 2
+                    && post_list[0].tag_ids == vec![7]
+                    && post_list[0].deleted_at.is_none()
+                    && post_list[1].deleted_at == Some(deleted_at)
+                    && !*dry_run
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(vec![10, 11]));
+
+        let mut import_service =
+            ImportService::new_with_repository(mocked_post_repository, mocked_tag_repository);
+        let result = import_service
+            .import(user_id, &archive, &AuditContext::default(), false)
+            .unwrap();
+
+        assert_eq!(result.created_count, 2);
+        assert_eq!(result.duplicated_count, 1);
+        assert_eq!(result.failed_count, 1);
+        assert_eq!(result.created_tag_count, 1);
+
+        let statuses: Vec<(&str, &ImportedFileStatus, Option<u64>)> = result
+            .files
+            .iter()
+            .map(|file| (file.path.as_str(), &file.status, file.post_id))
+            .collect();
+        assert_eq!(
+            statuses,
+            vec![
+                (
+                    "posts/2020-04-12-1.md",
+                    &ImportedFileStatus::Created,
+                    Some(10)
+                ),
+                (
+                    "posts/2020-04-12-2.md",
+                    &ImportedFileStatus::Duplicated,
+                    None
+                ),
+                (
+                    "trash/2020-04-12-3.md",
+                    &ImportedFileStatus::Created,
+                    Some(11)
+                ),
+                ("notes.txt", &ImportedFileStatus::Failed, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_import_with_invalid_archive() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        mocked_post_repository.expect_import().times(0);
+
+        let mut import_service = ImportService::new_with_repository(
+            mocked_post_repository,
+            MockTagRepositoryTrait::new(),
+        );
+        let result = import_service.import(5, b"Lorem ipsum", &AuditContext::default(), false);
+
+        assert!(result.is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use rand::distributions::Alphanumeric;
+use rand::{thread_rng, Rng};
+use std::env;
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, FieldError, ServiceError};
+use crate::models::invite::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::pagination_util::{get_offset_and_limit, Page, PageMeta, DEFAULT_PER_PAGE};
+
+/// Length of invite codes.
+const INVITE_CODE_LENGTH: usize = 12;
+
+/// Modes of registration set by `REGISTRATION_MODE`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RegistrationMode {
+    /// Anyone can sign up. It is the default.
+    Open,
+    /// Signups require an unused invite code minted by an admin.
+    Invite,
+}
+
+impl RegistrationMode {
+    /// Reads the mode from `REGISTRATION_MODE`, which is `open` or `invite`.
+    pub fn from_env() -> Self {
+        match env::var("REGISTRATION_MODE") {
+            Ok(mode) if mode == "invite" => Self::Invite,
+            _ => Self::Open,
+        }
+    }
+}
+
+/// Returns the error of `invite_code` field of the signup.
+fn get_invite_code_error(message: &str) -> ServiceError {
+    get_service_error(ServiceError::InvalidFields(vec![FieldError {
+        field: String::from("invite_code"),
+        message: message.to_string(),
+    }]))
+}
+
+/// Converts an invite to the DTO.
+fn get_invite_dto(invite: Invite) -> InviteDTO {
+    InviteDTO {
+        id: invite.id,
+        code: invite.code,
+        created_by: invite.created_by,
+        redeemed_by: invite.redeemed_by,
+        redeemed_at: invite.redeemed_at,
+        created_at: invite.created_at,
+    }
+}
+
+pub struct InviteService {
+    invite_repository: Option<InviteRepository>,
+    registration_mode: RegistrationMode,
+    clock: Arc<dyn Clock>,
+}
+
+impl InviteService {
+    pub fn new() -> Self {
+        Self {
+            invite_repository: None,
+            registration_mode: RegistrationMode::from_env(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn invite_repository(&mut self, new_repository: Option<InviteRepository>) -> &InviteRepository {
+        match new_repository {
+            Some(_) => {
+                self.invite_repository = new_repository;
+                self.invite_repository.as_ref().unwrap()
+            }
+            None => self.invite_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds invites with the total count, the most recently minted first.
+    pub fn get_invites(
+        &mut self,
+        page: &Option<u32>,
+        per_page: &Option<u32>,
+    ) -> Result<Page<InviteDTO>, ServiceError> {
+        let (offset, limit) = get_offset_and_limit(page, per_page)?;
+
+        let (invite_list, total_count) = {
+            let fallback_repository =
+                some_if_true!(self.invite_repository.is_none() => InviteRepository::new());
+            let invite_repository = self.invite_repository(fallback_repository);
+            (
+                invite_repository.find_all(offset, limit)?,
+                invite_repository.count()?,
+            )
+        };
+
+        Ok(Page {
+            items: invite_list.into_iter().map(get_invite_dto).collect(),
+            meta: PageMeta {
+                total_count,
+                page: Some(page.unwrap_or(1)),
+                per_page: Some(per_page.unwrap_or(DEFAULT_PER_PAGE)),
+            },
+        })
+    }
+
+    /// Mints a new invite code by an admin.
+    pub fn create(&mut self, created_by: u64) -> Result<InviteDTO, ServiceError> {
+        let code: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(INVITE_CODE_LENGTH)
+            .collect();
+
+        let fallback_repository =
+            some_if_true!(self.invite_repository.is_none() => InviteRepository::new());
+        let invite = self
+            .invite_repository(fallback_repository)
+            .create(&code, created_by)?;
+
+        Ok(get_invite_dto(invite))
+    }
+
+    /// Verifies an invite code of a signup, and returns the code to redeem.
+    ///
+    /// The code is required and must be unused only in invite mode. Otherwise it is ignored.
+    pub fn verify(&mut self, code: &Option<String>) -> Result<Option<String>, ServiceError> {
+        if self.registration_mode != RegistrationMode::Invite {
+            return Ok(None);
+        }
+
+        let code = match code.as_ref().map(|code| code.trim()) {
+            Some(code) if !code.is_empty() => code.to_string(),
+            _ => return Err(get_invite_code_error("An invite code is required")),
+        };
+
+        let fallback_repository =
+            some_if_true!(self.invite_repository.is_none() => InviteRepository::new());
+        let invite = match self
+            .invite_repository(fallback_repository)
+            .find_by_code(&code)
+        {
+            Ok(invite) => invite,
+            Err(ServiceError::NotFound(_)) => {
+                return Err(get_invite_code_error("The invite code is invalid"))
+            }
+            Err(error) => return Err(error),
+        };
+
+        if invite.redeemed_at.is_some() {
+            return Err(get_invite_code_error("The invite code is already used"));
+        }
+        Ok(Some(code))
+    }
+
+    /// Claims an invite code for a signup about to create the account.
+    ///
+    /// It fails if another signup has claimed the code since it was verified.
+    pub fn claim(&mut self, code: &str) -> Result<bool, ServiceError> {
+        let now = self.clock.now().naive_utc();
+        let fallback_repository =
+            some_if_true!(self.invite_repository.is_none() => InviteRepository::new());
+        if self
+            .invite_repository(fallback_repository)
+            .claim(code, &now)?
+        {
+            Ok(true)
+        } else {
+            Err(get_invite_code_error("The invite code is already used"))
+        }
+    }
+
+    /// Records the user created with a claimed invite code.
+    pub fn redeem(&mut self, code: &str, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.invite_repository.is_none() => InviteRepository::new());
+        self.invite_repository(fallback_repository)
+            .update_redeemed_by(code, user_id)
+    }
+
+    /// Releases a claimed invite code whose signup failed.
+    pub fn release(&mut self, code: &str) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.invite_repository.is_none() => InviteRepository::new());
+        self.invite_repository(fallback_repository).release(code)
+    }
+}
+
+impl Default for InviteService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::invite::MockInviteRepositoryTrait as InviteRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::invite::MockInviteRepositoryTrait;
+
+    impl InviteService {
+        pub fn new_with_repository(
+            invite_repository: InviteRepository,
+            registration_mode: RegistrationMode,
+        ) -> Self {
+            Self {
+                invite_repository: Some(invite_repository),
+                registration_mode,
+                clock: Arc::new(SystemClock),
+            }
+        }
+    }
+
+    fn invite(code: &str, is_redeemed: bool) -> Invite {
+        Invite {
+            id: 1,
+            code: code.to_string(),
+            created_by: Some(1),
+            redeemed_by: some_if_true!(is_redeemed => 2),
+            redeemed_at: some_if_true!(is_redeemed => Utc::now().naive_utc()),
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn test_verify_in_open_mode() {
+        let mut mocked_invite_repository = MockInviteRepositoryTrait::new();
+        mocked_invite_repository.expect_find_by_code().never();
+
+        let code =
+            InviteService::new_with_repository(mocked_invite_repository, RegistrationMode::Open)
+                .verify(&None)
+                .unwrap();
+        assert_eq!(code, None);
+    }
+
+    #[test]
+    fn test_verify_in_invite_mode() {
+        let mut mocked_invite_repository = MockInviteRepositoryTrait::new();
+        mocked_invite_repository
+            .expect_find_by_code()
+            .with(eq("a1b2c3d4e5f6"))
+            .times(1)
+            .returning(|code| Ok(invite(code, false)));
+        mocked_invite_repository
+            .expect_find_by_code()
+            .with(eq("f6e5d4c3b2a1"))
+            .times(1)
+            .returning(|code| Ok(invite(code, true)));
+
+        let mut invite_service =
+            InviteService::new_with_repository(mocked_invite_repository, RegistrationMode::Invite);
+        assert_eq!(
+            invite_service
+                .verify(&Some(String::from(" a1b2c3d4e5f6 ")))
+                .unwrap(),
+            Some(String::from("a1b2c3d4e5f6"))
+        );
+        assert!(matches!(
+            invite_service.verify(&Some(String::from("f6e5d4c3b2a1"))),
+            Err(ServiceError::InvalidFields(_))
+        ));
+        assert!(matches!(
+            invite_service.verify(&None),
+            Err(ServiceError::InvalidFields(_))
+        ));
+    }
+}
@@ -0,0 +1,155 @@
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::journal::*;
+
+pub struct JournalService {
+    journal_repository: Option<JournalRepository>,
+}
+
+impl JournalService {
+    pub fn new() -> Self {
+        Self {
+            journal_repository: None,
+        }
+    }
+
+    fn journal_repository(
+        &mut self,
+        new_repository: Option<JournalRepository>,
+    ) -> &JournalRepository {
+        match new_repository {
+            Some(_) => {
+                self.journal_repository = new_repository;
+                self.journal_repository.as_ref().unwrap()
+            }
+            None => self.journal_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds all journals of specific user, the default journal first.
+    pub fn get_list(&mut self, user_id: u64) -> Result<Vec<JournalDTO>, ServiceError> {
+        let journal_list = {
+            let fallback_repository =
+                some_if_true!(self.journal_repository.is_none() => JournalRepository::new());
+            self.journal_repository(fallback_repository)
+                .find_all(user_id)?
+        };
+
+        Ok(journal_list
+            .into_iter()
+            .map(|journal| JournalDTO {
+                id: journal.id,
+                name: journal.name,
+                is_default: journal.is_default,
+                created_at: journal.created_at,
+                updated_at: journal.updated_at,
+            })
+            .collect())
+    }
+
+    /// Creates a new journal and returns id of the created journal.
+    pub fn create(&mut self, user_id: u64, name: &str) -> Result<u64, ServiceError> {
+        if name.trim().is_empty() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let fallback_repository =
+            some_if_true!(self.journal_repository.is_none() => JournalRepository::new());
+        self.journal_repository(fallback_repository)
+            .create(user_id, name)
+    }
+
+    /// Renames a journal of specific user.
+    pub fn update(&mut self, id: u64, user_id: u64, name: &str) -> Result<bool, ServiceError> {
+        if name.trim().is_empty() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let fallback_repository =
+            some_if_true!(self.journal_repository.is_none() => JournalRepository::new());
+        self.journal_repository(fallback_repository)
+            .update(user_id, id, name)
+    }
+
+    /// Deletes a journal of specific user, and moves its posts to the default journal.
+    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.journal_repository.is_none() => JournalRepository::new());
+        self.journal_repository(fallback_repository)
+            .delete(user_id, id)
+    }
+}
+
+impl Default for JournalService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::journal::MockJournalRepositoryTrait as JournalRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::journal::MockJournalRepositoryTrait;
+
+    impl JournalService {
+        pub fn new_with_repository(journal_repository: JournalRepository) -> Self {
+            Self {
+                journal_repository: Some(journal_repository),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_list() {
+        let mut mocked_journal_repository = MockJournalRepositoryTrait::new();
+
+        let user_id = 5;
+
+        mocked_journal_repository
+            .expect_find_all()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|passed_user_id| {
+                Ok(vec![
+                    Journal {
+                        id: 1,
+                        user_id: passed_user_id,
+                        name: String::new(),
+                        is_default: true,
+                        created_at: Utc::now().naive_utc(),
+                        updated_at: None,
+                    },
+                    Journal {
+                        id: 2,
+                        user_id: passed_user_id,
+                        name: String::from("U2FsdGVkX1"),
+                        is_default: false,
+                        created_at: Utc::now().naive_utc(),
+                        updated_at: None,
+                    },
+                ])
+            });
+
+        let mut journal_service = JournalService::new_with_repository(mocked_journal_repository);
+        let journal_list = journal_service.get_list(user_id).unwrap();
+
+        assert_eq!(journal_list.len(), 2);
+        assert!(journal_list[0].is_default);
+        assert_eq!(journal_list[1].name, "U2FsdGVkX1");
+    }
+
+    #[test]
+    fn test_create_with_empty_name() {
+        let mut mocked_journal_repository = MockJournalRepositoryTrait::new();
+        mocked_journal_repository.expect_create().times(0);
+
+        let mut journal_service = JournalService::new_with_repository(mocked_journal_repository);
+
+        assert!(journal_service.create(5, " ").is_err());
+    }
+}
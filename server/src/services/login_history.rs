@@ -0,0 +1,269 @@
+use std::sync::Arc;
+
+use crate::models::error::ServiceError;
+use crate::models::login_history::*;
+use crate::models::post_audit::AuditContext;
+use crate::models::user::UserRepository;
+use crate::services::email::EmailService;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::html_util;
+use crate::utils::pagination_util::get_offset_and_limit;
+
+pub struct LoginHistoryService {
+    login_history_repository: Option<LoginHistoryRepository>,
+    user_repository: Option<UserRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl LoginHistoryService {
+    pub fn new() -> Self {
+        Self {
+            login_history_repository: None,
+            user_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn login_history_repository(
+        &mut self,
+        new_repository: Option<LoginHistoryRepository>,
+    ) -> &LoginHistoryRepository {
+        match new_repository {
+            Some(_) => {
+                self.login_history_repository = new_repository;
+                self.login_history_repository.as_ref().unwrap()
+            }
+            None => self.login_history_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_repository = new_repository;
+                self.user_repository.as_ref().unwrap()
+            }
+            None => self.user_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds logins of specific user, the most recent first.
+    pub fn get_list(
+        &mut self,
+        user_id: u64,
+        page: &Option<u32>,
+        per_page: &Option<u32>,
+    ) -> Result<Vec<LoginHistoryDTO>, ServiceError> {
+        let (offset, limit) = get_offset_and_limit(page, per_page)?;
+
+        let history = {
+            let fallback_repository = some_if_true!(self.login_history_repository.is_none() => LoginHistoryRepository::new());
+            self.login_history_repository(fallback_repository)
+                .find_all_by_user_id(user_id, offset, limit)?
+        };
+
+        Ok(history
+            .into_iter()
+            .map(|login| LoginHistoryDTO {
+                id: login.id,
+                ip: login.ip,
+                country: login.country,
+                user_agent: login.user_agent,
+                created_at: login.created_at,
+            })
+            .collect())
+    }
+
+    /// Records a login of the device described by `context`, and returns whether
+    /// the device has never been used by the user before.
+    ///
+    /// The user is notified by email of a new device, except on the first login.
+    /// Devices are told apart by `User-Agent` header.
+    pub fn record(&mut self, user_id: u64, context: &AuditContext) -> Result<bool, ServiceError> {
+        let is_new_device = {
+            let fallback_repository = some_if_true!(self.login_history_repository.is_none() => LoginHistoryRepository::new());
+            let login_history_repository = self.login_history_repository(fallback_repository);
+            login_history_repository.count_by_user_id(user_id)? > 0
+                && login_history_repository.count_by_user_agent(user_id, &context.user_agent)? == 0
+        };
+
+        self.login_history_repository(None).create(
+            user_id,
+            &context.ip,
+            &context.country,
+            &context.user_agent,
+        )?;
+
+        if is_new_device {
+            self.notify_new_device(user_id, context)?;
+        }
+        Ok(is_new_device)
+    }
+
+    /// Emails the user that a new device has signed in.
+    fn notify_new_device(
+        &mut self,
+        user_id: u64,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+
+        let unknown = String::from("Unknown");
+        let location = match (&context.ip, &context.country) {
+            (Some(ip), Some(country)) => format!("{} ({})", ip, country),
+            (Some(ip), None) => ip.clone(),
+            (None, _) => unknown.clone(),
+        };
+        let email_content = format!(
+            "Hello :)<br/><br/>\
+            Your account has been signed in from a new device:<br/><br/>\
+            Time: {} UTC<br/>\
+            Location: {}<br/>\
+            Device: {}<br/><br/>\
+            If it was not you, please sign out the device in the settings and change your password.",
+            self.clock.now().format("%Y-%m-%d %H:%M"),
+            html_util::escape(&location),
+            html_util::escape(context.user_agent.as_ref().unwrap_or(&unknown)),
+        );
+
+        EmailService::new().enqueue(
+            &format!("{} <{}>", user.name, user.email),
+            &String::from("New sign-in to Darim 🔔"),
+            &email_content,
+            &None,
+        )?;
+        EmailService::send_soon();
+
+        Ok(true)
+    }
+}
+
+impl Default for LoginHistoryService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::login_history::MockLoginHistoryRepositoryTrait as LoginHistoryRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::login_history::MockLoginHistoryRepositoryTrait;
+    use crate::models::user::MockUserRepositoryTrait;
+
+    impl LoginHistoryService {
+        pub fn new_with_repository(
+            login_history_repository: LoginHistoryRepository,
+            user_repository: UserRepository,
+        ) -> Self {
+            Self {
+                login_history_repository: Some(login_history_repository),
+                user_repository: Some(user_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+    }
+
+    fn context() -> AuditContext {
+        AuditContext {
+            ip: Some(String::from("127.0.0.1")),
+            user_agent: Some(String::from("Mozilla/5.0")),
+            session_id: None,
+            country: Some(String::from("KR")),
+        }
+    }
+
+    #[test]
+    fn test_get_list() {
+        let mut mocked_login_history_repository = MockLoginHistoryRepositoryTrait::new();
+        mocked_login_history_repository
+            .expect_find_all_by_user_id()
+            .with(eq(5), eq(0), eq(20))
+            .times(1)
+            .returning(|user_id, _, _| {
+                Ok(vec![LoginHistory {
+                    id: 1,
+                    user_id,
+                    ip: Some(String::from("127.0.0.1")),
+                    country: Some(String::from("KR")),
+                    user_agent: Some(String::from("Mozilla/5.0")),
+                    created_at: Utc.ymd(2020, 4, 13).and_hms(16, 31, 9).naive_utc(),
+                }])
+            });
+
+        let mut login_history_service = LoginHistoryService::new_with_repository(
+            mocked_login_history_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        let history = login_history_service.get_list(5, &None, &None).unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].country, Some(String::from("KR")));
+    }
+
+    #[test]
+    fn test_record_first_login() {
+        let mut mocked_login_history_repository = MockLoginHistoryRepositoryTrait::new();
+        mocked_login_history_repository
+            .expect_count_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(0));
+        mocked_login_history_repository
+            .expect_create()
+            .with(
+                eq(5),
+                eq(Some(String::from("127.0.0.1"))),
+                eq(Some(String::from("KR"))),
+                eq(Some(String::from("Mozilla/5.0"))),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+
+        let mut login_history_service = LoginHistoryService::new_with_repository(
+            mocked_login_history_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        // The first login is not a new device to be notified.
+        assert!(!login_history_service.record(5, &context()).unwrap());
+    }
+
+    #[test]
+    fn test_record_known_device() {
+        let mut mocked_login_history_repository = MockLoginHistoryRepositoryTrait::new();
+        mocked_login_history_repository
+            .expect_count_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(3));
+        mocked_login_history_repository
+            .expect_count_by_user_agent()
+            .with(eq(5), eq(Some(String::from("Mozilla/5.0"))))
+            .times(1)
+            .returning(|_, _| Ok(2));
+        mocked_login_history_repository
+            .expect_create()
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+
+        let mut login_history_service = LoginHistoryService::new_with_repository(
+            mocked_login_history_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        assert!(!login_history_service.record(5, &context()).unwrap());
+    }
+}
@@ -0,0 +1,1157 @@
+use chrono::{Duration, NaiveDateTime};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::login_session::*;
+use crate::models::post_audit::AuditContext;
+use crate::models::user::*;
+use crate::services::login_history::LoginHistoryService;
+use crate::utils::clock_util::{Clock, SystemClock};
+
+/// Minutes the time a login session has been used last is not updated for,
+/// so that each request does not write it.
+const LAST_SEEN_INTERVAL_MINUTES: i64 = 5;
+
+/// Maximum length of `User-Agent` header kept in a login session.
+const MAX_USER_AGENT_LENGTH: usize = 512;
+
+/// Length of a refresh token.
+const REFRESH_TOKEN_LENGTH: usize = 48;
+
+/// Maximum length of the label of a login session.
+const MAX_LABEL_LENGTH: usize = 64;
+
+/// Default days a login session lasts since the user has signed in, which is the same as
+/// the max age of the session cookie of the api gateway.
+const DEFAULT_SESSION_TTL_DAYS: i64 = 30;
+
+/// Default days a login session lasts since it has been used last.
+const DEFAULT_SESSION_IDLE_DAYS: i64 = 14;
+
+/// Minutes a login session issued to an admin impersonating a user lasts, however it is used.
+const IMPERSONATION_MINUTES: i64 = 30;
+
+/// Returns the hash of a session id or a refresh token in hex.
+fn hash_secret(secret: &str) -> String {
+    format!("{:x}", Sha256::digest(secret.as_bytes()))
+}
+
+/// Returns the session id forwarded in `context`, or `InvalidArgument` error if there is no one.
+fn get_session_id(context: &AuditContext) -> Result<&str, ServiceError> {
+    match &context.session_id {
+        Some(session_id) if !session_id.is_empty() => Ok(session_id.as_str()),
+        _ => Err(get_service_error(ServiceError::InvalidArgument)),
+    }
+}
+
+/// Returns the device of a login session signed in with the session cookie, or `InvalidArgument`
+/// error if the label is too long. A blank label is not kept.
+fn get_device(
+    device_id: &Option<String>,
+    label: &Option<String>,
+) -> Result<LoginDevice, ServiceError> {
+    let label = label
+        .as_ref()
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty());
+    if let Some(label) = &label {
+        if label.chars().count() > MAX_LABEL_LENGTH {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+    }
+
+    Ok(LoginDevice {
+        device_id_hash: device_id.as_deref().map(hash_secret),
+        label,
+    })
+}
+
+pub struct LoginSessionService {
+    login_session_repository: Option<LoginSessionRepository>,
+    user_repository: Option<UserRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl LoginSessionService {
+    pub fn new() -> Self {
+        Self {
+            login_session_repository: None,
+            user_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn login_session_repository(
+        &mut self,
+        new_repository: Option<LoginSessionRepository>,
+    ) -> &LoginSessionRepository {
+        match new_repository {
+            Some(_) => {
+                self.login_session_repository = new_repository;
+                self.login_session_repository.as_ref().unwrap()
+            }
+            None => self.login_session_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_repository = new_repository;
+                self.user_repository.as_ref().unwrap()
+            }
+            None => self.user_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Returns how long a login session lasts since the user has signed in,
+    /// set by `LOGIN_SESSION_TTL_DAYS`.
+    fn get_session_ttl() -> Duration {
+        let ttl_days = env::var("LOGIN_SESSION_TTL_DAYS")
+            .ok()
+            .and_then(|days| days.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_SESSION_TTL_DAYS);
+        Duration::days(ttl_days)
+    }
+
+    /// Returns how long a login session lasts since it has been used last,
+    /// set by `LOGIN_SESSION_IDLE_DAYS`.
+    fn get_session_idle_timeout() -> Duration {
+        let idle_days = env::var("LOGIN_SESSION_IDLE_DAYS")
+            .ok()
+            .and_then(|days| days.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_SESSION_IDLE_DAYS);
+        Duration::days(idle_days)
+    }
+
+    /// Returns whether a login session has ended by its absolute or idle lifetime at `now`.
+    fn is_expired(session: &LoginSession, now: &NaiveDateTime) -> bool {
+        session.expires_at <= *now
+            || *now - session.last_seen_at >= Self::get_session_idle_timeout()
+    }
+
+    /// Lists login sessions of specific user, marking the one of `current_session_id`.
+    pub fn get_list(
+        &mut self,
+        user_id: u64,
+        current_session_id: &Option<String>,
+    ) -> Result<Vec<LoginSessionDTO>, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        let sessions = self
+            .login_session_repository(fallback_repository)
+            .find_all_by_user_id(user_id)?;
+
+        let current_session_id_hash = current_session_id.as_deref().map(hash_secret);
+        Ok(sessions
+            .into_iter()
+            .map(|session| LoginSessionDTO {
+                is_current: current_session_id_hash.as_ref() == Some(&session.session_id_hash),
+                id: session.id,
+                user_agent: session.user_agent,
+                ip: session.ip,
+                created_at: session.created_at,
+                last_seen_at: session.last_seen_at,
+            })
+            .collect())
+    }
+
+    /// Lists login sessions of a device which have not expired, marking the one of
+    /// `current_session_id`.
+    pub fn get_device_list(
+        &mut self,
+        device_id: &str,
+        current_session_id: &Option<String>,
+    ) -> Result<Vec<DeviceLoginSessionDTO>, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        let sessions = self
+            .login_session_repository(fallback_repository)
+            .find_all_by_device_id_hash(&hash_secret(device_id))?;
+
+        let now = self.clock.now().naive_utc();
+        let current_session_id_hash = current_session_id.as_deref().map(hash_secret);
+        Ok(sessions
+            .into_iter()
+            .filter(|(session, _)| !Self::is_expired(session, &now))
+            .map(|(session, user)| DeviceLoginSessionDTO {
+                is_current: current_session_id_hash.as_ref() == Some(&session.session_id_hash),
+                id: session.id,
+                user_id: user.id,
+                user_name: user.name,
+                user_avatar_url: user.avatar_url,
+                label: session.label,
+                last_seen_at: session.last_seen_at,
+            })
+            .collect())
+    }
+
+    /// Creates a login session of the device described by `context`, which has signed in,
+    /// and records the login in the history.
+    ///
+    /// If the device uses the session cookie, `device_id` identifies it and `label` names
+    /// the session for switching to it later.
+    pub fn create(
+        &mut self,
+        user_id: u64,
+        context: &AuditContext,
+        device_id: &Option<String>,
+        label: &Option<String>,
+    ) -> Result<bool, ServiceError> {
+        let device = get_device(device_id, label)?;
+        self.create_with_refresh_token_hash(user_id, context, &None, &device)
+    }
+
+    /// Creates a login session of the device described by `context` which uses access tokens,
+    /// and returns its refresh token.
+    pub fn create_with_refresh_token(
+        &mut self,
+        user_id: u64,
+        context: &AuditContext,
+    ) -> Result<String, ServiceError> {
+        let refresh_token: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(REFRESH_TOKEN_LENGTH)
+            .collect();
+        self.create_with_refresh_token_hash(
+            user_id,
+            context,
+            &Some(hash_secret(&refresh_token)),
+            &LoginDevice::default(),
+        )?;
+        Ok(refresh_token)
+    }
+
+    fn create_with_refresh_token_hash(
+        &mut self,
+        user_id: u64,
+        context: &AuditContext,
+        refresh_token_hash: &Option<String>,
+        device: &LoginDevice,
+    ) -> Result<bool, ServiceError> {
+        let session_id = get_session_id(context)?;
+        let user_agent = context
+            .user_agent
+            .as_ref()
+            .map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_LENGTH).collect());
+        let expires_at = self.clock.now().naive_utc() + Self::get_session_ttl();
+
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        let result = self.login_session_repository(fallback_repository).create(
+            user_id,
+            &hash_secret(session_id),
+            &user_agent,
+            &context.ip,
+            refresh_token_hash,
+            device,
+            &expires_at,
+        )?;
+
+        LoginHistoryService::new().record(
+            user_id,
+            &AuditContext {
+                user_agent,
+                ..context.clone()
+            },
+        )?;
+        Ok(result)
+    }
+
+    /// Creates a login session of a user, which is issued to an admin impersonating the user
+    /// with the session id in `context`, and returns the time it expires at.
+    ///
+    /// It expires `IMPERSONATION_MINUTES` after it is created, and the login is not recorded
+    /// in the history of the user.
+    pub fn create_impersonated(
+        &mut self,
+        user_id: u64,
+        impersonation: &Impersonation,
+        context: &AuditContext,
+    ) -> Result<NaiveDateTime, ServiceError> {
+        let session_id = get_session_id(context)?;
+        let user_agent = context
+            .user_agent
+            .as_ref()
+            .map(|user_agent| user_agent.chars().take(MAX_USER_AGENT_LENGTH).collect());
+        let expires_at = self.clock.now().naive_utc() + Duration::minutes(IMPERSONATION_MINUTES);
+
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        self.login_session_repository(fallback_repository)
+            .create_impersonated(
+                user_id,
+                &hash_secret(session_id),
+                &user_agent,
+                &context.ip,
+                impersonation,
+                &expires_at,
+            )?;
+        Ok(expires_at)
+    }
+
+    /// Refreshes the login session of a refresh token with the new session id in `context`,
+    /// and returns id of the user and a new refresh token.
+    ///
+    /// The refresh token is rotated, so that it is used once. Access tokens of the old session id
+    /// are not accepted anymore. It fails with `Unauthorized` once the session has expired
+    /// or been idle for too long.
+    ///
+    /// A refresh token rotated out of a session must not come back, since one of the devices
+    /// holding it is not the one which has signed in. The whole session is revoked then.
+    pub fn refresh(
+        &mut self,
+        refresh_token: &str,
+        context: &AuditContext,
+    ) -> Result<(u64, String), ServiceError> {
+        let session_id = get_session_id(context)?;
+        let refresh_token_hash = hash_secret(refresh_token);
+
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        let session = match self
+            .login_session_repository(fallback_repository)
+            .find_by_refresh_token_hash(&refresh_token_hash)
+        {
+            Ok(session) => session,
+            Err(ServiceError::NotFound(_)) => {
+                self.revoke_rotated_refresh_token(&refresh_token_hash)?;
+                return Err(get_service_error(ServiceError::Unauthorized));
+            }
+            Err(error) => return Err(error),
+        };
+
+        let now = self.clock.now().naive_utc();
+        if Self::is_expired(&session, &now) {
+            return Err(get_service_error(ServiceError::Unauthorized));
+        }
+
+        let new_refresh_token: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(REFRESH_TOKEN_LENGTH)
+            .collect();
+        let is_rotated = self.login_session_repository(None).rotate_refresh_token(
+            &session,
+            &hash_secret(session_id),
+            &hash_secret(&new_refresh_token),
+            &now,
+        )?;
+        if !is_rotated {
+            // Another request has rotated the same refresh token meanwhile.
+            self.revoke_rotated_refresh_token(&refresh_token_hash)?;
+            return Err(get_service_error(ServiceError::Unauthorized));
+        }
+
+        Ok((session.user_id, new_refresh_token))
+    }
+
+    /// Deletes the login session a refresh token has been rotated out of, if there is one.
+    fn revoke_rotated_refresh_token(
+        &mut self,
+        refresh_token_hash: &str,
+    ) -> Result<bool, ServiceError> {
+        let session = match self
+            .login_session_repository(None)
+            .find_by_rotated_refresh_token_hash(refresh_token_hash)
+        {
+            Ok(session) => session,
+            Err(ServiceError::NotFound(_)) => return Ok(false),
+            Err(error) => return Err(error),
+        };
+
+        match self
+            .login_session_repository(None)
+            .delete(session.id, session.user_id)
+        {
+            Ok(_) | Err(ServiceError::NotFound(_)) => Ok(true),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Switches a device to its login session of `id` with the new session id in `context`,
+    /// and returns id of the user.
+    ///
+    /// The session id is rotated, since the device keeps only the session id it is using.
+    /// It fails with `NotFound` if the login session has not signed in on the device,
+    /// or it has expired.
+    pub fn switch(
+        &mut self,
+        id: u64,
+        device_id: &str,
+        context: &AuditContext,
+    ) -> Result<u64, ServiceError> {
+        let session_id = get_session_id(context)?;
+
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        let session = self
+            .login_session_repository(fallback_repository)
+            .find_by_device_id_hash(id, &hash_secret(device_id))?;
+
+        let now = self.clock.now().naive_utc();
+        if Self::is_expired(&session, &now) {
+            return Err(get_service_error(ServiceError::NotFound(id.to_string())));
+        }
+
+        self.login_session_repository(None).update_session_id_hash(
+            session.id,
+            &hash_secret(session_id),
+            &now,
+        )?;
+        Ok(session.user_id)
+    }
+
+    /// Deletes the login session of a refresh token, which signs out the device.
+    pub fn revoke_refresh_token(&mut self, refresh_token: &str) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        self.login_session_repository(fallback_repository)
+            .delete_by_refresh_token_hash(&hash_secret(refresh_token))
+    }
+
+    /// Returns the user of the login session of `session_id` if it is active, and marks it used.
+    /// The admin impersonating the user is returned as well, if the session has been issued
+    /// to the admin.
+    ///
+    /// A session is not active anymore `LOGIN_SESSION_TTL_DAYS` after signing in, or
+    /// `LOGIN_SESSION_IDLE_DAYS` after it has been used last, whatever the device keeps.
+    ///
+    /// The user is read from `users` table on every request, since the device keeps only
+    /// the session id, and the role of the user changes once the user is revoked from admins.
+    pub fn verify(
+        &mut self,
+        session_id: &str,
+    ) -> Result<Option<(User, Option<Impersonation>)>, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        let session = match self
+            .login_session_repository(fallback_repository)
+            .find_by_session_id_hash(&hash_secret(session_id))
+        {
+            Ok(session) => session,
+            Err(ServiceError::NotFound(_)) => return Ok(None),
+            Err(error) => return Err(error),
+        };
+
+        let now = self.clock.now().naive_utc();
+        if Self::is_expired(&session, &now) {
+            return Ok(None);
+        }
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            match self
+                .user_repository(fallback_repository)
+                .find_by_id(session.user_id)
+            {
+                Ok(user) => user,
+                Err(ServiceError::NotFound(_)) => return Ok(None),
+                Err(error) => return Err(error),
+            }
+        };
+
+        if now - session.last_seen_at >= Duration::minutes(LAST_SEEN_INTERVAL_MINUTES) {
+            self.login_session_repository(None)
+                .update_last_seen_at(session.id, &now)?;
+        }
+
+        let impersonation = session
+            .impersonator_id
+            .map(|impersonator_id| Impersonation {
+                impersonator_id,
+                allow_writes: session.allow_writes,
+            });
+        Ok(Some((user, impersonation)))
+    }
+
+    /// Deletes a login session of specific user, which signs out the device.
+    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        self.login_session_repository(fallback_repository)
+            .delete(id, user_id)
+    }
+
+    /// Deletes the login session of `session_id`, when the device signs out by itself.
+    pub fn delete_by_session_id(
+        &mut self,
+        user_id: u64,
+        session_id: &str,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        self.login_session_repository(fallback_repository)
+            .delete_by_session_id_hash(user_id, &hash_secret(session_id))
+    }
+
+    /// Deletes the login session of `session_id` issued to an admin impersonating a user,
+    /// and returns it.
+    ///
+    /// It fails with `InvalidArgument` if the session has been signed in by the user.
+    pub fn delete_impersonated(&mut self, session_id: &str) -> Result<LoginSession, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        let login_session_repository = self.login_session_repository(fallback_repository);
+
+        let session = login_session_repository.find_by_session_id_hash(&hash_secret(session_id))?;
+        if session.impersonator_id.is_none() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        login_session_repository.delete(session.id, session.user_id)?;
+        Ok(session)
+    }
+
+    /// Deletes login sessions expired or idle for too long, and returns the count.
+    pub fn prune(&mut self) -> Result<usize, ServiceError> {
+        let now = self.clock.now().naive_utc();
+        let last_seen_before = now - Self::get_session_idle_timeout();
+
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        self.login_session_repository(fallback_repository)
+            .delete_all_expired(&now, &last_seen_before)
+    }
+
+    /// Deletes all login sessions of specific user, which signs out every device.
+    pub fn delete_all(&mut self, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_session_repository.is_none() => LoginSessionRepository::new());
+        self.login_session_repository(fallback_repository)
+            .delete_all_by_user_id(user_id)?;
+        Ok(true)
+    }
+}
+
+impl Default for LoginSessionService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::login_session::MockLoginSessionRepositoryTrait as LoginSessionRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+    use mockall::Sequence;
+
+    use super::*;
+    use crate::models::login_session::MockLoginSessionRepositoryTrait;
+    use crate::models::user::MockUserRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl LoginSessionService {
+        pub fn new_with_repository(
+            login_session_repository: LoginSessionRepository,
+            user_repository: UserRepository,
+        ) -> Self {
+            Self {
+                login_session_repository: Some(login_session_repository),
+                user_repository: Some(user_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    fn login_session(id: u64, session_id: &str) -> LoginSession {
+        LoginSession {
+            id,
+            user_id: 5,
+            session_id_hash: hash_secret(session_id),
+            user_agent: Some(String::from("Mozilla/5.0")),
+            ip: Some(String::from("127.0.0.1")),
+            created_at: Utc.ymd(2020, 4, 13).and_hms(16, 31, 9).naive_utc(),
+            last_seen_at: Utc.ymd(2020, 4, 13).and_hms(16, 31, 9).naive_utc(),
+            refresh_token_hash: None,
+            device_id_hash: Some(hash_secret("d1")),
+            label: None,
+            expires_at: Utc.ymd(2020, 5, 13).and_hms(16, 31, 9).naive_utc(),
+            impersonator_id: None,
+            allow_writes: false,
+        }
+    }
+
+    fn user(role: &str) -> User {
+        User {
+            id: 5,
+            name: String::from("park"),
+            email: String::from("park@email.com"),
+            password: String::from("password"),
+            avatar_url: None,
+            created_at: Utc.ymd(2020, 4, 13).and_hms(16, 31, 9).naive_utc(),
+            updated_at: None,
+            key_metadata: None,
+            daily_word_goal: None,
+            monthly_word_goal: None,
+            role: role.to_string(),
+            suspended_at: None,
+        }
+    }
+
+    #[test]
+    fn test_get_list() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_all_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(vec![login_session(1, "a1b2"), login_session(2, "c3d4")]));
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        let sessions = login_session_service
+            .get_list(5, &Some(String::from("c3d4")))
+            .unwrap();
+        assert_eq!(
+            sessions
+                .iter()
+                .map(|session| (session.id, session.is_current))
+                .collect::<Vec<_>>(),
+            vec![(1, false), (2, true)]
+        );
+    }
+
+    #[test]
+    fn test_get_device_list() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_all_by_device_id_hash()
+            .with(eq(hash_secret("d1")))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![
+                    (
+                        LoginSession {
+                            label: Some(String::from("Mom")),
+                            ..login_session(1, "a1b2")
+                        },
+                        user("user"),
+                    ),
+                    (
+                        LoginSession {
+                            user_id: 6,
+                            ..login_session(2, "c3d4")
+                        },
+                        User {
+                            id: 6,
+                            ..user("user")
+                        },
+                    ),
+                    (
+                        LoginSession {
+                            user_id: 7,
+                            last_seen_at: Utc.ymd(2020, 3, 1).and_hms(0, 0, 0).naive_utc(),
+                            ..login_session(3, "e5f6")
+                        },
+                        User {
+                            id: 7,
+                            ..user("user")
+                        },
+                    ),
+                ])
+            });
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(
+            Utc.ymd(2020, 4, 14).and_hms(0, 0, 0),
+        )));
+
+        let sessions = login_session_service
+            .get_device_list("d1", &Some(String::from("a1b2")))
+            .unwrap();
+        assert_eq!(
+            sessions
+                .iter()
+                .map(|session| (
+                    session.id,
+                    session.user_id,
+                    session.label.clone(),
+                    session.is_current
+                ))
+                .collect::<Vec<_>>(),
+            vec![(1, 5, Some(String::from("Mom")), true), (2, 6, None, false)]
+        );
+    }
+
+    #[test]
+    fn test_get_device() {
+        let device = get_device(&Some(String::from("d1")), &Some(String::from(" Mom "))).unwrap();
+        assert_eq!(device.device_id_hash, Some(hash_secret("d1")));
+        assert_eq!(device.label, Some(String::from("Mom")));
+
+        let device = get_device(&None, &Some(String::from(" "))).unwrap();
+        assert_eq!(device.device_id_hash, None);
+        assert_eq!(device.label, None);
+
+        assert!(matches!(
+            get_device(&None, &Some("a".repeat(MAX_LABEL_LENGTH + 1))),
+            Err(ServiceError::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn test_switch() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_by_device_id_hash()
+            .with(eq(1), eq(hash_secret("d1")))
+            .times(1)
+            .returning(|_, _| Ok(login_session(1, "a1b2")));
+        mocked_login_session_repository
+            .expect_find_by_device_id_hash()
+            .with(eq(1), eq(hash_secret("d2")))
+            .times(1)
+            .returning(|id, _| Err(ServiceError::NotFound(id.to_string())));
+        mocked_login_session_repository
+            .expect_find_by_device_id_hash()
+            .with(eq(2), eq(hash_secret("d1")))
+            .times(1)
+            .returning(|_, _| {
+                Ok(LoginSession {
+                    last_seen_at: Utc.ymd(2020, 3, 1).and_hms(0, 0, 0).naive_utc(),
+                    ..login_session(2, "c3d4")
+                })
+            });
+        mocked_login_session_repository
+            .expect_update_session_id_hash()
+            .with(eq(1), eq(hash_secret("e5f6")), always())
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(
+            Utc.ymd(2020, 4, 14).and_hms(0, 0, 0),
+        )));
+        let context = AuditContext {
+            session_id: Some(String::from("e5f6")),
+            ..AuditContext::default()
+        };
+
+        assert_eq!(login_session_service.switch(1, "d1", &context).unwrap(), 5);
+        // Another device cannot switch to the session.
+        assert!(matches!(
+            login_session_service.switch(1, "d2", &context),
+            Err(ServiceError::NotFound(_))
+        ));
+        // The session has not been used for too long.
+        assert!(matches!(
+            login_session_service.switch(2, "d1", &context),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_by_session_id_hash()
+            .with(eq(hash_secret("a1b2")))
+            .times(2)
+            .returning(|_| Ok(login_session(1, "a1b2")));
+        mocked_login_session_repository
+            .expect_find_by_session_id_hash()
+            .with(eq(hash_secret("c3d4")))
+            .times(1)
+            .returning(|session_id_hash| Err(ServiceError::NotFound(session_id_hash.to_string())));
+        mocked_login_session_repository
+            .expect_update_last_seen_at()
+            .with(eq(1), always())
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+        for role in ["admin", "user"].iter() {
+            mocked_user_repository
+                .expect_find_by_id()
+                .with(eq(5))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(move |_| Ok(user(role)));
+        }
+
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 4, 13).and_hms(16, 32, 0)));
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            mocked_user_repository,
+        )
+        .with_clock(clock.clone());
+
+        // The session has been seen a minute ago, so it is not updated yet.
+        assert_eq!(
+            login_session_service
+                .verify("a1b2")
+                .unwrap()
+                .map(|(user, impersonation)| (user.id, user.role, impersonation)),
+            Some((5, String::from("admin"), None))
+        );
+        // The user has been revoked from admins since, which the session follows at once.
+        clock.advance(Duration::minutes(LAST_SEEN_INTERVAL_MINUTES));
+        assert_eq!(
+            login_session_service
+                .verify("a1b2")
+                .unwrap()
+                .map(|(user, impersonation)| (user.id, user.role, impersonation)),
+            Some((5, String::from("user"), None))
+        );
+        assert!(login_session_service.verify("c3d4").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_verify_expired() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_by_session_id_hash()
+            .with(eq(hash_secret("a1b2")))
+            .times(1)
+            .returning(|_| Ok(login_session(1, "a1b2")));
+        mocked_login_session_repository
+            .expect_find_by_session_id_hash()
+            .with(eq(hash_secret("c3d4")))
+            .times(1)
+            .returning(|_| {
+                Ok(LoginSession {
+                    last_seen_at: Utc.ymd(2020, 5, 13).and_hms(16, 0, 0).naive_utc(),
+                    ..login_session(2, "c3d4")
+                })
+            });
+        mocked_login_session_repository
+            .expect_update_last_seen_at()
+            .times(0);
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository.expect_find_by_id().times(0);
+
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 5, 13).and_hms(16, 31, 9)));
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            mocked_user_repository,
+        )
+        .with_clock(clock);
+
+        // The session has not been used for a month.
+        assert!(login_session_service.verify("a1b2").unwrap().is_none());
+        // The session has been used just before, but a month has passed since signing in.
+        assert!(login_session_service.verify("c3d4").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_create_impersonated() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        let now = Utc.ymd(2020, 4, 13).and_hms(16, 31, 9);
+        let impersonation = Impersonation {
+            impersonator_id: 1,
+            allow_writes: false,
+        };
+
+        mocked_login_session_repository
+            .expect_create_impersonated()
+            .with(
+                eq(5),
+                eq(hash_secret("e5f6")),
+                eq(Some(String::from("Mozilla/5.0"))),
+                eq(None),
+                eq(Impersonation {
+                    impersonator_id: 1,
+                    allow_writes: false,
+                }),
+                eq(now.naive_utc() + Duration::minutes(IMPERSONATION_MINUTES)),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(true));
+        mocked_login_session_repository.expect_create().times(0);
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+        let context = AuditContext {
+            user_agent: Some(String::from("Mozilla/5.0")),
+            session_id: Some(String::from("e5f6")),
+            ..AuditContext::default()
+        };
+
+        assert_eq!(
+            login_session_service
+                .create_impersonated(5, &impersonation, &context)
+                .unwrap(),
+            Utc.ymd(2020, 4, 13).and_hms(17, 1, 9).naive_utc()
+        );
+        assert!(matches!(
+            login_session_service.create_impersonated(5, &impersonation, &AuditContext::default()),
+            Err(ServiceError::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn test_verify_impersonated() {
+        let impersonated_session = || LoginSession {
+            expires_at: Utc.ymd(2020, 4, 13).and_hms(17, 1, 9).naive_utc(),
+            impersonator_id: Some(1),
+            allow_writes: true,
+            ..login_session(1, "a1b2")
+        };
+
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_by_session_id_hash()
+            .with(eq(hash_secret("a1b2")))
+            .times(2)
+            .returning(move |_| Ok(impersonated_session()));
+        mocked_login_session_repository
+            .expect_update_last_seen_at()
+            .returning(|_, _| Ok(true));
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(user("user")));
+
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 4, 13).and_hms(16, 32, 0)));
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            mocked_user_repository,
+        )
+        .with_clock(clock.clone());
+
+        assert_eq!(
+            login_session_service
+                .verify("a1b2")
+                .unwrap()
+                .map(|(user, impersonation)| (user.id, impersonation)),
+            Some((
+                5,
+                Some(Impersonation {
+                    impersonator_id: 1,
+                    allow_writes: true,
+                })
+            ))
+        );
+        // The session ends 30 minutes after it is issued, although it has been used just before.
+        clock.advance(Duration::minutes(30));
+        assert!(login_session_service.verify("a1b2").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_delete_impersonated() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_by_session_id_hash()
+            .with(eq(hash_secret("a1b2")))
+            .times(1)
+            .returning(|_| {
+                Ok(LoginSession {
+                    impersonator_id: Some(1),
+                    ..login_session(1, "a1b2")
+                })
+            });
+        mocked_login_session_repository
+            .expect_find_by_session_id_hash()
+            .with(eq(hash_secret("c3d4")))
+            .times(1)
+            .returning(|_| Ok(login_session(2, "c3d4")));
+        mocked_login_session_repository
+            .expect_delete()
+            .with(eq(1), eq(5))
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        let session = login_session_service.delete_impersonated("a1b2").unwrap();
+        assert_eq!((session.user_id, session.impersonator_id), (5, Some(1)));
+        // A session signed in by the user is not ended as an impersonation.
+        assert!(matches!(
+            login_session_service.delete_impersonated("c3d4"),
+            Err(ServiceError::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn test_refresh() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_by_refresh_token_hash()
+            .with(eq(hash_secret("r1")))
+            .times(1)
+            .returning(|_| {
+                Ok(LoginSession {
+                    refresh_token_hash: Some(hash_secret("r1")),
+                    ..login_session(1, "a1b2")
+                })
+            });
+        mocked_login_session_repository
+            .expect_find_by_refresh_token_hash()
+            .with(eq(hash_secret("r0")))
+            .times(1)
+            .returning(|refresh_token_hash| {
+                Err(ServiceError::NotFound(refresh_token_hash.to_string()))
+            });
+        mocked_login_session_repository
+            .expect_find_by_rotated_refresh_token_hash()
+            .with(eq(hash_secret("r0")))
+            .times(1)
+            .returning(|refresh_token_hash| {
+                Err(ServiceError::NotFound(refresh_token_hash.to_string()))
+            });
+        mocked_login_session_repository
+            .expect_rotate_refresh_token()
+            .with(always(), eq(hash_secret("c3d4")), always(), always())
+            .times(1)
+            .returning(|session, _, refresh_token_hash, _| {
+                assert_eq!(session.id, 1);
+                assert_ne!(refresh_token_hash, hash_secret("r1"));
+                Ok(true)
+            });
+        mocked_login_session_repository.expect_delete().times(0);
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(
+            Utc.ymd(2020, 4, 14).and_hms(0, 0, 0),
+        )));
+        let context = AuditContext {
+            ip: None,
+            user_agent: None,
+            session_id: Some(String::from("c3d4")),
+            country: None,
+        };
+
+        let (user_id, refresh_token) = login_session_service.refresh("r1", &context).unwrap();
+        assert_eq!(user_id, 5);
+        assert_eq!(refresh_token.len(), REFRESH_TOKEN_LENGTH);
+        assert!(matches!(
+            login_session_service.refresh("r0", &context),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn test_refresh_expired() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_by_refresh_token_hash()
+            .with(eq(hash_secret("r1")))
+            .times(1)
+            .returning(|_| {
+                Ok(LoginSession {
+                    refresh_token_hash: Some(hash_secret("r1")),
+                    ..login_session(1, "a1b2")
+                })
+            });
+        mocked_login_session_repository
+            .expect_rotate_refresh_token()
+            .times(0);
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(
+            Utc.ymd(2020, 5, 13).and_hms(16, 31, 9),
+        )));
+        let context = AuditContext {
+            session_id: Some(String::from("c3d4")),
+            ..AuditContext::default()
+        };
+
+        assert!(matches!(
+            login_session_service.refresh("r1", &context),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn test_refresh_rotated_out() {
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_find_by_refresh_token_hash()
+            .with(eq(hash_secret("r1")))
+            .times(1)
+            .returning(|refresh_token_hash| {
+                Err(ServiceError::NotFound(refresh_token_hash.to_string()))
+            });
+        mocked_login_session_repository
+            .expect_find_by_rotated_refresh_token_hash()
+            .with(eq(hash_secret("r1")))
+            .times(1)
+            .returning(|_| {
+                Ok(LoginSession {
+                    refresh_token_hash: Some(hash_secret("r2")),
+                    ..login_session(1, "a1b2")
+                })
+            });
+        mocked_login_session_repository
+            .expect_delete()
+            .with(eq(1), eq(5))
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let context = AuditContext {
+            session_id: Some(String::from("c3d4")),
+            ..AuditContext::default()
+        };
+
+        // The refresh token has been rotated to `r2` by another device, which is revoked with it.
+        assert!(matches!(
+            login_session_service.refresh("r1", &context),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+
+    #[test]
+    fn test_prune() {
+        let now = Utc.ymd(2020, 5, 13).and_hms(16, 31, 9);
+
+        let mut mocked_login_session_repository = MockLoginSessionRepositoryTrait::new();
+        mocked_login_session_repository
+            .expect_delete_all_expired()
+            .with(
+                eq(now.naive_utc()),
+                eq(now.naive_utc() - Duration::days(DEFAULT_SESSION_IDLE_DAYS)),
+            )
+            .times(1)
+            .returning(|_, _| Ok(2));
+
+        let mut login_session_service = LoginSessionService::new_with_repository(
+            mocked_login_session_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(login_session_service.prune().unwrap(), 2);
+    }
+}
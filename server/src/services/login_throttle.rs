@@ -0,0 +1,584 @@
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::login_attempt::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+
+/// Seconds of the sliding window in which failures are counted.
+const WINDOW_SECONDS: i64 = 900; // 15 min
+
+/// Failures of an account in the window, after which the account is locked.
+const MAX_ACCOUNT_FAILURES: usize = 5;
+
+/// Failures from an IP in the window, after which the IP is locked.
+/// It is larger than the one of an account, since users behind a NAT share an IP.
+const MAX_IP_FAILURES: usize = 20;
+
+/// Seconds of the first lockout, which doubles on each failure after that.
+const BASE_LOCKOUT_SECONDS: i64 = 60;
+
+/// Maximum seconds of a lockout.
+const MAX_LOCKOUT_SECONDS: i64 = 3600; // 1 hour
+
+/// Seconds login attempts are kept since the last failure, after which lockouts start over.
+const ATTEMPTS_TTL_SECONDS: usize = 86400; // 1 day
+
+/// Scope of signing in, whose attempts are counted apart from the ones of the other scopes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ThrottleScope {
+    /// Signing in with the password.
+    Login,
+    /// Emailing a magic link.
+    MagicLink,
+    /// Emailing a token for resetting the password.
+    PasswordToken,
+    /// Finishing signing in with a two-factor code.
+    TwoFactor,
+    /// Signing in with a passkey.
+    Passkey,
+}
+
+impl ThrottleScope {
+    /// Returns the prefix of keys of the scope. Signing in with the password has none,
+    /// so that keys stored before the other scopes are kept.
+    fn get_key_prefix(self) -> &'static str {
+        match self {
+            ThrottleScope::Login => "",
+            ThrottleScope::MagicLink => "magic_link:",
+            ThrottleScope::PasswordToken => "password_token:",
+            ThrottleScope::TwoFactor => "2fa:",
+            ThrottleScope::Passkey => "passkey:",
+        }
+    }
+
+    /// Returns whether a success is counted like a failure, since it sends an email.
+    fn is_sending_email(self) -> bool {
+        matches!(
+            self,
+            ThrottleScope::MagicLink | ThrottleScope::PasswordToken
+        )
+    }
+}
+
+/// Returns key of login attempts of an account in the scope.
+fn get_account_key(scope: ThrottleScope, account: &str) -> String {
+    format!(
+        "{}account:{}",
+        scope.get_key_prefix(),
+        account.trim().to_lowercase()
+    )
+}
+
+/// Returns keys of login attempts of an account and an IP in the scope, with the failures
+/// allowed to each.
+fn get_keys(
+    scope: ThrottleScope,
+    account: Option<&str>,
+    ip: &Option<String>,
+) -> Vec<(String, usize)> {
+    let mut keys = vec![];
+    if let Some(account) = account {
+        keys.push((get_account_key(scope, account), MAX_ACCOUNT_FAILURES));
+    }
+    if let Some(ip) = ip {
+        keys.push((
+            format!("{}ip:{}", scope.get_key_prefix(), ip),
+            MAX_IP_FAILURES,
+        ));
+    }
+    keys
+}
+
+/// Returns seconds of the lockout after `lockout_count` lockouts in a row.
+fn get_lockout_seconds(lockout_count: u32) -> i64 {
+    BASE_LOCKOUT_SECONDS
+        .saturating_mul(2_i64.saturating_pow(lockout_count))
+        .min(MAX_LOCKOUT_SECONDS)
+}
+
+/// Returns whether the error means the credentials were wrong.
+fn is_failure(error: &ServiceError) -> bool {
+    matches!(
+        error,
+        ServiceError::Unauthorized | ServiceError::NotFound(_) | ServiceError::UserNotFound(_)
+    )
+}
+
+/// Throttles signing in per account and per IP, in a scope of signing in.
+///
+/// Failures are counted in a sliding window. Once they reach the limit, signing in is locked
+/// for `BASE_LOCKOUT_SECONDS`, and every failure after the lockout locks it twice as long
+/// until the user signs in or a day passes. Scopes sending an email count every email as well.
+pub struct LoginThrottleService {
+    login_attempt_repository: Option<LoginAttemptRepository>,
+    clock: Arc<dyn Clock>,
+    scope: ThrottleScope,
+}
+
+impl LoginThrottleService {
+    pub fn new() -> Self {
+        Self {
+            login_attempt_repository: None,
+            clock: Arc::new(SystemClock),
+            scope: ThrottleScope::Login,
+        }
+    }
+
+    /// Sets the scope of signing in, which is `ThrottleScope::Login` by default.
+    pub fn with_scope(mut self, scope: ThrottleScope) -> Self {
+        self.scope = scope;
+        self
+    }
+
+    fn login_attempt_repository(
+        &mut self,
+        new_repository: Option<LoginAttemptRepository>,
+    ) -> &mut LoginAttemptRepository {
+        match new_repository {
+            Some(_) => {
+                self.login_attempt_repository = new_repository;
+                self.login_attempt_repository.as_mut().unwrap()
+            }
+            None => self.login_attempt_repository.as_mut().unwrap(),
+        }
+    }
+
+    fn find_attempts(&mut self, key: &str) -> Result<LoginAttempts, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.login_attempt_repository.is_none() => LoginAttemptRepository::new());
+        let serialized_attempts = self
+            .login_attempt_repository(fallback_repository)
+            .find(key)?;
+
+        match serialized_attempts {
+            Some(serialized_attempts) => match serde_json::from_str(&serialized_attempts) {
+                Ok(attempts) => Ok(attempts),
+                Err(_) => Err(get_service_error(ServiceError::InvalidFormat)),
+            },
+            None => Ok(LoginAttempts::default()),
+        }
+    }
+
+    /// Runs an attempt to sign in as `account` from `ip`, unless either of them is locked.
+    /// The account is the email of the user, or `None` if it is unknown before the attempt.
+    ///
+    /// It fails with `TooManyRequests` containing seconds until the lockout ends if locked.
+    /// Otherwise, a failure of the attempt is counted, and a success clears the failures
+    /// of the account unless the scope sends an email.
+    pub fn throttle<T>(
+        &mut self,
+        account: Option<&str>,
+        ip: &Option<String>,
+        attempt: impl FnOnce() -> Result<T, ServiceError>,
+    ) -> Result<T, ServiceError> {
+        let keys = get_keys(self.scope, account, ip);
+        let now = self.clock.now().timestamp();
+
+        let mut retry_after = 0;
+        for (key, _) in &keys {
+            if let Some(locked_until) = self.find_attempts(key)?.locked_until {
+                retry_after = retry_after.max(locked_until - now);
+            }
+        }
+        if retry_after > 0 {
+            return Err(get_service_error(ServiceError::TooManyRequests(
+                retry_after as u64,
+            )));
+        }
+
+        let result = attempt();
+        let is_counted = match &result {
+            Ok(_) => self.scope.is_sending_email(),
+            Err(error) => is_failure(error),
+        };
+        if is_counted {
+            for (key, max_failures) in &keys {
+                self.record_failure(key, *max_failures, now)?;
+            }
+        } else if let (Ok(_), Some(account)) = (&result, account) {
+            let account_key = get_account_key(self.scope, account);
+            self.login_attempt_repository(None).delete(&account_key)?;
+        }
+        result
+    }
+
+    /// Returns the rate limit of signing in as `account` from `ip`, which is the one of the key
+    /// with the fewest failures remaining. It fails with `InvalidArgument` if neither is known.
+    ///
+    /// A key locked before has one failure remaining until its attempts expire, since the next
+    /// failure locks it again.
+    pub fn rate_limit(
+        &mut self,
+        account: Option<&str>,
+        ip: &Option<String>,
+    ) -> Result<RateLimit, ServiceError> {
+        let now = self.clock.now().timestamp();
+
+        let mut rate_limit: Option<RateLimit> = None;
+        for (key, max_failures) in get_keys(self.scope, account, ip) {
+            let attempts = self.find_attempts(&key)?;
+            let (remaining, reset_at) = match attempts.locked_until {
+                Some(locked_until) if locked_until > now => (0, locked_until),
+                Some(locked_until) => {
+                    let failed_at = locked_until
+                        - get_lockout_seconds(attempts.lockout_count.saturating_sub(1));
+                    (1, failed_at + ATTEMPTS_TTL_SECONDS as i64)
+                }
+                None => {
+                    let failures: Vec<&i64> = attempts
+                        .failures
+                        .iter()
+                        .filter(|failed_at| now - *failed_at < WINDOW_SECONDS)
+                        .collect();
+                    let reset_at = failures
+                        .iter()
+                        .min()
+                        .map_or(now, |failed_at| *failed_at + WINDOW_SECONDS);
+                    (max_failures.saturating_sub(failures.len()), reset_at)
+                }
+            };
+
+            let is_stricter = match &rate_limit {
+                Some(rate_limit) => {
+                    remaining < rate_limit.remaining
+                        || (remaining == rate_limit.remaining && reset_at > rate_limit.reset_at)
+                }
+                None => true,
+            };
+            if is_stricter {
+                rate_limit = Some(RateLimit {
+                    limit: max_failures,
+                    remaining,
+                    reset_after: (reset_at - now).max(0) as u64,
+                    reset_at,
+                });
+            }
+        }
+
+        rate_limit.ok_or_else(|| get_service_error(ServiceError::InvalidArgument))
+    }
+
+    /// Counts a failure, and locks the key if the failures reach `max_failures`
+    /// or it has been locked before.
+    fn record_failure(
+        &mut self,
+        key: &str,
+        max_failures: usize,
+        now: i64,
+    ) -> Result<bool, ServiceError> {
+        let mut attempts = self.find_attempts(key)?;
+        attempts
+            .failures
+            .retain(|failed_at| now - failed_at < WINDOW_SECONDS);
+        attempts.failures.push(now);
+
+        if attempts.lockout_count > 0 || attempts.failures.len() >= max_failures {
+            let lockout_seconds = get_lockout_seconds(attempts.lockout_count);
+            attempts.lockout_count += 1;
+            attempts.locked_until = Some(now + lockout_seconds);
+            attempts.failures.clear();
+        }
+
+        let serialized_attempts = if let Ok(serialized_attempts) = serde_json::to_string(&attempts)
+        {
+            serialized_attempts
+        } else {
+            return Err(get_service_error(ServiceError::InvalidFormat));
+        };
+        self.login_attempt_repository(None)
+            .save(key, &serialized_attempts, ATTEMPTS_TTL_SECONDS)
+    }
+}
+
+impl Default for LoginThrottleService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::login_attempt::MockLoginAttemptRepositoryTrait as LoginAttemptRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    use super::*;
+    use crate::models::login_attempt::MockLoginAttemptRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl LoginThrottleService {
+        pub fn new_with_repository(login_attempt_repository: LoginAttemptRepository) -> Self {
+            Self {
+                login_attempt_repository: Some(login_attempt_repository),
+                clock: Arc::new(SystemClock),
+                scope: ThrottleScope::Login,
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    /// Returns a repository keeping login attempts in memory.
+    fn in_memory_repository() -> MockLoginAttemptRepositoryTrait {
+        let store = Arc::new(Mutex::new(HashMap::<String, String>::new()));
+        let mut mocked_login_attempt_repository = MockLoginAttemptRepositoryTrait::new();
+
+        let find_store = store.clone();
+        mocked_login_attempt_repository
+            .expect_find()
+            .returning(move |key| Ok(find_store.lock().unwrap().get(key).cloned()));
+        let save_store = store.clone();
+        mocked_login_attempt_repository.expect_save().returning(
+            move |key, serialized_attempts, _| {
+                save_store
+                    .lock()
+                    .unwrap()
+                    .insert(key.to_string(), serialized_attempts.to_string());
+                Ok(true)
+            },
+        );
+        mocked_login_attempt_repository
+            .expect_delete()
+            .returning(move |key| Ok(store.lock().unwrap().remove(key).is_some()));
+
+        mocked_login_attempt_repository
+    }
+
+    fn retry_after(result: Result<bool, ServiceError>) -> Option<u64> {
+        match result {
+            Err(ServiceError::TooManyRequests(seconds)) => Some(seconds),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_throttle_account() {
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 4, 13).and_hms(16, 31, 9)));
+        let mut login_throttle_service =
+            LoginThrottleService::new_with_repository(in_memory_repository())
+                .with_clock(clock.clone());
+        let ip = Some(String::from("127.0.0.1"));
+
+        for _ in 0..MAX_ACCOUNT_FAILURES {
+            assert!(matches!(
+                login_throttle_service.throttle(Some("park@email.com"), &ip, || Err::<bool, _>(
+                    ServiceError::Unauthorized
+                )),
+                Err(ServiceError::Unauthorized)
+            ));
+        }
+
+        // The account is locked even with the right password, from another IP.
+        assert_eq!(
+            retry_after(login_throttle_service.throttle(
+                Some("Park@email.com"),
+                &Some(String::from("10.0.0.1")),
+                || Ok(true)
+            )),
+            Some(60)
+        );
+
+        // A failure after the lockout locks the account twice as long.
+        clock.advance(Duration::seconds(60));
+        login_throttle_service
+            .throttle(Some("park@email.com"), &ip, || {
+                Err::<bool, _>(ServiceError::Unauthorized)
+            })
+            .unwrap_err();
+        assert_eq!(
+            retry_after(login_throttle_service.throttle(Some("park@email.com"), &ip, || Ok(true))),
+            Some(120)
+        );
+
+        // Signing in clears the failures.
+        clock.advance(Duration::seconds(120));
+        assert!(login_throttle_service
+            .throttle(Some("park@email.com"), &ip, || Ok(true))
+            .unwrap());
+        login_throttle_service
+            .throttle(Some("park@email.com"), &ip, || {
+                Err::<bool, _>(ServiceError::Unauthorized)
+            })
+            .unwrap_err();
+        assert!(login_throttle_service
+            .throttle(Some("park@email.com"), &ip, || Ok(true))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_rate_limit() {
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 4, 13).and_hms(16, 31, 9)));
+        let now = clock.now().timestamp();
+        let mut login_throttle_service =
+            LoginThrottleService::new_with_repository(in_memory_repository())
+                .with_clock(clock.clone());
+        let ip = Some(String::from("127.0.0.1"));
+
+        assert_eq!(
+            login_throttle_service
+                .rate_limit(Some("park@email.com"), &ip)
+                .unwrap(),
+            RateLimit {
+                limit: MAX_ACCOUNT_FAILURES,
+                remaining: MAX_ACCOUNT_FAILURES,
+                reset_after: 0,
+                reset_at: now,
+            }
+        );
+
+        // The remaining failures count down until the first one leaves the window.
+        for failures in 1..MAX_ACCOUNT_FAILURES {
+            login_throttle_service
+                .throttle(Some("park@email.com"), &ip, || {
+                    Err::<bool, _>(ServiceError::Unauthorized)
+                })
+                .unwrap_err();
+            clock.advance(Duration::seconds(1));
+
+            let rate_limit = login_throttle_service
+                .rate_limit(Some("park@email.com"), &ip)
+                .unwrap();
+            assert_eq!(rate_limit.remaining, MAX_ACCOUNT_FAILURES - failures);
+            assert_eq!(rate_limit.reset_at, now + WINDOW_SECONDS);
+            assert!(rate_limit.reset_after > 0);
+        }
+
+        // The locked account is reset when the lockout ends.
+        login_throttle_service
+            .throttle(Some("park@email.com"), &ip, || {
+                Err::<bool, _>(ServiceError::Unauthorized)
+            })
+            .unwrap_err();
+        let locked_at = clock.now().timestamp();
+        assert_eq!(
+            login_throttle_service
+                .rate_limit(Some("park@email.com"), &ip)
+                .unwrap(),
+            RateLimit {
+                limit: MAX_ACCOUNT_FAILURES,
+                remaining: 0,
+                reset_after: BASE_LOCKOUT_SECONDS as u64,
+                reset_at: locked_at + BASE_LOCKOUT_SECONDS,
+            }
+        );
+
+        // After the lockout, the next failure locks the account again until the attempts expire.
+        clock.advance(Duration::seconds(BASE_LOCKOUT_SECONDS));
+        let rate_limit = login_throttle_service
+            .rate_limit(Some("park@email.com"), &ip)
+            .unwrap();
+        assert_eq!(rate_limit.remaining, 1);
+        assert_eq!(rate_limit.reset_at, locked_at + ATTEMPTS_TTL_SECONDS as i64);
+    }
+
+    #[test]
+    fn test_throttle_sliding_window() {
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 4, 13).and_hms(16, 31, 9)));
+        let mut login_throttle_service =
+            LoginThrottleService::new_with_repository(in_memory_repository())
+                .with_clock(clock.clone());
+
+        for _ in 1..MAX_ACCOUNT_FAILURES {
+            login_throttle_service
+                .throttle(Some("park@email.com"), &None, || {
+                    Err::<bool, _>(ServiceError::Unauthorized)
+                })
+                .unwrap_err();
+        }
+
+        // The failures have left the window, so the next one does not lock the account.
+        clock.advance(Duration::seconds(WINDOW_SECONDS));
+        login_throttle_service
+            .throttle(Some("park@email.com"), &None, || {
+                Err::<bool, _>(ServiceError::Unauthorized)
+            })
+            .unwrap_err();
+        assert!(login_throttle_service
+            .throttle(Some("park@email.com"), &None, || Ok(true))
+            .unwrap());
+    }
+
+    #[test]
+    fn test_throttle_ip() {
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 4, 13).and_hms(16, 31, 9)));
+        let mut login_throttle_service =
+            LoginThrottleService::new_with_repository(in_memory_repository())
+                .with_clock(clock.clone());
+        let ip = Some(String::from("127.0.0.1"));
+
+        for index in 0..MAX_IP_FAILURES {
+            let email = format!("user{}@email.com", index);
+            login_throttle_service
+                .throttle(Some(&email), &ip, || {
+                    Err::<bool, _>(ServiceError::NotFound(email.clone()))
+                })
+                .unwrap_err();
+        }
+
+        assert_eq!(
+            retry_after(login_throttle_service.throttle(Some("park@email.com"), &ip, || Ok(true))),
+            Some(60)
+        );
+        assert!(login_throttle_service
+            .throttle(
+                Some("park@email.com"),
+                &Some(String::from("10.0.0.1")),
+                || Ok(true)
+            )
+            .unwrap());
+    }
+
+    #[test]
+    fn test_throttle_scope() {
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 4, 13).and_hms(16, 31, 9)));
+        let ip = Some(String::from("127.0.0.1"));
+
+        // Every email sent counts, so the account is locked after the limit of emails.
+        let mut magic_link_throttle_service =
+            LoginThrottleService::new_with_repository(in_memory_repository())
+                .with_clock(clock.clone())
+                .with_scope(ThrottleScope::MagicLink);
+        for _ in 0..MAX_ACCOUNT_FAILURES {
+            assert!(magic_link_throttle_service
+                .throttle(Some("park@email.com"), &ip, || Ok(true))
+                .unwrap());
+        }
+        assert_eq!(
+            retry_after(
+                magic_link_throttle_service.throttle(Some("park@email.com"), &ip, || Ok(true))
+            ),
+            Some(60)
+        );
+
+        // Signing in with the password is counted apart from the emails.
+        let mut login_throttle_service =
+            magic_link_throttle_service.with_scope(ThrottleScope::Login);
+        assert!(login_throttle_service
+            .throttle(Some("park@email.com"), &ip, || Ok(true))
+            .unwrap());
+
+        // The attempts without an account are counted per IP.
+        let mut two_factor_throttle_service =
+            login_throttle_service.with_scope(ThrottleScope::TwoFactor);
+        two_factor_throttle_service
+            .throttle(None, &ip, || Err::<bool, _>(ServiceError::Unauthorized))
+            .unwrap_err();
+        assert_eq!(
+            two_factor_throttle_service
+                .rate_limit(None, &ip)
+                .unwrap()
+                .remaining,
+            MAX_IP_FAILURES - 1
+        );
+        assert!(matches!(
+            two_factor_throttle_service.rate_limit(None, &None),
+            Err(ServiceError::InvalidArgument)
+        ));
+    }
+}
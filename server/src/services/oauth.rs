@@ -0,0 +1,254 @@
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::oauth_account::*;
+use crate::models::oauth_provider::{self, OAuthProvider};
+use crate::models::user::UserRepository;
+
+pub struct OAuthService {
+    oauth_account_repository: Option<OAuthAccountRepository>,
+    user_repository: Option<UserRepository>,
+}
+
+impl OAuthService {
+    pub fn new() -> Self {
+        Self {
+            oauth_account_repository: None,
+            user_repository: None,
+        }
+    }
+
+    fn oauth_account_repository(
+        &mut self,
+        new_repository: Option<OAuthAccountRepository>,
+    ) -> &OAuthAccountRepository {
+        match new_repository {
+            Some(_) => {
+                self.oauth_account_repository = new_repository;
+                self.oauth_account_repository.as_ref().unwrap()
+            }
+            None => self.oauth_account_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_repository = new_repository;
+                self.user_repository.as_ref().unwrap()
+            }
+            None => self.user_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Returns the provider of `name`, or `NotFound` error if it is not supported or configured.
+    fn get_provider(name: &str) -> Result<Box<dyn OAuthProvider>, ServiceError> {
+        match oauth_provider::get_provider(name) {
+            Some(provider) => Ok(provider),
+            None => Err(get_service_error(ServiceError::NotFound(name.to_string()))),
+        }
+    }
+
+    /// Returns the URL of the consent page of a provider, which redirects back with `state`.
+    pub fn get_authorization_url(&self, name: &str, state: &str) -> Result<String, ServiceError> {
+        let provider = Self::get_provider(name)?;
+        Ok(provider.get_authorization_url(&oauth_provider::get_redirect_uri(name), state))
+    }
+
+    /// Signs in with a code of a provider, and returns id of the user who has signed in.
+    pub fn login(&mut self, name: &str, code: &str) -> Result<u64, ServiceError> {
+        let provider = Self::get_provider(name)?;
+        self.find_user_id(
+            name,
+            provider.as_ref(),
+            &oauth_provider::get_redirect_uri(name),
+            code,
+        )
+    }
+
+    /// Finds the user linked to the account granted by a code.
+    ///
+    /// 1. Exchanges the code with the account of the provider.
+    /// 2. If the account has been linked to a user, returns the user.
+    /// 3. Otherwise, links the account to the user whose email is the verified email of the account.
+    ///    It fails with `Unauthorized` if there is no such user, since signing up needs
+    ///    the key pair generated by the client.
+    fn find_user_id(
+        &mut self,
+        name: &str,
+        provider: &dyn OAuthProvider,
+        redirect_uri: &str,
+        code: &str,
+    ) -> Result<u64, ServiceError> {
+        let identity = provider.get_identity(redirect_uri, code)?;
+
+        let fallback_repository =
+            some_if_true!(self.oauth_account_repository.is_none() => OAuthAccountRepository::new());
+        match self
+            .oauth_account_repository(fallback_repository)
+            .find(name, &identity.subject)
+        {
+            Ok(account) => return Ok(account.user_id),
+            Err(ServiceError::NotFound(_)) => {}
+            Err(error) => return Err(error),
+        }
+
+        let email = match identity.verified_email {
+            Some(email) => email,
+            None => return Err(get_service_error(ServiceError::Unauthorized)),
+        };
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            match self
+                .user_repository(fallback_repository)
+                .find_by_email(&email)
+            {
+                Ok(user) => user,
+                Err(ServiceError::NotFound(_)) => {
+                    return Err(get_service_error(ServiceError::Unauthorized))
+                }
+                Err(error) => return Err(error),
+            }
+        };
+
+        self.oauth_account_repository(None)
+            .create(user.id, name, &identity.subject)?;
+        Ok(user.id)
+    }
+}
+
+impl Default for OAuthService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::oauth_account::MockOAuthAccountRepositoryTrait as OAuthAccountRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::oauth_account::MockOAuthAccountRepositoryTrait;
+    use crate::models::oauth_provider::OAuthIdentity;
+    use crate::models::user::{MockUserRepositoryTrait, User};
+
+    impl OAuthService {
+        pub fn new_with_repository(
+            oauth_account_repository: OAuthAccountRepository,
+            user_repository: UserRepository,
+        ) -> Self {
+            Self {
+                oauth_account_repository: Some(oauth_account_repository),
+                user_repository: Some(user_repository),
+            }
+        }
+    }
+
+    /// Provider granting an account with the verified email `park@email.com`.
+    struct TestProvider;
+
+    impl OAuthProvider for TestProvider {
+        fn get_authorization_url(&self, redirect_uri: &str, state: &str) -> String {
+            format!(
+                "https://example.com/auth?redirect_uri={}&state={}",
+                redirect_uri, state
+            )
+        }
+
+        fn get_identity(&self, _: &str, code: &str) -> Result<OAuthIdentity, ServiceError> {
+            match code {
+                "a1b2" => Ok(OAuthIdentity {
+                    subject: String::from("42"),
+                    verified_email: Some(String::from("park@email.com")),
+                }),
+                _ => Err(ServiceError::Unauthorized),
+            }
+        }
+    }
+
+    fn user() -> User {
+        User {
+            id: 5,
+            name: String::from("park"),
+            email: String::from("park@email.com"),
+            password: String::from("password"),
+            avatar_url: None,
+            created_at: Utc::now().naive_utc(),
+            updated_at: None,
+            key_metadata: None,
+            daily_word_goal: None,
+            monthly_word_goal: None,
+            role: String::from("user"),
+            suspended_at: None,
+        }
+    }
+
+    #[test]
+    fn test_link_by_verified_email() {
+        let mut mocked_oauth_account_repository = MockOAuthAccountRepositoryTrait::new();
+        mocked_oauth_account_repository
+            .expect_find()
+            .with(eq("test"), eq("42"))
+            .times(1)
+            .returning(|_, _| Err(ServiceError::NotFound(String::from("test:42"))));
+        mocked_oauth_account_repository
+            .expect_create()
+            .with(eq(5), eq("test"), eq("42"))
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_email()
+            .with(eq("park@email.com"))
+            .times(1)
+            .returning(|_| Ok(user()));
+
+        let mut oauth_service = OAuthService::new_with_repository(
+            mocked_oauth_account_repository,
+            mocked_user_repository,
+        );
+
+        assert_eq!(
+            oauth_service
+                .find_user_id("test", &TestProvider, "https://example.com", "a1b2")
+                .unwrap(),
+            5
+        );
+    }
+
+    #[test]
+    fn test_login_with_unknown_email() {
+        let mut mocked_oauth_account_repository = MockOAuthAccountRepositoryTrait::new();
+        mocked_oauth_account_repository
+            .expect_find()
+            .times(1)
+            .returning(|_, _| Err(ServiceError::NotFound(String::from("test:42"))));
+        mocked_oauth_account_repository.expect_create().times(0);
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_email()
+            .times(1)
+            .returning(|email| Err(ServiceError::NotFound(email.to_string())));
+
+        let mut oauth_service = OAuthService::new_with_repository(
+            mocked_oauth_account_repository,
+            mocked_user_repository,
+        );
+
+        assert!(matches!(
+            oauth_service.find_user_id("test", &TestProvider, "https://example.com", "a1b2"),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(matches!(
+            oauth_service.find_user_id("test", &TestProvider, "https://example.com", "c3d4"),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+}
@@ -0,0 +1,288 @@
+use chrono::Duration;
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::personal_access_token::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+
+/// Prefix of personal access tokens, which lets the api gateway tell them from access tokens.
+pub const TOKEN_PREFIX: &str = "darim_pat_";
+
+/// Length of a personal access token except the prefix.
+const TOKEN_LENGTH: usize = 40;
+
+/// Maximum length of the name of a personal access token.
+const MAX_NAME_LENGTH: usize = 100;
+
+/// Maximum number of personal access tokens a user has.
+const MAX_TOKENS_PER_USER: i64 = 20;
+
+/// Minutes the time a personal access token has been used last is not updated for,
+/// so that each request does not write it.
+const LAST_USED_INTERVAL_MINUTES: i64 = 5;
+
+/// Returns the hash of a personal access token in hex.
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+pub struct PersonalAccessTokenService {
+    personal_access_token_repository: Option<PersonalAccessTokenRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PersonalAccessTokenService {
+    pub fn new() -> Self {
+        Self {
+            personal_access_token_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn personal_access_token_repository(
+        &mut self,
+        new_repository: Option<PersonalAccessTokenRepository>,
+    ) -> &PersonalAccessTokenRepository {
+        match new_repository {
+            Some(_) => {
+                self.personal_access_token_repository = new_repository;
+                self.personal_access_token_repository.as_ref().unwrap()
+            }
+            None => self.personal_access_token_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Lists personal access tokens of specific user, without the tokens themselves.
+    pub fn get_list(&mut self, user_id: u64) -> Result<Vec<PersonalAccessTokenDTO>, ServiceError> {
+        let fallback_repository = some_if_true!(self.personal_access_token_repository.is_none() => PersonalAccessTokenRepository::new());
+        let tokens = self
+            .personal_access_token_repository(fallback_repository)
+            .find_all_by_user_id(user_id)?;
+
+        Ok(tokens
+            .into_iter()
+            .map(|token| PersonalAccessTokenDTO {
+                id: token.id,
+                name: token.name,
+                scope: token.scope,
+                created_at: token.created_at,
+                last_used_at: token.last_used_at,
+            })
+            .collect())
+    }
+
+    /// Creates a personal access token of specific user, and returns it with the token,
+    /// which is not able to be read again.
+    pub fn create(
+        &mut self,
+        user_id: u64,
+        name: &str,
+        scope: &str,
+    ) -> Result<CreatedPersonalAccessTokenDTO, ServiceError> {
+        let name = name.trim();
+        if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+        let scope = PersonalAccessTokenScope::parse(scope)?;
+
+        let fallback_repository = some_if_true!(self.personal_access_token_repository.is_none() => PersonalAccessTokenRepository::new());
+        if self
+            .personal_access_token_repository(fallback_repository)
+            .count_by_user_id(user_id)?
+            >= MAX_TOKENS_PER_USER
+        {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let random: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(TOKEN_LENGTH)
+            .collect();
+        let token = format!("{}{}", TOKEN_PREFIX, random);
+        let id = self.personal_access_token_repository(None).create(
+            user_id,
+            name,
+            &hash_token(&token),
+            scope.as_str(),
+        )?;
+
+        Ok(CreatedPersonalAccessTokenDTO {
+            id,
+            name: name.to_string(),
+            scope: scope.as_str().to_string(),
+            token,
+        })
+    }
+
+    /// Returns id of the user and the scope of a personal access token, and marks it used.
+    pub fn verify(&mut self, token: &str) -> Result<(u64, PersonalAccessTokenScope), ServiceError> {
+        if !token.starts_with(TOKEN_PREFIX) {
+            return Err(get_service_error(ServiceError::Unauthorized));
+        }
+
+        let fallback_repository = some_if_true!(self.personal_access_token_repository.is_none() => PersonalAccessTokenRepository::new());
+        let personal_access_token = match self
+            .personal_access_token_repository(fallback_repository)
+            .find_by_token_hash(&hash_token(token))
+        {
+            Ok(personal_access_token) => personal_access_token,
+            Err(ServiceError::NotFound(_)) => {
+                return Err(get_service_error(ServiceError::Unauthorized))
+            }
+            Err(error) => return Err(error),
+        };
+        let scope = PersonalAccessTokenScope::parse(&personal_access_token.scope)?;
+
+        let now = self.clock.now().naive_utc();
+        let is_stale = match personal_access_token.last_used_at {
+            Some(last_used_at) => {
+                now - last_used_at >= Duration::minutes(LAST_USED_INTERVAL_MINUTES)
+            }
+            None => true,
+        };
+        if is_stale {
+            self.personal_access_token_repository(None)
+                .update_last_used_at(personal_access_token.id, &now)?;
+        }
+
+        Ok((personal_access_token.user_id, scope))
+    }
+
+    /// Deletes a personal access token of specific user, which is not accepted anymore.
+    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository = some_if_true!(self.personal_access_token_repository.is_none() => PersonalAccessTokenRepository::new());
+        self.personal_access_token_repository(fallback_repository)
+            .delete(id, user_id)
+    }
+}
+
+impl Default for PersonalAccessTokenService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::personal_access_token::MockPersonalAccessTokenRepositoryTrait as PersonalAccessTokenRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::personal_access_token::MockPersonalAccessTokenRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl PersonalAccessTokenService {
+        pub fn new_with_repository(
+            personal_access_token_repository: PersonalAccessTokenRepository,
+        ) -> Self {
+            Self {
+                personal_access_token_repository: Some(personal_access_token_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    #[test]
+    fn test_create() {
+        let mut mocked_personal_access_token_repository =
+            MockPersonalAccessTokenRepositoryTrait::new();
+        mocked_personal_access_token_repository
+            .expect_count_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(0));
+        mocked_personal_access_token_repository
+            .expect_create()
+            .with(eq(5), eq("deploy script"), always(), eq("write"))
+            .times(1)
+            .returning(|_, _, _, _| Ok(1));
+
+        let mut personal_access_token_service = PersonalAccessTokenService::new_with_repository(
+            mocked_personal_access_token_repository,
+        );
+
+        let created = personal_access_token_service
+            .create(5, " deploy script ", "write")
+            .unwrap();
+        assert_eq!(created.id, 1);
+        assert!(created.token.starts_with(TOKEN_PREFIX));
+        assert_eq!(created.token.len(), TOKEN_PREFIX.len() + TOKEN_LENGTH);
+        assert!(matches!(
+            personal_access_token_service.create(5, "", "write"),
+            Err(ServiceError::InvalidArgument)
+        ));
+        assert!(matches!(
+            personal_access_token_service.create(5, "deploy script", "admin"),
+            Err(ServiceError::InvalidArgument)
+        ));
+    }
+
+    #[test]
+    fn test_verify() {
+        let token = format!("{}a1b2", TOKEN_PREFIX);
+        let token_hash = hash_token(&token);
+
+        let mut mocked_personal_access_token_repository =
+            MockPersonalAccessTokenRepositoryTrait::new();
+        mocked_personal_access_token_repository
+            .expect_find_by_token_hash()
+            .with(eq(token_hash.clone()))
+            .times(2)
+            .returning(move |token_hash| {
+                Ok(PersonalAccessToken {
+                    id: 1,
+                    user_id: 5,
+                    name: String::from("deploy script"),
+                    token_hash: token_hash.to_string(),
+                    scope: String::from("read"),
+                    created_at: Utc.ymd(2020, 4, 13).and_hms(16, 31, 9).naive_utc(),
+                    last_used_at: Some(Utc.ymd(2020, 4, 13).and_hms(16, 31, 9).naive_utc()),
+                })
+            });
+        mocked_personal_access_token_repository
+            .expect_find_by_token_hash()
+            .with(eq(hash_token(&format!("{}c3d4", TOKEN_PREFIX))))
+            .times(1)
+            .returning(|token_hash| Err(ServiceError::NotFound(token_hash.to_string())));
+        mocked_personal_access_token_repository
+            .expect_update_last_used_at()
+            .with(eq(1), always())
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let clock = Arc::new(TestClock::new(Utc.ymd(2020, 4, 13).and_hms(16, 32, 0)));
+        let mut personal_access_token_service = PersonalAccessTokenService::new_with_repository(
+            mocked_personal_access_token_repository,
+        )
+        .with_clock(clock.clone());
+
+        // The token has been used a minute ago, so it is not updated yet.
+        assert_eq!(
+            personal_access_token_service.verify(&token).unwrap(),
+            (5, PersonalAccessTokenScope::Read)
+        );
+        clock.advance(Duration::minutes(LAST_USED_INTERVAL_MINUTES));
+        assert_eq!(
+            personal_access_token_service.verify(&token).unwrap(),
+            (5, PersonalAccessTokenScope::Read)
+        );
+        assert!(matches!(
+            personal_access_token_service.verify(&format!("{}c3d4", TOKEN_PREFIX)),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(matches!(
+            personal_access_token_service.verify("a1b2"),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+}
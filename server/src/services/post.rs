@@ -1,16 +1,72 @@
-use chrono::NaiveDateTime;
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
 
-use crate::models::error::{get_service_error, ServiceError};
+use crate::models::error::{get_service_error, FieldError, ServiceError};
 use crate::models::post::*;
+use crate::models::post_audit::AuditContext;
+use crate::models::post_revision::PostRevisionDTO;
+use crate::models::template::*;
+use crate::models::user::*;
+use crate::models::user_settings::{self, UserSettings, UserSettingsRepository, WeekStartDay};
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::meta_util::{ResponseMeta, WithMeta};
+use crate::utils::pagination_util::{self, Batch, Page, PageMeta, DEFAULT_PER_PAGE};
+use crate::utils::password_util;
+use crate::utils::stats_util::{self, Granularity};
+
+/// Maximum number of operations in a bulk request.
+pub const MAX_BULK_OPERATIONS: usize = 500;
+
+/// Number of operations of a bulk request executed at once, in a transaction.
+pub const BULK_BATCH_SIZE: usize = 100;
+
+/// How far the cursor of changes goes back from when the changes are found, so that
+/// changes committed late by long transactions are found again by the next sync.
+const CHANGES_CURSOR_OVERLAP_SECONDS: i64 = 60;
+
+/// Default radius in meters of the circle to find posts written near a location.
+const DEFAULT_NEAR_RADIUS_METERS: f64 = 1000.0;
+
+/// Default interval of revisions taken by autosaves.
+const DEFAULT_AUTOSAVE_REVISION_MINUTES: i64 = 10;
+
+/// Default retention period of posts in the trash.
+const DEFAULT_TRASH_RETENTION_DAYS: i64 = 30;
+
+/// Period before posts in the trash are permanently deleted, in which clients are warned.
+const TRASH_PURGE_WARNING_DAYS: i64 = 3;
+
+/// Maximum length in bytes of titles and contents, which is the capacity of their columns.
+const MAX_TEXT_LENGTH: usize = 65535;
+
+/// Default maximum length in bytes of titles of posts.
+const DEFAULT_MAX_TITLE_LENGTH: usize = 1000;
+
+/// Default retention period of tombstones of permanently deleted posts.
+const DEFAULT_TOMBSTONE_RETENTION_DAYS: i64 = 365;
+
+/// Number of posts read at once while backfilling the offset of dates.
+const DATE_OFFSET_BACKFILL_BATCH_SIZE: i64 = 1000;
 
 pub struct PostService {
     post_repository: Option<PostRepository>,
+    user_repository: Option<UserRepository>,
+    template_repository: Option<TemplateRepository>,
+    user_settings_repository: Option<UserSettingsRepository>,
+    clock: Arc<dyn Clock>,
 }
 
 impl PostService {
     pub fn new() -> Self {
         Self {
             post_repository: None,
+            user_repository: None,
+            template_repository: None,
+            user_settings_repository: None,
+            clock: Arc::new(SystemClock),
         }
     }
 
@@ -24,50 +80,714 @@ impl PostService {
         }
     }
 
+    fn template_repository(
+        &mut self,
+        new_repository: Option<TemplateRepository>,
+    ) -> &TemplateRepository {
+        match new_repository {
+            Some(_) => {
+                self.template_repository = new_repository;
+                self.template_repository.as_ref().unwrap()
+            }
+            None => self.template_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_repository = new_repository;
+                self.user_repository.as_ref().unwrap()
+            }
+            None => self.user_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn user_settings_repository(
+        &mut self,
+        new_repository: Option<UserSettingsRepository>,
+    ) -> &UserSettingsRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_settings_repository = new_repository;
+                self.user_settings_repository.as_ref().unwrap()
+            }
+            None => self.user_settings_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds settings of specific user, or `None` if the user has never changed them.
+    fn find_settings(&mut self, user_id: u64) -> Result<Option<UserSettings>, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.user_settings_repository.is_none() => UserSettingsRepository::new());
+        self.user_settings_repository(fallback_repository)
+            .find_by_user_id(user_id)
+    }
+
+    /// Returns today in the time zone set by specific user, or in UTC if it is not set.
+    fn get_local_today(&mut self, user_id: u64) -> Result<NaiveDate, ServiceError> {
+        let settings = self.find_settings(user_id)?;
+        Ok(user_settings::get_local_date(&settings, &self.clock.now()))
+    }
+
+    /// Sorts posts in date order by the order in each day, keeping the order of the rest.
+    fn sort_in_day_order(mut post_list: Vec<Post>, sort_order: SortOrder) -> Vec<Post> {
+        post_list.sort_by(|a, b| {
+            let date_ordering = a.post_date().local_date().cmp(&b.post_date().local_date());
+            let date_ordering = match sort_order {
+                SortOrder::Asc => date_ordering,
+                SortOrder::Desc => date_ordering.reverse(),
+            };
+            date_ordering.then(a.intra_day_order.cmp(&b.intra_day_order))
+        });
+        post_list
+    }
+
+    /// Returns streaks of days with posts until `today`.
+    ///
+    /// # Arguments
+    ///
+    /// * `dates` - Local dates of posts without duplicates, in asc order.
+    /// * `today` - The local date of the user. Dates after it are ignored.
+    fn count_streaks(dates: &[NaiveDate], today: NaiveDate) -> StreakDTO {
+        let mut longest_streak = 0;
+        let mut streak = 0;
+        let mut last_date: Option<NaiveDate> = None;
+        for date in dates.iter().filter(|date| **date <= today) {
+            streak = match last_date {
+                Some(last_date) if last_date.succ() == *date => streak + 1,
+                _ => 1,
+            };
+            longest_streak = longest_streak.max(streak);
+            last_date = Some(*date);
+        }
+
+        let current_streak = match last_date {
+            Some(last_date) if last_date == today || last_date.succ() == today => streak,
+            _ => 0,
+        };
+
+        StreakDTO {
+            current_streak,
+            longest_streak,
+            last_entry_date: last_date,
+        }
+    }
+
+    /// Returns the maximum length in bytes of a text field set by the environment variable `key`,
+    /// which cannot exceed the capacity of the column.
+    fn get_max_length(key: &str, default_length: usize) -> usize {
+        env::var(key)
+            .ok()
+            .and_then(|length| length.parse::<usize>().ok())
+            .unwrap_or(default_length)
+            .min(MAX_TEXT_LENGTH)
+    }
+
+    /// Checks the lengths of the title and content of a post against `POST_MAX_TITLE_LENGTH`
+    /// and `POST_MAX_CONTENT_LENGTH`, and reports each field too long.
+    ///
+    /// The lengths are counted in bytes of the encrypted text, as stored.
+    fn check_lengths(title: Option<&str>, content: Option<&str>) -> Result<(), ServiceError> {
+        let limits = [
+            (
+                "title",
+                title,
+                Self::get_max_length("POST_MAX_TITLE_LENGTH", DEFAULT_MAX_TITLE_LENGTH),
+            ),
+            (
+                "content",
+                content,
+                Self::get_max_length("POST_MAX_CONTENT_LENGTH", MAX_TEXT_LENGTH),
+            ),
+        ];
+        let field_errors: Vec<FieldError> = limits
+            .iter()
+            .filter_map(|(field, text, max_length)| match text {
+                Some(text) if text.len() > *max_length => Some(FieldError {
+                    field: field.to_string(),
+                    message: format!("must be at most {} bytes", max_length),
+                }),
+                _ => None,
+            })
+            .collect();
+
+        if field_errors.is_empty() {
+            Ok(())
+        } else {
+            Err(get_service_error(ServiceError::InvalidFields(field_errors)))
+        }
+    }
+
+    /// Checks the mood of a post, and parses its weather.
+    fn parse_mood_and_weather(
+        mood: &Option<u8>,
+        weather: &Option<String>,
+    ) -> Result<Option<PostWeather>, ServiceError> {
+        if let Some(mood) = mood {
+            if *mood < MIN_MOOD || *mood > MAX_MOOD {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        match weather {
+            Some(weather) => Ok(Some(PostWeather::parse(weather)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Parses a location in `latitude,longitude` format used in `near` argument,
+    /// and returns a circle of `radius` meters around it.
+    fn parse_near(
+        near: &Option<String>,
+        radius: &Option<f64>,
+    ) -> Result<Option<GeoCircle>, ServiceError> {
+        let near = match near {
+            Some(near) => near,
+            None if radius.is_some() => {
+                return Err(get_service_error(ServiceError::InvalidArgument))
+            }
+            None => return Ok(None),
+        };
+
+        let mut coordinates = near
+            .splitn(2, ',')
+            .map(|coordinate| coordinate.trim().parse());
+        let (latitude, longitude) = match (coordinates.next(), coordinates.next()) {
+            (Some(Ok(latitude)), Some(Ok(longitude))) => (latitude, longitude),
+            _ => return Err(get_service_error(ServiceError::InvalidFormat)),
+        };
+        let location = PostLocation::parse(&PostLocationDTO {
+            latitude: Some(latitude),
+            longitude: Some(longitude),
+            place_name: None,
+        })?;
+
+        let radius = radius.unwrap_or(DEFAULT_NEAR_RADIUS_METERS);
+        if !radius.is_finite() || radius <= 0.0 {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        Ok(location.map(|location| GeoCircle {
+            latitude: location.latitude,
+            longitude: location.longitude,
+            radius,
+        }))
+    }
+
+    /// Checks arguments of a new post, and returns its date, status, weather, location,
+    /// and encryption.
+    ///
+    /// A post whose encryption is not given is encrypted in an unknown scheme.
+    fn parse_create_args(
+        title: &str,
+        content: &str,
+        date: &str,
+        status: &Option<String>,
+        mood: &Option<u8>,
+        weather: &Option<String>,
+        location: &PostLocationDTO,
+        encryption: &PostEncryptionDTO,
+    ) -> Result<
+        (
+            PostDate,
+            PostStatus,
+            Option<PostWeather>,
+            Option<PostLocation>,
+            PostEncryption,
+        ),
+        ServiceError,
+    > {
+        if title.trim().is_empty() || content.trim().is_empty() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+        Self::check_lengths(Some(title), Some(content))?;
+
+        let date = PostDate::parse(date)?;
+        let status = match status {
+            Some(status) => PostStatus::parse(status)?,
+            None => PostStatus::Published,
+        };
+        let weather = Self::parse_mood_and_weather(mood, weather)?;
+        let location = PostLocation::parse(location)?;
+        let encryption = PostEncryption::parse(encryption)?.unwrap_or_else(PostEncryption::legacy);
+        Ok((date, status, weather, location, encryption))
+    }
+
+    /// Checks arguments of an update of a post, and returns the date, weather, location,
+    /// and encryption to update if given.
+    ///
+    /// The encryption describes both title and content, so it requires both of them.
+    /// If either of them is given without the encryption, the post is encrypted in an unknown
+    /// scheme since then.
+    /// `has_version` is whether the update is based on a known version of the post.
+    fn parse_update_args(
+        title: &Option<String>,
+        content: &Option<String>,
+        date: &Option<String>,
+        tag_ids: &Option<Vec<u64>>,
+        mood: &Option<u8>,
+        weather: &Option<String>,
+        location: &PostLocationDTO,
+        journal_id: &Option<u64>,
+        word_count: &Option<u32>,
+        encryption: &PostEncryptionDTO,
+        has_version: bool,
+    ) -> Result<
+        (
+            Option<PostDate>,
+            Option<PostWeather>,
+            Option<PostLocation>,
+            Option<PostEncryption>,
+        ),
+        ServiceError,
+    > {
+        if title.is_none()
+            && content.is_none()
+            && date.is_none()
+            && tag_ids.is_none()
+            && mood.is_none()
+            && weather.is_none()
+            && !location.is_given()
+            && journal_id.is_none()
+            && word_count.is_none()
+        {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let is_version_required = env::var("POST_VERSION_REQUIRED")
+            .map(|required| required == "true")
+            .unwrap_or(false);
+        if !has_version && is_version_required {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        if let Some(content) = content {
+            if content.trim().is_empty() {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        if let Some(title) = title {
+            if title.trim().is_empty() {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+        Self::check_lengths(title.as_deref(), content.as_deref())?;
+
+        let date = match date {
+            Some(date) => Some(PostDate::parse(date)?),
+            None => None,
+        };
+        let weather = Self::parse_mood_and_weather(mood, weather)?;
+        let location = PostLocation::parse(location)?;
+        let encryption = match PostEncryption::parse(encryption)? {
+            Some(_) if title.is_none() || content.is_none() => {
+                return Err(get_service_error(ServiceError::InvalidArgument))
+            }
+            None if title.is_some() || content.is_some() => Some(PostEncryption::legacy()),
+            encryption => encryption,
+        };
+        Ok((date, weather, location, encryption))
+    }
+
+    /// Checks an operation of a bulk request, and converts it to be executed.
+    fn parse_operation(operation: &PostOperationDTO) -> Result<PostOperation, ServiceError> {
+        match operation {
+            PostOperationDTO::Create {
+                title,
+                content,
+                date,
+                tags,
+                status,
+                mood,
+                weather,
+                location,
+                journal_id,
+                word_count,
+                encryption,
+            } => {
+                let (date, status, weather, location, encryption) = Self::parse_create_args(
+                    title, content, date, status, mood, weather, location, encryption,
+                )?;
+                Ok(PostOperation::Create {
+                    title: title.clone(),
+                    content: content.clone(),
+                    date,
+                    tag_ids: tags.clone().unwrap_or_default(),
+                    status,
+                    mood: *mood,
+                    weather,
+                    location,
+                    journal_id: *journal_id,
+                    word_count: *word_count,
+                    encryption,
+                })
+            }
+            PostOperationDTO::Update {
+                id,
+                title,
+                content,
+                date,
+                tags,
+                mood,
+                weather,
+                location,
+                journal_id,
+                word_count,
+                encryption,
+                version,
+            } => {
+                let (date, weather, location, encryption) = Self::parse_update_args(
+                    title,
+                    content,
+                    date,
+                    tags,
+                    mood,
+                    weather,
+                    location,
+                    journal_id,
+                    word_count,
+                    encryption,
+                    version.is_some(),
+                )?;
+                Ok(PostOperation::Update {
+                    post_id: *id,
+                    title: title.clone(),
+                    content: content.clone(),
+                    date,
+                    tag_ids: tags.clone(),
+                    mood: *mood,
+                    weather,
+                    location,
+                    journal_id: *journal_id,
+                    word_count: *word_count,
+                    encryption,
+                    version: *version,
+                })
+            }
+            PostOperationDTO::Delete { id } => Ok(PostOperation::Delete { post_id: *id }),
+        }
+    }
+
+    /// Parses a local date used in `from` and `to` arguments.
+    fn parse_date(date: &Option<String>) -> Result<Option<NaiveDate>, ServiceError> {
+        match date {
+            Some(date) => match NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+                Ok(date) => Ok(Some(date)),
+                Err(_) => Err(get_service_error(ServiceError::InvalidFormat)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Parses `since` argument of changes, which is a cursor or an RFC 3339 datetime.
+    fn parse_since(since: &Option<String>) -> Result<Option<NaiveDateTime>, ServiceError> {
+        let since = match since {
+            Some(since) => since,
+            None => return Ok(None),
+        };
+
+        if let Ok(since) = DateTime::parse_from_rfc3339(since) {
+            return Ok(Some(since.naive_utc()));
+        }
+        match NaiveDateTime::parse_from_str(since, "%Y-%m-%dT%H:%M:%S%.f") {
+            Ok(since) => Ok(Some(since)),
+            Err(_) => Err(get_service_error(ServiceError::InvalidFormat)),
+        }
+    }
+
+    /// Returns how long posts stay in the trash, set by `POST_TRASH_RETENTION_DAYS`.
+    fn get_trash_retention() -> Duration {
+        let retention_days = env::var("POST_TRASH_RETENTION_DAYS")
+            .ok()
+            .and_then(|days| days.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_TRASH_RETENTION_DAYS);
+        Duration::days(retention_days)
+    }
+
+    /// Returns how long tombstones of permanently deleted posts are kept,
+    /// set by `POST_TOMBSTONE_RETENTION_DAYS`.
+    fn get_tombstone_retention() -> Duration {
+        let retention_days = env::var("POST_TOMBSTONE_RETENTION_DAYS")
+            .ok()
+            .and_then(|days| days.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_TOMBSTONE_RETENTION_DAYS);
+        Duration::days(retention_days)
+    }
+
+    /// Returns how often autosaves take a revision, set by `AUTOSAVE_REVISION_MINUTES`.
+    fn get_autosave_revision_interval() -> Duration {
+        let minutes = env::var("AUTOSAVE_REVISION_MINUTES")
+            .ok()
+            .and_then(|minutes| minutes.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_AUTOSAVE_REVISION_MINUTES);
+        Duration::minutes(minutes)
+    }
+
     /// Finds a post by user id and post id.
     pub fn get(&mut self, user_id: u64, id: u64) -> Result<PostDTO, ServiceError> {
-        let post = {
+        let (post, mut tag_ids) = {
             let fallback_repository =
                 some_if_true!(self.post_repository.is_none() => PostRepository::new());
-            self.post_repository(fallback_repository)
-                .find(user_id, id)?
+            let post_repository = self.post_repository(fallback_repository);
+            (
+                post_repository.find(user_id, id)?,
+                post_repository.find_tag_ids(&[id])?,
+            )
         };
 
         Ok(PostDTO {
             id: post.id,
+            date: post.post_date().to_rfc3339(),
             title: post.title,
             content: post.content,
-            date: post.date,
+            intra_day_order: post.intra_day_order,
+            tags: tag_ids.remove(&post.id).unwrap_or_default(),
+            journal_id: post.journal_id,
+            status: post.status,
             updated_at: post.updated_at,
             created_at: post.created_at,
+            version: post.version,
+            is_favorite: post.is_favorite,
+            mood: post.mood,
+            weather: post.weather,
+            location: PostLocationDTO {
+                latitude: post.latitude,
+                longitude: post.longitude,
+                place_name: post.place_name,
+            },
+            word_count: post.word_count,
+            is_locked: post.is_locked,
+            encryption: PostEncryptionDTO {
+                encrypted: Some(post.is_encrypted),
+                encryption_scheme: post.encryption_scheme,
+                nonce: post.encryption_nonce,
+                key_version: post.key_version,
+            },
         })
     }
 
-    /// Finds all post written by specific user.
-    pub fn get_list(&mut self, user_id: u64) -> Result<Vec<PostDTO>, ServiceError> {
-        let post_list = {
+    /// Parses the arguments of `get_list` and `get_summarized_page` into
+    /// the filter, the sort key and order, and the offset and limit of posts.
+    fn parse_list_args(
+        tag_id: &Option<u64>,
+        journal_id: &Option<u64>,
+        from: &Option<String>,
+        to: &Option<String>,
+        status: &Option<String>,
+        favorite: &Option<bool>,
+        near: &Option<String>,
+        radius: &Option<f64>,
+        sort_by: &Option<String>,
+        order: &Option<String>,
+        page: &Option<u32>,
+        per_page: &Option<u32>,
+    ) -> Result<(PostFilter, PostSortKey, SortOrder, Option<(i64, i64)>), ServiceError> {
+        let filter = PostFilter {
+            tag_id: *tag_id,
+            journal_id: *journal_id,
+            from: Self::parse_date(from)?,
+            to: Self::parse_date(to)?,
+            status: match status {
+                Some(status) => Some(PostStatus::parse(status)?),
+                None => Some(PostStatus::Published),
+            },
+            month_days: Vec::new(),
+            is_favorite: *favorite,
+            near: Self::parse_near(near, radius)?,
+        };
+        if let (Some(from), Some(to)) = (filter.from, filter.to) {
+            if from > to {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        let sort_key = match sort_by {
+            Some(sort_by) => PostSortKey::parse(sort_by)?,
+            None => PostSortKey::Date,
+        };
+        let sort_order = match order {
+            Some(order) => SortOrder::parse(order)?,
+            None => SortOrder::Desc,
+        };
+
+        let offset_and_limit = if page.is_none() && per_page.is_none() {
+            None
+        } else {
+            Some(pagination_util::get_offset_and_limit(page, per_page)?)
+        };
+
+        Ok((filter, sort_key, sort_order, offset_and_limit))
+    }
+
+    /// Finds posts written by specific user with the total count.
+    ///
+    /// If `tag_id` is given, finds only the posts with the tag.
+    /// If `journal_id` is given, finds only the posts in the journal.
+    /// If `from` or `to` is given, finds only the posts whose local date is in the range.
+    /// Finds only the posts in `status` (`published` or `draft`), which is `published` by default.
+    /// If `favorite` is given, finds only the posts marked as favorites or only the others.
+    /// If `near` is given in `latitude,longitude` format, finds only the posts written within
+    /// `radius` meters from there, which is 1000 by default.
+    /// Posts are sorted by `sort_by` (`date`, `created_at` or `updated_at`) in `order`
+    /// (`asc` or `desc`), which are `date` and `desc` by default.
+    /// If neither `page` nor `per_page` is given, finds all posts.
+    pub fn get_list(
+        &mut self,
+        user_id: u64,
+        tag_id: &Option<u64>,
+        journal_id: &Option<u64>,
+        from: &Option<String>,
+        to: &Option<String>,
+        status: &Option<String>,
+        favorite: &Option<bool>,
+        near: &Option<String>,
+        radius: &Option<f64>,
+        sort_by: &Option<String>,
+        order: &Option<String>,
+        page: &Option<u32>,
+        per_page: &Option<u32>,
+    ) -> Result<Page<PostDTO>, ServiceError> {
+        let (filter, sort_key, sort_order, offset_and_limit) = Self::parse_list_args(
+            tag_id, journal_id, from, to, status, favorite, near, radius, sort_by, order, page,
+            per_page,
+        )?;
+
+        let (post_list, total_count, mut tag_ids) = {
             let fallback_repository =
                 some_if_true!(self.post_repository.is_none() => PostRepository::new());
-            self.post_repository(fallback_repository)
-                .find_all_in_desc_date_order(user_id)?
+            let post_repository = self.post_repository(fallback_repository);
+            let post_list = post_repository.find_list(
+                user_id,
+                &filter,
+                PostFields::All,
+                sort_key,
+                sort_order,
+                &offset_and_limit,
+            )?;
+            let post_ids: Vec<u64> = post_list.iter().map(|post| post.id).collect();
+            (
+                post_list,
+                post_repository.count(user_id, &filter)?,
+                post_repository.find_tag_ids(&post_ids)?,
+            )
+        };
+        let post_list = match sort_key {
+            PostSortKey::Date => Self::sort_in_day_order(post_list, sort_order),
+            _ => post_list,
         };
 
-        Ok(post_list
+        let items = post_list
             .iter()
             .map(|post| -> PostDTO {
                 PostDTO {
                     id: post.id,
                     title: post.title.clone(),
                     content: post.content.clone(),
-                    date: post.date,
+                    date: post.post_date().to_rfc3339(),
+                    intra_day_order: post.intra_day_order,
+                    tags: tag_ids.remove(&post.id).unwrap_or_default(),
+                    journal_id: post.journal_id,
+                    status: post.status.clone(),
                     created_at: post.created_at,
                     updated_at: post.updated_at,
+                    version: post.version,
+                    is_favorite: post.is_favorite,
+                    mood: post.mood,
+                    weather: post.weather.clone(),
+                    location: PostLocationDTO {
+                        latitude: post.latitude,
+                        longitude: post.longitude,
+                        place_name: post.place_name.clone(),
+                    },
+                    word_count: post.word_count,
+                    is_locked: post.is_locked,
+                    encryption: PostEncryptionDTO {
+                        encrypted: Some(post.is_encrypted),
+                        encryption_scheme: post.encryption_scheme.clone(),
+                        nonce: post.encryption_nonce.clone(),
+                        key_version: post.key_version,
+                    },
                 }
             })
-            .collect())
+            .collect();
+
+        Ok(Page {
+            items,
+            meta: PageMeta {
+                total_count,
+                page: offset_and_limit.map(|_| page.unwrap_or(1)),
+                per_page: offset_and_limit.map(|_| per_page.unwrap_or(DEFAULT_PER_PAGE)),
+            },
+        })
+    }
+
+    /// Finds summarized posts written by specific user with the total count.
+    ///
+    /// Posts are found in the same way as `get_list`, but contents of the posts are not loaded.
+    pub fn get_summarized_page(
+        &mut self,
+        user_id: u64,
+        tag_id: &Option<u64>,
+        journal_id: &Option<u64>,
+        from: &Option<String>,
+        to: &Option<String>,
+        status: &Option<String>,
+        favorite: &Option<bool>,
+        near: &Option<String>,
+        radius: &Option<f64>,
+        sort_by: &Option<String>,
+        order: &Option<String>,
+        page: &Option<u32>,
+        per_page: &Option<u32>,
+    ) -> Result<Page<SummarizedPostDTO>, ServiceError> {
+        let (filter, sort_key, sort_order, offset_and_limit) = Self::parse_list_args(
+            tag_id, journal_id, from, to, status, favorite, near, radius, sort_by, order, page,
+            per_page,
+        )?;
+
+        let (post_list, total_count) = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            let post_repository = self.post_repository(fallback_repository);
+            (
+                post_repository.find_list(
+                    user_id,
+                    &filter,
+                    PostFields::Summary,
+                    sort_key,
+                    sort_order,
+                    &offset_and_limit,
+                )?,
+                post_repository.count(user_id, &filter)?,
+            )
+        };
+        let post_list = match sort_key {
+            PostSortKey::Date => Self::sort_in_day_order(post_list, sort_order),
+            _ => post_list,
+        };
+
+        let items = post_list
+            .into_iter()
+            .map(|post| SummarizedPostDTO {
+                date: post.post_date().to_rfc3339(),
+                id: post.id,
+                title: post.title,
+            })
+            .collect();
+
+        Ok(Page {
+            items,
+            meta: PageMeta {
+                total_count,
+                page: offset_and_limit.map(|_| page.unwrap_or(1)),
+                per_page: offset_and_limit.map(|_| per_page.unwrap_or(DEFAULT_PER_PAGE)),
+            },
+        })
     }
 
-    /// Finds all summarized post written by specific user.
+    /// Finds all summarized post written by specific user, except drafts.
     pub fn get_summarized_list(
         &mut self,
         user_id: u64,
@@ -75,9 +795,19 @@ impl PostService {
         let post_list = {
             let fallback_repository =
                 some_if_true!(self.post_repository.is_none() => PostRepository::new());
-            self.post_repository(fallback_repository)
-                .find_all_in_desc_date_order(user_id)?
+            self.post_repository(fallback_repository).find_list(
+                user_id,
+                &PostFilter {
+                    status: Some(PostStatus::Published),
+                    ..PostFilter::default()
+                },
+                PostFields::Summary,
+                PostSortKey::Date,
+                SortOrder::Desc,
+                &None,
+            )?
         };
+        let post_list = Self::sort_in_day_order(post_list, SortOrder::Desc);
 
         Ok(post_list
             .iter()
@@ -85,129 +815,4056 @@ impl PostService {
                 SummarizedPostDTO {
                     id: post.id,
                     title: post.title.clone(),
-                    date: post.date,
+                    date: post.post_date().to_rfc3339(),
                 }
             })
             .collect())
     }
 
-    /// Creates a new post and returns id of the created post.
-    pub fn create(
+    /// Finds posts written by specific user in a month, grouped by the local date.
+    ///
+    /// Only the days with posts are found, and each day has ids and titles of its posts.
+    /// Drafts are not found. A post without the offset where it was written is on the date
+    /// in the time zone of the user.
+    pub fn get_calendar(
         &mut self,
         user_id: u64,
-        title: &str,
-        content: &str,
-        date: &NaiveDateTime,
-    ) -> Result<u64, ServiceError> {
-        if title.trim().is_empty() || content.trim().is_empty() {
-            return Err(get_service_error(ServiceError::InvalidArgument));
+        year: i32,
+        month: u32,
+    ) -> Result<Vec<CalendarDayDTO>, ServiceError> {
+        let first_date = NaiveDate::from_ymd_opt(year, month, 1)
+            .ok_or_else(|| get_service_error(ServiceError::InvalidArgument))?;
+        let next_first_date = match month {
+            12 => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+            _ => NaiveDate::from_ymd_opt(year, month + 1, 1),
         }
+        .ok_or_else(|| get_service_error(ServiceError::InvalidArgument))?;
+        // The range is compared in UTC for posts without offset, so it is a day wider.
+        let filter = PostFilter {
+            tag_id: None,
+            journal_id: None,
+            from: first_date.pred_opt(),
+            to: Some(next_first_date),
+            status: Some(PostStatus::Published),
+            month_days: Vec::new(),
+            is_favorite: None,
+            near: None,
+        };
+        let timezone = user_settings::get_timezone(&self.find_settings(user_id)?);
 
-        let post_list = {
+        let summary_list = {
             let fallback_repository =
                 some_if_true!(self.post_repository.is_none() => PostRepository::new());
             self.post_repository(fallback_repository)
-                .create(user_id, title, content, date)?;
-            self.post_repository(None).find_all(user_id)?
+                .find_summaries(user_id, &filter)?
         };
 
-        Ok(post_list[post_list.len() - 1].id)
+        let mut calendar: Vec<CalendarDayDTO> = Vec::new();
+        for (id, title, date) in summary_list {
+            let local_date = stats_util::get_local_date(&date, &timezone);
+            if local_date < first_date || local_date >= next_first_date {
+                continue;
+            }
+            let post = SummarizedPostDTO {
+                id,
+                title,
+                date: date.to_rfc3339(),
+            };
+
+            match calendar.last_mut() {
+                Some(day) if day.date == local_date => {
+                    day.count += 1;
+                    day.posts.push(post);
+                }
+                _ => calendar.push(CalendarDayDTO {
+                    date: local_date,
+                    count: 1,
+                    posts: vec![post],
+                }),
+            }
+        }
+        Ok(calendar)
     }
 
-    /// Deletes a post written by specific user.
-    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
-        let fallback_repository =
-            some_if_true!(self.post_repository.is_none() => PostRepository::new());
-        self.post_repository(fallback_repository)
-            .delete(user_id, id)
+    /// Counts posts written by specific user by the year and month of the local date.
+    ///
+    /// Years and months are in asc order, and those without posts are omitted.
+    /// Drafts are not counted.
+    pub fn get_archive(&mut self, user_id: u64) -> Result<Vec<ArchiveYearDTO>, ServiceError> {
+        let filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        let count_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .count_by_month(user_id, &filter)?
+        };
+
+        let mut archive: Vec<ArchiveYearDTO> = Vec::new();
+        for (year, month, count) in count_list {
+            let month = ArchiveMonthDTO { month, count };
+
+            match archive.last_mut() {
+                Some(archive_year) if archive_year.year == year => {
+                    archive_year.count += count;
+                    archive_year.months.push(month);
+                }
+                _ => archive.push(ArchiveYearDTO {
+                    year,
+                    count,
+                    months: vec![month],
+                }),
+            }
+        }
+        Ok(archive)
     }
 
-    /// Updates a post written by specific user.
-    pub fn update(
+    /// Finds posts written by specific user in a year, grouped by the month of the local date.
+    ///
+    /// Only the months with posts are found in asc order, and each month has ids and titles
+    /// of its posts in asc date order. Drafts are not found.
+    pub fn get_archive_year(
         &mut self,
-        id: u64,
         user_id: u64,
-        title: &Option<String>,
-        content: &Option<String>,
-        date: &Option<NaiveDateTime>,
-    ) -> Result<bool, ServiceError> {
-        if title.is_none() && content.is_none() && date.is_none() {
-            return Err(get_service_error(ServiceError::InvalidArgument));
-        }
+        year: i32,
+    ) -> Result<Vec<ArchiveMonthPostsDTO>, ServiceError> {
+        let filter = PostFilter {
+            from: Some(
+                NaiveDate::from_ymd_opt(year, 1, 1)
+                    .ok_or_else(|| get_service_error(ServiceError::InvalidArgument))?,
+            ),
+            to: Some(
+                NaiveDate::from_ymd_opt(year, 12, 31)
+                    .ok_or_else(|| get_service_error(ServiceError::InvalidArgument))?,
+            ),
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
 
-        if let Some(content) = content {
-            if content.trim().is_empty() {
-                return Err(get_service_error(ServiceError::InvalidArgument));
-            }
-        }
+        let summary_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .find_summaries(user_id, &filter)?
+        };
 
-        if let Some(title) = title {
-            if title.trim().is_empty() {
-                return Err(get_service_error(ServiceError::InvalidArgument));
+        let mut archive: Vec<ArchiveMonthPostsDTO> = Vec::new();
+        for (id, title, date) in summary_list {
+            let month = date.local_date().month();
+            let post = SummarizedPostDTO {
+                id,
+                title,
+                date: date.to_rfc3339(),
+            };
+
+            match archive.last_mut() {
+                Some(archive_month) if archive_month.month == month => {
+                    archive_month.count += 1;
+                    archive_month.posts.push(post);
+                }
+                _ => archive.push(ArchiveMonthPostsDTO {
+                    month,
+                    count: 1,
+                    posts: vec![post],
+                }),
             }
         }
-
-        let fallback_repository =
-            some_if_true!(self.post_repository.is_none() => PostRepository::new());
-        self.post_repository(fallback_repository)
-            .update(user_id, id, title, content, date)
-    }
-}
-
-impl Default for PostService {
-    fn default() -> Self {
-        Self::new()
+        Ok(archive)
     }
-}
-
-#[cfg(test)]
-use crate::models::post::MockPostRepositoryTrait as PostRepository;
 
-#[cfg(test)]
-mod tests {
-    use chrono::Utc;
-    use mockall::predicate::*;
+    /// Counts posts written by specific user on each day of a year, for a heatmap of activity.
+    ///
+    /// `counts` has an element for every day from January 1, so its length is the number of days
+    /// in the year. `year` is the current year in UTC by default. Drafts are not counted.
+    pub fn get_heatmap(
+        &mut self,
+        user_id: u64,
+        year: &Option<i32>,
+    ) -> Result<HeatmapDTO, ServiceError> {
+        let year = year.unwrap_or_else(|| self.clock.now().year());
+        let (first_date, last_date) = match (
+            NaiveDate::from_ymd_opt(year, 1, 1),
+            NaiveDate::from_ymd_opt(year, 12, 31),
+        ) {
+            (Some(first_date), Some(last_date)) => (first_date, last_date),
+            _ => return Err(get_service_error(ServiceError::InvalidArgument)),
+        };
 
-    use super::*;
-    use crate::models::post::MockPostRepositoryTrait;
+        let count_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository).count_by_date(
+                user_id,
+                &first_date,
+                &last_date,
+            )?
+        };
 
-    impl PostService {
-        pub fn new_with_repository(post_repository: PostRepository) -> Self {
-            Self {
-                post_repository: Some(post_repository),
-            }
+        let mut counts = vec![0; last_date.ordinal() as usize];
+        for (date, count) in count_list {
+            counts[date.ordinal0() as usize] = count;
         }
+        Ok(HeatmapDTO { year, counts })
     }
 
-    #[test]
-    fn test_get_list() {
-        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+    /// Finds posts written by specific user on the same month and day as `date`
+    /// in previous years, in desc date order. Drafts are not found.
+    ///
+    /// `date` is the local date of the client, and the date of each post is compared
+    /// in the offset where the post was written. It is today in the time zone of the user
+    /// by default. On February 28 of a common year, posts written on February 29 are also found.
+    pub fn get_on_this_day(
+        &mut self,
+        user_id: u64,
+        date: &Option<String>,
+    ) -> Result<Vec<PostDTO>, ServiceError> {
+        let date = match Self::parse_date(date)? {
+            Some(date) => date,
+            None => self.get_local_today(user_id)?,
+        };
 
-        let id = 3;
-        let user_id = 5;
+        let mut month_days = vec![(date.month(), date.day())];
+        if (date.month(), date.day()) == (2, 28)
+            && NaiveDate::from_ymd_opt(date.year(), 2, 29).is_none()
+        {
+            month_days.push((2, 29));
+        }
+        let filter = PostFilter {
+            tag_id: None,
+            journal_id: None,
+            from: None,
+            to: NaiveDate::from_ymd_opt(date.year() - 1, 12, 31),
+            status: Some(PostStatus::Published),
+            month_days,
+            is_favorite: None,
+            near: None,
+        };
 
-        mocked_post_repository
-            .expect_find_all_in_desc_date_order()
-            .with(eq(user_id))
-            .times(1)
-            .returning(move |passed_user_id| {
-                let now = Utc::now().naive_utc();
-                let post = Post {
+        let (post_list, mut tag_ids) = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            let post_repository = self.post_repository(fallback_repository);
+            let post_list = post_repository.find_list(
+                user_id,
+                &filter,
+                PostFields::All,
+                PostSortKey::Date,
+                SortOrder::Desc,
+                &None,
+            )?;
+            let post_ids: Vec<u64> = post_list.iter().map(|post| post.id).collect();
+            (post_list, post_repository.find_tag_ids(&post_ids)?)
+        };
+        let post_list = Self::sort_in_day_order(post_list, SortOrder::Desc);
+
+        Ok(post_list
+            .into_iter()
+            .map(|post| PostDTO {
+                id: post.id,
+                date: post.post_date().to_rfc3339(),
+                tags: tag_ids.remove(&post.id).unwrap_or_default(),
+                journal_id: post.journal_id,
+                title: post.title,
+                content: post.content,
+                intra_day_order: post.intra_day_order,
+                status: post.status,
+                created_at: post.created_at,
+                updated_at: post.updated_at,
+                version: post.version,
+                is_favorite: post.is_favorite,
+                mood: post.mood,
+                weather: post.weather,
+                location: PostLocationDTO {
+                    latitude: post.latitude,
+                    longitude: post.longitude,
+                    place_name: post.place_name,
+                },
+                word_count: post.word_count,
+                is_locked: post.is_locked,
+                encryption: PostEncryptionDTO {
+                    encrypted: Some(post.is_encrypted),
+                    encryption_scheme: post.encryption_scheme,
+                    nonce: post.encryption_nonce,
+                    key_version: post.key_version,
+                },
+            })
+            .collect())
+    }
+
+    /// Returns the writing streaks of specific user. Drafts are not counted.
+    ///
+    /// `date` is the local date of the client, and the date of each post is compared
+    /// in the offset where the post was written. It is today in the time zone of the user
+    /// by default.
+    pub fn get_streak(
+        &mut self,
+        user_id: u64,
+        date: &Option<String>,
+    ) -> Result<StreakDTO, ServiceError> {
+        let today = match Self::parse_date(date)? {
+            Some(date) => date,
+            None => self.get_local_today(user_id)?,
+        };
+        let filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        let date_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .find_local_dates(user_id, &filter)?
+        };
+
+        Ok(Self::count_streaks(&date_list, today))
+    }
+
+    /// Returns progress of specific user toward the daily and monthly word goals.
+    ///
+    /// Words of posts are counted by the local date of each post, including drafts.
+    /// Posts without word counts are not counted.
+    /// `date` is the local date of the client, and it is today in the time zone of the user
+    /// by default.
+    pub fn get_word_goal_progress(
+        &mut self,
+        user_id: u64,
+        date: &Option<String>,
+    ) -> Result<WordGoalProgressDTO, ServiceError> {
+        let date = match Self::parse_date(date)? {
+            Some(date) => date,
+            None => self.get_local_today(user_id)?,
+        };
+        let next_first_date = match date.month() {
+            12 => NaiveDate::from_ymd_opt(date.year() + 1, 1, 1),
+            _ => NaiveDate::from_ymd_opt(date.year(), date.month() + 1, 1),
+        }
+        .ok_or_else(|| get_service_error(ServiceError::InvalidArgument))?;
+        let daily_filter = PostFilter {
+            from: Some(date),
+            to: Some(date),
+            ..PostFilter::default()
+        };
+        let monthly_filter = PostFilter {
+            from: date.with_day(1),
+            to: next_first_date.pred_opt(),
+            ..PostFilter::default()
+        };
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+        let (daily_word_count, monthly_word_count) = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            let post_repository = self.post_repository(fallback_repository);
+            (
+                post_repository.sum_word_counts(user_id, &daily_filter)?,
+                post_repository.sum_word_counts(user_id, &monthly_filter)?,
+            )
+        };
+
+        let progress = |goal: Option<u32>, word_count: u64| WordGoalDTO {
+            goal,
+            word_count,
+            is_achieved: goal.map_or(false, |goal| word_count >= u64::from(goal)),
+        };
+        Ok(WordGoalProgressDTO {
+            date,
+            daily: progress(user.daily_word_goal, daily_word_count),
+            monthly: progress(user.monthly_word_goal, monthly_word_count),
+        })
+    }
+
+    /// Aggregates moods of posts by `granularity`, and returns the trend in asc order
+    /// of the periods.
+    ///
+    /// `moods` are pairs of local date and mood in asc order of the dates.
+    /// Periods without moods are omitted.
+    fn aggregate_moods(
+        moods: &[(NaiveDate, u8)],
+        granularity: Granularity,
+        week_start_day: WeekStartDay,
+    ) -> Vec<MoodTrendDTO> {
+        let mut trends: Vec<MoodTrendDTO> = Vec::new();
+        for (date, mood) in moods {
+            let period = granularity.start_of(*date, week_start_day);
+            let is_new_period = trends.last().map_or(true, |trend| trend.date != period);
+            if is_new_period {
+                trends.push(MoodTrendDTO {
+                    date: period,
+                    count: 0,
+                    average: 0.0,
+                    distribution: [0; MAX_MOOD as usize],
+                });
+            }
+
+            if let Some(trend) = trends.last_mut() {
+                trend.count += 1;
+                trend.distribution[usize::from(mood - MIN_MOOD)] += 1;
+            }
+        }
+
+        for trend in trends.iter_mut() {
+            let sum: usize = trend
+                .distribution
+                .iter()
+                .enumerate()
+                .map(|(index, count)| (index + usize::from(MIN_MOOD)) * count)
+                .sum();
+            trend.average = sum as f64 / trend.count as f64;
+        }
+        trends
+    }
+
+    /// Finds moods of published posts written by specific user, and returns their trend.
+    ///
+    /// If `from` or `to` is given, finds only the posts whose local date is in the range.
+    /// Moods are aggregated by `interval` (`day`, `week` or `month`), which is `month` by default.
+    /// A week starts on the week start day of the user.
+    pub fn get_mood_trends(
+        &mut self,
+        user_id: u64,
+        from: &Option<String>,
+        to: &Option<String>,
+        interval: &Option<String>,
+    ) -> Result<Vec<MoodTrendDTO>, ServiceError> {
+        let filter = PostFilter {
+            from: Self::parse_date(from)?,
+            to: Self::parse_date(to)?,
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+        if let (Some(from), Some(to)) = (filter.from, filter.to) {
+            if from > to {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        let granularity = match interval {
+            Some(interval) => Granularity::parse(interval)?,
+            None => Granularity::Monthly,
+        };
+        let week_start_day = user_settings::get_week_start_day(&self.find_settings(user_id)?);
+
+        let mood_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .find_moods(user_id, &filter)?
+        };
+
+        Ok(Self::aggregate_moods(
+            &mood_list,
+            granularity,
+            week_start_day,
+        ))
+    }
+
+    /// Counts published posts written by specific user in each period of `granularity`,
+    /// and returns the counts in asc order of the periods. Periods without posts are omitted.
+    ///
+    /// `granularity` is `daily`, `weekly` or `monthly`, which is `weekly` by default.
+    /// A week starts on the week start day of the user, and a post without the offset where
+    /// it was written is on the date in the time zone of the user. If `from` or `to` is given,
+    /// only the posts whose local date is in the range are counted.
+    pub fn get_entry_counts(
+        &mut self,
+        user_id: u64,
+        from: &Option<String>,
+        to: &Option<String>,
+        granularity: &Option<String>,
+    ) -> Result<Vec<EntryCountDTO>, ServiceError> {
+        let from = Self::parse_date(from)?;
+        let to = Self::parse_date(to)?;
+        if let (Some(from), Some(to)) = (from, to) {
+            if from > to {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+        let granularity = match granularity {
+            Some(granularity) => Granularity::parse(granularity)?,
+            None => Granularity::Weekly,
+        };
+
+        let settings = self.find_settings(user_id)?;
+        let timezone = user_settings::get_timezone(&settings);
+        let week_start_day = user_settings::get_week_start_day(&settings);
+
+        // The range is compared in UTC for posts without offset, so it is a day wider.
+        let filter = PostFilter {
+            from: from.and_then(|from| from.pred_opt()),
+            to: to.and_then(|to| to.succ_opt()),
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+        let date_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .find_dates(user_id, &filter)?
+        };
+
+        let local_date_list: Vec<NaiveDate> = date_list
+            .iter()
+            .map(|date| stats_util::get_local_date(date, &timezone))
+            .filter(|date| from.map_or(true, |from| *date >= from))
+            .filter(|date| to.map_or(true, |to| *date <= to))
+            .collect();
+        Ok(
+            stats_util::count_by_period(&local_date_list, granularity, week_start_day)
+                .into_iter()
+                .map(|(date, count)| EntryCountDTO { date, count })
+                .collect(),
+        )
+    }
+
+    /// Creates a new post with tags of `tag_ids`, and returns id of the created post.
+    ///
+    /// The post is a draft if `status` is `draft`, and published by default.
+    /// `mood` is from 1 (worst) to 5 (best), and `weather` is a name of `PostWeather`.
+    /// `location` requires both latitude and longitude if any of its fields is given.
+    /// The post is written in the journal of `journal_id`, or the default journal if omitted.
+    /// `word_count` is the number of words in the content, which is counted by the client
+    /// since the content is encrypted.
+    /// `encryption` records how the client encrypted `title` and `content`, which are stored
+    /// as given. The post is encrypted in an unknown scheme if it is not given.
+    /// If `template_id` is given, `title` and `content` are taken from the template when omitted.
+    /// The id is returned with the warnings of `get_response_meta`.
+    pub fn create(
+        &mut self,
+        user_id: u64,
+        title: &Option<String>,
+        content: &Option<String>,
+        date: &str,
+        tag_ids: &[u64],
+        status: &Option<String>,
+        mood: &Option<u8>,
+        weather: &Option<String>,
+        location: &PostLocationDTO,
+        journal_id: &Option<u64>,
+        word_count: &Option<u32>,
+        encryption: &PostEncryptionDTO,
+        template_id: &Option<u64>,
+        audit_context: &AuditContext,
+    ) -> Result<WithMeta<u64>, ServiceError> {
+        let (title, content) = match (title, content, template_id) {
+            (Some(title), Some(content), _) => (title.clone(), content.clone()),
+            (title, content, Some(template_id)) => {
+                let fallback_repository = some_if_true!(
+                    self.template_repository.is_none() => TemplateRepository::new()
+                );
+                let template = self
+                    .template_repository(fallback_repository)
+                    .find(user_id, *template_id)?;
+                (
+                    title.clone().unwrap_or(template.title),
+                    content.clone().unwrap_or(template.content),
+                )
+            }
+            _ => return Err(get_service_error(ServiceError::InvalidArgument)),
+        };
+        let (date, status, weather, location, encryption) = Self::parse_create_args(
+            &title, &content, date, status, mood, weather, location, encryption,
+        )?;
+
+        let id = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository).create(
+                user_id,
+                &title,
+                &content,
+                &date,
+                tag_ids,
+                status,
+                *mood,
+                weather,
+                &location,
+                *journal_id,
+                *word_count,
+                &encryption,
+                audit_context,
+            )?
+        };
+
+        Ok(WithMeta {
+            data: id,
+            meta: self.get_response_meta(user_id)?,
+        })
+    }
+
+    /// Returns warnings for specific user attached to responses, such as posts in the trash
+    /// permanently deleted within `TRASH_PURGE_WARNING_DAYS`.
+    pub fn get_response_meta(&mut self, user_id: u64) -> Result<ResponseMeta, ServiceError> {
+        let threshold = self.clock.now().naive_utc() - Self::get_trash_retention()
+            + Duration::days(TRASH_PURGE_WARNING_DAYS);
+
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        let count = self
+            .post_repository(fallback_repository)
+            .count_trashed_before(user_id, &threshold)?;
+        Ok(ResponseMeta::new().posts_in_trash_purging_soon(count))
+    }
+
+    /// Copies a post written by specific user to a new post, and returns id of the created post.
+    ///
+    /// The new post is dated `date`, or now in the offset where the original post was written
+    /// if omitted, and placed after the other posts of its date.
+    pub fn duplicate(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        date: &Option<String>,
+        audit_context: &AuditContext,
+    ) -> Result<u64, ServiceError> {
+        let date = match date {
+            Some(date) => PostDate::parse(date)?,
+            None => {
+                let fallback_repository =
+                    some_if_true!(self.post_repository.is_none() => PostRepository::new());
+                let post = self
+                    .post_repository(fallback_repository)
+                    .find(user_id, id)?;
+                PostDate {
+                    date: self.clock.now().naive_utc(),
+                    offset: post.date_offset,
+                }
+            }
+        };
+
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .duplicate(user_id, id, &date, audit_context)
+    }
+
+    /// Publishes a draft written by specific user.
+    ///
+    /// Returns false if the post is already published.
+    pub fn publish(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .publish(user_id, id, audit_context)
+    }
+
+    /// Marks or unmarks a post written by specific user as a favorite.
+    ///
+    /// Returns false if the post is already marked or unmarked.
+    pub fn set_favorite(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        is_favorite: bool,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .set_favorite(user_id, id, is_favorite)
+    }
+
+    /// Locks or unlocks a post written by specific user.
+    ///
+    /// Locked posts reject updates, autosaves, and deletion with `Locked` error until unlocked.
+    /// Returns false if the post is already locked or unlocked.
+    pub fn set_locked(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        is_locked: bool,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .set_locked(user_id, id, is_locked)
+    }
+
+    /// Moves a post written by specific user to the trash.
+    pub fn delete(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .delete(user_id, id, audit_context)
+    }
+
+    /// Finds changes of posts written by specific user since `since`, for clients syncing changes.
+    ///
+    /// `since` is `cursor` of the last sync, or an RFC 3339 datetime. Without it, all posts
+    /// except posts in the trash are found. Posts may be found again by the next sync,
+    /// and each post is always in its current state.
+    /// `since` older than `POST_TOMBSTONE_RETENTION_DAYS` is rejected, because posts
+    /// permanently deleted after it may not be found anymore.
+    pub fn get_changes(
+        &mut self,
+        user_id: u64,
+        since: &Option<String>,
+    ) -> Result<PostChangesDTO, ServiceError> {
+        let since = Self::parse_since(since)?;
+        if let Some(since) = since {
+            if since < self.clock.now().naive_utc() - Self::get_tombstone_retention() {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        let (changes, mut tag_ids) = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            let post_repository = self.post_repository(fallback_repository);
+            let changes = post_repository.find_changes(user_id, &since)?;
+            let post_ids: Vec<u64> = changes.posts.iter().map(|post| post.id).collect();
+            (changes, post_repository.find_tag_ids(&post_ids)?)
+        };
+
+        let overlapped_cursor =
+            changes.found_at - Duration::seconds(CHANGES_CURSOR_OVERLAP_SECONDS);
+        let cursor = match since {
+            Some(since) if since > overlapped_cursor => since,
+            _ => overlapped_cursor,
+        };
+
+        Ok(PostChangesDTO {
+            posts: changes
+                .posts
+                .iter()
+                .map(|post| PostDTO {
+                    id: post.id,
+                    title: post.title.clone(),
+                    content: post.content.clone(),
+                    date: post.post_date().to_rfc3339(),
+                    intra_day_order: post.intra_day_order,
+                    tags: tag_ids.remove(&post.id).unwrap_or_default(),
+                    journal_id: post.journal_id,
+                    status: post.status.clone(),
+                    created_at: post.created_at,
+                    updated_at: post.updated_at,
+                    version: post.version,
+                    is_favorite: post.is_favorite,
+                    mood: post.mood,
+                    weather: post.weather.clone(),
+                    location: PostLocationDTO {
+                        latitude: post.latitude,
+                        longitude: post.longitude,
+                        place_name: post.place_name.clone(),
+                    },
+                    word_count: post.word_count,
+                    is_locked: post.is_locked,
+                    encryption: PostEncryptionDTO {
+                        encrypted: Some(post.is_encrypted),
+                        encryption_scheme: post.encryption_scheme.clone(),
+                        nonce: post.encryption_nonce.clone(),
+                        key_version: post.key_version,
+                    },
+                })
+                .collect(),
+            deleted: changes
+                .deleted_posts
+                .iter()
+                .map(|(id, deleted_at)| DeletedPostDTO {
+                    id: *id,
+                    deleted_at: *deleted_at,
+                })
+                .collect(),
+            cursor,
+        })
+    }
+
+    /// Finds posts in the trash of specific user, with when each post is permanently deleted.
+    pub fn get_trash(&mut self, user_id: u64) -> Result<Vec<TrashedPostDTO>, ServiceError> {
+        let post_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .find_all_trashed(user_id)?
+        };
+        let retention = Self::get_trash_retention();
+
+        Ok(post_list
+            .into_iter()
+            .filter_map(|post| {
+                let date = post.post_date().to_rfc3339();
+                post.deleted_at.map(|deleted_at| TrashedPostDTO {
+                    id: post.id,
+                    title: post.title,
+                    content: post.content,
+                    date,
+                    deleted_at,
+                    purge_at: deleted_at + retention,
+                    encryption: PostEncryptionDTO {
+                        encrypted: Some(post.is_encrypted),
+                        encryption_scheme: post.encryption_scheme,
+                        nonce: post.encryption_nonce,
+                        key_version: post.key_version,
+                    },
+                })
+            })
+            .collect())
+    }
+
+    /// Restores a post written by specific user from the trash.
+    pub fn restore(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .restore(user_id, id, audit_context)
+    }
+
+    /// Permanently deletes posts in the trash older than `POST_TRASH_RETENTION_DAYS`
+    /// and returns the count.
+    pub fn purge_trash(&mut self) -> Result<usize, ServiceError> {
+        let threshold = self.clock.now().naive_utc() - Self::get_trash_retention();
+
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .purge_trashed(&threshold)
+    }
+
+    /// Deletes tombstones of permanently deleted posts older than `POST_TOMBSTONE_RETENTION_DAYS`
+    /// and returns the count.
+    pub fn prune_tombstones(&mut self) -> Result<usize, ServiceError> {
+        let threshold = self.clock.now().naive_utc() - Self::get_tombstone_retention();
+
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .prune_tombstones(&threshold)
+    }
+
+    /// Sets the offset of dates of posts written before it was recorded, and returns the count.
+    ///
+    /// Their dates are local datetimes of the writers, so they are converted to UTC
+    /// with the offset in the time zone set by each writer, or in UTC if it is not set.
+    /// Revisions of the posts are left as they are.
+    pub fn backfill_date_offsets(&mut self) -> Result<usize, ServiceError> {
+        let mut timezones: HashMap<u64, Tz> = HashMap::new();
+        let mut after_id = 0;
+        let mut count = 0;
+
+        loop {
+            let post_list = {
+                let fallback_repository =
+                    some_if_true!(self.post_repository.is_none() => PostRepository::new());
+                self.post_repository(fallback_repository)
+                    .find_all_without_date_offset(after_id, DATE_OFFSET_BACKFILL_BATCH_SIZE)?
+            };
+            after_id = match post_list.last() {
+                Some((id, _, _)) => *id,
+                None => break,
+            };
+
+            for (id, user_id, date) in post_list {
+                let timezone = match timezones.get(&user_id) {
+                    Some(timezone) => *timezone,
+                    None => {
+                        let settings = {
+                            let fallback_repository = some_if_true!(self.user_settings_repository.is_none() => UserSettingsRepository::new());
+                            self.user_settings_repository(fallback_repository)
+                                .find_by_user_id(user_id)?
+                        };
+                        let timezone = user_settings::get_timezone(&settings);
+                        timezones.insert(user_id, timezone);
+                        timezone
+                    }
+                };
+
+                if self
+                    .post_repository(None)
+                    .set_date_offset(id, &PostDate::from_local(date, &timezone))?
+                {
+                    count += 1;
+                }
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Updates a post written by specific user.
+    ///
+    /// If `tag_ids` is given, tags of the post are replaced with them.
+    /// `mood`, `weather`, and `location` replace the recorded ones if given.
+    /// If `journal_id` is given, the post is moved to the journal.
+    /// `word_count` is the number of words in the new content counted by the client, and
+    /// the word count of the post is cleared if `content` is given without it.
+    /// `encryption` records how the client encrypted the new `title` and `content`, and requires
+    /// both of them.
+    /// `version` is the version of the post the edit is based on. It can be omitted
+    /// to overwrite the post regardless of its version, unless `POST_VERSION_REQUIRED` is set.
+    /// `unmodified_since` can be given instead of `version`, and the post is not updated
+    /// if it has been modified after that time.
+    pub fn update(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        title: &Option<String>,
+        content: &Option<String>,
+        date: &Option<String>,
+        tag_ids: &Option<Vec<u64>>,
+        mood: &Option<u8>,
+        weather: &Option<String>,
+        location: &PostLocationDTO,
+        journal_id: &Option<u64>,
+        word_count: &Option<u32>,
+        encryption: &PostEncryptionDTO,
+        version: &Option<u32>,
+        unmodified_since: &Option<NaiveDateTime>,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let has_version = version.is_some() || unmodified_since.is_some();
+        let (date, weather, location, encryption) = Self::parse_update_args(
+            title,
+            content,
+            date,
+            tag_ids,
+            mood,
+            weather,
+            location,
+            journal_id,
+            word_count,
+            encryption,
+            has_version,
+        )?;
+
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        let post_repository = self.post_repository(fallback_repository);
+
+        // The version found here is required by the update, so the post is not updated
+        // if it is modified between them.
+        let version = match unmodified_since {
+            Some(unmodified_since) => {
+                let post = post_repository.find(user_id, id)?;
+                let modified_at = post.updated_at.unwrap_or(post.created_at);
+                if modified_at > *unmodified_since {
+                    return Err(get_service_error(ServiceError::Conflict(post.version)));
+                }
+                version.or(Some(post.version))
+            }
+            None => *version,
+        };
+
+        post_repository.update(
+            user_id,
+            id,
+            title,
+            content,
+            &date,
+            tag_ids,
+            mood,
+            &weather,
+            &location,
+            journal_id,
+            word_count,
+            &encryption,
+            &version,
+            audit_context,
+        )
+    }
+
+    /// Saves a post written by specific user while it is edited, and returns its version.
+    ///
+    /// Autosaves keep `updated_at`, and are coalesced into one revision for each
+    /// `AUTOSAVE_REVISION_MINUTES`. The first autosave after an update takes a revision,
+    /// and so does the first one after the interval from the last revision taken by autosaves.
+    /// If `version` is given, the post is saved only when it is still in that version.
+    /// `encryption` requires `title` as it does in `update`.
+    pub fn autosave(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        title: &Option<String>,
+        content: &str,
+        encryption: &PostEncryptionDTO,
+        version: &Option<u32>,
+        audit_context: &AuditContext,
+    ) -> Result<u32, ServiceError> {
+        if content.trim().is_empty() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let encryption = match PostEncryption::parse(encryption)? {
+            Some(_) if title.is_none() => {
+                return Err(get_service_error(ServiceError::InvalidArgument))
+            }
+            Some(encryption) => encryption,
+            None => PostEncryption::legacy(),
+        };
+
+        if let Some(title) = title {
+            if title.trim().is_empty() {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+        Self::check_lengths(title.as_deref(), Some(content))?;
+
+        let now = self.clock.now().naive_utc();
+        let interval = Self::get_autosave_revision_interval();
+
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        let post_repository = self.post_repository(fallback_repository);
+
+        // The version found here is required by the autosave, so the decision to take
+        // a revision is not made on a post modified in the meantime.
+        let post = post_repository.find(user_id, id)?;
+        if let Some(version) = version {
+            if *version != post.version {
+                return Err(get_service_error(ServiceError::Conflict(post.version)));
+            }
+        }
+
+        let revises = post
+            .autosave_started_at
+            .map_or(true, |started_at| started_at + interval <= now);
+        post_repository.autosave(
+            user_id,
+            id,
+            title,
+            content,
+            &encryption,
+            post.version,
+            revises,
+            &now,
+            audit_context,
+        )
+    }
+
+    /// Executes a batch of operations on posts written by specific user in a transaction,
+    /// and returns the result of each operation in the same order with the progress.
+    ///
+    /// The batch is up to `BULK_BATCH_SIZE` operations from the index of `continue_from`,
+    /// which is the start by default. The operations before it are skipped, so that a request
+    /// retried with `next_cursor` of the last batch resumes without executing them again.
+    /// The result of an operation is id of the created, updated, or deleted post.
+    /// Invalid or failed operations are reported without affecting the others.
+    pub fn execute_bulk(
+        &mut self,
+        user_id: u64,
+        operations: &[PostOperationDTO],
+        continue_from: &Option<usize>,
+        audit_context: &AuditContext,
+    ) -> Result<Batch<u64>, ServiceError> {
+        let start = continue_from.unwrap_or(0);
+        if operations.is_empty()
+            || operations.len() > MAX_BULK_OPERATIONS
+            || start >= operations.len()
+        {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+        let end = operations.len().min(start + BULK_BATCH_SIZE);
+
+        let parsed_operations: Vec<Result<PostOperation, ServiceError>> = operations[start..end]
+            .iter()
+            .map(Self::parse_operation)
+            .collect();
+        let valid_operations: Vec<PostOperation> = parsed_operations
+            .iter()
+            .filter_map(|operation| operation.as_ref().ok().cloned())
+            .collect();
+
+        let mut executed_results = if valid_operations.is_empty() {
+            Vec::new()
+        } else {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository).execute_bulk(
+                user_id,
+                &valid_operations,
+                audit_context,
+            )?
+        }
+        .into_iter();
+
+        let results = parsed_operations
+            .into_iter()
+            .map(|operation| match operation {
+                Ok(_) => executed_results
+                    .next()
+                    .unwrap_or_else(|| Err(get_service_error(ServiceError::QueryExecutionFailure))),
+                Err(error) => Err(error),
+            })
+            .collect();
+        Ok(Batch {
+            results,
+            processed: end,
+            remaining: operations.len() - end,
+            next_cursor: Some(end).filter(|end| *end < operations.len()),
+        })
+    }
+
+    /// Finds revisions of a post written by specific user, recent versions first.
+    pub fn get_revisions(
+        &mut self,
+        id: u64,
+        user_id: u64,
+    ) -> Result<Vec<PostRevisionDTO>, ServiceError> {
+        let revision_list = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .find_revisions(user_id, id)?
+        };
+
+        Ok(revision_list
+            .into_iter()
+            .map(|revision| PostRevisionDTO {
+                version: revision.version,
+                date: revision.post_date().to_rfc3339(),
+                title: revision.title,
+                content: revision.content,
+                created_at: revision.created_at,
+                encryption: PostEncryptionDTO {
+                    encrypted: Some(revision.is_encrypted),
+                    encryption_scheme: revision.encryption_scheme,
+                    nonce: revision.encryption_nonce,
+                    key_version: revision.key_version,
+                },
+            })
+            .collect())
+    }
+
+    /// Restores title, content, date and encryption of a post written by specific user
+    /// to a revision.
+    ///
+    /// The restoration is an update of the post, so the current post is also kept as a revision.
+    pub fn restore_revision(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        version: u32,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        let post_repository = self.post_repository(fallback_repository);
+
+        let revision = post_repository.find_revision(user_id, id, version)?;
+        post_repository.update(
+            user_id,
+            id,
+            &Some(revision.title.clone()),
+            &Some(revision.content.clone()),
+            &Some(revision.post_date()),
+            &None,
+            &None,
+            &None,
+            &None,
+            &None,
+            &None,
+            &Some(revision.encryption()),
+            &None,
+            audit_context,
+        )
+    }
+
+    /// Counts encrypted posts and revisions written by specific user by the scheme
+    /// and the key version.
+    ///
+    /// Clients rotating keys re-encrypt posts in the old key versions, and keep the old keys
+    /// while revisions are left in them.
+    pub fn get_key_versions(
+        &mut self,
+        user_id: u64,
+    ) -> Result<Vec<KeyVersionCountDTO>, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .count_by_key_version(user_id)
+    }
+
+    /// Moves a post written by specific user to `position` among the posts of its date.
+    pub fn reorder(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        position: usize,
+        audit_context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_repository.is_none() => PostRepository::new());
+        self.post_repository(fallback_repository)
+            .reorder(user_id, id, position, audit_context)
+    }
+
+    /// Permanently deletes all posts written by specific user on a date.
+    ///
+    /// 1. Checks `permanent` is set, since the deletion cannot be undone.
+    /// 2. Compares password of the user and it from the arguments.
+    /// 3. Deletes posts whose date in the offset they were written is `date`, with their audit entries
+    ///    and revisions.
+    ///
+    /// If `dry_run` is true, returns the data to be removed without removing anything.
+    pub fn delete_by_date(
+        &mut self,
+        user_id: u64,
+        date: &str,
+        password: &str,
+        permanent: bool,
+        dry_run: bool,
+    ) -> Result<PostDateDeletionDTO, ServiceError> {
+        if !permanent {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let date = NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|_| get_service_error(ServiceError::InvalidFormat))?;
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+
+        if !password_util::check_password(password, &user.password) {
+            return Err(ServiceError::Unauthorized);
+        }
+
+        let deletion = {
+            let fallback_repository =
+                some_if_true!(self.post_repository.is_none() => PostRepository::new());
+            self.post_repository(fallback_repository)
+                .delete_by_date(user_id, &date, dry_run)?
+        };
+
+        Ok(PostDateDeletionDTO {
+            dry_run,
+            date,
+            post_ids: deletion.post_ids,
+            post_audit_count: deletion.post_audit_count,
+            attachment_count: deletion.attachment_count,
+        })
+    }
+}
+
+impl Default for PostService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::post::MockPostRepositoryTrait as PostRepository;
+#[cfg(test)]
+use crate::models::template::MockTemplateRepositoryTrait as TemplateRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+#[cfg(test)]
+use crate::models::user_settings::MockUserSettingsRepositoryTrait as UserSettingsRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+    use mockall::Sequence;
+    use std::collections::HashMap;
+
+    use super::*;
+    use crate::models::post::MockPostRepositoryTrait;
+    use crate::models::post_revision::PostRevision;
+    use crate::models::template::MockTemplateRepositoryTrait;
+    use crate::models::user::{MockUserRepositoryTrait, User};
+    use crate::models::user_settings::{MockUserSettingsRepositoryTrait, UserSettings};
+    use crate::utils::clock_util::TestClock;
+
+    impl PostService {
+        pub fn new_with_repository(
+            post_repository: PostRepository,
+            user_repository: UserRepository,
+        ) -> Self {
+            let mut user_settings_repository = MockUserSettingsRepositoryTrait::new();
+            user_settings_repository
+                .expect_find_by_user_id()
+                .returning(|_| Ok(None));
+
+            Self {
+                post_repository: Some(post_repository),
+                user_repository: Some(user_repository),
+                template_repository: None,
+                user_settings_repository: Some(user_settings_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_template_repository(mut self, template_repository: TemplateRepository) -> Self {
+            self.template_repository = Some(template_repository);
+            self
+        }
+
+        pub fn with_user_settings_repository(
+            mut self,
+            user_settings_repository: UserSettingsRepository,
+        ) -> Self {
+            self.user_settings_repository = Some(user_settings_repository);
+            self
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    #[test]
+    fn test_get_list() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let id = 3;
+        let user_id = 5;
+        let filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        mocked_post_repository
+            .expect_find_list()
+            .with(
+                eq(user_id),
+                eq(filter.clone()),
+                eq(PostFields::All),
+                eq(PostSortKey::Date),
+                eq(SortOrder::Desc),
+                eq(None),
+            )
+            .times(1)
+            .returning(move |passed_user_id, _, _, _, _, _| {
+                let now = Utc::now().naive_utc();
+                let post = Post {
+                    id,
+                    user_id: passed_user_id,
+                    title: String::from("Title"),
+                    content: String::from("Content"),
+                    date: now.clone(),
+                    date_offset: None,
+                    intra_day_order: 0,
+                    created_at: now.clone(),
+                    updated_at: None,
+                    version: 1,
+                    deleted_at: None,
+                    status: String::from("published"),
+                    autosave_started_at: None,
+                    changed_at: now.clone(),
+                    is_favorite: false,
+                    mood: None,
+                    weather: None,
+                    latitude: None,
+                    longitude: None,
+                    place_name: None,
+                    journal_id: 1,
+                    word_count: None,
+                    is_locked: false,
+                    is_encrypted: true,
+                    encryption_scheme: None,
+                    encryption_nonce: None,
+                    key_version: None,
+                };
+
+                Ok(vec![post])
+            });
+        mocked_post_repository
+            .expect_count()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| Ok(1));
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(1)
+            .returning(move |_| Ok(vec![(id, vec![2, 4])].into_iter().collect()));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let post_page: Page<PostDTO> = post_service
+            .get_list(
+                user_id, &None, &None, &None, &None, &None, &None, &None, &None, &None, &None,
+                &None, &None,
+            )
+            .unwrap();
+
+        assert_eq!(post_page.items.first().unwrap().id, id);
+        assert_eq!(post_page.items.first().unwrap().tags, vec![2, 4]);
+        assert_eq!(
+            post_page.meta,
+            PageMeta {
+                total_count: 1,
+                page: None,
+                per_page: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_list_with_page() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        mocked_post_repository
+            .expect_find_list()
+            .with(
+                eq(user_id),
+                eq(filter.clone()),
+                eq(PostFields::All),
+                eq(PostSortKey::Date),
+                eq(SortOrder::Desc),
+                eq(Some((20, 10))),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(vec![]));
+        mocked_post_repository
+            .expect_count()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| Ok(25));
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(1)
+            .returning(|_| Ok(HashMap::new()));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let post_page = post_service
+            .get_list(
+                user_id,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(3),
+                &Some(10),
+            )
+            .unwrap();
+
+        assert!(post_page.items.is_empty());
+        assert_eq!(
+            post_page.meta,
+            PageMeta {
+                total_count: 25,
+                page: Some(3),
+                per_page: Some(10),
+            }
+        );
+        assert!(post_service
+            .get_list(
+                user_id,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(0),
+                &None
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_summarized_page() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        mocked_post_repository
+            .expect_find_list()
+            .with(
+                eq(user_id),
+                eq(filter.clone()),
+                eq(PostFields::Summary),
+                eq(PostSortKey::CreatedAt),
+                eq(SortOrder::Desc),
+                eq(Some((0, 10))),
+            )
+            .times(1)
+            .returning(|user_id, _, _, _, _, _| {
+                let date = PostDate::parse("2020-04-13T16:31:09+09:00").unwrap();
+                Ok(vec![Post {
+                    id: 1,
+                    user_id,
+                    title: String::from("Title"),
+                    content: String::new(),
+                    date: date.date,
+                    date_offset: date.offset,
+                    intra_day_order: 0,
+                    created_at: date.date,
+                    updated_at: None,
+                    version: 1,
+                    deleted_at: None,
+                    status: String::from("published"),
+                    autosave_started_at: None,
+                    changed_at: date.date,
+                    is_favorite: false,
+                    mood: None,
+                    weather: None,
+                    latitude: None,
+                    longitude: None,
+                    place_name: None,
+                    journal_id: 1,
+                    word_count: None,
+                    is_locked: false,
+                    is_encrypted: true,
+                    encryption_scheme: None,
+                    encryption_nonce: None,
+                    key_version: None,
+                }])
+            });
+        mocked_post_repository
+            .expect_count()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| Ok(1));
+        mocked_post_repository.expect_find_tag_ids().times(0);
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let post_page = post_service
+            .get_summarized_page(
+                user_id,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(String::from("created_at")),
+                &None,
+                &None,
+                &Some(10),
+            )
+            .unwrap();
+
+        assert_eq!(post_page.items.len(), 1);
+        assert_eq!(post_page.items[0].title, "Title");
+        assert_eq!(post_page.items[0].date, "2020-04-13T16:31:09+09:00");
+        assert_eq!(
+            post_page.meta,
+            PageMeta {
+                total_count: 1,
+                page: Some(1),
+                per_page: Some(10),
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_list_with_filter() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            tag_id: Some(7),
+            journal_id: Some(2),
+            from: Some(NaiveDate::from_ymd(2020, 4, 1)),
+            to: Some(NaiveDate::from_ymd(2020, 4, 30)),
+            status: Some(PostStatus::Draft),
+            month_days: Vec::new(),
+            is_favorite: Some(true),
+            near: Some(GeoCircle {
+                latitude: 37.5665,
+                longitude: 126.978,
+                radius: 500.0,
+            }),
+        };
+
+        mocked_post_repository
+            .expect_find_list()
+            .with(
+                eq(user_id),
+                eq(filter.clone()),
+                eq(PostFields::All),
+                eq(PostSortKey::CreatedAt),
+                eq(SortOrder::Asc),
+                eq(None),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(vec![]));
+        mocked_post_repository
+            .expect_count()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| Ok(0));
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(1)
+            .returning(|_| Ok(HashMap::new()));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let post_page = post_service
+            .get_list(
+                user_id,
+                &Some(7),
+                &Some(2),
+                &Some(String::from("2020-04-01")),
+                &Some(String::from("2020-04-30")),
+                &Some(String::from("draft")),
+                &Some(true),
+                &Some(String::from("37.5665, 126.978")),
+                &Some(500.0),
+                &Some(String::from("created_at")),
+                &Some(String::from("asc")),
+                &None,
+                &None,
+            )
+            .unwrap();
+
+        assert!(post_page.items.is_empty());
+        assert_eq!(post_page.meta.total_count, 0);
+    }
+
+    #[test]
+    fn test_get_list_by_status() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+
+        let id = 3;
+        let user_id = 5;
+        let published_filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+        let draft_filter = PostFilter {
+            status: Some(PostStatus::Draft),
+            ..PostFilter::default()
+        };
+
+        // Drafts are excluded by default.
+        mocked_post_repository
+            .expect_find_list()
+            .with(
+                eq(user_id),
+                eq(published_filter.clone()),
+                always(),
+                always(),
+                always(),
+                always(),
+            )
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _, _, _, _| Ok(vec![]));
+        mocked_post_repository
+            .expect_count()
+            .with(eq(user_id), eq(published_filter))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Ok(0));
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(HashMap::new()));
+
+        // Only drafts are found with `draft` status.
+        mocked_post_repository
+            .expect_find_list()
+            .with(
+                eq(user_id),
+                eq(draft_filter.clone()),
+                always(),
+                always(),
+                always(),
+                always(),
+            )
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |passed_user_id, _, _, _, _, _| {
+                let now = Utc::now().naive_utc();
+                Ok(vec![Post {
+                    id,
+                    user_id: passed_user_id,
+                    title: String::from("Title"),
+                    content: String::from("Content"),
+                    date: now,
+                    date_offset: None,
+                    intra_day_order: 0,
+                    created_at: now,
+                    updated_at: None,
+                    version: 1,
+                    deleted_at: None,
+                    status: String::from("draft"),
+                    autosave_started_at: None,
+                    changed_at: now,
+                    is_favorite: false,
+                    mood: None,
+                    weather: None,
+                    latitude: None,
+                    longitude: None,
+                    place_name: None,
+                    journal_id: 1,
+                    word_count: None,
+                    is_locked: false,
+                    is_encrypted: false,
+                    encryption_scheme: None,
+                    encryption_nonce: None,
+                    key_version: None,
+                }])
+            });
+        mocked_post_repository
+            .expect_count()
+            .with(eq(user_id), eq(draft_filter))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Ok(1));
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(HashMap::new()));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let post_page = post_service
+            .get_list(
+                user_id, &None, &None, &None, &None, &None, &None, &None, &None, &None, &None,
+                &None, &None,
+            )
+            .unwrap();
+        assert!(post_page.items.is_empty());
+
+        let post_page = post_service
+            .get_list(
+                user_id,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(String::from("draft")),
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+            )
+            .unwrap();
+        assert_eq!(post_page.items.len(), 1);
+        assert_eq!(post_page.items[0].id, id);
+        assert_eq!(post_page.items[0].status, "draft");
+        assert_eq!(post_page.meta.total_count, 1);
+
+        // An unknown status is rejected before finding posts.
+        assert!(post_service
+            .get_list(
+                user_id,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(String::from("archived")),
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_list_by_favorite() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+
+        let user_id = 5;
+        for is_favorite in &[true, false] {
+            let filter = PostFilter {
+                status: Some(PostStatus::Published),
+                is_favorite: Some(*is_favorite),
+                ..PostFilter::default()
+            };
+
+            mocked_post_repository
+                .expect_find_list()
+                .with(
+                    eq(user_id),
+                    eq(filter.clone()),
+                    always(),
+                    always(),
+                    always(),
+                    always(),
+                )
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _, _, _, _, _| Ok(vec![]));
+            mocked_post_repository
+                .expect_count()
+                .with(eq(user_id), eq(filter))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _| Ok(0));
+            mocked_post_repository
+                .expect_find_tag_ids()
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_| Ok(HashMap::new()));
+        }
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        for is_favorite in &[true, false] {
+            let post_page = post_service
+                .get_list(
+                    user_id,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    &Some(*is_favorite),
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                    &None,
+                )
+                .unwrap();
+            assert!(post_page.items.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_get_list_with_invalid_filter() {
+        let mut post_service = PostService::new_with_repository(
+            MockPostRepositoryTrait::new(),
+            MockUserRepositoryTrait::new(),
+        );
+
+        let from = Some(String::from("2020-04-30"));
+        let to = Some(String::from("2020-04-01"));
+        assert!(post_service
+            .get_list(
+                5, &None, &None, &from, &to, &None, &None, &None, &None, &None, &None, &None, &None
+            )
+            .is_err());
+        assert!(post_service
+            .get_list(
+                5,
+                &None,
+                &None,
+                &to,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(String::from("title")),
+                &None,
+                &None,
+                &None
+            )
+            .is_err());
+        assert!(post_service
+            .get_list(
+                5,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(String::from("up")),
+                &None,
+                &None
+            )
+            .is_err());
+        assert!(post_service
+            .get_list(
+                5,
+                &None,
+                &None,
+                &Some(String::from("April")),
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None
+            )
+            .is_err());
+        assert!(post_service
+            .get_list(
+                5,
+                &None,
+                &None,
+                &None,
+                &None,
+                &Some(String::from("archived")),
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None,
+                &None
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_list_in_day_order() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        mocked_post_repository
+            .expect_find_list()
+            .times(1)
+            .returning(|user_id, _, _, _, _, _| {
+                let post = |id: u64, date: &str, intra_day_order: u16| {
+                    let date = PostDate::parse(date).unwrap();
+                    Post {
+                        id,
+                        user_id,
+                        title: String::from("Title"),
+                        content: String::from("Content"),
+                        date: date.date,
+                        date_offset: date.offset,
+                        intra_day_order,
+                        created_at: date.date,
+                        updated_at: None,
+                        version: 1,
+                        deleted_at: None,
+                        status: String::from("published"),
+                        autosave_started_at: None,
+                        changed_at: date.date,
+                        is_favorite: false,
+                        mood: None,
+                        weather: None,
+                        latitude: None,
+                        longitude: None,
+                        place_name: None,
+                        journal_id: 1,
+                        word_count: None,
+                        is_locked: false,
+                        is_encrypted: true,
+                        encryption_scheme: None,
+                        encryption_nonce: None,
+                        key_version: None,
+                    }
+                };
+
+                Ok(vec![
+                    post(1, "2020-04-12T20:00:00+09:00", 1),
+                    post(2, "2020-04-12T08:00:00+09:00", 0),
+                    post(3, "2020-04-12T01:00:00+09:00", 2),
+                    post(4, "2020-04-11T23:00:00+09:00", 0),
+                ])
+            });
+        mocked_post_repository
+            .expect_count()
+            .times(1)
+            .returning(|_, _| Ok(4));
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(1)
+            .returning(|_| Ok(HashMap::new()));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let post_ids: Vec<u64> = post_service
+            .get_list(
+                5, &None, &None, &None, &None, &None, &None, &None, &None, &None, &None, &None,
+                &None,
+            )
+            .unwrap()
+            .items
+            .iter()
+            .map(|post| post.id)
+            .collect();
+
+        assert_eq!(post_ids, vec![2, 1, 3, 4]);
+    }
+
+    #[test]
+    fn test_get_calendar() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            tag_id: None,
+            journal_id: None,
+            from: Some(NaiveDate::from_ymd(2020, 11, 30)),
+            to: Some(NaiveDate::from_ymd(2021, 1, 1)),
+            status: Some(PostStatus::Published),
+            month_days: Vec::new(),
+            is_favorite: None,
+            near: None,
+        };
+
+        mocked_post_repository
+            .expect_find_summaries()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| {
+                let summary = |id: u64, date: &str| {
+                    (id, String::from("Title"), PostDate::parse(date).unwrap())
+                };
+
+                // Post 4 has no offset, and is on 2020-11-30 in UTC.
+                Ok(vec![
+                    summary(4, "2020-11-30T20:00:00"),
+                    summary(1, "2020-12-01T09:00:00+09:00"),
+                    summary(2, "2020-12-24T08:00:00+09:00"),
+                    summary(3, "2020-12-24T20:00:00+09:00"),
+                ])
+            });
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let calendar = post_service.get_calendar(user_id, 2020, 12).unwrap();
+
+        assert_eq!(calendar.len(), 2);
+        assert_eq!(calendar[1].date, NaiveDate::from_ymd(2020, 12, 24));
+        assert_eq!(calendar[1].count, 2);
+        assert_eq!(
+            calendar[1]
+                .posts
+                .iter()
+                .map(|post| post.id)
+                .collect::<Vec<u64>>(),
+            vec![2, 3]
+        );
+        assert!(post_service.get_calendar(user_id, 2020, 13).is_err());
+    }
+
+    #[test]
+    fn test_get_heatmap() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+
+        mocked_post_repository
+            .expect_count_by_date()
+            .with(
+                eq(user_id),
+                eq(NaiveDate::from_ymd(2020, 1, 1)),
+                eq(NaiveDate::from_ymd(2020, 12, 31)),
+            )
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(vec![
+                    (NaiveDate::from_ymd(2020, 1, 2), 2),
+                    (NaiveDate::from_ymd(2020, 12, 31), 1),
+                ])
+            });
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(
+            Utc.ymd(2020, 4, 12).and_hms(9, 0, 0),
+        )));
+        let heatmap = post_service.get_heatmap(user_id, &None).unwrap();
+
+        assert_eq!(heatmap.year, 2020);
+        assert_eq!(heatmap.counts.len(), 366);
+        assert_eq!(heatmap.counts[1], 2);
+        assert_eq!(heatmap.counts[365], 1);
+        assert_eq!(heatmap.counts.iter().sum::<usize>(), 3);
+        assert!(post_service.get_heatmap(user_id, &Some(300000)).is_err());
+    }
+
+    #[test]
+    fn test_get_archive() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        mocked_post_repository
+            .expect_count_by_month()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| Ok(vec![(2019, 12, 2), (2020, 1, 3), (2020, 4, 1)]));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let archive = post_service.get_archive(user_id).unwrap();
+
+        assert_eq!(
+            archive,
+            vec![
+                ArchiveYearDTO {
+                    year: 2019,
+                    count: 2,
+                    months: vec![ArchiveMonthDTO {
+                        month: 12,
+                        count: 2
+                    }],
+                },
+                ArchiveYearDTO {
+                    year: 2020,
+                    count: 4,
+                    months: vec![
+                        ArchiveMonthDTO { month: 1, count: 3 },
+                        ArchiveMonthDTO { month: 4, count: 1 },
+                    ],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_get_archive_year() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            from: Some(NaiveDate::from_ymd(2020, 1, 1)),
+            to: Some(NaiveDate::from_ymd(2020, 12, 31)),
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        mocked_post_repository
+            .expect_find_summaries()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| {
+                let summary = |id: u64, date: &str| {
+                    (id, String::from("Title"), PostDate::parse(date).unwrap())
+                };
+
+                Ok(vec![
+                    summary(1, "2020-01-01T08:00:00+09:00"),
+                    summary(2, "2020-03-31T23:30:00-05:00"),
+                    summary(3, "2020-04-01T09:00:00+09:00"),
+                ])
+            });
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let archive = post_service.get_archive_year(user_id, 2020).unwrap();
+
+        assert_eq!(
+            archive
+                .iter()
+                .map(|month| (month.month, month.count))
+                .collect::<Vec<(u32, usize)>>(),
+            vec![(1, 1), (3, 1), (4, 1)]
+        );
+        assert_eq!(archive[1].posts[0].id, 2);
+    }
+
+    #[test]
+    fn test_get_on_this_day() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let on_this_day_filter = PostFilter {
+            tag_id: None,
+            journal_id: None,
+            from: None,
+            to: Some(NaiveDate::from_ymd(2020, 12, 31)),
+            status: Some(PostStatus::Published),
+            month_days: vec![(4, 12)],
+            is_favorite: None,
+            near: None,
+        };
+        let leap_day_filter = PostFilter {
+            tag_id: None,
+            journal_id: None,
+            from: None,
+            to: Some(NaiveDate::from_ymd(2025, 12, 31)),
+            status: Some(PostStatus::Published),
+            month_days: vec![(2, 28), (2, 29)],
+            is_favorite: None,
+            near: None,
+        };
+
+        mocked_post_repository
+            .expect_find_list()
+            .with(
+                eq(user_id),
+                eq(on_this_day_filter),
+                eq(PostFields::All),
+                eq(PostSortKey::Date),
+                eq(SortOrder::Desc),
+                eq(None),
+            )
+            .times(1)
+            .returning(|user_id, _, _, _, _, _| {
+                let post = |id: u64, date: &str| {
+                    let date = PostDate::parse(date).unwrap();
+                    Post {
+                        id,
+                        user_id,
+                        title: String::from("Title"),
+                        content: String::from("Content"),
+                        date: date.date,
+                        date_offset: date.offset,
+                        intra_day_order: 0,
+                        created_at: date.date,
+                        updated_at: None,
+                        version: 1,
+                        deleted_at: None,
+                        status: String::from("published"),
+                        autosave_started_at: None,
+                        changed_at: date.date,
+                        is_favorite: false,
+                        mood: None,
+                        weather: None,
+                        latitude: None,
+                        longitude: None,
+                        place_name: None,
+                        journal_id: 1,
+                        word_count: None,
+                        is_locked: false,
+                        is_encrypted: true,
+                        encryption_scheme: None,
+                        encryption_nonce: None,
+                        key_version: None,
+                    }
+                };
+
+                Ok(vec![
+                    post(2, "2019-04-12T23:30:00-07:00"),
+                    post(1, "2018-04-12T08:00:00+09:00"),
+                ])
+            });
+        mocked_post_repository
+            .expect_find_list()
+            .with(
+                eq(user_id),
+                eq(leap_day_filter),
+                eq(PostFields::All),
+                eq(PostSortKey::Date),
+                eq(SortOrder::Desc),
+                eq(None),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(vec![]));
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .times(2)
+            .returning(|_| Ok(vec![(2, vec![3])].into_iter().collect()));
+
+        let now = Utc.ymd(2026, 2, 28).and_hms(9, 0, 0);
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        let post_list = post_service
+            .get_on_this_day(user_id, &Some(String::from("2021-04-12")))
+            .unwrap();
+        assert_eq!(
+            post_list.iter().map(|post| post.id).collect::<Vec<u64>>(),
+            vec![2, 1]
+        );
+        assert_eq!(post_list[0].date, "2019-04-12T23:30:00-07:00");
+        assert_eq!(post_list[0].tags, vec![3]);
+
+        assert!(post_service
+            .get_on_this_day(user_id, &None)
+            .unwrap()
+            .is_empty());
+        assert!(post_service
+            .get_on_this_day(user_id, &Some(String::from("04-12")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_count_streaks() {
+        let date = |day: u32| NaiveDate::from_ymd(2020, 4, day);
+        let dates = vec![date(1), date(2), date(3), date(5), date(6), date(20)];
+
+        assert_eq!(
+            PostService::count_streaks(&dates, date(6)),
+            StreakDTO {
+                current_streak: 2,
+                longest_streak: 3,
+                last_entry_date: Some(date(6)),
+            }
+        );
+        assert_eq!(
+            PostService::count_streaks(&dates, date(7)).current_streak,
+            2
+        );
+        assert_eq!(
+            PostService::count_streaks(&dates, date(8)).current_streak,
+            0
+        );
+        assert_eq!(
+            PostService::count_streaks(&dates, date(3)),
+            StreakDTO {
+                current_streak: 3,
+                longest_streak: 3,
+                last_entry_date: Some(date(3)),
+            }
+        );
+        assert_eq!(
+            PostService::count_streaks(&[], date(1)),
+            StreakDTO {
+                current_streak: 0,
+                longest_streak: 0,
+                last_entry_date: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_get_streak() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        mocked_post_repository
+            .expect_find_local_dates()
+            .with(eq(user_id), eq(filter))
+            .times(2)
+            .returning(|_, _| {
+                Ok(vec![
+                    NaiveDate::from_ymd(2026, 10, 14),
+                    NaiveDate::from_ymd(2026, 10, 15),
+                ])
+            });
+
+        let now = Utc.ymd(2026, 10, 16).and_hms(9, 0, 0);
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(
+            post_service
+                .get_streak(user_id, &None)
+                .unwrap()
+                .current_streak,
+            2
+        );
+        assert_eq!(
+            post_service
+                .get_streak(user_id, &Some(String::from("2026-10-17")))
+                .unwrap()
+                .current_streak,
+            0
+        );
+    }
+
+    #[test]
+    fn test_backfill_date_offsets() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_user_settings_repository = MockUserSettingsRepositoryTrait::new();
+        let mut seq = Sequence::new();
+
+        let date = NaiveDate::from_ymd(2026, 10, 17).and_hms(7, 0, 0);
+
+        mocked_post_repository
+            .expect_find_all_without_date_offset()
+            .with(eq(0), always())
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(move |_, _| Ok(vec![(1, 5, date), (2, 6, date), (3, 5, date)]));
+        mocked_post_repository
+            .expect_find_all_without_date_offset()
+            .with(eq(3), always())
+            .times(1)
+            .in_sequence(&mut seq)
+            .returning(|_, _| Ok(vec![]));
+        // Posts of the user in Seoul are written 9 hours earlier in UTC.
+        mocked_post_repository
+            .expect_set_date_offset()
+            .with(
+                function(|id: &u64| *id == 1 || *id == 3),
+                eq(PostDate {
+                    date: NaiveDate::from_ymd(2026, 10, 16).and_hms(22, 0, 0),
+                    offset: Some(32400),
+                }),
+            )
+            .times(2)
+            .returning(|_, _| Ok(true));
+        mocked_post_repository
+            .expect_set_date_offset()
+            .with(
+                eq(2),
+                eq(PostDate {
+                    date,
+                    offset: Some(0),
+                }),
+            )
+            .times(1)
+            .returning(|_, _| Ok(false));
+        // Settings are found once for each user.
+        mocked_user_settings_repository
+            .expect_find_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|user_id| {
+                Ok(Some(UserSettings {
+                    user_id,
+                    timezone: String::from("Asia/Seoul"),
+                    locale: String::from("ko-KR"),
+                    week_start_day: String::from("sunday"),
+                    editor_preferences: None,
+                    telemetry_opt_in: false,
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                }))
+            });
+        mocked_user_settings_repository
+            .expect_find_by_user_id()
+            .with(eq(6))
+            .times(1)
+            .returning(|_| Ok(None));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_user_settings_repository(mocked_user_settings_repository);
+
+        assert_eq!(post_service.backfill_date_offsets().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_get_streak_in_timezone() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_user_settings_repository = MockUserSettingsRepositoryTrait::new();
+
+        let user_id = 5;
+
+        mocked_post_repository
+            .expect_find_local_dates()
+            .times(1)
+            .returning(|_, _| {
+                Ok(vec![
+                    NaiveDate::from_ymd(2026, 10, 14),
+                    NaiveDate::from_ymd(2026, 10, 15),
+                ])
+            });
+        mocked_user_settings_repository
+            .expect_find_by_user_id()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|user_id| {
+                Ok(Some(UserSettings {
+                    user_id,
+                    timezone: String::from("Asia/Seoul"),
+                    locale: String::from("ko-KR"),
+                    week_start_day: String::from("sunday"),
+                    editor_preferences: None,
+                    telemetry_opt_in: false,
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                }))
+            });
+
+        // It is still October 16 in UTC, but already October 17 in Seoul.
+        let now = Utc.ymd(2026, 10, 16).and_hms(20, 0, 0);
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_user_settings_repository(mocked_user_settings_repository)
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(
+            post_service
+                .get_streak(user_id, &None)
+                .unwrap()
+                .current_streak,
+            0
+        );
+    }
+
+    #[test]
+    fn test_get_word_goal_progress() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+
+        let user_id = 5;
+        let daily_filter = PostFilter {
+            from: Some(NaiveDate::from_ymd(2026, 10, 16)),
+            to: Some(NaiveDate::from_ymd(2026, 10, 16)),
+            ..PostFilter::default()
+        };
+        let monthly_filter = PostFilter {
+            from: Some(NaiveDate::from_ymd(2026, 10, 1)),
+            to: Some(NaiveDate::from_ymd(2026, 10, 31)),
+            ..PostFilter::default()
+        };
+
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|id| {
+                Ok(User {
+                    id,
+                    name: String::from("Name"),
+                    email: String::from("name@example.com"),
+                    password: String::from("password"),
+                    avatar_url: None,
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                    key_metadata: None,
+                    daily_word_goal: Some(500),
+                    monthly_word_goal: None,
+                    role: String::from("user"),
+                    suspended_at: None,
+                })
+            });
+        mocked_post_repository
+            .expect_sum_word_counts()
+            .with(eq(user_id), eq(daily_filter))
+            .times(1)
+            .returning(|_, _| Ok(620));
+        mocked_post_repository
+            .expect_sum_word_counts()
+            .with(eq(user_id), eq(monthly_filter))
+            .times(1)
+            .returning(|_, _| Ok(8400));
+
+        let now = Utc.ymd(2026, 10, 16).and_hms(9, 0, 0);
+        let mut post_service =
+            PostService::new_with_repository(mocked_post_repository, mocked_user_repository)
+                .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(
+            post_service.get_word_goal_progress(user_id, &None).unwrap(),
+            WordGoalProgressDTO {
+                date: NaiveDate::from_ymd(2026, 10, 16),
+                daily: WordGoalDTO {
+                    goal: Some(500),
+                    word_count: 620,
+                    is_achieved: true,
+                },
+                monthly: WordGoalDTO {
+                    goal: None,
+                    word_count: 8400,
+                    is_achieved: false,
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_aggregate_moods() {
+        let date = |month: u32, day: u32| NaiveDate::from_ymd(2020, month, day);
+        let moods = vec![
+            (date(3, 31), 2),
+            (date(4, 1), 3),
+            (date(4, 1), 5),
+            (date(4, 6), 4),
+        ];
+
+        assert_eq!(
+            PostService::aggregate_moods(&moods, Granularity::Monthly, WeekStartDay::Monday),
+            vec![
+                MoodTrendDTO {
+                    date: date(3, 1),
+                    count: 1,
+                    average: 2.0,
+                    distribution: [0, 1, 0, 0, 0],
+                },
+                MoodTrendDTO {
+                    date: date(4, 1),
+                    count: 3,
+                    average: 4.0,
+                    distribution: [0, 0, 1, 1, 1],
+                },
+            ]
+        );
+
+        let weekly_trends =
+            PostService::aggregate_moods(&moods, Granularity::Weekly, WeekStartDay::Monday);
+        assert_eq!(weekly_trends.len(), 2);
+        assert_eq!(weekly_trends[0].date, date(3, 30));
+        assert_eq!(weekly_trends[0].count, 3);
+        assert_eq!(weekly_trends[1].date, date(4, 6));
+
+        // 2020-04-05 is Sunday.
+        let weekly_trends =
+            PostService::aggregate_moods(&moods, Granularity::Weekly, WeekStartDay::Sunday);
+        assert_eq!(weekly_trends.len(), 2);
+        assert_eq!(weekly_trends[0].date, date(3, 29));
+        assert_eq!(weekly_trends[1].date, date(4, 5));
+        assert_eq!(weekly_trends[1].count, 1);
+
+        assert_eq!(
+            PostService::aggregate_moods(&moods, Granularity::Daily, WeekStartDay::Monday).len(),
+            3
+        );
+        assert!(
+            PostService::aggregate_moods(&[], Granularity::Daily, WeekStartDay::Monday).is_empty()
+        );
+    }
+
+    #[test]
+    fn test_get_mood_trends() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            from: Some(NaiveDate::from_ymd(2020, 1, 1)),
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        mocked_post_repository
+            .expect_find_moods()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| Ok(vec![(NaiveDate::from_ymd(2020, 4, 12), 3)]));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        let trends = post_service
+            .get_mood_trends(user_id, &Some(String::from("2020-01-01")), &None, &None)
+            .unwrap();
+        assert_eq!(trends.len(), 1);
+        assert_eq!(trends[0].date, NaiveDate::from_ymd(2020, 4, 1));
+        assert!(post_service
+            .get_mood_trends(user_id, &None, &None, &Some(String::from("year")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_entry_counts() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_user_settings_repository = MockUserSettingsRepositoryTrait::new();
+
+        let user_id = 5;
+        let filter = PostFilter {
+            from: Some(NaiveDate::from_ymd(2020, 10, 31)),
+            to: Some(NaiveDate::from_ymd(2020, 12, 1)),
+            status: Some(PostStatus::Published),
+            ..PostFilter::default()
+        };
+
+        mocked_user_settings_repository
+            .expect_find_by_user_id()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|user_id| {
+                Ok(Some(UserSettings {
+                    user_id,
+                    timezone: String::from("America/New_York"),
+                    locale: String::from("en-US"),
+                    week_start_day: String::from("sunday"),
+                    editor_preferences: None,
+                    telemetry_opt_in: false,
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                }))
+            });
+        // Daylight saving time ends in New York on 2020-11-01, which is Sunday.
+        mocked_post_repository
+            .expect_find_dates()
+            .with(eq(user_id), eq(filter))
+            .times(1)
+            .returning(|_, _| {
+                Ok(vec![
+                    // 2020-10-31 (Saturday) in New York, before the range.
+                    PostDate::parse("2020-11-01T03:30:00").unwrap(),
+                    // 2020-11-01 (Sunday) in EST.
+                    PostDate::parse("2020-11-02T04:30:00").unwrap(),
+                    PostDate::parse("2020-11-02T23:00:00+09:00").unwrap(),
+                    // 2020-11-07 (Saturday) in EST.
+                    PostDate::parse("2020-11-08T04:30:00").unwrap(),
+                    PostDate::parse("2020-11-09T04:30:00").unwrap(),
+                ])
+            });
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_user_settings_repository(mocked_user_settings_repository);
+
+        let counts = post_service
+            .get_entry_counts(
+                user_id,
+                &Some(String::from("2020-11-01")),
+                &Some(String::from("2020-11-30")),
+                &None,
+            )
+            .unwrap();
+        assert_eq!(
+            counts,
+            vec![
+                EntryCountDTO {
+                    date: NaiveDate::from_ymd(2020, 11, 1),
+                    count: 3,
+                },
+                EntryCountDTO {
+                    date: NaiveDate::from_ymd(2020, 11, 8),
+                    count: 1,
+                },
+            ]
+        );
+        assert!(post_service
+            .get_entry_counts(
+                user_id,
+                &Some(String::from("2020-12-01")),
+                &Some(String::from("2020-11-01")),
+                &None,
+            )
+            .is_err());
+        assert!(post_service
+            .get_entry_counts(user_id, &None, &None, &Some(String::from("year")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_move_in_day() {
+        assert_eq!(move_in_day(&[1, 2, 3], 3, 0), vec![3, 1, 2]);
+        assert_eq!(move_in_day(&[1, 2, 3], 1, 1), vec![2, 1, 3]);
+        assert_eq!(move_in_day(&[1, 2, 3], 2, 10), vec![1, 3, 2]);
+    }
+
+    #[test]
+    fn test_delete_by_date() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+
+        let user_id = 5;
+        let date = NaiveDate::from_ymd(2020, 4, 12);
+
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(user_id))
+            .times(2)
+            .returning(|id| {
+                Ok(User {
+                    id,
+                    name: String::from("Name"),
+                    email: String::from("name@example.com"),
+                    password: password_util::get_hashed_password("password"),
+                    avatar_url: None,
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                    key_metadata: None,
+                    daily_word_goal: None,
+                    monthly_word_goal: None,
+                    role: String::from("user"),
+                    suspended_at: None,
+                })
+            });
+        mocked_post_repository
+            .expect_delete_by_date()
+            .with(eq(user_id), eq(date), eq(true))
+            .times(1)
+            .returning(|_, _, _| {
+                Ok(PostDateDeletion {
+                    post_ids: vec![3, 4],
+                    post_audit_count: 6,
+                    attachment_count: 1,
+                })
+            });
+
+        let mut post_service =
+            PostService::new_with_repository(mocked_post_repository, mocked_user_repository);
+
+        let deletion = post_service
+            .delete_by_date(user_id, "2020-04-12", "password", true, true)
+            .unwrap();
+        assert_eq!(deletion.post_ids, vec![3, 4]);
+        assert_eq!(deletion.post_audit_count, 6);
+        assert_eq!(deletion.attachment_count, 1);
+
+        assert!(post_service
+            .delete_by_date(user_id, "2020-04-12", "wrong", true, true)
+            .is_err());
+        assert!(post_service
+            .delete_by_date(user_id, "2020-04-12", "password", false, true)
+            .is_err());
+    }
+
+    #[test]
+    fn test_execute_bulk() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let operations = vec![
+            PostOperationDTO::Create {
+                title: String::from("Lorem ipsum"),
+                content: String::from("Lorem ipsum dolor sit amet"),
+                date: String::from("2020-04-12T09:00:00+09:00"),
+                tags: None,
+                status: None,
+                mood: Some(4),
+                weather: Some(String::from("sunny")),
+                location: PostLocationDTO {
+                    latitude: Some(37.5665),
+                    longitude: Some(126.978),
+                    place_name: None,
+                },
+                journal_id: None,
+                word_count: None,
+                encryption: PostEncryptionDTO {
+                    encrypted: Some(true),
+                    encryption_scheme: Some(String::from("aes-256-gcm")),
+                    nonce: Some(String::from("bm9uY2Vub25jZQ==")),
+                    key_version: Some(2),
+                },
+            },
+            PostOperationDTO::Update {
+                id: 3,
+                title: None,
+                content: Some(String::from("  ")),
+                date: None,
+                tags: None,
+                mood: None,
+                weather: None,
+                location: PostLocationDTO::default(),
+                journal_id: None,
+                word_count: None,
+                encryption: PostEncryptionDTO::default(),
+                version: None,
+            },
+            PostOperationDTO::Update {
+                id: 4,
+                title: Some(String::from("Dolor")),
+                content: None,
+                date: None,
+                tags: None,
+                mood: None,
+                weather: None,
+                location: PostLocationDTO::default(),
+                journal_id: None,
+                word_count: None,
+                encryption: PostEncryptionDTO::default(),
+                version: Some(2),
+            },
+            PostOperationDTO::Update {
+                id: 5,
+                title: None,
+                content: None,
+                date: None,
+                tags: None,
+                mood: Some(6),
+                weather: None,
+                location: PostLocationDTO::default(),
+                journal_id: None,
+                word_count: None,
+                encryption: PostEncryptionDTO::default(),
+                version: None,
+            },
+            PostOperationDTO::Delete { id: 6 },
+        ];
+        let executed_operations = vec![
+            PostOperation::Create {
+                title: String::from("Lorem ipsum"),
+                content: String::from("Lorem ipsum dolor sit amet"),
+                date: PostDate::parse("2020-04-12T09:00:00+09:00").unwrap(),
+                tag_ids: Vec::new(),
+                status: PostStatus::Published,
+                mood: Some(4),
+                weather: Some(PostWeather::Sunny),
+                location: Some(PostLocation {
+                    latitude: 37.5665,
+                    longitude: 126.978,
+                    place_name: None,
+                }),
+                journal_id: None,
+                word_count: None,
+                encryption: PostEncryption {
+                    is_encrypted: true,
+                    scheme: Some(String::from("aes-256-gcm")),
+                    nonce: Some(String::from("bm9uY2Vub25jZQ==")),
+                    key_version: Some(2),
+                },
+            },
+            PostOperation::Update {
+                post_id: 4,
+                title: Some(String::from("Dolor")),
+                content: None,
+                date: None,
+                tag_ids: None,
+                mood: None,
+                weather: None,
+                location: None,
+                journal_id: None,
+                word_count: None,
+                encryption: Some(PostEncryption::legacy()),
+                version: Some(2),
+            },
+            PostOperation::Delete { post_id: 6 },
+        ];
+
+        mocked_post_repository
+            .expect_execute_bulk()
+            .with(eq(user_id), eq(executed_operations), always())
+            .times(1)
+            .returning(|_, _, _| Ok(vec![Ok(10), Err(ServiceError::Conflict(3)), Ok(6)]));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        let batch = post_service
+            .execute_bulk(user_id, &operations, &None, &AuditContext::default())
+            .unwrap();
+        assert_eq!(batch.processed, 5);
+        assert_eq!(batch.remaining, 0);
+        assert_eq!(batch.next_cursor, None);
+        let results = batch.results;
+        assert_eq!(results.len(), 5);
+        assert_eq!(results[0].as_ref().ok(), Some(&10));
+        assert!(matches!(results[1], Err(ServiceError::InvalidArgument)));
+        assert!(matches!(results[2], Err(ServiceError::Conflict(3))));
+        assert!(matches!(results[3], Err(ServiceError::InvalidArgument)));
+        assert_eq!(results[4].as_ref().ok(), Some(&6));
+
+        assert!(post_service
+            .execute_bulk(user_id, &[], &None, &AuditContext::default())
+            .is_err());
+        assert!(post_service
+            .execute_bulk(user_id, &operations, &Some(5), &AuditContext::default())
+            .is_err());
+    }
+
+    #[test]
+    fn test_execute_bulk_resumed_after_interruption() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+
+        let user_id = 5;
+        let operations: Vec<PostOperationDTO> = (0..BULK_BATCH_SIZE + 50)
+            .map(|index| PostOperationDTO::Create {
+                title: format!("Post {}", index),
+                content: String::from("Lorem ipsum dolor sit amet"),
+                date: String::from("2020-04-12T09:00:00+09:00"),
+                tags: None,
+                status: None,
+                mood: None,
+                weather: None,
+                location: PostLocationDTO::default(),
+                journal_id: None,
+                word_count: None,
+                encryption: PostEncryptionDTO::default(),
+            })
+            .collect();
+        let titles = |operations: &[PostOperation]| -> Vec<String> {
+            operations
+                .iter()
+                .filter_map(|operation| match operation {
+                    PostOperation::Create { title, .. } => Some(title.clone()),
+                    _ => None,
+                })
+                .collect()
+        };
+        let expected_titles = |range: std::ops::Range<usize>| -> Vec<String> {
+            range.map(|index| format!("Post {}", index)).collect()
+        };
+
+        // Each post gets an id from its index, so that duplicates are found by the ids.
+        let create_all = |operations: &[PostOperation]| -> Vec<Result<u64, ServiceError>> {
+            operations
+                .iter()
+                .map(|operation| match operation {
+                    PostOperation::Create { title, .. } => {
+                        Ok(title["Post ".len()..].parse::<u64>().unwrap() + 1)
+                    }
+                    _ => Err(ServiceError::InvalidArgument),
+                })
+                .collect()
+        };
+        let first_batch = expected_titles(0..BULK_BATCH_SIZE);
+        let second_batch = expected_titles(BULK_BATCH_SIZE..BULK_BATCH_SIZE + 50);
+        mocked_post_repository
+            .expect_execute_bulk()
+            .withf(move |_, operations, _| titles(operations) == first_batch)
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_, operations, _| Ok(create_all(operations)));
+        // The request is interrupted in the second batch, whose transaction is rolled back.
+        let interrupted_batch = second_batch.clone();
+        mocked_post_repository
+            .expect_execute_bulk()
+            .withf(move |_, operations, _| titles(operations) == interrupted_batch)
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Err(ServiceError::QueryExecutionFailure));
+        mocked_post_repository
+            .expect_execute_bulk()
+            .withf(move |_, operations, _| titles(operations) == second_batch)
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(move |_, operations, _| Ok(create_all(operations)));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        let first = post_service
+            .execute_bulk(user_id, &operations, &None, &AuditContext::default())
+            .unwrap();
+        assert_eq!(first.processed, BULK_BATCH_SIZE);
+        assert_eq!(first.remaining, 50);
+        assert_eq!(first.next_cursor, Some(BULK_BATCH_SIZE));
+
+        assert!(post_service
+            .execute_bulk(
+                user_id,
+                &operations,
+                &first.next_cursor,
+                &AuditContext::default()
+            )
+            .is_err());
+
+        let resumed = post_service
+            .execute_bulk(
+                user_id,
+                &operations,
+                &first.next_cursor,
+                &AuditContext::default(),
+            )
+            .unwrap();
+        assert_eq!(resumed.processed, BULK_BATCH_SIZE + 50);
+        assert_eq!(resumed.remaining, 0);
+        assert_eq!(resumed.next_cursor, None);
+
+        let mut ids: Vec<u64> = first
+            .results
+            .into_iter()
+            .chain(resumed.results.into_iter())
+            .map(|result| result.unwrap())
+            .collect();
+        ids.sort_unstable();
+        assert_eq!(
+            ids,
+            (1..=(BULK_BATCH_SIZE + 50) as u64).collect::<Vec<u64>>()
+        );
+    }
+
+    #[test]
+    fn test_restore_revision() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let id = 3;
+        let user_id = 5;
+        let date = PostDate::parse("2020-04-12T16:43:03+09:00").unwrap();
+
+        mocked_post_repository
+            .expect_find_revision()
+            .with(eq(user_id), eq(id), eq(2))
+            .times(1)
+            .returning(move |passed_user_id, passed_post_id, version| {
+                Ok(PostRevision {
+                    id: 1,
+                    user_id: passed_user_id,
+                    post_id: passed_post_id,
+                    version,
+                    title: String::from("Title"),
+                    content: String::from("Content"),
+                    date: date.date,
+                    date_offset: date.offset,
+                    created_at: Utc::now().naive_utc(),
+                    is_encrypted: true,
+                    encryption_scheme: Some(String::from("aes-256-gcm")),
+                    encryption_nonce: None,
+                    key_version: Some(1),
+                })
+            });
+        mocked_post_repository
+            .expect_update()
+            .with(
+                eq(user_id),
+                eq(id),
+                eq(Some(String::from("Title"))),
+                eq(Some(String::from("Content"))),
+                eq(Some(date)),
+                eq(None),
+                eq(None),
+                eq(None),
+                eq(None),
+                eq(None),
+                eq(None),
+                eq(Some(PostEncryption {
+                    is_encrypted: true,
+                    scheme: Some(String::from("aes-256-gcm")),
+                    nonce: None,
+                    key_version: Some(1),
+                })),
+                eq(None),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _, _| Ok(true));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        assert!(post_service
+            .restore_revision(id, user_id, 2, &AuditContext::default())
+            .unwrap());
+    }
+
+    #[test]
+    fn test_update_with_unmodified_since() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let id = 3;
+        let user_id = 5;
+        let updated_at = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0).naive_utc();
+
+        mocked_post_repository
+            .expect_find()
+            .with(eq(user_id), eq(id))
+            .times(2)
+            .returning(move |user_id, id| {
+                Ok(Post {
                     id,
+                    user_id,
+                    title: String::from("Title"),
+                    content: String::from("Content"),
+                    date: updated_at,
+                    date_offset: None,
+                    intra_day_order: 0,
+                    created_at: updated_at - Duration::days(1),
+                    updated_at: Some(updated_at),
+                    version: 4,
+                    deleted_at: None,
+                    status: String::from("published"),
+                    autosave_started_at: None,
+                    changed_at: updated_at,
+                    is_favorite: false,
+                    mood: None,
+                    weather: None,
+                    latitude: None,
+                    longitude: None,
+                    place_name: None,
+                    journal_id: 1,
+                    word_count: None,
+                    is_locked: false,
+                    is_encrypted: true,
+                    encryption_scheme: None,
+                    encryption_nonce: None,
+                    key_version: None,
+                })
+            });
+        mocked_post_repository
+            .expect_update()
+            .with(
+                eq(user_id),
+                eq(id),
+                eq(None),
+                eq(Some(String::from("Edited"))),
+                eq(None),
+                eq(None),
+                eq(None),
+                eq(None),
+                eq(None),
+                eq(None),
+                eq(Some(120)),
+                eq(Some(PostEncryption::legacy())),
+                eq(Some(4)),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _, _| Ok(true));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        let content = Some(String::from("Edited"));
+        assert!(post_service
+            .update(
+                id,
+                user_id,
+                &None,
+                &content,
+                &None,
+                &None,
+                &None,
+                &None,
+                &PostLocationDTO::default(),
+                &None,
+                &Some(120),
+                &PostEncryptionDTO::default(),
+                &None,
+                &Some(updated_at),
+                &AuditContext::default(),
+            )
+            .unwrap());
+        assert!(matches!(
+            post_service.update(
+                id,
+                user_id,
+                &None,
+                &content,
+                &None,
+                &None,
+                &None,
+                &None,
+                &PostLocationDTO::default(),
+                &None,
+                &Some(120),
+                &PostEncryptionDTO::default(),
+                &None,
+                &Some(updated_at - Duration::seconds(1)),
+                &AuditContext::default(),
+            ),
+            Err(ServiceError::Conflict(4))
+        ));
+    }
+
+    #[test]
+    fn test_autosave() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let id = 3;
+        let user_id = 5;
+        let now = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0);
+        // Whether each autosave takes a revision by when the ongoing autosaves started.
+        let cases = vec![
+            (None, true),
+            (Some(now.naive_utc() - Duration::minutes(3)), false),
+            (Some(now.naive_utc() - Duration::minutes(10)), true),
+        ];
+
+        let mut sequence = Sequence::new();
+        for (started_at, revises) in cases {
+            mocked_post_repository
+                .expect_find()
+                .with(eq(user_id), eq(id))
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(move |user_id, id| {
+                    Ok(Post {
+                        id,
+                        user_id,
+                        title: String::from("Title"),
+                        content: String::from("Content"),
+                        date: now.naive_utc(),
+                        date_offset: None,
+                        intra_day_order: 0,
+                        created_at: now.naive_utc() - Duration::days(1),
+                        updated_at: None,
+                        version: 2,
+                        deleted_at: None,
+                        status: String::from("published"),
+                        autosave_started_at: started_at,
+                        changed_at: now.naive_utc() - Duration::days(1),
+                        is_favorite: false,
+                        mood: None,
+                        weather: None,
+                        latitude: None,
+                        longitude: None,
+                        place_name: None,
+                        journal_id: 1,
+                        word_count: None,
+                        is_locked: false,
+                        is_encrypted: true,
+                        encryption_scheme: None,
+                        encryption_nonce: None,
+                        key_version: None,
+                    })
+                });
+            mocked_post_repository
+                .expect_autosave()
+                .with(
+                    eq(user_id),
+                    eq(id),
+                    eq(None),
+                    eq("Edited"),
+                    eq(PostEncryption::legacy()),
+                    eq(2),
+                    eq(revises),
+                    eq(now.naive_utc()),
+                    always(),
+                )
+                .times(1)
+                .in_sequence(&mut sequence)
+                .returning(|_, _, _, _, _, _, _, _, _| Ok(3));
+        }
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        for _ in 0..3 {
+            assert_eq!(
+                post_service
+                    .autosave(
+                        id,
+                        user_id,
+                        &None,
+                        "Edited",
+                        &PostEncryptionDTO::default(),
+                        &None,
+                        &AuditContext::default()
+                    )
+                    .unwrap(),
+                3
+            );
+        }
+        assert!(post_service
+            .autosave(
+                id,
+                user_id,
+                &None,
+                " ",
+                &PostEncryptionDTO::default(),
+                &None,
+                &AuditContext::default()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_get_changes() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let found_at = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0).naive_utc();
+        let since = found_at - Duration::minutes(30);
+
+        mocked_post_repository
+            .expect_find_changes()
+            .with(eq(user_id), eq(Some(since)))
+            .times(1)
+            .returning(move |user_id, _| {
+                let date = PostDate::parse("2020-04-12T16:43:03+09:00").unwrap();
+                Ok(PostChanges {
+                    posts: vec![Post {
+                        id: 3,
+                        user_id,
+                        title: String::from("Title"),
+                        content: String::from("Content"),
+                        date: date.date,
+                        date_offset: date.offset,
+                        intra_day_order: 0,
+                        created_at: found_at - Duration::minutes(10),
+                        updated_at: None,
+                        version: 1,
+                        deleted_at: None,
+                        status: String::from("published"),
+                        autosave_started_at: None,
+                        changed_at: found_at - Duration::minutes(10),
+                        is_favorite: false,
+                        mood: None,
+                        weather: None,
+                        latitude: None,
+                        longitude: None,
+                        place_name: None,
+                        journal_id: 1,
+                        word_count: None,
+                        is_locked: false,
+                        is_encrypted: true,
+                        encryption_scheme: None,
+                        encryption_nonce: None,
+                        key_version: None,
+                    }],
+                    deleted_posts: vec![(4, found_at - Duration::minutes(5))],
+                    found_at,
+                })
+            });
+        mocked_post_repository
+            .expect_find_tag_ids()
+            .with(eq(vec![3]))
+            .times(1)
+            .returning(|_| Ok(vec![(3, vec![2])].into_iter().collect()));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(Utc.from_utc_datetime(&found_at))));
+
+        let changes = post_service
+            .get_changes(user_id, &Some(String::from("2020-04-12T17:30:00+09:00")))
+            .unwrap();
+        assert_eq!(changes.posts.len(), 1);
+        assert_eq!(changes.posts[0].tags, vec![2]);
+        assert_eq!(changes.deleted.len(), 1);
+        assert_eq!(changes.deleted[0].id, 4);
+        assert_eq!(changes.cursor, found_at - Duration::minutes(1));
+
+        assert!(post_service
+            .get_changes(user_id, &Some(String::from("yesterday")))
+            .is_err());
+        assert!(post_service
+            .get_changes(user_id, &Some(String::from("2018-04-12T17:30:00+09:00")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let since = NaiveDate::from_ymd(2020, 4, 12).and_hms_micro(9, 0, 0, 123456);
+
+        assert_eq!(
+            PostService::parse_since(&Some(String::from("2020-04-12T09:00:00.123456"))).unwrap(),
+            Some(since)
+        );
+        assert_eq!(
+            PostService::parse_since(&Some(String::from("2020-04-12T18:00:00.123456+09:00")))
+                .unwrap(),
+            Some(since)
+        );
+        assert_eq!(PostService::parse_since(&None).unwrap(), None);
+    }
+
+    #[test]
+    fn test_check_lengths() {
+        let long_title = "t".repeat(DEFAULT_MAX_TITLE_LENGTH + 1);
+        let long_content = "c".repeat(MAX_TEXT_LENGTH + 1);
+
+        assert!(PostService::check_lengths(Some("Title"), Some("Content")).is_ok());
+        assert!(PostService::check_lengths(None, None).is_ok());
+
+        match PostService::check_lengths(Some(&long_title), Some(&long_content)) {
+            Err(ServiceError::InvalidFields(field_errors)) => assert_eq!(
+                field_errors,
+                vec![
+                    FieldError {
+                        field: String::from("title"),
+                        message: format!("must be at most {} bytes", DEFAULT_MAX_TITLE_LENGTH),
+                    },
+                    FieldError {
+                        field: String::from("content"),
+                        message: format!("must be at most {} bytes", MAX_TEXT_LENGTH),
+                    },
+                ]
+            ),
+            _ => panic!("too long title and content must be reported"),
+        }
+        assert!(matches!(
+            PostService::check_lengths(None, Some(&long_content)),
+            Err(ServiceError::InvalidFields(_))
+        ));
+    }
+
+    #[test]
+    fn test_post_location() {
+        let location = |latitude, longitude, place_name: Option<&str>| PostLocationDTO {
+            latitude,
+            longitude,
+            place_name: place_name.map(String::from),
+        };
+
+        assert_eq!(
+            PostLocation::parse(&location(Some(37.5665), Some(126.978), Some("Seoul"))).unwrap(),
+            Some(PostLocation {
+                latitude: 37.5665,
+                longitude: 126.978,
+                place_name: Some(String::from("Seoul")),
+            })
+        );
+        assert_eq!(
+            PostLocation::parse(&PostLocationDTO::default()).unwrap(),
+            None
+        );
+        assert!(PostLocation::parse(&location(Some(37.5665), None, None)).is_err());
+        assert!(PostLocation::parse(&location(None, None, Some("Seoul"))).is_err());
+        assert!(PostLocation::parse(&location(Some(91.0), Some(0.0), None)).is_err());
+        assert!(PostLocation::parse(&location(Some(0.0), Some(-180.5), None)).is_err());
+        assert!(PostLocation::parse(&location(Some(f64::NAN), Some(0.0), None)).is_err());
+        assert!(PostLocation::parse(&location(Some(0.0), Some(0.0), Some(" "))).is_err());
+    }
+
+    #[test]
+    fn test_post_encryption() {
+        let encryption =
+            |encrypted, scheme: Option<&str>, nonce: Option<&str>, key_version| PostEncryptionDTO {
+                encrypted,
+                encryption_scheme: scheme.map(String::from),
+                nonce: nonce.map(String::from),
+                key_version,
+            };
+
+        assert_eq!(
+            PostEncryption::parse(&encryption(
+                Some(true),
+                Some("aes-256-gcm"),
+                Some("bm9uY2Vub25jZQ=="),
+                Some(2)
+            ))
+            .unwrap(),
+            Some(PostEncryption {
+                is_encrypted: true,
+                scheme: Some(String::from("aes-256-gcm")),
+                nonce: Some(String::from("bm9uY2Vub25jZQ==")),
+                key_version: Some(2),
+            })
+        );
+        assert_eq!(
+            PostEncryption::parse(&encryption(Some(false), None, None, None)).unwrap(),
+            Some(PostEncryption {
+                is_encrypted: false,
+                scheme: None,
+                nonce: None,
+                key_version: None,
+            })
+        );
+        assert_eq!(
+            PostEncryption::parse(&PostEncryptionDTO::default()).unwrap(),
+            None
+        );
+        assert!(PostEncryption::parse(&encryption(Some(false), None, None, Some(1))).is_err());
+        assert!(PostEncryption::parse(&encryption(None, Some("AES"), None, None)).is_err());
+        assert!(PostEncryption::parse(&encryption(None, None, Some("bm9uY2U="), None)).is_err());
+        assert!(
+            PostEncryption::parse(&encryption(None, Some("aes-256-gcm"), Some("n.o"), None))
+                .is_err()
+        );
+        assert!(PostEncryption::parse(&encryption(None, None, None, Some(0))).is_err());
+    }
+
+    #[test]
+    fn test_parse_update_args_with_encryption() {
+        let parse = |title: Option<&str>, content: Option<&str>, encryption| {
+            PostService::parse_update_args(
+                &title.map(String::from),
+                &content.map(String::from),
+                &None,
+                &None,
+                &None,
+                &None,
+                &PostLocationDTO::default(),
+                &None,
+                &None,
+                &encryption,
+                true,
+            )
+            .map(|(_, _, _, encryption)| encryption)
+        };
+        let given = PostEncryptionDTO {
+            encrypted: Some(true),
+            encryption_scheme: Some(String::from("aes-256-gcm")),
+            nonce: None,
+            key_version: Some(3),
+        };
+
+        assert_eq!(
+            parse(Some("Title"), Some("Content"), given.clone())
+                .unwrap()
+                .and_then(|encryption| encryption.key_version),
+            Some(3)
+        );
+        assert_eq!(
+            parse(None, Some("Content"), PostEncryptionDTO::default()).unwrap(),
+            Some(PostEncryption::legacy())
+        );
+        assert!(parse(None, Some("Content"), given).is_err());
+    }
+
+    #[test]
+    fn test_parse_near() {
+        assert_eq!(
+            PostService::parse_near(&Some(String::from("37.5665,126.978")), &None).unwrap(),
+            Some(GeoCircle {
+                latitude: 37.5665,
+                longitude: 126.978,
+                radius: DEFAULT_NEAR_RADIUS_METERS,
+            })
+        );
+        assert_eq!(PostService::parse_near(&None, &None).unwrap(), None);
+        assert!(PostService::parse_near(&None, &Some(500.0)).is_err());
+        assert!(PostService::parse_near(&Some(String::from("37.5665")), &None).is_err());
+        assert!(PostService::parse_near(&Some(String::from("north,east")), &None).is_err());
+        assert!(PostService::parse_near(&Some(String::from("0,0")), &Some(0.0)).is_err());
+    }
+
+    #[test]
+    fn test_get_trash() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let user_id = 5;
+        let deleted_at = Utc.ymd(2026, 10, 1).and_hms(9, 0, 0).naive_utc();
+
+        mocked_post_repository
+            .expect_find_all_trashed()
+            .with(eq(user_id))
+            .times(1)
+            .returning(move |passed_user_id| {
+                Ok(vec![Post {
+                    id: 3,
                     user_id: passed_user_id,
                     title: String::from("Title"),
                     content: String::from("Content"),
-                    date: now.clone(),
-                    created_at: now.clone(),
+                    date: deleted_at,
+                    date_offset: None,
+                    intra_day_order: 0,
+                    created_at: deleted_at,
                     updated_at: None,
-                };
+                    version: 1,
+                    deleted_at: Some(deleted_at),
+                    status: String::from("published"),
+                    autosave_started_at: None,
+                    changed_at: deleted_at,
+                    is_favorite: false,
+                    mood: None,
+                    weather: None,
+                    latitude: None,
+                    longitude: None,
+                    place_name: None,
+                    journal_id: 1,
+                    word_count: None,
+                    is_locked: false,
+                    is_encrypted: true,
+                    encryption_scheme: None,
+                    encryption_nonce: None,
+                    key_version: None,
+                }])
+            });
 
-                Ok(vec![post])
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let trash = post_service.get_trash(user_id).unwrap();
+
+        assert_eq!(trash.first().unwrap().id, 3);
+        assert_eq!(
+            trash.first().unwrap().purge_at,
+            deleted_at + PostService::get_trash_retention()
+        );
+    }
+
+    #[test]
+    fn test_get_response_meta() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+
+        let user_id = 5;
+        let now = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0);
+        let threshold = (now - PostService::get_trash_retention()
+            + Duration::days(TRASH_PURGE_WARNING_DAYS))
+        .naive_utc();
+
+        mocked_post_repository
+            .expect_count_trashed_before()
+            .with(eq(user_id), eq(threshold))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Ok(3));
+        mocked_post_repository
+            .expect_count_trashed_before()
+            .with(eq(user_id), eq(threshold))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _| Ok(0));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(
+            post_service.get_response_meta(user_id).unwrap(),
+            ResponseMeta::new().posts_in_trash_purging_soon(3)
+        );
+        assert!(post_service.get_response_meta(user_id).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_purge_trash() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let now = Utc.ymd(2026, 10, 15).and_hms(9, 0, 0);
+
+        mocked_post_repository
+            .expect_purge_trashed()
+            .with(eq((now - PostService::get_trash_retention()).naive_utc()))
+            .times(1)
+            .returning(|_| Ok(2));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(post_service.purge_trash().unwrap(), 2);
+    }
+
+    #[test]
+    fn test_prune_tombstones() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let now = Utc.ymd(2026, 10, 15).and_hms(9, 0, 0);
+
+        mocked_post_repository
+            .expect_prune_tombstones()
+            .with(eq(
+                (now - PostService::get_tombstone_retention()).naive_utc()
+            ))
+            .times(1)
+            .returning(|_| Ok(3));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(post_service.prune_tombstones().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_create_from_template() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut mocked_template_repository = MockTemplateRepositoryTrait::new();
+
+        let user_id = 5;
+        let template_id = 2;
+
+        mocked_template_repository
+            .expect_find()
+            .with(eq(user_id), eq(template_id))
+            .times(2)
+            .returning(|passed_user_id, passed_template_id| {
+                Ok(Template {
+                    id: passed_template_id,
+                    user_id: passed_user_id,
+                    name: String::from("Weekly review"),
+                    title: String::from("Template title"),
+                    content: String::from("Template content"),
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                })
+            });
+        mocked_post_repository
+            .expect_create()
+            .withf(
+                move |passed_user_id, title, content, _, _, _, _, _, _, _, _, _, _| {
+                    *passed_user_id == user_id
+                        && title == "Template title"
+                        && content == "My content"
+                },
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _| Ok(1));
+        mocked_post_repository
+            .expect_create()
+            .withf(
+                move |passed_user_id, title, content, _, _, _, _, _, _, _, _, _, _| {
+                    *passed_user_id == user_id
+                        && title == "Template title"
+                        && content == "Template content"
+                },
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _, _, _, _, _, _, _, _| Ok(2));
+
+        mocked_post_repository
+            .expect_count_trashed_before()
+            .with(eq(user_id), always())
+            .times(2)
+            .returning(|_, _| Ok(0));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_template_repository(mocked_template_repository);
+
+        let create = |post_service: &mut PostService, content: Option<&str>, template_id| {
+            post_service.create(
+                user_id,
+                &None,
+                &content.map(String::from),
+                "2020-04-12T16:43:03+09:00",
+                &[],
+                &None,
+                &None,
+                &None,
+                &PostLocationDTO {
+                    latitude: None,
+                    longitude: None,
+                    place_name: None,
+                },
+                &None,
+                &None,
+                &PostEncryptionDTO::default(),
+                &template_id,
+                &AuditContext::default(),
+            )
+        };
+        assert_eq!(
+            create(&mut post_service, Some("My content"), Some(template_id))
+                .unwrap()
+                .data,
+            1
+        );
+        assert_eq!(
+            create(&mut post_service, None, Some(template_id))
+                .unwrap()
+                .data,
+            2
+        );
+        assert!(create(&mut post_service, None, None).is_err());
+    }
+
+    #[test]
+    fn test_duplicate() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+
+        let id = 3;
+        let user_id = 5;
+        let now = Utc.ymd(2026, 10, 15).and_hms(9, 0, 0);
+        let written_at = Utc.ymd(2026, 10, 8).and_hms(9, 0, 0).naive_utc();
+
+        mocked_post_repository
+            .expect_find()
+            .with(eq(user_id), eq(id))
+            .times(1)
+            .returning(move |passed_user_id, passed_post_id| {
+                Ok(Post {
+                    id: passed_post_id,
+                    user_id: passed_user_id,
+                    title: String::from("Weekly review"),
+                    content: String::from("Content"),
+                    date: written_at,
+                    date_offset: Some(32400),
+                    intra_day_order: 0,
+                    created_at: written_at,
+                    updated_at: None,
+                    version: 1,
+                    deleted_at: None,
+                    status: String::from("published"),
+                    autosave_started_at: None,
+                    changed_at: written_at,
+                    is_favorite: false,
+                    mood: None,
+                    weather: None,
+                    latitude: None,
+                    longitude: None,
+                    place_name: None,
+                    journal_id: 1,
+                    word_count: None,
+                    is_locked: false,
+                    is_encrypted: true,
+                    encryption_scheme: None,
+                    encryption_nonce: None,
+                    key_version: None,
+                })
             });
+        mocked_post_repository
+            .expect_duplicate()
+            .with(
+                eq(user_id),
+                eq(id),
+                eq(PostDate {
+                    date: now.naive_utc(),
+                    offset: Some(32400),
+                }),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(7));
+        mocked_post_repository
+            .expect_duplicate()
+            .with(
+                eq(user_id),
+                eq(id),
+                eq(PostDate::parse("2026-10-22T21:00:00+09:00").unwrap()),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(8));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        let audit_context = AuditContext::default();
+        assert_eq!(
+            post_service
+                .duplicate(id, user_id, &None, &audit_context)
+                .unwrap(),
+            7
+        );
+        assert_eq!(
+            post_service
+                .duplicate(
+                    id,
+                    user_id,
+                    &Some(String::from("2026-10-22T21:00:00+09:00")),
+                    &audit_context
+                )
+                .unwrap(),
+            8
+        );
+        assert!(post_service
+            .duplicate(id, user_id, &Some(String::from("today")), &audit_context)
+            .is_err());
+    }
+
+    #[test]
+    fn test_publish() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+
+        let id = 3;
+        let user_id = 5;
+
+        mocked_post_repository
+            .expect_publish()
+            .with(eq(user_id), eq(id), always())
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(true));
+        // The post is already published.
+        mocked_post_repository
+            .expect_publish()
+            .with(eq(user_id), eq(id), always())
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(false));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        let audit_context = AuditContext::default();
+        assert!(post_service.publish(id, user_id, &audit_context).unwrap());
+        assert!(!post_service.publish(id, user_id, &audit_context).unwrap());
+    }
+
+    #[test]
+    fn test_set_favorite() {
+        let mut mocked_post_repository = MockPostRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+
+        let id = 3;
+        let user_id = 5;
+
+        mocked_post_repository
+            .expect_set_favorite()
+            .with(eq(user_id), eq(id), eq(true))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(true));
+        // The post is already marked.
+        mocked_post_repository
+            .expect_set_favorite()
+            .with(eq(user_id), eq(id), eq(true))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(false));
+        mocked_post_repository
+            .expect_set_favorite()
+            .with(eq(user_id), eq(id), eq(false))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _| Ok(true));
+        mocked_post_repository
+            .expect_set_favorite()
+            .with(eq(user_id), eq(id + 1), eq(false))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, id, _| Err(ServiceError::NotFound(id.to_string())));
+
+        let mut post_service = PostService::new_with_repository(
+            mocked_post_repository,
+            MockUserRepositoryTrait::new(),
+        );
+        assert!(post_service.set_favorite(id, user_id, true).unwrap());
+        assert!(!post_service.set_favorite(id, user_id, true).unwrap());
+        assert!(post_service.set_favorite(id, user_id, false).unwrap());
+        assert!(post_service.set_favorite(id + 1, user_id, false).is_err());
+    }
+
+    #[test]
+    fn test_post_date() {
+        let date = PostDate::parse("2020-04-12T16:43:03+09:00").unwrap();
+        assert_eq!(date.date.to_string(), "2020-04-12 07:43:03");
+        assert_eq!(date.offset, Some(9 * 3600));
+        assert_eq!(date.to_rfc3339(), "2020-04-12T16:43:03+09:00");
 
-        let mut post_service = PostService::new_with_repository(mocked_post_repository);
-        let post_list: Vec<PostDTO> = post_service.get_list(user_id).unwrap();
+        let legacy_date = PostDate::parse("2020-04-12T16:43:03").unwrap();
+        assert_eq!(legacy_date.offset, None);
+        assert_eq!(legacy_date.to_rfc3339(), "2020-04-12T16:43:03");
 
-        assert_eq!(post_list.first().unwrap().id, id);
+        assert!(PostDate::parse("2020-04-12").is_err());
     }
 }
@@ -0,0 +1,207 @@
+use chrono::{Duration, NaiveDateTime};
+use std::env;
+use std::sync::Arc;
+
+use crate::models::error::ServiceError;
+use crate::models::post_audit::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::pagination_util::get_offset_and_limit;
+
+/// Default retention period of audit entries.
+const DEFAULT_RETENTION_DAYS: i64 = 365;
+
+pub struct PostAuditService {
+    post_audit_repository: Option<PostAuditRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PostAuditService {
+    pub fn new() -> Self {
+        Self {
+            post_audit_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn post_audit_repository(
+        &mut self,
+        new_repository: Option<PostAuditRepository>,
+    ) -> &PostAuditRepository {
+        match new_repository {
+            Some(_) => {
+                self.post_audit_repository = new_repository;
+                self.post_audit_repository.as_ref().unwrap()
+            }
+            None => self.post_audit_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn to_dto(audit: PostAudit) -> PostAuditDTO {
+        PostAuditDTO {
+            id: audit.id,
+            post_id: audit.post_id,
+            action: audit.action,
+            ip: audit.ip,
+            user_agent: audit.user_agent,
+            session_id: audit.session_id,
+            created_at: audit.created_at,
+        }
+    }
+
+    /// Finds audit entries of a post written by specific user.
+    pub fn get_list_by_post(
+        &mut self,
+        user_id: u64,
+        post_id: u64,
+        page: &Option<u32>,
+        per_page: &Option<u32>,
+    ) -> Result<Vec<PostAuditDTO>, ServiceError> {
+        let (offset, limit) = get_offset_and_limit(page, per_page)?;
+
+        let audit_list = {
+            let fallback_repository =
+                some_if_true!(self.post_audit_repository.is_none() => PostAuditRepository::new());
+            self.post_audit_repository(fallback_repository)
+                .find_all_by_post_id(user_id, post_id, offset, limit)?
+        };
+
+        Ok(audit_list.into_iter().map(Self::to_dto).collect())
+    }
+
+    /// Finds audit entries of all posts written by specific user since the datetime.
+    pub fn get_list(
+        &mut self,
+        user_id: u64,
+        since: &Option<NaiveDateTime>,
+        page: &Option<u32>,
+        per_page: &Option<u32>,
+    ) -> Result<Vec<PostAuditDTO>, ServiceError> {
+        let (offset, limit) = get_offset_and_limit(page, per_page)?;
+
+        let audit_list = {
+            let fallback_repository =
+                some_if_true!(self.post_audit_repository.is_none() => PostAuditRepository::new());
+            self.post_audit_repository(fallback_repository)
+                .find_all_since(user_id, since, offset, limit)?
+        };
+
+        Ok(audit_list.into_iter().map(Self::to_dto).collect())
+    }
+
+    /// Deletes audit entries older than `POST_AUDIT_RETENTION_DAYS` and returns the count.
+    pub fn prune(&mut self) -> Result<usize, ServiceError> {
+        let retention_days = env::var("POST_AUDIT_RETENTION_DAYS")
+            .ok()
+            .and_then(|days| days.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+        let threshold = self.clock.now().naive_utc() - Duration::days(retention_days);
+
+        let fallback_repository =
+            some_if_true!(self.post_audit_repository.is_none() => PostAuditRepository::new());
+        self.post_audit_repository(fallback_repository)
+            .delete_older_than(&threshold)
+    }
+}
+
+impl Default for PostAuditService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::post_audit::MockPostAuditRepositoryTrait as PostAuditRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::post_audit::MockPostAuditRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+    use crate::utils::pagination_util::MAX_PER_PAGE;
+
+    impl PostAuditService {
+        pub fn new_with_repository(post_audit_repository: PostAuditRepository) -> Self {
+            Self {
+                post_audit_repository: Some(post_audit_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    #[test]
+    fn test_get_list_by_post() {
+        let mut mocked_post_audit_repository = MockPostAuditRepositoryTrait::new();
+
+        let user_id = 5;
+        let post_id = 3;
+
+        mocked_post_audit_repository
+            .expect_find_all_by_post_id()
+            .with(eq(user_id), eq(post_id), eq(20), eq(10))
+            .times(1)
+            .returning(move |passed_user_id, passed_post_id, _, _| {
+                Ok(vec![PostAudit {
+                    id: 1,
+                    user_id: passed_user_id,
+                    post_id: passed_post_id,
+                    action: String::from("delete"),
+                    ip: Some(String::from("127.0.0.1")),
+                    user_agent: None,
+                    session_id: None,
+                    created_at: Utc::now().naive_utc(),
+                }])
+            });
+
+        let mut post_audit_service =
+            PostAuditService::new_with_repository(mocked_post_audit_repository);
+        let audit_list = post_audit_service
+            .get_list_by_post(user_id, post_id, &Some(3), &Some(10))
+            .unwrap();
+
+        assert_eq!(audit_list.first().unwrap().action, "delete");
+    }
+
+    #[test]
+    fn test_get_list_with_invalid_page() {
+        let mut post_audit_service =
+            PostAuditService::new_with_repository(MockPostAuditRepositoryTrait::new());
+
+        assert!(post_audit_service
+            .get_list(5, &None, &Some(0), &None)
+            .is_err());
+        assert!(post_audit_service
+            .get_list(5, &None, &None, &Some(MAX_PER_PAGE + 1))
+            .is_err());
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut mocked_post_audit_repository = MockPostAuditRepositoryTrait::new();
+
+        let now = Utc.ymd(2026, 10, 15).and_hms(9, 0, 0);
+        let retention_days = env::var("POST_AUDIT_RETENTION_DAYS")
+            .ok()
+            .and_then(|days| days.parse::<i64>().ok())
+            .unwrap_or(DEFAULT_RETENTION_DAYS);
+
+        mocked_post_audit_repository
+            .expect_delete_older_than()
+            .with(eq((now - Duration::days(retention_days)).naive_utc()))
+            .times(1)
+            .returning(|_| Ok(2));
+
+        let mut post_audit_service =
+            PostAuditService::new_with_repository(mocked_post_audit_repository)
+                .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(post_audit_service.prune().unwrap(), 2);
+    }
+}
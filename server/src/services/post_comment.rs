@@ -0,0 +1,420 @@
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, FieldError, ServiceError};
+use crate::models::post_comment::*;
+use crate::models::post_share::*;
+use crate::services::post_share::check_readable;
+use crate::utils::clock_util::{Clock, SystemClock};
+
+/// Maximum length of comments in bytes.
+const MAX_COMMENT_LENGTH: usize = 2000;
+
+pub struct PostCommentService {
+    post_comment_repository: Option<PostCommentRepository>,
+    post_share_repository: Option<PostShareRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PostCommentService {
+    pub fn new() -> Self {
+        Self {
+            post_comment_repository: None,
+            post_share_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn post_comment_repository(
+        &mut self,
+        new_repository: Option<PostCommentRepository>,
+    ) -> &PostCommentRepository {
+        match new_repository {
+            Some(_) => {
+                self.post_comment_repository = new_repository;
+                self.post_comment_repository.as_ref().unwrap()
+            }
+            None => self.post_comment_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn post_share_repository(
+        &mut self,
+        new_repository: Option<PostShareRepository>,
+    ) -> &PostShareRepository {
+        match new_repository {
+            Some(_) => {
+                self.post_share_repository = new_repository;
+                self.post_share_repository.as_ref().unwrap()
+            }
+            None => self.post_share_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Checks that a comment is neither blank nor longer than `MAX_COMMENT_LENGTH`.
+    fn check_content(content: &str) -> Result<(), ServiceError> {
+        let message = if content.trim().is_empty() {
+            String::from("must not be blank")
+        } else if content.len() > MAX_COMMENT_LENGTH {
+            format!("must be at most {} bytes", MAX_COMMENT_LENGTH)
+        } else {
+            return Ok(());
+        };
+
+        Err(get_service_error(ServiceError::InvalidFields(vec![
+            FieldError {
+                field: String::from("content"),
+                message,
+            },
+        ])))
+    }
+
+    /// Finds the id of the post shared by `token`, which the share lets `passphrase` read.
+    fn find_shared_post_id(
+        &mut self,
+        token: &str,
+        passphrase: &Option<String>,
+    ) -> Result<u64, ServiceError> {
+        let (share, _) = {
+            let fallback_repository =
+                some_if_true!(self.post_share_repository.is_none() => PostShareRepository::new());
+            self.post_share_repository(fallback_repository)
+                .find_by_token(token)?
+        };
+
+        check_readable(&share, passphrase, &self.clock.now().naive_utc())?;
+        Ok(share.post_id)
+    }
+
+    /// Checks that a post is written by specific user, who can read and write all its comments.
+    fn check_post_owned(&mut self, user_id: u64, post_id: u64) -> Result<(), ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_comment_repository.is_none() => PostCommentRepository::new());
+        let is_owned = self
+            .post_comment_repository(fallback_repository)
+            .is_post_owned(user_id, post_id)?;
+
+        if is_owned {
+            Ok(())
+        } else {
+            Err(get_service_error(ServiceError::NotFound(
+                post_id.to_string(),
+            )))
+        }
+    }
+
+    /// Finds comments of a post.
+    fn find_comments(
+        &mut self,
+        user_id: u64,
+        post_id: u64,
+    ) -> Result<Vec<PostCommentDTO>, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_comment_repository.is_none() => PostCommentRepository::new());
+        let comment_list = self
+            .post_comment_repository(fallback_repository)
+            .find_by_post_id(post_id)?;
+
+        Ok(comment_list
+            .into_iter()
+            .map(|(comment, writer_name)| PostCommentDTO {
+                id: comment.id,
+                writer_name,
+                content: comment.content,
+                created_at: comment.created_at,
+                is_mine: comment.user_id == user_id,
+            })
+            .collect())
+    }
+
+    /// Creates a comment on a post, and returns id of the created comment.
+    fn create_comment(
+        &mut self,
+        user_id: u64,
+        post_id: u64,
+        content: &str,
+    ) -> Result<u64, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_comment_repository.is_none() => PostCommentRepository::new());
+        self.post_comment_repository(fallback_repository)
+            .create(post_id, user_id, content)
+    }
+
+    /// Finds comments of a post written by specific user.
+    pub fn get_list(
+        &mut self,
+        user_id: u64,
+        post_id: u64,
+    ) -> Result<Vec<PostCommentDTO>, ServiceError> {
+        self.check_post_owned(user_id, post_id)?;
+        self.find_comments(user_id, post_id)
+    }
+
+    /// Finds comments of a post shared by `token`, for specific user reading the share.
+    ///
+    /// Expired shares are not found, and shares protected by a passphrase require `passphrase`.
+    pub fn get_shared_list(
+        &mut self,
+        user_id: u64,
+        token: &str,
+        passphrase: &Option<String>,
+    ) -> Result<Vec<PostCommentDTO>, ServiceError> {
+        let post_id = self.find_shared_post_id(token, passphrase)?;
+        self.find_comments(user_id, post_id)
+    }
+
+    /// Creates a comment of specific user on a post written by the user,
+    /// and returns id of the created comment.
+    pub fn create(
+        &mut self,
+        user_id: u64,
+        post_id: u64,
+        content: &str,
+    ) -> Result<u64, ServiceError> {
+        Self::check_content(content)?;
+        self.check_post_owned(user_id, post_id)?;
+        self.create_comment(user_id, post_id, content)
+    }
+
+    /// Creates a comment of specific user on a post shared by `token`,
+    /// and returns id of the created comment.
+    ///
+    /// Only users who can read the share can comment on it.
+    pub fn create_shared(
+        &mut self,
+        user_id: u64,
+        token: &str,
+        passphrase: &Option<String>,
+        content: &str,
+    ) -> Result<u64, ServiceError> {
+        Self::check_content(content)?;
+        let post_id = self.find_shared_post_id(token, passphrase)?;
+        self.create_comment(user_id, post_id, content)
+    }
+
+    /// Deletes a comment, which only its writer or the writer of the post can delete.
+    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_comment_repository.is_none() => PostCommentRepository::new());
+        self.post_comment_repository(fallback_repository)
+            .delete(id, user_id)
+    }
+}
+
+impl Default for PostCommentService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::post_comment::MockPostCommentRepositoryTrait as PostCommentRepository;
+#[cfg(test)]
+use crate::models::post_share::MockPostShareRepositoryTrait as PostShareRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::post::Post;
+    use crate::models::post_comment::MockPostCommentRepositoryTrait;
+    use crate::models::post_share::MockPostShareRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl PostCommentService {
+        pub fn new_with_repository(
+            post_comment_repository: PostCommentRepository,
+            post_share_repository: PostShareRepository,
+        ) -> Self {
+            Self {
+                post_comment_repository: Some(post_comment_repository),
+                post_share_repository: Some(post_share_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    fn share_and_post(expires_at: Option<NaiveDateTime>) -> (PostShare, Post) {
+        let created_at = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0).naive_utc();
+        let share = PostShare {
+            id: 1,
+            user_id: 5,
+            post_id: 3,
+            token: String::from("a1b2c3"),
+            passphrase: None,
+            expires_at,
+            created_at,
+            title: None,
+            content: None,
+        };
+        let post = Post {
+            id: 3,
+            user_id: 5,
+            title: String::from("Lorem ipsum"),
+            content: String::from("Lorem ipsum dolor sit amet"),
+            date: created_at,
+            date_offset: Some(32400),
+            intra_day_order: 0,
+            created_at,
+            updated_at: None,
+            version: 1,
+            deleted_at: None,
+            status: String::from("published"),
+            autosave_started_at: None,
+            changed_at: created_at,
+            is_favorite: false,
+            mood: None,
+            weather: None,
+            latitude: None,
+            longitude: None,
+            place_name: None,
+            journal_id: 1,
+            word_count: None,
+            is_locked: false,
+            is_encrypted: true,
+            encryption_scheme: None,
+            encryption_nonce: None,
+            key_version: None,
+        };
+        (share, post)
+    }
+
+    fn comment(id: u64, user_id: u64) -> (PostComment, String) {
+        let comment = PostComment {
+            id,
+            post_id: 3,
+            user_id,
+            content: String::from("Lovely day"),
+            created_at: Utc.ymd(2020, 4, 13).and_hms(9, 0, 0).naive_utc(),
+        };
+        (comment, format!("User {}", user_id))
+    }
+
+    #[test]
+    fn test_get_list() {
+        let mut mocked_post_comment_repository = MockPostCommentRepositoryTrait::new();
+        mocked_post_comment_repository
+            .expect_is_post_owned()
+            .with(eq(5), eq(3))
+            .times(1)
+            .returning(|_, _| Ok(true));
+        mocked_post_comment_repository
+            .expect_find_by_post_id()
+            .with(eq(3))
+            .times(1)
+            .returning(|_| Ok(vec![comment(1, 5), comment(2, 7)]));
+
+        let mut post_comment_service = PostCommentService::new_with_repository(
+            mocked_post_comment_repository,
+            MockPostShareRepositoryTrait::new(),
+        );
+        let comment_list = post_comment_service.get_list(5, 3).unwrap();
+
+        assert_eq!(comment_list.len(), 2);
+        assert!(comment_list[0].is_mine);
+        assert!(!comment_list[1].is_mine);
+        assert_eq!(comment_list[1].writer_name, "User 7");
+    }
+
+    #[test]
+    fn test_get_list_of_post_not_owned() {
+        let mut mocked_post_comment_repository = MockPostCommentRepositoryTrait::new();
+        mocked_post_comment_repository
+            .expect_is_post_owned()
+            .times(1)
+            .returning(|_, _| Ok(false));
+        mocked_post_comment_repository
+            .expect_find_by_post_id()
+            .times(0);
+
+        let mut post_comment_service = PostCommentService::new_with_repository(
+            mocked_post_comment_repository,
+            MockPostShareRepositoryTrait::new(),
+        );
+
+        assert!(matches!(
+            post_comment_service.get_list(7, 3),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_shared() {
+        let mut mocked_post_comment_repository = MockPostCommentRepositoryTrait::new();
+        let mut mocked_post_share_repository = MockPostShareRepositoryTrait::new();
+        mocked_post_share_repository
+            .expect_find_by_token()
+            .with(eq("a1b2c3"))
+            .times(1)
+            .returning(|_| Ok(share_and_post(None)));
+        mocked_post_comment_repository
+            .expect_create()
+            .with(eq(3), eq(7), eq("Lovely day"))
+            .times(1)
+            .returning(|_, _, _| Ok(1));
+
+        let mut post_comment_service = PostCommentService::new_with_repository(
+            mocked_post_comment_repository,
+            mocked_post_share_repository,
+        );
+
+        assert_eq!(
+            post_comment_service
+                .create_shared(7, "a1b2c3", &None, "Lovely day")
+                .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_create_shared_with_expired_share() {
+        let mut mocked_post_comment_repository = MockPostCommentRepositoryTrait::new();
+        let mut mocked_post_share_repository = MockPostShareRepositoryTrait::new();
+
+        let now = Utc.ymd(2020, 4, 20).and_hms(9, 0, 0);
+        let expires_at = now.naive_utc() - Duration::days(1);
+
+        mocked_post_share_repository
+            .expect_find_by_token()
+            .times(1)
+            .returning(move |_| Ok(share_and_post(Some(expires_at))));
+        mocked_post_comment_repository.expect_create().times(0);
+
+        let mut post_comment_service = PostCommentService::new_with_repository(
+            mocked_post_comment_repository,
+            mocked_post_share_repository,
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert!(matches!(
+            post_comment_service.create_shared(7, "a1b2c3", &None, "Lovely day"),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_create_with_invalid_content() {
+        let mut mocked_post_comment_repository = MockPostCommentRepositoryTrait::new();
+        mocked_post_comment_repository.expect_create().times(0);
+
+        let mut post_comment_service = PostCommentService::new_with_repository(
+            mocked_post_comment_repository,
+            MockPostShareRepositoryTrait::new(),
+        );
+
+        for content in &[String::from("  "), "a".repeat(MAX_COMMENT_LENGTH + 1)] {
+            match post_comment_service.create(5, 3, content) {
+                Err(ServiceError::InvalidFields(field_errors)) => {
+                    assert_eq!(field_errors[0].field, "content")
+                }
+                _ => panic!("content `{}` must be invalid", content),
+            }
+        }
+    }
+}
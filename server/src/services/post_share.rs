@@ -0,0 +1,438 @@
+use chrono::{DateTime, NaiveDateTime};
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::post_audit::AuditContext;
+use crate::models::post_share::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::password_util;
+use crate::utils::url_util::PublicUrl;
+
+/// Length of tokens of shares, which is long enough not to be guessed.
+const SHARE_TOKEN_LENGTH: usize = 32;
+
+/// Returns an error unless a share can be read at `now` with `passphrase`.
+///
+/// Expired shares are not found, and shares protected by a passphrase require `passphrase`.
+pub fn check_readable(
+    share: &PostShare,
+    passphrase: &Option<String>,
+    now: &NaiveDateTime,
+) -> Result<(), ServiceError> {
+    if let Some(expires_at) = share.expires_at {
+        if expires_at <= *now {
+            return Err(get_service_error(ServiceError::NotFound(String::from(
+                "share",
+            ))));
+        }
+    }
+
+    if let Some(hashed_passphrase) = &share.passphrase {
+        let is_valid = passphrase
+            .as_ref()
+            .map(|passphrase| password_util::check_password(passphrase, hashed_passphrase))
+            .unwrap_or(false);
+        if !is_valid {
+            return Err(get_service_error(ServiceError::Unauthorized));
+        }
+    }
+    Ok(())
+}
+
+pub struct PostShareService {
+    post_share_repository: Option<PostShareRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PostShareService {
+    pub fn new() -> Self {
+        Self {
+            post_share_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn post_share_repository(
+        &mut self,
+        new_repository: Option<PostShareRepository>,
+    ) -> &PostShareRepository {
+        match new_repository {
+            Some(_) => {
+                self.post_share_repository = new_repository;
+                self.post_share_repository.as_ref().unwrap()
+            }
+            None => self.post_share_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds a post shared by `token`.
+    ///
+    /// Expired shares are not found, and shares protected by a passphrase require `passphrase`.
+    /// Title and content are the plaintext copies given by the writer if the share has them.
+    pub fn get(
+        &mut self,
+        token: &str,
+        passphrase: &Option<String>,
+    ) -> Result<SharedPostDTO, ServiceError> {
+        let (share, post) = {
+            let fallback_repository =
+                some_if_true!(self.post_share_repository.is_none() => PostShareRepository::new());
+            self.post_share_repository(fallback_repository)
+                .find_by_token(token)?
+        };
+
+        check_readable(&share, passphrase, &self.clock.now().naive_utc())?;
+
+        let date = post.post_date().to_rfc3339();
+        let (title, content, is_plaintext) = match (share.title, share.content) {
+            (Some(title), Some(content)) => (title, content, true),
+            _ => (post.title, post.content, false),
+        };
+        Ok(SharedPostDTO {
+            title,
+            content,
+            date,
+            mood: post.mood,
+            weather: post.weather,
+            created_at: post.created_at,
+            updated_at: post.updated_at,
+            is_plaintext,
+        })
+    }
+
+    /// Shares a post written by specific user, and returns the share with a new token.
+    ///
+    /// The previous share of the post is revoked. `expires_at` is an RFC 3339 datetime
+    /// in the future, and the share never expires without it. `title` and `content` are
+    /// plaintext copies of the post for readers without the key, given together or not at all.
+    pub fn share(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        expires_at: &Option<String>,
+        passphrase: &Option<String>,
+        title: &Option<String>,
+        content: &Option<String>,
+        context: &AuditContext,
+    ) -> Result<PostShareDTO, ServiceError> {
+        if title.is_some() != content.is_some() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let expires_at = match expires_at {
+            Some(expires_at) => match DateTime::parse_from_rfc3339(expires_at) {
+                Ok(expires_at) => Some(expires_at.naive_utc()),
+                Err(_) => return Err(get_service_error(ServiceError::InvalidFormat)),
+            },
+            None => None,
+        };
+        if let Some(expires_at) = expires_at {
+            if expires_at <= self.clock.now().naive_utc() {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+
+        let hashed_passphrase = match passphrase {
+            Some(passphrase) if passphrase.is_empty() => {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+            Some(passphrase) => Some(password_util::get_hashed_password(passphrase)),
+            None => None,
+        };
+
+        let token: String = thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(SHARE_TOKEN_LENGTH)
+            .collect();
+
+        {
+            let fallback_repository =
+                some_if_true!(self.post_share_repository.is_none() => PostShareRepository::new());
+            self.post_share_repository(fallback_repository).create(
+                user_id,
+                id,
+                &token,
+                &hashed_passphrase,
+                &expires_at,
+                title,
+                content,
+                context,
+            )?;
+        }
+
+        let url = PublicUrl::from_env()
+            .expect("Invalid PUBLIC_BASE_URL")
+            .share_post_url(&token);
+        Ok(PostShareDTO {
+            token,
+            url,
+            expires_at,
+            has_passphrase: hashed_passphrase.is_some(),
+        })
+    }
+
+    /// Revokes the share of a post written by specific user.
+    pub fn revoke(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        context: &AuditContext,
+    ) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.post_share_repository.is_none() => PostShareRepository::new());
+        self.post_share_repository(fallback_repository)
+            .delete(user_id, id, context)
+    }
+}
+
+impl Default for PostShareService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::post_share::MockPostShareRepositoryTrait as PostShareRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDateTime, TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::post::Post;
+    use crate::models::post_share::MockPostShareRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl PostShareService {
+        pub fn new_with_repository(post_share_repository: PostShareRepository) -> Self {
+            Self {
+                post_share_repository: Some(post_share_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    fn share_and_post(
+        passphrase: Option<&str>,
+        expires_at: Option<NaiveDateTime>,
+    ) -> (PostShare, Post) {
+        let created_at = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0).naive_utc();
+        let share = PostShare {
+            id: 1,
+            user_id: 5,
+            post_id: 3,
+            token: String::from("a1b2c3"),
+            passphrase: passphrase.map(password_util::get_hashed_password),
+            expires_at,
+            created_at,
+            title: None,
+            content: None,
+        };
+        let post = Post {
+            id: 3,
+            user_id: 5,
+            title: String::from("Lorem ipsum"),
+            content: String::from("Lorem ipsum dolor sit amet"),
+            date: created_at,
+            date_offset: Some(32400),
+            intra_day_order: 0,
+            created_at,
+            updated_at: None,
+            version: 1,
+            deleted_at: None,
+            status: String::from("published"),
+            autosave_started_at: None,
+            changed_at: created_at,
+            is_favorite: false,
+            mood: Some(4),
+            weather: None,
+            latitude: None,
+            longitude: None,
+            place_name: None,
+            journal_id: 1,
+            word_count: None,
+            is_locked: false,
+            is_encrypted: true,
+            encryption_scheme: None,
+            encryption_nonce: None,
+            key_version: None,
+        };
+        (share, post)
+    }
+
+    #[test]
+    fn test_get() {
+        let mut mocked_post_share_repository = MockPostShareRepositoryTrait::new();
+        mocked_post_share_repository
+            .expect_find_by_token()
+            .with(eq("a1b2c3"))
+            .times(1)
+            .returning(|_| Ok(share_and_post(None, None)));
+
+        let mut post_share_service =
+            PostShareService::new_with_repository(mocked_post_share_repository);
+        let shared_post = post_share_service.get("a1b2c3", &None).unwrap();
+
+        assert_eq!(shared_post.title, "Lorem ipsum");
+        assert_eq!(shared_post.date, "2020-04-12T18:00:00+09:00");
+        assert_eq!(shared_post.mood, Some(4));
+        assert!(!shared_post.is_plaintext);
+    }
+
+    #[test]
+    fn test_get_plaintext_copy() {
+        let mut mocked_post_share_repository = MockPostShareRepositoryTrait::new();
+        mocked_post_share_repository
+            .expect_find_by_token()
+            .times(1)
+            .returning(|_| {
+                let (mut share, post) = share_and_post(None, None);
+                share.title = Some(String::from("Plain title"));
+                share.content = Some(String::from("**Plain** content"));
+                Ok((share, post))
+            });
+
+        let mut post_share_service =
+            PostShareService::new_with_repository(mocked_post_share_repository);
+        let shared_post = post_share_service.get("a1b2c3", &None).unwrap();
+
+        assert_eq!(shared_post.title, "Plain title");
+        assert_eq!(shared_post.content, "**Plain** content");
+        assert!(shared_post.is_plaintext);
+    }
+
+    #[test]
+    fn test_get_with_passphrase() {
+        let mut mocked_post_share_repository = MockPostShareRepositoryTrait::new();
+        mocked_post_share_repository
+            .expect_find_by_token()
+            .times(3)
+            .returning(|_| Ok(share_and_post(Some("open sesame"), None)));
+
+        let mut post_share_service =
+            PostShareService::new_with_repository(mocked_post_share_repository);
+
+        assert!(matches!(
+            post_share_service.get("a1b2c3", &None),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(matches!(
+            post_share_service.get("a1b2c3", &Some(String::from("open"))),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(post_share_service
+            .get("a1b2c3", &Some(String::from("open sesame")))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_get_expired() {
+        let now = Utc.ymd(2020, 4, 13).and_hms(9, 0, 0);
+        let expires_at = (now + Duration::hours(1)).naive_utc();
+
+        let mut mocked_post_share_repository = MockPostShareRepositoryTrait::new();
+        mocked_post_share_repository
+            .expect_find_by_token()
+            .times(2)
+            .returning(move |_| Ok(share_and_post(None, Some(expires_at))));
+
+        let clock = Arc::new(TestClock::new(now));
+        let mut post_share_service =
+            PostShareService::new_with_repository(mocked_post_share_repository)
+                .with_clock(clock.clone());
+
+        assert!(post_share_service.get("a1b2c3", &None).is_ok());
+        clock.advance(Duration::hours(1));
+        assert!(matches!(
+            post_share_service.get("a1b2c3", &None),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_share_with_invalid_args() {
+        let mut mocked_post_share_repository = MockPostShareRepositoryTrait::new();
+        mocked_post_share_repository.expect_create().times(0);
+
+        let now = Utc.ymd(2020, 4, 13).and_hms(9, 0, 0);
+        let mut post_share_service =
+            PostShareService::new_with_repository(mocked_post_share_repository)
+                .with_clock(Arc::new(TestClock::new(now)));
+
+        assert!(post_share_service
+            .share(
+                3,
+                5,
+                &Some(String::from("2020-04-13T08:00:00Z")),
+                &None,
+                &None,
+                &None,
+                &AuditContext::default()
+            )
+            .is_err());
+        assert!(post_share_service
+            .share(
+                3,
+                5,
+                &Some(String::from("tomorrow")),
+                &None,
+                &None,
+                &None,
+                &AuditContext::default()
+            )
+            .is_err());
+        assert!(post_share_service
+            .share(
+                3,
+                5,
+                &None,
+                &Some(String::new()),
+                &None,
+                &None,
+                &AuditContext::default()
+            )
+            .is_err());
+        assert!(post_share_service
+            .share(
+                3,
+                5,
+                &None,
+                &None,
+                &None,
+                &Some(String::from("content")),
+                &AuditContext::default()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_revoke() {
+        let mut mocked_post_share_repository = MockPostShareRepositoryTrait::new();
+        mocked_post_share_repository
+            .expect_delete()
+            .with(
+                eq(5),
+                eq(3),
+                function(|context: &AuditContext| context.ip == Some(String::from("127.0.0.1"))),
+            )
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+
+        let context = AuditContext {
+            ip: Some(String::from("127.0.0.1")),
+            ..AuditContext::default()
+        };
+        let mut post_share_service =
+            PostShareService::new_with_repository(mocked_post_share_repository);
+
+        // The repository appends `unshare` entry with the context in the same transaction.
+        assert!(post_share_service.revoke(3, 5, &context).unwrap());
+    }
+}
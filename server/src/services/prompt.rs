@@ -0,0 +1,320 @@
+use chrono::{Datelike, Duration, NaiveDate};
+use std::sync::Arc;
+
+use crate::models::email_job::*;
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::prompt::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::html_util;
+use crate::utils::unsubscribe_util::{self, EmailCategory};
+use crate::utils::url_util::PublicUrl;
+
+/// Days the unsubscribe link in a prompt email works, which is long enough for an old email.
+const UNSUBSCRIBE_TOKEN_TTL_DAYS: i64 = 90;
+
+pub struct PromptService {
+    prompt_repository: Option<PromptRepository>,
+    email_job_repository: Option<EmailJobRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl PromptService {
+    pub fn new() -> Self {
+        Self {
+            prompt_repository: None,
+            email_job_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn prompt_repository(&mut self, new_repository: Option<PromptRepository>) -> &PromptRepository {
+        match new_repository {
+            Some(_) => {
+                self.prompt_repository = new_repository;
+                self.prompt_repository.as_ref().unwrap()
+            }
+            None => self.prompt_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn email_job_repository(
+        &mut self,
+        new_repository: Option<EmailJobRepository>,
+    ) -> &EmailJobRepository {
+        match new_repository {
+            Some(_) => {
+                self.email_job_repository = new_repository;
+                self.email_job_repository.as_ref().unwrap()
+            }
+            None => self.email_job_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Parses a date in `YYYY-MM-DD` format, and returns today in UTC without it.
+    fn parse_date(&self, date: &Option<String>) -> Result<NaiveDate, ServiceError> {
+        match date {
+            Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d")
+                .map_err(|_| get_service_error(ServiceError::InvalidFormat)),
+            None => Ok(self.clock.now().naive_utc().date()),
+        }
+    }
+
+    /// Returns the offset of the prompt served on `date` among `count` prompts.
+    ///
+    /// Prompts rotate one by one every day, so every user is served the same prompt on a date.
+    fn get_offset(date: &NaiveDate, count: i64) -> i64 {
+        i64::from(date.num_days_from_ce()).rem_euclid(count)
+    }
+
+    /// Finds the prompt served on a date.
+    ///
+    /// `date` is the local date of the client, and it is today in UTC by default.
+    pub fn get_today(&mut self, date: &Option<String>) -> Result<PromptDTO, ServiceError> {
+        let date = self.parse_date(date)?;
+
+        let fallback_repository =
+            some_if_true!(self.prompt_repository.is_none() => PromptRepository::new());
+        let prompt_repository = self.prompt_repository(fallback_repository);
+
+        let count = prompt_repository.count()?;
+        if count == 0 {
+            return Err(get_service_error(ServiceError::NotFound(String::from(
+                "prompt",
+            ))));
+        }
+        let prompt = prompt_repository.find_nth(Self::get_offset(&date, count))?;
+
+        Ok(PromptDTO {
+            id: prompt.id,
+            text: prompt.text,
+            date,
+        })
+    }
+
+    /// Subscribes specific user to daily prompt emails.
+    ///
+    /// Subscribing again keeps the subscription as it is.
+    pub fn subscribe(&mut self, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.prompt_repository.is_none() => PromptRepository::new());
+        self.prompt_repository(fallback_repository)
+            .subscribe(user_id)?;
+        Ok(true)
+    }
+
+    /// Unsubscribes specific user from daily prompt emails.
+    pub fn unsubscribe(&mut self, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.prompt_repository.is_none() => PromptRepository::new());
+        self.prompt_repository(fallback_repository)
+            .unsubscribe(user_id)
+    }
+
+    /// Enqueues the prompt of today to subscribers who have not received it,
+    /// and returns the number of enqueued emails.
+    ///
+    /// Today is the date in UTC, so each subscriber receives a prompt a day at most.
+    /// Each email has a signed link unsubscribing its recipient, also in `List-Unsubscribe` header.
+    pub fn send_daily_prompts(&mut self) -> Result<usize, ServiceError> {
+        let now = self.clock.now().naive_utc();
+        let today = now.date();
+        let prompt = match self.get_today(&Some(today.format("%Y-%m-%d").to_string())) {
+            Ok(prompt) => prompt,
+            Err(ServiceError::NotFound(_)) => return Ok(0),
+            Err(error) => return Err(error),
+        };
+        let recipient_list = self
+            .prompt_repository
+            .as_ref()
+            .unwrap()
+            .find_recipients(&today)?;
+        if recipient_list.is_empty() {
+            return Ok(0);
+        }
+
+        let api_url = PublicUrl::api_from_env().expect("Invalid PUBLIC_API_BASE_URL");
+        let secret = unsubscribe_util::get_secret();
+        let expires_at = now + Duration::days(UNSUBSCRIBE_TOKEN_TTL_DAYS);
+        let fallback_repository =
+            some_if_true!(self.email_job_repository.is_none() => EmailJobRepository::new());
+        self.email_job_repository(fallback_repository);
+
+        let mut count = 0;
+        for recipient in recipient_list {
+            let token = unsubscribe_util::issue_token(
+                &secret,
+                recipient.user_id,
+                EmailCategory::Prompt,
+                &expires_at,
+            );
+            let unsubscribe_url = api_url.unsubscribe_url(&token);
+            let email_content = format!(
+                "Hello {} :)<br/><br/>\
+                Here is a prompt for today:<br/><br/>\
+                <div style=\"background-color: #f0f0f0; padding: 10px; font-size: 20px\">{}</div><br/><br/>\
+                <a href=\"{}\">Unsubscribe from daily prompts</a>",
+                html_util::escape(&recipient.name),
+                html_util::escape(&prompt.text),
+                unsubscribe_url,
+            );
+
+            self.email_job_repository.as_ref().unwrap().create(
+                &format!("{} <{}>", recipient.name, recipient.email),
+                "Today's writing prompt ✍️",
+                &email_content,
+                &None,
+                &Some(unsubscribe_url),
+            )?;
+            self.prompt_repository
+                .as_ref()
+                .unwrap()
+                .mark_sent(recipient.user_id, &today)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl Default for PromptService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::email_job::MockEmailJobRepositoryTrait as EmailJobRepository;
+#[cfg(test)]
+use crate::models::prompt::MockPromptRepositoryTrait as PromptRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+    use std::env;
+
+    use super::*;
+    use crate::models::email_job::MockEmailJobRepositoryTrait;
+    use crate::models::prompt::MockPromptRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl PromptService {
+        pub fn new_with_repository(
+            prompt_repository: PromptRepository,
+            email_job_repository: EmailJobRepository,
+        ) -> Self {
+            Self {
+                prompt_repository: Some(prompt_repository),
+                email_job_repository: Some(email_job_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    fn prompt(offset: i64) -> Prompt {
+        Prompt {
+            id: offset as u64 + 1,
+            text: format!("Prompt {}", offset + 1),
+            created_at: Utc::now().naive_utc(),
+        }
+    }
+
+    #[test]
+    fn test_get_today() {
+        let mut mocked_prompt_repository = MockPromptRepositoryTrait::new();
+
+        mocked_prompt_repository
+            .expect_count()
+            .times(2)
+            .returning(|| Ok(7));
+        mocked_prompt_repository
+            .expect_find_nth()
+            .times(2)
+            .returning(|offset| Ok(prompt(offset)));
+
+        let mut prompt_service = PromptService::new_with_repository(
+            mocked_prompt_repository,
+            MockEmailJobRepositoryTrait::new(),
+        );
+
+        let today = prompt_service
+            .get_today(&Some(String::from("2020-04-12")))
+            .unwrap();
+        let tomorrow = prompt_service
+            .get_today(&Some(String::from("2020-04-13")))
+            .unwrap();
+        assert_eq!(today.date, NaiveDate::from_ymd(2020, 4, 12));
+        assert_eq!(tomorrow.id % 7, (today.id + 1) % 7);
+
+        assert!(prompt_service
+            .get_today(&Some(String::from("yesterday")))
+            .is_err());
+    }
+
+    #[test]
+    fn test_send_daily_prompts() {
+        let mut mocked_prompt_repository = MockPromptRepositoryTrait::new();
+        let mut mocked_email_job_repository = MockEmailJobRepositoryTrait::new();
+
+        let now = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0);
+        let today = now.naive_utc().date();
+
+        mocked_prompt_repository
+            .expect_count()
+            .times(1)
+            .returning(|| Ok(7));
+        mocked_prompt_repository
+            .expect_find_nth()
+            .times(1)
+            .returning(|offset| Ok(prompt(offset)));
+        mocked_prompt_repository
+            .expect_find_recipients()
+            .with(eq(today))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![PromptRecipient {
+                    user_id: 5,
+                    name: String::from("Park"),
+                    email: String::from("park@example.com"),
+                }])
+            });
+        mocked_prompt_repository
+            .expect_mark_sent()
+            .with(eq(5), eq(today))
+            .times(1)
+            .returning(|_, _| Ok(true));
+        let token = unsubscribe_util::issue_token(
+            "s3cr3t",
+            5,
+            EmailCategory::Prompt,
+            &(now.naive_utc() + Duration::days(UNSUBSCRIBE_TOKEN_TTL_DAYS)),
+        );
+        let unsubscribe_url = format!("https://api.darim.app/unsubscribe/{}", token);
+        let linked_url = unsubscribe_url.clone();
+        mocked_email_job_repository
+            .expect_create()
+            .with(
+                eq("Park <park@example.com>"),
+                always(),
+                function(move |body: &str| body.contains(&linked_url)),
+                eq(None),
+                eq(Some(unsubscribe_url)),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _| Ok(true));
+
+        env::set_var("PUBLIC_API_BASE_URL", "https://api.darim.app");
+        env::set_var("UNSUBSCRIBE_SECRET", "s3cr3t");
+        let mut prompt_service = PromptService::new_with_repository(
+            mocked_prompt_repository,
+            mocked_email_job_repository,
+        )
+        .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(prompt_service.send_daily_prompts().unwrap(), 1);
+    }
+}
@@ -0,0 +1,323 @@
+use actix_web::rt;
+use chrono::{Duration, NaiveDateTime};
+use std::sync::Arc;
+
+use crate::models::error::ServiceError;
+use crate::models::scheduled_task::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+
+/// Seconds between checks for due tasks.
+const TICK_SECONDS: u64 = 60;
+
+/// Minutes a task stays locked while running.
+const LOCK_LEASE_MINUTES: i64 = 30;
+
+/// Maximum length of the status recorded in `scheduled_tasks` table.
+const MAX_STATUS_LENGTH: usize = 255;
+
+/// A periodic task run by the scheduler.
+pub struct Task {
+    pub name: &'static str,
+    pub interval: Duration,
+    /// Runs the task and returns the count of rows it affected, such as the rows reaped.
+    pub handler: fn() -> Result<usize, ServiceError>,
+}
+
+pub struct SchedulerService {
+    tasks: Vec<Task>,
+    scheduled_task_repository: Option<ScheduledTaskRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl SchedulerService {
+    pub fn new() -> Self {
+        Self {
+            tasks: Vec::new(),
+            scheduled_task_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn scheduled_task_repository(
+        &mut self,
+        new_repository: Option<ScheduledTaskRepository>,
+    ) -> &ScheduledTaskRepository {
+        match new_repository {
+            Some(_) => {
+                self.scheduled_task_repository = new_repository;
+                self.scheduled_task_repository.as_ref().unwrap()
+            }
+            None => self.scheduled_task_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Returns whether a task that last ran at `last_run_at` is due at `now`.
+    fn is_due(last_run_at: &Option<NaiveDateTime>, interval: Duration, now: NaiveDateTime) -> bool {
+        match last_run_at {
+            Some(last_run_at) => now - *last_run_at >= interval,
+            None => true,
+        }
+    }
+
+    /// Registers a task that runs every `interval`.
+    pub fn register(
+        &mut self,
+        name: &'static str,
+        interval: Duration,
+        handler: fn() -> Result<usize, ServiceError>,
+    ) {
+        self.tasks.push(Task {
+            name,
+            interval,
+            handler,
+        });
+    }
+
+    /// Runs registered tasks that are due, and returns names of the tasks that ran.
+    ///
+    /// The count returned by each task is recorded in its status, such as `ok: 3 rows`.
+    /// Last-run times are read from `scheduled_tasks` table, so restarting the server
+    /// neither runs a task again nor skips it. A task is locked while running,
+    /// so that it never runs concurrently.
+    pub fn run_due_tasks(&mut self) -> Result<Vec<&'static str>, ServiceError> {
+        let fallback_repository = some_if_true!(self.scheduled_task_repository.is_none() => ScheduledTaskRepository::new());
+        self.scheduled_task_repository(fallback_repository);
+        let scheduled_task_repository = self.scheduled_task_repository.as_ref().unwrap();
+
+        for task in &self.tasks {
+            scheduled_task_repository.create_if_not_exists(task.name)?;
+        }
+
+        let task_list = scheduled_task_repository.find_all()?;
+        let mut ran_tasks = Vec::new();
+        for task in &self.tasks {
+            let now = self.clock.now().naive_utc();
+            let last_run_at = task_list
+                .iter()
+                .find(|scheduled_task| scheduled_task.name == task.name)
+                .and_then(|scheduled_task| scheduled_task.last_run_at);
+
+            if !Self::is_due(&last_run_at, task.interval, now)
+                || !scheduled_task_repository.lock(
+                    task.name,
+                    &last_run_at,
+                    &now,
+                    Duration::minutes(LOCK_LEASE_MINUTES),
+                )?
+            {
+                continue;
+            }
+
+            let status = match (task.handler)() {
+                Ok(count) => format!("ok: {} rows", count),
+                Err(error) => format!("failed: {}", error)
+                    .chars()
+                    .take(MAX_STATUS_LENGTH)
+                    .collect(),
+            };
+            scheduled_task_repository.unlock(task.name, &now, &status)?;
+            ran_tasks.push(task.name);
+        }
+
+        Ok(ran_tasks)
+    }
+
+    /// Finds the last run of all scheduled tasks.
+    pub fn get_list(&mut self) -> Result<Vec<ScheduledTaskDTO>, ServiceError> {
+        let task_list = {
+            let fallback_repository = some_if_true!(self.scheduled_task_repository.is_none() => ScheduledTaskRepository::new());
+            self.scheduled_task_repository(fallback_repository)
+                .find_all()?
+        };
+
+        let now = self.clock.now().naive_utc();
+        Ok(task_list
+            .into_iter()
+            .map(|task| ScheduledTaskDTO {
+                running: task
+                    .locked_until
+                    .map(|locked_until| locked_until > now)
+                    .unwrap_or(false),
+                name: task.name,
+                last_run_at: task.last_run_at,
+                last_status: task.last_status,
+            })
+            .collect())
+    }
+
+    /// Spawns the scheduler on the actix system.
+    ///
+    /// The scheduler stops with the system on shutdown. A task running at that moment
+    /// finishes, and a task killed in the middle stays locked until the lock expires.
+    pub fn spawn(mut self) {
+        rt::spawn(async move {
+            let mut interval = rt::time::interval(std::time::Duration::from_secs(TICK_SECONDS));
+            loop {
+                interval.tick().await;
+                // Connects again on every tick, so that a dropped connection does not stop the scheduler.
+                self.scheduled_task_repository = None;
+                let _ = self.run_due_tasks();
+            }
+        });
+    }
+}
+
+impl Default for SchedulerService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::scheduled_task::MockScheduledTaskRepositoryTrait as ScheduledTaskRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::scheduled_task::MockScheduledTaskRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl SchedulerService {
+        pub fn new_with_repository(scheduled_task_repository: ScheduledTaskRepository) -> Self {
+            Self {
+                tasks: Vec::new(),
+                scheduled_task_repository: Some(scheduled_task_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    #[test]
+    fn test_run_due_tasks() {
+        let mut mocked_scheduled_task_repository = MockScheduledTaskRepositoryTrait::new();
+
+        let now = Utc.ymd(2026, 10, 15).and_hms(9, 0, 0);
+        let last_run_at = (now - Duration::minutes(10)).naive_utc();
+
+        mocked_scheduled_task_repository
+            .expect_create_if_not_exists()
+            .times(2)
+            .returning(|_| Ok(false));
+        mocked_scheduled_task_repository
+            .expect_find_all()
+            .times(1)
+            .returning(move || {
+                Ok(vec![
+                    ScheduledTask {
+                        name: String::from("due"),
+                        last_run_at: Some(last_run_at),
+                        last_status: Some(String::from("ok")),
+                        locked_until: None,
+                    },
+                    ScheduledTask {
+                        name: String::from("not_due"),
+                        last_run_at: Some(last_run_at),
+                        last_status: Some(String::from("ok")),
+                        locked_until: None,
+                    },
+                ])
+            });
+        mocked_scheduled_task_repository
+            .expect_lock()
+            .with(
+                eq("due"),
+                eq(Some(last_run_at)),
+                eq(now.naive_utc()),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+        mocked_scheduled_task_repository
+            .expect_unlock()
+            .with(eq("due"), always(), eq("ok: 3 rows"))
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+
+        let mut scheduler_service =
+            SchedulerService::new_with_repository(mocked_scheduled_task_repository)
+                .with_clock(Arc::new(TestClock::new(now)));
+        scheduler_service.register("due", Duration::minutes(5), || Ok(3));
+        scheduler_service.register("not_due", Duration::hours(1), || Ok(0));
+
+        assert_eq!(scheduler_service.run_due_tasks().unwrap(), vec!["due"]);
+    }
+
+    #[test]
+    fn test_run_due_tasks_skips_locked_task() {
+        let mut mocked_scheduled_task_repository = MockScheduledTaskRepositoryTrait::new();
+
+        mocked_scheduled_task_repository
+            .expect_create_if_not_exists()
+            .returning(|_| Ok(true));
+        mocked_scheduled_task_repository
+            .expect_find_all()
+            .returning(|| Ok(vec![]));
+        mocked_scheduled_task_repository
+            .expect_lock()
+            .returning(|_, _, _, _| Ok(false));
+        mocked_scheduled_task_repository.expect_unlock().times(0);
+
+        let mut scheduler_service =
+            SchedulerService::new_with_repository(mocked_scheduled_task_repository);
+        scheduler_service.register("locked", Duration::minutes(5), || {
+            panic!("locked task must not run")
+        });
+
+        assert!(scheduler_service.run_due_tasks().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_run_due_tasks_after_interval() {
+        let mut mocked_scheduled_task_repository = MockScheduledTaskRepositoryTrait::new();
+
+        let now = Utc.ymd(2026, 10, 15).and_hms(9, 0, 0);
+        let clock = Arc::new(TestClock::new(now));
+        let last_run_at = (now - Duration::minutes(50)).naive_utc();
+
+        mocked_scheduled_task_repository
+            .expect_create_if_not_exists()
+            .returning(|_| Ok(false));
+        mocked_scheduled_task_repository
+            .expect_find_all()
+            .times(2)
+            .returning(move || {
+                Ok(vec![ScheduledTask {
+                    name: String::from("hourly"),
+                    last_run_at: Some(last_run_at),
+                    last_status: Some(String::from("ok")),
+                    locked_until: None,
+                }])
+            });
+        mocked_scheduled_task_repository
+            .expect_lock()
+            .with(
+                eq("hourly"),
+                eq(Some(last_run_at)),
+                eq((now + Duration::minutes(10)).naive_utc()),
+                always(),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+        mocked_scheduled_task_repository
+            .expect_unlock()
+            .times(1)
+            .returning(|_, _, _| Ok(true));
+
+        let mut scheduler_service =
+            SchedulerService::new_with_repository(mocked_scheduled_task_repository)
+                .with_clock(clock.clone());
+        scheduler_service.register("hourly", Duration::hours(1), || Ok(0));
+
+        assert!(scheduler_service.run_due_tasks().unwrap().is_empty());
+        clock.advance(Duration::minutes(10));
+        assert_eq!(scheduler_service.run_due_tasks().unwrap(), vec!["hourly"]);
+    }
+}
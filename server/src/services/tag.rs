@@ -0,0 +1,196 @@
+use std::cmp::Reverse;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::tag::*;
+
+pub struct TagService {
+    tag_repository: Option<TagRepository>,
+}
+
+impl TagService {
+    pub fn new() -> Self {
+        Self {
+            tag_repository: None,
+        }
+    }
+
+    fn tag_repository(&mut self, new_repository: Option<TagRepository>) -> &TagRepository {
+        match new_repository {
+            Some(_) => {
+                self.tag_repository = new_repository;
+                self.tag_repository.as_ref().unwrap()
+            }
+            None => self.tag_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds all tags of specific user.
+    pub fn get_list(&mut self, user_id: u64) -> Result<Vec<TagDTO>, ServiceError> {
+        let tag_list = {
+            let fallback_repository =
+                some_if_true!(self.tag_repository.is_none() => TagRepository::new());
+            self.tag_repository(fallback_repository).find_all(user_id)?
+        };
+
+        Ok(tag_list
+            .into_iter()
+            .map(|tag| TagDTO {
+                id: tag.id,
+                name: tag.name,
+                created_at: tag.created_at,
+                updated_at: tag.updated_at,
+            })
+            .collect())
+    }
+
+    /// Finds all tags of specific user with the number of posts with each tag,
+    /// most used tags first.
+    pub fn get_cloud(&mut self, user_id: u64) -> Result<Vec<TagUsageDTO>, ServiceError> {
+        let tag_list = {
+            let fallback_repository =
+                some_if_true!(self.tag_repository.is_none() => TagRepository::new());
+            self.tag_repository(fallback_repository)
+                .find_all_with_post_count(user_id)?
+        };
+
+        let mut tag_usages: Vec<TagUsageDTO> = tag_list
+            .into_iter()
+            .map(|(tag, post_count)| TagUsageDTO {
+                id: tag.id,
+                name: tag.name,
+                post_count,
+            })
+            .collect();
+        // The sort is stable, so tags used equally stay in the order of creation.
+        tag_usages.sort_by_key(|tag_usage| Reverse(tag_usage.post_count));
+        Ok(tag_usages)
+    }
+
+    /// Creates a new tag and returns id of the created tag.
+    pub fn create(&mut self, user_id: u64, name: &str) -> Result<u64, ServiceError> {
+        if name.trim().is_empty() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let fallback_repository =
+            some_if_true!(self.tag_repository.is_none() => TagRepository::new());
+        self.tag_repository(fallback_repository)
+            .create(user_id, name)
+    }
+
+    /// Renames a tag of specific user.
+    pub fn update(&mut self, id: u64, user_id: u64, name: &str) -> Result<bool, ServiceError> {
+        if name.trim().is_empty() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let fallback_repository =
+            some_if_true!(self.tag_repository.is_none() => TagRepository::new());
+        self.tag_repository(fallback_repository)
+            .update(user_id, id, name)
+    }
+
+    /// Deletes a tag of specific user, and removes it from posts.
+    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.tag_repository.is_none() => TagRepository::new());
+        self.tag_repository(fallback_repository).delete(user_id, id)
+    }
+}
+
+impl Default for TagService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::tag::MockTagRepositoryTrait as TagRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::tag::MockTagRepositoryTrait;
+
+    impl TagService {
+        pub fn new_with_repository(tag_repository: TagRepository) -> Self {
+            Self {
+                tag_repository: Some(tag_repository),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_list() {
+        let mut mocked_tag_repository = MockTagRepositoryTrait::new();
+
+        let user_id = 5;
+
+        mocked_tag_repository
+            .expect_find_all()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|passed_user_id| {
+                Ok(vec![Tag {
+                    id: 1,
+                    user_id: passed_user_id,
+                    name: String::from("U2FsdGVkX1"),
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                }])
+            });
+
+        let mut tag_service = TagService::new_with_repository(mocked_tag_repository);
+        let tag_list = tag_service.get_list(user_id).unwrap();
+
+        assert_eq!(tag_list.first().unwrap().id, 1);
+        assert_eq!(tag_list.first().unwrap().name, "U2FsdGVkX1");
+    }
+
+    #[test]
+    fn test_get_cloud() {
+        let mut mocked_tag_repository = MockTagRepositoryTrait::new();
+
+        let user_id = 5;
+
+        mocked_tag_repository
+            .expect_find_all_with_post_count()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|passed_user_id| {
+                Ok(vec![(1, 2), (2, 7), (3, 2)]
+                    .into_iter()
+                    .map(|(id, post_count)| {
+                        let tag = Tag {
+                            id,
+                            user_id: passed_user_id,
+                            name: String::from("U2FsdGVkX1"),
+                            created_at: Utc::now().naive_utc(),
+                            updated_at: None,
+                        };
+                        (tag, post_count)
+                    })
+                    .collect())
+            });
+
+        let mut tag_service = TagService::new_with_repository(mocked_tag_repository);
+        let tag_usages = tag_service.get_cloud(user_id).unwrap();
+
+        let ids: Vec<u64> = tag_usages.iter().map(|tag_usage| tag_usage.id).collect();
+        assert_eq!(ids, vec![2, 1, 3]);
+        assert_eq!(tag_usages[0].post_count, 7);
+    }
+
+    #[test]
+    fn test_create_with_empty_name() {
+        let mut mocked_tag_repository = MockTagRepositoryTrait::new();
+        mocked_tag_repository.expect_create().times(0);
+
+        let mut tag_service = TagService::new_with_repository(mocked_tag_repository);
+
+        assert!(tag_service.create(5, " ").is_err());
+    }
+}
@@ -0,0 +1,188 @@
+use chrono::Duration;
+use std::env;
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::telemetry::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+
+/// Default number of days counted by `get_counts`.
+const DEFAULT_COUNT_DAYS: u32 = 30;
+
+/// Maximum number of days counted by `get_counts`.
+const MAX_COUNT_DAYS: u32 = 365;
+
+pub struct TelemetryService {
+    telemetry_repository: Option<TelemetryRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl TelemetryService {
+    pub fn new() -> Self {
+        Self {
+            telemetry_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn telemetry_repository(
+        &mut self,
+        new_repository: Option<TelemetryRepository>,
+    ) -> &TelemetryRepository {
+        match new_repository {
+            Some(_) => {
+                self.telemetry_repository = new_repository;
+                self.telemetry_repository.as_ref().unwrap()
+            }
+            None => self.telemetry_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Returns whether the telemetry is enabled by `TELEMETRY_ENABLED`.
+    pub fn is_enabled() -> bool {
+        env::var("TELEMETRY_ENABLED")
+            .map(|enabled| enabled == "true")
+            .unwrap_or(false)
+    }
+
+    /// Counts an event sent by the user, and returns whether the event was counted.
+    ///
+    /// Events from users who have not opted in are dropped.
+    pub fn record(&mut self, user_id: u64, event: &str) -> Result<bool, ServiceError> {
+        if !TELEMETRY_EVENTS.contains(&event) {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let today = self.clock.now().naive_utc().date();
+
+        let fallback_repository =
+            some_if_true!(self.telemetry_repository.is_none() => TelemetryRepository::new());
+        let telemetry_repository = self.telemetry_repository(fallback_repository);
+
+        if !telemetry_repository.find_opt_in(user_id)? {
+            return Ok(false);
+        }
+
+        telemetry_repository.increase(event, &today)
+    }
+
+    /// Finds counts of events on each day for the last `days` days including today,
+    /// in asc order of the dates and the events. Days without an event are omitted.
+    pub fn get_counts(
+        &mut self,
+        days: &Option<u32>,
+    ) -> Result<Vec<TelemetryCountDTO>, ServiceError> {
+        let days = days.unwrap_or(DEFAULT_COUNT_DAYS);
+        if days == 0 || days > MAX_COUNT_DAYS {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let from = self.clock.now().naive_utc().date() - Duration::days(i64::from(days) - 1);
+
+        let fallback_repository =
+            some_if_true!(self.telemetry_repository.is_none() => TelemetryRepository::new());
+        Ok(self
+            .telemetry_repository(fallback_repository)
+            .find_all_since(&from)?
+            .into_iter()
+            .map(|event| TelemetryCountDTO {
+                event: event.event,
+                date: event.date,
+                count: event.count,
+            })
+            .collect())
+    }
+}
+
+impl Default for TelemetryService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::telemetry::MockTelemetryRepositoryTrait as TelemetryRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::telemetry::MockTelemetryRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl TelemetryService {
+        pub fn new_with_repository(telemetry_repository: TelemetryRepository) -> Self {
+            Self {
+                telemetry_repository: Some(telemetry_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    #[test]
+    fn test_record_drops_event_without_opt_in() {
+        let mut mocked_telemetry_repository = MockTelemetryRepositoryTrait::new();
+
+        mocked_telemetry_repository
+            .expect_find_opt_in()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(false));
+        mocked_telemetry_repository.expect_increase().times(0);
+
+        let mut telemetry_service =
+            TelemetryService::new_with_repository(mocked_telemetry_repository);
+
+        assert!(!telemetry_service.record(5, "calendar_view").unwrap());
+    }
+
+    #[test]
+    fn test_record_rejects_unknown_event() {
+        let mut telemetry_service =
+            TelemetryService::new_with_repository(MockTelemetryRepositoryTrait::new());
+
+        assert!(telemetry_service.record(5, "clicked_here").is_err());
+    }
+
+    #[test]
+    fn test_get_counts() {
+        let mut mocked_telemetry_repository = MockTelemetryRepositoryTrait::new();
+
+        mocked_telemetry_repository
+            .expect_find_all_since()
+            .with(eq(NaiveDate::from_ymd(2020, 4, 6)))
+            .times(1)
+            .returning(|_| {
+                Ok(vec![TelemetryEvent {
+                    event: String::from("calendar_view"),
+                    date: NaiveDate::from_ymd(2020, 4, 12),
+                    count: 3,
+                }])
+            });
+
+        let mut telemetry_service =
+            TelemetryService::new_with_repository(mocked_telemetry_repository).with_clock(
+                Arc::new(TestClock::new(Utc.ymd(2020, 4, 12).and_hms(9, 0, 0))),
+            );
+
+        assert_eq!(
+            telemetry_service.get_counts(&Some(7)).unwrap(),
+            vec![TelemetryCountDTO {
+                event: String::from("calendar_view"),
+                date: NaiveDate::from_ymd(2020, 4, 12),
+                count: 3,
+            }]
+        );
+        assert!(telemetry_service.get_counts(&Some(0)).is_err());
+        assert!(telemetry_service
+            .get_counts(&Some(MAX_COUNT_DAYS + 1))
+            .is_err());
+    }
+}
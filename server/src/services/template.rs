@@ -0,0 +1,170 @@
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::template::*;
+
+pub struct TemplateService {
+    template_repository: Option<TemplateRepository>,
+}
+
+impl TemplateService {
+    pub fn new() -> Self {
+        Self {
+            template_repository: None,
+        }
+    }
+
+    fn template_repository(
+        &mut self,
+        new_repository: Option<TemplateRepository>,
+    ) -> &TemplateRepository {
+        match new_repository {
+            Some(_) => {
+                self.template_repository = new_repository;
+                self.template_repository.as_ref().unwrap()
+            }
+            None => self.template_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds all templates of specific user in the order of creation.
+    pub fn get_list(&mut self, user_id: u64) -> Result<Vec<TemplateDTO>, ServiceError> {
+        let template_list = {
+            let fallback_repository =
+                some_if_true!(self.template_repository.is_none() => TemplateRepository::new());
+            self.template_repository(fallback_repository)
+                .find_all(user_id)?
+        };
+
+        Ok(template_list
+            .into_iter()
+            .map(|template| TemplateDTO {
+                id: template.id,
+                name: template.name,
+                title: template.title,
+                content: template.content,
+                created_at: template.created_at,
+                updated_at: template.updated_at,
+            })
+            .collect())
+    }
+
+    /// Creates a new template and returns id of the created template.
+    pub fn create(
+        &mut self,
+        user_id: u64,
+        name: &str,
+        title: &str,
+        content: &str,
+    ) -> Result<u64, ServiceError> {
+        if name.trim().is_empty() || content.trim().is_empty() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let fallback_repository =
+            some_if_true!(self.template_repository.is_none() => TemplateRepository::new());
+        self.template_repository(fallback_repository)
+            .create(user_id, name, title, content)
+    }
+
+    /// Updates a template of specific user.
+    pub fn update(
+        &mut self,
+        id: u64,
+        user_id: u64,
+        name: &Option<String>,
+        title: &Option<String>,
+        content: &Option<String>,
+    ) -> Result<bool, ServiceError> {
+        if name.is_none() && title.is_none() && content.is_none() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+        let is_empty = |field: &Option<String>| {
+            field
+                .as_ref()
+                .map_or(false, |field| field.trim().is_empty())
+        };
+        if is_empty(name) || is_empty(content) {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let fallback_repository =
+            some_if_true!(self.template_repository.is_none() => TemplateRepository::new());
+        self.template_repository(fallback_repository)
+            .update(user_id, id, name, title, content)
+    }
+
+    /// Deletes a template of specific user.
+    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.template_repository.is_none() => TemplateRepository::new());
+        self.template_repository(fallback_repository)
+            .delete(user_id, id)
+    }
+}
+
+impl Default for TemplateService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::template::MockTemplateRepositoryTrait as TemplateRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::template::MockTemplateRepositoryTrait;
+
+    impl TemplateService {
+        pub fn new_with_repository(template_repository: TemplateRepository) -> Self {
+            Self {
+                template_repository: Some(template_repository),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_list() {
+        let mut mocked_template_repository = MockTemplateRepositoryTrait::new();
+
+        let user_id = 5;
+
+        mocked_template_repository
+            .expect_find_all()
+            .with(eq(user_id))
+            .times(1)
+            .returning(|passed_user_id| {
+                Ok(vec![Template {
+                    id: 1,
+                    user_id: passed_user_id,
+                    name: String::from("U2FsdGVkX1"),
+                    title: String::from("U2FsdGVkX2"),
+                    content: String::from("U2FsdGVkX3"),
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                }])
+            });
+
+        let mut template_service = TemplateService::new_with_repository(mocked_template_repository);
+        let template_list = template_service.get_list(user_id).unwrap();
+
+        assert_eq!(template_list.len(), 1);
+        assert_eq!(template_list[0].content, "U2FsdGVkX3");
+    }
+
+    #[test]
+    fn test_update_with_invalid_args() {
+        let mut mocked_template_repository = MockTemplateRepositoryTrait::new();
+        mocked_template_repository.expect_update().times(0);
+
+        let mut template_service = TemplateService::new_with_repository(mocked_template_repository);
+
+        assert!(template_service.update(1, 5, &None, &None, &None).is_err());
+        assert!(template_service
+            .update(1, 5, &Some(String::from(" ")), &None, &None)
+            .is_err());
+    }
+}
@@ -0,0 +1,351 @@
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::two_factor::*;
+use crate::models::user::UserRepository;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::totp_util;
+
+/// Name of the service shown in authenticator apps.
+const TOTP_ISSUER: &str = "Darim";
+
+/// Number of recovery codes given when two-factor authentication is enabled.
+const RECOVERY_CODE_COUNT: usize = 10;
+
+/// Length of recovery codes without the hyphen in the middle.
+const RECOVERY_CODE_LENGTH: usize = 10;
+
+/// Returns the hash of a recovery code, ignoring its case, hyphens and whitespaces.
+fn hash_recovery_code(code: &str) -> String {
+    let normalized_code: String = code
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect();
+    format!("{:x}", Sha256::digest(normalized_code.as_bytes()))
+}
+
+/// Returns a random recovery code in `xxxxx-xxxxx` format.
+fn generate_recovery_code() -> String {
+    let code: String = thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(RECOVERY_CODE_LENGTH)
+        .collect::<String>()
+        .to_ascii_lowercase();
+    let (head, tail) = code.split_at(RECOVERY_CODE_LENGTH / 2);
+    format!("{}-{}", head, tail)
+}
+
+pub struct TwoFactorService {
+    two_factor_repository: Option<TwoFactorRepository>,
+    user_repository: Option<UserRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl TwoFactorService {
+    pub fn new() -> Self {
+        Self {
+            two_factor_repository: None,
+            user_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn two_factor_repository(
+        &mut self,
+        new_repository: Option<TwoFactorRepository>,
+    ) -> &TwoFactorRepository {
+        match new_repository {
+            Some(_) => {
+                self.two_factor_repository = new_repository;
+                self.two_factor_repository.as_ref().unwrap()
+            }
+            None => self.two_factor_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_repository = new_repository;
+                self.user_repository.as_ref().unwrap()
+            }
+            None => self.user_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds two-factor authentication of specific user, which may be pending.
+    fn find_two_factor(&mut self, user_id: u64) -> Result<Option<TwoFactor>, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.two_factor_repository.is_none() => TwoFactorRepository::new());
+        match self
+            .two_factor_repository(fallback_repository)
+            .find_by_user_id(user_id)
+        {
+            Ok(two_factor) => Ok(Some(two_factor)),
+            Err(ServiceError::NotFound(_)) => Ok(None),
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Returns the time step of a TOTP code of now, or `None` if the code is wrong.
+    fn find_step(&self, two_factor: &TwoFactor, code: &str) -> Option<u64> {
+        let timestamp = self.clock.now().timestamp() as u64;
+        totp_util::find_step(&two_factor.secret, code.trim(), timestamp)
+    }
+
+    /// Returns whether specific user has enabled two-factor authentication.
+    pub fn is_enabled(&mut self, user_id: u64) -> Result<bool, ServiceError> {
+        Ok(self
+            .find_two_factor(user_id)?
+            .map(|two_factor| two_factor.enabled_at.is_some())
+            .unwrap_or(false))
+    }
+
+    /// Starts to set up two-factor authentication of specific user, and returns a new secret.
+    ///
+    /// It stays pending until `enable` verifies a code of the secret.
+    /// Setting up again replaces the pending secret, but it fails if the user has enabled it.
+    pub fn setup(&mut self, user_id: u64) -> Result<TwoFactorSetupDTO, ServiceError> {
+        if self.is_enabled(user_id)? {
+            return Err(get_service_error(ServiceError::DuplicatedKey));
+        }
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+
+        let secret = totp_util::generate_secret();
+        self.two_factor_repository(None).create(user_id, &secret)?;
+
+        Ok(TwoFactorSetupDTO {
+            uri: totp_util::get_uri(TOTP_ISSUER, &user.email, &secret),
+            secret,
+        })
+    }
+
+    /// Enables pending two-factor authentication of specific user by a code of the secret,
+    /// and returns new recovery codes, which are not shown again.
+    pub fn enable(&mut self, user_id: u64, code: &str) -> Result<Vec<String>, ServiceError> {
+        let two_factor = match self.find_two_factor(user_id)? {
+            Some(two_factor) if two_factor.enabled_at.is_none() => two_factor,
+            Some(_) => return Err(get_service_error(ServiceError::DuplicatedKey)),
+            None => {
+                return Err(get_service_error(ServiceError::NotFound(
+                    user_id.to_string(),
+                )))
+            }
+        };
+
+        let step = match self.find_step(&two_factor, code) {
+            Some(step) => step,
+            None => return Err(get_service_error(ServiceError::Unauthorized)),
+        };
+
+        let recovery_codes: Vec<String> = (0..RECOVERY_CODE_COUNT)
+            .map(|_| generate_recovery_code())
+            .collect();
+        let recovery_code_hashes: Vec<String> = recovery_codes
+            .iter()
+            .map(|code| hash_recovery_code(code))
+            .collect();
+        let now = self.clock.now().naive_utc();
+        self.two_factor_repository(None)
+            .enable(user_id, step, &now, &recovery_code_hashes)?;
+
+        Ok(recovery_codes)
+    }
+
+    /// Checks a TOTP code or a recovery code of specific user who has enabled
+    /// two-factor authentication.
+    ///
+    /// A TOTP code is accepted once, and a recovery code is deleted once it is accepted.
+    pub fn check_code(&mut self, user_id: u64, code: &str) -> Result<(), ServiceError> {
+        let two_factor = match self.find_two_factor(user_id)? {
+            Some(two_factor) if two_factor.enabled_at.is_some() => two_factor,
+            _ => return Err(get_service_error(ServiceError::Unauthorized)),
+        };
+
+        let is_accepted = match self.find_step(&two_factor, code) {
+            Some(step) => self.two_factor_repository(None).mark_used(user_id, step)?,
+            None => self
+                .two_factor_repository(None)
+                .use_recovery_code(user_id, &hash_recovery_code(code))?,
+        };
+
+        if is_accepted {
+            Ok(())
+        } else {
+            Err(get_service_error(ServiceError::Unauthorized))
+        }
+    }
+
+    /// Disables two-factor authentication of specific user by a TOTP code or a recovery code.
+    pub fn disable(&mut self, user_id: u64, code: &str) -> Result<bool, ServiceError> {
+        self.check_code(user_id, code)?;
+        self.two_factor_repository(None).delete(user_id)
+    }
+}
+
+impl Default for TwoFactorService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::two_factor::MockTwoFactorRepositoryTrait as TwoFactorRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::two_factor::MockTwoFactorRepositoryTrait;
+    use crate::models::user::MockUserRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl TwoFactorService {
+        pub fn new_with_repository(
+            two_factor_repository: TwoFactorRepository,
+            user_repository: UserRepository,
+        ) -> Self {
+            Self {
+                two_factor_repository: Some(two_factor_repository),
+                user_repository: Some(user_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    /// Secret of the test vectors of RFC 6238, whose code is `081804` at 2005-03-18 01:58:29.
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    fn two_factor(is_enabled: bool) -> TwoFactor {
+        let created_at = Utc.ymd(2005, 3, 18).and_hms(1, 0, 0).naive_utc();
+        TwoFactor {
+            user_id: 5,
+            secret: String::from(SECRET),
+            enabled_at: if is_enabled { Some(created_at) } else { None },
+            last_used_step: None,
+            created_at,
+        }
+    }
+
+    fn clock() -> Arc<dyn Clock> {
+        Arc::new(TestClock::new(Utc.timestamp(1_111_111_109, 0)))
+    }
+
+    #[test]
+    fn test_hash_recovery_code() {
+        let code = generate_recovery_code();
+        assert_eq!(code.len(), RECOVERY_CODE_LENGTH + 1);
+        assert_eq!(
+            hash_recovery_code(&code),
+            hash_recovery_code(&format!(" {} ", code.to_uppercase().replace('-', "")))
+        );
+    }
+
+    #[test]
+    fn test_enable() {
+        let mut mocked_two_factor_repository = MockTwoFactorRepositoryTrait::new();
+        mocked_two_factor_repository
+            .expect_find_by_user_id()
+            .with(eq(5))
+            .times(2)
+            .returning(|_| Ok(two_factor(false)));
+        mocked_two_factor_repository
+            .expect_enable()
+            .with(
+                eq(5),
+                eq(37_037_036),
+                always(),
+                function(|hashes: &[String]| hashes.len() == RECOVERY_CODE_COUNT),
+            )
+            .times(1)
+            .returning(|_, _, _, _| Ok(true));
+
+        let mut two_factor_service = TwoFactorService::new_with_repository(
+            mocked_two_factor_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(clock());
+
+        assert!(matches!(
+            two_factor_service.enable(5, "123456"),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert_eq!(
+            two_factor_service.enable(5, "081804").unwrap().len(),
+            RECOVERY_CODE_COUNT
+        );
+    }
+
+    #[test]
+    fn test_check_code() {
+        let mut mocked_two_factor_repository = MockTwoFactorRepositoryTrait::new();
+        mocked_two_factor_repository
+            .expect_find_by_user_id()
+            .times(3)
+            .returning(|_| Ok(two_factor(true)));
+        mocked_two_factor_repository
+            .expect_mark_used()
+            .with(eq(5), eq(37_037_036))
+            .times(2)
+            .returning({
+                let mut is_used = false;
+                move |_, _| Ok(!std::mem::replace(&mut is_used, true))
+            });
+        mocked_two_factor_repository
+            .expect_use_recovery_code()
+            .with(eq(5), eq(hash_recovery_code("abcde-12345")))
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let mut two_factor_service = TwoFactorService::new_with_repository(
+            mocked_two_factor_repository,
+            MockUserRepositoryTrait::new(),
+        )
+        .with_clock(clock());
+
+        assert!(two_factor_service.check_code(5, "081804").is_ok());
+        assert!(matches!(
+            two_factor_service.check_code(5, "081804"),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(two_factor_service.check_code(5, "ABCDE12345").is_ok());
+    }
+
+    #[test]
+    fn test_setup_when_enabled() {
+        let mut mocked_two_factor_repository = MockTwoFactorRepositoryTrait::new();
+        mocked_two_factor_repository
+            .expect_find_by_user_id()
+            .times(1)
+            .returning(|_| Ok(two_factor(true)));
+        mocked_two_factor_repository.expect_create().times(0);
+
+        let mut two_factor_service = TwoFactorService::new_with_repository(
+            mocked_two_factor_repository,
+            MockUserRepositoryTrait::new(),
+        );
+
+        assert!(matches!(
+            two_factor_service.setup(5),
+            Err(ServiceError::DuplicatedKey)
+        ));
+    }
+}
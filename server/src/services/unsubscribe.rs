@@ -0,0 +1,174 @@
+use std::sync::Arc;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::prompt::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::unsubscribe_util::{self, EmailCategory};
+
+pub struct UnsubscribeService {
+    prompt_repository: Option<PromptRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl UnsubscribeService {
+    pub fn new() -> Self {
+        Self {
+            prompt_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn prompt_repository(&mut self, new_repository: Option<PromptRepository>) -> &PromptRepository {
+        match new_repository {
+            Some(_) => {
+                self.prompt_repository = new_repository;
+                self.prompt_repository.as_ref().unwrap()
+            }
+            None => self.prompt_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Unsubscribes the user of a signed token from emails of its category,
+    /// and returns the category.
+    ///
+    /// Unsubscribing again succeeds as well, so that a link opened twice, or requested by
+    /// a mail client before the user opens it, does not end with an error. A forged or expired
+    /// token is not found, without telling whether its user exists.
+    pub fn unsubscribe(&mut self, token: &str) -> Result<EmailCategory, ServiceError> {
+        let now = self.clock.now().naive_utc();
+        let claims =
+            match unsubscribe_util::verify_token(&unsubscribe_util::get_secret(), token, &now) {
+                Some(claims) => claims,
+                None => {
+                    return Err(get_service_error(ServiceError::NotFound(String::from(
+                        "unsubscribe token",
+                    ))))
+                }
+            };
+
+        match claims.category {
+            EmailCategory::Prompt => {
+                let fallback_repository =
+                    some_if_true!(self.prompt_repository.is_none() => PromptRepository::new());
+                match self
+                    .prompt_repository(fallback_repository)
+                    .unsubscribe(claims.user_id)
+                {
+                    Ok(_) | Err(ServiceError::NotFound(_)) => Ok(claims.category),
+                    Err(error) => Err(error),
+                }
+            }
+        }
+    }
+}
+
+impl Default for UnsubscribeService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::prompt::MockPromptRepositoryTrait as PromptRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use mockall::predicate::*;
+    use mockall::Sequence;
+    use std::env;
+
+    use super::*;
+    use crate::models::prompt::MockPromptRepositoryTrait;
+    use crate::utils::clock_util::TestClock;
+
+    impl UnsubscribeService {
+        pub fn new_with_repository(prompt_repository: PromptRepository) -> Self {
+            Self {
+                prompt_repository: Some(prompt_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+
+        pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+            self.clock = clock;
+            self
+        }
+    }
+
+    #[test]
+    fn test_unsubscribe_twice() {
+        let mut mocked_prompt_repository = MockPromptRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+
+        let now = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0);
+
+        mocked_prompt_repository
+            .expect_unsubscribe()
+            .with(eq(5))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(true));
+        mocked_prompt_repository
+            .expect_unsubscribe()
+            .with(eq(5))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|user_id| Err(ServiceError::NotFound(user_id.to_string())));
+
+        env::set_var("UNSUBSCRIBE_SECRET", "s3cr3t");
+        let token = unsubscribe_util::issue_token(
+            "s3cr3t",
+            5,
+            EmailCategory::Prompt,
+            &(now.naive_utc() + Duration::days(1)),
+        );
+        let mut unsubscribe_service =
+            UnsubscribeService::new_with_repository(mocked_prompt_repository)
+                .with_clock(Arc::new(TestClock::new(now)));
+
+        assert_eq!(
+            unsubscribe_service.unsubscribe(&token).unwrap(),
+            EmailCategory::Prompt
+        );
+        assert_eq!(
+            unsubscribe_service.unsubscribe(&token).unwrap(),
+            EmailCategory::Prompt
+        );
+    }
+
+    #[test]
+    fn test_unsubscribe_forged_or_expired() {
+        let mut mocked_prompt_repository = MockPromptRepositoryTrait::new();
+
+        let now = Utc.ymd(2020, 4, 12).and_hms(9, 0, 0);
+        let clock = Arc::new(TestClock::new(now));
+
+        mocked_prompt_repository.expect_unsubscribe().times(0);
+
+        env::set_var("UNSUBSCRIBE_SECRET", "s3cr3t");
+        let expires_at = now.naive_utc() + Duration::days(1);
+        let token = unsubscribe_util::issue_token("s3cr3t", 5, EmailCategory::Prompt, &expires_at);
+        let forged_token = token.replacen('5', "6", 1);
+        let foreign_token =
+            unsubscribe_util::issue_token("another", 5, EmailCategory::Prompt, &expires_at);
+        let mut unsubscribe_service =
+            UnsubscribeService::new_with_repository(mocked_prompt_repository)
+                .with_clock(clock.clone());
+
+        assert!(matches!(
+            unsubscribe_service.unsubscribe(&forged_token),
+            Err(ServiceError::NotFound(_))
+        ));
+        assert!(matches!(
+            unsubscribe_service.unsubscribe(&foreign_token),
+            Err(ServiceError::NotFound(_))
+        ));
+
+        clock.advance(Duration::days(1));
+        assert!(matches!(
+            unsubscribe_service.unsubscribe(&token),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+}
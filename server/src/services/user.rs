@@ -1,17 +1,53 @@
+use chrono::Utc;
 use reqwest::Client;
+use sha2::{Digest, Sha256};
 use std::env;
+use std::time::Duration;
 
+use crate::models::attachment::AttachmentFile;
 use crate::models::auth::*;
-use crate::models::error::{get_service_error, ServiceError};
+use crate::models::connection;
+use crate::models::error::{get_service_error, FieldError, ServiceError};
+use crate::models::storage::Storage;
 use crate::models::user::*;
 use crate::models::user_key::UserKeyRepository;
+use crate::services::email::EmailService;
+use crate::services::invite::InviteService;
+use crate::utils::html_util;
+use crate::utils::image_util;
 use crate::utils::password_util;
+use crate::utils::url_util::PublicUrl;
+
+/// Time limit of a range query of HaveIBeenPwned.
+const BREACH_CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Returns the key of the avatar of a user in the storage.
+///
+/// A new avatar replaces the file of the key, so that each user has only one.
+pub fn get_avatar_key(user_id: u64) -> String {
+    format!("avatar-{}", user_id)
+}
+
+/// Returns the SHA-256 hash of an avatar in hex.
+fn get_avatar_hash(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+/// Returns the URL of the avatar of a user on the api gateway.
+///
+/// The path is stable, and the query changes with the avatar so that caches are refreshed.
+fn get_avatar_url(user_id: u64, hash: &str) -> String {
+    format!("/users/{}/avatar?v={}", user_id, &hash[..16])
+}
 
 pub struct UserService {
     sign_up_token_repository: Option<SignUpTokenRepository>,
     password_token_repository: Option<PasswordTokenRepository>,
+    email_change_token_repository: Option<EmailChangeTokenRepository>,
     user_key_repository: Option<UserKeyRepository>,
     user_repository: Option<UserRepository>,
+    storage: Option<Box<dyn Storage>>,
+    email_service: Option<EmailService>,
 }
 
 impl UserService {
@@ -19,8 +55,11 @@ impl UserService {
         Self {
             sign_up_token_repository: None,
             password_token_repository: None,
+            email_change_token_repository: None,
             user_key_repository: None,
             user_repository: None,
+            storage: None,
+            email_service: None,
         }
     }
 
@@ -50,6 +89,19 @@ impl UserService {
         }
     }
 
+    fn email_change_token_repository(
+        &mut self,
+        new_repository: Option<EmailChangeTokenRepository>,
+    ) -> &mut EmailChangeTokenRepository {
+        match new_repository {
+            Some(_) => {
+                self.email_change_token_repository = new_repository;
+                self.email_change_token_repository.as_mut().unwrap()
+            }
+            None => self.email_change_token_repository.as_mut().unwrap(),
+        }
+    }
+
     fn user_key_repository(
         &mut self,
         new_repository: Option<UserKeyRepository>,
@@ -63,6 +115,10 @@ impl UserService {
         }
     }
 
+    fn email_service(&mut self) -> &mut EmailService {
+        self.email_service.get_or_insert_with(EmailService::new)
+    }
+
     fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
         match new_repository {
             Some(_) => {
@@ -73,6 +129,13 @@ impl UserService {
         }
     }
 
+    fn storage(&mut self) -> &dyn Storage {
+        if self.storage.is_none() {
+            self.storage = Some(connection::connect_storage());
+        }
+        self.storage.as_deref().unwrap()
+    }
+
     /// Finds a user by id.
     pub fn get_one(&mut self, id: u64) -> Result<UserDTO, ServiceError> {
         let user = {
@@ -91,6 +154,91 @@ impl UserService {
         })
     }
 
+    /// Finds the profile of a user with the fingerprint of the public key and the settings.
+    pub fn get_profile(&mut self, id: u64) -> Result<ProfileDTO, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository).find_by_id(id)?
+        };
+
+        let public_key_fingerprint = {
+            let fallback_repository =
+                some_if_true!(self.user_key_repository.is_none() => UserKeyRepository::new());
+            match self
+                .user_key_repository(fallback_repository)
+                .find_by_user_id(id)
+            {
+                Ok(user_key) => Some(user_key.fingerprint()),
+                Err(ServiceError::NotFound(_)) => None,
+                Err(error) => return Err(error),
+            }
+        };
+
+        Ok(ProfileDTO {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            avatar_url: user.avatar_url,
+            created_at: user.created_at,
+            public_key_fingerprint,
+            settings: ProfileSettingsDTO {
+                daily_word_goal: user.daily_word_goal,
+                monthly_word_goal: user.monthly_word_goal,
+            },
+        })
+    }
+
+    /// Finds key metadata of a user, or `None` if the client has never set it.
+    pub fn get_key_metadata(&mut self, id: u64) -> Result<Option<KeyMetadata>, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository).find_by_id(id)?
+        };
+
+        match user.key_metadata {
+            Some(key_metadata) => match serde_json::from_str(&key_metadata) {
+                Ok(key_metadata) => Ok(Some(key_metadata)),
+                Err(_) => Err(get_service_error(ServiceError::InvalidFormat)),
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// Replaces key metadata of a user.
+    pub fn set_key_metadata(
+        &mut self,
+        id: u64,
+        key_metadata: &KeyMetadata,
+    ) -> Result<bool, ServiceError> {
+        let serialized_key_metadata = key_metadata.to_validated_json()?;
+
+        let fallback_repository =
+            some_if_true!(self.user_repository.is_none() => UserRepository::new());
+        self.user_repository(fallback_repository)
+            .update_key_metadata(id, &serialized_key_metadata)
+    }
+
+    /// Sets word goals of a user for a day and a month.
+    ///
+    /// Goals not given are kept, and a goal of 0 removes the goal.
+    pub fn set_word_goals(
+        &mut self,
+        id: u64,
+        daily: &Option<u32>,
+        monthly: &Option<u32>,
+    ) -> Result<bool, ServiceError> {
+        if daily.is_none() && monthly.is_none() {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let fallback_repository =
+            some_if_true!(self.user_repository.is_none() => UserRepository::new());
+        self.user_repository(fallback_repository)
+            .update_word_goals(id, daily, monthly)
+    }
+
     /// Finds all users.
     pub fn get_list(&mut self) -> Result<Vec<UserDTO>, ServiceError> {
         let user_list = {
@@ -139,11 +287,104 @@ impl UserService {
         }
     }
 
+    /// Returns how many times a password has appeared in data breaches, if
+    /// `PASSWORD_BREACH_CHECK_ENABLED` is set.
+    ///
+    /// Only the first 5 characters of the SHA-1 hash of the password are sent to HaveIBeenPwned.
+    /// The check is skipped if HaveIBeenPwned is unavailable, so that passwords can be changed.
+    async fn count_password_breaches(&self, password: &str) -> u64 {
+        let is_enabled = env::var("PASSWORD_BREACH_CHECK_ENABLED")
+            .map(|enabled| enabled == "true")
+            .unwrap_or(false);
+        if !is_enabled {
+            return 0;
+        }
+
+        let (prefix, suffix) = password_util::get_breach_range(password);
+        let client = match Client::builder().timeout(BREACH_CHECK_TIMEOUT).build() {
+            Ok(client) => client,
+            Err(_) => return 0,
+        };
+        let response = client
+            .get(&format!("https://api.pwnedpasswords.com/range/{}", prefix))
+            .header("Add-Padding", "true")
+            .send()
+            .await;
+
+        let range = match response {
+            Ok(response) if response.status().is_success() => response.text().await,
+            Ok(response) => {
+                println!(
+                    "[{}] Failed to check password breaches: {}",
+                    Utc::now(),
+                    response.status()
+                );
+                return 0;
+            }
+            Err(error) => {
+                println!(
+                    "[{}] Failed to check password breaches: {}",
+                    Utc::now(),
+                    error
+                );
+                return 0;
+            }
+        };
+
+        match range {
+            Ok(range) => password_util::count_breaches(&range, &suffix),
+            Err(_) => 0,
+        }
+    }
+
+    /// Validates a new password, and fails with errors on `field` if it is too weak
+    /// or has appeared in data breaches.
+    ///
+    /// # Arguments
+    ///
+    /// * `field` - A name of the field of the password in the request
+    /// * `password` - A new plain-text password
+    /// * `user_inputs` - Words related to the user such as the name and the email
+    async fn validate_password(
+        &self,
+        field: &str,
+        password: &str,
+        user_inputs: &[&str],
+    ) -> Result<(), ServiceError> {
+        let mut messages = password_util::get_weaknesses(password, user_inputs);
+        if messages.is_empty() {
+            let breach_count = self.count_password_breaches(password).await;
+            if breach_count > 0 {
+                messages.push(format!(
+                    "has appeared in {} data breaches, and should never be used",
+                    breach_count
+                ));
+            }
+        }
+
+        if messages.is_empty() {
+            Ok(())
+        } else {
+            Err(get_service_error(ServiceError::InvalidFields(
+                messages
+                    .into_iter()
+                    .map(|message| FieldError {
+                        field: field.to_string(),
+                        message,
+                    })
+                    .collect(),
+            )))
+        }
+    }
+
     /// Creates a new user.
     ///
     /// 1. Finds serialized token by token key from arguments.
     /// 2. Deserializes the found token and compares pin from token and it from arguments.
     /// 3. If the pins are equal, deletes the token from redis and creates a new user.
+    ///
+    /// On instances in invite mode, the invite code of the token is claimed before the user is
+    /// created, and released if the creation fails.
     pub async fn create(
         &mut self,
         user_public_key: &str,
@@ -178,21 +419,43 @@ impl UserService {
                         }
                     };
 
+                    let invite_code = {
+                        let mut invite_service = InviteService::new();
+                        let invite_code = invite_service.verify(&token.invite_code)?;
+                        if let Some(invite_code) = &invite_code {
+                            invite_service.claim(invite_code)?;
+                        }
+                        invite_code
+                    };
+
                     let user = {
                         let fallback_repository =
                             some_if_true!(self.user_repository.is_none() => UserRepository::new());
                         let user_repository = self.user_repository(fallback_repository);
 
-                        user_repository.create(
-                            &token.name,
-                            &token.email,
-                            &token.password,
-                            &token.avatar_url,
-                        )?;
-
-                        user_repository.find_by_email(&token.email)?
+                        let user = user_repository
+                            .create(
+                                &token.name,
+                                &token.email,
+                                &token.password,
+                                &token.avatar_url,
+                            )
+                            .and_then(|_| user_repository.find_by_email(&token.email));
+                        match user {
+                            Ok(user) => user,
+                            Err(error) => {
+                                if let Some(invite_code) = &invite_code {
+                                    InviteService::new().release(invite_code)?;
+                                }
+                                return Err(error);
+                            }
+                        }
                     };
 
+                    if let Some(invite_code) = &invite_code {
+                        InviteService::new().redeem(invite_code, user.id)?;
+                    }
+
                     let fallback_repository = some_if_true!(self.user_key_repository.is_none() => UserKeyRepository::new());
                     self.user_key_repository(fallback_repository)
                         .create(user.id, user_public_key)
@@ -205,32 +468,61 @@ impl UserService {
     }
 
     /// Deletes a user.
-    pub fn delete(&mut self, id: u64) -> Result<bool, ServiceError> {
+    ///
+    /// If `dry_run` is true, reports the data to be removed without removing anything.
+    /// The avatar is removed from the storage after the user is deleted.
+    pub fn delete(&mut self, id: u64, dry_run: bool) -> Result<UserDeletionDTO, ServiceError> {
         let fallback_repository =
             some_if_true!(self.user_repository.is_none() => UserRepository::new());
-        self.user_repository(fallback_repository).delete(id)
+        let deletion = self
+            .user_repository(fallback_repository)
+            .delete(id, dry_run)?;
+        if !dry_run {
+            self.storage().delete(&get_avatar_key(id))?;
+        }
+
+        Ok(UserDeletionDTO {
+            dry_run,
+            user_id: id,
+            post_ids: deletion.post_ids,
+            tag_count: deletion.tag_count,
+            user_key_count: deletion.user_key_count,
+        })
     }
 
     /// Updates a new user.
-    pub fn update(
+    ///
+    /// A new password is validated by `validate_password`.
+    /// The avatar is changed by `upload_avatar` instead.
+    pub async fn update(
         &mut self,
         id: u64,
         name: &Option<String>,
         password: &Option<String>,
-        avatar_url: &Option<String>,
     ) -> Result<bool, ServiceError> {
-        if name.is_none() && password.is_none() && avatar_url.is_none() {
+        if name.is_none() && password.is_none() {
             return Err(get_service_error(ServiceError::InvalidArgument));
         }
 
-        if let (Some(name), Some(password), Some(avatar_url)) = (name, password, avatar_url) {
-            if name.trim().is_empty() || password.trim().is_empty() || avatar_url.trim().is_empty()
-            {
+        if let (Some(name), Some(password)) = (name, password) {
+            if name.trim().is_empty() || password.trim().is_empty() {
                 return Err(get_service_error(ServiceError::InvalidArgument));
             }
         }
 
         let hashed_password = if let Some(password) = password {
+            let user = {
+                let fallback_repository =
+                    some_if_true!(self.user_repository.is_none() => UserRepository::new());
+                self.user_repository(fallback_repository).find_by_id(id)?
+            };
+            let mut user_inputs = vec![user.name.as_str(), user.email.as_str()];
+            if let Some(name) = name {
+                user_inputs.push(name.as_str());
+            }
+            self.validate_password("password", password, &user_inputs)
+                .await?;
+
             Some(password_util::get_hashed_password(&password))
         } else {
             None
@@ -239,11 +531,53 @@ impl UserService {
         let fallback_repository =
             some_if_true!(self.user_repository.is_none() => UserRepository::new());
         self.user_repository(fallback_repository)
-            .update(id, name, &hashed_password, avatar_url)
+            .update(id, name, &hashed_password, &None)
+    }
+
+    /// Replaces the avatar of a user with an uploaded image, and returns the URL of the avatar.
+    ///
+    /// The image is resized and stripped of metadata by `image_util::process_avatar`.
+    pub fn upload_avatar(&mut self, id: u64, data: &[u8]) -> Result<String, ServiceError> {
+        let avatar = image_util::process_avatar(data)?;
+        let avatar_url = get_avatar_url(id, &get_avatar_hash(&avatar));
+
+        let fallback_repository =
+            some_if_true!(self.user_repository.is_none() => UserRepository::new());
+        self.user_repository(fallback_repository).find_by_id(id)?;
+
+        self.storage().put(&get_avatar_key(id), &avatar)?;
+        self.user_repository(None)
+            .update(id, &None, &None, &Some(avatar_url.clone()))?;
+        Ok(avatar_url)
+    }
+
+    /// Returns the avatar of a user uploaded by `upload_avatar`.
+    ///
+    /// The content is omitted if the hash of the avatar is in `cached_hashes`,
+    /// which the client has from `ETag` of a previous response.
+    pub fn download_avatar(
+        &mut self,
+        id: u64,
+        cached_hashes: &[String],
+    ) -> Result<AttachmentFile, ServiceError> {
+        let avatar = self.storage().get(&get_avatar_key(id))?;
+        let hash = get_avatar_hash(&avatar);
+        let data = if cached_hashes.contains(&hash) {
+            None
+        } else {
+            Some(avatar)
+        };
+
+        Ok(AttachmentFile {
+            filename: String::from("avatar.jpg"),
+            mime_type: String::from("image/jpeg"),
+            hash,
+            data,
+        })
     }
 
     // Reset the password.
-    pub fn reset_password(
+    pub async fn reset_password(
         &mut self,
         email: &str,
         token_id: &str,
@@ -267,6 +601,13 @@ impl UserService {
         };
 
         if token.id == token_id && token.password == temporary_password {
+            self.validate_password(
+                "new_password",
+                new_password,
+                &[user.name.as_str(), user.email.as_str()],
+            )
+            .await?;
+
             let hashed_password = password_util::get_hashed_password(new_password);
             self.user_repository(None)
                 .update(user.id, &None, &Some(hashed_password), &None)?;
@@ -277,6 +618,112 @@ impl UserService {
             )))
         }
     }
+
+    /// Requests changing the email of a user to `email`.
+    ///
+    /// The new email is kept pending in a token, which is emailed to the new address in a link
+    /// confirmed by `confirm_email_change`. The current address keeps signing in until then,
+    /// and is notified of the request.
+    pub fn request_email_change(&mut self, id: u64, email: &str) -> Result<bool, ServiceError> {
+        let email = email.trim();
+        if email.is_empty() || !email.contains('@') {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository).find_by_id(id)?
+        };
+        if user.email.eq_ignore_ascii_case(email) {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        match self.user_repository(None).find_by_email(email) {
+            Ok(_) => return Err(get_service_error(ServiceError::DuplicatedKey)),
+            Err(ServiceError::NotFound(_)) => {}
+            Err(error) => return Err(error),
+        }
+
+        let token = EmailChangeToken {
+            user_id: user.id,
+            email: email.to_string(),
+        };
+        let serialized_token = if let Ok(serialized_token) = serde_json::to_string(&token) {
+            serialized_token
+        } else {
+            return Err(get_service_error(ServiceError::InvalidFormat));
+        };
+
+        let key = {
+            let fallback_repository = some_if_true!(self.email_change_token_repository.is_none() => EmailChangeTokenRepository::new());
+            self.email_change_token_repository(fallback_repository)
+                .save(&serialized_token)?
+        };
+
+        let email_confirm_url = PublicUrl::from_env()
+            .expect("Invalid PUBLIC_BASE_URL")
+            .email_confirm_url(&key);
+        let confirmation_content = format!(
+            "Hello :)<br/><br/>\
+            Please visit the link to use this address for your Darim account:<br/><br/>\
+            <a href=\"{}\">{}</a><br/><br/>\
+            The link is valid for 3 minutes and can be used once.",
+            email_confirm_url, email_confirm_url,
+        );
+        self.email_service().enqueue(
+            &format!("{} <{}>", user.name, email),
+            &String::from("Confirm your new email for Darim 📮"),
+            &confirmation_content,
+            &Some(get_email_change_token_key(&key)),
+        )?;
+
+        let notification_content = format!(
+            "Hello :)<br/><br/>\
+            It has been requested to change the email of your account to {}.<br/><br/>\
+            This address is used until the new one is confirmed. \
+            If it was not you, please change your password.",
+            html_util::escape(email),
+        );
+        self.email_service().enqueue(
+            &format!("{} <{}>", user.name, user.email),
+            &String::from("Your Darim email is being changed 📮"),
+            &notification_content,
+            &None,
+        )?;
+        EmailService::send_soon();
+
+        Ok(true)
+    }
+
+    /// Changes the email of a user to the pending one in an email change token.
+    ///
+    /// The token is deleted when it is used, and it fails with `DuplicatedKey`
+    /// if another user has taken the email meanwhile.
+    pub fn confirm_email_change(&mut self, token_key: &str) -> Result<bool, ServiceError> {
+        let token: EmailChangeToken = {
+            let fallback_repository = some_if_true!(self.email_change_token_repository.is_none() => EmailChangeTokenRepository::new());
+            let email_change_token_repository =
+                self.email_change_token_repository(fallback_repository);
+            let serialized_token = email_change_token_repository.find(token_key)?;
+            if !email_change_token_repository.delete(token_key)? {
+                return Err(get_service_error(ServiceError::NotFound(
+                    token_key.to_string(),
+                )));
+            }
+
+            if let Ok(deserialized_token) = serde_json::from_str(&serialized_token) {
+                deserialized_token
+            } else {
+                return Err(get_service_error(ServiceError::InvalidFormat));
+            }
+        };
+
+        let fallback_repository =
+            some_if_true!(self.user_repository.is_none() => UserRepository::new());
+        self.user_repository(fallback_repository)
+            .update_email(token.user_id, &token.email)
+    }
 }
 
 impl Default for UserService {
@@ -285,23 +732,314 @@ impl Default for UserService {
     }
 }
 
+#[cfg(test)]
+use crate::models::auth::MockEmailChangeTokenRepositoryTrait as EmailChangeTokenRepository;
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+
 #[cfg(test)]
 mod tests {
+    use mockall::predicate::*;
+    use mockall::Sequence;
+
     use super::*;
+    use crate::models::auth::{MockEmailChangeTokenRepositoryTrait, MockTokenRepositoryTrait};
+    use crate::models::email_job::MockEmailJobRepositoryTrait;
+    use crate::models::user::MockUserRepositoryTrait;
+    use crate::models::user_key::UserKey;
+    use crate::utils::email_util::SendmailSender;
 
     impl UserService {
         pub fn new_with_repository(
-            sign_up_token_repository: SignUpTokenRepository,
-            password_token_repository: PasswordTokenRepository,
-            user_key_repository: UserKeyRepository,
+            email_change_token_repository: EmailChangeTokenRepository,
             user_repository: UserRepository,
         ) -> Self {
             Self {
-                sign_up_token_repository: Some(sign_up_token_repository),
-                password_token_repository: Some(password_token_repository),
-                user_key_repository: Some(user_key_repository),
+                sign_up_token_repository: None,
+                password_token_repository: None,
+                email_change_token_repository: Some(email_change_token_repository),
+                user_key_repository: None,
                 user_repository: Some(user_repository),
+                storage: None,
+                email_service: None,
             }
         }
+
+        pub fn with_email_service(mut self, email_service: EmailService) -> Self {
+            self.email_service = Some(email_service);
+            self
+        }
+    }
+
+    fn user(id: u64, email: &str) -> User {
+        User {
+            id,
+            name: String::from("park"),
+            email: email.to_string(),
+            password: String::from("password"),
+            avatar_url: None,
+            created_at: Utc::now().naive_utc(),
+            updated_at: None,
+            key_metadata: None,
+            daily_word_goal: None,
+            monthly_word_goal: None,
+            role: String::from("user"),
+            suspended_at: None,
+        }
+    }
+
+    /// Returns an email service which must not enqueue any email.
+    fn silent_email_service() -> EmailService {
+        let mut mocked_email_job_repository = MockEmailJobRepositoryTrait::new();
+        mocked_email_job_repository.expect_create().never();
+        EmailService::new_with_repository(
+            mocked_email_job_repository,
+            MockTokenRepositoryTrait::new(),
+            Box::new(SendmailSender),
+        )
+    }
+
+    #[test]
+    fn test_request_email_change() {
+        env::set_var("PUBLIC_BASE_URL", "https://darim.vercel.app");
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|id| Ok(user(id, "park@email.com")));
+        mocked_user_repository
+            .expect_find_by_email()
+            .with(function(|email: &str| email == "new@email.com"))
+            .times(1)
+            .returning(|email| Err(ServiceError::NotFound(email.to_string())));
+
+        let mut mocked_email_change_token_repository = MockEmailChangeTokenRepositoryTrait::new();
+        mocked_email_change_token_repository
+            .expect_save()
+            .with(function(|token: &str| {
+                serde_json::from_str::<EmailChangeToken>(token).ok()
+                    == Some(EmailChangeToken {
+                        user_id: 5,
+                        email: String::from("new@email.com"),
+                    })
+            }))
+            .times(1)
+            .returning(|_| Ok(String::from("a1b2")));
+
+        // The link goes to the new address, and the old address is notified without a token.
+        let mut mocked_email_job_repository = MockEmailJobRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+        mocked_email_job_repository
+            .expect_create()
+            .with(
+                function(|to: &str| to == "park <new@email.com>"),
+                always(),
+                function(|body: &str| body.contains("/email_confirm/a1b2")),
+                eq(Some(get_email_change_token_key("a1b2"))),
+                eq(None),
+            )
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _, _, _| Ok(true));
+        mocked_email_job_repository
+            .expect_create()
+            .with(
+                function(|to: &str| to == "park <park@email.com>"),
+                always(),
+                function(|body: &str| body.contains("new@email.com") && !body.contains("a1b2")),
+                eq(None),
+                eq(None),
+            )
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_, _, _, _, _| Ok(true));
+
+        let result = UserService::new_with_repository(
+            mocked_email_change_token_repository,
+            mocked_user_repository,
+        )
+        .with_email_service(EmailService::new_with_repository(
+            mocked_email_job_repository,
+            MockTokenRepositoryTrait::new(),
+            Box::new(SendmailSender),
+        ))
+        .request_email_change(5, " new@email.com ");
+        assert!(result.unwrap());
+    }
+
+    #[test]
+    fn test_request_email_change_to_same_email() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|id| Ok(user(id, "park@email.com")));
+        mocked_user_repository.expect_find_by_email().never();
+
+        let mut mocked_email_change_token_repository = MockEmailChangeTokenRepositoryTrait::new();
+        mocked_email_change_token_repository.expect_save().never();
+
+        let result = UserService::new_with_repository(
+            mocked_email_change_token_repository,
+            mocked_user_repository,
+        )
+        .with_email_service(silent_email_service())
+        .request_email_change(5, "Park@Email.com");
+        assert!(matches!(result, Err(ServiceError::InvalidArgument)));
+    }
+
+    #[test]
+    fn test_request_email_change_to_duplicated_email() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|id| Ok(user(id, "park@email.com")));
+        mocked_user_repository
+            .expect_find_by_email()
+            .with(function(|email: &str| email == "kim@email.com"))
+            .times(1)
+            .returning(|email| Ok(user(6, email)));
+
+        let mut mocked_email_change_token_repository = MockEmailChangeTokenRepositoryTrait::new();
+        mocked_email_change_token_repository.expect_save().never();
+
+        let result = UserService::new_with_repository(
+            mocked_email_change_token_repository,
+            mocked_user_repository,
+        )
+        .with_email_service(silent_email_service())
+        .request_email_change(5, "kim@email.com");
+        assert!(matches!(result, Err(ServiceError::DuplicatedKey)));
+    }
+
+    #[test]
+    fn test_confirm_email_change_once() {
+        let mut mocked_email_change_token_repository = MockEmailChangeTokenRepositoryTrait::new();
+        let mut sequence = Sequence::new();
+        mocked_email_change_token_repository
+            .expect_find()
+            .with(function(|key: &str| key == "a1b2"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(String::from("{\"user_id\":5,\"email\":\"new@email.com\"}")));
+        mocked_email_change_token_repository
+            .expect_delete()
+            .with(function(|key: &str| key == "a1b2"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|_| Ok(true));
+        mocked_email_change_token_repository
+            .expect_find()
+            .with(function(|key: &str| key == "a1b2"))
+            .times(1)
+            .in_sequence(&mut sequence)
+            .returning(|key| Err(ServiceError::NotFound(key.to_string())));
+
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_update_email()
+            .with(eq(5), function(|email: &str| email == "new@email.com"))
+            .times(1)
+            .returning(|_, _| Ok(true));
+
+        let mut user_service = UserService::new_with_repository(
+            mocked_email_change_token_repository,
+            mocked_user_repository,
+        );
+        assert!(user_service.confirm_email_change("a1b2").unwrap());
+        assert!(matches!(
+            user_service.confirm_email_change("a1b2"),
+            Err(ServiceError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_confirm_email_change_taken_meanwhile() {
+        let mut mocked_email_change_token_repository = MockEmailChangeTokenRepositoryTrait::new();
+        mocked_email_change_token_repository
+            .expect_find()
+            .times(1)
+            .returning(|_| Ok(String::from("{\"user_id\":5,\"email\":\"new@email.com\"}")));
+        mocked_email_change_token_repository
+            .expect_delete()
+            .times(1)
+            .returning(|_| Ok(true));
+
+        // Another user has signed up or changed to the email since it was requested.
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_update_email()
+            .with(eq(5), function(|email: &str| email == "new@email.com"))
+            .times(1)
+            .returning(|_, _| Err(ServiceError::DuplicatedKey));
+
+        let result = UserService::new_with_repository(
+            mocked_email_change_token_repository,
+            mocked_user_repository,
+        )
+        .confirm_email_change("a1b2");
+        assert!(matches!(result, Err(ServiceError::DuplicatedKey)));
+    }
+
+    #[test]
+    fn test_public_key_fingerprint() {
+        let user_key = UserKey {
+            id: 1,
+            user_id: 1,
+            public_key: String::from("abc"),
+            created_at: chrono::NaiveDate::from_ymd(2020, 4, 12).and_hms(7, 43, 3),
+            updated_at: None,
+        };
+
+        assert_eq!(
+            user_key.fingerprint(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn test_avatar_url() {
+        let hash = get_avatar_hash(b"abc");
+
+        assert_eq!(get_avatar_key(5), "avatar-5");
+        assert_eq!(
+            get_avatar_url(5, &hash),
+            "/users/5/avatar?v=ba7816bf8f01cfea"
+        );
+    }
+
+    #[test]
+    fn test_key_metadata() {
+        let key = |key_id: &str| KeyMetadataEntry {
+            key_id: key_id.to_string(),
+            algorithm: String::from("AES-256-GCM"),
+            created_at: chrono::NaiveDate::from_ymd(2020, 4, 12).and_hms(7, 43, 3),
+        };
+
+        let key_metadata = KeyMetadata {
+            keys: vec![key("k1"), key("k2")],
+            checksum: Some(String::from("a1b2c3")),
+        };
+        assert!(key_metadata.to_validated_json().is_ok());
+
+        let duplicated_key_metadata = KeyMetadata {
+            keys: vec![key("k1"), key("k1")],
+            checksum: None,
+        };
+        assert!(duplicated_key_metadata.to_validated_json().is_err());
+
+        let too_many_key_metadata = KeyMetadata {
+            keys: (0..33).map(|index| key(&format!("k{}", index))).collect(),
+            checksum: None,
+        };
+        assert!(too_many_key_metadata.to_validated_json().is_err());
+
+        let unknown_field = r#"{"keys":[],"checksum":null,"private_key":"secret"}"#;
+        assert!(serde_json::from_str::<KeyMetadata>(unknown_field).is_err());
     }
 }
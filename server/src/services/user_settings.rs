@@ -0,0 +1,375 @@
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::journal::JournalRepository;
+use crate::models::user_settings::*;
+
+pub struct UserSettingsService {
+    user_settings_repository: Option<UserSettingsRepository>,
+    journal_repository: Option<JournalRepository>,
+}
+
+impl UserSettingsService {
+    pub fn new() -> Self {
+        Self {
+            user_settings_repository: None,
+            journal_repository: None,
+        }
+    }
+
+    fn user_settings_repository(
+        &mut self,
+        new_repository: Option<UserSettingsRepository>,
+    ) -> &UserSettingsRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_settings_repository = new_repository;
+                self.user_settings_repository.as_ref().unwrap()
+            }
+            None => self.user_settings_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn journal_repository(
+        &mut self,
+        new_repository: Option<JournalRepository>,
+    ) -> &JournalRepository {
+        match new_repository {
+            Some(_) => {
+                self.journal_repository = new_repository;
+                self.journal_repository.as_ref().unwrap()
+            }
+            None => self.journal_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn find_settings(&mut self, user_id: u64) -> Result<Option<UserSettings>, ServiceError> {
+        let fallback_repository =
+            some_if_true!(self.user_settings_repository.is_none() => UserSettingsRepository::new());
+        self.user_settings_repository(fallback_repository)
+            .find_by_user_id(user_id)
+    }
+
+    /// Finds settings of specific user, which are the defaults if the user has never changed them.
+    pub fn get(&mut self, user_id: u64) -> Result<UserSettingsDTO, ServiceError> {
+        let settings = self.find_settings(user_id)?;
+
+        let default_journal_id = {
+            let fallback_repository =
+                some_if_true!(self.journal_repository.is_none() => JournalRepository::new());
+            self.journal_repository(fallback_repository)
+                .find_all(user_id)?
+                .into_iter()
+                .find(|journal| journal.is_default)
+                .map(|journal| journal.id)
+        };
+
+        Ok(match settings {
+            Some(settings) => UserSettingsDTO {
+                timezone: settings.timezone,
+                locale: settings.locale,
+                week_start_day: settings.week_start_day,
+                editor_preferences: settings
+                    .editor_preferences
+                    .and_then(|preferences| serde_json::from_str(&preferences).ok())
+                    .unwrap_or_default(),
+                default_journal_id,
+                telemetry_opt_in: settings.telemetry_opt_in,
+            },
+            None => UserSettingsDTO {
+                timezone: DEFAULT_TIMEZONE.to_string(),
+                locale: DEFAULT_LOCALE.to_string(),
+                week_start_day: WeekStartDay::Monday.as_str().to_string(),
+                editor_preferences: EditorPreferences::default(),
+                default_journal_id,
+                telemetry_opt_in: false,
+            },
+        })
+    }
+
+    /// Updates settings of specific user. Settings not given are kept.
+    ///
+    /// `editor_preferences` replaces the editor preferences as a whole.
+    /// The default journal is kept in the journals, so that posts go to it.
+    /// `telemetry_opt_in` allows anonymous usage counting, which is not allowed by default.
+    pub fn update(
+        &mut self,
+        user_id: u64,
+        timezone: &Option<String>,
+        locale: &Option<String>,
+        week_start_day: &Option<String>,
+        editor_preferences: &Option<EditorPreferences>,
+        default_journal_id: &Option<u64>,
+        telemetry_opt_in: &Option<bool>,
+    ) -> Result<bool, ServiceError> {
+        if timezone.is_none()
+            && locale.is_none()
+            && week_start_day.is_none()
+            && editor_preferences.is_none()
+            && default_journal_id.is_none()
+            && telemetry_opt_in.is_none()
+        {
+            return Err(get_service_error(ServiceError::InvalidArgument));
+        }
+
+        if let Some(timezone) = timezone {
+            parse_timezone(timezone)?;
+        }
+        if let Some(locale) = locale {
+            if !is_valid_locale(locale) {
+                return Err(get_service_error(ServiceError::InvalidArgument));
+            }
+        }
+        if let Some(week_start_day) = week_start_day {
+            WeekStartDay::parse(week_start_day)?;
+        }
+        let serialized_editor_preferences = match editor_preferences {
+            Some(editor_preferences) => Some(editor_preferences.to_validated_json()?),
+            None => None,
+        };
+
+        if timezone.is_some()
+            || locale.is_some()
+            || week_start_day.is_some()
+            || editor_preferences.is_some()
+            || telemetry_opt_in.is_some()
+        {
+            let settings = self.find_settings(user_id)?;
+            let (
+                current_timezone,
+                current_locale,
+                current_week_start_day,
+                current_editor,
+                current_telemetry_opt_in,
+            ) = match settings {
+                Some(settings) => (
+                    settings.timezone,
+                    settings.locale,
+                    settings.week_start_day,
+                    settings.editor_preferences,
+                    settings.telemetry_opt_in,
+                ),
+                None => (
+                    DEFAULT_TIMEZONE.to_string(),
+                    DEFAULT_LOCALE.to_string(),
+                    WeekStartDay::Monday.as_str().to_string(),
+                    None,
+                    false,
+                ),
+            };
+
+            self.user_settings_repository(None).save(
+                user_id,
+                timezone.as_ref().unwrap_or(&current_timezone),
+                locale.as_ref().unwrap_or(&current_locale),
+                week_start_day.as_ref().unwrap_or(&current_week_start_day),
+                &serialized_editor_preferences.or(current_editor),
+                telemetry_opt_in.unwrap_or(current_telemetry_opt_in),
+            )?;
+        }
+
+        if let Some(default_journal_id) = default_journal_id {
+            let fallback_repository =
+                some_if_true!(self.journal_repository.is_none() => JournalRepository::new());
+            self.journal_repository(fallback_repository)
+                .set_default(user_id, *default_journal_id)?;
+        }
+
+        Ok(true)
+    }
+}
+
+impl Default for UserSettingsService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::journal::MockJournalRepositoryTrait as JournalRepository;
+#[cfg(test)]
+use crate::models::user_settings::MockUserSettingsRepositoryTrait as UserSettingsRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::Utc;
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::journal::{Journal, MockJournalRepositoryTrait};
+    use crate::models::user_settings::MockUserSettingsRepositoryTrait;
+
+    impl UserSettingsService {
+        pub fn new_with_repository(
+            user_settings_repository: UserSettingsRepository,
+            journal_repository: JournalRepository,
+        ) -> Self {
+            Self {
+                user_settings_repository: Some(user_settings_repository),
+                journal_repository: Some(journal_repository),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_default() {
+        let mut mocked_user_settings_repository = MockUserSettingsRepositoryTrait::new();
+        let mut mocked_journal_repository = MockJournalRepositoryTrait::new();
+
+        mocked_user_settings_repository
+            .expect_find_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(None));
+        mocked_journal_repository
+            .expect_find_all()
+            .with(eq(5))
+            .times(1)
+            .returning(|user_id| {
+                Ok(vec![Journal {
+                    id: 8,
+                    user_id,
+                    name: String::new(),
+                    is_default: true,
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                }])
+            });
+
+        let mut user_settings_service = UserSettingsService::new_with_repository(
+            mocked_user_settings_repository,
+            mocked_journal_repository,
+        );
+
+        assert_eq!(
+            user_settings_service.get(5).unwrap(),
+            UserSettingsDTO {
+                timezone: String::from("UTC"),
+                locale: String::from("en"),
+                week_start_day: String::from("monday"),
+                editor_preferences: EditorPreferences::default(),
+                default_journal_id: Some(8),
+                telemetry_opt_in: false,
+            }
+        );
+    }
+
+    #[test]
+    fn test_update() {
+        let mut mocked_user_settings_repository = MockUserSettingsRepositoryTrait::new();
+
+        mocked_user_settings_repository
+            .expect_find_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|user_id| {
+                Ok(Some(UserSettings {
+                    user_id,
+                    timezone: String::from("Asia/Seoul"),
+                    locale: String::from("ko-KR"),
+                    week_start_day: String::from("sunday"),
+                    editor_preferences: Some(String::from(r#"{"font_size":16}"#)),
+                    telemetry_opt_in: false,
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                }))
+            });
+        mocked_user_settings_repository
+            .expect_save()
+            .with(
+                eq(5),
+                eq("America/New_York"),
+                eq("ko-KR"),
+                eq("sunday"),
+                eq(Some(String::from(r#"{"font_size":16}"#))),
+                eq(false),
+            )
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(true));
+
+        let mut user_settings_service = UserSettingsService::new_with_repository(
+            mocked_user_settings_repository,
+            MockJournalRepositoryTrait::new(),
+        );
+
+        assert!(user_settings_service
+            .update(
+                5,
+                &Some(String::from("America/New_York")),
+                &None,
+                &None,
+                &None,
+                &None,
+                &None
+            )
+            .unwrap());
+
+        // Invalid settings are rejected before anything is saved.
+        assert!(user_settings_service
+            .update(
+                5,
+                &Some(String::from("Mars/Olympus")),
+                &None,
+                &None,
+                &None,
+                &None,
+                &None
+            )
+            .is_err());
+        assert!(user_settings_service
+            .update(
+                5,
+                &None,
+                &Some(String::from("ko_KR")),
+                &None,
+                &None,
+                &None,
+                &None
+            )
+            .is_err());
+        assert!(user_settings_service
+            .update(
+                5,
+                &None,
+                &None,
+                &Some(String::from("friday")),
+                &None,
+                &None,
+                &None
+            )
+            .is_err());
+        let too_large_font = EditorPreferences {
+            font_size: Some(64),
+            ..EditorPreferences::default()
+        };
+        assert!(user_settings_service
+            .update(5, &None, &None, &None, &Some(too_large_font), &None, &None)
+            .is_err());
+        assert!(user_settings_service
+            .update(5, &None, &None, &None, &None, &None, &None)
+            .is_err());
+    }
+
+    #[test]
+    fn test_update_telemetry_opt_in() {
+        let mut mocked_user_settings_repository = MockUserSettingsRepositoryTrait::new();
+
+        mocked_user_settings_repository
+            .expect_find_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(None));
+        mocked_user_settings_repository
+            .expect_save()
+            .with(eq(5), eq("UTC"), eq("en"), eq("monday"), eq(None), eq(true))
+            .times(1)
+            .returning(|_, _, _, _, _, _| Ok(true));
+
+        let mut user_settings_service = UserSettingsService::new_with_repository(
+            mocked_user_settings_repository,
+            MockJournalRepositoryTrait::new(),
+        );
+
+        assert!(user_settings_service
+            .update(5, &None, &None, &None, &None, &None, &Some(true))
+            .unwrap());
+    }
+}
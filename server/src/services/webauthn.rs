@@ -0,0 +1,472 @@
+use rand::{distributions::Alphanumeric, thread_rng, Rng};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use webauthn_rs::ephemeral::WebauthnEphemeralConfig;
+use webauthn_rs::proto::{
+    CreationChallengeResponse, Credential, PublicKeyCredential, RegisterPublicKeyCredential,
+    RequestChallengeResponse, UserVerificationPolicy,
+};
+use webauthn_rs::{AuthenticationState, RegistrationState, Webauthn};
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::user::UserRepository;
+use crate::models::webauthn::*;
+use crate::utils::clock_util::{Clock, SystemClock};
+use crate::utils::url_util::PublicUrl;
+
+/// Name of the service shown by authenticators.
+const RELYING_PARTY_NAME: &str = "Darim";
+
+/// State of an authentication ceremony kept in redis, with the user who is signing in.
+#[derive(Serialize, Deserialize)]
+struct LoginState {
+    user_id: u64,
+    state: AuthenticationState,
+}
+
+/// Challenge of signing in with a passkey.
+#[derive(Serialize, Deserialize)]
+pub struct WebauthnLoginChallengeDTO {
+    /// Token of the ceremony, which is given back with the response of the authenticator.
+    pub token: String,
+    /// Options passed to `navigator.credentials.get()`.
+    pub options: RequestChallengeResponse,
+}
+
+/// Returns the relying party of the client at `PUBLIC_BASE_URL`.
+///
+/// Passkeys are bound to the domain of the client, so changing `PUBLIC_BASE_URL`
+/// to another domain invalidates every registered passkey.
+fn get_webauthn() -> Webauthn<WebauthnEphemeralConfig> {
+    let public_url = PublicUrl::from_env().expect("Invalid PUBLIC_BASE_URL");
+    Webauthn::new(WebauthnEphemeralConfig::new(
+        RELYING_PARTY_NAME,
+        public_url.origin(),
+        public_url.domain(),
+        None,
+    ))
+}
+
+/// Returns the hash of a credential id in hex.
+fn hash_credential_id(credential_id: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(credential_id))
+}
+
+pub struct WebauthnService {
+    webauthn_credential_repository: Option<WebauthnCredentialRepository>,
+    webauthn_state_repository: Option<WebauthnStateRepository>,
+    user_repository: Option<UserRepository>,
+    clock: Arc<dyn Clock>,
+}
+
+impl WebauthnService {
+    pub fn new() -> Self {
+        Self {
+            webauthn_credential_repository: None,
+            webauthn_state_repository: None,
+            user_repository: None,
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    fn webauthn_credential_repository(
+        &mut self,
+        new_repository: Option<WebauthnCredentialRepository>,
+    ) -> &WebauthnCredentialRepository {
+        match new_repository {
+            Some(_) => {
+                self.webauthn_credential_repository = new_repository;
+                self.webauthn_credential_repository.as_ref().unwrap()
+            }
+            None => self.webauthn_credential_repository.as_ref().unwrap(),
+        }
+    }
+
+    fn webauthn_state_repository(
+        &mut self,
+        new_repository: Option<WebauthnStateRepository>,
+    ) -> &mut WebauthnStateRepository {
+        match new_repository {
+            Some(_) => {
+                self.webauthn_state_repository = new_repository;
+                self.webauthn_state_repository.as_mut().unwrap()
+            }
+            None => self.webauthn_state_repository.as_mut().unwrap(),
+        }
+    }
+
+    fn user_repository(&mut self, new_repository: Option<UserRepository>) -> &UserRepository {
+        match new_repository {
+            Some(_) => {
+                self.user_repository = new_repository;
+                self.user_repository.as_ref().unwrap()
+            }
+            None => self.user_repository.as_ref().unwrap(),
+        }
+    }
+
+    /// Finds passkeys of specific user with their deserialized credentials.
+    fn find_credentials(
+        &mut self,
+        user_id: u64,
+    ) -> Result<Vec<(WebauthnCredential, Credential)>, ServiceError> {
+        let fallback_repository = some_if_true!(self.webauthn_credential_repository.is_none() => WebauthnCredentialRepository::new());
+        let credentials = self
+            .webauthn_credential_repository(fallback_repository)
+            .find_all_by_user_id(user_id)?;
+
+        credentials
+            .into_iter()
+            .map(
+                |credential| match serde_json::from_str(&credential.credential) {
+                    Ok(deserialized_credential) => Ok((credential, deserialized_credential)),
+                    Err(_) => Err(get_service_error(ServiceError::InvalidFormat)),
+                },
+            )
+            .collect()
+    }
+
+    /// Takes the state of a ceremony, which is deleted so that it is not used twice.
+    fn take_state(&mut self, key: &str) -> Result<String, ServiceError> {
+        let fallback_repository = some_if_true!(self.webauthn_state_repository.is_none() => WebauthnStateRepository::new());
+        let webauthn_state_repository = self.webauthn_state_repository(fallback_repository);
+        let serialized_state = webauthn_state_repository.find(key)?;
+        webauthn_state_repository.delete(key)?;
+        Ok(serialized_state)
+    }
+
+    /// Saves the state of a ceremony.
+    fn save_state<T: Serialize>(&mut self, key: &str, state: &T) -> Result<bool, ServiceError> {
+        let serialized_state = if let Ok(serialized_state) = serde_json::to_string(state) {
+            serialized_state
+        } else {
+            return Err(get_service_error(ServiceError::InvalidFormat));
+        };
+
+        let fallback_repository = some_if_true!(self.webauthn_state_repository.is_none() => WebauthnStateRepository::new());
+        self.webauthn_state_repository(fallback_repository)
+            .save(key, &serialized_state)
+    }
+
+    /// Lists passkeys of specific user.
+    pub fn get_list(&mut self, user_id: u64) -> Result<Vec<WebauthnCredentialDTO>, ServiceError> {
+        let fallback_repository = some_if_true!(self.webauthn_credential_repository.is_none() => WebauthnCredentialRepository::new());
+        let credentials = self
+            .webauthn_credential_repository(fallback_repository)
+            .find_all_by_user_id(user_id)?;
+
+        Ok(credentials
+            .into_iter()
+            .map(|credential| WebauthnCredentialDTO {
+                id: credential.id,
+                created_at: credential.created_at,
+                last_used_at: credential.last_used_at,
+            })
+            .collect())
+    }
+
+    /// Starts to register a new passkey of specific user, and returns options passed to
+    /// `navigator.credentials.create()`.
+    ///
+    /// Passkeys the user has registered are excluded, so that an authenticator is registered once.
+    pub fn start_registration(
+        &mut self,
+        user_id: u64,
+    ) -> Result<CreationChallengeResponse, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            self.user_repository(fallback_repository)
+                .find_by_id(user_id)?
+        };
+        let exclude_credentials = self
+            .find_credentials(user_id)?
+            .into_iter()
+            .map(|(_, credential)| credential.cred_id)
+            .collect();
+
+        let (options, state) = match get_webauthn().generate_challenge_register_options(
+            user_id.to_be_bytes().to_vec(),
+            user.email,
+            user.name,
+            Some(exclude_credentials),
+            Some(UserVerificationPolicy::Required),
+            None,
+        ) {
+            Ok(challenge) => challenge,
+            Err(_) => return Err(get_service_error(ServiceError::InternalServerError)),
+        };
+
+        self.save_state(&get_registration_state_key(user_id), &state)?;
+        Ok(options)
+    }
+
+    /// Finishes registering a new passkey of specific user by the response of the authenticator.
+    pub fn finish_registration(
+        &mut self,
+        user_id: u64,
+        response: &RegisterPublicKeyCredential,
+    ) -> Result<bool, ServiceError> {
+        let state: RegistrationState = {
+            let serialized_state = self.take_state(&get_registration_state_key(user_id))?;
+            if let Ok(deserialized_state) = serde_json::from_str(&serialized_state) {
+                deserialized_state
+            } else {
+                return Err(get_service_error(ServiceError::InvalidFormat));
+            }
+        };
+
+        let credential = match get_webauthn().register_credential(response, &state, |_| Ok(false)) {
+            Ok((credential, _)) => credential,
+            Err(_) => return Err(get_service_error(ServiceError::Unauthorized)),
+        };
+        let credential_id_hash = hash_credential_id(&credential.cred_id);
+        let serialized_credential =
+            if let Ok(serialized_credential) = serde_json::to_string(&credential) {
+                serialized_credential
+            } else {
+                return Err(get_service_error(ServiceError::InvalidFormat));
+            };
+
+        let fallback_repository = some_if_true!(self.webauthn_credential_repository.is_none() => WebauthnCredentialRepository::new());
+        let webauthn_credential_repository =
+            self.webauthn_credential_repository(fallback_repository);
+        if webauthn_credential_repository.exists_by_credential_id_hash(&credential_id_hash)? {
+            return Err(get_service_error(ServiceError::DuplicatedKey));
+        }
+        webauthn_credential_repository.create(user_id, &credential_id_hash, &serialized_credential)
+    }
+
+    /// Starts to sign in with a passkey of the user specified by email.
+    ///
+    /// It fails with `Unauthorized` if the user does not exist or has no passkey,
+    /// in which case the client falls back to the password.
+    pub fn start_login(&mut self, email: &str) -> Result<WebauthnLoginChallengeDTO, ServiceError> {
+        let user = {
+            let fallback_repository =
+                some_if_true!(self.user_repository.is_none() => UserRepository::new());
+            match self
+                .user_repository(fallback_repository)
+                .find_by_email(email)
+            {
+                Ok(user) => user,
+                Err(ServiceError::NotFound(_)) => {
+                    return Err(get_service_error(ServiceError::Unauthorized))
+                }
+                Err(error) => return Err(error),
+            }
+        };
+
+        let credentials: Vec<Credential> = self
+            .find_credentials(user.id)?
+            .into_iter()
+            .map(|(_, credential)| credential)
+            .collect();
+        if credentials.is_empty() {
+            return Err(get_service_error(ServiceError::Unauthorized));
+        }
+
+        let (options, state) = match get_webauthn().generate_challenge_authenticate(credentials) {
+            Ok(challenge) => challenge,
+            Err(_) => return Err(get_service_error(ServiceError::InternalServerError)),
+        };
+
+        let token: String = thread_rng().sample_iter(&Alphanumeric).take(32).collect();
+        self.save_state(
+            &get_login_state_key(&token),
+            &LoginState {
+                user_id: user.id,
+                state,
+            },
+        )?;
+
+        Ok(WebauthnLoginChallengeDTO { token, options })
+    }
+
+    /// Finishes signing in with a passkey by the response of the authenticator,
+    /// and returns id of the user who has signed in.
+    ///
+    /// The signature counter of the passkey is updated, so that a cloned authenticator is detected.
+    pub fn finish_login(
+        &mut self,
+        token: &str,
+        response: &PublicKeyCredential,
+    ) -> Result<u64, ServiceError> {
+        let login_state: LoginState = {
+            let serialized_state = self.take_state(&get_login_state_key(token))?;
+            if let Ok(deserialized_state) = serde_json::from_str(&serialized_state) {
+                deserialized_state
+            } else {
+                return Err(get_service_error(ServiceError::InvalidFormat));
+            }
+        };
+
+        let (credential_id, authenticator_data) =
+            match get_webauthn().authenticate_credential(response, &login_state.state) {
+                Ok(result) => result,
+                Err(_) => return Err(get_service_error(ServiceError::Unauthorized)),
+            };
+
+        let (credential, mut deserialized_credential) = match self
+            .find_credentials(login_state.user_id)?
+            .into_iter()
+            .find(|(_, credential)| credential.cred_id == credential_id)
+        {
+            Some(credential) => credential,
+            None => return Err(get_service_error(ServiceError::Unauthorized)),
+        };
+
+        deserialized_credential.counter = authenticator_data.counter;
+        let serialized_credential =
+            if let Ok(serialized_credential) = serde_json::to_string(&deserialized_credential) {
+                serialized_credential
+            } else {
+                return Err(get_service_error(ServiceError::InvalidFormat));
+            };
+        let now = self.clock.now().naive_utc();
+        self.webauthn_credential_repository(None)
+            .update_credential(credential.id, &serialized_credential, &now)?;
+
+        Ok(login_state.user_id)
+    }
+
+    /// Deletes a passkey of specific user.
+    pub fn delete(&mut self, id: u64, user_id: u64) -> Result<bool, ServiceError> {
+        let fallback_repository = some_if_true!(self.webauthn_credential_repository.is_none() => WebauthnCredentialRepository::new());
+        self.webauthn_credential_repository(fallback_repository)
+            .delete(id, user_id)
+    }
+}
+
+impl Default for WebauthnService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+use crate::models::user::MockUserRepositoryTrait as UserRepository;
+#[cfg(test)]
+use crate::models::webauthn::MockWebauthnCredentialRepositoryTrait as WebauthnCredentialRepository;
+#[cfg(test)]
+use crate::models::webauthn::MockWebauthnStateRepositoryTrait as WebauthnStateRepository;
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use mockall::predicate::*;
+
+    use super::*;
+    use crate::models::user::{MockUserRepositoryTrait, User};
+    use crate::models::webauthn::{
+        MockWebauthnCredentialRepositoryTrait, MockWebauthnStateRepositoryTrait,
+    };
+
+    impl WebauthnService {
+        pub fn new_with_repository(
+            webauthn_credential_repository: WebauthnCredentialRepository,
+            webauthn_state_repository: WebauthnStateRepository,
+            user_repository: UserRepository,
+        ) -> Self {
+            Self {
+                webauthn_credential_repository: Some(webauthn_credential_repository),
+                webauthn_state_repository: Some(webauthn_state_repository),
+                user_repository: Some(user_repository),
+                clock: Arc::new(SystemClock),
+            }
+        }
+    }
+
+    #[test]
+    fn test_get_list() {
+        let created_at = Utc.ymd(2020, 4, 13).and_hms(16, 31, 9).naive_utc();
+        let mut mocked_webauthn_credential_repository =
+            MockWebauthnCredentialRepositoryTrait::new();
+        mocked_webauthn_credential_repository
+            .expect_find_all_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(move |_| {
+                Ok(vec![WebauthnCredential {
+                    id: 1,
+                    user_id: 5,
+                    credential_id_hash: hash_credential_id(b"credential"),
+                    credential: String::from("{}"),
+                    created_at,
+                    last_used_at: None,
+                }])
+            });
+
+        let mut webauthn_service = WebauthnService::new_with_repository(
+            mocked_webauthn_credential_repository,
+            MockWebauthnStateRepositoryTrait::new(),
+            MockUserRepositoryTrait::new(),
+        );
+
+        assert_eq!(
+            webauthn_service.get_list(5).unwrap(),
+            vec![WebauthnCredentialDTO {
+                id: 1,
+                created_at,
+                last_used_at: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_start_login_without_passkeys() {
+        let mut mocked_user_repository = MockUserRepositoryTrait::new();
+        mocked_user_repository
+            .expect_find_by_email()
+            .with(eq("park@email.com"))
+            .times(1)
+            .returning(|_| {
+                Ok(User {
+                    id: 5,
+                    name: String::from("park"),
+                    email: String::from("park@email.com"),
+                    password: String::from("password"),
+                    avatar_url: None,
+                    created_at: Utc::now().naive_utc(),
+                    updated_at: None,
+                    key_metadata: None,
+                    daily_word_goal: None,
+                    monthly_word_goal: None,
+                    role: String::from("user"),
+                    suspended_at: None,
+                })
+            });
+        mocked_user_repository
+            .expect_find_by_email()
+            .with(eq("unknown@email.com"))
+            .times(1)
+            .returning(|email| Err(ServiceError::NotFound(email.to_string())));
+
+        let mut mocked_webauthn_credential_repository =
+            MockWebauthnCredentialRepositoryTrait::new();
+        mocked_webauthn_credential_repository
+            .expect_find_all_by_user_id()
+            .with(eq(5))
+            .times(1)
+            .returning(|_| Ok(vec![]));
+
+        let mut mocked_webauthn_state_repository = MockWebauthnStateRepositoryTrait::new();
+        mocked_webauthn_state_repository.expect_save().times(0);
+
+        let mut webauthn_service = WebauthnService::new_with_repository(
+            mocked_webauthn_credential_repository,
+            mocked_webauthn_state_repository,
+            mocked_user_repository,
+        );
+
+        assert!(matches!(
+            webauthn_service.start_login("park@email.com"),
+            Err(ServiceError::Unauthorized)
+        ));
+        assert!(matches!(
+            webauthn_service.start_login("unknown@email.com"),
+            Err(ServiceError::Unauthorized)
+        ));
+    }
+}
@@ -0,0 +1,245 @@
+use diesel::prelude::*;
+use diesel::sql_types::Varchar;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+use crate::models::connection;
+use crate::utils::email_util::SmtpSender;
+
+/// Time limit of each probe.
+const PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Directory of the migrations applied by `diesel migration run`.
+const MIGRATIONS_DIRECTORY: &str = "migrations";
+
+/// Path of the sendmail binary used by `email_util` without an SMTP relay.
+const SENDMAIL_PATH: &str = "/usr/sbin/sendmail";
+
+/// Result of a probe on an external dependency.
+pub struct CheckResult {
+    pub name: &'static str,
+    pub result: Result<String, String>,
+}
+
+impl CheckResult {
+    pub fn to_dto(&self) -> CheckResultDTO {
+        let (passed, message) = match &self.result {
+            Ok(message) => (true, message.clone()),
+            Err(message) => (false, message.clone()),
+        };
+        CheckResultDTO {
+            name: self.name.to_string(),
+            passed,
+            message,
+        }
+    }
+}
+
+/// Result of a probe using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct CheckResultDTO {
+    pub name: String,
+    pub passed: bool,
+    pub message: String,
+}
+
+/// Results of every probe using between api gateway and the service.
+#[derive(Serialize, Deserialize)]
+pub struct SelftestDTO {
+    /// Whether every probe passed.
+    pub passed: bool,
+    pub checks: Vec<CheckResultDTO>,
+}
+
+#[derive(QueryableByName)]
+struct MigrationVersion {
+    #[sql_type = "Varchar"]
+    version: String,
+}
+
+/// Runs a probe in a separate thread, and fails it if it does not finish in `timeout`.
+fn probe<F>(name: &'static str, timeout: Duration, check: F) -> CheckResult
+where
+    F: FnOnce() -> Result<String, String> + Send + 'static,
+{
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let _ = sender.send(check());
+    });
+
+    let result = match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => Err(format!("timed out after {:?}", timeout)),
+        Err(RecvTimeoutError::Disconnected) => Err(String::from("probe panicked")),
+    };
+
+    CheckResult { name, result }
+}
+
+/// Returns versions of the migrations not applied yet.
+fn get_pending_migrations(available: &[String], applied: &[String]) -> Vec<String> {
+    available
+        .iter()
+        .filter(|version| !applied.contains(version))
+        .cloned()
+        .collect()
+}
+
+/// Connects to the database and checks that every migration has been applied.
+fn check_database() -> Result<String, String> {
+    let conn = connection::try_connect_rdb()?;
+    let applied: Vec<String> = diesel::sql_query("SELECT version FROM __diesel_schema_migrations")
+        .load::<MigrationVersion>(&conn)
+        .map_err(|error| format!("Failed to read migration status: {}", error))?
+        .into_iter()
+        .map(|migration| migration.version)
+        .collect();
+
+    let mut available: Vec<String> = fs::read_dir(MIGRATIONS_DIRECTORY)
+        .map_err(|error| format!("Failed to read {}: {}", MIGRATIONS_DIRECTORY, error))?
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .filter_map(|name| {
+            name.split('_')
+                .next()
+                .map(|version| version.replace("-", ""))
+        })
+        .collect();
+    available.sort();
+
+    let pending = get_pending_migrations(&available, &applied);
+    if pending.is_empty() {
+        Ok(format!("{} migrations applied", applied.len()))
+    } else {
+        Err(format!("pending migrations: {}", pending.join(", ")))
+    }
+}
+
+/// Connects to redis and sends `PING`.
+fn check_redis() -> Result<String, String> {
+    let mut conn = connection::try_connect_redis()?;
+    redis::cmd("PING")
+        .query::<String>(&mut conn)
+        .map_err(|error| format!("Failed to ping redis: {}", error))
+}
+
+/// Checks the sender address and the SMTP relay or the sendmail binary, without sending any email.
+fn check_email() -> Result<String, String> {
+    let email_address = env::var("EMAIL_ADDRESS").map_err(|_| "EMAIL_ADDRESS not found")?;
+    email_address
+        .parse::<lettre::message::Mailbox>()
+        .map_err(|_| format!("Invalid EMAIL_ADDRESS: {}", email_address))?;
+
+    if let Some(sender) = SmtpSender::from_env() {
+        sender
+            .test_connection()
+            .map_err(|error| format!("Failed to connect to SMTP relay: {}", error))?;
+        return Ok(format!("sending as {} through SMTP", email_address));
+    }
+
+    let metadata = fs::metadata(SENDMAIL_PATH)
+        .map_err(|error| format!("Failed to find {}: {}", SENDMAIL_PATH, error))?;
+    if metadata.permissions().mode() & 0o111 == 0 {
+        return Err(format!("{} is not executable", SENDMAIL_PATH));
+    }
+
+    Ok(format!("sending as {}", email_address))
+}
+
+/// Opens the storage of files and checks that a file can be written, read, and deleted.
+fn check_storage() -> Result<String, String> {
+    connection::try_connect_storage()?.check()
+}
+
+/// Runs every probe, and returns the results.
+pub fn run_probes() -> Vec<CheckResult> {
+    vec![
+        probe("database", PROBE_TIMEOUT, check_database),
+        probe("redis", PROBE_TIMEOUT, check_redis),
+        probe("email", PROBE_TIMEOUT, check_email),
+        probe("storage", PROBE_TIMEOUT, check_storage),
+    ]
+}
+
+/// Runs every probe, and returns the results for `POST /admin/selftest`.
+pub fn run_selftest() -> SelftestDTO {
+    let results = run_probes();
+    SelftestDTO {
+        passed: results.iter().all(|check| check.result.is_ok()),
+        checks: results.iter().map(CheckResult::to_dto).collect(),
+    }
+}
+
+/// Runs every probe, prints the results as a table, and returns the exit code.
+///
+/// It returns non-zero if any probe fails.
+pub fn run() -> i32 {
+    let results = run_probes();
+
+    for CheckResult { name, result } in &results {
+        match result {
+            Ok(message) => println!("PASS  {:<10} {}", name, message),
+            Err(message) => println!("FAIL  {:<10} {}", name, message),
+        }
+    }
+
+    if results.iter().all(|check| check.result.is_ok()) {
+        0
+    } else {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_probe_times_out() {
+        let check = probe("slow", Duration::from_millis(10), || {
+            thread::sleep(Duration::from_secs(1));
+            Ok(String::from("done"))
+        });
+
+        assert!(check.result.is_err());
+    }
+
+    #[test]
+    fn test_check_result_to_dto() {
+        let passed = CheckResult {
+            name: "redis",
+            result: Ok(String::from("PONG")),
+        }
+        .to_dto();
+        assert_eq!(passed.name, "redis");
+        assert!(passed.passed);
+        assert_eq!(passed.message, "PONG");
+
+        let failed = probe("slow", Duration::from_millis(10), || {
+            thread::sleep(Duration::from_secs(1));
+            Ok(String::from("done"))
+        })
+        .to_dto();
+        assert!(!failed.passed);
+        assert_eq!(failed.message, "timed out after 10ms");
+    }
+
+    #[test]
+    fn test_get_pending_migrations() {
+        let available = vec![
+            String::from("20200404115803"),
+            String::from("20261015010000"),
+        ];
+        let applied = vec![String::from("20200404115803")];
+
+        assert_eq!(
+            get_pending_migrations(&available, &applied),
+            vec![String::from("20261015010000")]
+        );
+    }
+}
@@ -0,0 +1,49 @@
+#[cfg(test)]
+use chrono::Duration;
+use chrono::{DateTime, Utc};
+#[cfg(test)]
+use std::sync::Mutex;
+
+/// Source of the current time for expiry logic, so that tests can control the time.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Clock reading the system time.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Clock that stands still until a test advances it.
+#[cfg(test)]
+pub struct TestClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+#[cfg(test)]
+impl TestClock {
+    /// Creates a clock standing at `now`.
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Moves the clock forward by `duration`.
+    pub fn advance(&self, duration: Duration) {
+        let mut now = self.now.lock().unwrap();
+        *now = *now + duration;
+    }
+}
+
+#[cfg(test)]
+impl Clock for TestClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
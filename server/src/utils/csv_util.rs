@@ -0,0 +1,59 @@
+/// Characters with which a spreadsheet starts a formula at the beginning of a cell.
+const FORMULA_PREFIXES: [char; 6] = ['=', '+', '-', '@', '\t', '\r'];
+
+/// Escapes a field of CSV.
+///
+/// A field starting with a character of a formula is prefixed with `'`, so that a spreadsheet
+/// shows it as text instead of evaluating it. A field containing a comma, a quote, or a line
+/// break is quoted, with its quotes doubled.
+pub fn escape_field(field: &str) -> String {
+    let field = if field.starts_with(&FORMULA_PREFIXES[..]) {
+        format!("'{}", field)
+    } else {
+        field.to_string()
+    };
+
+    if field.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field
+    }
+}
+
+/// Joins escaped fields to a row of CSV, which ends with CRLF as RFC 4180 does.
+pub fn to_row(fields: &[&str]) -> String {
+    let fields: Vec<String> = fields.iter().map(|field| escape_field(field)).collect();
+    format!("{}\r\n", fields.join(","))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape_field() {
+        assert_eq!(escape_field("Mozilla/5.0"), "Mozilla/5.0");
+        assert_eq!(escape_field(""), "");
+        assert_eq!(escape_field("a, b"), "\"a, b\"");
+        assert_eq!(escape_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape_field("line\nbreak"), "\"line\nbreak\"");
+    }
+
+    #[test]
+    fn test_escape_formula() {
+        assert_eq!(
+            escape_field("=HYPERLINK(\"x\")"),
+            "\"'=HYPERLINK(\"\"x\"\")\""
+        );
+        assert_eq!(escape_field("+1"), "'+1");
+        assert_eq!(escape_field("-1"), "'-1");
+        assert_eq!(escape_field("@SUM(A1)"), "'@SUM(A1)");
+        assert_eq!(escape_field("\tcmd"), "'\tcmd");
+    }
+
+    #[test]
+    fn test_to_row() {
+        assert_eq!(to_row(&["id", "a,b", "=1"]), "id,\"a,b\",'=1\r\n");
+        assert_eq!(to_row(&[]), "\r\n");
+    }
+}
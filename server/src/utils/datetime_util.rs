@@ -0,0 +1,111 @@
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+
+/// Formats naive datetime in UTC as RFC 3339 UTC datetime.
+pub fn to_rfc3339(datetime: &NaiveDateTime) -> String {
+    DateTime::<Utc>::from_utc(*datetime, Utc).to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+/// Parses RFC 3339 datetime to naive datetime in UTC.
+/// Naive datetime serialized before RFC 3339 is accepted as it is.
+pub fn parse(value: &str) -> Option<NaiveDateTime> {
+    match DateTime::parse_from_rfc3339(value) {
+        Ok(datetime) => Some(datetime.naive_utc()),
+        Err(_) => NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").ok(),
+    }
+}
+
+/// Serializes naive datetime in UTC as RFC 3339, with `#[serde(with = "datetime_util::rfc3339")]`.
+pub mod rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        datetime: &NaiveDateTime,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_rfc3339(datetime))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<NaiveDateTime, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse(&value).ok_or_else(|| D::Error::custom(format!("invalid datetime `{}`", value)))
+    }
+}
+
+/// Serializes optional naive datetime in UTC as RFC 3339,
+/// with `#[serde(default, with = "datetime_util::option_rfc3339")]`.
+pub mod option_rfc3339 {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        datetime: &Option<NaiveDateTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match datetime {
+            Some(datetime) => serializer.serialize_some(&to_rfc3339(datetime)),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<NaiveDateTime>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(value) => parse(&value)
+                .map(Some)
+                .ok_or_else(|| D::Error::custom(format!("invalid datetime `{}`", value))),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+    use serde::{Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Item {
+        #[serde(with = "rfc3339")]
+        created_at: NaiveDateTime,
+        #[serde(default, with = "option_rfc3339")]
+        updated_at: Option<NaiveDateTime>,
+    }
+
+    #[test]
+    fn test_rfc3339() {
+        let item = Item {
+            created_at: NaiveDate::from_ymd(2020, 4, 13).and_hms_micro(16, 31, 9, 123456),
+            updated_at: None,
+        };
+
+        let serialized_item = serde_json::to_string(&item).unwrap();
+        assert_eq!(
+            serialized_item,
+            r#"{"created_at":"2020-04-13T16:31:09.123456Z","updated_at":null}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<Item>(&serialized_item).unwrap(),
+            item
+        );
+
+        // Naive datetime serialized before, and RFC 3339 in another offset, are accepted.
+        assert_eq!(
+            serde_json::from_str::<Item>(concat!(
+                r#"{"created_at":"2020-04-13T16:31:09.123456","#,
+                r#""updated_at":"2020-04-14T01:31:09+09:00"}"#
+            ))
+            .unwrap(),
+            Item {
+                updated_at: Some(NaiveDate::from_ymd(2020, 4, 13).and_hms(16, 31, 9)),
+                ..item
+            }
+        );
+        assert!(serde_json::from_str::<Item>(r#"{"created_at":"yesterday"}"#).is_err());
+    }
+}
@@ -1,24 +1,256 @@
+use hyperx::header::{Formatter, Header, RawLike};
 use lettre::message::header::ContentType;
-use lettre::message::{Message, SinglePart};
+use lettre::message::{Message, MultiPart, SinglePart};
 use lettre::transport::sendmail::SendmailTransport;
-use lettre::Transport;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{SmtpTransport, Transport};
 use std::env;
+use std::fmt;
 
 use crate::models::error::ServiceError;
 
-pub fn send_email(to: &str, subject: &str, body: &str) -> Result<bool, ServiceError> {
+/// Sender of emails.
+///
+/// An email with `unsubscribe_url` is sent with `List-Unsubscribe` and `List-Unsubscribe-Post`
+/// headers, so that mail clients can unsubscribe it by a click as RFC 8058.
+pub trait EmailSender {
+    fn send(
+        &self,
+        to: &str,
+        subject: &str,
+        body: &str,
+        unsubscribe_url: &Option<String>,
+    ) -> Result<bool, ServiceError>;
+}
+
+/// Sender of emails through sendmail of the host.
+pub struct SendmailSender;
+
+impl EmailSender for SendmailSender {
+    fn send(
+        &self,
+        to: &str,
+        subject: &str,
+        body: &str,
+        unsubscribe_url: &Option<String>,
+    ) -> Result<bool, ServiceError> {
+        send_email(to, subject, body, unsubscribe_url)
+    }
+}
+
+/// Sender of emails through an SMTP relay over TLS.
+///
+/// It is configured by `SMTP_HOST`, and optionally `SMTP_PORT`, `SMTP_USERNAME`, and `SMTP_PASSWORD`.
+pub struct SmtpSender {
+    transport: SmtpTransport,
+}
+
+impl SmtpSender {
+    /// Creates a sender from the environment, or returns `None` if `SMTP_HOST` is not set.
+    pub fn from_env() -> Option<Self> {
+        let host = env::var("SMTP_HOST").ok()?;
+        let mut builder = SmtpTransport::relay(&host).expect("Invalid SMTP_HOST");
+        if let Ok(port) = env::var("SMTP_PORT") {
+            builder = builder.port(port.parse().expect("Invalid SMTP_PORT"));
+        }
+        if let (Ok(username), Ok(password)) = (env::var("SMTP_USERNAME"), env::var("SMTP_PASSWORD"))
+        {
+            builder = builder.credentials(Credentials::new(username, password));
+        }
+
+        Some(Self {
+            transport: builder.build(),
+        })
+    }
+
+    /// Connects to the relay and returns whether it accepts connections, without sending any email.
+    pub fn test_connection(&self) -> Result<bool, String> {
+        self.transport
+            .test_connection()
+            .map_err(|error| error.to_string())
+    }
+}
+
+impl EmailSender for SmtpSender {
+    fn send(
+        &self,
+        to: &str,
+        subject: &str,
+        body: &str,
+        unsubscribe_url: &Option<String>,
+    ) -> Result<bool, ServiceError> {
+        let email = build_email(to, subject, body, unsubscribe_url)?;
+        match self.transport.send(&email) {
+            Ok(_) => Ok(true),
+            Err(_) => Err(ServiceError::EmailFailure(to.to_string())),
+        }
+    }
+}
+
+/// Returns the SMTP sender if `SMTP_HOST` is set, or the sendmail sender otherwise.
+pub fn get_sender() -> Box<dyn EmailSender> {
+    match SmtpSender::from_env() {
+        Some(sender) => Box::new(sender),
+        None => Box::new(SendmailSender),
+    }
+}
+
+/// Converts the HTML body of an email to plain text for clients not rendering HTML.
+///
+/// Line breaks and blocks become new lines, links are followed by their URLs,
+/// and the other tags are removed.
+pub fn to_plain_text(html: &str) -> String {
+    let mut text = String::new();
+    let mut tag = String::new();
+    let mut is_in_tag = false;
+    let mut link: Option<(String, usize)> = None;
+    for character in html.chars() {
+        match character {
+            '<' => {
+                is_in_tag = true;
+                tag.clear();
+            }
+            '>' if is_in_tag => {
+                is_in_tag = false;
+                let is_closing = tag.starts_with('/');
+                let name = tag
+                    .trim_start_matches('/')
+                    .split(|c: char| c.is_whitespace() || c == '/')
+                    .next()
+                    .unwrap_or("")
+                    .to_lowercase();
+                match name.as_str() {
+                    "br" => text.push('\n'),
+                    "div" | "h1" | "h2" | "p" if is_closing => text.push('\n'),
+                    "a" if is_closing => {
+                        if let Some((href, start)) = link.take() {
+                            if text[start..] != href {
+                                text.push_str(&format!(" ({})", href));
+                            }
+                        }
+                    }
+                    "a" => {
+                        link = tag
+                            .split("href=\"")
+                            .nth(1)
+                            .and_then(|rest| rest.split('"').next())
+                            .map(|href| (href.to_string(), text.len()));
+                    }
+                    _ => {}
+                }
+            }
+            _ if is_in_tag => tag.push(character),
+            _ => text.push(character),
+        }
+    }
+
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#x27;", "'")
+        .replace("&#39;", "'")
+        .replace("&amp;", "&")
+}
+
+/// `List-Unsubscribe` header with the URL unsubscribing the email.
+#[derive(Debug, Clone, PartialEq)]
+struct ListUnsubscribe(String);
+
+impl Header for ListUnsubscribe {
+    fn header_name() -> &'static str {
+        "List-Unsubscribe"
+    }
+
+    fn parse_header<'a, T>(raw: &'a T) -> hyperx::Result<Self>
+    where
+        T: RawLike<'a>,
+    {
+        raw.one()
+            .and_then(|line| std::str::from_utf8(line).ok())
+            .map(|line| line.trim_start_matches('<').trim_end_matches('>'))
+            .map(|url| ListUnsubscribe(url.to_string()))
+            .ok_or(hyperx::Error::Header)
+    }
+
+    fn fmt_header(&self, f: &mut Formatter) -> fmt::Result {
+        f.fmt_line(&format!("<{}>", self.0))
+    }
+}
+
+/// `List-Unsubscribe-Post` header telling the URL of `List-Unsubscribe` unsubscribes by a POST
+/// without any confirmation.
+#[derive(Debug, Clone, PartialEq)]
+struct ListUnsubscribePost;
+
+impl ListUnsubscribePost {
+    const VALUE: &'static str = "List-Unsubscribe=One-Click";
+}
+
+impl Header for ListUnsubscribePost {
+    fn header_name() -> &'static str {
+        "List-Unsubscribe-Post"
+    }
+
+    fn parse_header<'a, T>(raw: &'a T) -> hyperx::Result<Self>
+    where
+        T: RawLike<'a>,
+    {
+        match raw.one() {
+            Some(line) if line == Self::VALUE.as_bytes() => Ok(ListUnsubscribePost),
+            _ => Err(hyperx::Error::Header),
+        }
+    }
+
+    fn fmt_header(&self, f: &mut Formatter) -> fmt::Result {
+        f.fmt_line(&Self::VALUE)
+    }
+}
+
+/// Builds an email with the HTML body and its plain text alternative.
+fn build_email(
+    to: &str,
+    subject: &str,
+    body: &str,
+    unsubscribe_url: &Option<String>,
+) -> Result<Message, ServiceError> {
     let email_address = env::var("EMAIL_ADDRESS").expect("EMAIL_ADDRESS not found");
     let parsed_email_address = email_address.parse().unwrap();
-    let email = Message::builder()
+    let parsed_to = to
+        .parse()
+        .map_err(|_| ServiceError::EmailFailure(to.to_string()))?;
+    let mut builder = Message::builder()
         .from(parsed_email_address)
-        .to(to.parse().unwrap())
-        .subject(subject)
-        .singlepart(
-            SinglePart::builder()
-                .header(ContentType("text/html; charset=utf8".parse().unwrap()))
-                .body(body.to_string()),
+        .to(parsed_to)
+        .subject(subject);
+    if let Some(unsubscribe_url) = unsubscribe_url {
+        builder = builder
+            .header(ListUnsubscribe(unsubscribe_url.clone()))
+            .header(ListUnsubscribePost);
+    }
+    builder
+        .multipart(
+            MultiPart::alternative()
+                .singlepart(
+                    SinglePart::builder()
+                        .header(ContentType("text/plain; charset=utf8".parse().unwrap()))
+                        .body(to_plain_text(body)),
+                )
+                .singlepart(
+                    SinglePart::builder()
+                        .header(ContentType("text/html; charset=utf8".parse().unwrap()))
+                        .body(body.to_string()),
+                ),
         )
-        .unwrap();
+        .map_err(|_| ServiceError::EmailFailure(to.to_string()))
+}
+
+pub fn send_email(
+    to: &str,
+    subject: &str,
+    body: &str,
+    unsubscribe_url: &Option<String>,
+) -> Result<bool, ServiceError> {
+    let email = build_email(to, subject, body, unsubscribe_url)?;
 
     let sender = SendmailTransport::new();
     match sender.send(&email) {
@@ -26,3 +258,42 @@ pub fn send_email(to: &str, subject: &str, body: &str) -> Result<bool, ServiceEr
         Err(_) => Err(ServiceError::EmailFailure(to.to_string())),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_plain_text() {
+        let html = "<h1>🏕 Welcome</h1>Hello Park &amp; friends :)<br/><br/>\
+            <div style=\"padding: 10px\">a1b2c3</div>\
+            <a href=\"https://example.com/unsubscribe\">Unsubscribe</a><br/>\
+            <a href=\"https://example.com\">https://example.com</a>";
+
+        assert_eq!(
+            to_plain_text(html),
+            "🏕 Welcome\nHello Park & friends :)\n\na1b2c3\nUnsubscribe (https://example.com/unsubscribe)\nhttps://example.com"
+        );
+    }
+
+    #[test]
+    fn test_build_email_with_unsubscribe_url() {
+        env::set_var("EMAIL_ADDRESS", "darim@example.com");
+
+        let email = build_email(
+            "park@example.com",
+            "Today's writing prompt",
+            "Hello :)",
+            &Some(String::from("https://api.example.com/unsubscribe/a1b2c3")),
+        )
+        .unwrap();
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(formatted
+            .contains("List-Unsubscribe: <https://api.example.com/unsubscribe/a1b2c3>\r\n"));
+        assert!(formatted.contains("List-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n"));
+
+        let email = build_email("park@example.com", "Welcome", "Hello :)", &None).unwrap();
+        let formatted = String::from_utf8(email.formatted()).unwrap();
+        assert!(!formatted.contains("List-Unsubscribe"));
+    }
+}
@@ -0,0 +1,97 @@
+use pulldown_cmark::{html, Options, Parser};
+
+/// `Content-Security-Policy` of rendered pages, which allows no scripts at all
+/// in case something slips through the sanitization.
+pub const PAGE_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'none'; style-src 'unsafe-inline'; img-src https: data:; form-action 'self'; frame-ancestors 'none'";
+
+/// Escapes characters having special meaning in HTML.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Renders markdown to HTML, removing scripts, event handlers and other unsafe markup.
+///
+/// Raw HTML in the markdown is allowed as far as it survives the sanitization.
+pub fn render_markdown(markdown: &str) -> String {
+    let mut options = Options::empty();
+    options.insert(Options::ENABLE_TABLES);
+    options.insert(Options::ENABLE_STRIKETHROUGH);
+    options.insert(Options::ENABLE_TASKLISTS);
+
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, Parser::new_ext(markdown, options));
+    ammonia::clean(&unsafe_html)
+}
+
+/// Renders a standalone HTML page, which is not indexed by search engines.
+///
+/// # Arguments
+///
+/// * `title` - A title of the page in plaintext.
+/// * `body` - A body of the page in HTML, which must be escaped or sanitized.
+pub fn render_page(title: &str, body: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<meta name="robots" content="noindex, nofollow">
+<meta name="referrer" content="no-referrer">
+<title>{}</title>
+<style>
+body {{ max-width: 720px; margin: 0 auto; padding: 40px 20px; font-family: sans-serif; line-height: 1.6; color: #333; }}
+img {{ max-width: 100%; }}
+pre {{ overflow-x: auto; }}
+.meta {{ color: #999; }}
+</style>
+</head>
+<body>
+{}
+</body>
+</html>
+"#,
+        escape(title),
+        body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape(r#"<a href="x">Tom & 'Jerry'</a>"#),
+            "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#39;Jerry&#39;&lt;/a&gt;"
+        );
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        assert_eq!(
+            render_markdown("# Lorem\n\n**ipsum** ~~dolor~~"),
+            "<h1>Lorem</h1>\n<p><strong>ipsum</strong> <del>dolor</del></p>\n"
+        );
+
+        let rendered = render_markdown(
+            "<script>alert(1)</script>\n\n<img src=\"x.png\" onerror=\"alert(2)\">\n\n[link](javascript:alert(3))",
+        );
+        assert!(!rendered.contains("<script"));
+        assert!(!rendered.contains("onerror"));
+        assert!(!rendered.contains("javascript:"));
+    }
+}
@@ -1,13 +1,89 @@
-use actix_web::HttpResponse;
+use actix_web::dev::SizedStream;
+use actix_web::error::{ErrorInternalServerError, InternalError, JsonPayloadError};
+use actix_web::http::header::{
+    ACCEPT_RANGES, CACHE_CONTROL, CONTENT_DISPOSITION, CONTENT_RANGE, CONTENT_SECURITY_POLICY,
+    ETAG, IF_NONE_MATCH, IF_UNMODIFIED_SINCE, RANGE, RETRY_AFTER,
+};
+use actix_web::http::{HeaderName, HeaderValue, StatusCode};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, NaiveDateTime};
+use futures::stream::{self, StreamExt};
 use serde::Serialize;
+use std::iter;
 
-use crate::models::error::ServiceError;
+use crate::models::attachment::AttachmentFile;
+use crate::models::error::{FieldError, ServiceError};
+use crate::models::export_job::ByteRange;
+use crate::models::login_attempt::RateLimit;
+use crate::models::post_audit::AuditContext;
+use crate::utils::html_util;
+use crate::utils::meta_util::{ResponseMeta, WithMeta};
+use crate::utils::pagination_util::{Batch, Page, PageMeta};
+
+/// Content type of JSON responses.
+const JSON_CONTENT_TYPE: &str = "application/json; charset=utf-8";
+
+/// Content type of HTML responses.
+const HTML_CONTENT_TYPE: &str = "text/html; charset=utf-8";
+
+/// Header of the number of requests allowed in the window of a rate-limited scope.
+const RATE_LIMIT_LIMIT: &str = "ratelimit-limit";
+
+/// Header of the number of requests remaining in the window of a rate-limited scope.
+const RATE_LIMIT_REMAINING: &str = "ratelimit-remaining";
+
+/// Header of seconds until the limit of a rate-limited scope is reset.
+const RATE_LIMIT_RESET: &str = "ratelimit-reset";
+
+/// Metadata in `meta` of a response.
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Meta {
+    Page(PageMeta),
+    Warnings(ResponseMeta),
+}
 
 /// HTTP response of the API.
 #[derive(Serialize)]
 pub struct ServiceResponse<T> {
     data: Option<T>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    meta: Option<Meta>,
     error: Option<String>,
+    /// Errors of each field, if the error is on fields of the request.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<FieldError>>,
+    /// Seconds to wait before retrying, if there have been too many attempts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    retry_after: Option<u64>,
+    /// Unix timestamp when the limit is reset, if there have been too many attempts.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reset_at: Option<i64>,
+    /// Current version of the item, if the request conflicts with it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_version: Option<u32>,
+}
+
+/// Result of an item in HTTP response of a request on several items.
+#[derive(Serialize)]
+pub struct ItemResponse<T> {
+    status: u16,
+    data: Option<T>,
+    error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fields: Option<Vec<FieldError>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current_version: Option<u32>,
+}
+
+/// Data of HTTP response of a batch of a bulk request.
+#[derive(Serialize)]
+struct BatchResponse<T> {
+    results: Vec<ItemResponse<T>>,
+    processed: usize,
+    remaining: usize,
+    next_cursor: Option<usize>,
 }
 
 impl<T> ServiceResponse<T> {
@@ -15,42 +91,855 @@ impl<T> ServiceResponse<T> {
     fn ok(data: T) -> Self {
         ServiceResponse {
             data: Some(data),
+            meta: None,
             error: None,
+            fields: None,
+            retry_after: None,
+            reset_at: None,
+            current_version: None,
         }
     }
 
     /// Creates a response containing error.
     fn err(error: ServiceError) -> Self {
+        let message = format!("{}", error);
         ServiceResponse {
             data: None,
-            error: Some(format!("{}", error)),
+            meta: None,
+            error: Some(message),
+            retry_after: get_retry_after(&error),
+            reset_at: None,
+            current_version: get_current_version(&error),
+            fields: get_field_errors(error),
+        }
+    }
+}
+
+impl<T> ServiceResponse<Vec<T>> {
+    /// Creates a response containing a page of a list.
+    fn page(page: Page<T>) -> Self {
+        ServiceResponse {
+            data: Some(page.items),
+            meta: Some(Meta::Page(page.meta)),
+            error: None,
+            fields: None,
+            retry_after: None,
+            reset_at: None,
+            current_version: None,
+        }
+    }
+}
+
+/// Returns 200 OK HTTP response that contains `data`.
+///
+/// # Arguments
+///
+/// * `data` - The data to be contained in response.
+pub fn ok<T: Serialize>(data: T) -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(JSON_CONTENT_TYPE)
+        .json(ServiceResponse::ok(data))
+}
+
+/// Returns 201 Created HTTP response that contains `data`.
+///
+/// # Arguments
+///
+/// * `data` - The data to be contained in response.
+pub fn created<T: Serialize>(data: T) -> HttpResponse {
+    HttpResponse::Created()
+        .content_type(JSON_CONTENT_TYPE)
+        .json(ServiceResponse::ok(data))
+}
+
+/// Returns HTTP response that contains a rendered HTML page.
+///
+/// # Arguments
+///
+/// * `status_code` - HTTP status code.
+/// * `page` - An HTML page rendered by `html_util`.
+pub fn html(status_code: StatusCode, page: String) -> HttpResponse {
+    HttpResponse::build(status_code)
+        .content_type(HTML_CONTENT_TYPE)
+        .header(
+            CONTENT_SECURITY_POLICY,
+            html_util::PAGE_CONTENT_SECURITY_POLICY,
+        )
+        .header(CACHE_CONTROL, "no-store")
+        .body(page)
+}
+
+/// Returns HTTP error response whose status code is determined by the error.
+///
+/// # Arguments
+///
+/// * `error` - An error of the service.
+pub fn err(error: ServiceError) -> HttpResponse {
+    HttpResponse::from(error)
+}
+
+/// Returns the status code of the error, and the error to be shown to the client,
+/// which hides internal errors.
+fn get_error_status(error: ServiceError) -> (StatusCode, ServiceError) {
+    match error {
+        ServiceError::NotFound(_) => (StatusCode::NOT_FOUND, error),
+        ServiceError::InvalidArgument | ServiceError::InvalidFormat => {
+            (StatusCode::BAD_REQUEST, error)
+        }
+        ServiceError::InvalidFields(_) => (StatusCode::UNPROCESSABLE_ENTITY, error),
+        ServiceError::DuplicatedKey | ServiceError::Conflict(_) => (StatusCode::CONFLICT, error),
+        ServiceError::Locked(_) => (StatusCode::LOCKED, error),
+        ServiceError::Unauthorized => (StatusCode::UNAUTHORIZED, error),
+        ServiceError::Suspended(_) => (StatusCode::FORBIDDEN, error),
+        ServiceError::TooManyRequests(_) => (StatusCode::TOO_MANY_REQUESTS, error),
+        ServiceError::PayloadTooLarge => (StatusCode::PAYLOAD_TOO_LARGE, error),
+        ServiceError::UnsupportedMediaType => (StatusCode::UNSUPPORTED_MEDIA_TYPE, error),
+        ServiceError::RangeNotSatisfiable(_) => (StatusCode::RANGE_NOT_SATISFIABLE, error),
+        _ => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            ServiceError::InternalServerError,
+        ),
+    }
+}
+
+/// Returns errors of each field in the error, if the error is on fields of the request.
+fn get_field_errors(error: ServiceError) -> Option<Vec<FieldError>> {
+    match error {
+        ServiceError::InvalidFields(field_errors) => Some(field_errors),
+        _ => None,
+    }
+}
+
+/// Returns seconds to wait before retrying, if the error is on too many attempts.
+fn get_retry_after(error: &ServiceError) -> Option<u64> {
+    match error {
+        ServiceError::TooManyRequests(seconds) => Some(*seconds),
+        _ => None,
+    }
+}
+
+/// Returns the current version of the item, if the error is on a conflict with it.
+fn get_current_version(error: &ServiceError) -> Option<u32> {
+    match error {
+        ServiceError::Conflict(version) => Some(*version),
+        _ => None,
+    }
+}
+
+/// Returns HTTP error response whose status code is determined by the error.
+///
+/// # Arguments
+///
+/// * `error` - An error of the service.
+/// * `reset_at` - Unix timestamp when the limit is reset, which is contained if there have been
+/// too many attempts.
+fn get_err_response(error: ServiceError, reset_at: Option<i64>) -> HttpResponse {
+    let (status_code, error) = get_error_status(error);
+
+    let mut response = HttpResponse::build(status_code);
+    let retry_after = get_retry_after(&error);
+    if let Some(seconds) = retry_after {
+        response.header(RETRY_AFTER, seconds.to_string());
+    }
+    if let ServiceError::RangeNotSatisfiable(size) = &error {
+        response.header(CONTENT_RANGE, format!("bytes */{}", size));
+    }
+    response
+        .content_type(JSON_CONTENT_TYPE)
+        .json(ServiceResponse::<()> {
+            reset_at: reset_at.filter(|_| retry_after.is_some()),
+            ..ServiceResponse::err(error)
+        })
+}
+
+impl From<ServiceError> for HttpResponse {
+    fn from(error: ServiceError) -> Self {
+        get_err_response(error, None)
+    }
+}
+
+/// Returns configuration of JSON extractor, which responds `415 Unsupported Media Type`
+/// if the request is not `application/json` or `+json`, and `400 Bad Request` if the body is invalid.
+pub fn get_json_config() -> web::JsonConfig {
+    web::JsonConfig::default().error_handler(|error, _| {
+        let status_code = match error {
+            JsonPayloadError::ContentType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            JsonPayloadError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::BAD_REQUEST,
+        };
+        let message = format!("{}", error);
+        let response = HttpResponse::build(status_code)
+            .content_type(JSON_CONTENT_TYPE)
+            .json(ServiceResponse::<()> {
+                data: None,
+                meta: None,
+                error: Some(message.clone()),
+                fields: None,
+                retry_after: None,
+                reset_at: None,
+                current_version: None,
+            });
+        InternalError::from_response(message, response).into()
+    })
+}
+
+/// Converts service result to HTTP response, and returns it.
+///
+/// # Arguments
+///
+/// * `result` - A result of the service.
+pub fn respond<T: Serialize>(result: Result<T, ServiceError>) -> HttpResponse {
+    match result {
+        Ok(data) => ok(data),
+        Err(error) => err(error),
+    }
+}
+
+/// Converts service result containing warnings to HTTP response like `respond`, and returns it.
+///
+/// The warnings are contained in `meta`, which is omitted if there are no warnings.
+///
+/// # Arguments
+///
+/// * `result` - A result of the service.
+pub fn respond_with_meta<T: Serialize>(result: Result<WithMeta<T>, ServiceError>) -> HttpResponse {
+    match result {
+        Ok(WithMeta { data, meta }) => {
+            HttpResponse::Ok()
+                .content_type(JSON_CONTENT_TYPE)
+                .json(ServiceResponse {
+                    meta: Some(meta)
+                        .filter(|meta| !meta.is_empty())
+                        .map(Meta::Warnings),
+                    ..ServiceResponse::ok(data)
+                })
         }
+        Err(error) => err(error),
     }
 }
 
-/// Converts service result to HTTP response, and return it.
+/// Converts service result of a rate-limited scope to HTTP response like `respond`, with
+/// `RateLimit-Limit`, `RateLimit-Remaining` and `RateLimit-Reset` headers.
+///
+/// `429 Too Many Requests` contains the unix timestamp when the limit is reset in `reset_at`.
 ///
 /// # Arguments
 ///
-/// * `data` - A result of the service.
-pub fn get_response<T: Serialize>(data: Result<T, ServiceError>) -> HttpResponse {
-    match data {
-        Ok(data) => HttpResponse::Ok().json(ServiceResponse::<T>::ok(data)),
-        Err(ServiceError::NotFound(key)) => {
-            HttpResponse::NotFound().json(ServiceResponse::<T>::err(ServiceError::NotFound(key)))
+/// * `result` - A result of the service.
+/// * `rate_limit` - The rate limit after the request, or `None` if it is unknown.
+pub fn respond_with_rate_limit<T: Serialize>(
+    result: Result<T, ServiceError>,
+    rate_limit: Option<RateLimit>,
+) -> HttpResponse {
+    let mut response = match result {
+        Ok(data) => ok(data),
+        Err(error) => get_err_response(error, rate_limit.as_ref().map(|limit| limit.reset_at)),
+    };
+
+    if let Some(rate_limit) = rate_limit {
+        let headers = response.headers_mut();
+        headers.insert(
+            HeaderName::from_static(RATE_LIMIT_LIMIT),
+            HeaderValue::from(rate_limit.limit),
+        );
+        headers.insert(
+            HeaderName::from_static(RATE_LIMIT_REMAINING),
+            HeaderValue::from(rate_limit.remaining),
+        );
+        headers.insert(
+            HeaderName::from_static(RATE_LIMIT_RESET),
+            HeaderValue::from(rate_limit.reset_after),
+        );
+    }
+    response
+}
+
+/// Converts service result containing a page to HTTP response, and returns it.
+///
+/// The items are contained in `data`, and the pagination metadata in `meta`.
+///
+/// # Arguments
+///
+/// * `result` - A result of the service.
+pub fn respond_page<T: Serialize>(result: Result<Page<T>, ServiceError>) -> HttpResponse {
+    match result {
+        Ok(page) => HttpResponse::Ok()
+            .content_type(JSON_CONTENT_TYPE)
+            .json(ServiceResponse::page(page)),
+        Err(error) => err(error),
+    }
+}
+
+/// Converts a result of an item to its result in HTTP response, which has the status code
+/// and the error in the same form as a response.
+fn get_item_response<T>(result: Result<T, ServiceError>) -> ItemResponse<T> {
+    match result {
+        Ok(data) => ItemResponse {
+            status: StatusCode::OK.as_u16(),
+            data: Some(data),
+            error: None,
+            fields: None,
+            current_version: None,
+        },
+        Err(error) => {
+            let (status_code, error) = get_error_status(error);
+            ItemResponse {
+                status: status_code.as_u16(),
+                data: None,
+                error: Some(format!("{}", error)),
+                current_version: get_current_version(&error),
+                fields: get_field_errors(error),
+            }
         }
-        Err(ServiceError::InvalidArgument) => HttpResponse::BadRequest()
-            .json(ServiceResponse::<T>::err(ServiceError::InvalidArgument)),
-        Err(ServiceError::InvalidFormat) => {
-            HttpResponse::BadRequest().json(ServiceResponse::<T>::err(ServiceError::InvalidFormat))
+    }
+}
+
+/// Converts service result containing a batch of a bulk request to HTTP response,
+/// and returns it.
+///
+/// Each item of the batch has its own status code and error in `results`, with the progress
+/// of the request in `processed`, `remaining` and `next_cursor`.
+///
+/// # Arguments
+///
+/// * `result` - A result of the service.
+pub fn respond_batch<T: Serialize>(result: Result<Batch<T>, ServiceError>) -> HttpResponse {
+    match result {
+        Ok(batch) => ok(BatchResponse {
+            results: batch.results.into_iter().map(get_item_response).collect(),
+            processed: batch.processed,
+            remaining: batch.remaining,
+            next_cursor: batch.next_cursor,
+        }),
+        Err(error) => err(error),
+    }
+}
+
+/// Converts chunks of a file written by the service to HTTP response streaming the file.
+///
+/// The first chunk is written before responding, so that an error of it responds
+/// the error status. An error of a later chunk aborts the response, leaving a truncated file.
+///
+/// # Arguments
+///
+/// * `chunks` - An iterator writing the file chunk by chunk.
+/// * `content_type` - A content type of the file.
+/// * `filename` - A name of the file to be downloaded.
+pub fn respond_stream<I>(mut chunks: I, content_type: &str, filename: &str) -> HttpResponse
+where
+    I: Iterator<Item = Result<Vec<u8>, ServiceError>> + Unpin + 'static,
+{
+    let first_chunk = match chunks.next() {
+        Some(Ok(chunk)) => chunk,
+        Some(Err(error)) => return err(error),
+        None => Vec::new(),
+    };
+
+    let body = stream::iter(iter::once(Ok(first_chunk)).chain(chunks))
+        .map(|chunk| chunk.map(Bytes::from).map_err(ErrorInternalServerError));
+
+    HttpResponse::Ok()
+        .content_type(content_type)
+        .header(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .streaming(body)
+}
+
+/// Converts chunks of a file of known length to HTTP response streaming the file, and returns it.
+///
+/// The response tells that ranges of the file can be requested, and it is `206 Partial Content`
+/// with `Content-Range` header if only a range of the file is streamed. Since `Content-Length`
+/// is set, the client can tell that a download broken in the middle is incomplete.
+///
+/// # Arguments
+///
+/// * `chunks` - Chunks of the file, or of the range of it.
+/// * `content_type` - A content type of the file.
+/// * `filename` - A name of the file to be saved as.
+/// * `length` - The number of bytes of the chunks.
+/// * `content_range` - `Content-Range` header of the range, if a range is streamed.
+pub fn respond_file_stream<I>(
+    chunks: I,
+    content_type: &str,
+    filename: &str,
+    length: u64,
+    content_range: Option<String>,
+) -> HttpResponse
+where
+    I: Iterator<Item = Result<Vec<u8>, ServiceError>> + Unpin + 'static,
+{
+    let body =
+        stream::iter(chunks).map(|chunk| chunk.map(Bytes::from).map_err(ErrorInternalServerError));
+
+    let mut response = match content_range {
+        Some(content_range) => {
+            let mut response = HttpResponse::PartialContent();
+            response.header(CONTENT_RANGE, content_range);
+            response
         }
-        Err(ServiceError::DuplicatedKey) => {
-            HttpResponse::Conflict().json(ServiceResponse::<T>::err(ServiceError::DuplicatedKey))
+        None => HttpResponse::Ok(),
+    };
+    response
+        .content_type(content_type)
+        .header(ACCEPT_RANGES, "bytes")
+        .header(
+            CONTENT_DISPOSITION,
+            format!("attachment; filename=\"{}\"", filename),
+        )
+        .body(SizedStream::new(length, body))
+}
+
+/// Returns the range of bytes requested by `Range` header.
+///
+/// The header is ignored if it is not a single range of bytes,
+/// so that the whole file is responded as HTTP allows.
+pub fn get_byte_range(req: &HttpRequest) -> Option<ByteRange> {
+    req.headers()
+        .get(RANGE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(ByteRange::parse)
+}
+
+/// Returns hashes of the files the client already has, from `If-None-Match` header.
+pub fn get_cached_hashes(req: &HttpRequest) -> Vec<String> {
+    req.headers()
+        .get(IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .split(',')
+                .map(|etag| etag.trim().trim_start_matches("W/").trim_matches('"'))
+                .filter(|hash| !hash.is_empty())
+                .map(|hash| hash.to_string())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Converts service result containing a file to HTTP response, and returns it.
+///
+/// The file is validated by its hash in `ETag`, and `304 Not Modified` is responded
+/// without the content if the client already has it.
+///
+/// # Arguments
+///
+/// * `result` - A result of the service.
+pub fn respond_file(result: Result<AttachmentFile, ServiceError>) -> HttpResponse {
+    let file = match result {
+        Ok(file) => file,
+        Err(error) => return err(error),
+    };
+
+    let mut response = match file.data {
+        Some(_) => HttpResponse::Ok(),
+        None => HttpResponse::NotModified(),
+    };
+    response
+        .header(ETAG, format!("\"{}\"", file.hash))
+        .header(CACHE_CONTROL, "private, no-cache");
+
+    match file.data {
+        Some(data) => response
+            .content_type(file.mime_type)
+            .header(
+                CONTENT_DISPOSITION,
+                format!("inline; filename=\"{}\"", file.filename),
+            )
+            .body(data),
+        None => response.finish(),
+    }
+}
+
+/// Returns information of the client forwarded by the api gateway.
+///
+/// The IP is the last one of `X-Forwarded-For` header, which is the one the api gateway
+/// has found, even if the header the client sent is passed on by a proxy between them.
+///
+/// # Arguments
+///
+/// * `req` - An HTTP request forwarded by the api gateway.
+pub fn get_audit_context(req: &HttpRequest) -> AuditContext {
+    let get_header = |name: &str| -> Option<String> {
+        req.headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.to_string())
+    };
+
+    AuditContext {
+        ip: get_header("X-Forwarded-For")
+            .and_then(|ip| ip.rsplit(',').next().map(|ip| ip.trim().to_string()))
+            .filter(|ip| !ip.is_empty()),
+        user_agent: get_header("User-Agent"),
+        session_id: get_header("X-Session-Id"),
+        country: get_header("X-Geo-Country"),
+    }
+}
+
+/// Returns id of the device signed in with the session cookie, forwarded by the api gateway
+/// in `X-Device-Id` header.
+///
+/// # Arguments
+///
+/// * `req` - An HTTP request forwarded by the api gateway.
+pub fn get_device_id(req: &HttpRequest) -> Option<String> {
+    req.headers()
+        .get("X-Device-Id")
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+}
+
+/// Returns id of the admin requesting an admin API, forwarded by the api gateway
+/// in `X-Admin-Id` header.
+///
+/// # Arguments
+///
+/// * `req` - An HTTP request forwarded by the api gateway.
+pub fn get_admin_id(req: &HttpRequest) -> Option<u64> {
+    req.headers()
+        .get("X-Admin-Id")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok())
+}
+
+/// Returns the time of `If-Unmodified-Since` header in UTC.
+///
+/// The header is ignored if it is not a valid HTTP date.
+pub fn get_unmodified_since(req: &HttpRequest) -> Option<NaiveDateTime> {
+    req.headers()
+        .get(IF_UNMODIFIED_SINCE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| DateTime::parse_from_rfc2822(value).ok())
+        .map(|datetime| datetime.naive_utc())
+}
+
+#[cfg(test)]
+mod tests {
+    use actix_web::body::Body;
+    use actix_web::http::StatusCode;
+    use actix_web::test::TestRequest;
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn get_body(response: &HttpResponse) -> String {
+        match response.body().as_ref() {
+            Some(Body::Bytes(bytes)) => String::from_utf8(bytes.to_vec()).unwrap(),
+            _ => panic!("response body is not bytes"),
         }
-        Err(ServiceError::Unauthorized) => {
-            HttpResponse::Unauthorized().json(ServiceResponse::<T>::err(ServiceError::Unauthorized))
+    }
+
+    #[test]
+    fn test_respond_ok() {
+        let response = respond(Ok(vec![3, 5]));
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "application/json; charset=utf-8"
+        );
+        assert_eq!(get_body(&response), r#"{"data":[3,5],"error":null}"#);
+    }
+
+    #[test]
+    fn test_respond_ok_with_struct() {
+        #[derive(Serialize)]
+        struct Data {
+            id: u64,
+            title: String,
+            updated_at: Option<String>,
         }
-        _ => HttpResponse::InternalServerError()
-            .json(ServiceResponse::<T>::err(ServiceError::InternalServerError)),
+
+        let response = respond(Ok(Data {
+            id: 1,
+            title: String::from("Lorem ipsum"),
+            updated_at: None,
+        }));
+
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":{"id":1,"title":"Lorem ipsum","updated_at":null},"error":null}"#
+        );
+    }
+
+    #[test]
+    fn test_respond_page() {
+        let response = respond_page(Ok(Page {
+            items: vec![3, 5],
+            meta: PageMeta {
+                total_count: 12,
+                page: Some(2),
+                per_page: Some(2),
+            },
+        }));
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":[3,5],"meta":{"total_count":12,"page":2,"per_page":2},"error":null}"#
+        );
+    }
+
+    #[test]
+    fn test_respond_with_meta() {
+        let response = respond_with_meta(Ok(WithMeta {
+            data: 3,
+            meta: ResponseMeta::new().posts_in_trash_purging_soon(2),
+        }));
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":3,"meta":{"posts_in_trash_purging_soon":2},"error":null}"#
+        );
+    }
+
+    #[test]
+    fn test_respond_with_empty_meta() {
+        let response = respond_with_meta(Ok(WithMeta {
+            data: 3,
+            meta: ResponseMeta::new().posts_in_trash_purging_soon(0),
+        }));
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(get_body(&response), r#"{"data":3,"error":null}"#);
+    }
+
+    #[test]
+    fn test_respond_err() {
+        let response = respond::<bool>(Err(ServiceError::NotFound(String::from("3"))));
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":null,"error":"data for key `3` not found"}"#
+        );
+    }
+
+    #[test]
+    fn test_respond_batch() {
+        let response = respond_batch(Ok(Batch {
+            results: vec![
+                Ok(3),
+                Err(ServiceError::Conflict(4)),
+                Err(ServiceError::QueryExecutionFailure),
+            ],
+            processed: 3,
+            remaining: 2,
+            next_cursor: Some(3),
+        }));
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            get_body(&response),
+            concat!(
+                r#"{"data":{"results":[{"status":200,"data":3,"error":null},"#,
+                r#"{"status":409,"data":null,"error":"conflict with current version `4`","#,
+                r#""current_version":4},"#,
+                r#"{"status":500,"data":null,"error":"internal server error"}],"#,
+                r#""processed":3,"remaining":2,"next_cursor":3},"error":null}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_get_unmodified_since() {
+        let req = TestRequest::default()
+            .header("If-Unmodified-Since", "Sun, 12 Apr 2020 09:00:00 GMT")
+            .to_http_request();
+        assert_eq!(
+            get_unmodified_since(&req),
+            Some(NaiveDate::from_ymd(2020, 4, 12).and_hms(9, 0, 0))
+        );
+
+        let req = TestRequest::default()
+            .header("If-Unmodified-Since", "yesterday")
+            .to_http_request();
+        assert_eq!(get_unmodified_since(&req), None);
+    }
+
+    #[test]
+    fn test_get_byte_range() {
+        let get_range = |value: &str| {
+            get_byte_range(
+                &TestRequest::default()
+                    .header("Range", value)
+                    .to_http_request(),
+            )
+        };
+        assert_eq!(
+            get_range("bytes=0-499"),
+            Some(ByteRange::From(0, Some(499)))
+        );
+        assert_eq!(get_range("bytes=500-"), Some(ByteRange::From(500, None)));
+        assert_eq!(get_range("bytes=-500"), Some(ByteRange::Suffix(500)));
+        assert_eq!(get_range("bytes=0-99,200-299"), None);
+        assert_eq!(get_range("bytes=500-499"), None);
+        assert_eq!(get_range("lines=0-9"), None);
+        assert_eq!(
+            get_byte_range(&TestRequest::default().to_http_request()),
+            None
+        );
+
+        assert_eq!(ByteRange::From(0, Some(499)).resolve(300), Some((0, 299)));
+        assert_eq!(ByteRange::Suffix(500).resolve(300), Some((0, 299)));
+        assert_eq!(ByteRange::Suffix(100).resolve(300), Some((200, 299)));
+        assert_eq!(ByteRange::From(300, None).resolve(300), None);
+    }
+
+    #[test]
+    fn test_err_range_not_satisfiable() {
+        let response = err(ServiceError::RangeNotSatisfiable(300));
+
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            response.headers().get("content-range").unwrap(),
+            "bytes */300"
+        );
+    }
+
+    #[test]
+    fn test_get_audit_context() {
+        let req = TestRequest::default()
+            .header("X-Forwarded-For", "6.6.6.6, 203.0.113.7")
+            .header("User-Agent", "Mozilla/5.0")
+            .to_http_request();
+
+        // The IP the client has put in front is not the key of throttling logins.
+        let audit_context = get_audit_context(&req);
+        assert_eq!(audit_context.ip, Some(String::from("203.0.113.7")));
+        assert_eq!(audit_context.user_agent, Some(String::from("Mozilla/5.0")));
+        assert_eq!(
+            get_audit_context(&TestRequest::default().to_http_request()).ip,
+            None
+        );
+    }
+
+    #[test]
+    fn test_err_conflict() {
+        let response = err(ServiceError::Conflict(4));
+
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":null,"error":"conflict with current version `4`","current_version":4}"#
+        );
+    }
+
+    #[test]
+    fn test_err_locked() {
+        let response = err(ServiceError::Locked(String::from("3")));
+
+        assert_eq!(response.status(), StatusCode::LOCKED);
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":null,"error":"post `3` is locked"}"#
+        );
+    }
+
+    #[test]
+    fn test_err_too_many_requests() {
+        let response = err(ServiceError::TooManyRequests(120));
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get("retry-after").unwrap(), "120");
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":null,"error":"too many attempts, retry after 120 seconds","retry_after":120}"#
+        );
+    }
+
+    #[test]
+    fn test_respond_with_rate_limit() {
+        let response = respond_with_rate_limit(
+            Ok(true),
+            Some(RateLimit {
+                limit: 5,
+                remaining: 4,
+                reset_after: 900,
+                reset_at: 1586795469,
+            }),
+        );
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("RateLimit-Limit").unwrap(), "5");
+        assert_eq!(response.headers().get("RateLimit-Remaining").unwrap(), "4");
+        assert_eq!(response.headers().get("RateLimit-Reset").unwrap(), "900");
+        assert_eq!(get_body(&response), r#"{"data":true,"error":null}"#);
+    }
+
+    #[test]
+    fn test_respond_with_rate_limit_too_many_requests() {
+        let response = respond_with_rate_limit::<bool>(
+            Err(ServiceError::TooManyRequests(60)),
+            Some(RateLimit {
+                limit: 5,
+                remaining: 0,
+                reset_after: 60,
+                reset_at: 1586795469,
+            }),
+        );
+
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(response.headers().get("retry-after").unwrap(), "60");
+        assert_eq!(response.headers().get("RateLimit-Remaining").unwrap(), "0");
+        assert_eq!(response.headers().get("RateLimit-Reset").unwrap(), "60");
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":null,"error":"too many attempts, retry after 60 seconds","retry_after":60,"reset_at":1586795469}"#
+        );
+    }
+
+    #[test]
+    fn test_err_invalid_fields() {
+        let response = err(ServiceError::InvalidFields(vec![FieldError {
+            field: String::from("title"),
+            message: String::from("must be at most 1000 bytes"),
+        }]));
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            get_body(&response),
+            concat!(
+                r#"{"data":null,"error":"invalid fields","#,
+                r#""fields":[{"field":"title","message":"must be at most 1000 bytes"}]}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_err_hides_internal_errors() {
+        let response = err(ServiceError::QueryExecutionFailure);
+
+        assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(
+            get_body(&response),
+            r#"{"data":null,"error":"internal server error"}"#
+        );
+    }
+
+    #[test]
+    fn test_respond_file_not_modified() {
+        let response = respond_file(Ok(AttachmentFile {
+            filename: String::from("photo.jpg"),
+            mime_type: String::from("image/jpeg"),
+            hash: String::from("a1b2"),
+            data: None,
+        }));
+
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get("etag").unwrap(), "\"a1b2\"");
+        assert!(response.headers().get("content-disposition").is_none());
+    }
+
+    #[test]
+    fn test_created() {
+        let response = created(1);
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(get_body(&response), r#"{"data":1,"error":null}"#);
     }
 }
@@ -0,0 +1,113 @@
+use image::codecs::jpeg::JpegEncoder;
+use image::imageops::FilterType;
+use image::io::Reader;
+use std::io::Cursor;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::services::attachment::detect_mime_type;
+
+/// Maximum size of an uploaded avatar in bytes.
+pub const MAX_AVATAR_SIZE: usize = 5 * 1024 * 1024;
+
+/// Width and height of a processed avatar in pixels.
+pub const AVATAR_DIMENSION: u32 = 256;
+
+/// Maximum number of pixels of an uploaded avatar, which keeps a small file
+/// from being decoded into a huge image.
+const MAX_AVATAR_PIXELS: u64 = 40_000_000;
+
+/// Quality of a processed avatar from 1 to 100.
+const AVATAR_JPEG_QUALITY: u8 = 85;
+
+/// Returns an avatar as a JPEG of `AVATAR_DIMENSION` square, cropped around the center.
+///
+/// The image is decoded and encoded again, so metadata such as EXIF is not kept.
+/// JPEG, PNG, GIF, and WebP are accepted, and the first frame of an animation is used.
+///
+/// # Arguments
+///
+/// * `data` - An uploaded image, whose type is detected by its content
+pub fn process_avatar(data: &[u8]) -> Result<Vec<u8>, ServiceError> {
+    if data.is_empty() {
+        return Err(get_service_error(ServiceError::InvalidArgument));
+    }
+    if data.len() > MAX_AVATAR_SIZE {
+        return Err(get_service_error(ServiceError::PayloadTooLarge));
+    }
+    match detect_mime_type(data) {
+        Some("image/jpeg") | Some("image/png") | Some("image/gif") | Some("image/webp") => {}
+        _ => return Err(get_service_error(ServiceError::UnsupportedMediaType)),
+    }
+
+    let reader = || {
+        Reader::new(Cursor::new(data))
+            .with_guessed_format()
+            .map_err(|_| get_service_error(ServiceError::InvalidFormat))
+    };
+    let (width, height) = reader()?
+        .into_dimensions()
+        .map_err(|_| get_service_error(ServiceError::InvalidFormat))?;
+    if u64::from(width) * u64::from(height) > MAX_AVATAR_PIXELS {
+        return Err(get_service_error(ServiceError::PayloadTooLarge));
+    }
+
+    let avatar = reader()?
+        .decode()
+        .map_err(|_| get_service_error(ServiceError::InvalidFormat))?
+        .resize_to_fill(AVATAR_DIMENSION, AVATAR_DIMENSION, FilterType::Lanczos3)
+        .to_rgb8();
+
+    let mut encoded = Vec::new();
+    JpegEncoder::new_with_quality(&mut encoded, AVATAR_JPEG_QUALITY)
+        .encode_image(&avatar)
+        .map_err(|_| get_service_error(ServiceError::InternalServerError))?;
+    Ok(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::{DynamicImage, ImageFormat, RgbImage};
+
+    use super::*;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut data = Vec::new();
+        DynamicImage::ImageRgb8(RgbImage::new(width, height))
+            .write_to(&mut data, ImageFormat::Png)
+            .unwrap();
+        data
+    }
+
+    #[test]
+    fn test_process_avatar() {
+        let avatar = process_avatar(&png(640, 480)).unwrap();
+
+        assert_eq!(detect_mime_type(&avatar), Some("image/jpeg"));
+        assert_eq!(
+            image::load_from_memory(&avatar)
+                .unwrap()
+                .to_rgb8()
+                .dimensions(),
+            (AVATAR_DIMENSION, AVATAR_DIMENSION)
+        );
+    }
+
+    #[test]
+    fn test_process_invalid_avatar() {
+        let truncated_png = &png(640, 480)[..64];
+
+        assert!(matches!(
+            process_avatar(b""),
+            Err(ServiceError::InvalidArgument)
+        ));
+        assert!(matches!(
+            process_avatar(&vec![0xFF; MAX_AVATAR_SIZE + 1]),
+            Err(ServiceError::PayloadTooLarge)
+        ));
+        assert!(matches!(
+            process_avatar(b"<svg></svg>"),
+            Err(ServiceError::UnsupportedMediaType)
+        ));
+        assert!(process_avatar(truncated_png).is_err());
+    }
+}
@@ -0,0 +1,53 @@
+use serde::Serialize;
+
+/// Warnings contained in `meta` of a response, so that clients can warn users before a limit
+/// fails hard. Warnings not set are omitted, and `meta` itself is omitted without any warnings.
+#[derive(Debug, Default, PartialEq, Serialize)]
+pub struct ResponseMeta {
+    /// Number of posts in the trash permanently deleted soon.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    posts_in_trash_purging_soon: Option<usize>,
+}
+
+impl ResponseMeta {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Warns that `count` posts in the trash are permanently deleted soon, unless it is zero.
+    pub fn posts_in_trash_purging_soon(mut self, count: usize) -> Self {
+        self.posts_in_trash_purging_soon = Some(count).filter(|count| *count > 0);
+        self
+    }
+
+    /// Returns true if there are no warnings.
+    pub fn is_empty(&self) -> bool {
+        self.posts_in_trash_purging_soon.is_none()
+    }
+}
+
+/// Data with warnings of a response, using between routes layer and service layer.
+#[derive(Debug, PartialEq)]
+pub struct WithMeta<T> {
+    pub data: T,
+    pub meta: ResponseMeta,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_posts_in_trash_purging_soon() {
+        assert!(ResponseMeta::new().is_empty());
+        assert!(ResponseMeta::new()
+            .posts_in_trash_purging_soon(0)
+            .is_empty());
+        assert_eq!(
+            ResponseMeta::new().posts_in_trash_purging_soon(3),
+            ResponseMeta {
+                posts_in_trash_purging_soon: Some(3),
+            }
+        );
+    }
+}
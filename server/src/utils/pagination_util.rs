@@ -0,0 +1,67 @@
+use serde::Serialize;
+
+use crate::models::error::{get_service_error, ServiceError};
+
+/// Default number of items in a page.
+pub const DEFAULT_PER_PAGE: u32 = 20;
+
+/// Maximum number of items in a page.
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// Pagination metadata, contained in `meta` of a response.
+#[derive(Debug, PartialEq, Serialize)]
+pub struct PageMeta {
+    pub total_count: i64,
+    pub page: Option<u32>,
+    pub per_page: Option<u32>,
+}
+
+/// A page of a list, using between routes layer and service layer.
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub meta: PageMeta,
+}
+
+/// A batch of a bulk request processed at once, using between routes layer and service layer.
+///
+/// A bulk request is processed batch by batch, so that a client sends it again with
+/// `next_cursor` in `continue_from` until all items are processed.
+pub struct Batch<T> {
+    /// Result of each item in the batch, in the same order.
+    pub results: Vec<Result<T, ServiceError>>,
+    /// Number of items processed so far, including the previous batches.
+    pub processed: usize,
+    /// Number of items left after the batch.
+    pub remaining: usize,
+    /// Index of the first item of the next batch, or `None` if all items are processed.
+    pub next_cursor: Option<usize>,
+}
+
+/// Converts 1-based page number and page size to offset and limit.
+pub fn get_offset_and_limit(
+    page: &Option<u32>,
+    per_page: &Option<u32>,
+) -> Result<(i64, i64), ServiceError> {
+    let page = page.unwrap_or(1);
+    let per_page = per_page.unwrap_or(DEFAULT_PER_PAGE);
+
+    if page == 0 || per_page == 0 || per_page > MAX_PER_PAGE {
+        return Err(get_service_error(ServiceError::InvalidArgument));
+    }
+
+    Ok((i64::from((page - 1) * per_page), i64::from(per_page)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_offset_and_limit() {
+        assert_eq!(get_offset_and_limit(&None, &None).unwrap(), (0, 20));
+        assert_eq!(get_offset_and_limit(&Some(3), &Some(10)).unwrap(), (20, 10));
+        assert!(get_offset_and_limit(&Some(0), &None).is_err());
+        assert!(get_offset_and_limit(&None, &Some(0)).is_err());
+        assert!(get_offset_and_limit(&None, &Some(MAX_PER_PAGE + 1)).is_err());
+    }
+}
@@ -1,7 +1,76 @@
+use argon2::{Config, ThreadMode, Variant, Version};
 use cfg_if::cfg_if;
-use scrypt::{scrypt_check, scrypt_simple, ScryptParams};
+use rand::{thread_rng, Rng};
+use scrypt::scrypt_check;
+use sha1::{Digest, Sha1};
+use zxcvbn::zxcvbn;
 
-/// Returns a password that is hashed by scrypt.
+/// Prefix of passwords hashed by Argon2id in PHC string format.
+const ARGON2ID_PREFIX: &str = "$argon2id$";
+
+/// Length of a salt in bytes.
+const SALT_LENGTH: usize = 16;
+
+/// Minimum number of characters of a password.
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Minimum zxcvbn score of a password, from 0 (too guessable) to 4 (very unguessable).
+const MIN_PASSWORD_SCORE: u8 = 3;
+
+/// Length of a SHA-1 hash prefix sent to HaveIBeenPwned, which keeps the password anonymous.
+const BREACH_RANGE_PREFIX_LENGTH: usize = 5;
+
+/// Cost parameters of Argon2id.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct CostParams {
+    /// Memory cost in KiB.
+    memory_cost: u32,
+    /// Number of iterations.
+    time_cost: u32,
+}
+
+impl CostParams {
+    /// Returns the parameters in the form of PHC string format, e.g. `m=19456,t=2,p=1`.
+    fn to_phc(self) -> String {
+        format!("m={},t={},p=1", self.memory_cost, self.time_cost)
+    }
+}
+
+cfg_if! {
+    if #[cfg(test)] {
+        fn get_cost_params() -> CostParams {
+            CostParams {
+                memory_cost: 64,
+                time_cost: 1,
+            }
+        }
+    } else {
+        use std::env;
+
+        /// Default memory cost of Argon2id in KiB, which is recommended by OWASP.
+        const DEFAULT_MEMORY_COST: u32 = 19456; // 19 MiB
+
+        /// Default number of iterations of Argon2id.
+        const DEFAULT_TIME_COST: u32 = 2;
+
+        /// Reads cost parameters from `PASSWORD_MEMORY_COST_KIB` and `PASSWORD_TIME_COST`.
+        fn get_cost_params() -> CostParams {
+            let get_var = |name: &str, default: u32| {
+                env::var(name)
+                    .ok()
+                    .and_then(|value| value.parse::<u32>().ok())
+                    .unwrap_or(default)
+            };
+
+            CostParams {
+                memory_cost: get_var("PASSWORD_MEMORY_COST_KIB", DEFAULT_MEMORY_COST),
+                time_cost: get_var("PASSWORD_TIME_COST", DEFAULT_TIME_COST),
+            }
+        }
+    }
+}
+
+/// Returns a password that is hashed by Argon2id in PHC string format.
 ///
 /// # Arguments
 ///
@@ -10,37 +79,40 @@ use scrypt::{scrypt_check, scrypt_simple, ScryptParams};
 /// # Example
 ///
 /// ```ignore
-/// use scrypt::scrypt_check;
-/// use darim::utils::password_util::get_hashed_password;
+/// use darim::utils::password_util::{check_password, get_hashed_password};
 ///
 /// let password = String::from("123");
 /// let hashed_password = get_hashed_password(&password);
 ///
-/// assert!(scrypt_check(&password, &hashed_password).is_ok());
+/// assert!(hashed_password.starts_with("$argon2id$"));
+/// assert!(check_password(&password, &hashed_password));
 /// ```
 pub fn get_hashed_password(password: &str) -> String {
-    let params = get_params_for_password_hashing();
-    scrypt_simple(password, &params).unwrap()
-}
+    let params = get_cost_params();
+    let config = Config {
+        variant: Variant::Argon2id,
+        version: Version::Version13,
+        mem_cost: params.memory_cost,
+        time_cost: params.time_cost,
+        lanes: 1,
+        thread_mode: ThreadMode::Sequential,
+        secret: &[],
+        ad: &[],
+        hash_length: 32,
+    };
+    let salt: [u8; SALT_LENGTH] = thread_rng().gen();
 
-cfg_if! {
-    if #[cfg(test)] {
-        fn get_params_for_password_hashing() -> ScryptParams {
-            ScryptParams::new(7, 4, 1).unwrap()
-        }
-    } else {
-        fn get_params_for_password_hashing() -> ScryptParams {
-            ScryptParams::new(15, 8, 1).unwrap()
-        }
-    }
+    argon2::hash_encoded(password.as_bytes(), &salt, &config).unwrap()
 }
 
 /// Compares a plain-text password between hashed password
 ///
+/// Passwords hashed by scrypt before Argon2id are also accepted.
+///
 /// # Arguments
 ///
 /// * `password` - A password to compare
-/// * `hashed_password` - A hashed password returned by scrypt_simple()
+/// * `hashed_password` - A hashed password returned by `get_hashed_password()`, or by scrypt_simple()
 ///
 /// # Example
 ///
@@ -55,12 +127,102 @@ cfg_if! {
 /// assert!(check_password(&password, &hashed_password));
 /// ```
 pub fn check_password(password: &str, hashed_password: &str) -> bool {
-    scrypt_check(password, hashed_password).is_ok()
+    if hashed_password.starts_with("$argon2") {
+        argon2::verify_encoded(hashed_password, password.as_bytes()).unwrap_or(false)
+    } else {
+        scrypt_check(password, hashed_password).is_ok()
+    }
+}
+
+/// Returns whether a hashed password should be hashed again, because it is not hashed
+/// by Argon2id or its cost parameters differ from the current ones.
+///
+/// # Arguments
+///
+/// * `hashed_password` - A hashed password which has been checked
+pub fn needs_rehash(hashed_password: &str) -> bool {
+    match hashed_password.strip_prefix(ARGON2ID_PREFIX) {
+        // e.g. `v=19$m=19456,t=2,p=1$<salt>$<hash>`
+        Some(rest) => rest.split('$').nth(1) != Some(get_cost_params().to_phc().as_str()),
+        None => true,
+    }
+}
+
+/// Returns reasons a password is too weak, or an empty list if it is strong enough.
+///
+/// The strength is estimated by zxcvbn, and a password similar to `user_inputs`
+/// such as the name or the email of the user is considered weak.
+///
+/// # Arguments
+///
+/// * `password` - A plain-text password to be estimated
+/// * `user_inputs` - Words related to the user, which should not be guessable from the password
+pub fn get_weaknesses(password: &str, user_inputs: &[&str]) -> Vec<String> {
+    if password.chars().count() < MIN_PASSWORD_LENGTH {
+        return vec![format!(
+            "must be at least {} characters",
+            MIN_PASSWORD_LENGTH
+        )];
+    }
+
+    let entropy = match zxcvbn(password, user_inputs) {
+        Ok(entropy) => entropy,
+        Err(_) => return vec![String::from("must not be blank")],
+    };
+    if entropy.score() >= MIN_PASSWORD_SCORE {
+        return vec![];
+    }
+
+    let mut weaknesses = vec![String::from("is too easy to guess")];
+    if let Some(feedback) = entropy.feedback() {
+        if let Some(warning) = feedback.warning() {
+            weaknesses.push(warning.to_string());
+        }
+        weaknesses.extend(
+            feedback
+                .suggestions()
+                .iter()
+                .map(|suggestion| suggestion.to_string()),
+        );
+    }
+    weaknesses
+}
+
+/// Returns the prefix and the suffix of the uppercase SHA-1 hex of a password,
+/// for a range query of HaveIBeenPwned.
+///
+/// Only the prefix is sent, and the suffix is looked up in the response.
+pub fn get_breach_range(password: &str) -> (String, String) {
+    let hash = format!("{:X}", Sha1::digest(password.as_bytes()));
+    let (prefix, suffix) = hash.split_at(BREACH_RANGE_PREFIX_LENGTH);
+    (prefix.to_string(), suffix.to_string())
+}
+
+/// Returns how many times a password has appeared in data breaches,
+/// from a response of a HaveIBeenPwned range query.
+///
+/// # Arguments
+///
+/// * `range` - Lines of `SUFFIX:COUNT` responded by HaveIBeenPwned
+/// * `suffix` - The suffix returned by `get_breach_range()`
+pub fn count_breaches(range: &str, suffix: &str) -> u64 {
+    range
+        .lines()
+        .find_map(|line| {
+            let mut columns = line.trim().splitn(2, ':');
+            match (columns.next(), columns.next()) {
+                (Some(line_suffix), Some(count)) if line_suffix.eq_ignore_ascii_case(suffix) => {
+                    count.parse::<u64>().ok()
+                }
+                _ => None,
+            }
+        })
+        .unwrap_or(0)
 }
 
 #[cfg(test)]
 mod tests {
-    use scrypt::{scrypt_check, scrypt_simple, ScryptParams};
+    use scrypt::{scrypt_simple, ScryptParams};
 
     use super::*;
 
@@ -69,7 +231,10 @@ mod tests {
         let password = String::from("123");
         let hashed_password = get_hashed_password(&password);
 
-        assert!(scrypt_check(&password, &hashed_password).is_ok());
+        assert!(hashed_password.starts_with("$argon2id$v=19$m=64,t=1,p=1$"));
+        assert!(check_password(&password, &hashed_password));
+        assert!(!check_password("124", &hashed_password));
+        assert_ne!(hashed_password, get_hashed_password(&password));
     }
 
     #[test]
@@ -80,4 +245,47 @@ mod tests {
 
         assert!(check_password(&password, &hashed_password));
     }
+
+    #[test]
+    fn test_needs_rehash() {
+        let params = ScryptParams::new(7, 4, 1).unwrap();
+        let scrypt_password = scrypt_simple("123", &params).unwrap();
+        let argon2_password = get_hashed_password("123");
+        let outdated_password = argon2_password.replace("m=64,t=1", "m=32,t=1");
+
+        assert!(needs_rehash(&scrypt_password));
+        assert!(!needs_rehash(&argon2_password));
+        assert!(needs_rehash(&outdated_password));
+    }
+
+    #[test]
+    fn test_get_weaknesses() {
+        assert_eq!(
+            get_weaknesses("abc123", &[]),
+            vec![String::from("must be at least 8 characters")]
+        );
+
+        let weaknesses = get_weaknesses("password1", &[]);
+        assert_eq!(weaknesses[0], "is too easy to guess");
+        assert!(weaknesses.len() > 1);
+
+        assert!(!get_weaknesses("darimparksb", &["parksb", "darim"]).is_empty());
+        assert!(get_weaknesses("correct horse battery staple", &[]).is_empty());
+    }
+
+    #[test]
+    fn test_count_breaches() {
+        let (prefix, suffix) = get_breach_range("password");
+        assert_eq!(prefix, "5BAA6");
+        assert_eq!(suffix, "1E4C9B93F3F0682250B6CF8331B7EE68FD8");
+
+        let range = "003D68EB55068C33ACE09247EE4C639306B:3\r\n\
+            1E4C9B93F3F0682250B6CF8331B7EE68FD8:9659365\r\n\
+            01330C689E5D64F660D6947A93AD634EF8F:0";
+        assert_eq!(count_breaches(range, &suffix), 9659365);
+        assert_eq!(
+            count_breaches(range, "0000000000000000000000000000000000A"),
+            0
+        );
+    }
 }
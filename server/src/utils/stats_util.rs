@@ -0,0 +1,218 @@
+use chrono::{Datelike, Duration, NaiveDate, TimeZone};
+use chrono_tz::Tz;
+use std::collections::BTreeMap;
+
+use crate::models::error::{get_service_error, ServiceError};
+use crate::models::post::PostDate;
+use crate::models::user_settings::WeekStartDay;
+
+/// Periods to group posts by.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Granularity {
+    Daily,
+    /// A week starting on the week start day of the user.
+    Weekly,
+    Monthly,
+}
+
+impl Granularity {
+    /// Parses the name of the period used in `granularity` argument.
+    ///
+    /// `day`, `week` and `month` of `interval` argument are also accepted.
+    pub fn parse(granularity: &str) -> Result<Self, ServiceError> {
+        match granularity {
+            "daily" | "day" => Ok(Self::Daily),
+            "weekly" | "week" => Ok(Self::Weekly),
+            "monthly" | "month" => Ok(Self::Monthly),
+            _ => Err(get_service_error(ServiceError::InvalidArgument)),
+        }
+    }
+
+    /// Returns the first date of the period containing `date`.
+    pub fn start_of(&self, date: NaiveDate, week_start_day: WeekStartDay) -> NaiveDate {
+        match self {
+            Self::Daily => date,
+            Self::Weekly => start_of_week(date, week_start_day),
+            Self::Monthly => date.with_day(1).unwrap_or(date),
+        }
+    }
+}
+
+/// Returns the first date of the week containing `date`, which starts on `week_start_day`.
+pub fn start_of_week(date: NaiveDate, week_start_day: WeekStartDay) -> NaiveDate {
+    let first_day = week_start_day.weekday().num_days_from_monday();
+    let day = date.weekday().num_days_from_monday();
+    date - Duration::days(i64::from((day + 7 - first_day) % 7))
+}
+
+/// Returns the local date of a post.
+///
+/// A post is on the date in the offset where it was written. A post written before offsets
+/// were recorded is on the date in `timezone` at the time it was written, with daylight saving
+/// time of the time, instead of rolling over at UTC midnight.
+pub fn get_local_date(date: &PostDate, timezone: &Tz) -> NaiveDate {
+    match date.offset {
+        Some(_) => date.local_date(),
+        None => timezone.from_utc_datetime(&date.date).date().naive_local(),
+    }
+}
+
+/// Counts local dates in each period, and returns pairs of the first date of the period
+/// and the count in asc order of the periods. Periods without dates are omitted.
+pub fn count_by_period(
+    dates: &[NaiveDate],
+    granularity: Granularity,
+    week_start_day: WeekStartDay,
+) -> Vec<(NaiveDate, usize)> {
+    let mut counts: BTreeMap<NaiveDate, usize> = BTreeMap::new();
+    for date in dates {
+        *counts
+            .entry(granularity.start_of(*date, week_start_day))
+            .or_insert(0) += 1;
+    }
+    counts.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDateTime;
+
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd(year, month, day)
+    }
+
+    fn utc(datetime: &str) -> PostDate {
+        PostDate {
+            date: NaiveDateTime::parse_from_str(datetime, "%Y-%m-%dT%H:%M:%S").unwrap(),
+            offset: None,
+        }
+    }
+
+    #[test]
+    fn test_start_of_week() {
+        // 2020-04-12 is Sunday.
+        let sunday = date(2020, 4, 12);
+        assert_eq!(
+            start_of_week(sunday, WeekStartDay::Monday),
+            date(2020, 4, 6)
+        );
+        assert_eq!(start_of_week(sunday, WeekStartDay::Sunday), sunday);
+        assert_eq!(
+            start_of_week(sunday, WeekStartDay::Saturday),
+            date(2020, 4, 11)
+        );
+
+        let saturday = date(2020, 4, 11);
+        assert_eq!(
+            start_of_week(saturday, WeekStartDay::Monday),
+            date(2020, 4, 6)
+        );
+        assert_eq!(
+            start_of_week(saturday, WeekStartDay::Sunday),
+            date(2020, 4, 5)
+        );
+        assert_eq!(start_of_week(saturday, WeekStartDay::Saturday), saturday);
+    }
+
+    #[test]
+    fn test_count_by_period_over_year_boundary() {
+        // 2020-12-27 is Sunday, and 2021-01-02 is Saturday.
+        let dates = vec![
+            date(2020, 12, 26),
+            date(2020, 12, 27),
+            date(2020, 12, 31),
+            date(2021, 1, 1),
+            date(2021, 1, 2),
+            date(2021, 1, 3),
+        ];
+
+        assert_eq!(
+            count_by_period(&dates, Granularity::Weekly, WeekStartDay::Sunday),
+            vec![
+                (date(2020, 12, 20), 1),
+                (date(2020, 12, 27), 4),
+                (date(2021, 1, 3), 1),
+            ]
+        );
+        assert_eq!(
+            count_by_period(&dates, Granularity::Weekly, WeekStartDay::Monday),
+            vec![(date(2020, 12, 21), 2), (date(2020, 12, 28), 4)]
+        );
+        assert_eq!(
+            count_by_period(&dates, Granularity::Weekly, WeekStartDay::Saturday),
+            vec![(date(2020, 12, 26), 4), (date(2021, 1, 2), 2)]
+        );
+        assert_eq!(
+            count_by_period(&dates, Granularity::Monthly, WeekStartDay::Monday),
+            vec![(date(2020, 12, 1), 3), (date(2021, 1, 1), 3)]
+        );
+        assert_eq!(
+            count_by_period(&dates, Granularity::Daily, WeekStartDay::Monday).len(),
+            6
+        );
+        assert!(count_by_period(&[], Granularity::Weekly, WeekStartDay::Monday).is_empty());
+    }
+
+    #[test]
+    fn test_get_local_date_over_dst_transition() {
+        let new_york: Tz = "America/New_York".parse().unwrap();
+
+        // Daylight saving time ends at 06:00 UTC on 2020-11-01, when clocks go back to 01:00.
+        // 04:30 UTC on the next day is 23:30 in EST, while it would be 00:30 in EDT.
+        assert_eq!(
+            get_local_date(&utc("2020-11-02T04:30:00"), &new_york),
+            date(2020, 11, 1)
+        );
+        assert_eq!(
+            get_local_date(&utc("2020-11-01T03:30:00"), &new_york),
+            date(2020, 10, 31)
+        );
+
+        // Daylight saving time starts at 07:00 UTC on 2020-03-08.
+        // 03:30 UTC on the next day is 23:30 in EDT, while it would be 22:30 in EST.
+        assert_eq!(
+            get_local_date(&utc("2020-03-09T03:30:00"), &new_york),
+            date(2020, 3, 8)
+        );
+        assert_eq!(
+            get_local_date(&utc("2020-03-09T04:30:00"), &new_york),
+            date(2020, 3, 9)
+        );
+
+        // A post with its offset stays on the date where it was written.
+        assert_eq!(
+            get_local_date(
+                &PostDate::parse("2020-11-01T23:30:00+09:00").unwrap(),
+                &new_york
+            ),
+            date(2020, 11, 1)
+        );
+    }
+
+    #[test]
+    fn test_count_by_period_over_dst_transition() {
+        let new_york: Tz = "America/New_York".parse().unwrap();
+
+        // 2020-11-01 is Sunday, on which daylight saving time ends.
+        // The posts are on Saturday, Sunday and Monday in New York.
+        let dates: Vec<NaiveDate> = vec![
+            utc("2020-11-01T03:30:00"),
+            utc("2020-11-02T04:30:00"),
+            utc("2020-11-02T05:30:00"),
+        ]
+        .iter()
+        .map(|date| get_local_date(date, &new_york))
+        .collect();
+
+        assert_eq!(
+            count_by_period(&dates, Granularity::Weekly, WeekStartDay::Monday),
+            vec![(date(2020, 10, 26), 2), (date(2020, 11, 2), 1)]
+        );
+        assert_eq!(
+            count_by_period(&dates, Granularity::Weekly, WeekStartDay::Sunday),
+            vec![(date(2020, 10, 25), 1), (date(2020, 11, 1), 2)]
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use hmac::{Hmac, Mac, NewMac};
+use rand::{thread_rng, RngCore};
+use sha1::Sha1;
+
+/// Seconds in a time step, in which a code is valid.
+const TIME_STEP_SECONDS: u64 = 30;
+
+/// Number of digits of a code.
+const CODE_DIGITS: u32 = 6;
+
+/// Length of secrets in bytes, which is recommended for HMAC-SHA1 by RFC 4226.
+const SECRET_LENGTH: usize = 20;
+
+/// Number of time steps before and after the current one in which codes are accepted,
+/// so that clocks of authenticator apps may drift a little.
+const ALLOWED_DRIFT_STEPS: u64 = 1;
+
+/// Alphabet of base32 in RFC 4648.
+const BASE32_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+/// Returns a random secret encoded in base32, as authenticator apps take it.
+pub fn generate_secret() -> String {
+    let mut secret = [0u8; SECRET_LENGTH];
+    thread_rng().fill_bytes(&mut secret);
+    encode_base32(&secret)
+}
+
+/// Encodes bytes in base32 without padding.
+pub fn encode_base32(bytes: &[u8]) -> String {
+    let mut encoded = String::new();
+    let mut buffer: u32 = 0;
+    let mut bit_count = 0;
+    for byte in bytes {
+        buffer = (buffer << 8) | u32::from(*byte);
+        bit_count += 8;
+        while bit_count >= 5 {
+            bit_count -= 5;
+            encoded.push(BASE32_ALPHABET[((buffer >> bit_count) & 0x1f) as usize] as char);
+        }
+    }
+    if bit_count > 0 {
+        encoded.push(BASE32_ALPHABET[((buffer << (5 - bit_count)) & 0x1f) as usize] as char);
+    }
+    encoded
+}
+
+/// Decodes base32 without padding, and returns `None` if it has a character out of the alphabet.
+pub fn decode_base32(encoded: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::new();
+    let mut buffer: u32 = 0;
+    let mut bit_count = 0;
+    for character in encoded.trim_end_matches('=').bytes() {
+        let value = BASE32_ALPHABET
+            .iter()
+            .position(|c| *c == character.to_ascii_uppercase())?;
+        buffer = (buffer << 5) | value as u32;
+        bit_count += 5;
+        if bit_count >= 8 {
+            bit_count -= 8;
+            bytes.push((buffer >> bit_count) as u8);
+        }
+    }
+    Some(bytes)
+}
+
+/// Returns the time step of a unix timestamp.
+pub fn get_step(timestamp: u64) -> u64 {
+    timestamp / TIME_STEP_SECONDS
+}
+
+/// Returns the code of a time step by HOTP in RFC 4226.
+pub fn get_code(secret: &[u8], step: u64) -> String {
+    let mut mac = Hmac::<Sha1>::new_varkey(secret).expect("HMAC accepts a key of any length");
+    mac.update(&step.to_be_bytes());
+    let hash = mac.finalize().into_bytes();
+
+    let offset = (hash[hash.len() - 1] & 0x0f) as usize;
+    let binary = (u32::from(hash[offset] & 0x7f) << 24)
+        | (u32::from(hash[offset + 1]) << 16)
+        | (u32::from(hash[offset + 2]) << 8)
+        | u32::from(hash[offset + 3]);
+    format!(
+        "{:0width$}",
+        binary % 10u32.pow(CODE_DIGITS),
+        width = CODE_DIGITS as usize
+    )
+}
+
+/// Finds the time step whose code is `code` around a unix timestamp, allowing a little drift.
+///
+/// # Arguments
+///
+/// * `secret` - A secret encoded in base32.
+/// * `code` - A code given by the user.
+/// * `timestamp` - A unix timestamp of now.
+pub fn find_step(secret: &str, code: &str, timestamp: u64) -> Option<u64> {
+    if code.len() != CODE_DIGITS as usize {
+        return None;
+    }
+    let secret = decode_base32(secret)?;
+    let step = get_step(timestamp);
+    (step.saturating_sub(ALLOWED_DRIFT_STEPS)..=step + ALLOWED_DRIFT_STEPS)
+        .find(|step| get_code(&secret, *step) == code)
+}
+
+/// Returns `otpauth` URI of a secret, which authenticator apps register by a QR code.
+///
+/// # Arguments
+///
+/// * `issuer` - A name of the service shown in authenticator apps.
+/// * `account` - A name of the account, such as an email.
+/// * `secret` - A secret encoded in base32.
+pub fn get_uri(issuer: &str, account: &str, secret: &str) -> String {
+    let encode = |text: &str| -> String {
+        text.bytes()
+            .map(|byte| match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'@' => {
+                    (byte as char).to_string()
+                }
+                _ => format!("%{:02X}", byte),
+            })
+            .collect()
+    };
+    format!(
+        "otpauth://totp/{}:{}?secret={}&issuer={}&algorithm=SHA1&digits={}&period={}",
+        encode(issuer),
+        encode(account),
+        secret,
+        encode(issuer),
+        CODE_DIGITS,
+        TIME_STEP_SECONDS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base32() {
+        assert_eq!(encode_base32(b"foobar"), "MZXW6YTBOI");
+        assert_eq!(decode_base32("MZXW6YTBOI").unwrap(), b"foobar");
+        assert_eq!(decode_base32("mzxw6ytboi======").unwrap(), b"foobar");
+        assert!(decode_base32("MZXW6YTB0I").is_none());
+
+        let secret = generate_secret();
+        assert_eq!(secret.len(), 32);
+        assert_eq!(decode_base32(&secret).unwrap().len(), SECRET_LENGTH);
+    }
+
+    #[test]
+    fn test_get_code() {
+        // Test vectors of RFC 6238 in 6 digits.
+        let secret = b"12345678901234567890";
+        assert_eq!(get_code(secret, get_step(59)), "287082");
+        assert_eq!(get_code(secret, get_step(1_111_111_109)), "081804");
+        assert_eq!(get_code(secret, get_step(2_000_000_000)), "279037");
+    }
+
+    #[test]
+    fn test_find_step() {
+        let secret = encode_base32(b"12345678901234567890");
+        assert_eq!(
+            find_step(&secret, "081804", 1_111_111_109),
+            Some(37_037_036)
+        );
+        assert_eq!(
+            find_step(&secret, "081804", 1_111_111_139),
+            Some(37_037_036)
+        );
+        assert_eq!(find_step(&secret, "081804", 1_111_111_199), None);
+        assert_eq!(find_step(&secret, "81804", 1_111_111_109), None);
+    }
+
+    #[test]
+    fn test_get_uri() {
+        assert_eq!(
+            get_uri("Darim", "park@example.com", "MZXW6YTBOI"),
+            "otpauth://totp/Darim:park@example.com?secret=MZXW6YTBOI&issuer=Darim&algorithm=SHA1&digits=6&period=30"
+        );
+    }
+}
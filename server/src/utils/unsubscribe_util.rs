@@ -0,0 +1,181 @@
+use chrono::NaiveDateTime;
+use hmac::{Hmac, Mac, NewMac};
+use sha2::Sha256;
+use std::env;
+
+/// Category of emails which can be unsubscribed by a link without logging in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmailCategory {
+    /// Daily writing prompts.
+    Prompt,
+}
+
+impl EmailCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EmailCategory::Prompt => "prompt",
+        }
+    }
+
+    pub fn parse(category: &str) -> Option<Self> {
+        match category {
+            "prompt" => Some(EmailCategory::Prompt),
+            _ => None,
+        }
+    }
+
+    /// Returns what the emails of the category are, shown on the page after unsubscribing.
+    pub fn description(&self) -> &'static str {
+        match self {
+            EmailCategory::Prompt => "daily writing prompts",
+        }
+    }
+}
+
+/// Claims of an unsubscribe token.
+#[derive(Debug, PartialEq)]
+pub struct UnsubscribeClaims {
+    pub user_id: u64,
+    pub category: EmailCategory,
+}
+
+/// Returns the secret signing unsubscribe tokens, which is `UNSUBSCRIBE_SECRET`.
+pub fn get_secret() -> String {
+    env::var("UNSUBSCRIBE_SECRET").expect("UNSUBSCRIBE_SECRET not found")
+}
+
+fn sign(secret: &str, payload: &str) -> Hmac<Sha256> {
+    let mut mac =
+        Hmac::<Sha256>::new_varkey(secret.as_bytes()).expect("HMAC accepts a key of any length");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 || !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&hex[index..index + 2], 16).ok())
+        .collect()
+}
+
+/// Issues a token unsubscribing specific user from emails of a category until `expires_at`.
+///
+/// The token is `{user_id}.{category}.{expires_at}.{signature}`, where `expires_at` is
+/// a UNIX timestamp and `signature` is HMAC-SHA256 of the rest in hex. Nothing is stored,
+/// so the token cannot be revoked before it expires.
+pub fn issue_token(
+    secret: &str,
+    user_id: u64,
+    category: EmailCategory,
+    expires_at: &NaiveDateTime,
+) -> String {
+    let payload = format!(
+        "{}.{}.{}",
+        user_id,
+        category.as_str(),
+        expires_at.timestamp()
+    );
+    let signature: String = sign(secret, &payload)
+        .finalize()
+        .into_bytes()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+    format!("{}.{}", payload, signature)
+}
+
+/// Verifies a token, and returns its claims if it is signed by `secret` and not expired at `now`.
+///
+/// The signature is compared in constant time.
+pub fn verify_token(secret: &str, token: &str, now: &NaiveDateTime) -> Option<UnsubscribeClaims> {
+    let signature_index = token.rfind('.')?;
+    let payload = &token[..signature_index];
+    let signature = decode_hex(&token[signature_index + 1..])?;
+    sign(secret, payload).verify(&signature).ok()?;
+
+    let mut claims = payload.splitn(3, '.');
+    let user_id = claims.next()?.parse::<u64>().ok()?;
+    let category = EmailCategory::parse(claims.next()?)?;
+    let expires_at = claims.next()?.parse::<i64>().ok()?;
+    if now.timestamp() >= expires_at {
+        return None;
+    }
+
+    Some(UnsubscribeClaims { user_id, category })
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate};
+
+    use super::*;
+
+    const SECRET: &str = "s3cr3t";
+
+    fn now() -> NaiveDateTime {
+        NaiveDate::from_ymd(2020, 4, 12).and_hms(9, 0, 0)
+    }
+
+    #[test]
+    fn test_verify_token() {
+        let token = issue_token(
+            SECRET,
+            5,
+            EmailCategory::Prompt,
+            &(now() + Duration::days(1)),
+        );
+
+        assert_eq!(
+            verify_token(SECRET, &token, &now()),
+            Some(UnsubscribeClaims {
+                user_id: 5,
+                category: EmailCategory::Prompt,
+            })
+        );
+        assert_eq!(
+            verify_token(SECRET, &token, &(now() + Duration::days(1))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_verify_forged_token() {
+        let expires_at = now() + Duration::days(1);
+        let token = issue_token(SECRET, 5, EmailCategory::Prompt, &expires_at);
+        let signature = token.rsplit('.').next().unwrap();
+
+        // Another user with the signature of user 5.
+        let forged_user = format!("6.prompt.{}.{}", expires_at.timestamp(), signature);
+        assert_eq!(verify_token(SECRET, &forged_user, &now()), None);
+
+        // A later expiry with the signature of user 5.
+        let forged_expiry = format!(
+            "5.prompt.{}.{}",
+            (expires_at + Duration::days(365)).timestamp(),
+            signature
+        );
+        assert_eq!(verify_token(SECRET, &forged_expiry, &now()), None);
+
+        // A token signed by another secret.
+        let foreign_token = issue_token("another", 5, EmailCategory::Prompt, &expires_at);
+        assert_eq!(verify_token(SECRET, &foreign_token, &now()), None);
+
+        // A truncated signature, and no signature at all.
+        assert_eq!(
+            verify_token(SECRET, &token[..token.len() - 2], &now()),
+            None
+        );
+        assert_eq!(
+            verify_token(
+                SECRET,
+                &format!("5.prompt.{}", expires_at.timestamp()),
+                &now()
+            ),
+            None
+        );
+        assert_eq!(verify_token(SECRET, "", &now()), None);
+    }
+}
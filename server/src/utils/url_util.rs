@@ -0,0 +1,173 @@
+use std::env;
+
+/// Builder of absolute URLs pointing the client, used in emails and share links.
+///
+/// The origin is always taken from `PUBLIC_BASE_URL`, and never from `Host` headers,
+/// since the server runs behind the api gateway and reverse proxies.
+#[derive(Clone, Debug)]
+pub struct PublicUrl {
+    base_url: String,
+}
+
+impl PublicUrl {
+    /// Creates a URL builder on `base_url`.
+    ///
+    /// `base_url` must have a scheme and a host, and may have a path if the client is
+    /// mounted on a sub path, e.g. `https://example.com/darim`. It must not end with a slash.
+    pub fn new(base_url: &str) -> Result<Self, String> {
+        let without_scheme = base_url
+            .strip_prefix("https://")
+            .or_else(|| base_url.strip_prefix("http://"))
+            .ok_or_else(|| format!("`{}` must start with http:// or https://", base_url))?;
+
+        let host = without_scheme.split('/').next().unwrap_or_default();
+        if host.is_empty() {
+            return Err(format!("`{}` has no host", base_url));
+        }
+
+        if base_url.ends_with('/') {
+            return Err(format!("`{}` must not end with a slash", base_url));
+        }
+
+        if base_url.contains('?') || base_url.contains('#') {
+            return Err(format!(
+                "`{}` must not have a query or a fragment",
+                base_url
+            ));
+        }
+
+        Ok(Self {
+            base_url: base_url.to_string(),
+        })
+    }
+
+    /// Creates a URL builder on `PUBLIC_BASE_URL`.
+    pub fn from_env() -> Result<Self, String> {
+        let base_url =
+            env::var("PUBLIC_BASE_URL").map_err(|_| String::from("PUBLIC_BASE_URL not found"))?;
+        Self::new(&base_url)
+    }
+
+    /// Creates a URL builder on `PUBLIC_API_BASE_URL`, which is the api gateway.
+    ///
+    /// It is used for links handled without the client, like unsubscribe links which
+    /// mail clients request by themselves.
+    pub fn api_from_env() -> Result<Self, String> {
+        let base_url = env::var("PUBLIC_API_BASE_URL")
+            .map_err(|_| String::from("PUBLIC_API_BASE_URL not found"))?;
+        Self::new(&base_url)
+    }
+
+    /// Returns the origin of the client, which is the base URL without its path.
+    pub fn origin(&self) -> &str {
+        let scheme_length = self.base_url.find("://").unwrap_or_default() + 3;
+        match self.base_url[scheme_length..].find('/') {
+            Some(index) => &self.base_url[..scheme_length + index],
+            None => &self.base_url,
+        }
+    }
+
+    /// Returns the domain of the client, which is the host of the origin without its port.
+    pub fn domain(&self) -> &str {
+        let host = self.origin().split("://").nth(1).unwrap_or_default();
+        host.split(':').next().unwrap_or_default()
+    }
+
+    fn build(&self, path: &str, token: &str) -> String {
+        format!("{}/{}/{}", self.base_url, path, token)
+    }
+
+    /// Returns the URL of a post shared by `token`.
+    pub fn share_post_url(&self, token: &str) -> String {
+        self.build("share", token)
+    }
+
+    /// Returns the URL resetting password with the password token `token`.
+    pub fn password_reset_url(&self, token: &str) -> String {
+        self.build("password_reset", token)
+    }
+
+    /// Returns the URL signing in with the magic link token `token`.
+    pub fn magic_link_url(&self, token: &str) -> String {
+        self.build("magic_link", token)
+    }
+
+    /// Returns the URL confirming a new email with the email change token `token`.
+    pub fn email_confirm_url(&self, token: &str) -> String {
+        self.build("email_confirm", token)
+    }
+
+    /// Returns the URL downloading the archive of an account with the account export token `token`.
+    pub fn account_export_url(&self, token: &str) -> String {
+        self.build("account_export", token)
+    }
+
+    /// Returns the URL downloading the archive of an export job with the download token `token`.
+    pub fn export_download_url(&self, token: &str) -> String {
+        self.build("export_download", token)
+    }
+
+    /// Returns the URL unsubscribing emails with `token`, which must be built on the api gateway.
+    pub fn unsubscribe_url(&self, token: &str) -> String {
+        self.build("unsubscribe", token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_build_urls() {
+        let public_url = PublicUrl::new("https://darim.vercel.app").unwrap();
+        assert_eq!(
+            public_url.password_reset_url("a1b2"),
+            "https://darim.vercel.app/password_reset/a1b2"
+        );
+        assert_eq!(
+            public_url.share_post_url("c3d4"),
+            "https://darim.vercel.app/share/c3d4"
+        );
+        assert_eq!(
+            public_url.magic_link_url("g7h8"),
+            "https://darim.vercel.app/magic_link/g7h8"
+        );
+        assert_eq!(
+            public_url.email_confirm_url("i9j0"),
+            "https://darim.vercel.app/email_confirm/i9j0"
+        );
+        assert_eq!(
+            public_url.account_export_url("k1l2"),
+            "https://darim.vercel.app/account_export/k1l2"
+        );
+        assert_eq!(
+            public_url.export_download_url("m3n4"),
+            "https://darim.vercel.app/export_download/m3n4"
+        );
+
+        let mounted_public_url = PublicUrl::new("http://localhost:8080/darim").unwrap();
+        assert_eq!(
+            mounted_public_url.unsubscribe_url("e5f6"),
+            "http://localhost:8080/darim/unsubscribe/e5f6"
+        );
+    }
+
+    #[test]
+    fn test_origin() {
+        let public_url = PublicUrl::new("https://darim.vercel.app").unwrap();
+        assert_eq!(public_url.origin(), "https://darim.vercel.app");
+        assert_eq!(public_url.domain(), "darim.vercel.app");
+
+        let mounted_public_url = PublicUrl::new("http://localhost:8080/darim").unwrap();
+        assert_eq!(mounted_public_url.origin(), "http://localhost:8080");
+        assert_eq!(mounted_public_url.domain(), "localhost");
+    }
+
+    #[test]
+    fn test_invalid_base_url() {
+        assert!(PublicUrl::new("darim.vercel.app").is_err());
+        assert!(PublicUrl::new("https://").is_err());
+        assert!(PublicUrl::new("https://darim.vercel.app/").is_err());
+        assert!(PublicUrl::new("https://darim.vercel.app/?ref=email").is_err());
+    }
+}